@@ -4,6 +4,9 @@
 -- This is to ease development, and after things are stable and "production" starts to exist,
 -- further database migrations will be regular migration files.
 
+-- Used for autocomplete on partial names and titles
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
 --
 -- User
 --
@@ -20,6 +23,7 @@ CREATE TABLE "user" (
     user_type user_type NOT NULL DEFAULT 'regular',
     created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
     updated_at TIMESTAMP WITH TIME ZONE,
+    version INTEGER NOT NULL DEFAULT 0,  -- Incremented on each settings update, for optimistic locking
     deleted_at TIMESTAMP WITH TIME ZONE,
     from_wikidot BOOLEAN NOT NULL DEFAULT false,
     name TEXT NOT NULL,
@@ -32,6 +36,7 @@ CREATE TABLE "user" (
     password TEXT NOT NULL,
     multi_factor_secret TEXT,
     multi_factor_recovery_codes TEXT[],
+    magic_link_login BOOLEAN NOT NULL DEFAULT false,  -- Whether the user may log in with emailed links
     locales TEXT[] NOT NULL,
     avatar_s3_hash BYTEA,
     real_name TEXT,
@@ -62,6 +67,13 @@ CREATE TABLE "user" (
     CHECK (avatar_s3_hash IS NULL OR length(avatar_s3_hash) = 64)   -- SHA-512 hash size (if set)
 );
 
+-- For autocomplete, slugs are matched by prefix and names anywhere
+CREATE INDEX user_slug_prefix_idx ON "user" (slug text_pattern_ops);
+CREATE INDEX user_name_trgm_idx ON "user" USING GIN (name gin_trgm_ops);
+
+-- For counting references to blobs
+CREATE INDEX user_avatar_idx ON "user" (avatar_s3_hash) WHERE avatar_s3_hash IS NOT NULL;
+
 CREATE TABLE user_bot_owner (
     bot_user_id BIGINT REFERENCES "user"(user_id),
     human_user_id BIGINT REFERENCES "user"(user_id),
@@ -76,10 +88,37 @@ CREATE TABLE user_bot_owner (
 -- Site
 --
 
+-- See ftml's ParserProfile
+CREATE TYPE parser_profile AS ENUM (
+    'standard',
+    'compatibility',
+    'strict'
+);
+
+-- See ftml's ModuleState
+CREATE TYPE module_state AS ENUM (
+    'enabled',
+    'placeholder',
+    'hidden'
+);
+
+-- See ftml's HeadingIdStrategy
+CREATE TYPE heading_id_strategy AS ENUM (
+    'suffix',
+    'hash'
+);
+
+-- Formats which uploaded images can be converted to for serving
+CREATE TYPE image_conversion_format AS ENUM (
+    'webp',
+    'avif'
+);
+
 CREATE TABLE site (
     site_id BIGSERIAL PRIMARY KEY,
     created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
     updated_at TIMESTAMP WITH TIME ZONE,
+    version INTEGER NOT NULL DEFAULT 0,
     deleted_at TIMESTAMP WITH TIME ZONE,
     from_wikidot BOOLEAN NOT NULL DEFAULT false,
     slug TEXT NOT NULL,
@@ -89,6 +128,23 @@ CREATE TABLE site (
     locale TEXT NOT NULL,
     default_page TEXT NOT NULL DEFAULT 'start',
     custom_domain TEXT,  -- Dependency cycle, add foreign key constraint after
+    shortlink_domain TEXT UNIQUE,
+    serve_stable_revisions BOOLEAN NOT NULL DEFAULT false,
+    strip_image_metadata BOOLEAN NOT NULL DEFAULT false,  -- Remove EXIF, etc. from uploaded images
+    allow_svg_uploads BOOLEAN NOT NULL DEFAULT true,  -- Sanitized either way
+    parser_profile parser_profile NOT NULL DEFAULT 'standard',
+    heading_id_strategy heading_id_strategy NOT NULL DEFAULT 'suffix',
+    allow_embeds BOOLEAN NOT NULL DEFAULT true,  -- Iframes, embeds, and raw HTML
+    allow_css BOOLEAN NOT NULL DEFAULT true,  -- [[module CSS]] and style attributes
+    allow_external_images BOOLEAN NOT NULL DEFAULT true,
+    rating_module module_state NOT NULL DEFAULT 'enabled',
+    comments_module module_state NOT NULL DEFAULT 'enabled',
+    file_revision_retention_days INTEGER,  -- Expire old intermediate file revisions, if set
+    image_conversion image_conversion_format,  -- Convert uploaded PNG and JPEG images, if set
+    image_conversion_quality INTEGER NOT NULL DEFAULT 80,
+
+    CHECK (file_revision_retention_days IS NULL OR file_revision_retention_days > 0),
+    CHECK (image_conversion_quality BETWEEN 1 AND 100),
 
     UNIQUE (slug, deleted_at)
 );
@@ -171,11 +227,20 @@ CREATE TABLE session (
     user_id BIGINT NOT NULL REFERENCES "user"(user_id),
     created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
     expires_at TIMESTAMP WITH TIME ZONE NOT NULL CHECK (expires_at > created_at),
+    login_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),  -- Carried over when the token is rotated
+    rotate_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    replaced_by TEXT,  -- Set once rotated, the old token remains valid for a short grace period
     ip_address TEXT NOT NULL,  -- TODO change to INET
     user_agent TEXT NOT NULL,
-    restricted BOOLEAN NOT NULL
+    restricted BOOLEAN NOT NULL,
+    impersonator_id BIGINT REFERENCES "user"(user_id),  -- Set if an admin is acting as this user
+    refresh_family_id TEXT,  -- Set if issued for a refresh token, carried over when the token is rotated
+
+    CHECK (impersonator_id IS NULL OR impersonator_id != user_id)
 );
 
+CREATE INDEX session_refresh_family_idx ON session (refresh_family_id);
+
 --
 -- Page
 --
@@ -184,8 +249,16 @@ CREATE TABLE page_category (
     category_id BIGSERIAL PRIMARY KEY,
     created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
     updated_at TIMESTAMP WITH TIME ZONE,
+    version INTEGER NOT NULL DEFAULT 0,
     site_id BIGINT NOT NULL REFERENCES site(site_id),
     slug TEXT NOT NULL,
+    noindex BOOLEAN NOT NULL DEFAULT false,
+    nofollow BOOLEAN NOT NULL DEFAULT false,
+    canonical_url TEXT, -- base URL, the page slug is appended
+    parser_profile parser_profile, -- overrides the site's, if set
+    rating_module module_state, -- same
+    comments_module module_state, -- same
+    form JSONB, -- data form schema, if pages in this category are structured
 
     UNIQUE (site_id, slug)
 );
@@ -198,9 +271,13 @@ CREATE TABLE page (
     from_wikidot BOOLEAN NOT NULL DEFAULT false,
     site_id BIGINT NOT NULL REFERENCES site(site_id),
     latest_revision_id BIGINT, -- nullable to avoid an initial page_revision dependency cycle
+    stable_revision_id BIGINT, -- revision served publicly, if the site serves stable revisions
     page_category_id BIGINT NOT NULL REFERENCES page_category(category_id),
     slug TEXT NOT NULL,
     discussion_thread_id BIGINT, -- TODO: add REFERENCES to forum threads
+    noindex BOOLEAN, -- NULL means inherit from the category
+    nofollow BOOLEAN, -- NULL means inherit from the category
+    canonical_url TEXT,
 
     UNIQUE (site_id, slug, deleted_at)
 );
@@ -245,37 +322,30 @@ CREATE TABLE page_revision (
     revision_type page_revision_type NOT NULL DEFAULT 'regular',
     created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
     updated_at TIMESTAMP WITH TIME ZONE,
-    revision_number INT NOT NULL,
+    revision_number BIGINT NOT NULL,
     page_id BIGINT NOT NULL REFERENCES page(page_id),
     site_id BIGINT NOT NULL REFERENCES site(site_id),
     user_id BIGINT NOT NULL REFERENCES "user"(user_id),
     from_wikidot BOOLEAN NOT NULL DEFAULT false,
-    changes TEXT[] NOT NULL, -- List of changes in this revision
+    changes page_revision_change[] NOT NULL, -- List of changes in this revision
     wikitext_hash BYTEA NOT NULL REFERENCES text(hash),
     compiled_hash BYTEA NOT NULL REFERENCES text(hash),
     compiled_at TIMESTAMP WITH TIME ZONE NOT NULL,
     compiled_generator TEXT NOT NULL,
+    content_stats JSON NOT NULL, -- Word count, reading time, etc from the last render
+    compiled_csp JSON NOT NULL, -- Inline style digests to allow in the Content-Security-Policy
     comments TEXT NOT NULL,
     hidden TEXT[] NOT NULL DEFAULT '{}', -- List of fields to be hidden/suppressed
     title TEXT NOT NULL,
     alt_title TEXT,
     slug TEXT NOT NULL,
     tags TEXT[] NOT NULL DEFAULT '{}', -- Should be sorted and deduplicated before insertion
-
-    -- Ensure array only contains valid values
-    -- Change this to use the 'page_revision_change' type later
-    CHECK (changes <@ '{
-        wikitext,
-        title,
-        alt_title,
-        slug,
-        tags
-    }'),
+    applied_tag_rules BIGINT[] NOT NULL DEFAULT '{}', -- IDs of tag rules which changed the tags
 
     -- Ensure first revision reports all changes
     --
     -- This is implemented  by seeing if it's a superset or equal to all valid values.
-    -- Since we already check if it's a subset or equal, this is the same as
+    -- Since the enum type only permits valid values, this is the same as
     -- strict equivalence, but without regard for ordering.
     CHECK (
         revision_type != 'create' OR
@@ -298,6 +368,10 @@ CREATE TABLE page_revision (
     UNIQUE (page_id, site_id, revision_number)
 );
 
+-- For searching edit summaries, by substring and by full-text
+CREATE INDEX page_revision_comments_trgm_idx ON page_revision USING GIN (comments gin_trgm_ops);
+CREATE INDEX page_revision_comments_fts_idx ON page_revision USING GIN (to_tsvector('simple', comments));
+
 -- Add foreign key constraint for latest_revision_id
 ALTER TABLE page ADD CONSTRAINT page_revision_revision_id_fk
     FOREIGN KEY (latest_revision_id) REFERENCES page_revision(revision_id);
@@ -326,6 +400,35 @@ CREATE TABLE page_attribution (
     PRIMARY KEY (page_id, user_id, attribution_type, attribution_date)
 );
 
+-- Work-in-progress edits, which are saved without becoming a revision.
+--
+-- A draft is of an existing page, or of one which doesn't exist yet, in which
+-- case it has the slug the page is to be created at. Drafts are only visible
+-- to the user who saved them, and are deleted once published.
+CREATE TABLE page_draft (
+    draft_id BIGSERIAL PRIMARY KEY,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP WITH TIME ZONE,
+    site_id BIGINT NOT NULL REFERENCES site(site_id),
+    page_id BIGINT REFERENCES page(page_id),  -- NULL if the page doesn't exist yet
+    slug TEXT,  -- Only set if the page doesn't exist yet
+    user_id BIGINT NOT NULL REFERENCES "user"(user_id),
+    base_revision_id BIGINT REFERENCES page_revision(revision_id),  -- Latest revision when the draft was started
+    wikitext_hash BYTEA NOT NULL REFERENCES text(hash),
+    title TEXT NOT NULL,
+    alt_title TEXT,
+    tags TEXT[] NOT NULL DEFAULT '{}',
+    comments TEXT NOT NULL DEFAULT '',
+    publish_at TIMESTAMP WITH TIME ZONE,  -- When to publish automatically, if scheduled
+    publish_error TEXT,  -- Why the last scheduled publication failed, if it did
+
+    CHECK ((page_id IS NULL) = (slug IS NOT NULL)),
+    CHECK ((page_id IS NULL) = (base_revision_id IS NULL))
+);
+
+CREATE INDEX page_draft_user_idx ON page_draft (user_id, site_id);
+CREATE INDEX page_draft_publish_idx ON page_draft (publish_at) WHERE publish_at IS NOT NULL;
+
 CREATE TABLE page_lock (
     page_lock_id BIGSERIAL PRIMARY KEY,
     created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
@@ -389,6 +492,75 @@ CREATE TABLE page_connection_missing (
     PRIMARY KEY (from_page_id, to_site_id, to_page_slug, connection_type)
 );
 
+-- References to files attached to pages, such as images.
+--
+-- Like page_connection_missing, the target is stored by name rather than
+-- ID, so references to files which don't exist (or were deleted) are kept.
+CREATE TABLE page_file_reference (
+    from_page_id BIGINT REFERENCES page(page_id),
+    to_site_id BIGINT REFERENCES site(site_id),
+    to_page_slug TEXT,
+    to_filename TEXT,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP WITH TIME ZONE,
+    count INT NOT NULL CHECK (count > 0),
+
+    PRIMARY KEY (from_page_id, to_site_id, to_page_slug, to_filename)
+);
+
+--
+-- Page render errors
+--
+
+-- Diagnostics from the latest render of a page, if it had any problems.
+--
+-- A row only exists while the page's latest render produced diagnostics at or
+-- above the configured severity threshold, and is removed once it renders cleanly.
+CREATE TABLE page_render_error (
+    page_id BIGINT PRIMARY KEY REFERENCES page(page_id),
+    site_id BIGINT NOT NULL REFERENCES site(site_id),
+    first_failed_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    rendered_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    error_count INTEGER NOT NULL,
+    warning_count INTEGER NOT NULL,
+    notice_count INTEGER NOT NULL,
+    diagnostics JSONB NOT NULL,  -- The ftml parse errors, with their severity
+
+    CHECK (error_count >= 0 AND warning_count >= 0 AND notice_count >= 0),
+    CHECK (error_count + warning_count + notice_count > 0)
+);
+
+CREATE INDEX page_render_error_site_idx ON page_render_error (site_id);
+
+-- Field values of pages in categories with a data form.
+--
+-- The page's wikitext is the source of truth, this is extracted from it
+-- on each render so that the values can be queried.
+CREATE TABLE page_form_data (
+    page_id BIGINT PRIMARY KEY REFERENCES page(page_id),
+    site_id BIGINT NOT NULL REFERENCES site(site_id),
+    category_id BIGINT NOT NULL REFERENCES page_category(category_id),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    data JSONB NOT NULL
+);
+
+CREATE INDEX page_form_data_category_idx ON page_form_data (category_id);
+CREATE INDEX page_form_data_data_idx ON page_form_data USING GIN (data);
+
+-- Users to notify when a form field changes on any page in a category
+CREATE TABLE page_field_watch (
+    watch_id BIGSERIAL PRIMARY KEY,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    user_id BIGINT NOT NULL REFERENCES "user"(user_id),
+    site_id BIGINT NOT NULL REFERENCES site(site_id),
+    category_id BIGINT NOT NULL REFERENCES page_category(category_id),
+    field TEXT NOT NULL,
+
+    UNIQUE (user_id, category_id, field)
+);
+
+CREATE INDEX page_field_watch_category_idx ON page_field_watch (category_id, field);
+
 --
 -- Page votes
 --
@@ -421,6 +593,7 @@ CREATE TYPE file_revision_type AS ENUM (
 );
 
 CREATE TYPE file_revision_change AS ENUM (
+    'page',
     'name',
     'blob',
     'mime',
@@ -436,6 +609,7 @@ CREATE TABLE file (
     name TEXT NOT NULL,
     page_id BIGINT NOT NULL REFERENCES page(page_id),
     site_id BIGINT NOT NULL REFERENCES site(site_id),
+    stable_revision_id BIGINT, -- revision served publicly, if the site serves stable revisions
 
     UNIQUE (page_id, name, deleted_at)
 );
@@ -444,38 +618,30 @@ CREATE TABLE file_revision (
     revision_id BIGSERIAL PRIMARY KEY,
     revision_type file_revision_type NOT NULL,
     created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
-    revision_number INTEGER NOT NULL,
+    revision_number BIGINT NOT NULL,
     file_id BIGINT NOT NULL REFERENCES file(file_id),
     page_id BIGINT NOT NULL REFERENCES page(page_id),
     site_id BIGINT NOT NULL REFERENCES site(site_id),
     user_id BIGINT NOT NULL REFERENCES "user"(user_id),
     name TEXT NOT NULL,
     s3_hash BYTEA NOT NULL,
+    original_s3_hash BYTEA,  -- Hash of the upload as received, if it was sanitized or had metadata stripped
     mime_hint TEXT NOT NULL,
     size_hint BIGINT NOT NULL,
     licensing JSON NOT NULL,
-    changes TEXT[] NOT NULL DEFAULT '{}', -- List of changes in this revision
+    changes file_revision_change[] NOT NULL DEFAULT '{}', -- List of changes in this revision
     comments TEXT NOT NULL,
     hidden TEXT[] NOT NULL DEFAULT '{}', -- List of fields to be hidden/suppressed
 
     CHECK (length(name) > 0 AND length(name) < 256),  -- Constrain filename length
     CHECK (length(s3_hash) = 64),                     -- SHA-512 hash size
+    CHECK (length(original_s3_hash) = 64),            -- SHA-512 hash size
     CHECK (mime_hint != ''),                          -- Should have a MIME hint
 
-    -- Ensure array only contains valid values
-    -- Change this to use the 'page_revision_change' type later
-    CHECK (changes <@ '{
-        page,
-        name,
-        blob,
-        mime,
-        licensing
-    }'),
-
     -- Ensure first revision reports all changes
     --
     -- This is implemented  by seeing if it's a superset or equal to all valid values.
-    -- Since we already check if it's a subset or equal, this is the same as
+    -- Since the enum type only permits valid values, this is the same as
     -- strict equivalence, but without regard for ordering.
     CHECK (
         revision_type != 'create' OR
@@ -498,6 +664,171 @@ CREATE TABLE file_revision (
     UNIQUE (file_id, page_id, revision_number)
 );
 
+-- For finding files with the same contents, as blobs are shared
+CREATE INDEX file_revision_s3_hash_idx ON file_revision (s3_hash);
+
+-- For searching edit summaries, same as page_revision
+CREATE INDEX file_revision_comments_trgm_idx ON file_revision USING GIN (comments gin_trgm_ops);
+CREATE INDEX file_revision_comments_fts_idx ON file_revision USING GIN (to_tsvector('simple', comments));
+
+-- Files displayed by pages, such as images and file cards.
+--
+-- Unlike page_file_reference, these are by ID, and only for files which
+-- existed when the page was rendered. This lets the pages displaying a
+-- file be rerendered when its contents change, wherever they are.
+CREATE TABLE page_file_embed (
+    from_page_id BIGINT REFERENCES page(page_id),
+    file_id BIGINT REFERENCES file(file_id),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP WITH TIME ZONE,
+    count INT NOT NULL CHECK (count > 0),
+
+    PRIMARY KEY (from_page_id, file_id)
+);
+
+-- For finding the pages to rerender when a file changes
+CREATE INDEX page_file_embed_file_id_idx ON page_file_embed (file_id);
+
+-- Scaled-down copies of image blobs, at each configured size.
+--
+-- Keyed by the source blob rather than the revision, since blobs are shared.
+-- If the image already fits within the size, the two hashes are the same.
+CREATE TABLE file_thumbnail (
+    s3_hash BYTEA NOT NULL,
+    size INTEGER NOT NULL,
+    thumbnail_s3_hash BYTEA NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+
+    CHECK (length(s3_hash) = 64),            -- SHA-512 hash size
+    CHECK (length(thumbnail_s3_hash) = 64),  -- SHA-512 hash size
+    CHECK (size > 0),
+
+    PRIMARY KEY (s3_hash, size)
+);
+
+-- For finding thumbnails in use during blob garbage collection
+CREATE INDEX file_thumbnail_thumbnail_s3_hash_idx ON file_thumbnail (thumbnail_s3_hash);
+
+-- Images converted to another format, per the site's image conversion policy.
+--
+-- Like thumbnails, these are recorded against the source blob, and the original
+-- remains the file's contents. Since sites may use different quality settings,
+-- the same blob can have several derivatives in a format. If converting an image
+-- didn't make it any smaller, it is recorded as its own derivative.
+CREATE TABLE file_derivative (
+    s3_hash BYTEA NOT NULL,
+    format image_conversion_format NOT NULL,
+    quality INTEGER NOT NULL,
+    derivative_s3_hash BYTEA NOT NULL,
+    size BIGINT NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+
+    CHECK (length(s3_hash) = 64),             -- SHA-512 hash size
+    CHECK (length(derivative_s3_hash) = 64),  -- SHA-512 hash size
+    CHECK (quality BETWEEN 1 AND 100),
+    CHECK (size >= 0),
+
+    PRIMARY KEY (s3_hash, format, quality)
+);
+
+-- For finding derivatives in use during blob garbage collection
+CREATE INDEX file_derivative_derivative_s3_hash_idx ON file_derivative (derivative_s3_hash);
+
+-- Uploads sent directly to S3 with a presigned URL, which haven't been finalized.
+--
+-- The client declares the hash of the file ahead of time, which is checked once
+-- it confirms the upload. The object is kept under a temporary key until then,
+-- since its contents can't be trusted. If file_id is set, this is a new version
+-- of that file, otherwise it creates a new file with this name.
+--
+-- For new versions, the name and licensing are null if they are being left unchanged.
+CREATE TABLE file_pending_upload (
+    upload_id BIGSERIAL PRIMARY KEY,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    site_id BIGINT NOT NULL REFERENCES site(site_id),
+    page_id BIGINT NOT NULL REFERENCES page(page_id),
+    file_id BIGINT REFERENCES file(file_id),
+    user_id BIGINT NOT NULL REFERENCES "user"(user_id),
+    name TEXT,
+    licensing JSON,
+    revision_comments TEXT NOT NULL,
+    s3_path TEXT NOT NULL UNIQUE,
+    s3_hash BYTEA NOT NULL,
+    size BIGINT NOT NULL,
+    chunked BOOLEAN NOT NULL DEFAULT false, -- Sent through DEEPWELL in chunks, rather than to S3 directly
+    received_size BIGINT NOT NULL DEFAULT 0,
+    chunk_count INT NOT NULL DEFAULT 0,
+
+    CHECK (length(s3_hash) = 64),  -- SHA-512 hash size
+    CHECK (size >= 0),
+    CHECK (received_size >= 0 AND received_size <= size),
+    CHECK (chunk_count >= 0),
+    CHECK (chunked OR (received_size = 0 AND chunk_count = 0)),
+    CHECK (expires_at > created_at),
+    CHECK (file_id IS NOT NULL OR name IS NOT NULL)
+);
+
+-- For pruning expired uploads
+CREATE INDEX file_pending_upload_expires_at_idx ON file_pending_upload (expires_at);
+
+-- The chunks received so far for a chunked upload.
+--
+-- Each chunk's checksum is given by the client and verified when it is received,
+-- and again when the chunks are put together, so corruption in either direction
+-- is caught. Clients resuming an upload use these to see what was received.
+CREATE TABLE file_pending_upload_chunk (
+    upload_id BIGINT NOT NULL REFERENCES file_pending_upload(upload_id) ON DELETE CASCADE,
+    chunk_number INT NOT NULL,
+    received_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    start_offset BIGINT NOT NULL,
+    size BIGINT NOT NULL,
+    checksum BYTEA NOT NULL,
+
+    CHECK (chunk_number >= 0),
+    CHECK (start_offset >= 0),
+    CHECK (size > 0),
+    CHECK (length(checksum) = 64),  -- SHA-512 hash size
+
+    PRIMARY KEY (upload_id, chunk_number),
+    UNIQUE (upload_id, start_offset)
+);
+
+-- Files uploaded to a user's personal staging area, which aren't on any page yet.
+--
+-- This lets files be uploaded before the page they're for exists, such as from
+-- the editor. The contents have already been checked and stored as a blob, so
+-- attaching one to a page only needs to create the file and its first revision.
+-- Staged files which aren't attached before they expire are discarded.
+CREATE TABLE file_staging (
+    staged_file_id BIGSERIAL PRIMARY KEY,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    site_id BIGINT NOT NULL REFERENCES site(site_id),
+    user_id BIGINT NOT NULL REFERENCES "user"(user_id),
+    name TEXT NOT NULL,
+    s3_hash BYTEA NOT NULL,
+    original_s3_hash BYTEA, -- Hash as received, if it was sanitized or had metadata stripped
+    size_hint BIGINT NOT NULL,
+    mime_hint TEXT NOT NULL,
+
+    CHECK (length(name) > 0 AND length(name) < 256),  -- Constrain filename length
+    CHECK (length(s3_hash) = 64),                     -- SHA-512 hash size
+    CHECK (length(original_s3_hash) = 64),            -- SHA-512 hash size
+    CHECK (size_hint >= 0),
+    CHECK (mime_hint != ''),                          -- Should have a MIME hint
+    CHECK (expires_at > created_at)
+);
+
+-- For listing and counting a user's staged files
+CREATE INDEX file_staging_user_id_idx ON file_staging (user_id);
+
+-- For pruning expired staged files
+CREATE INDEX file_staging_expires_at_idx ON file_staging (expires_at);
+
+-- For finding staged files during blob garbage collection
+CREATE INDEX file_staging_s3_hash_idx ON file_staging (s3_hash);
+
 --
 -- Direct Messages
 --
@@ -622,3 +953,745 @@ CREATE TABLE filter (
 
     UNIQUE (site_id, regex, deleted_at)
 );
+
+--
+-- Special page overrides
+--
+
+-- Per-site, per-locale replacement text for special pages (e.g. missing page, private page).
+--
+-- These take precedence over the site's special pages (e.g. "_404") and the
+-- instance's localized defaults. See SpecialPageService for resolution order.
+CREATE TABLE special_page_override (
+    site_id BIGINT NOT NULL REFERENCES site(site_id),
+    page_type TEXT NOT NULL,
+    locale TEXT NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP WITH TIME ZONE,
+    wikitext_hash BYTEA NOT NULL REFERENCES text(hash),
+
+    PRIMARY KEY (site_id, page_type, locale),
+    CHECK (page_type IN ('template', 'missing', 'private', 'banned', 'join')),
+    CHECK (length(locale) > 0)
+);
+
+--
+-- Shortlinks
+--
+
+-- Short codes which redirect to a page, or a particular revision of one.
+--
+-- Codes are unique across the whole instance, so they can be resolved
+-- from any domain. Because NULLs are distinct in UNIQUE constraints,
+-- page-level links (without a revision) have their own partial index.
+CREATE TABLE shortlink (
+    shortlink_id BIGSERIAL PRIMARY KEY,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    code TEXT NOT NULL UNIQUE,
+    site_id BIGINT NOT NULL REFERENCES site(site_id),
+    page_id BIGINT NOT NULL REFERENCES page(page_id),
+    revision_id BIGINT REFERENCES page_revision(revision_id),
+    clicks BIGINT NOT NULL DEFAULT 0,
+    last_clicked_at TIMESTAMP WITH TIME ZONE,
+
+    UNIQUE (page_id, revision_id),
+    CHECK (length(code) > 0)
+);
+
+CREATE UNIQUE INDEX shortlink_page_idx ON shortlink (page_id) WHERE revision_id IS NULL;
+
+--
+-- Exports
+--
+
+CREATE TYPE export_format AS ENUM (
+    'epub',
+    'pdf'
+);
+
+CREATE TYPE export_status AS ENUM (
+    'queued',
+    'completed',
+    'failed'
+);
+
+-- Requests to bundle pages into a downloadable document, built by a job.
+--
+-- The pages are resolved when the export is requested and stored in order,
+-- along with the revision of each at the time, so the job only needs the
+-- export ID and requests for unchanged pages can reuse the same export.
+-- The produced file is stored as a blob.
+CREATE TABLE export (
+    export_id BIGSERIAL PRIMARY KEY,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    completed_at TIMESTAMP WITH TIME ZONE,
+    site_id BIGINT NOT NULL REFERENCES site(site_id),
+    user_id BIGINT NOT NULL REFERENCES "user"(user_id),
+    export_format export_format NOT NULL,
+    status export_status NOT NULL DEFAULT 'queued',
+    title TEXT NOT NULL,
+    page_ids BIGINT[] NOT NULL,
+    revision_ids BIGINT[] NOT NULL,  -- Parallel to page_ids
+    s3_hash BYTEA,  -- Only set once completed
+    size BIGINT,
+    error TEXT,  -- Diagnostic message, only set if failed
+
+    CHECK ((status = 'completed') = (s3_hash IS NOT NULL)),
+    CHECK ((status = 'failed') = (error IS NOT NULL)),
+    CHECK (length(title) > 0),
+    CHECK (cardinality(page_ids) > 0),
+    CHECK (cardinality(revision_ids) = cardinality(page_ids))
+);
+
+--
+-- Git mirrors
+--
+
+-- Sites which mirror their page sources into a Git repository.
+--
+-- Each revision becomes a commit. The repository itself is stored on disk.
+CREATE TABLE site_git_mirror (
+    site_id BIGINT PRIMARY KEY REFERENCES site(site_id),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP WITH TIME ZONE,
+    remote_url TEXT,  -- If set, the repository is pushed here after each sync
+    public BOOLEAN NOT NULL DEFAULT false,  -- Whether it can be cloned from the site
+    synced_at TIMESTAMP WITH TIME ZONE,
+    error TEXT,  -- Diagnostic message, only set if the last sync failed
+
+    CHECK (remote_url IS NULL OR length(remote_url) > 0)
+);
+
+-- The page revisions which have been committed to a site's Git mirror.
+--
+-- Revision IDs are assigned before their transaction commits, and these may
+-- commit out of order, so each mirrored revision is recorded rather than only
+-- the highest ID. Otherwise a revision committed late would never be mirrored.
+CREATE TABLE site_git_mirror_revision (
+    site_id BIGINT NOT NULL REFERENCES site_git_mirror(site_id),
+    revision_id BIGINT NOT NULL REFERENCES page_revision(revision_id),
+    synced_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+
+    PRIMARY KEY (site_id, revision_id)
+);
+
+--
+-- Service accounts
+--
+
+-- Credentials for internal components, such as the frontend or importers,
+-- which call the API as themselves rather than as a user.
+CREATE TABLE service_account (
+    service_account_id BIGSERIAL PRIMARY KEY,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP WITH TIME ZONE,
+    revoked_at TIMESTAMP WITH TIME ZONE,
+    created_by BIGINT NOT NULL REFERENCES "user"(user_id),
+    name TEXT NOT NULL UNIQUE CHECK (length(name) > 0 AND length(name) <= 100),
+    description TEXT NOT NULL DEFAULT '',
+    key_hash BYTEA NOT NULL UNIQUE,  -- SHA-256 of the key held by the component
+    scopes TEXT[] NOT NULL,  -- API methods it may call, or prefixes ending in '*'
+
+    CHECK (length(key_hash) = 32)
+);
+
+--
+-- Audit log
+--
+
+-- Actions which are recorded for later review by platform staff.
+--
+-- The actor is the user who actually performed the action, and the subject
+-- is the user it was performed as. These only differ during impersonation.
+-- If the action was requested by a service account, it is also recorded.
+-- Actions taken on content within a site, such as hiding a revision, also
+-- have that site, so the log can be filtered to a single site.
+CREATE TABLE audit_log (
+    audit_id BIGSERIAL PRIMARY KEY,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    actor_id BIGINT NOT NULL REFERENCES "user"(user_id),
+    subject_id BIGINT NOT NULL REFERENCES "user"(user_id),
+    service_account_id BIGINT REFERENCES service_account(service_account_id),
+    site_id BIGINT REFERENCES site(site_id),
+    action TEXT NOT NULL,
+    data JSONB NOT NULL DEFAULT '{}',
+
+    CHECK (length(action) > 0)
+);
+
+CREATE INDEX audit_log_site_idx ON audit_log (site_id, audit_id);
+
+--
+-- Event outbox
+--
+
+-- Domain events waiting to be delivered to their subscribers.
+--
+-- Events are inserted in the same transaction as the change which produced
+-- them, so they are only visible once it commits, and are never lost if the
+-- process dies before they are delivered. Rows are removed once delivered.
+CREATE TABLE event_outbox (
+    event_id BIGSERIAL PRIMARY KEY,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    event JSONB NOT NULL,
+    attempts SMALLINT NOT NULL DEFAULT 0,
+    last_error TEXT  -- Diagnostic message, only set if the last delivery failed
+);
+
+--
+-- Search index
+--
+
+CREATE TYPE search_document_type AS ENUM (
+    'file',
+    'page'
+);
+
+-- Searchable contents of pages and files, derived from their latest revisions.
+--
+-- This is a read model maintained from domain events, and can be rebuilt
+-- from scratch at any time. The revision ID is what was last indexed,
+-- so it can be compared against the object to find stale entries.
+CREATE TABLE search_document (
+    document_type search_document_type NOT NULL,
+    document_id BIGINT NOT NULL,  -- Either a page ID or file ID
+    site_id BIGINT NOT NULL REFERENCES site(site_id),
+    revision_id BIGINT NOT NULL,
+    indexed_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    name TEXT NOT NULL,  -- Either a page slug or filename
+    title TEXT NOT NULL,
+    body TEXT NOT NULL,
+    tags TEXT[] NOT NULL DEFAULT '{}',  -- Only set for pages
+    content TSVECTOR GENERATED ALWAYS AS (to_tsvector('simple', title || ' ' || body)) STORED,
+
+    PRIMARY KEY (document_type, document_id)
+);
+
+CREATE INDEX search_document_content_idx ON search_document USING GIN (content);
+CREATE INDEX search_document_site_idx ON search_document (site_id);
+CREATE INDEX search_document_name_prefix_idx ON search_document (site_id, name text_pattern_ops);
+CREATE INDEX search_document_title_trgm_idx ON search_document USING GIN (title gin_trgm_ops);
+CREATE INDEX search_document_tags_idx ON search_document USING GIN (tags);
+
+--
+-- API usage
+--
+
+-- Daily totals of API calls made by each user against a site.
+--
+-- Calls are counted in Redis as they happen, and periodically added here.
+-- Calls which don't specify both a site and user are not tracked.
+CREATE TABLE api_usage (
+    site_id BIGINT NOT NULL REFERENCES site(site_id),
+    user_id BIGINT NOT NULL REFERENCES "user"(user_id),
+    method TEXT NOT NULL,
+    usage_date DATE NOT NULL,
+    call_count BIGINT NOT NULL DEFAULT 0,
+    error_count BIGINT NOT NULL DEFAULT 0,
+    request_bytes BIGINT NOT NULL DEFAULT 0,
+    response_bytes BIGINT NOT NULL DEFAULT 0,
+
+    PRIMARY KEY (site_id, user_id, method, usage_date)
+);
+
+CREATE INDEX api_usage_site_date_idx ON api_usage (site_id, usage_date);
+
+-- Limits set by site administrators on how often a user may call the API on their site.
+CREATE TABLE api_throttle (
+    site_id BIGINT NOT NULL REFERENCES site(site_id),
+    user_id BIGINT NOT NULL REFERENCES "user"(user_id),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    created_by BIGINT NOT NULL REFERENCES "user"(user_id),
+    requests_per_minute INTEGER NOT NULL,
+
+    PRIMARY KEY (site_id, user_id),
+    CHECK (requests_per_minute > 0)
+);
+
+--
+-- Site limits
+--
+
+-- Per-site overrides of the instance's size limits.
+--
+-- A NULL column means the instance default applies. Sites can only
+-- lower limits, so values above the instance default are rejected.
+CREATE TABLE site_limit (
+    site_id BIGINT PRIMARY KEY REFERENCES site(site_id),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP WITH TIME ZONE,
+    wikitext_bytes BIGINT,
+    comment_bytes BIGINT,
+    tags BIGINT,
+    upload_bytes BIGINT,
+    storage_bytes BIGINT,
+
+    CHECK (wikitext_bytes IS NULL OR wikitext_bytes > 0),
+    CHECK (comment_bytes IS NULL OR comment_bytes > 0),
+    CHECK (tags IS NULL OR tags >= 0),
+    CHECK (upload_bytes IS NULL OR upload_bytes > 0),
+    CHECK (storage_bytes IS NULL OR storage_bytes > 0)
+);
+
+-- Total size of the files stored by each site, for enforcing its storage quota.
+--
+-- This is kept up to date as file revisions with new contents are created
+-- or purged, rather than summing over every revision each time. Each such
+-- revision counts in full, even if its blob is shared with another.
+CREATE TABLE site_storage (
+    site_id BIGINT PRIMARY KEY REFERENCES site(site_id),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    stored_bytes BIGINT NOT NULL DEFAULT 0,
+
+    CHECK (stored_bytes >= 0)
+);
+
+--
+-- Link recomputation
+--
+
+-- Runs which re-extract the links of every page, or every page in a site.
+--
+-- This brings the link tables up to date after a parser upgrade changes what
+-- is extracted. Pages are processed in batches in order of ID, so this tracks
+-- how far the run has gotten, and how many pages had their links change.
+CREATE TABLE link_recompute (
+    recompute_id BIGSERIAL PRIMARY KEY,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    completed_at TIMESTAMP WITH TIME ZONE,
+    site_id BIGINT REFERENCES site(site_id),  -- If NULL, then all sites are covered
+    generator TEXT NOT NULL,  -- The ftml version the run was started with
+    last_page_id BIGINT NOT NULL DEFAULT 0,
+    pages_checked BIGINT NOT NULL DEFAULT 0,
+    pages_changed BIGINT NOT NULL DEFAULT 0
+);
+
+--
+-- Revision comparisons
+--
+
+-- Permalinks to the comparison between two revisions of a page.
+--
+-- Both revisions are fixed, so the comparison is the same however the page
+-- is edited afterwards, and links to it in discussions keep pointing at the
+-- same changes. Each pair of revisions has only one token.
+CREATE TABLE revision_comparison (
+    token TEXT PRIMARY KEY,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    site_id BIGINT NOT NULL REFERENCES site(site_id),
+    page_id BIGINT NOT NULL REFERENCES page(page_id),
+    from_revision_id BIGINT NOT NULL REFERENCES page_revision(revision_id),
+    to_revision_id BIGINT NOT NULL REFERENCES page_revision(revision_id),
+
+    UNIQUE (from_revision_id, to_revision_id),
+    CHECK (from_revision_id != to_revision_id)
+);
+
+--
+-- Watchlist
+--
+
+-- The latest revision of each watched page which a user has seen.
+--
+-- Revisions after this are what the watchlist shows as unseen. If a watched
+-- page has no row here, then revisions made after the watch began are unseen.
+CREATE TABLE page_watch_seen (
+    user_id BIGINT NOT NULL REFERENCES "user"(user_id),
+    page_id BIGINT NOT NULL REFERENCES page(page_id),
+    revision_number BIGINT NOT NULL,
+    seen_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+
+    PRIMARY KEY (user_id, page_id),
+    CHECK (revision_number >= 0)
+);
+
+--
+-- Tag rules
+--
+
+-- Rules which automatically add or remove tags when a page is created or edited.
+--
+-- A rule applies when all of its conditions match, and NULL conditions always
+-- match. Rules are applied in order of ID, and those which changed the page's
+-- tags are listed in the revision's applied_tag_rules column.
+CREATE TABLE tag_rule (
+    rule_id BIGSERIAL PRIMARY KEY,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP WITH TIME ZONE,
+    deleted_at TIMESTAMP WITH TIME ZONE,
+    site_id BIGINT NOT NULL REFERENCES site(site_id),
+    category TEXT,  -- Category slug, with '_default' for pages without one
+    title_regex TEXT,
+    author_min_role TEXT,  -- Least trusted role the editor must hold, check enum value in runtime
+    author_max_role TEXT,  -- Most trusted role the editor may hold, same
+    add_tags TEXT[] NOT NULL DEFAULT '{}',
+    remove_tags TEXT[] NOT NULL DEFAULT '{}',
+    description TEXT NOT NULL,
+
+    CHECK (cardinality(add_tags) > 0 OR cardinality(remove_tags) > 0),
+    CHECK (NOT (add_tags && remove_tags))  -- A tag cannot be both added and removed
+);
+
+--
+-- Upload rules
+--
+
+-- Restrictions on who may upload which files, by MIME type and size.
+--
+-- The MIME pattern is either exact ("image/png"), a whole top-level type
+-- ("image/*"), or everything ("*"). Only rules with the most specific pattern
+-- matching an upload are used, and of those, the one with the smallest
+-- maximum_bytes which still fits the file. If the file is larger than all of
+-- them, the upload is denied. NULL maximum_bytes means no size restriction
+-- beyond the site's limits.
+CREATE TABLE upload_rule (
+    rule_id BIGSERIAL PRIMARY KEY,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    deleted_at TIMESTAMP WITH TIME ZONE,
+    site_id BIGINT NOT NULL REFERENCES site(site_id),
+    mime_pattern TEXT NOT NULL,
+    maximum_bytes BIGINT CHECK (maximum_bytes > 0),
+    requirement TEXT NOT NULL,  -- Same values as permission_acl.requirement
+    description TEXT NOT NULL
+);
+
+--
+-- Permission ACLs
+--
+
+-- Overrides of what is needed to call an API method on particular pages.
+--
+-- Each entry is either for a page, or for every page in a category. When
+-- evaluating, the page's own entry is used first, then its category's, then
+-- the _default category's, and finally the method's usual requirement.
+CREATE TABLE permission_acl (
+    acl_id BIGSERIAL PRIMARY KEY,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP WITH TIME ZONE,
+    site_id BIGINT NOT NULL REFERENCES site(site_id),
+    category_id BIGINT REFERENCES page_category(category_id),
+    page_id BIGINT REFERENCES page(page_id),
+    method TEXT NOT NULL,  -- check method name in runtime
+    requirement TEXT NOT NULL,  -- check enum value in runtime
+
+    UNIQUE (category_id, method),
+    UNIQUE (page_id, method),
+    CHECK ((category_id IS NULL) != (page_id IS NULL))  -- Exactly one scope is set
+);
+
+--
+-- Magic login links
+--
+
+CREATE TABLE magic_link (
+    token_hash BYTEA PRIMARY KEY,  -- SHA-256 of the token sent by email
+    user_id BIGINT NOT NULL REFERENCES "user"(user_id),
+    device_hash BYTEA NOT NULL,  -- SHA-256 of the token held by the requesting browser
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL CHECK (expires_at > created_at),
+    used_at TIMESTAMP WITH TIME ZONE,
+
+    CHECK (length(token_hash) = 32),
+    CHECK (length(device_hash) = 32)
+);
+
+--
+-- Refresh tokens
+--
+
+CREATE TABLE refresh_token (
+    token_hash BYTEA PRIMARY KEY,  -- SHA-256 of the token held by the client
+    family_id TEXT NOT NULL,  -- Shared by all tokens descended from the same login
+    user_id BIGINT NOT NULL REFERENCES "user"(user_id),
+    client_id TEXT NOT NULL CHECK (length(client_id) > 0 AND length(client_id) <= 100),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL CHECK (expires_at > created_at),
+    used_at TIMESTAMP WITH TIME ZONE,  -- Set once exchanged, using it again revokes the family
+    revoked_at TIMESTAMP WITH TIME ZONE,
+
+    CHECK (length(token_hash) = 32)
+);
+
+CREATE INDEX refresh_token_family_idx ON refresh_token (family_id);
+
+--
+-- Account recovery
+--
+
+CREATE TABLE recovery_contact (
+    contact_id BIGSERIAL PRIMARY KEY,
+    user_id BIGINT NOT NULL REFERENCES "user"(user_id),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    deleted_at TIMESTAMP WITH TIME ZONE,
+    email TEXT,  -- Set for backup email addresses
+    contact_user_id BIGINT REFERENCES "user"(user_id),  -- Set for trusted contacts
+    verified_at TIMESTAMP WITH TIME ZONE,  -- Backup emails are unused until verified
+    verification_token_hash BYTEA UNIQUE,  -- SHA-256 of the token last sent to a backup email
+
+    UNIQUE (user_id, email, deleted_at),
+    UNIQUE (user_id, contact_user_id, deleted_at),
+    CHECK ((email IS NULL) != (contact_user_id IS NULL)),
+    CHECK (contact_user_id IS NULL OR contact_user_id != user_id)
+);
+
+CREATE TABLE account_recovery (
+    recovery_id BIGSERIAL PRIMARY KEY,
+    user_id BIGINT NOT NULL REFERENCES "user"(user_id),
+    token_hash BYTEA NOT NULL UNIQUE,  -- SHA-256 of the token held by the requester
+    cancel_token_hash BYTEA NOT NULL UNIQUE,  -- SHA-256 of the token sent to the account's email
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    ready_at TIMESTAMP WITH TIME ZONE NOT NULL CHECK (ready_at >= created_at),
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL CHECK (expires_at > ready_at),
+    required_confirmations SMALLINT NOT NULL CHECK (required_confirmations > 0),
+    completed_at TIMESTAMP WITH TIME ZONE,
+    cancelled_at TIMESTAMP WITH TIME ZONE,
+
+    CHECK (completed_at IS NULL OR cancelled_at IS NULL)
+);
+
+CREATE TABLE account_recovery_confirmation (
+    recovery_id BIGINT NOT NULL REFERENCES account_recovery(recovery_id),
+    contact_id BIGINT NOT NULL REFERENCES recovery_contact(contact_id),
+    token_hash BYTEA UNIQUE,  -- For backup emails, SHA-256 of the token sent to it
+    confirmed_at TIMESTAMP WITH TIME ZONE,
+
+    PRIMARY KEY (recovery_id, contact_id)
+);
+
+--
+-- OAuth applications
+--
+
+CREATE TABLE oauth_application (
+    application_id BIGSERIAL PRIMARY KEY,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP WITH TIME ZONE,
+    deleted_at TIMESTAMP WITH TIME ZONE,
+    owner_user_id BIGINT NOT NULL REFERENCES "user"(user_id),
+    name TEXT NOT NULL CHECK (length(name) > 0 AND length(name) <= 100),
+    client_id TEXT NOT NULL UNIQUE,
+    client_secret_hash BYTEA NOT NULL,  -- SHA-256 of the secret held by the application
+    redirect_uris TEXT[] NOT NULL CHECK (cardinality(redirect_uris) > 0),
+    scopes TEXT[] NOT NULL,  -- API methods the application may request
+
+    CHECK (length(client_secret_hash) = 32)
+);
+
+-- A user's consent for an application to act on their behalf
+CREATE TABLE oauth_authorization (
+    authorization_id BIGSERIAL PRIMARY KEY,
+    application_id BIGINT NOT NULL REFERENCES oauth_application(application_id),
+    user_id BIGINT NOT NULL REFERENCES "user"(user_id),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP WITH TIME ZONE,
+    revoked_at TIMESTAMP WITH TIME ZONE,
+    scopes TEXT[] NOT NULL,
+
+    UNIQUE (application_id, user_id)
+);
+
+CREATE TABLE oauth_code (
+    code_hash BYTEA PRIMARY KEY,  -- SHA-256 of the code given to the application
+    authorization_id BIGINT NOT NULL REFERENCES oauth_authorization(authorization_id),
+    redirect_uri TEXT NOT NULL,
+    scopes TEXT[] NOT NULL,
+    code_challenge TEXT,  -- PKCE challenge, S256 only
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL CHECK (expires_at > created_at),
+    used_at TIMESTAMP WITH TIME ZONE,
+
+    CHECK (length(code_hash) = 32)
+);
+
+CREATE TABLE oauth_token (
+    token_id BIGSERIAL PRIMARY KEY,
+    authorization_id BIGINT NOT NULL REFERENCES oauth_authorization(authorization_id),
+    access_token_hash BYTEA NOT NULL UNIQUE,  -- SHA-256 of the access token
+    refresh_token_hash BYTEA NOT NULL UNIQUE,  -- SHA-256 of the refresh token
+    scopes TEXT[] NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    access_expires_at TIMESTAMP WITH TIME ZONE NOT NULL CHECK (access_expires_at > created_at),
+    refresh_expires_at TIMESTAMP WITH TIME ZONE NOT NULL CHECK (refresh_expires_at >= access_expires_at),
+    last_used_at TIMESTAMP WITH TIME ZONE,
+    revoked_at TIMESTAMP WITH TIME ZONE,  -- Set when revoked or refreshed
+
+    CHECK (length(access_token_hash) = 32),
+    CHECK (length(refresh_token_hash) = 32)
+);
+
+--
+-- Consistency reports
+--
+
+-- Results of checking invariants which span several tables, such as every
+-- file having a revision. These are run periodically, or by an administrator.
+--
+-- Each issue lists the check which found it, the IDs of the affected rows,
+-- and whether it was repaired. Only kinds of issues which can be fixed without
+-- losing data are ever repaired, and only if requested.
+CREATE TABLE consistency_report (
+    report_id BIGSERIAL PRIMARY KEY,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    requested_by BIGINT REFERENCES "user"(user_id),  -- NULL if run by the scheduled job
+    repair BOOLEAN NOT NULL,
+    issue_count INTEGER NOT NULL,
+    repaired_count INTEGER NOT NULL,
+    issues JSONB NOT NULL
+);
+
+--
+-- Blob spool
+--
+
+-- Uploads which couldn't be written to S3 because it was unavailable.
+--
+-- A recurring job writes these to S3 once it recovers, deleting each row as
+-- it goes, and blob reads fall back to this table until then. Only small
+-- uploads are spooled, so that an outage can't fill the database.
+CREATE TABLE blob_spool (
+    s3_hash BYTEA PRIMARY KEY,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    mime TEXT NOT NULL,
+    size BIGINT NOT NULL,
+    data BYTEA NOT NULL,
+    flush_attempts INTEGER NOT NULL DEFAULT 0,
+    last_attempt_at TIMESTAMP WITH TIME ZONE,
+
+    CHECK (length(s3_hash) = 64),   -- SHA-512 hash size
+    CHECK (size = length(data))
+);
+
+-- Blobs which are stored compressed in S3.
+--
+-- The hash is always of the original data, so deduplication is unaffected.
+-- Compressed blobs can't be served by S3 directly, so an uncompressed copy
+-- is written alongside them the first time a download URL is requested.
+CREATE TABLE blob_compression (
+    s3_hash BYTEA PRIMARY KEY,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    algorithm TEXT NOT NULL,
+    logical_size BIGINT NOT NULL,
+    physical_size BIGINT NOT NULL,
+    has_uncompressed_copy BOOLEAN NOT NULL DEFAULT false,
+
+    CHECK (length(s3_hash) = 64),   -- SHA-512 hash size
+    CHECK (physical_size < logical_size)
+);
+
+--
+-- Blob garbage collection
+--
+
+-- Results of each pass deleting S3 blobs which nothing refers to anymore,
+-- such as uploads whose transaction was rolled back after the blob was written.
+--
+-- Dry runs are recorded too, with the blobs which would have been deleted.
+CREATE TABLE blob_collection (
+    collection_id BIGSERIAL PRIMARY KEY,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    requested_by BIGINT REFERENCES "user"(user_id),  -- NULL if run by the scheduled job
+    dry_run BOOLEAN NOT NULL,
+    blobs_scanned BIGINT NOT NULL,
+    blobs_deleted BIGINT NOT NULL,
+    bytes_reclaimed BIGINT NOT NULL,
+
+    CHECK (blobs_deleted <= blobs_scanned)
+);
+
+--
+-- Blob migration
+--
+
+-- Runs copying every object in the S3 bucket to another storage backend.
+--
+-- Objects are listed in order of key, so this tracks how far the run has
+-- gotten, and it resumes from there if interrupted. Each copy is read back
+-- and checked against its hash, and any which don't match are listed here.
+CREATE TABLE blob_migration (
+    migration_id BIGSERIAL PRIMARY KEY,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP WITH TIME ZONE,
+    completed_at TIMESTAMP WITH TIME ZONE,
+    requested_by BIGINT NOT NULL REFERENCES "user"(user_id),
+    target TEXT NOT NULL,
+    last_key TEXT,  -- NULL until the first batch is copied
+    objects_copied BIGINT NOT NULL DEFAULT 0,
+    objects_skipped BIGINT NOT NULL DEFAULT 0,  -- Already present in the target
+    bytes_copied BIGINT NOT NULL DEFAULT 0,
+    failed_keys TEXT[] NOT NULL DEFAULT '{}'
+);
+
+-- Only one run may be in progress at a time
+CREATE UNIQUE INDEX blob_migration_in_progress_idx ON blob_migration ((true)) WHERE completed_at IS NULL;
+
+--
+-- Scheduled tasks
+--
+
+-- Maintenance tasks which site administrators have set up to run regularly,
+-- such as rerendering a listing page every night. The schedule is a cron
+-- expression, and a recurring job runs each task once next_run_at has passed.
+--
+-- The action is a JSON object, see ScheduledAction for its structure.
+CREATE TABLE scheduled_task (
+    task_id BIGSERIAL PRIMARY KEY,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP WITH TIME ZONE,
+    deleted_at TIMESTAMP WITH TIME ZONE,
+    site_id BIGINT NOT NULL REFERENCES site(site_id),
+    created_by BIGINT NOT NULL REFERENCES "user"(user_id),
+    name TEXT NOT NULL,
+    schedule TEXT NOT NULL,
+    action JSONB NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT true,
+    next_run_at TIMESTAMP WITH TIME ZONE,  -- NULL if the schedule never fires again
+    last_run_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE UNIQUE INDEX scheduled_task_name_idx ON scheduled_task (site_id, name) WHERE deleted_at IS NULL;
+CREATE INDEX scheduled_task_due_idx ON scheduled_task (next_run_at) WHERE enabled AND deleted_at IS NULL;
+
+-- The history of each time a scheduled task was run, and what happened.
+CREATE TABLE scheduled_task_run (
+    run_id BIGSERIAL PRIMARY KEY,
+    task_id BIGINT NOT NULL REFERENCES scheduled_task(task_id),
+    site_id BIGINT NOT NULL REFERENCES site(site_id),
+    scheduled_for TIMESTAMP WITH TIME ZONE NOT NULL,
+    started_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    succeeded BOOLEAN NOT NULL,
+    pages_queued INTEGER NOT NULL,
+    error TEXT,
+
+    CHECK (succeeded = (error IS NULL))
+);
+
+CREATE INDEX scheduled_task_run_task_idx ON scheduled_task_run (task_id, run_id);
+
+--
+-- Custom blocks
+--
+
+CREATE TYPE custom_block_body AS ENUM (
+    'none',
+    'text',
+    'elements'
+);
+
+-- Blocks which a site adds to its wikitext, beyond those built into ftml,
+-- such as [[weather]]. These are validated by ftml when created, and loaded
+-- into the parser settings whenever the site's pages are rendered.
+--
+-- The arguments are a JSON array of objects with "name" and "required" fields.
+CREATE TABLE custom_block (
+    custom_block_id BIGSERIAL PRIMARY KEY,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    deleted_at TIMESTAMP WITH TIME ZONE,
+    site_id BIGINT NOT NULL REFERENCES site(site_id),
+    created_by BIGINT NOT NULL REFERENCES "user"(user_id),
+    name TEXT NOT NULL,
+    arguments JSONB NOT NULL DEFAULT '[]',
+    body custom_block_body NOT NULL DEFAULT 'none',
+    template TEXT NOT NULL
+);
+
+CREATE UNIQUE INDEX custom_block_name_idx ON custom_block (site_id, name) WHERE deleted_at IS NULL;
@@ -26,20 +26,32 @@
 //! This module should only contain definitions for the web server and its routes, and
 //! not any of the implementations themselves. Those should be in the `methods` module.
 
-use crate::config::{Config, Secrets};
+use crate::config::{BlobMigrationTarget, Config, Secrets};
 use crate::endpoints::{
-    auth::*, category::*, domain::*, email::*, file::*, file_revision::*, link::*,
-    locale::*, message::*, misc::*, page::*, page_revision::*, parent::*, site::*,
-    site_member::*, text::*, user::*, user_bot::*, view::*, vote::*,
+    account_recovery::*, api_usage::*, audit::*, auth::*, autocomplete::*, blob::*,
+    category::*, cdn::*, consistency::*, custom_block::*, domain::*, email::*,
+    event_stream::*, export::*, file::*, file_revision::*, git_mirror::*, limit::*,
+    link::*, locale::*, message::*, misc::*, oauth::*, page::*, page_draft::*,
+    page_field_watch::*, page_form::*, page_revision::*, page_template::*, parent::*,
+    permission::*, public_api::*, qr_code::*, render_error::*, revision_comparison::*,
+    revision_search::*, scheduled_task::*, search::*, service_account::*, shortlink::*,
+    site::*, site_member::*, special_page::*, stable_revision::*, tag_rule::*, text::*,
+    upload_rule::*, user::*, user_bot::*, view::*, vote::*, watchlist::*,
 };
 use crate::locales::Localizations;
-use crate::services::blob::MimeAnalyzer;
-use crate::services::job::JobWorker;
-use crate::services::{into_rpc_error, ServiceContext};
+use crate::services::api_usage::ApiCall;
+use crate::services::blob::{BlobStore, MimeAnalyzer};
+use crate::services::job::{Job, JobService, JobWorker};
+use crate::services::render::RenderLimiter;
+use crate::services::{
+    into_rpc_error, ApiUsageService, Error as ServiceError, ImpersonationService,
+    PermissionService, ServiceAccountService, ServiceContext,
+};
 use crate::utils::debug_pointer;
 use crate::{database, redis as redis_db};
 use jsonrpsee::server::{RpcModule, Server, ServerHandle};
 use jsonrpsee::types::error::ErrorObjectOwned;
+use jsonrpsee::types::params::Params;
 use redis::aio::ConnectionManager;
 use rsmq_async::MultiplexedRsmq;
 use s3::bucket::Bucket;
@@ -47,6 +59,7 @@ use sea_orm::{DatabaseConnection, TransactionTrait};
 use std::fmt::{self, Debug};
 use std::sync::Arc;
 use std::time::Duration;
+use tokio::time::Instant;
 
 pub type ServerState = Arc<ServerStateInner>;
 
@@ -58,6 +71,9 @@ pub struct ServerStateInner {
     pub localizations: Localizations,
     pub mime_analyzer: MimeAnalyzer,
     pub s3_bucket: Bucket,
+    pub render_limiter: RenderLimiter,
+    pub cdn_signing_key: Vec<u8>,
+    pub blob_migration_target: Option<BlobStore>,
 }
 
 impl Debug for ServerStateInner {
@@ -70,6 +86,8 @@ impl Debug for ServerStateInner {
             .field("localizations", &self.localizations)
             .field("mime_analyzer", &self.mime_analyzer)
             .field("s3_bucket", &self.s3_bucket)
+            .field("render_limiter", &self.render_limiter)
+            .field("blob_migration_target", &self.blob_migration_target)
             .finish()
     }
 }
@@ -95,19 +113,17 @@ pub async fn build_server_state(
     // Create S3 bucket
     info!("Opening S3 bucket");
 
-    let s3_bucket = {
-        let mut bucket = Bucket::new(
-            &secrets.s3_bucket,
-            secrets.s3_region.clone(),
-            secrets.s3_credentials.clone(),
-        )?;
-
-        if secrets.s3_path_style {
-            bucket = bucket.with_path_style();
+    let s3_bucket = open_bucket(&secrets.s3_bucket, &secrets)?;
+    let blob_migration_target = match &secrets.blob_migration_target {
+        None => None,
+        Some(BlobMigrationTarget::S3 { bucket }) => {
+            info!("Opening blob migration target S3 bucket");
+            Some(BlobStore::S3(Box::new(open_bucket(bucket, &secrets)?)))
+        }
+        Some(BlobMigrationTarget::Filesystem { path }) => {
+            info!("Using blob migration target directory {}", path.display());
+            Some(BlobStore::Filesystem(path.clone()))
         }
-
-        bucket.request_timeout = Some(Duration::from_millis(500));
-        bucket
     };
 
     // Build server state
@@ -119,26 +135,112 @@ pub async fn build_server_state(
         localizations,
         mime_analyzer,
         s3_bucket,
+        render_limiter: RenderLimiter::new(),
+        cdn_signing_key: secrets.cdn_signing_key.into_bytes(),
+        blob_migration_target,
     });
 
     // Start workers listening to the job queue (requires ServerState)
     JobWorker::spawn_all(&state);
 
+    // Deliver any events left over from a previous run,
+    // and periodically check for undelivered ones after that.
+    {
+        let txn = state.database.begin().await?;
+        let ctx = ServiceContext::new(&state, &txn);
+        JobService::queue_job(&ctx, &Job::SweepEvents, None).await?;
+        JobService::queue_job(&ctx, &Job::FlushApiUsage, None).await?;
+        JobService::queue_job(&ctx, &Job::FlushBlobSpool, None).await?;
+        JobService::queue_job(&ctx, &Job::RunScheduledTasks, None).await?;
+        JobService::queue_job(
+            &ctx,
+            &Job::CheckConsistency,
+            Some(state.config.job_consistency_check),
+        )
+        .await?;
+        JobService::queue_job(
+            &ctx,
+            &Job::CollectBlobGarbage,
+            Some(state.config.job_collect_blob_garbage),
+        )
+        .await?;
+        JobService::queue_job(
+            &ctx,
+            &Job::PruneUploads,
+            Some(state.config.job_prune_uploads),
+        )
+        .await?;
+        JobService::queue_job(
+            &ctx,
+            &Job::ApplyFileRetention,
+            Some(state.config.job_file_retention),
+        )
+        .await?;
+        txn.commit().await?;
+    }
+
     // Return server state
     Ok(state)
 }
 
 pub async fn build_server(app_state: ServerState) -> anyhow::Result<ServerHandle> {
     let socket_address = app_state.config.address;
-    let server = Server::builder().build(socket_address).await?;
+    let server = Server::builder()
+        .max_request_body_size(app_state.config.maximum_request_bytes)
+        .build(socket_address)
+        .await?;
     let module = build_module(app_state).await?;
     let handle = server.start(module);
     Ok(handle)
 }
 
+fn open_bucket(name: &str, secrets: &Secrets) -> anyhow::Result<Bucket> {
+    let mut bucket = Bucket::new(
+        name,
+        secrets.s3_region.clone(),
+        secrets.s3_credentials.clone(),
+    )?;
+
+    if secrets.s3_path_style {
+        bucket = bucket.with_path_style();
+    }
+
+    bucket.request_timeout = Some(Duration::from_millis(500));
+    Ok(bucket)
+}
+
 async fn build_module(app_state: ServerState) -> anyhow::Result<RpcModule<ServerState>> {
     let mut module = RpcModule::new(app_state);
 
+    // Runs an endpoint's implementation, unless the caller is throttled,
+    // doesn't meet the method's requirement, or their service account may
+    // not call it, and counts the call towards their usage of the site either way.
+    //
+    // Calls made while impersonating a user are recorded before being run.
+    macro_rules! run_method {
+        ($ctx:expr, $call:expr, $service_key:expr, $method:ident, $params:expr $(,)?) => {{
+            let result = async {
+                let service_account =
+                    ServiceAccountService::authenticate($ctx, $call.method, $service_key)
+                        .await?;
+
+                let ctx = $ctx.clone().with_service_account(service_account);
+                PermissionService::authorize(&ctx, $call, &$params).await?;
+                ApiUsageService::check_throttle(&ctx, $call).await?;
+
+                let impersonator_id =
+                    ImpersonationService::record_call(&ctx, $call, &$params).await?;
+
+                let ctx = ctx.with_impersonator(impersonator_id);
+                ctx.until_deadline($method(&ctx, $params)).await
+            }
+            .await;
+
+            ApiUsageService::record($ctx, $call, &result).await;
+            result
+        }};
+    }
+
     macro_rules! register {
         ($name:expr, $method:ident $(,)?) => {{
             // Register async method.
@@ -157,14 +259,49 @@ async fn build_module(app_state: ServerState) -> anyhow::Result<RpcModule<Server
                 //
                 // At this level, we take the database-or-RPC error and make it just an RPC error.
                 let db_state = Arc::clone(&state);
+
+                // Details to enforce throttles and count usage, taken before
+                // the parameters are consumed by the endpoint.
+                let (params, service_key) = split_service_key(params);
+                let call = ApiCall::from_params($name, &params);
+                let deadline = get_deadline(&params, &state.config);
+
+                // For dry runs, the endpoint is run as normal, so that its inputs
+                // and any checks it performs are validated, but the transaction
+                // is always rolled back afterwards.
+                if is_check_only(&params) {
+                    let txn = db_state.database.begin().await.map_err(|error| {
+                        ErrorObjectOwned::from(ServiceError::Database(error))
+                    })?;
+
+                    let result = {
+                        let ctx = ServiceContext::new_check_only(&state, &txn)
+                            .with_caller(call.user_id)
+                            .with_deadline(deadline);
+                        let service_key = service_key.as_deref();
+                        let result = run_method!(&ctx, &call, service_key, $method, params);
+                        result.map_err(ErrorObjectOwned::from)
+                    };
+
+                    txn.rollback().await.map_err(|error| {
+                        ErrorObjectOwned::from(ServiceError::Database(error))
+                    })?;
+
+                    return result;
+                }
+
                 db_state
                     .database
                     .transaction(move |txn| {
                         Box::pin(async move {
                             // Run the endpoint's implementation, and convert from
                             // ServiceError to an RPC error.
-                            let ctx = ServiceContext::new(&state, &txn);
-                            $method(&ctx, params).await.map_err(ErrorObjectOwned::from)
+                            let ctx = ServiceContext::new(&state, &txn)
+                                .with_caller(call.user_id)
+                                .with_deadline(deadline);
+                            let service_key = service_key.as_deref();
+                            let result = run_method!(&ctx, &call, service_key, $method, params);
+                            result.map_err(ErrorObjectOwned::from)
                         })
                     })
                     .await
@@ -175,6 +312,7 @@ async fn build_module(app_state: ServerState) -> anyhow::Result<RpcModule<Server
 
     // Miscellaneous
     register!("ping", ping);
+    register!("health", health);
     register!("error", yield_error);
     register!("version", version);
     register!("version_full", full_version);
@@ -193,15 +331,98 @@ async fn build_module(app_state: ServerState) -> anyhow::Result<RpcModule<Server
 
     // Authentication
     register!("login", auth_login);
+    register!("magic_link_request", auth_magic_link_request);
+    register!("magic_link_login", auth_magic_link_login);
     register!("logout", auth_logout);
     register!("session_get", auth_session_get);
+    register!("session_refresh", auth_session_refresh);
     register!("session_get_others", auth_session_get_others);
     register!("session_invalidate_others", auth_session_invalidate_others);
     register!("session_renew", auth_session_renew);
+    register!("refresh_token_create", auth_refresh_token_create);
+    register!("refresh_token_exchange", auth_refresh_token_exchange);
+    register!("refresh_token_revoke", auth_refresh_token_revoke);
     register!("mfa_verify", auth_mfa_verify);
     register!("mfa_setup", auth_mfa_setup);
     register!("mfa_disable", auth_mfa_disable);
     register!("mfa_reset_recovery", auth_mfa_reset_recovery);
+    register!("impersonation_start", auth_impersonation_start);
+
+    // Account recovery
+    register!("recovery_contact_add", recovery_contact_add);
+    register!("recovery_contact_remove", recovery_contact_remove);
+    register!("recovery_contact_get_all", recovery_contact_get_all);
+    register!("recovery_contact_token", recovery_contact_token);
+    register!("recovery_contact_verify", recovery_contact_verify);
+    register!("recovery_start", recovery_start);
+    register!("recovery_confirm_email", recovery_confirm_email);
+    register!("recovery_confirm_contact", recovery_confirm_contact);
+    register!("recovery_cancel", recovery_cancel);
+    register!("recovery_status", recovery_status);
+    register!("recovery_complete", recovery_complete);
+
+    // OAuth
+    register!("oauth_application_create", oauth_application_create);
+    register!("oauth_application_get", oauth_application_get);
+    register!("oauth_application_get_all", oauth_application_get_all);
+    register!("oauth_application_delete", oauth_application_delete);
+    register!("oauth_authorize", oauth_authorize);
+    register!("oauth_token", oauth_token);
+    register!("oauth_token_check", oauth_token_check);
+    register!("oauth_authorization_get_all", oauth_authorization_get_all);
+    register!("oauth_authorization_revoke", oauth_authorization_revoke);
+
+    // Audit log
+    register!("audit_log_get", audit_log_get);
+
+    // API usage
+    register!("api_usage_get", api_usage_get);
+    register!("api_throttle_set", api_throttle_set);
+    register!("api_access_revoke", api_access_revoke);
+
+    // Service accounts
+    register!("service_account_create", service_account_create);
+    register!("service_account_get_all", service_account_get_all);
+    register!("service_account_update", service_account_update);
+    register!("service_account_rotate_key", service_account_rotate_key);
+    register!("service_account_revoke", service_account_revoke);
+
+    // Permissions
+    register!("permission_matrix_get", permission_matrix_get);
+    register!("permission_acl_set", permission_acl_set);
+    register!("permission_acl_get_all", permission_acl_get_all);
+    register!("permission_explain", permission_explain);
+
+    // Event stream
+    register!("event_stream_get", event_stream_get);
+    register!("event_stream_group_create", event_stream_group_create);
+    register!("event_stream_group_delete", event_stream_group_delete);
+    register!("event_stream_group_replay", event_stream_group_replay);
+
+    // Search index
+    register!("search_index_rebuild", search_index_rebuild);
+    register!("search_index_check", search_index_check);
+
+    // Link recomputation
+    register!("link_recompute_start", link_recompute_start);
+    register!("link_recompute_get", link_recompute_get);
+
+    // Consistency checks
+    register!("consistency_check_run", consistency_check_run);
+    register!("consistency_report_get_all", consistency_report_get_all);
+
+    // Blob garbage collection
+    register!("blob_collection_run", blob_collection_run);
+    register!("blob_collection_get_all", blob_collection_get_all);
+
+    // Blob migration
+    register!("blob_migration_start", blob_migration_start);
+    register!("blob_migration_get", blob_migration_get);
+
+    // Autocomplete
+    register!("autocomplete_page", autocomplete_page);
+    register!("autocomplete_user", autocomplete_user);
+    register!("autocomplete_tag", autocomplete_tag);
 
     // Site
     register!("site_create", site_create);
@@ -214,14 +435,50 @@ async fn build_module(app_state: ServerState) -> anyhow::Result<RpcModule<Server
     register!("custom_domain_get", site_custom_domain_get);
     register!("custom_domain_delete", site_custom_domain_delete);
 
+    // Site limits
+    register!("site_limits_get", site_limits_get);
+    register!("site_limits_set", site_limits_set);
+    register!("site_storage_usage", site_storage_usage);
+
+    // Tag rules
+    register!("tag_rule_create", tag_rule_create);
+    register!("tag_rule_get_all", tag_rule_get_all);
+    register!("tag_rule_delete", tag_rule_delete);
+
+    // Custom blocks
+    register!("custom_block_create", custom_block_create);
+    register!("custom_block_get_all", custom_block_get_all);
+    register!("custom_block_delete", custom_block_delete);
+
+    // Upload rules
+    register!("upload_rule_create", upload_rule_create);
+    register!("upload_rule_get_all", upload_rule_get_all);
+    register!("upload_rule_delete", upload_rule_delete);
+
+    // Scheduled tasks
+    register!("scheduled_task_create", scheduled_task_create);
+    register!("scheduled_task_get_all", scheduled_task_get_all);
+    register!("scheduled_task_update", scheduled_task_update);
+    register!("scheduled_task_delete", scheduled_task_delete);
+    register!("scheduled_task_run_get_all", scheduled_task_run_get_all);
+
     // Site membership
     register!("member_set", membership_set);
     register!("member_get", membership_get);
     register!("member_delete", membership_delete);
 
+    // Special pages
+    register!("special_page_get", special_page_get);
+    register!("special_page_override_get", special_page_override_get);
+    register!("special_page_override_set", special_page_override_set);
+    register!("special_page_override_delete", special_page_override_delete);
+
     // Category
     register!("category_get", category_get);
     register!("category_get_all", category_get_all);
+    register!("category_indexing_edit", category_indexing_edit);
+    register!("category_render_edit", category_render_edit);
+    register!("category_form_edit", category_form_edit);
 
     // Page
     register!("page_create", page_create);
@@ -232,13 +489,46 @@ async fn build_module(app_state: ServerState) -> anyhow::Result<RpcModule<Server
     register!("page_move", page_move);
     register!("page_rollback", page_rollback);
     register!("page_rerender", page_rerender);
+    register!("page_preview", page_preview);
+    register!("page_print", page_print);
     register!("page_restore", page_restore);
+    register!("page_indexing_edit", page_indexing_edit);
+    register!("page_get_indexable", page_get_indexable);
+
+    // Page drafts
+    register!("page_draft_create", page_draft_create);
+    register!("page_draft_get", page_draft_get);
+    register!("page_draft_get_all", page_draft_get_all);
+    register!("page_draft_edit", page_draft_edit);
+    register!("page_draft_delete", page_draft_delete);
+    register!("page_draft_publish", page_draft_publish);
+    register!("page_draft_schedule", page_draft_schedule);
+
+    // Page forms
+    register!("page_form_get", page_form_get);
+
+    // Page field watches
+    register!("page_field_watch_create", page_field_watch_create);
+    register!("page_field_watch_delete", page_field_watch_delete);
+    register!("page_field_watch_get_all", page_field_watch_get_all);
+
+    // Page templates
+    register!("page_template_get", page_template_get);
+    register!("page_template_instantiate", page_template_instantiate);
 
     // Page revisions
     register!("page_revision_create", page_revision_edit);
     register!("page_revision_get", page_revision_get);
     register!("page_revision_count", page_revision_count);
     register!("page_revision_range", page_revision_range);
+    register!("page_revision_diff", page_revision_diff);
+    register!("page_revision_blame", page_revision_blame);
+    register!("page_revision_mass_revert", page_revision_mass_revert);
+    register!("page_revision_compare", page_revision_compare);
+    register!("page_revision_comparison_get", page_revision_comparison_get);
+
+    // Revision search
+    register!("revision_comment_search", revision_comment_search);
 
     // Page links
     register!("page_get_links_from", page_links_from_get);
@@ -246,6 +536,38 @@ async fn build_module(app_state: ServerState) -> anyhow::Result<RpcModule<Server
     register!("page_get_links_to_missing", page_links_to_missing_get);
     register!("page_get_urls_from", page_links_external_from);
     register!("page_get_urls_to", page_links_external_to);
+    register!("site_get_broken_references", site_broken_references_get);
+
+    // Page render errors
+    register!("page_get_render_errors", page_render_errors_get);
+    register!("site_get_render_errors", site_render_errors_get);
+
+    // Shortlinks
+    register!("shortlink_create", shortlink_create);
+    register!("shortlink_get_page", shortlink_get_page);
+    register!("shortlink_resolve", shortlink_resolve);
+
+    // QR codes
+    register!("qr_code_get", qr_code_get);
+
+    // Exports
+    register!("export_create", export_create);
+    register!("export_get", export_get);
+    register!("export_download", export_download);
+
+    // Public API
+    register!("public_page_get", public_page_get);
+    register!("public_page_list", public_page_list);
+    register!("public_user_get", public_user_get);
+
+    // CDN
+    register!("cdn_get", cdn_get);
+
+    // Git mirrors
+    register!("git_mirror_get", git_mirror_get);
+    register!("git_mirror_set", git_mirror_set);
+    register!("git_mirror_remove", git_mirror_remove);
+    register!("git_mirror_file", git_mirror_file);
 
     // Page parents
     register!("parent_set", parent_set);
@@ -255,7 +577,19 @@ async fn build_module(app_state: ServerState) -> anyhow::Result<RpcModule<Server
 
     // Files
     register!("file_upload", file_upload);
+    register!("file_paste", file_paste);
+    register!("file_upload_start", file_upload_start);
+    register!("file_upload_start_chunked", file_upload_start_chunked);
+    register!("file_upload_append", file_upload_append);
+    register!("file_upload_status", file_upload_status);
+    register!("file_upload_abort", file_upload_abort);
+    register!("file_upload_finish", file_upload_finish);
+    register!("file_stage", file_stage);
+    register!("file_staged_get_all", file_staged_get_all);
+    register!("file_staged_remove", file_staged_remove);
+    register!("file_attach", file_attach);
     register!("file_get", file_get);
+    register!("file_download_url", file_download_url);
     register!("file_edit", file_edit);
     register!("file_delete", file_delete);
     register!("file_move", file_move);
@@ -265,8 +599,19 @@ async fn build_module(app_state: ServerState) -> anyhow::Result<RpcModule<Server
     // File revisions
     register!("file_revision_get", file_revision_get);
     register!("file_revision_edit", file_revision_edit);
+    register!("file_revision_edit_many", file_revision_edit_many);
+    register!("file_revision_purge_blob", file_revision_purge_blob);
     register!("file_revision_count", file_revision_count);
     register!("file_revision_range", file_revision_range);
+    register!("file_revision_diff", file_revision_diff);
+    register!("file_revision_restore", file_revision_restore);
+    register!("file_revision_revert", file_revision_revert);
+    register!("file_thumbnail_get", file_thumbnail_get);
+
+    // Stable revisions
+    register!("page_stable_set", page_stable_set);
+    register!("page_pending_changes_get", page_pending_changes_get);
+    register!("file_stable_set", file_stable_set);
 
     // Text
     register!("text_create", text_create);
@@ -303,6 +648,131 @@ async fn build_module(app_state: ServerState) -> anyhow::Result<RpcModule<Server
     register!("vote_list", vote_list_get);
     register!("vote_list_count", vote_list_count);
 
+    // Watchlist
+    register!("watchlist_get", watchlist_get);
+    register!("watchlist_mark_seen", watchlist_mark_seen);
+
     // Return
     Ok(module)
 }
+
+/// Separates the key of the service account making this call from its parameters.
+///
+/// Endpoints which take an object of parameters can be passed the key alongside
+/// them, as `"service_key"`. For any endpoint, including those which take
+/// positional parameters, the parameters can instead be wrapped, as
+/// `{"service_key": "...", "params": [...]}`.
+fn split_service_key(params: Params<'static>) -> (Params<'static>, Option<String>) {
+    #[derive(Deserialize, Debug)]
+    #[serde(deny_unknown_fields)]
+    struct Wrapped {
+        service_key: String,
+        params: serde_json::Value,
+    }
+
+    #[derive(Deserialize, Debug)]
+    struct ServiceKey {
+        #[serde(default)]
+        service_key: Option<String>,
+    }
+
+    if let Ok(Wrapped {
+        service_key,
+        params: inner,
+    }) = params.parse()
+    {
+        let inner = match inner {
+            serde_json::Value::Null => Params::new(None),
+            inner => Params::new(Some(&inner.to_string())).into_owned(),
+        };
+
+        return (inner, Some(service_key));
+    }
+
+    let service_key = params
+        .parse::<ServiceKey>()
+        .ok()
+        .and_then(|input| input.service_key);
+
+    (params, service_key)
+}
+
+/// When this call must finish by, from the configured limit or a shorter `"deadline_ms"`.
+///
+/// Callers can't extend the deadline past the configured limit.
+fn get_deadline(params: &Params, config: &Config) -> Instant {
+    #[derive(Deserialize, Debug)]
+    struct Deadline {
+        #[serde(default)]
+        deadline_ms: Option<u64>,
+    }
+
+    let duration = params
+        .parse::<Deadline>()
+        .ok()
+        .and_then(|input| input.deadline_ms)
+        .map(Duration::from_millis)
+        .map_or(config.request_deadline, |duration| {
+            duration.min(config.request_deadline)
+        });
+
+    Instant::now() + duration
+}
+
+/// Whether this call is a dry run, set by passing `"check_only": true`.
+///
+/// This works with any endpoint which takes an object of parameters.
+fn is_check_only(params: &Params) -> bool {
+    #[derive(Deserialize, Debug)]
+    struct CheckOnly {
+        #[serde(default)]
+        check_only: bool,
+    }
+
+    params
+        .parse::<CheckOnly>()
+        .map(|input| input.check_only)
+        .unwrap_or(false)
+}
+
+#[test]
+fn service_key() {
+    macro_rules! check {
+        ($params:expr, $expected_params:expr, $expected_key:expr $(,)?) => {{
+            let (params, service_key) = split_service_key(Params::new($params));
+            assert_eq!(
+                params.as_str(),
+                $expected_params,
+                "Unwrapped params don't match"
+            );
+            assert_eq!(
+                service_key.as_deref(),
+                $expected_key,
+                "Service key doesn't match"
+            );
+        }};
+    }
+
+    check!(None, None, None);
+    check!(Some(r#"[1]"#), Some("[1]"), None);
+    check!(
+        Some(r#"{"site_id":1,"service_key":"abc"}"#),
+        Some(r#"{"site_id":1,"service_key":"abc"}"#),
+        Some("abc"),
+    );
+    check!(
+        Some(r#"{"service_key":"abc","params":[1]}"#),
+        Some("[1]"),
+        Some("abc"),
+    );
+    check!(
+        Some(r#"{"service_key":"abc"}"#),
+        Some(r#"{"service_key":"abc"}"#),
+        Some("abc"),
+    );
+    check!(
+        Some(r#"{"service_key":"abc","params":null}"#),
+        None,
+        Some("abc")
+    );
+}
@@ -19,8 +19,11 @@
  */
 
 use super::Config;
+use crate::services::file_scan::FileScanner;
+use crate::utils::RedactionPolicy;
+use crate::web::RenderSeverity;
 use anyhow::Result;
-use femme::LevelFilter;
+use log::LevelFilter;
 use std::convert::TryFrom;
 use std::fs::File;
 use std::io::Read;
@@ -43,6 +46,7 @@ use time::Duration as TimeDuration;
 #[serde(rename_all = "kebab-case")]
 pub struct ConfigFile {
     logger: Logger,
+    privacy: Privacy,
     server: Server,
     database: Database,
     security: Security,
@@ -53,6 +57,26 @@ pub struct ConfigFile {
     special_pages: SpecialPages,
     user: User,
     message: Message,
+    shortlink: Shortlink,
+    qr_code: QrCode,
+    page_blame: PageBlame,
+    thumbnail: Thumbnail,
+    file_upload: FileUpload,
+    file_download: FileDownload,
+    file_scan: FileScan,
+    export: Export,
+    git_mirror: GitMirror,
+    public_api: PublicApi,
+    cdn: Cdn,
+    event_stream: EventStream,
+    autocomplete: Autocomplete,
+    api_usage: ApiUsage,
+    blob_spool: BlobSpool,
+    blob_compression: BlobCompression,
+    blob_collection: BlobCollection,
+    blob_migration: BlobMigration,
+    scheduled_task: ScheduledTask,
+    limits: Limits,
 }
 
 /// Structure containing extra fields not found in `ConfigFile`.
@@ -72,11 +96,22 @@ struct Logger {
     level: LevelFilter,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+struct Privacy {
+    redact_emails: bool,
+    redact_session_tokens: bool,
+    ipv4_prefix_bits: u8,
+    ipv6_prefix_bits: u8,
+    truncate_stored_ips: bool,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "kebab-case")]
 struct Server {
     address: SocketAddr,
     pid_file: Option<PathBuf>,
+    request_deadline_ms: u64,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -93,6 +128,12 @@ struct Security {
     authentication_fail_delay_ms: u64,
     session: Session,
     mfa: Mfa,
+    impersonation: Impersonation,
+    magic_link: MagicLink,
+    refresh_token: RefreshToken,
+    recovery: Recovery,
+    oauth: Oauth,
+    service_account: ServiceAccount,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -102,6 +143,9 @@ struct Session {
     token_length: usize,
     duration_session_minutes: u64,
     duration_login_minutes: u64,
+    maximum_duration_hours: u64,
+    rotation_minutes: u64,
+    rotation_grace_secs: u64,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -113,6 +157,49 @@ struct Mfa {
     time_skew: i64,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+struct Impersonation {
+    admins: Vec<i64>,
+    duration_minutes: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+struct MagicLink {
+    duration_minutes: u64,
+    rate_limit_requests: u64,
+    rate_limit_window_secs: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+struct RefreshToken {
+    duration_days: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+struct Recovery {
+    required_confirmations: u16,
+    delay_hours: u64,
+    expiry_days: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+struct Oauth {
+    code_duration_minutes: u64,
+    access_token_duration_minutes: u64,
+    refresh_token_duration_days: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+struct ServiceAccount {
+    required: bool,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "kebab-case")]
 struct Job {
@@ -125,6 +212,15 @@ struct Job {
     prune_text_secs: u64,
     name_change_refill_secs: u64,
     lift_expired_punishments_secs: u64,
+    sweep_events_secs: u64,
+    flush_api_usage_secs: u64,
+    consistency_check_secs: u64,
+    consistency_repair: bool,
+    flush_blob_spool_secs: u64,
+    scheduled_tasks_secs: u64,
+    collect_blob_garbage_secs: u64,
+    prune_uploads_secs: u64,
+    file_retention_secs: u64,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -144,6 +240,12 @@ struct Domain {
 #[serde(rename_all = "kebab-case")]
 struct Ftml {
     render_timeout_ms: u64,
+    error_threshold: RenderSeverity,
+    error_notify_watchers: bool,
+    error_notify_site: bool,
+    shed_threshold: usize,
+    shed_retry_after_secs: u64,
+    settings_cache_ttl_secs: u64,
     rerender_skip: Vec<RerenderSkip>,
 }
 
@@ -162,6 +264,8 @@ struct SpecialPages {
     missing: String,
     private: String,
     banned: String,
+    join: String,
+    template_category: String,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -181,6 +285,166 @@ struct Message {
     maximum_recipients: usize,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+struct Shortlink {
+    code_length: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+struct QrCode {
+    default_size: u32,
+    maximum_size: u32,
+    cache_ttl_secs: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+struct PageBlame {
+    cache_ttl_secs: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+struct Thumbnail {
+    sizes: Vec<u32>,
+    maximum_source_pixels: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+struct FileUpload {
+    url_expiry_secs: u32,
+    chunked_expiry_secs: u64,
+    maximum_pending_per_user: u64,
+    staging_expiry_secs: u64,
+    staging_maximum_files_per_user: u64,
+    staging_maximum_bytes_per_user: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+struct FileDownload {
+    url_expiry_secs: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+struct FileScan {
+    scanner: FileScanner,
+    clamd_address: String,
+    timeout_secs: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+struct Export {
+    maximum_pages: usize,
+    license: String,
+    pdf_renderer_url: Option<String>,
+    pdf_renderer_timeout_secs: u64,
+    pdf_allowed_origins: Vec<String>,
+    maximum_daily_pdf_exports: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+struct GitMirror {
+    directory: Option<PathBuf>,
+    batch_size: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+struct PublicApi {
+    browser_max_age_secs: u64,
+    edge_max_age_secs: u64,
+    stale_secs: u64,
+    purge_url: Option<String>,
+    listing_limit: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+struct Cdn {
+    max_age_secs: u64,
+    signed_url_expiry_secs: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+struct EventStream {
+    enabled: bool,
+    key: String,
+    max_length: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+struct Autocomplete {
+    maximum_results: u64,
+    rate_limit_requests: u64,
+    rate_limit_window_secs: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+struct ApiUsage {
+    enabled: bool,
+    retention_days: u16,
+    maximum_report_days: u16,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+struct BlobSpool {
+    enabled: bool,
+    maximum_blob_bytes: u64,
+    maximum_total_bytes: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+struct BlobCompression {
+    enabled: bool,
+    level: i32,
+    minimum_bytes: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+struct BlobCollection {
+    dry_run: bool,
+    minimum_age_hours: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+struct BlobMigration {
+    batch_size: usize,
+    batch_bytes: u64,
+    batch_delay_secs: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+struct ScheduledTask {
+    maximum_per_site: u64,
+    maximum_pages: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+struct Limits {
+    maximum_request_bytes: u32,
+    maximum_wikitext_bytes: u64,
+    maximum_comment_bytes: u64,
+    maximum_tags: u64,
+    maximum_upload_bytes: u64,
+    maximum_storage_bytes: u64,
+}
+
 impl ConfigFile {
     pub fn load(path: PathBuf) -> Result<(Self, ExtraConfig)> {
         // Read TOML
@@ -222,10 +486,19 @@ impl ConfigFile {
                     enable: logger,
                     level: logger_level,
                 },
+            privacy:
+                Privacy {
+                    redact_emails,
+                    redact_session_tokens,
+                    ipv4_prefix_bits,
+                    ipv6_prefix_bits,
+                    truncate_stored_ips,
+                },
             server:
                 Server {
                     address,
                     mut pid_file,
+                    request_deadline_ms,
                 },
             database:
                 Database {
@@ -242,6 +515,9 @@ impl ConfigFile {
                             token_length,
                             duration_session_minutes,
                             duration_login_minutes,
+                            maximum_duration_hours: session_maximum_duration_hours,
+                            rotation_minutes: session_rotation_minutes,
+                            rotation_grace_secs: session_rotation_grace_secs,
                         },
                     mfa:
                         Mfa {
@@ -250,6 +526,38 @@ impl ConfigFile {
                             time_step,
                             time_skew,
                         },
+                    impersonation:
+                        Impersonation {
+                            admins: impersonation_admins,
+                            duration_minutes: impersonation_duration_minutes,
+                        },
+                    magic_link:
+                        MagicLink {
+                            duration_minutes: magic_link_duration_minutes,
+                            rate_limit_requests: magic_link_rate_limit_requests,
+                            rate_limit_window_secs: magic_link_rate_limit_window_secs,
+                        },
+                    refresh_token:
+                        RefreshToken {
+                            duration_days: refresh_token_duration_days,
+                        },
+                    recovery:
+                        Recovery {
+                            required_confirmations: recovery_required_confirmations,
+                            delay_hours: recovery_delay_hours,
+                            expiry_days: recovery_expiry_days,
+                        },
+                    oauth:
+                        Oauth {
+                            code_duration_minutes: oauth_code_duration_minutes,
+                            access_token_duration_minutes:
+                                oauth_access_token_duration_minutes,
+                            refresh_token_duration_days: oauth_refresh_token_duration_days,
+                        },
+                    service_account:
+                        ServiceAccount {
+                            required: service_account_required,
+                        },
                 },
             domain:
                 Domain {
@@ -267,6 +575,15 @@ impl ConfigFile {
                     prune_text_secs: job_prune_text_secs,
                     name_change_refill_secs: job_name_change_refill_secs,
                     lift_expired_punishments_secs: job_lift_expired_punishments_secs,
+                    sweep_events_secs: job_sweep_events_secs,
+                    flush_api_usage_secs: job_flush_api_usage_secs,
+                    consistency_check_secs: job_consistency_check_secs,
+                    consistency_repair: job_consistency_repair,
+                    flush_blob_spool_secs: job_flush_blob_spool_secs,
+                    scheduled_tasks_secs: job_scheduled_tasks_secs,
+                    collect_blob_garbage_secs: job_collect_blob_garbage_secs,
+                    prune_uploads_secs: job_prune_uploads_secs,
+                    file_retention_secs: job_file_retention_secs,
                 },
             locale: Locale {
                 path: localization_path,
@@ -274,6 +591,12 @@ impl ConfigFile {
             ftml:
                 Ftml {
                     render_timeout_ms,
+                    error_threshold: render_error_threshold,
+                    error_notify_watchers: render_error_notify_watchers,
+                    error_notify_site: render_error_notify_site,
+                    shed_threshold: render_shed_threshold,
+                    shed_retry_after_secs: render_shed_retry_after_secs,
+                    settings_cache_ttl_secs: render_settings_cache_ttl_secs,
                     rerender_skip,
                 },
             special_pages:
@@ -283,6 +606,8 @@ impl ConfigFile {
                     missing: special_page_missing,
                     private: special_page_private,
                     banned: special_page_banned,
+                    join: special_page_join,
+                    template_category: special_page_template_category,
                 },
             user:
                 User {
@@ -297,6 +622,126 @@ impl ConfigFile {
                     maximum_body_bytes: maximum_message_body_bytes,
                     maximum_recipients: maximum_message_recipients,
                 },
+            shortlink:
+                Shortlink {
+                    code_length: shortlink_code_length,
+                },
+            qr_code:
+                QrCode {
+                    default_size: qr_code_default_size,
+                    maximum_size: qr_code_maximum_size,
+                    cache_ttl_secs: qr_code_cache_ttl_secs,
+                },
+            page_blame:
+                PageBlame {
+                    cache_ttl_secs: page_blame_cache_ttl_secs,
+                },
+            thumbnail:
+                Thumbnail {
+                    sizes: mut thumbnail_sizes,
+                    maximum_source_pixels: thumbnail_maximum_source_pixels,
+                },
+            file_upload:
+                FileUpload {
+                    url_expiry_secs: file_upload_url_expiry_secs,
+                    chunked_expiry_secs: file_upload_chunked_expiry_secs,
+                    maximum_pending_per_user: file_upload_maximum_pending,
+                    staging_expiry_secs: file_staging_expiry_secs,
+                    staging_maximum_files_per_user: file_staging_maximum_files,
+                    staging_maximum_bytes_per_user: file_staging_maximum_bytes,
+                },
+            file_download:
+                FileDownload {
+                    url_expiry_secs: file_download_url_expiry_secs,
+                },
+            file_scan:
+                FileScan {
+                    scanner: file_scanner,
+                    clamd_address: file_scan_clamd_address,
+                    timeout_secs: file_scan_timeout_secs,
+                },
+            export:
+                Export {
+                    maximum_pages: maximum_export_pages,
+                    license: export_license,
+                    pdf_renderer_url: mut export_pdf_renderer_url,
+                    pdf_renderer_timeout_secs: export_pdf_renderer_timeout_secs,
+                    pdf_allowed_origins: export_pdf_allowed_origins,
+                    maximum_daily_pdf_exports,
+                },
+            git_mirror:
+                GitMirror {
+                    directory: mut git_mirror_directory,
+                    batch_size: git_mirror_batch_size,
+                },
+            public_api:
+                PublicApi {
+                    browser_max_age_secs: public_api_browser_max_age_secs,
+                    edge_max_age_secs: public_api_edge_max_age_secs,
+                    stale_secs: public_api_stale_secs,
+                    purge_url: mut public_api_purge_url,
+                    listing_limit: public_api_listing_limit,
+                },
+            cdn:
+                Cdn {
+                    max_age_secs: cdn_max_age_secs,
+                    signed_url_expiry_secs: cdn_signed_url_expiry_secs,
+                },
+            event_stream:
+                EventStream {
+                    enabled: event_stream_enabled,
+                    key: event_stream_key,
+                    max_length: event_stream_max_length,
+                },
+            autocomplete:
+                Autocomplete {
+                    maximum_results: autocomplete_maximum_results,
+                    rate_limit_requests: autocomplete_rate_limit_requests,
+                    rate_limit_window_secs: autocomplete_rate_limit_window_secs,
+                },
+            api_usage:
+                ApiUsage {
+                    enabled: api_usage_enabled,
+                    retention_days: api_usage_retention_days,
+                    maximum_report_days: api_usage_maximum_report_days,
+                },
+            blob_spool:
+                BlobSpool {
+                    enabled: blob_spool_enabled,
+                    maximum_blob_bytes: blob_spool_maximum_blob_bytes,
+                    maximum_total_bytes: blob_spool_maximum_total_bytes,
+                },
+            blob_compression:
+                BlobCompression {
+                    enabled: blob_compression_enabled,
+                    level: blob_compression_level,
+                    minimum_bytes: blob_compression_minimum_bytes,
+                },
+            blob_collection:
+                BlobCollection {
+                    dry_run: blob_collection_dry_run,
+                    minimum_age_hours: blob_collection_minimum_age_hours,
+                },
+            blob_migration:
+                BlobMigration {
+                    batch_size: blob_migration_batch_size,
+                    batch_bytes: blob_migration_batch_bytes,
+                    batch_delay_secs: blob_migration_batch_delay_secs,
+                },
+            scheduled_task:
+                ScheduledTask {
+                    maximum_per_site: scheduled_task_maximum_per_site,
+                    maximum_pages: scheduled_task_maximum_pages,
+                },
+            limits:
+                Limits {
+                    maximum_request_bytes,
+                    maximum_wikitext_bytes,
+                    maximum_comment_bytes,
+                    maximum_tags,
+                    maximum_upload_bytes,
+                    maximum_storage_bytes,
+                },
         } = self;
 
         // Assertions for bad values
@@ -306,6 +751,7 @@ impl ConfigFile {
             job_prune_session_secs < RSMQ_DELAY_LIMIT,
             "Session prune job period time too long",
         );
+        assert!(request_deadline_ms > 0, "Request deadline must be positive");
         assert!(
             job_prune_text_secs < RSMQ_DELAY_LIMIT,
             "Text prune job period time too long",
@@ -318,12 +764,129 @@ impl ConfigFile {
             job_lift_expired_punishments_secs < RSMQ_DELAY_LIMIT,
             "Expired punishment cleanup job period time too long",
         );
+        assert!(
+            job_sweep_events_secs < RSMQ_DELAY_LIMIT,
+            "Event outbox sweep job period time too long",
+        );
+        assert!(
+            job_flush_api_usage_secs < RSMQ_DELAY_LIMIT,
+            "API usage flush job period time too long",
+        );
+        assert!(
+            job_consistency_check_secs < RSMQ_DELAY_LIMIT,
+            "Consistency check job period time too long",
+        );
+        assert!(
+            job_flush_blob_spool_secs < RSMQ_DELAY_LIMIT,
+            "Blob spool flush job period time too long",
+        );
+        assert!(
+            job_scheduled_tasks_secs > 0 && job_scheduled_tasks_secs < RSMQ_DELAY_LIMIT,
+            "Scheduled task job period time must be positive and not too long",
+        );
+        assert!(
+            job_collect_blob_garbage_secs < RSMQ_DELAY_LIMIT,
+            "Blob garbage collection job period time too long",
+        );
+        assert!(
+            job_prune_uploads_secs < RSMQ_DELAY_LIMIT,
+            "Upload prune job period time too long",
+        );
+        assert!(
+            job_file_retention_secs < RSMQ_DELAY_LIMIT,
+            "File retention job period time too long",
+        );
+        assert!(
+            blob_collection_minimum_age_hours > 0,
+            "Blob garbage collection must leave recent blobs alone",
+        );
+        assert!(
+            blob_migration_batch_size > 0 && blob_migration_batch_size <= 1000,
+            "Blob migration batch size must be between 1 and 1000, as S3 lists at most that many",
+        );
+        assert!(
+            blob_migration_batch_delay_secs < RSMQ_DELAY_LIMIT,
+            "Blob migration batch delay too long",
+        );
+        assert!(
+            thumbnail_sizes
+                .iter()
+                .all(|&size| size > 0 && i32::try_from(size).is_ok()),
+            "Thumbnail sizes must be positive and fit in an INTEGER column",
+        );
+        assert!(
+            file_upload_url_expiry_secs > 0 && file_upload_url_expiry_secs <= 604800,
+            "Upload URL expiry must be between 1 second and 7 days, as S3 requires",
+        );
+        assert!(
+            file_upload_chunked_expiry_secs > 0,
+            "Chunked uploads must be kept for some time between chunks",
+        );
+        assert!(
+            file_download_url_expiry_secs > 0 && file_download_url_expiry_secs <= 604800,
+            "Download URL expiry must be between 1 second and 7 days, as S3 requires",
+        );
+        assert!(
+            file_staging_expiry_secs > 0,
+            "Staged files must be kept for some time",
+        );
+        assert!(
+            file_scanner != FileScanner::Clamd || !file_scan_clamd_address.is_empty(),
+            "Scanning with clamd requires its address",
+        );
+        assert!(
+            file_scan_timeout_secs > 0,
+            "File scan timeout must be positive"
+        );
+        assert!(
+            maximum_wikitext_bytes < u64::from(maximum_request_bytes)
+                && maximum_upload_bytes * 2 < u64::from(maximum_request_bytes),
+            "Request size limit is too small for the wikitext or upload limits",
+        );
+        assert!(
+            maximum_storage_bytes >= maximum_upload_bytes
+                && maximum_storage_bytes <= i64::MAX as u64,
+            "Storage limit must fit at least one upload, and fit in a BIGINT column",
+        );
+        assert!(
+            recovery_required_confirmations > 0 && recovery_expiry_days > 0,
+            "Account recovery must require confirmations and have time to complete",
+        );
+        assert!(
+            oauth_code_duration_minutes > 0
+                && oauth_access_token_duration_minutes > 0
+                && oauth_refresh_token_duration_days * 24 * 60
+                    >= oauth_access_token_duration_minutes,
+            "OAuth durations must be positive, and refresh tokens must outlive access tokens",
+        );
+        assert!(
+            blob_spool_maximum_blob_bytes <= blob_spool_maximum_total_bytes,
+            "Blob spool total size limit is smaller than the per-blob limit",
+        );
+        assert!(
+            (1..=22).contains(&blob_compression_level),
+            "Blob compression level must be between 1 and 22",
+        );
+
+        // Build the redaction policy now, since it needs the session token prefix
+        let redaction_policy = RedactionPolicy::new(
+            redact_emails,
+            redact_session_tokens,
+            &token_prefix,
+            ipv4_prefix_bits,
+            ipv6_prefix_bits,
+        );
 
         // Prefix domains with '.' so we can do easy subdomain checks
         // and concatenations.
         let (main_domain, main_domain_no_dot) = prefix_domain(main_domain);
         let (files_domain, files_domain_no_dot) = prefix_domain(files_domain);
 
+        // Order doesn't matter in the configuration, but duplicates would
+        // produce the same thumbnail twice.
+        thumbnail_sizes.sort_unstable();
+        thumbnail_sizes.dedup();
+
         // Treats empty strings (which aren't valid paths anyways)
         // as null for the purpose of pid_file.
         if let Some(ref path) = pid_file {
@@ -332,13 +895,37 @@ impl ConfigFile {
             }
         }
 
+        // Same for the PDF renderer, where it means PDF exports are disabled.
+        if let Some(ref url) = export_pdf_renderer_url {
+            if url.is_empty() {
+                export_pdf_renderer_url = None;
+            }
+        }
+
+        // And for Git mirrors, where it means they are disabled.
+        if let Some(ref path) = git_mirror_directory {
+            if path.as_os_str().is_empty() {
+                git_mirror_directory = None;
+            }
+        }
+
+        // And for edge cache purging, where it means it is not done.
+        if let Some(ref url) = public_api_purge_url {
+            if url.is_empty() {
+                public_api_purge_url = None;
+            }
+        }
+
         Config {
             raw_toml,
             raw_toml_path,
             logger,
             logger_level,
+            redaction_policy,
+            truncate_stored_ips,
             address,
             pid_file,
+            request_deadline: StdDuration::from_millis(request_deadline_ms),
             main_domain,
             main_domain_no_dot,
             files_domain,
@@ -361,10 +948,58 @@ impl ConfigFile {
                 from_secs,
                 duration_login_minutes * 60,
             ),
+            maximum_session_duration: time_duration!(
+                from_secs,
+                session_maximum_duration_hours * 60 * 60,
+            ),
+            session_rotation_interval: time_duration!(
+                from_secs,
+                session_rotation_minutes * 60,
+            ),
+            session_rotation_grace: time_duration!(
+                from_secs,
+                session_rotation_grace_secs
+            ),
             recovery_code_count,
             recovery_code_length,
             totp_time_step: time_step,
             totp_time_skew: time_skew,
+            impersonation_admins,
+            impersonation_session_duration: time_duration!(
+                from_secs,
+                impersonation_duration_minutes * 60,
+            ),
+            magic_link_duration: time_duration!(
+                from_secs,
+                magic_link_duration_minutes * 60,
+            ),
+            magic_link_rate_limit_requests,
+            magic_link_rate_limit_window: StdDuration::from_secs(
+                magic_link_rate_limit_window_secs,
+            ),
+            refresh_token_duration: time_duration!(
+                from_secs,
+                refresh_token_duration_days * 24 * 60 * 60,
+            ),
+            recovery_required_confirmations,
+            recovery_delay: time_duration!(from_secs, recovery_delay_hours * 60 * 60),
+            recovery_expiry: time_duration!(
+                from_secs,
+                recovery_expiry_days * 24 * 60 * 60,
+            ),
+            oauth_code_duration: time_duration!(
+                from_secs,
+                oauth_code_duration_minutes * 60,
+            ),
+            oauth_access_token_duration: time_duration!(
+                from_secs,
+                oauth_access_token_duration_minutes * 60,
+            ),
+            oauth_refresh_token_duration: time_duration!(
+                from_secs,
+                oauth_refresh_token_duration_days * 24 * 60 * 60,
+            ),
+            service_account_required,
             job_workers,
             job_max_attempts,
             job_work_delay: StdDuration::from_millis(job_work_delay_ms),
@@ -376,7 +1011,26 @@ impl ConfigFile {
             job_lift_expired_punishments: StdDuration::from_secs(
                 job_lift_expired_punishments_secs,
             ),
+            job_sweep_events: StdDuration::from_secs(job_sweep_events_secs),
+            job_flush_api_usage: StdDuration::from_secs(job_flush_api_usage_secs),
+            job_consistency_check: StdDuration::from_secs(job_consistency_check_secs),
+            job_consistency_repair,
+            job_flush_blob_spool: StdDuration::from_secs(job_flush_blob_spool_secs),
+            job_scheduled_tasks: StdDuration::from_secs(job_scheduled_tasks_secs),
+            job_collect_blob_garbage: StdDuration::from_secs(
+                job_collect_blob_garbage_secs,
+            ),
+            job_prune_uploads: StdDuration::from_secs(job_prune_uploads_secs),
+            job_file_retention: StdDuration::from_secs(job_file_retention_secs),
             render_timeout: StdDuration::from_millis(render_timeout_ms),
+            render_error_threshold,
+            render_error_notify_watchers,
+            render_error_notify_site,
+            render_shed_threshold,
+            render_shed_retry_after: StdDuration::from_secs(render_shed_retry_after_secs),
+            render_settings_cache_ttl: StdDuration::from_secs(
+                render_settings_cache_ttl_secs,
+            ),
             rerender_skip: rerender_skip
                 .iter()
                 .map(
@@ -401,6 +1055,8 @@ impl ConfigFile {
             special_page_missing,
             special_page_private,
             special_page_banned,
+            special_page_join,
+            special_page_template_category,
             default_name_changes: i16::from(default_name_changes),
             maximum_name_changes: i16::from(maximum_name_changes),
             refill_name_change: StdDuration::from_secs(
@@ -410,6 +1066,82 @@ impl ConfigFile {
             maximum_message_subject_bytes,
             maximum_message_body_bytes,
             maximum_message_recipients,
+            shortlink_code_length,
+            qr_code_default_size,
+            qr_code_maximum_size,
+            qr_code_cache_ttl: StdDuration::from_secs(qr_code_cache_ttl_secs),
+            page_blame_cache_ttl: StdDuration::from_secs(page_blame_cache_ttl_secs),
+            thumbnail_sizes,
+            thumbnail_maximum_source_pixels,
+            file_upload_url_expiry: StdDuration::from_secs(u64::from(
+                file_upload_url_expiry_secs,
+            )),
+            file_upload_chunked_expiry: StdDuration::from_secs(
+                file_upload_chunked_expiry_secs,
+            ),
+            file_upload_maximum_pending,
+            file_staging_expiry: StdDuration::from_secs(file_staging_expiry_secs),
+            file_staging_maximum_files,
+            file_staging_maximum_bytes,
+            file_download_url_expiry: StdDuration::from_secs(u64::from(
+                file_download_url_expiry_secs,
+            )),
+            file_scanner,
+            file_scan_clamd_address,
+            file_scan_timeout: StdDuration::from_secs(file_scan_timeout_secs),
+            maximum_export_pages,
+            export_license,
+            export_pdf_renderer_url,
+            export_pdf_renderer_timeout: StdDuration::from_secs(
+                export_pdf_renderer_timeout_secs,
+            ),
+            export_pdf_allowed_origins,
+            maximum_daily_pdf_exports,
+            git_mirror_directory,
+            git_mirror_batch_size,
+            public_api_browser_max_age: StdDuration::from_secs(
+                public_api_browser_max_age_secs,
+            ),
+            public_api_edge_max_age: StdDuration::from_secs(public_api_edge_max_age_secs),
+            public_api_stale: StdDuration::from_secs(public_api_stale_secs),
+            public_api_purge_url,
+            public_api_listing_limit,
+            cdn_max_age: StdDuration::from_secs(cdn_max_age_secs),
+            cdn_signed_url_expiry: StdDuration::from_secs(cdn_signed_url_expiry_secs),
+            event_stream_enabled,
+            event_stream_key,
+            event_stream_max_length,
+            autocomplete_maximum_results,
+            autocomplete_rate_limit_requests,
+            autocomplete_rate_limit_window: StdDuration::from_secs(
+                autocomplete_rate_limit_window_secs,
+            ),
+            api_usage_enabled,
+            api_usage_retention: TimeDuration::days(api_usage_retention_days.into()),
+            api_usage_maximum_report_days,
+            blob_spool_enabled,
+            blob_spool_maximum_blob_bytes,
+            blob_spool_maximum_total_bytes,
+            blob_compression_enabled,
+            blob_compression_level,
+            blob_compression_minimum_bytes,
+            blob_collection_dry_run,
+            blob_collection_minimum_age: TimeDuration::hours(
+                blob_collection_minimum_age_hours.into(),
+            ),
+            blob_migration_batch_size,
+            blob_migration_batch_bytes,
+            blob_migration_batch_delay: StdDuration::from_secs(
+                blob_migration_batch_delay_secs,
+            ),
+            scheduled_task_maximum_per_site,
+            scheduled_task_maximum_pages,
+            maximum_request_bytes,
+            maximum_wikitext_bytes,
+            maximum_comment_bytes,
+            maximum_tags,
+            maximum_upload_bytes,
+            maximum_storage_bytes,
         }
     }
 }
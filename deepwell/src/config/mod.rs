@@ -25,7 +25,7 @@ mod secrets;
 mod special_action;
 
 pub use self::object::Config;
-pub use self::secrets::Secrets;
+pub use self::secrets::{BlobMigrationTarget, Secrets};
 
 use self::args::parse_args;
 use self::special_action::run_special_action;
@@ -19,8 +19,11 @@
  */
 
 use super::file::ConfigFile;
+use crate::services::file_scan::FileScanner;
+use crate::utils::RedactionPolicy;
+use crate::web::RenderSeverity;
 use anyhow::Result;
-use femme::LevelFilter;
+use log::LevelFilter;
 use std::env;
 use std::net::SocketAddr;
 use std::num::NonZeroU16;
@@ -47,12 +50,22 @@ pub struct Config {
     /// What log level to use during execution.
     pub logger_level: LevelFilter,
 
+    /// What personal information to remove from log output and audit entries.
+    pub redaction_policy: RedactionPolicy,
+
+    /// Whether IP addresses stored alongside sessions are truncated
+    /// using the same prefix lengths as the redaction policy.
+    pub truncate_stored_ips: bool,
+
     /// The address the server will be hosted on.
     pub address: SocketAddr,
 
     /// The PID file (if any) to write to on boot.
     pub pid_file: Option<PathBuf>,
 
+    /// The longest time a single API call may run for.
+    pub request_deadline: StdDuration,
+
     /// The main domain to serve sites from.
     ///
     /// Always starts with a `.`
@@ -98,12 +111,21 @@ pub struct Config {
     /// Length of randomly-generated segment in session tokens.
     pub session_token_length: usize,
 
-    /// How long normal sessions last before expiry.
+    /// How long normal sessions last before expiry, without activity.
     pub normal_session_duration: TimeDuration,
 
     /// How long restricted sessions last before expiry.
     pub restricted_session_duration: TimeDuration,
 
+    /// The longest a session can be extended to, counting from login.
+    pub maximum_session_duration: TimeDuration,
+
+    /// How often session tokens are replaced with new ones.
+    pub session_rotation_interval: TimeDuration,
+
+    /// How long a rotated session token continues to work.
+    pub session_rotation_grace: TimeDuration,
+
     /// The number of recovery codes to have per user.
     pub recovery_code_count: usize,
 
@@ -116,6 +138,50 @@ pub struct Config {
     /// How much leniency should be allowed for TOTP.
     pub totp_time_skew: i64,
 
+    /// User IDs of instance administrators who may impersonate other users.
+    ///
+    /// If empty, then impersonation is disabled.
+    pub impersonation_admins: Vec<i64>,
+
+    /// How long impersonation sessions last before expiry.
+    ///
+    /// These cannot be renewed, so this is the maximum length of time
+    /// an administrator may act as another user without starting over.
+    pub impersonation_session_duration: TimeDuration,
+
+    /// How long emailed login links are valid for.
+    pub magic_link_duration: TimeDuration,
+
+    /// How many login links a user may request in each rate limit window.
+    pub magic_link_rate_limit_requests: u64,
+
+    /// The length of the login link rate limit window.
+    pub magic_link_rate_limit_window: StdDuration,
+
+    /// How long each refresh token is valid for.
+    pub refresh_token_duration: TimeDuration,
+
+    /// How many recovery contacts must confirm an account recovery.
+    pub recovery_required_confirmations: u16,
+
+    /// How long after an account recovery starts before it can be completed.
+    pub recovery_delay: TimeDuration,
+
+    /// How long after an account recovery is ready before it lapses.
+    pub recovery_expiry: TimeDuration,
+
+    /// How long an OAuth authorization code can be exchanged for.
+    pub oauth_code_duration: TimeDuration,
+
+    /// How long each OAuth access token is valid for.
+    pub oauth_access_token_duration: TimeDuration,
+
+    /// How long each OAuth refresh token is valid for.
+    pub oauth_refresh_token_duration: TimeDuration,
+
+    /// Whether internal methods can only be called by service accounts.
+    pub service_account_required: bool,
+
     /// The number of job workers to run in this process.
     pub job_workers: NonZeroU16,
 
@@ -145,9 +211,59 @@ pub struct Config {
     /// How often to run the "lift expired punishments" recurring job.
     pub job_lift_expired_punishments: StdDuration,
 
+    /// How often to run the "sweep event outbox" recurring job.
+    pub job_sweep_events: StdDuration,
+
+    /// How often to run the "flush API usage counters" recurring job.
+    pub job_flush_api_usage: StdDuration,
+
+    /// How often to run the "check database consistency" recurring job.
+    pub job_consistency_check: StdDuration,
+
+    /// Whether the recurring consistency check repairs the issues it safely can.
+    pub job_consistency_repair: bool,
+
+    /// How often to run the "flush blob spool" recurring job.
+    pub job_flush_blob_spool: StdDuration,
+
+    /// How often to run the "run scheduled tasks" recurring job.
+    pub job_scheduled_tasks: StdDuration,
+
+    /// How often to run the "collect blob garbage" recurring job.
+    pub job_collect_blob_garbage: StdDuration,
+
+    /// How often to run the "prune expired uploads" recurring job.
+    pub job_prune_uploads: StdDuration,
+
+    /// How often to run the "apply file revision retention" recurring job.
+    pub job_file_retention: StdDuration,
+
     /// Maximum run time for a render request.
     pub render_timeout: StdDuration,
 
+    /// The least severe render diagnostic which is recorded against a page.
+    pub render_error_threshold: RenderSeverity,
+
+    /// Whether to message page watchers when a page starts having render errors.
+    pub render_error_notify_watchers: bool,
+
+    /// Whether to message the site user when a page starts having render errors.
+    pub render_error_notify_site: bool,
+
+    /// How many renders may run at once before low-priority ones are turned away.
+    ///
+    /// Interactive requests are always rendered, regardless of load.
+    pub render_shed_threshold: usize,
+
+    /// How long callers turned away by the render limit are told to wait.
+    pub render_shed_retry_after: StdDuration,
+
+    /// How long a site's render settings are cached for.
+    ///
+    /// The cache is cleared when the site's settings change, so this
+    /// only bounds how stale they can be if that is missed.
+    pub render_settings_cache_ttl: StdDuration,
+
     /// In what circumstances a page rerender should be skipped.
     ///
     /// A list of rerender job depths and durations. If any item in this
@@ -177,6 +293,12 @@ pub struct Config {
     /// Page slug for when the user is banned, and the site disallows banned viewing. Default: `_ban`
     pub special_page_banned: String,
 
+    /// Page slug for the page shown to users applying to join a site. Default: `_join`
+    pub special_page_join: String,
+
+    /// Category of the templates which pages can be instantiated from. Default: `template`
+    pub special_page_template_category: String,
+
     /// Default name changes per user.
     pub default_name_changes: i16,
 
@@ -197,6 +319,216 @@ pub struct Config {
 
     /// Maximum number of total recipients allowed in a direct message.
     pub maximum_message_recipients: usize,
+
+    /// Length of generated shortlink codes.
+    pub shortlink_code_length: usize,
+
+    /// Width and height of generated QR codes, in pixels, if not specified.
+    pub qr_code_default_size: u32,
+
+    /// Largest width and height of QR codes which may be requested, in pixels.
+    pub qr_code_maximum_size: u32,
+
+    /// How long generated QR codes are cached in Redis.
+    pub qr_code_cache_ttl: StdDuration,
+
+    /// How long computed page blame annotations are cached in Redis.
+    ///
+    /// Only the annotation for the newest revision seen is kept, and
+    /// later requests pick up from it, so this mostly affects pages
+    /// which have not been looked at in a while.
+    pub page_blame_cache_ttl: StdDuration,
+
+    /// Widths and heights of thumbnails generated for image files, in pixels.
+    ///
+    /// Thumbnails preserve the aspect ratio, so this is the bounding box.
+    /// Sorted in ascending order. If empty, thumbnails are not generated.
+    pub thumbnail_sizes: Vec<u32>,
+
+    /// Largest image, by total number of pixels, which thumbnails are generated for.
+    ///
+    /// Decoding is done in memory, so this bounds how much a single upload can use.
+    pub thumbnail_maximum_source_pixels: u64,
+
+    /// How long presigned upload URLs are valid for.
+    ///
+    /// Uploads which haven't been finalized by then are discarded.
+    pub file_upload_url_expiry: StdDuration,
+
+    /// How long a chunked upload is kept without receiving a chunk.
+    ///
+    /// Incomplete uploads which haven't received a chunk in this
+    /// time are considered abandoned, and are discarded.
+    pub file_upload_chunked_expiry: StdDuration,
+
+    /// Maximum number of unfinished direct uploads each user can have at once.
+    pub file_upload_maximum_pending: u64,
+
+    /// How long a staged file is kept before it is discarded, if it isn't attached to a page.
+    pub file_staging_expiry: StdDuration,
+
+    /// Maximum number of files each user can have staged at once.
+    pub file_staging_maximum_files: u64,
+
+    /// Maximum total size of the files each user can have staged at once, in bytes.
+    pub file_staging_maximum_bytes: u64,
+
+    /// How long presigned file download URLs are valid for.
+    pub file_download_url_expiry: StdDuration,
+
+    /// Which scanner uploaded files are checked with for malware.
+    pub file_scanner: FileScanner,
+
+    /// Address of the ClamAV daemon, if that is the scanner.
+    pub file_scan_clamd_address: String,
+
+    /// How long to wait for a file to be scanned before failing the upload.
+    pub file_scan_timeout: StdDuration,
+
+    /// Maximum number of pages which can be bundled into one export.
+    pub maximum_export_pages: usize,
+
+    /// Name of the license content is released under, for export back matter.
+    pub export_license: String,
+
+    /// URL of the headless browser service which renders PDF exports.
+    ///
+    /// If `None`, then PDF exports are disabled.
+    pub export_pdf_renderer_url: Option<String>,
+
+    /// How long to wait for the PDF renderer before giving up.
+    pub export_pdf_renderer_timeout: StdDuration,
+
+    /// Origins the PDF renderer may load resources from.
+    ///
+    /// Anything not embedded in the document or from these is blocked.
+    pub export_pdf_allowed_origins: Vec<String>,
+
+    /// Maximum number of PDF exports each site can request per day.
+    pub maximum_daily_pdf_exports: u64,
+
+    /// Directory where Git mirrors of sites are stored.
+    ///
+    /// If `None`, then Git mirrors are disabled.
+    pub git_mirror_directory: Option<PathBuf>,
+
+    /// Maximum number of revisions to commit in one sync job.
+    pub git_mirror_batch_size: u64,
+
+    /// How long browsers may cache responses from the public API.
+    pub public_api_browser_max_age: StdDuration,
+
+    /// How long shared caches, such as a CDN, may cache responses from the public API.
+    pub public_api_edge_max_age: StdDuration,
+
+    /// How long shared caches may serve stale public API responses while
+    /// revalidating them, or if DEEPWELL is unavailable.
+    pub public_api_stale: StdDuration,
+
+    /// URL to send edge cache purge requests to.
+    ///
+    /// If `None`, then no purge requests are made.
+    pub public_api_purge_url: Option<String>,
+
+    /// Maximum number of pages returned in one public API listing.
+    pub public_api_listing_limit: u64,
+
+    /// How long public files served under CDN paths may be cached for.
+    ///
+    /// These paths are addressed by hash, so their contents never change.
+    pub cdn_max_age: StdDuration,
+
+    /// How long signed CDN paths for files which aren't public stay valid.
+    ///
+    /// Expiry times are rounded, so a path is valid for between one
+    /// and two of these periods, and can be shared by requests in that time.
+    pub cdn_signed_url_expiry: StdDuration,
+
+    /// Whether domain events are published to a Redis stream.
+    pub event_stream_enabled: bool,
+
+    /// The Redis key of the stream which events are published to.
+    pub event_stream_key: String,
+
+    /// Approximate number of events to retain in the stream.
+    pub event_stream_max_length: u64,
+
+    /// Maximum number of results returned by an autocomplete request.
+    pub autocomplete_maximum_results: u64,
+
+    /// How many autocomplete requests a user may make in each rate limit window.
+    pub autocomplete_rate_limit_requests: u64,
+
+    /// The length of the autocomplete rate limit window.
+    pub autocomplete_rate_limit_window: StdDuration,
+
+    /// Whether API calls are counted for per-site usage reports.
+    pub api_usage_enabled: bool,
+
+    /// How long daily API usage totals are kept.
+    pub api_usage_retention: TimeDuration,
+
+    /// The most days which can be covered by one API usage report.
+    pub api_usage_maximum_report_days: u16,
+
+    /// Whether uploads are spooled in the database while S3 is unavailable.
+    pub blob_spool_enabled: bool,
+
+    /// The largest upload which can be spooled, in bytes.
+    pub blob_spool_maximum_blob_bytes: u64,
+
+    /// The most data which can be spooled at once, in bytes.
+    pub blob_spool_maximum_total_bytes: u64,
+
+    /// Whether new blobs of compressible types are stored compressed.
+    pub blob_compression_enabled: bool,
+
+    /// The zstd compression level to use for stored blobs.
+    pub blob_compression_level: i32,
+
+    /// The smallest blob which is compressed, in bytes.
+    pub blob_compression_minimum_bytes: u64,
+
+    /// Whether blob garbage collection only reports what it would delete.
+    pub blob_collection_dry_run: bool,
+
+    /// How old an unreferenced blob must be before garbage collection deletes it.
+    pub blob_collection_minimum_age: TimeDuration,
+
+    /// The most objects a blob migration copies in each batch.
+    pub blob_migration_batch_size: usize,
+
+    /// How many bytes a blob migration copies before ending a batch early.
+    pub blob_migration_batch_bytes: u64,
+
+    /// How long a blob migration waits between batches.
+    pub blob_migration_batch_delay: StdDuration,
+
+    /// The most scheduled tasks a site can have.
+    pub scheduled_task_maximum_per_site: u64,
+
+    /// The most pages a single scheduled task run may queue jobs for.
+    pub scheduled_task_maximum_pages: usize,
+
+    /// Largest request body which the server will accept, in bytes.
+    pub maximum_request_bytes: u32,
+
+    /// Default maximum size of page wikitext, in bytes.
+    ///
+    /// Like the other limits below, sites may lower this but not raise it.
+    pub maximum_wikitext_bytes: u64,
+
+    /// Default maximum size of revision comments, in bytes.
+    pub maximum_comment_bytes: u64,
+
+    /// Default maximum number of tags on a page.
+    pub maximum_tags: u64,
+
+    /// Default maximum size of uploaded files, in bytes.
+    pub maximum_upload_bytes: u64,
+
+    /// Default maximum total size of a site's stored files, in bytes.
+    pub maximum_storage_bytes: u64,
 }
 
 impl Config {
@@ -225,6 +557,13 @@ impl Config {
         );
         info!("Migrations: {}", bool_str(self.run_migrations));
         info!("Seeder: {}", bool_str(self.run_seeder));
+        info!(
+            "Log redaction: emails {}, session tokens {}, IP prefix /{} and /{}",
+            bool_str(self.redaction_policy.redacts_emails()),
+            bool_str(self.redaction_policy.redacts_session_tokens()),
+            self.redaction_policy.ip_prefix_bits().0,
+            self.redaction_policy.ip_prefix_bits().1,
+        );
         info!("Localization path: {}", self.localization_path.display());
         info!("Seeder path: {}", self.seeder_path.display());
         info!(
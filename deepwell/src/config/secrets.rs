@@ -21,6 +21,7 @@
 use dotenvy::dotenv;
 use ref_map::*;
 use s3::{creds::Credentials, region::Region};
+use std::path::PathBuf;
 use std::{env, process};
 
 #[derive(Debug, Clone)]
@@ -59,6 +60,31 @@ pub struct Secrets {
     /// Alternatively you can have it read from the AWS credentials file.
     /// The profile to read from can be set in the `AWS_PROFILE_NAME` environment variable.
     pub s3_credentials: Credentials,
+
+    /// The key used to sign CDN URLs for files which aren't public.
+    ///
+    /// Set using environment variable `CDN_SIGNING_KEY`.
+    pub cdn_signing_key: String,
+
+    /// Where a blob migration copies the S3 bucket's contents to, if anywhere.
+    ///
+    /// Set using environment variable `BLOB_MIGRATION_TARGET`, which is optional.
+    /// See `BlobMigrationTarget` for the accepted values.
+    pub blob_migration_target: Option<BlobMigrationTarget>,
+}
+
+/// A storage backend which blobs can be migrated to.
+#[derive(Debug, Clone)]
+pub enum BlobMigrationTarget {
+    /// Another S3 bucket, with the same region and credentials as the main one.
+    ///
+    /// Given as `s3://<bucket>`.
+    S3 { bucket: String },
+
+    /// A directory on the local filesystem, which must already exist.
+    ///
+    /// Given as `file://<path>`.
+    Filesystem { path: PathBuf },
 }
 
 impl Secrets {
@@ -142,6 +168,32 @@ impl Secrets {
             }
         };
 
+        let cdn_signing_key = get_env!("CDN_SIGNING_KEY");
+        if cdn_signing_key.len() < 32 {
+            eprintln!("CDN_SIGNING_KEY variable must be at least 32 characters");
+            process::exit(1);
+        }
+
+        let blob_migration_target = match env::var("BLOB_MIGRATION_TARGET") {
+            Err(_) => None,
+            Ok(value) => {
+                if let Some(bucket) = value.strip_prefix("s3://") {
+                    Some(BlobMigrationTarget::S3 {
+                        bucket: str!(bucket),
+                    })
+                } else if let Some(path) = value.strip_prefix("file://") {
+                    Some(BlobMigrationTarget::Filesystem {
+                        path: PathBuf::from(path),
+                    })
+                } else {
+                    eprintln!(
+                        "BLOB_MIGRATION_TARGET variable must start with s3:// or file://"
+                    );
+                    process::exit(1);
+                }
+            }
+        };
+
         // Build and return
         Secrets {
             database_url,
@@ -150,6 +202,8 @@ impl Secrets {
             s3_region,
             s3_path_style,
             s3_credentials,
+            cdn_signing_key,
+            blob_migration_target,
         }
     }
 }
@@ -187,6 +187,7 @@ pub async fn seed(state: &ServerState) -> Result<()> {
                     revision_comments: str!(""),
                     user_id: SYSTEM_USER_ID,
                     bypass_filter: true,
+                    form_data: None,
                 },
             )
             .await?;
@@ -0,0 +1,157 @@
+/*
+ * endpoints/account_recovery.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::recovery_contact::Model as RecoveryContactModel;
+use crate::services::account_recovery::{
+    AccountRecoveryStatus, AddRecoveryContact, CompleteAccountRecovery,
+    ConfirmAccountRecovery, ContactVerificationOutput, GetRecoveryContacts,
+    RemoveRecoveryContact, RequestContactVerification, StartAccountRecovery,
+    StartAccountRecoveryOutput,
+};
+use crate::services::Error;
+
+pub async fn recovery_contact_add(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<RecoveryContactModel> {
+    let input: AddRecoveryContact = params.parse()?;
+    check_session(ctx, &input.session_token, input.user_id).await?;
+    AccountRecoveryService::add_contact(ctx, input).await
+}
+
+pub async fn recovery_contact_remove(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let RemoveRecoveryContact {
+        user_id,
+        session_token,
+        contact_id,
+    } = params.parse()?;
+
+    check_session(ctx, &session_token, user_id).await?;
+    AccountRecoveryService::remove_contact(ctx, user_id, contact_id).await
+}
+
+pub async fn recovery_contact_get_all(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<RecoveryContactModel>> {
+    let GetRecoveryContacts {
+        user_id,
+        session_token,
+    } = params.parse()?;
+
+    check_session(ctx, &session_token, user_id).await?;
+    AccountRecoveryService::get_contacts(ctx, user_id).await
+}
+
+pub async fn recovery_contact_token(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Option<ContactVerificationOutput>> {
+    let input: RequestContactVerification = params.parse()?;
+    AccountRecoveryService::request_verification(ctx, input).await
+}
+
+pub async fn recovery_contact_verify(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let token: String = params.one()?;
+    AccountRecoveryService::verify_contact(ctx, &token).await
+}
+
+pub async fn recovery_start(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Option<StartAccountRecoveryOutput>> {
+    let input: StartAccountRecovery = params.parse()?;
+    AccountRecoveryService::start(ctx, input).await
+}
+
+pub async fn recovery_confirm_email(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let token: String = params.one()?;
+    AccountRecoveryService::confirm_email(ctx, &token).await
+}
+
+pub async fn recovery_confirm_contact(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let input: ConfirmAccountRecovery = params.parse()?;
+    check_session(ctx, &input.session_token, input.user_id).await?;
+    AccountRecoveryService::confirm_contact(ctx, input).await
+}
+
+pub async fn recovery_cancel(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let cancel_token: String = params.one()?;
+    AccountRecoveryService::cancel(ctx, &cancel_token).await
+}
+
+pub async fn recovery_status(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<AccountRecoveryStatus> {
+    let recovery_token: String = params.one()?;
+    AccountRecoveryService::status(ctx, &recovery_token).await
+}
+
+pub async fn recovery_complete(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let input: CompleteAccountRecovery = params.parse()?;
+    AccountRecoveryService::complete(ctx, input).await
+}
+
+/// Ensures the session belongs to the given user, and is not an impersonation.
+///
+/// Recovery contacts can take over an account, so administrators
+/// should not be able to change them while signed in as someone else.
+async fn check_session(
+    ctx: &ServiceContext<'_>,
+    session_token: &str,
+    user_id: i64,
+) -> Result<()> {
+    let session = SessionService::get(ctx, session_token).await?;
+    ImpersonationService::check(&session, "user.recovery")?;
+
+    if session.user_id != user_id {
+        error!(
+            "Passed user ID ({}) does not match session token ({})",
+            user_id, session.user_id,
+        );
+
+        return Err(Error::SessionUserId {
+            active_user_id: user_id,
+            session_user_id: session.user_id,
+        });
+    }
+
+    Ok(())
+}
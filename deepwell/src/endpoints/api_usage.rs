@@ -0,0 +1,48 @@
+/*
+ * endpoints/api_usage.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::services::api_usage::{
+    ApiThrottle, ApiUsageReport, GetApiUsage, RevokeApiAccess, SetApiThrottle,
+};
+
+pub async fn api_usage_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<ApiUsageReport> {
+    let input: GetApiUsage = params.parse()?;
+    ApiUsageService::get_report(ctx, input).await
+}
+
+pub async fn api_throttle_set(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Option<ApiThrottle>> {
+    let input: SetApiThrottle = params.parse()?;
+    ApiUsageService::set_throttle(ctx, input).await
+}
+
+pub async fn api_access_revoke(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let input: RevokeApiAccess = params.parse()?;
+    ApiUsageService::revoke(ctx, input).await
+}
@@ -21,12 +21,18 @@
 use super::prelude::*;
 use crate::models::session::Model as SessionModel;
 use crate::services::authentication::{
-    AuthenticateUserOutput, AuthenticationService, LoginUser, LoginUserMfa,
-    LoginUserOutput, MultiFactorAuthenticateUser,
+    AuthenticateUserOutput, AuthenticationService, LoginUser, LoginUserMagicLink,
+    LoginUserMfa, LoginUserOutput, MagicLinkAuthenticateUser, MagicLinkOutput,
+    MultiFactorAuthenticateUser, RequestMagicLink,
 };
+use crate::services::impersonation::StartImpersonation;
 use crate::services::mfa::{
     MultiFactorConfigure, MultiFactorResetOutput, MultiFactorSetupOutput,
 };
+use crate::services::refresh_token::{
+    CreateRefreshToken, CreateRefreshTokenOutput, ExchangeRefreshToken,
+    ExchangeRefreshTokenOutput,
+};
 use crate::services::session::{
     CreateSession, GetOtherSessions, GetOtherSessionsOutput, InvalidateOtherSessions,
     RenewSession,
@@ -89,6 +95,76 @@ pub async fn auth_login(
             ip_address,
             user_agent,
             restricted: !login_complete,
+            impersonator_id: None,
+        },
+    )
+    .await?;
+
+    Ok(LoginUserOutput {
+        session_token,
+        needs_mfa,
+    })
+}
+
+/// Issues a login link for the user with the given email, if they have opted in.
+///
+/// The caller is responsible for emailing the link and storing the device token
+/// in the requesting browser. It must respond identically whether or not a link
+/// was issued.
+pub async fn auth_magic_link_request(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Option<MagicLinkOutput>> {
+    let input: RequestMagicLink = params.parse()?;
+    AuthenticationService::request_magic_link(ctx, input).await
+}
+
+pub async fn auth_magic_link_login(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<LoginUserOutput> {
+    let LoginUserMagicLink {
+        token,
+        device_token,
+        ip_address,
+        user_agent,
+    } = params.parse()?;
+
+    let result = AuthenticationService::auth_magic_link(
+        ctx,
+        MagicLinkAuthenticateUser {
+            token: &token,
+            device_token: &device_token,
+        },
+    )
+    .await;
+
+    // Same as password login, only expose generic authentication failures
+    let AuthenticateUserOutput { needs_mfa, user_id } = match result {
+        Ok(output) => output,
+        Err(mut error) => {
+            if !matches!(error, Error::InvalidAuthentication) {
+                error!("Unexpected error during magic link authentication: {error}");
+                error = Error::AuthenticationBackend(Box::new(error));
+            }
+
+            return Err(error);
+        }
+    };
+
+    info!(
+        "Magic link authentication for user ID {user_id} succeeded (login complete: {})",
+        !needs_mfa,
+    );
+
+    let session_token = SessionService::create(
+        ctx,
+        CreateSession {
+            user_id,
+            ip_address,
+            user_agent,
+            restricted: needs_mfa,
+            impersonator_id: None,
         },
     )
     .await?;
@@ -119,6 +195,21 @@ pub async fn auth_session_get(
     SessionService::get_optional(ctx, &session_token).await
 }
 
+/// Refreshes a session in response to user activity.
+///
+/// If its token was rotated, then the returned session has a different
+/// token, which the caller must store and use from now on.
+pub async fn auth_session_refresh(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Option<SessionModel>> {
+    let session_token: String = params.one()?;
+    match SessionService::get_optional(ctx, &session_token).await? {
+        Some(session) => SessionService::refresh(ctx, session).await.map(Some),
+        None => Ok(None),
+    }
+}
+
 pub async fn auth_session_renew(
     ctx: &ServiceContext<'_>,
     params: Params<'static>,
@@ -170,6 +261,30 @@ pub async fn auth_session_invalidate_others(
     SessionService::invalidate_others(ctx, &session_token, user_id).await
 }
 
+pub async fn auth_refresh_token_create(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<CreateRefreshTokenOutput> {
+    let input: CreateRefreshToken = params.parse()?;
+    RefreshTokenService::create(ctx, input).await
+}
+
+pub async fn auth_refresh_token_exchange(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<ExchangeRefreshTokenOutput> {
+    let input: ExchangeRefreshToken = params.parse()?;
+    RefreshTokenService::exchange(ctx, input).await
+}
+
+pub async fn auth_refresh_token_revoke(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let refresh_token: String = params.one()?;
+    RefreshTokenService::revoke(ctx, &refresh_token).await
+}
+
 pub async fn auth_mfa_verify(
     ctx: &ServiceContext<'_>,
     params: Params<'static>,
@@ -222,6 +337,9 @@ pub async fn auth_mfa_disable(
         session_token,
     } = params.parse()?;
 
+    let session = SessionService::get(ctx, &session_token).await?;
+    ImpersonationService::check(&session, "user.mfa")?;
+
     let user = SessionService::get_user(ctx, &session_token, false).await?;
     if user.user_id != user_id {
         error!(
@@ -247,6 +365,9 @@ pub async fn auth_mfa_reset_recovery(
         session_token,
     } = params.parse()?;
 
+    let session = SessionService::get(ctx, &session_token).await?;
+    ImpersonationService::check(&session, "user.mfa")?;
+
     let user = SessionService::get_user(ctx, &session_token, false).await?;
     if user.user_id != user_id {
         error!(
@@ -262,3 +383,11 @@ pub async fn auth_mfa_reset_recovery(
 
     MfaService::reset_recovery_codes(ctx, &user).await
 }
+
+pub async fn auth_impersonation_start(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<String> {
+    let input: StartImpersonation = params.parse()?;
+    ImpersonationService::start(ctx, input).await
+}
@@ -0,0 +1,46 @@
+/*
+ * endpoints/autocomplete.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::services::autocomplete::{Autocomplete, AutocompletePage, AutocompleteUser};
+
+pub async fn autocomplete_page(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<AutocompletePage>> {
+    let input: Autocomplete = params.parse()?;
+    AutocompleteService::pages(ctx, input).await
+}
+
+pub async fn autocomplete_user(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<AutocompleteUser>> {
+    let input: Autocomplete = params.parse()?;
+    AutocompleteService::users(ctx, input).await
+}
+
+pub async fn autocomplete_tag(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<String>> {
+    let input: Autocomplete = params.parse()?;
+    AutocompleteService::tags(ctx, input).await
+}
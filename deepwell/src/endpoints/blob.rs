@@ -0,0 +1,68 @@
+/*
+ * endpoints/blob.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::blob_collection::Model as BlobCollectionModel;
+use crate::models::blob_migration::Model as BlobMigrationModel;
+use crate::services::blob::{RunBlobCollection, StartBlobMigration};
+
+pub async fn blob_collection_run(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<BlobCollectionModel> {
+    let RunBlobCollection {
+        acting_user_id,
+        dry_run,
+    } = params.parse()?;
+
+    info!(
+        "Collecting unreferenced blobs for user ID {acting_user_id} (dry run: {dry_run})"
+    );
+    BlobService::collect_garbage(ctx, Some(acting_user_id), dry_run).await
+}
+
+pub async fn blob_collection_get_all(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<BlobCollectionModel>> {
+    let limit: u64 = params.one()?;
+    BlobService::get_collections(ctx, limit).await
+}
+
+pub async fn blob_migration_start(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<BlobMigrationModel> {
+    let input: StartBlobMigration = params.parse()?;
+    info!(
+        "Starting blob migration for user ID {}",
+        input.acting_user_id,
+    );
+    BlobService::start_migration(ctx, input).await
+}
+
+pub async fn blob_migration_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Option<BlobMigrationModel>> {
+    let migration_id: i64 = params.one()?;
+    info!("Getting blob migration ID {migration_id}");
+    BlobService::get_migration_optional(ctx, migration_id).await
+}
@@ -20,7 +20,9 @@
 
 use super::prelude::*;
 use crate::models::page_category::Model as PageCategoryModel;
-use crate::services::category::GetCategory;
+use crate::services::category::{
+    EditCategoryForm, EditCategoryIndexing, EditCategoryRender, GetCategory,
+};
 use crate::services::site::GetSite;
 
 pub async fn category_get(
@@ -42,3 +44,50 @@ pub async fn category_get_all(
     info!("Getting all page categories in site ID {site_id}");
     CategoryService::get_all(ctx, site_id).await
 }
+
+pub async fn category_indexing_edit(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<PageCategoryModel> {
+    let EditCategoryIndexing {
+        site,
+        category,
+        body,
+    } = params.parse()?;
+
+    let site_id = SiteService::get_id(ctx, site).await?;
+    info!(
+        "Editing indexing settings for page category {category:?} in site ID {site_id}"
+    );
+    CategoryService::edit_indexing(ctx, site_id, category, body).await
+}
+
+pub async fn category_render_edit(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<PageCategoryModel> {
+    let EditCategoryRender {
+        site,
+        category,
+        body,
+    } = params.parse()?;
+
+    let site_id = SiteService::get_id(ctx, site).await?;
+    info!("Editing render settings for page category {category:?} in site ID {site_id}");
+    CategoryService::edit_render(ctx, site_id, category, body).await
+}
+
+pub async fn category_form_edit(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<PageCategoryModel> {
+    let EditCategoryForm {
+        site,
+        category,
+        body,
+    } = params.parse()?;
+
+    let site_id = SiteService::get_id(ctx, site).await?;
+    info!("Editing data form for page category {category:?} in site ID {site_id}");
+    CategoryService::edit_form(ctx, site_id, category, body).await
+}
@@ -0,0 +1,44 @@
+/*
+ * endpoints/consistency.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::consistency_report::Model as ConsistencyReportModel;
+use crate::services::consistency::RunConsistencyCheck;
+
+pub async fn consistency_check_run(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<ConsistencyReportModel> {
+    let RunConsistencyCheck {
+        acting_user_id,
+        repair,
+    } = params.parse()?;
+
+    info!("Running consistency checks for user ID {acting_user_id} (repair: {repair})");
+    ConsistencyService::run(ctx, Some(acting_user_id), repair).await
+}
+
+pub async fn consistency_report_get_all(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<ConsistencyReportModel>> {
+    let limit: u64 = params.one()?;
+    ConsistencyService::get_reports(ctx, limit).await
+}
@@ -0,0 +1,55 @@
+/*
+ * endpoints/event_stream.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::services::event_stream::{
+    CreateEventStreamGroup, EventStreamInfo, ReplayEventStreamGroup,
+};
+
+pub async fn event_stream_get(
+    ctx: &ServiceContext<'_>,
+    _params: Params<'static>,
+) -> Result<EventStreamInfo> {
+    EventStreamService::get_info(ctx).await
+}
+
+pub async fn event_stream_group_create(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let input: CreateEventStreamGroup = params.parse()?;
+    EventStreamService::create_group(ctx, input).await
+}
+
+pub async fn event_stream_group_delete(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let name: String = params.one()?;
+    EventStreamService::delete_group(ctx, &name).await
+}
+
+pub async fn event_stream_group_replay(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let input: ReplayEventStreamGroup = params.parse()?;
+    EventStreamService::replay_group(ctx, input).await
+}
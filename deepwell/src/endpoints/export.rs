@@ -0,0 +1,60 @@
+/*
+ * endpoints/export.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::services::export::{
+    CreateExport, DownloadExportOutput, ExportOutput, GetExport,
+};
+
+pub async fn export_create(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<ExportOutput> {
+    let input: CreateExport = params.parse()?;
+    info!(
+        "Requesting {:?} export for site ID {} by user ID {}",
+        input.export_format, input.site_id, input.user_id,
+    );
+    ExportService::create(ctx, input).await
+}
+
+pub async fn export_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<ExportOutput> {
+    let input: GetExport = params.parse()?;
+    info!(
+        "Getting export ID {} for user ID {}",
+        input.export_id, input.user_id,
+    );
+    ExportService::get_output(ctx, input).await
+}
+
+pub async fn export_download(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<DownloadExportOutput> {
+    let input: GetExport = params.parse()?;
+    info!(
+        "Downloading export ID {} for user ID {}",
+        input.export_id, input.user_id,
+    );
+    ExportService::download(ctx, input).await
+}
@@ -22,18 +22,30 @@ use super::prelude::*;
 use crate::models::file::Model as FileModel;
 use crate::models::file_revision::Model as FileRevisionModel;
 use crate::services::file::{
-    DeleteFile, DeleteFileOutput, EditFile, EditFileOutput, GetFileDetails,
-    GetFileOutput, MoveFile, MoveFileOutput, RestoreFile, RestoreFileOutput, UploadFile,
-    UploadFileOutput,
+    AttachFile, AttachFileOutput, DeleteFile, DeleteFileOutput, EditFile, EditFileOutput,
+    FileDownloadUrl, GetFileDetails, GetFileDownloadUrl, GetFileOutput, MoveFile,
+    MoveFileOutput, PasteFile, PasteFileOutput, RestoreFile, RestoreFileOutput,
+    UploadFile, UploadFileOutput,
+};
+use crate::services::file_staging::{
+    GetStagedFiles, StageFile, StagedFile, StagedFileReference,
+};
+use crate::services::file_upload::{
+    AppendFileUploadChunk, FileUploadReference, FileUploadStatus, FinishFileUpload,
+    FinishFileUploadOutput, StartFileUpload, StartFileUploadOutput,
 };
 use crate::services::Result;
-use crate::web::{Bytes, FileDetails};
+use crate::web::{Bytes, FileDetails, Reference};
 
 pub async fn file_get(
     ctx: &ServiceContext<'_>,
     params: Params<'static>,
 ) -> Result<Option<GetFileOutput>> {
-    let GetFileDetails { input, details } = params.parse()?;
+    let GetFileDetails {
+        input,
+        details,
+        latest,
+    } = params.parse()?;
 
     info!(
         "Getting file {:?} from page ID {} in site ID {}",
@@ -44,13 +56,18 @@ pub async fn file_get(
     match FileService::get_optional(ctx, input).await? {
         None => Ok(None),
         Some(file) => {
-            let revision = FileRevisionService::get_latest(
-                ctx,
-                file.site_id,
-                file.page_id,
-                file.file_id,
-            )
-            .await?;
+            let revision = if latest {
+                FileRevisionService::get_latest(
+                    ctx,
+                    file.site_id,
+                    file.page_id,
+                    file.file_id,
+                )
+                .await?
+            } else {
+                let site = SiteService::get(ctx, Reference::Id(file.site_id)).await?;
+                StableRevisionService::get_file_revision(ctx, &site, &file).await?
+            };
 
             let output = build_file_response(ctx, file, revision, details).await?;
             Ok(Some(output))
@@ -75,6 +92,164 @@ pub async fn file_upload(
     FileService::upload(ctx, input).await
 }
 
+pub async fn file_download_url(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<FileDownloadUrl> {
+    let input: GetFileDownloadUrl = params.parse()?;
+
+    info!(
+        "Getting download URL for file ID {} on page ID {} in site ID {}",
+        input.file_id, input.page_id, input.site_id,
+    );
+
+    FileService::download_url(ctx, input).await
+}
+
+pub async fn file_paste(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<PasteFileOutput> {
+    let input: PasteFile = params.parse()?;
+
+    info!(
+        "Pasting image ({} bytes) to page ID {} in site ID {}",
+        input.data.len(),
+        input.page_id,
+        input.site_id,
+    );
+
+    FileService::paste(ctx, input).await
+}
+
+pub async fn file_upload_start(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<StartFileUploadOutput> {
+    let input: StartFileUpload = params.parse()?;
+
+    info!(
+        "Starting direct upload ({} bytes) to page ID {} in site ID {}",
+        input.size, input.page_id, input.site_id,
+    );
+
+    FileUploadService::start(ctx, input).await
+}
+
+pub async fn file_upload_start_chunked(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<FileUploadStatus> {
+    let input: StartFileUpload = params.parse()?;
+    FileUploadService::start_chunked(ctx, input).await
+}
+
+pub async fn file_upload_append(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<FileUploadStatus> {
+    let input: AppendFileUploadChunk = params.parse()?;
+
+    info!(
+        "Appending {} bytes at offset {} to upload ID {} in site ID {}",
+        input.data.len(),
+        input.offset,
+        input.upload_id,
+        input.site_id,
+    );
+
+    FileUploadService::append(ctx, input).await
+}
+
+pub async fn file_upload_status(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<FileUploadStatus> {
+    let input: FileUploadReference = params.parse()?;
+
+    info!(
+        "Getting status of upload ID {} in site ID {}",
+        input.upload_id, input.site_id,
+    );
+
+    FileUploadService::status(ctx, input).await
+}
+
+pub async fn file_upload_abort(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let input: FileUploadReference = params.parse()?;
+    FileUploadService::abort(ctx, input).await
+}
+
+pub async fn file_upload_finish(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<FinishFileUploadOutput> {
+    let input: FinishFileUpload = params.parse()?;
+
+    info!(
+        "Finishing upload ID {} in site ID {}",
+        input.upload_id, input.site_id,
+    );
+
+    FileUploadService::finish(ctx, input).await
+}
+
+pub async fn file_stage(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<StagedFile> {
+    let input: StageFile = params.parse()?;
+
+    info!(
+        "Staging file '{}' ({} bytes) for user ID {} in site ID {}",
+        input.name,
+        input.data.len(),
+        input.user_id,
+        input.site_id,
+    );
+
+    FileStagingService::stage(ctx, input).await
+}
+
+pub async fn file_staged_get_all(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<StagedFile>> {
+    let input: GetStagedFiles = params.parse()?;
+
+    info!(
+        "Getting staged files for user ID {} in site ID {}",
+        input.user_id, input.site_id,
+    );
+
+    FileStagingService::get_all(ctx, input).await
+}
+
+pub async fn file_staged_remove(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let input: StagedFileReference = params.parse()?;
+    FileStagingService::remove(ctx, input).await
+}
+
+pub async fn file_attach(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<AttachFileOutput> {
+    let input: AttachFile = params.parse()?;
+
+    info!(
+        "Attaching staged file ID {} to page ID {} in site ID {}",
+        input.staged_file_id, input.page_id, input.site_id,
+    );
+
+    FileService::attach(ctx, input).await
+}
+
 pub async fn file_edit(
     ctx: &ServiceContext<'_>,
     params: Params<'static>,
@@ -128,7 +303,7 @@ pub async fn file_move(
         input.file_id, input.current_page_id, input.destination_page_id, input.site_id,
     );
 
-    FileService::r#move(ctx, input).await
+    FileService::move_file(ctx, input).await
 }
 
 pub async fn file_hard_delete(
@@ -151,6 +326,16 @@ async fn build_file_response(
     details: FileDetails,
 ) -> Result<GetFileOutput> {
     let data = BlobService::get_maybe(ctx, details.data, &revision.s3_hash).await?;
+
+    // Only images can have derivatives, so skip looking up the site otherwise
+    let blob_hidden = revision.hidden.iter().any(|field| field == "blob");
+    let derivative = if !blob_hidden && ThumbnailService::supports(&revision.mime_hint) {
+        let site = SiteService::get(ctx, Reference::Id(file.site_id)).await?;
+        ThumbnailService::get_derivative(ctx, &site, &revision.s3_hash).await?
+    } else {
+        None
+    };
+
     Ok(GetFileOutput {
         file_id: file.file_id,
         file_created_at: file.created_at,
@@ -169,5 +354,6 @@ async fn build_file_response(
         licensing: revision.licensing,
         revision_comments: revision.comments,
         hidden_fields: revision.hidden,
+        derivative,
     })
 }
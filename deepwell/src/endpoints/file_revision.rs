@@ -20,10 +20,16 @@
 
 use super::prelude::*;
 use crate::models::file_revision::Model as FileRevisionModel;
-use crate::services::file::GetFile;
+use crate::services::file::{
+    GetFile, RestoreHiddenFileRevision, RestoreHiddenFileRevisionOutput,
+};
 use crate::services::file_revision::{
-    FileRevisionCountOutput, GetFileRevision, GetFileRevisionRange, UpdateFileRevision,
+    CreateFileRevisionOutput, FileRevisionCountOutput, FileRevisionDiff, GetFileRevision,
+    GetFileRevisionDiff, GetFileRevisionRange, PurgeFileRevisionBlob,
+    PurgeFileRevisionBlobOutput, RevertFileRevision, SummarizedFileRevision,
+    UpdateFileRevision, UpdateFileRevisions,
 };
+use crate::services::thumbnail::GetThumbnail;
 
 pub async fn file_revision_count(
     ctx: &ServiceContext<'_>,
@@ -61,12 +67,48 @@ pub async fn file_revision_get(
     FileRevisionService::get_optional(ctx, input).await
 }
 
+pub async fn file_thumbnail_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Option<Vec<u8>>> {
+    let input: GetThumbnail = params.parse()?;
+
+    info!(
+        "Getting {}px thumbnail of file revision {} for file ID {} on page ID {}",
+        input.size, input.revision_number, input.file_id, input.page_id,
+    );
+
+    ThumbnailService::get_hash(ctx, input).await
+}
+
 pub async fn file_revision_range(
     ctx: &ServiceContext<'_>,
     params: Params<'static>,
-) -> Result<Vec<FileRevisionModel>> {
+) -> Result<Vec<SummarizedFileRevision>> {
     let input: GetFileRevisionRange = params.parse()?;
-    FileRevisionService::get_range(ctx, input).await
+    let revisions = FileRevisionService::get_range(ctx, input).await?;
+    let mut summarized = Vec::new();
+
+    for revision in revisions {
+        let summary = FileRevisionService::summarize(ctx, &revision).await?;
+        summarized.push(SummarizedFileRevision { revision, summary });
+    }
+
+    Ok(summarized)
+}
+
+pub async fn file_revision_diff(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<FileRevisionDiff> {
+    let input: GetFileRevisionDiff = params.parse()?;
+
+    info!(
+        "Comparing revisions {} and {} for file ID {}",
+        input.from_revision_number, input.to_revision_number, input.file_id,
+    );
+
+    FileRevisionService::diff(ctx, input).await
 }
 
 pub async fn file_revision_edit(
@@ -82,3 +124,61 @@ pub async fn file_revision_edit(
 
     FileRevisionService::update(ctx, input).await
 }
+
+pub async fn file_revision_edit_many(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<FileRevisionModel>> {
+    let input: UpdateFileRevisions = params.parse()?;
+
+    info!(
+        "Editing {} file revisions for file ID {} on page {}",
+        input.revision_ids.len(),
+        input.file_id,
+        input.page_id,
+    );
+
+    FileRevisionService::update_many(ctx, input).await
+}
+
+pub async fn file_revision_purge_blob(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<PurgeFileRevisionBlobOutput> {
+    let input: PurgeFileRevisionBlob = params.parse()?;
+
+    info!(
+        "Purging blob of file revision ID {} for file ID {} on page {}",
+        input.revision_id, input.file_id, input.page_id,
+    );
+
+    FileRevisionService::purge_blob(ctx, input).await
+}
+
+pub async fn file_revision_restore(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<RestoreHiddenFileRevisionOutput> {
+    let input: RestoreHiddenFileRevision = params.parse()?;
+
+    info!(
+        "Restoring hidden file revision ID {} for file ID {} on page {}",
+        input.revision_id, input.file_id, input.page_id,
+    );
+
+    FileService::restore_hidden_revision(ctx, input).await
+}
+
+pub async fn file_revision_revert(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Option<CreateFileRevisionOutput>> {
+    let input: RevertFileRevision = params.parse()?;
+
+    info!(
+        "Reverting file ID {} on page ID {} to revision {}",
+        input.file_id, input.page_id, input.revision_number,
+    );
+
+    FileRevisionService::revert(ctx, input).await
+}
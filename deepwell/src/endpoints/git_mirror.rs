@@ -0,0 +1,58 @@
+/*
+ * endpoints/git_mirror.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::site_git_mirror::Model as SiteGitMirrorModel;
+use crate::services::git_mirror::{GetGitMirrorFile, GitMirrorFileOutput, SetGitMirror};
+
+pub async fn git_mirror_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Option<SiteGitMirrorModel>> {
+    let site_id: i64 = params.one()?;
+    info!("Getting Git mirror for site ID {site_id}");
+    GitMirrorService::get_optional(ctx, site_id).await
+}
+
+pub async fn git_mirror_set(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<SiteGitMirrorModel> {
+    let input: SetGitMirror = params.parse()?;
+    info!("Setting Git mirror for site ID {}", input.site_id);
+    GitMirrorService::set(ctx, input).await
+}
+
+pub async fn git_mirror_remove(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let site_id: i64 = params.one()?;
+    info!("Removing Git mirror for site ID {site_id}");
+    GitMirrorService::remove(ctx, site_id).await
+}
+
+pub async fn git_mirror_file(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<GitMirrorFileOutput> {
+    let input: GetGitMirrorFile = params.parse()?;
+    GitMirrorService::read_file(ctx, input).await
+}
@@ -0,0 +1,49 @@
+/*
+ * endpoints/limit.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::services::limit::{SetSiteLimits, SiteLimits};
+use crate::services::storage_quota::SiteStorageUsage;
+
+pub async fn site_limits_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<SiteLimits> {
+    let site_id: i64 = params.one()?;
+    info!("Getting limits for site ID {site_id}");
+    LimitService::get(ctx, site_id).await
+}
+
+pub async fn site_limits_set(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<SiteLimits> {
+    let input: SetSiteLimits = params.parse()?;
+    LimitService::set(ctx, input).await
+}
+
+pub async fn site_storage_usage(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<SiteStorageUsage> {
+    let site_id: i64 = params.one()?;
+    info!("Getting storage usage for site ID {site_id}");
+    StorageQuotaService::get_usage(ctx, site_id).await
+}
@@ -19,10 +19,12 @@
  */
 
 use super::prelude::*;
+use crate::models::link_recompute::Model as LinkRecomputeModel;
 use crate::services::link::{
-    GetLinksExternalFrom, GetLinksExternalFromOutput, GetLinksExternalTo,
-    GetLinksExternalToOutput, GetLinksFrom, GetLinksFromOutput, GetLinksTo,
-    GetLinksToMissing, GetLinksToMissingOutput, GetLinksToOutput,
+    BrokenReferencesReport, GetLinksExternalFrom, GetLinksExternalFromOutput,
+    GetLinksExternalTo, GetLinksExternalToOutput, GetLinksFrom, GetLinksFromOutput,
+    GetLinksTo, GetLinksToMissing, GetLinksToMissingOutput, GetLinksToOutput,
+    StartLinkRecompute,
 };
 
 pub async fn page_links_from_get(
@@ -86,3 +88,28 @@ pub async fn page_links_external_to(
     info!("Getting external links to URL {url} in site ID {site_id}");
     LinkService::get_external_to(ctx, site_id, &url).await
 }
+
+pub async fn site_broken_references_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<BrokenReferencesReport> {
+    let site_id: i64 = params.one()?;
+    LinkService::get_broken_references(ctx, site_id).await
+}
+
+pub async fn link_recompute_start(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<LinkRecomputeModel> {
+    let input: StartLinkRecompute = params.parse()?;
+    LinkService::start_recompute(ctx, input).await
+}
+
+pub async fn link_recompute_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Option<LinkRecomputeModel>> {
+    let recompute_id: i64 = params.one()?;
+    info!("Getting link recomputation run ID {recompute_id}");
+    LinkService::get_recompute_optional(ctx, recompute_id).await
+}
@@ -20,10 +20,18 @@
 
 use super::prelude::*;
 use crate::info;
+use crate::services::blob::BlobStorageStatus;
 use sea_orm::{ConnectionTrait, DatabaseBackend, Statement};
 use std::path::PathBuf;
 use wikidot_normalize::normalize;
 
+#[derive(Serialize, Debug, Clone)]
+pub struct HealthOutput {
+    /// Whether any dependency is unavailable, but DEEPWELL is working around it.
+    degraded: bool,
+    blob_storage: BlobStorageStatus,
+}
+
 async fn postgres_check(ctx: &ServiceContext<'_>) -> Result<()> {
     ctx.transaction()
         .execute(Statement::from_string(
@@ -55,6 +63,24 @@ pub async fn ping(
     Ok("Pong!")
 }
 
+/// Reports the status of each dependency in more detail than `ping`.
+///
+/// Postgres and Redis are required, so this fails if either is down,
+/// but S3 outages are reported rather than failing the check.
+pub async fn health(
+    ctx: &ServiceContext<'_>,
+    _params: Params<'static>,
+) -> Result<HealthOutput> {
+    info!("Health check request");
+    try_join!(postgres_check(ctx), redis_check(ctx))?;
+
+    let blob_storage = BlobService::storage_status(ctx).await?;
+    Ok(HealthOutput {
+        degraded: !blob_storage.available || blob_storage.spooled_blobs > 0,
+        blob_storage,
+    })
+}
+
 /// Method which always returns an error.
 /// For testing.
 pub async fn yield_error(
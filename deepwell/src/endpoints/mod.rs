@@ -30,34 +30,75 @@
 mod prelude {
     pub use crate::api::ServerState;
     pub use crate::services::{
-        AliasService, BlobService, CategoryService, DomainService, Error as ServiceError,
-        FileRevisionService, FileService, LinkService, MessageReportService,
-        MessageService, MfaService, PageRevisionService, PageService, ParentService,
-        RelationService, RenderService, Result, ScoreService, ServiceContext,
-        SessionService, SiteService, StdResult, TextService, UserService, ViewService,
-        VoteService,
+        AccountRecoveryService, AliasService, ApiUsageService, AuditService,
+        AutocompleteService, BlobService, CategoryService, CdnService,
+        ConsistencyService, CustomBlockService, DomainService, Error as ServiceError,
+        EventStreamService, ExportService, FileRevisionService, FileService,
+        FileStagingService, FileUploadService, GitMirrorService, ImpersonationService,
+        LimitService, LinkService, MessageService, MfaService, OauthService,
+        PageDraftService, PageFieldWatchService, PageFormService, PageRevisionService,
+        PageService, PageTemplateService, ParentService, PermissionService,
+        PublicApiService, QrCodeService, RefreshTokenService, RelationService,
+        RenderErrorService, Result, RevisionComparisonService, RevisionSearchService,
+        ScheduledTaskService, ScoreService, SearchService, ServiceAccountService,
+        ServiceContext, SessionService, ShortlinkService, SiteService,
+        SpecialPageService, StableRevisionService, StorageQuotaService, TagRuleService,
+        TextService, ThumbnailService, UploadRuleService, UserService, ViewService,
+        VoteService, WatchlistService,
     };
     pub use jsonrpsee::types::params::Params;
     pub use std::convert::TryFrom;
 }
 
+pub mod account_recovery;
+pub mod api_usage;
+pub mod audit;
 pub mod auth;
+pub mod autocomplete;
+pub mod blob;
 pub mod category;
+pub mod cdn;
+pub mod consistency;
+pub mod custom_block;
 pub mod domain;
 pub mod email;
+pub mod event_stream;
+pub mod export;
 pub mod file;
 pub mod file_revision;
+pub mod git_mirror;
+pub mod limit;
 pub mod link;
 pub mod locale;
 pub mod message;
 pub mod misc;
+pub mod oauth;
 pub mod page;
+pub mod page_draft;
+pub mod page_field_watch;
+pub mod page_form;
 pub mod page_revision;
+pub mod page_template;
 pub mod parent;
+pub mod permission;
+pub mod public_api;
+pub mod qr_code;
+pub mod render_error;
+pub mod revision_comparison;
+pub mod revision_search;
+pub mod scheduled_task;
+pub mod search;
+pub mod service_account;
+pub mod shortlink;
 pub mod site;
 pub mod site_member;
+pub mod special_page;
+pub mod stable_revision;
+pub mod tag_rule;
 pub mod text;
+pub mod upload_rule;
 pub mod user;
 pub mod user_bot;
 pub mod view;
 pub mod vote;
+pub mod watchlist;
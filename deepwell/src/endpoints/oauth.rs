@@ -0,0 +1,152 @@
+/*
+ * endpoints/oauth.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::services::oauth::{
+    AuthorizeOauthApplication, AuthorizeOauthApplicationOutput,
+    AuthorizedOauthApplication, CheckOauthToken, CreateOauthApplication,
+    CreateOauthApplicationOutput, DeleteOauthApplication, GetOauthApplications,
+    GetOauthAuthorizations, OauthApplication, OauthTokenCheck, OauthTokenOutput,
+    OauthTokenRequest, RevokeOauthAuthorization,
+};
+use crate::services::Error;
+
+pub async fn oauth_application_create(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<CreateOauthApplicationOutput> {
+    let input: CreateOauthApplication = params.parse()?;
+    check_session(ctx, &input.session_token, input.user_id).await?;
+    OauthService::create_application(ctx, input).await
+}
+
+pub async fn oauth_application_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<OauthApplication> {
+    let client_id: String = params.one()?;
+    OauthService::get_application(ctx, &client_id).await
+}
+
+pub async fn oauth_application_get_all(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<OauthApplication>> {
+    let GetOauthApplications {
+        user_id,
+        session_token,
+    } = params.parse()?;
+
+    check_session(ctx, &session_token, user_id).await?;
+    OauthService::get_applications(ctx, user_id).await
+}
+
+pub async fn oauth_application_delete(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let DeleteOauthApplication {
+        user_id,
+        session_token,
+        application_id,
+    } = params.parse()?;
+
+    check_session(ctx, &session_token, user_id).await?;
+    OauthService::delete_application(ctx, user_id, application_id).await
+}
+
+pub async fn oauth_authorize(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<AuthorizeOauthApplicationOutput> {
+    let input: AuthorizeOauthApplication = params.parse()?;
+    check_session(ctx, &input.session_token, input.user_id).await?;
+    OauthService::authorize(ctx, input).await
+}
+
+pub async fn oauth_token(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<OauthTokenOutput> {
+    let input: OauthTokenRequest = params.parse()?;
+    OauthService::token(ctx, input).await
+}
+
+pub async fn oauth_token_check(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<OauthTokenCheck> {
+    let input: CheckOauthToken = params.parse()?;
+    OauthService::check_token(ctx, input).await
+}
+
+pub async fn oauth_authorization_get_all(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<AuthorizedOauthApplication>> {
+    let GetOauthAuthorizations {
+        user_id,
+        session_token,
+    } = params.parse()?;
+
+    check_session(ctx, &session_token, user_id).await?;
+    OauthService::get_authorizations(ctx, user_id).await
+}
+
+pub async fn oauth_authorization_revoke(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let RevokeOauthAuthorization {
+        user_id,
+        session_token,
+        application_id,
+    } = params.parse()?;
+
+    check_session(ctx, &session_token, user_id).await?;
+    OauthService::revoke_authorization(ctx, user_id, application_id).await
+}
+
+/// Ensures the session belongs to the given user, and is not an impersonation.
+///
+/// Granting an application access is done by the user alone,
+/// so administrators cannot do it while signed in as them.
+async fn check_session(
+    ctx: &ServiceContext<'_>,
+    session_token: &str,
+    user_id: i64,
+) -> Result<()> {
+    let session = SessionService::get(ctx, session_token).await?;
+    ImpersonationService::check(&session, "user.oauth")?;
+
+    if session.user_id != user_id {
+        error!(
+            "Passed user ID ({}) does not match session token ({})",
+            user_id, session.user_id,
+        );
+
+        return Err(Error::SessionUserId {
+            active_user_id: user_id,
+            session_user_id: session.user_id,
+        });
+    }
+
+    Ok(())
+}
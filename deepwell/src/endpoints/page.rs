@@ -21,10 +21,13 @@
 use super::prelude::*;
 use crate::models::page::Model as PageModel;
 use crate::services::page::{
-    CreatePage, CreatePageOutput, DeletePage, DeletePageOutput, EditPage, EditPageOutput,
-    GetPageAnyDetails, GetPageDirect, GetPageOutput, GetPageReferenceDetails, MovePage,
-    MovePageOutput, RestorePage, RestorePageOutput, RollbackPage,
+    CreatePage, CreatePageOutput, DeletePage, DeletePageOutput, EditPage,
+    EditPageIndexing, EditPageOutput, GetIndexablePages, GetPageAnyDetails,
+    GetPageDirect, GetPageOutput, GetPageReference, GetPageReferenceDetails, MovePage,
+    MovePageOutput, PageIndexing, RestorePage, RestorePageOutput, RollbackPage,
 };
+use crate::services::page_revision::{PreviewPage, PrintPageOutput};
+use crate::services::render::RenderOutput;
 use crate::services::{Result, TextService};
 use crate::web::{PageDetails, Reference};
 
@@ -114,6 +117,30 @@ pub async fn page_rerender(
     PageRevisionService::rerender(ctx, site_id, page_id, 0).await
 }
 
+pub async fn page_preview(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<RenderOutput> {
+    let input: PreviewPage = params.parse()?;
+    info!(
+        "Previewing page {} in site ID {}",
+        input.slug, input.site_id,
+    );
+    PageRevisionService::preview(ctx, input).await
+}
+
+pub async fn page_print(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<PrintPageOutput> {
+    let input: GetPageReference = params.parse()?;
+    info!(
+        "Printing page {:?} in site ID {}",
+        input.page, input.site_id,
+    );
+    PageRevisionService::print(ctx, input).await
+}
+
 pub async fn page_restore(
     ctx: &ServiceContext<'_>,
     params: Params<'static>,
@@ -140,6 +167,33 @@ pub async fn page_rollback(
     PageService::rollback(ctx, input).await
 }
 
+pub async fn page_indexing_edit(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<PageIndexing> {
+    let input: EditPageIndexing = params.parse()?;
+    info!(
+        "Editing indexing settings for page {:?} in site ID {}",
+        input.page, input.site_id,
+    );
+    PageService::edit_indexing(ctx, input).await
+}
+
+pub async fn page_get_indexable(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<PageModel>> {
+    let GetIndexablePages {
+        site,
+        after_slug,
+        limit,
+    } = params.parse()?;
+
+    let site_id = SiteService::get_id(ctx, site).await?;
+    info!("Getting indexable pages in site ID {site_id} after {after_slug:?}");
+    PageService::get_indexable(ctx, site_id, after_slug.as_deref(), limit).await
+}
+
 async fn build_page_output(
     ctx: &ServiceContext<'_>,
     page: PageModel,
@@ -183,6 +237,8 @@ async fn build_page_output(
         compiled_html,
         compiled_at: revision.compiled_at,
         compiled_generator: revision.compiled_generator,
+        content_stats: revision.content_stats,
+        compiled_csp: revision.compiled_csp,
         revision_comments: revision.comments,
         hidden_fields: revision.hidden,
         title: revision.title,
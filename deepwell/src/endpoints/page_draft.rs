@@ -0,0 +1,97 @@
+/*
+ * endpoints/page_draft.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::page_draft::Model as PageDraftModel;
+use crate::services::page_draft::{
+    CreatePageDraft, GetPageDraft, GetPageDraftOutput, GetPageDrafts, PublishPageDraft,
+    PublishPageDraftOutput, SchedulePageDraft, UpdatePageDraft,
+};
+
+pub async fn page_draft_create(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<PageDraftModel> {
+    let input: CreatePageDraft = params.parse()?;
+    PageDraftService::create(ctx, input).await
+}
+
+pub async fn page_draft_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Option<GetPageDraftOutput>> {
+    let input: GetPageDraft = params.parse()?;
+    info!(
+        "Getting page draft ID {} for user ID {}",
+        input.draft_id, input.user_id,
+    );
+
+    match PageDraftService::get_optional(ctx, input).await? {
+        None => Ok(None),
+        Some(draft) => {
+            let wikitext = TextService::get(ctx, &draft.wikitext_hash).await?;
+            Ok(Some(GetPageDraftOutput { draft, wikitext }))
+        }
+    }
+}
+
+pub async fn page_draft_get_all(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<PageDraftModel>> {
+    let input: GetPageDrafts = params.parse()?;
+    info!(
+        "Getting page drafts for user ID {} in site ID {}",
+        input.user_id, input.site_id,
+    );
+    PageDraftService::get_all(ctx, input).await
+}
+
+pub async fn page_draft_edit(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<PageDraftModel> {
+    let input: UpdatePageDraft = params.parse()?;
+    PageDraftService::update(ctx, input).await
+}
+
+pub async fn page_draft_delete(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let input: GetPageDraft = params.parse()?;
+    PageDraftService::delete(ctx, input).await
+}
+
+pub async fn page_draft_publish(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<PublishPageDraftOutput> {
+    let input: PublishPageDraft = params.parse()?;
+    PageDraftService::publish(ctx, input).await
+}
+
+pub async fn page_draft_schedule(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<PageDraftModel> {
+    let input: SchedulePageDraft = params.parse()?;
+    PageDraftService::schedule(ctx, input).await
+}
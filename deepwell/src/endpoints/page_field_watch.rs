@@ -0,0 +1,53 @@
+/*
+ * endpoints/page_field_watch.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::page_field_watch::Model as PageFieldWatchModel;
+use crate::services::page_field_watch::{
+    CreatePageFieldWatch, DeletePageFieldWatch, GetPageFieldWatches,
+};
+
+pub async fn page_field_watch_create(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<PageFieldWatchModel> {
+    let input: CreatePageFieldWatch = params.parse()?;
+    PageFieldWatchService::create(ctx, input).await
+}
+
+pub async fn page_field_watch_delete(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let input: DeletePageFieldWatch = params.parse()?;
+    PageFieldWatchService::delete(ctx, input).await
+}
+
+pub async fn page_field_watch_get_all(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<PageFieldWatchModel>> {
+    let input: GetPageFieldWatches = params.parse()?;
+    info!(
+        "Getting field watches for user ID {} in site ID {}",
+        input.user_id, input.site_id,
+    );
+    PageFieldWatchService::get_all(ctx, input).await
+}
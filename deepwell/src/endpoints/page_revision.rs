@@ -22,8 +22,10 @@ use super::prelude::*;
 use crate::models::page_revision::Model as PageRevisionModel;
 use crate::services::page::GetPageReferenceDetails;
 use crate::services::page_revision::{
-    GetPageRevision, GetPageRevisionDetails, GetPageRevisionRangeDetails,
-    PageRevisionCountOutput, PageRevisionModelFiltered, UpdatePageRevisionDetails,
+    GetPageBlame, GetPageRevision, GetPageRevisionDetails, GetPageRevisionDiff,
+    GetPageRevisionRangeDetails, MassRevertOutput, MassRevertPages, PageBlame,
+    PageRevisionCountOutput, PageRevisionDiff, PageRevisionModelFiltered,
+    RevisionSummary, UpdatePageRevisionDetails,
 };
 use crate::services::{Result, TextService};
 use crate::web::PageDetails;
@@ -108,6 +110,36 @@ pub async fn page_revision_range(
     filter_and_populate_revisions(ctx, revisions, details).await
 }
 
+pub async fn page_revision_diff(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<PageRevisionDiff> {
+    let input: GetPageRevisionDiff = params.parse()?;
+    PageRevisionService::diff(ctx, input).await
+}
+
+pub async fn page_revision_blame(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<PageBlame> {
+    let input: GetPageBlame = params.parse()?;
+
+    info!(
+        "Getting blame for page ID {} in site ID {}",
+        input.page_id, input.site_id,
+    );
+
+    PageRevisionService::blame(ctx, input).await
+}
+
+pub async fn page_revision_mass_revert(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<MassRevertOutput> {
+    let input: MassRevertPages = params.parse()?;
+    PageRevisionService::mass_revert(ctx, input).await
+}
+
 // Helper functions
 
 async fn filter_and_populate_revision(
@@ -115,6 +147,11 @@ async fn filter_and_populate_revision(
     model: PageRevisionModel,
     mut details: PageDetails,
 ) -> Result<PageRevisionModelFiltered> {
+    let RevisionSummary {
+        size_delta,
+        summary,
+    } = PageRevisionService::summarize(ctx, &model).await?;
+
     let PageRevisionModel {
         revision_id,
         revision_type,
@@ -130,12 +167,15 @@ async fn filter_and_populate_revision(
         compiled_hash,
         compiled_at,
         compiled_generator,
+        content_stats,
+        compiled_csp,
         comments,
         hidden,
         title,
         mut alt_title,
         slug,
         tags,
+        applied_tag_rules,
     } = model;
 
     // Strip hidden fields
@@ -176,16 +216,21 @@ async fn filter_and_populate_revision(
         site_id,
         user_id,
         changes,
+        size_delta,
+        summary,
         wikitext,
         compiled_html,
         compiled_at,
         compiled_generator,
+        content_stats,
+        compiled_csp,
         comments,
         hidden,
         title,
         alt_title,
         slug,
         tags,
+        applied_tag_rules,
     })
 }
 
@@ -0,0 +1,45 @@
+/*
+ * endpoints/page_template.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::services::page_template::{
+    GetPageTemplate, GetPageTemplateOutput, InstantiatePageTemplate,
+    InstantiatePageTemplateOutput,
+};
+
+pub async fn page_template_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<GetPageTemplateOutput> {
+    let input: GetPageTemplate = params.parse()?;
+    info!(
+        "Getting page template '{}' in site ID {}",
+        input.name, input.site_id,
+    );
+    PageTemplateService::get(ctx, input).await
+}
+
+pub async fn page_template_instantiate(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<InstantiatePageTemplateOutput> {
+    let input: InstantiatePageTemplate = params.parse()?;
+    PageTemplateService::instantiate(ctx, input).await
+}
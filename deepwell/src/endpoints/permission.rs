@@ -0,0 +1,58 @@
+/*
+ * endpoints/permission.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::permission_acl::Model as PermissionAclModel;
+use crate::services::permission::{
+    ExplainPermission, GetPermissionMatrix, PermissionExplanation, PermissionMatrix,
+    SetPermissionAcl,
+};
+
+pub async fn permission_matrix_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<PermissionMatrix> {
+    let input: GetPermissionMatrix = params.parse()?;
+    PermissionService::get_matrix(ctx, input).await
+}
+
+pub async fn permission_acl_set(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Option<PermissionAclModel>> {
+    let input: SetPermissionAcl = params.parse()?;
+    PermissionService::set_acl(ctx, input).await
+}
+
+pub async fn permission_acl_get_all(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<PermissionAclModel>> {
+    let site_id: i64 = params.one()?;
+    PermissionService::get_acls(ctx, site_id).await
+}
+
+pub async fn permission_explain(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<PermissionExplanation> {
+    let input: ExplainPermission = params.parse()?;
+    PermissionService::explain(ctx, input).await
+}
@@ -0,0 +1,58 @@
+/*
+ * endpoints/public_api.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::services::public_api::{
+    GetPublicPage, GetPublicPageList, PublicOutput, PublicPage, PublicPageList,
+    PublicUser,
+};
+
+pub async fn public_page_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<PublicOutput<PublicPage>> {
+    let input: GetPublicPage = params.parse()?;
+    info!(
+        "Getting public page '{}' from domain '{}'",
+        input.slug, input.domain,
+    );
+    PublicApiService::get_page(ctx, input).await
+}
+
+pub async fn public_page_list(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<PublicOutput<PublicPageList>> {
+    let input: GetPublicPageList = params.parse()?;
+    info!(
+        "Getting public page listing from domain '{}' (offset {})",
+        input.domain, input.offset,
+    );
+    PublicApiService::get_page_list(ctx, input).await
+}
+
+pub async fn public_user_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<PublicOutput<PublicUser>> {
+    let slug: String = params.one()?;
+    info!("Getting public profile for user '{slug}'");
+    PublicApiService::get_user(ctx, slug).await
+}
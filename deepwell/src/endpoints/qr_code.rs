@@ -0,0 +1,34 @@
+/*
+ * endpoints/qr_code.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::services::qr_code::{GetQrCode, QrCodeOutput};
+
+pub async fn qr_code_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<QrCodeOutput> {
+    let input: GetQrCode = params.parse()?;
+    info!(
+        "Getting QR code for page {:?} in site ID {}",
+        input.page, input.site_id,
+    );
+    QrCodeService::get(ctx, input).await
+}
@@ -0,0 +1,40 @@
+/*
+ * endpoints/render_error.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::services::render_error::{
+    GetPageRenderErrors, GetSiteRenderErrors, PageRenderErrors,
+};
+
+pub async fn page_render_errors_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Option<PageRenderErrors>> {
+    let input: GetPageRenderErrors = params.parse()?;
+    RenderErrorService::get_page(ctx, input).await
+}
+
+pub async fn site_render_errors_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<PageRenderErrors>> {
+    let input: GetSiteRenderErrors = params.parse()?;
+    RenderErrorService::get_site(ctx, input).await
+}
@@ -0,0 +1,45 @@
+/*
+ * endpoints/revision_comparison.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::services::revision_comparison::{
+    CreateRevisionComparison, RevisionComparison,
+};
+
+pub async fn page_revision_compare(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<RevisionComparison> {
+    let input: CreateRevisionComparison = params.parse()?;
+    info!(
+        "Comparing revisions {} and {} of page {:?} in site ID {}",
+        input.from_revision_number, input.to_revision_number, input.page, input.site_id,
+    );
+    RevisionComparisonService::get_or_create(ctx, input).await
+}
+
+pub async fn page_revision_comparison_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Option<RevisionComparison>> {
+    let token: String = params.one()?;
+    info!("Getting revision comparison with token '{token}'");
+    RevisionComparisonService::get_optional(ctx, &token).await
+}
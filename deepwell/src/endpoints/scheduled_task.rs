@@ -0,0 +1,73 @@
+/*
+ * endpoints/scheduled_task.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::scheduled_task::Model as ScheduledTaskModel;
+use crate::models::scheduled_task_run::Model as ScheduledTaskRunModel;
+use crate::services::scheduled_task::{
+    CreateScheduledTask, DeleteScheduledTask, GetScheduledTaskRuns, UpdateScheduledTask,
+};
+
+pub async fn scheduled_task_create(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<ScheduledTaskModel> {
+    let input: CreateScheduledTask = params.parse()?;
+    ScheduledTaskService::create(ctx, input).await
+}
+
+pub async fn scheduled_task_get_all(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<ScheduledTaskModel>> {
+    let site_id: i64 = params.one()?;
+    info!("Getting scheduled tasks for site ID {site_id}");
+    ScheduledTaskService::get_all(ctx, site_id).await
+}
+
+pub async fn scheduled_task_update(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<ScheduledTaskModel> {
+    let input: UpdateScheduledTask = params.parse()?;
+    ScheduledTaskService::update(ctx, input).await
+}
+
+pub async fn scheduled_task_delete(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let input: DeleteScheduledTask = params.parse()?;
+    ScheduledTaskService::delete(ctx, input).await
+}
+
+pub async fn scheduled_task_run_get_all(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<ScheduledTaskRunModel>> {
+    let input: GetScheduledTaskRuns = params.parse()?;
+
+    info!(
+        "Getting runs of scheduled task ID {} in site ID {}",
+        input.task_id, input.site_id,
+    );
+
+    ScheduledTaskService::get_runs(ctx, input).await
+}
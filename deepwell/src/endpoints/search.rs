@@ -0,0 +1,38 @@
+/*
+ * endpoints/search.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::services::search::{CheckSearchIndex, SearchIndexReport};
+
+pub async fn search_index_rebuild(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let site_id: i64 = params.one()?;
+    SearchService::queue_rebuild(ctx, site_id).await
+}
+
+pub async fn search_index_check(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<SearchIndexReport> {
+    let input: CheckSearchIndex = params.parse()?;
+    SearchService::check(ctx, input).await
+}
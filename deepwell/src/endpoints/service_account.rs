@@ -0,0 +1,64 @@
+/*
+ * endpoints/service_account.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::services::service_account::{
+    CreateServiceAccount, CreateServiceAccountOutput, RevokeServiceAccount,
+    RotateServiceAccountKey, ServiceAccount, UpdateServiceAccount,
+};
+
+pub async fn service_account_create(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<CreateServiceAccountOutput> {
+    let input: CreateServiceAccount = params.parse()?;
+    ServiceAccountService::create(ctx, input).await
+}
+
+pub async fn service_account_get_all(
+    ctx: &ServiceContext<'_>,
+    _params: Params<'static>,
+) -> Result<Vec<ServiceAccount>> {
+    ServiceAccountService::get_all(ctx).await
+}
+
+pub async fn service_account_update(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<ServiceAccount> {
+    let input: UpdateServiceAccount = params.parse()?;
+    ServiceAccountService::update(ctx, input).await
+}
+
+pub async fn service_account_rotate_key(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<String> {
+    let input: RotateServiceAccountKey = params.parse()?;
+    ServiceAccountService::rotate_key(ctx, input).await
+}
+
+pub async fn service_account_revoke(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let input: RevokeServiceAccount = params.parse()?;
+    ServiceAccountService::revoke(ctx, input).await
+}
@@ -0,0 +1,57 @@
+/*
+ * endpoints/shortlink.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::services::shortlink::{
+    CreateShortlink, GetPageShortlinks, ResolveShortlink, ResolveShortlinkOutput,
+    ShortlinkOutput,
+};
+
+pub async fn shortlink_create(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<ShortlinkOutput> {
+    let input: CreateShortlink = params.parse()?;
+    info!(
+        "Getting or creating shortlink for page {:?} in site ID {}",
+        input.page, input.site_id,
+    );
+    ShortlinkService::get_or_create(ctx, input).await
+}
+
+pub async fn shortlink_get_page(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<ShortlinkOutput>> {
+    let input: GetPageShortlinks = params.parse()?;
+    info!(
+        "Getting all shortlinks for page {:?} in site ID {}",
+        input.page, input.site_id,
+    );
+    ShortlinkService::get_all_for_page(ctx, input).await
+}
+
+pub async fn shortlink_resolve(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Option<ResolveShortlinkOutput>> {
+    let input: ResolveShortlink = params.parse()?;
+    ShortlinkService::resolve(ctx, input).await
+}
@@ -0,0 +1,57 @@
+/*
+ * endpoints/special_page.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::services::special_page::{
+    GetSpecialPage, GetSpecialPageOutput, GetSpecialPageOverride, SetSpecialPageOverride,
+    SpecialPageOverrideOutput,
+};
+
+pub async fn special_page_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<GetSpecialPageOutput> {
+    let input: GetSpecialPage = params.parse()?;
+    SpecialPageService::get_rendered(ctx, input).await
+}
+
+pub async fn special_page_override_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Option<SpecialPageOverrideOutput>> {
+    let input: GetSpecialPageOverride = params.parse()?;
+    SpecialPageService::get_override(ctx, input).await
+}
+
+pub async fn special_page_override_set(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let input: SetSpecialPageOverride = params.parse()?;
+    SpecialPageService::set_override(ctx, input).await
+}
+
+pub async fn special_page_override_delete(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let input: GetSpecialPageOverride = params.parse()?;
+    SpecialPageService::remove_override(ctx, input).await
+}
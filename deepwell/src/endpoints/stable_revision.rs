@@ -0,0 +1,55 @@
+/*
+ * endpoints/stable_revision.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::services::stable_revision::{
+    GetPendingChanges, PendingChanges, SetStableFileRevision, SetStablePageRevision,
+    SetStableRevisionOutput,
+};
+
+pub async fn page_stable_set(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<SetStableRevisionOutput> {
+    let input: SetStablePageRevision = params.parse()?;
+    StableRevisionService::set_page(ctx, input).await
+}
+
+pub async fn page_pending_changes_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Option<PendingChanges>> {
+    let input: GetPendingChanges = params.parse()?;
+
+    info!(
+        "Getting pending changes for page {:?} in site ID {}",
+        input.page, input.site_id,
+    );
+
+    StableRevisionService::get_pending_changes(ctx, input).await
+}
+
+pub async fn file_stable_set(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<SetStableRevisionOutput> {
+    let input: SetStableFileRevision = params.parse()?;
+    StableRevisionService::set_file(ctx, input).await
+}
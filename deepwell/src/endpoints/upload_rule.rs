@@ -0,0 +1,48 @@
+/*
+ * endpoints/upload_rule.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::upload_rule::Model as UploadRuleModel;
+use crate::services::upload_rule::{CreateUploadRule, DeleteUploadRule};
+
+pub async fn upload_rule_create(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<UploadRuleModel> {
+    let input: CreateUploadRule = params.parse()?;
+    UploadRuleService::create(ctx, input).await
+}
+
+pub async fn upload_rule_get_all(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<UploadRuleModel>> {
+    let site_id: i64 = params.one()?;
+    info!("Getting upload rules for site ID {site_id}");
+    UploadRuleService::get_all(ctx, site_id).await
+}
+
+pub async fn upload_rule_delete(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let input: DeleteUploadRule = params.parse()?;
+    UploadRuleService::delete(ctx, input).await
+}
@@ -0,0 +1,106 @@
+/*
+ * logger.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The logger implementation used by DEEPWELL.
+//!
+//! This emits the same formats `femme` did (pretty while debugging, ndjson in
+//! release builds), but every message is first passed through the instance's
+//! `RedactionPolicy`. Because this happens at the logger level, no service
+//! needs to worry about whether a value it is logging is sensitive.
+
+use crate::utils::RedactionPolicy;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::io::{self, Write};
+use std::time::UNIX_EPOCH;
+
+// ANSI terminal codes, for pretty output.
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const YELLOW: &str = "\x1b[33m";
+
+/// Installs the scrubbing logger as the global logger.
+pub fn start(level: LevelFilter, policy: RedactionPolicy) {
+    let logger = Box::new(ScrubbingLogger { policy });
+    log::set_boxed_logger(logger).expect("Could not start logging");
+    log::set_max_level(level);
+}
+
+#[derive(Debug)]
+struct ScrubbingLogger {
+    policy: RedactionPolicy,
+}
+
+impl Log for ScrubbingLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let message = self.policy.scrub(&record.args().to_string());
+        let stdout = io::stdout();
+        let mut handle = stdout.lock();
+
+        // Like println!(), there's nothing useful to do if stdout is gone.
+        let _ = if cfg!(debug_assertions) {
+            write_pretty(&mut handle, record, &message)
+        } else {
+            write_ndjson(&mut handle, record, &message)
+        };
+    }
+
+    fn flush(&self) {
+        let _ = io::stdout().flush();
+    }
+}
+
+fn write_pretty<W: Write>(out: &mut W, record: &Record, message: &str) -> io::Result<()> {
+    let color = match record.level() {
+        Level::Trace | Level::Debug | Level::Info => GREEN,
+        Level::Warn => YELLOW,
+        Level::Error => RED,
+    };
+
+    writeln!(out, "{color}{BOLD}{}{RESET} {message}", record.target())
+}
+
+fn write_ndjson<W: Write>(out: &mut W, record: &Record, message: &str) -> io::Result<()> {
+    let level = match record.level() {
+        Level::Trace => 10,
+        Level::Debug => 20,
+        Level::Info => 30,
+        Level::Warn => 40,
+        Level::Error => 50,
+    };
+
+    let time = UNIX_EPOCH
+        .elapsed()
+        .map(|duration| duration.as_millis())
+        .unwrap_or(0);
+
+    write!(out, "{{\"level\":{level},\"time\":{time},\"msg\":")?;
+    serde_json::to_writer(&mut *out, message)?;
+    writeln!(out, "}}")
+}
@@ -46,6 +46,7 @@ mod endpoints;
 mod hash;
 mod info;
 mod locales;
+mod logger;
 mod models;
 mod redis;
 mod services;
@@ -73,7 +74,7 @@ async fn main() -> Result<()> {
 
     // Configure the logger
     if config.logger {
-        femme::with_level(config.logger_level);
+        logger::start(config.logger_level, config.redaction_policy.clone());
         info!("Loaded server configuration:");
         config.log();
 
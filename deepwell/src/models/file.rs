@@ -16,6 +16,7 @@ pub struct Model {
     pub name: String,
     pub page_id: i64,
     pub site_id: i64,
+    pub stable_revision_id: Option<i64>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -30,6 +31,8 @@ pub enum Relation {
         on_delete = "NoAction"
     )]
     Page,
+    #[sea_orm(has_many = "super::page_file_embed::Entity")]
+    PageFileEmbed,
     #[sea_orm(
         belongs_to = "super::site::Entity",
         from = "Column::SiteId",
@@ -52,6 +55,12 @@ impl Related<super::page::Entity> for Entity {
     }
 }
 
+impl Related<super::page_file_embed::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PageFileEmbed.def()
+    }
+}
+
 impl Related<super::site::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Site.def()
@@ -1,6 +1,6 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.12.14
 
-use super::sea_orm_active_enums::FileRevisionType;
+use super::sea_orm_active_enums::{FileRevisionChange, FileRevisionType};
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
@@ -11,7 +11,7 @@ pub struct Model {
     pub revision_id: i64,
     pub revision_type: FileRevisionType,
     pub created_at: TimeDateTimeWithTimeZone,
-    pub revision_number: i32,
+    pub revision_number: i64,
     pub file_id: i64,
     pub page_id: i64,
     pub site_id: i64,
@@ -20,11 +20,13 @@ pub struct Model {
     pub name: String,
     #[sea_orm(column_type = "Binary(BlobSize::Blob(None))")]
     pub s3_hash: Vec<u8>,
+    #[sea_orm(column_type = "Binary(BlobSize::Blob(None))", nullable)]
+    pub original_s3_hash: Option<Vec<u8>>,
     #[sea_orm(column_type = "Text")]
     pub mime_hint: String,
     pub size_hint: i64,
     pub licensing: Json,
-    pub changes: Vec<String>,
+    pub changes: Vec<FileRevisionChange>,
     #[sea_orm(column_type = "Text")]
     pub comments: String,
     pub hidden: Vec<String>,
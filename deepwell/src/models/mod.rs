@@ -2,30 +2,77 @@
 
 pub mod prelude;
 
+pub mod account_recovery;
+pub mod account_recovery_confirmation;
 pub mod alias;
+pub mod api_throttle;
+pub mod api_usage;
+pub mod audit_log;
+pub mod blob_collection;
+pub mod blob_compression;
+pub mod blob_migration;
+pub mod blob_spool;
+pub mod consistency_report;
+pub mod custom_block;
+pub mod event_outbox;
+pub mod export;
 pub mod file;
+pub mod file_derivative;
+pub mod file_pending_upload;
+pub mod file_pending_upload_chunk;
 pub mod file_revision;
+pub mod file_staging;
+pub mod file_thumbnail;
 pub mod filter;
+pub mod link_recompute;
+pub mod magic_link;
 pub mod message;
 pub mod message_draft;
 pub mod message_recipient;
 pub mod message_record;
 pub mod message_report;
+pub mod oauth_application;
+pub mod oauth_authorization;
+pub mod oauth_code;
+pub mod oauth_token;
 pub mod page;
 pub mod page_attribution;
 pub mod page_category;
 pub mod page_connection;
 pub mod page_connection_missing;
+pub mod page_draft;
+pub mod page_field_watch;
+pub mod page_file_embed;
+pub mod page_file_reference;
+pub mod page_form_data;
 pub mod page_link;
 pub mod page_lock;
 pub mod page_parent;
+pub mod page_render_error;
 pub mod page_revision;
 pub mod page_vote;
+pub mod page_watch_seen;
+pub mod permission_acl;
+pub mod recovery_contact;
+pub mod refresh_token;
 pub mod relation;
+pub mod revision_comparison;
+pub mod scheduled_task;
+pub mod scheduled_task_run;
 pub mod sea_orm_active_enums;
+pub mod search_document;
+pub mod service_account;
 pub mod session;
+pub mod shortlink;
 pub mod site;
 pub mod site_domain;
+pub mod site_git_mirror;
+pub mod site_git_mirror_revision;
+pub mod site_limit;
+pub mod site_storage;
+pub mod special_page_override;
+pub mod tag_rule;
 pub mod text;
+pub mod upload_rule;
 pub mod user;
 pub mod user_bot_owner;
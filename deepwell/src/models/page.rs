@@ -14,10 +14,15 @@ pub struct Model {
     pub from_wikidot: bool,
     pub site_id: i64,
     pub latest_revision_id: Option<i64>,
+    pub stable_revision_id: Option<i64>,
     pub page_category_id: i64,
     #[sea_orm(column_type = "Text")]
     pub slug: String,
     pub discussion_thread_id: Option<i64>,
+    pub noindex: Option<bool>,
+    pub nofollow: Option<bool>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub canonical_url: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -36,6 +41,14 @@ pub enum Relation {
         on_delete = "NoAction"
     )]
     PageCategory,
+    #[sea_orm(has_many = "super::page_draft::Entity")]
+    PageDraft,
+    #[sea_orm(has_many = "super::page_file_embed::Entity")]
+    PageFileEmbed,
+    #[sea_orm(has_many = "super::page_file_reference::Entity")]
+    PageFileReference,
+    #[sea_orm(has_one = "super::page_form_data::Entity")]
+    PageFormData,
     #[sea_orm(has_many = "super::page_link::Entity")]
     PageLink,
     #[sea_orm(has_many = "super::page_lock::Entity")]
@@ -48,8 +61,18 @@ pub enum Relation {
         on_delete = "NoAction"
     )]
     PageRevision,
+    #[sea_orm(has_one = "super::page_render_error::Entity")]
+    PageRenderError,
     #[sea_orm(has_many = "super::page_vote::Entity")]
     PageVote,
+    #[sea_orm(has_many = "super::page_watch_seen::Entity")]
+    PageWatchSeen,
+    #[sea_orm(has_many = "super::permission_acl::Entity")]
+    PermissionAcl,
+    #[sea_orm(has_many = "super::revision_comparison::Entity")]
+    RevisionComparison,
+    #[sea_orm(has_many = "super::shortlink::Entity")]
+    Shortlink,
     #[sea_orm(
         belongs_to = "super::site::Entity",
         from = "Column::SiteId",
@@ -84,6 +107,30 @@ impl Related<super::page_category::Entity> for Entity {
     }
 }
 
+impl Related<super::page_draft::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PageDraft.def()
+    }
+}
+
+impl Related<super::page_file_embed::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PageFileEmbed.def()
+    }
+}
+
+impl Related<super::page_file_reference::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PageFileReference.def()
+    }
+}
+
+impl Related<super::page_form_data::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PageFormData.def()
+    }
+}
+
 impl Related<super::page_link::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::PageLink.def()
@@ -96,6 +143,12 @@ impl Related<super::page_lock::Entity> for Entity {
     }
 }
 
+impl Related<super::page_render_error::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PageRenderError.def()
+    }
+}
+
 impl Related<super::page_revision::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::PageRevision.def()
@@ -108,6 +161,30 @@ impl Related<super::page_vote::Entity> for Entity {
     }
 }
 
+impl Related<super::page_watch_seen::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PageWatchSeen.def()
+    }
+}
+
+impl Related<super::permission_acl::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PermissionAcl.def()
+    }
+}
+
+impl Related<super::revision_comparison::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::RevisionComparison.def()
+    }
+}
+
+impl Related<super::shortlink::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Shortlink.def()
+    }
+}
+
 impl Related<super::site::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Site.def()
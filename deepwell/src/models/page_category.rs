@@ -1,5 +1,6 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.12.14
 
+use super::sea_orm_active_enums::{ModuleState, ParserProfile};
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
@@ -10,15 +11,30 @@ pub struct Model {
     pub category_id: i64,
     pub created_at: TimeDateTimeWithTimeZone,
     pub updated_at: Option<TimeDateTimeWithTimeZone>,
+    pub version: i32,
     pub site_id: i64,
     #[sea_orm(column_type = "Text")]
     pub slug: String,
+    pub noindex: bool,
+    pub nofollow: bool,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub canonical_url: Option<String>,
+    pub parser_profile: Option<ParserProfile>,
+    pub rating_module: Option<ModuleState>,
+    pub comments_module: Option<ModuleState>,
+    pub form: Option<Json>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
     #[sea_orm(has_many = "super::page::Entity")]
     Page,
+    #[sea_orm(has_many = "super::page_field_watch::Entity")]
+    PageFieldWatch,
+    #[sea_orm(has_many = "super::page_form_data::Entity")]
+    PageFormData,
+    #[sea_orm(has_many = "super::permission_acl::Entity")]
+    PermissionAcl,
     #[sea_orm(
         belongs_to = "super::site::Entity",
         from = "Column::SiteId",
@@ -35,6 +51,24 @@ impl Related<super::page::Entity> for Entity {
     }
 }
 
+impl Related<super::page_field_watch::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PageFieldWatch.def()
+    }
+}
+
+impl Related<super::page_form_data::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PageFormData.def()
+    }
+}
+
+impl Related<super::permission_acl::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PermissionAcl.def()
+    }
+}
+
 impl Related<super::site::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Site.def()
@@ -1,6 +1,6 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.12.14
 
-use super::sea_orm_active_enums::PageRevisionType;
+use super::sea_orm_active_enums::{PageRevisionChange, PageRevisionType};
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
@@ -12,12 +12,12 @@ pub struct Model {
     pub revision_type: PageRevisionType,
     pub created_at: TimeDateTimeWithTimeZone,
     pub updated_at: Option<TimeDateTimeWithTimeZone>,
-    pub revision_number: i32,
+    pub revision_number: i64,
     pub page_id: i64,
     pub site_id: i64,
     pub user_id: i64,
     pub from_wikidot: bool,
-    pub changes: Vec<String>,
+    pub changes: Vec<PageRevisionChange>,
     #[sea_orm(column_type = "Binary(BlobSize::Blob(None))")]
     pub wikitext_hash: Vec<u8>,
     #[sea_orm(column_type = "Binary(BlobSize::Blob(None))")]
@@ -25,6 +25,8 @@ pub struct Model {
     pub compiled_at: TimeDateTimeWithTimeZone,
     #[sea_orm(column_type = "Text")]
     pub compiled_generator: String,
+    pub content_stats: Json,
+    pub compiled_csp: Json,
     #[sea_orm(column_type = "Text")]
     pub comments: String,
     pub hidden: Vec<String>,
@@ -35,6 +37,7 @@ pub struct Model {
     #[sea_orm(column_type = "Text")]
     pub slug: String,
     pub tags: Vec<String>,
+    pub applied_tag_rules: Vec<i64>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -47,6 +50,8 @@ pub enum Relation {
         on_delete = "NoAction"
     )]
     Page,
+    #[sea_orm(has_many = "super::shortlink::Entity")]
+    Shortlink,
     #[sea_orm(
         belongs_to = "super::site::Entity",
         from = "Column::SiteId",
@@ -55,6 +60,8 @@ pub enum Relation {
         on_delete = "NoAction"
     )]
     Site,
+    #[sea_orm(has_many = "super::site_git_mirror_revision::Entity")]
+    SiteGitMirrorRevision,
     #[sea_orm(
         belongs_to = "super::text::Entity",
         from = "Column::CompiledHash",
@@ -87,12 +94,24 @@ impl Related<super::page::Entity> for Entity {
     }
 }
 
+impl Related<super::shortlink::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Shortlink.def()
+    }
+}
+
 impl Related<super::site::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Site.def()
     }
 }
 
+impl Related<super::site_git_mirror_revision::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::SiteGitMirrorRevision.def()
+    }
+}
+
 impl Related<super::user::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::User.def()
@@ -1,28 +1,75 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.12.14
 
+pub use super::account_recovery::Entity as AccountRecovery;
+pub use super::account_recovery_confirmation::Entity as AccountRecoveryConfirmation;
 pub use super::alias::Entity as Alias;
+pub use super::api_throttle::Entity as ApiThrottle;
+pub use super::api_usage::Entity as ApiUsage;
+pub use super::audit_log::Entity as AuditLog;
+pub use super::blob_collection::Entity as BlobCollection;
+pub use super::blob_compression::Entity as BlobCompression;
+pub use super::blob_migration::Entity as BlobMigration;
+pub use super::blob_spool::Entity as BlobSpool;
+pub use super::consistency_report::Entity as ConsistencyReport;
+pub use super::custom_block::Entity as CustomBlock;
+pub use super::event_outbox::Entity as EventOutbox;
+pub use super::export::Entity as Export;
 pub use super::file::Entity as File;
+pub use super::file_derivative::Entity as FileDerivative;
+pub use super::file_pending_upload::Entity as FilePendingUpload;
+pub use super::file_pending_upload_chunk::Entity as FilePendingUploadChunk;
 pub use super::file_revision::Entity as FileRevision;
+pub use super::file_staging::Entity as FileStaging;
+pub use super::file_thumbnail::Entity as FileThumbnail;
 pub use super::filter::Entity as Filter;
+pub use super::link_recompute::Entity as LinkRecompute;
+pub use super::magic_link::Entity as MagicLink;
 pub use super::message::Entity as Message;
 pub use super::message_draft::Entity as MessageDraft;
 pub use super::message_recipient::Entity as MessageRecipient;
 pub use super::message_record::Entity as MessageRecord;
 pub use super::message_report::Entity as MessageReport;
+pub use super::oauth_application::Entity as OauthApplication;
+pub use super::oauth_authorization::Entity as OauthAuthorization;
+pub use super::oauth_code::Entity as OauthCode;
+pub use super::oauth_token::Entity as OauthToken;
 pub use super::page::Entity as Page;
 pub use super::page_attribution::Entity as PageAttribution;
 pub use super::page_category::Entity as PageCategory;
 pub use super::page_connection::Entity as PageConnection;
 pub use super::page_connection_missing::Entity as PageConnectionMissing;
+pub use super::page_draft::Entity as PageDraft;
+pub use super::page_field_watch::Entity as PageFieldWatch;
+pub use super::page_file_embed::Entity as PageFileEmbed;
+pub use super::page_file_reference::Entity as PageFileReference;
+pub use super::page_form_data::Entity as PageFormData;
 pub use super::page_link::Entity as PageLink;
 pub use super::page_lock::Entity as PageLock;
 pub use super::page_parent::Entity as PageParent;
+pub use super::page_render_error::Entity as PageRenderError;
 pub use super::page_revision::Entity as PageRevision;
 pub use super::page_vote::Entity as PageVote;
+pub use super::page_watch_seen::Entity as PageWatchSeen;
+pub use super::permission_acl::Entity as PermissionAcl;
+pub use super::recovery_contact::Entity as RecoveryContact;
+pub use super::refresh_token::Entity as RefreshToken;
 pub use super::relation::Entity as Relation;
+pub use super::revision_comparison::Entity as RevisionComparison;
+pub use super::scheduled_task::Entity as ScheduledTask;
+pub use super::scheduled_task_run::Entity as ScheduledTaskRun;
+pub use super::search_document::Entity as SearchDocument;
+pub use super::service_account::Entity as ServiceAccount;
 pub use super::session::Entity as Session;
+pub use super::shortlink::Entity as Shortlink;
 pub use super::site::Entity as Site;
 pub use super::site_domain::Entity as SiteDomain;
+pub use super::site_git_mirror::Entity as SiteGitMirror;
+pub use super::site_git_mirror_revision::Entity as SiteGitMirrorRevision;
+pub use super::site_limit::Entity as SiteLimit;
+pub use super::site_storage::Entity as SiteStorage;
+pub use super::special_page_override::Entity as SpecialPageOverride;
+pub use super::tag_rule::Entity as TagRule;
 pub use super::text::Entity as Text;
+pub use super::upload_rule::Entity as UploadRule;
 pub use super::user::Entity as User;
 pub use super::user_bot_owner::Entity as UserBotOwner;
@@ -17,6 +17,64 @@ pub enum AliasType {
 #[derive(
     Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Copy, Serialize, Deserialize,
 )]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "custom_block_body")]
+#[serde(rename_all = "kebab-case")]
+pub enum CustomBlockBody {
+    #[sea_orm(string_value = "elements")]
+    Elements,
+    #[sea_orm(string_value = "none")]
+    None,
+    #[sea_orm(string_value = "text")]
+    Text,
+}
+#[derive(
+    Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Copy, Serialize, Deserialize,
+)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "export_format")]
+#[serde(rename_all = "kebab-case")]
+pub enum ExportFormat {
+    #[sea_orm(string_value = "epub")]
+    Epub,
+    #[sea_orm(string_value = "pdf")]
+    Pdf,
+}
+#[derive(
+    Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Copy, Serialize, Deserialize,
+)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "export_status")]
+#[serde(rename_all = "kebab-case")]
+pub enum ExportStatus {
+    #[sea_orm(string_value = "completed")]
+    Completed,
+    #[sea_orm(string_value = "failed")]
+    Failed,
+    #[sea_orm(string_value = "queued")]
+    Queued,
+}
+#[derive(
+    Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Copy, Serialize, Deserialize,
+)]
+#[sea_orm(
+    rs_type = "String",
+    db_type = "Enum",
+    enum_name = "file_revision_change"
+)]
+#[serde(rename_all = "snake_case")]
+pub enum FileRevisionChange {
+    #[sea_orm(string_value = "blob")]
+    Blob,
+    #[sea_orm(string_value = "licensing")]
+    Licensing,
+    #[sea_orm(string_value = "mime")]
+    Mime,
+    #[sea_orm(string_value = "name")]
+    Name,
+    #[sea_orm(string_value = "page")]
+    Page,
+}
+#[derive(
+    Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Copy, Serialize, Deserialize,
+)]
 #[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "file_revision_type")]
 #[serde(rename_all = "kebab-case")]
 pub enum FileRevisionType {
@@ -32,6 +90,36 @@ pub enum FileRevisionType {
 #[derive(
     Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Copy, Serialize, Deserialize,
 )]
+#[sea_orm(
+    rs_type = "String",
+    db_type = "Enum",
+    enum_name = "heading_id_strategy"
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum HeadingIdStrategy {
+    #[sea_orm(string_value = "hash")]
+    Hash,
+    #[sea_orm(string_value = "suffix")]
+    Suffix,
+}
+#[derive(
+    Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Copy, Serialize, Deserialize,
+)]
+#[sea_orm(
+    rs_type = "String",
+    db_type = "Enum",
+    enum_name = "image_conversion_format"
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum ImageConversionFormat {
+    #[sea_orm(string_value = "avif")]
+    Avif,
+    #[sea_orm(string_value = "webp")]
+    Webp,
+}
+#[derive(
+    Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Copy, Serialize, Deserialize,
+)]
 #[sea_orm(
     rs_type = "String",
     db_type = "Enum",
@@ -49,6 +137,53 @@ pub enum MessageRecipientType {
 #[derive(
     Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Copy, Serialize, Deserialize,
 )]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "module_state")]
+#[serde(rename_all = "kebab-case")]
+pub enum ModuleState {
+    #[sea_orm(string_value = "enabled")]
+    Enabled,
+    #[sea_orm(string_value = "hidden")]
+    Hidden,
+    #[sea_orm(string_value = "placeholder")]
+    Placeholder,
+}
+#[derive(
+    Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Copy, Serialize, Deserialize,
+)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "parser_profile")]
+#[serde(rename_all = "kebab-case")]
+pub enum ParserProfile {
+    #[sea_orm(string_value = "compatibility")]
+    Compatibility,
+    #[sea_orm(string_value = "standard")]
+    Standard,
+    #[sea_orm(string_value = "strict")]
+    Strict,
+}
+#[derive(
+    Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Copy, Serialize, Deserialize,
+)]
+#[sea_orm(
+    rs_type = "String",
+    db_type = "Enum",
+    enum_name = "page_revision_change"
+)]
+#[serde(rename_all = "snake_case")]
+pub enum PageRevisionChange {
+    #[sea_orm(string_value = "alt_title")]
+    AltTitle,
+    #[sea_orm(string_value = "slug")]
+    Slug,
+    #[sea_orm(string_value = "tags")]
+    Tags,
+    #[sea_orm(string_value = "title")]
+    Title,
+    #[sea_orm(string_value = "wikitext")]
+    Wikitext,
+}
+#[derive(
+    Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Copy, Serialize, Deserialize,
+)]
 #[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "page_revision_type")]
 #[serde(rename_all = "kebab-case")]
 pub enum PageRevisionType {
@@ -85,6 +220,21 @@ pub enum RelationObjectType {
 #[derive(
     Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Copy, Serialize, Deserialize,
 )]
+#[sea_orm(
+    rs_type = "String",
+    db_type = "Enum",
+    enum_name = "search_document_type"
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum SearchDocumentType {
+    #[sea_orm(string_value = "file")]
+    File,
+    #[sea_orm(string_value = "page")]
+    Page,
+}
+#[derive(
+    Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Copy, Serialize, Deserialize,
+)]
 #[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "user_type")]
 #[serde(rename_all = "kebab-case")]
 pub enum UserType {
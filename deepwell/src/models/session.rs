@@ -11,15 +11,30 @@ pub struct Model {
     pub user_id: i64,
     pub created_at: TimeDateTimeWithTimeZone,
     pub expires_at: TimeDateTimeWithTimeZone,
+    pub login_at: TimeDateTimeWithTimeZone,
+    pub rotate_at: TimeDateTimeWithTimeZone,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub replaced_by: Option<String>,
     #[sea_orm(column_type = "Text")]
     pub ip_address: String,
     #[sea_orm(column_type = "Text")]
     pub user_agent: String,
     pub restricted: bool,
+    pub impersonator_id: Option<i64>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub refresh_family_id: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::ImpersonatorId",
+        to = "super::user::Column::UserId",
+        on_update = "NoAction",
+        on_delete = "NoAction"
+    )]
+    User2,
     #[sea_orm(
         belongs_to = "super::user::Entity",
         from = "Column::UserId",
@@ -27,13 +42,7 @@ pub enum Relation {
         on_update = "NoAction",
         on_delete = "NoAction"
     )]
-    User,
-}
-
-impl Related<super::user::Entity> for Entity {
-    fn to() -> RelationDef {
-        Relation::User.def()
-    }
+    User1,
 }
 
 impl ActiveModelBehavior for ActiveModel {}
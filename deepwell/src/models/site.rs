@@ -1,5 +1,8 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.12.14
 
+use super::sea_orm_active_enums::{
+    HeadingIdStrategy, ImageConversionFormat, ModuleState, ParserProfile,
+};
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
@@ -10,6 +13,7 @@ pub struct Model {
     pub site_id: i64,
     pub created_at: TimeDateTimeWithTimeZone,
     pub updated_at: Option<TimeDateTimeWithTimeZone>,
+    pub version: i32,
     pub deleted_at: Option<TimeDateTimeWithTimeZone>,
     pub from_wikidot: bool,
     #[sea_orm(column_type = "Text")]
@@ -26,24 +30,71 @@ pub struct Model {
     pub default_page: String,
     #[sea_orm(column_type = "Text", nullable)]
     pub custom_domain: Option<String>,
+    #[sea_orm(column_type = "Text", nullable, unique)]
+    pub shortlink_domain: Option<String>,
+    pub serve_stable_revisions: bool,
+    pub strip_image_metadata: bool,
+    pub allow_svg_uploads: bool,
+    pub parser_profile: ParserProfile,
+    pub heading_id_strategy: HeadingIdStrategy,
+    pub allow_embeds: bool,
+    pub allow_css: bool,
+    pub allow_external_images: bool,
+    pub rating_module: ModuleState,
+    pub comments_module: ModuleState,
+    pub file_revision_retention_days: Option<i32>,
+    pub image_conversion: Option<ImageConversionFormat>,
+    pub image_conversion_quality: i32,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::api_throttle::Entity")]
+    ApiThrottle,
+    #[sea_orm(has_many = "super::api_usage::Entity")]
+    ApiUsage,
+    #[sea_orm(has_many = "super::audit_log::Entity")]
+    AuditLog,
+    #[sea_orm(has_many = "super::custom_block::Entity")]
+    CustomBlock,
+    #[sea_orm(has_many = "super::export::Entity")]
+    Export,
     #[sea_orm(has_many = "super::file::Entity")]
     File,
     #[sea_orm(has_many = "super::file_revision::Entity")]
     FileRevision,
     #[sea_orm(has_many = "super::filter::Entity")]
     Filter,
+    #[sea_orm(has_many = "super::link_recompute::Entity")]
+    LinkRecompute,
     #[sea_orm(has_many = "super::message_report::Entity")]
     MessageReport,
     #[sea_orm(has_many = "super::page::Entity")]
     Page,
     #[sea_orm(has_many = "super::page_category::Entity")]
     PageCategory,
+    #[sea_orm(has_many = "super::page_field_watch::Entity")]
+    PageFieldWatch,
+    #[sea_orm(has_many = "super::page_file_reference::Entity")]
+    PageFileReference,
+    #[sea_orm(has_many = "super::page_form_data::Entity")]
+    PageFormData,
+    #[sea_orm(has_many = "super::page_render_error::Entity")]
+    PageRenderError,
     #[sea_orm(has_many = "super::page_revision::Entity")]
     PageRevision,
+    #[sea_orm(has_many = "super::permission_acl::Entity")]
+    PermissionAcl,
+    #[sea_orm(has_many = "super::revision_comparison::Entity")]
+    RevisionComparison,
+    #[sea_orm(has_many = "super::scheduled_task::Entity")]
+    ScheduledTask,
+    #[sea_orm(has_many = "super::scheduled_task_run::Entity")]
+    ScheduledTaskRun,
+    #[sea_orm(has_many = "super::search_document::Entity")]
+    SearchDocument,
+    #[sea_orm(has_many = "super::shortlink::Entity")]
+    Shortlink,
     #[sea_orm(
         belongs_to = "super::site_domain::Entity",
         from = "Column::CustomDomain",
@@ -52,6 +103,48 @@ pub enum Relation {
         on_delete = "NoAction"
     )]
     SiteDomain,
+    #[sea_orm(has_one = "super::site_git_mirror::Entity")]
+    SiteGitMirror,
+    #[sea_orm(has_one = "super::site_limit::Entity")]
+    SiteLimit,
+    #[sea_orm(has_one = "super::site_storage::Entity")]
+    SiteStorage,
+    #[sea_orm(has_many = "super::special_page_override::Entity")]
+    SpecialPageOverride,
+    #[sea_orm(has_many = "super::tag_rule::Entity")]
+    TagRule,
+    #[sea_orm(has_many = "super::upload_rule::Entity")]
+    UploadRule,
+}
+
+impl Related<super::api_throttle::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ApiThrottle.def()
+    }
+}
+
+impl Related<super::api_usage::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ApiUsage.def()
+    }
+}
+
+impl Related<super::audit_log::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::AuditLog.def()
+    }
+}
+
+impl Related<super::custom_block::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::CustomBlock.def()
+    }
+}
+
+impl Related<super::export::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Export.def()
+    }
 }
 
 impl Related<super::file::Entity> for Entity {
@@ -72,6 +165,12 @@ impl Related<super::filter::Entity> for Entity {
     }
 }
 
+impl Related<super::link_recompute::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::LinkRecompute.def()
+    }
+}
+
 impl Related<super::message_report::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::MessageReport.def()
@@ -90,18 +189,114 @@ impl Related<super::page_category::Entity> for Entity {
     }
 }
 
+impl Related<super::page_field_watch::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PageFieldWatch.def()
+    }
+}
+
+impl Related<super::page_file_reference::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PageFileReference.def()
+    }
+}
+
+impl Related<super::page_form_data::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PageFormData.def()
+    }
+}
+
+impl Related<super::page_render_error::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PageRenderError.def()
+    }
+}
+
 impl Related<super::page_revision::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::PageRevision.def()
     }
 }
 
+impl Related<super::permission_acl::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PermissionAcl.def()
+    }
+}
+
+impl Related<super::revision_comparison::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::RevisionComparison.def()
+    }
+}
+
+impl Related<super::scheduled_task::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ScheduledTask.def()
+    }
+}
+
+impl Related<super::scheduled_task_run::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ScheduledTaskRun.def()
+    }
+}
+
+impl Related<super::search_document::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::SearchDocument.def()
+    }
+}
+
+impl Related<super::shortlink::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Shortlink.def()
+    }
+}
+
 impl Related<super::site_domain::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::SiteDomain.def()
     }
 }
 
+impl Related<super::site_git_mirror::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::SiteGitMirror.def()
+    }
+}
+
+impl Related<super::site_limit::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::SiteLimit.def()
+    }
+}
+
+impl Related<super::site_storage::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::SiteStorage.def()
+    }
+}
+
+impl Related<super::special_page_override::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::SpecialPageOverride.def()
+    }
+}
+
+impl Related<super::tag_rule::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TagRule.def()
+    }
+}
+
+impl Related<super::upload_rule::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::UploadRule.def()
+    }
+}
+
 impl Related<super::message::Entity> for Entity {
     fn to() -> RelationDef {
         super::message_report::Relation::Message.def()
@@ -17,6 +17,15 @@ pub struct Model {
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
-pub enum Relation {}
+pub enum Relation {
+    #[sea_orm(has_many = "super::special_page_override::Entity")]
+    SpecialPageOverride,
+}
+
+impl Related<super::special_page_override::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::SpecialPageOverride.def()
+    }
+}
 
 impl ActiveModelBehavior for ActiveModel {}
@@ -12,6 +12,7 @@ pub struct Model {
     pub user_type: UserType,
     pub created_at: TimeDateTimeWithTimeZone,
     pub updated_at: Option<TimeDateTimeWithTimeZone>,
+    pub version: i32,
     pub deleted_at: Option<TimeDateTimeWithTimeZone>,
     pub from_wikidot: bool,
     #[sea_orm(column_type = "Text")]
@@ -29,6 +30,7 @@ pub struct Model {
     #[sea_orm(column_type = "Text", nullable)]
     pub multi_factor_secret: Option<String>,
     pub multi_factor_recovery_codes: Option<Vec<String>>,
+    pub magic_link_login: bool,
     pub locales: Vec<String>,
     #[sea_orm(column_type = "Binary(BlobSize::Blob(None))", nullable)]
     pub avatar_s3_hash: Option<Vec<u8>>,
@@ -47,10 +49,24 @@ pub struct Model {
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::account_recovery::Entity")]
+    AccountRecovery,
     #[sea_orm(has_many = "super::alias::Entity")]
     Alias,
+    #[sea_orm(has_many = "super::api_usage::Entity")]
+    ApiUsage,
+    #[sea_orm(has_many = "super::blob_collection::Entity")]
+    BlobCollection,
+    #[sea_orm(has_many = "super::consistency_report::Entity")]
+    ConsistencyReport,
+    #[sea_orm(has_many = "super::custom_block::Entity")]
+    CustomBlock,
+    #[sea_orm(has_many = "super::export::Entity")]
+    Export,
     #[sea_orm(has_many = "super::file_revision::Entity")]
     FileRevision,
+    #[sea_orm(has_many = "super::magic_link::Entity")]
+    MagicLink,
     #[sea_orm(has_many = "super::message::Entity")]
     Message,
     #[sea_orm(has_many = "super::message_draft::Entity")]
@@ -59,14 +75,32 @@ pub enum Relation {
     MessageRecipient,
     #[sea_orm(has_many = "super::message_record::Entity")]
     MessageRecord,
+    #[sea_orm(has_many = "super::oauth_application::Entity")]
+    OauthApplication,
+    #[sea_orm(has_many = "super::oauth_authorization::Entity")]
+    OauthAuthorization,
     #[sea_orm(has_many = "super::page_attribution::Entity")]
     PageAttribution,
+    #[sea_orm(has_many = "super::page_field_watch::Entity")]
+    PageFieldWatch,
     #[sea_orm(has_many = "super::page_lock::Entity")]
     PageLock,
     #[sea_orm(has_many = "super::page_revision::Entity")]
     PageRevision,
-    #[sea_orm(has_many = "super::session::Entity")]
-    Session,
+    #[sea_orm(has_many = "super::page_watch_seen::Entity")]
+    PageWatchSeen,
+    #[sea_orm(has_many = "super::refresh_token::Entity")]
+    RefreshToken,
+    #[sea_orm(has_many = "super::scheduled_task::Entity")]
+    ScheduledTask,
+    #[sea_orm(has_many = "super::service_account::Entity")]
+    ServiceAccount,
+}
+
+impl Related<super::account_recovery::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::AccountRecovery.def()
+    }
 }
 
 impl Related<super::alias::Entity> for Entity {
@@ -75,12 +109,48 @@ impl Related<super::alias::Entity> for Entity {
     }
 }
 
+impl Related<super::api_usage::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ApiUsage.def()
+    }
+}
+
+impl Related<super::blob_collection::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::BlobCollection.def()
+    }
+}
+
+impl Related<super::consistency_report::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ConsistencyReport.def()
+    }
+}
+
+impl Related<super::custom_block::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::CustomBlock.def()
+    }
+}
+
+impl Related<super::export::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Export.def()
+    }
+}
+
 impl Related<super::file_revision::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::FileRevision.def()
     }
 }
 
+impl Related<super::magic_link::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::MagicLink.def()
+    }
+}
+
 impl Related<super::message::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Message.def()
@@ -105,12 +175,30 @@ impl Related<super::message_record::Entity> for Entity {
     }
 }
 
+impl Related<super::oauth_application::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::OauthApplication.def()
+    }
+}
+
+impl Related<super::oauth_authorization::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::OauthAuthorization.def()
+    }
+}
+
 impl Related<super::page_attribution::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::PageAttribution.def()
     }
 }
 
+impl Related<super::page_field_watch::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PageFieldWatch.def()
+    }
+}
+
 impl Related<super::page_lock::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::PageLock.def()
@@ -123,9 +211,27 @@ impl Related<super::page_revision::Entity> for Entity {
     }
 }
 
-impl Related<super::session::Entity> for Entity {
+impl Related<super::page_watch_seen::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PageWatchSeen.def()
+    }
+}
+
+impl Related<super::refresh_token::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::RefreshToken.def()
+    }
+}
+
+impl Related<super::scheduled_task::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ScheduledTask.def()
+    }
+}
+
+impl Related<super::service_account::Entity> for Entity {
     fn to() -> RelationDef {
-        Relation::Session.def()
+        Relation::ServiceAccount.def()
     }
 }
 
@@ -25,6 +25,10 @@ use anyhow::Result;
 use redis::aio::ConnectionManager;
 use rsmq_async::{MultiplexedRsmq, RsmqConnection};
 
+/// Gets what a fixed window counter would be after one more request, without changing it.
+const PEEK_COUNTER_SCRIPT: &str =
+    "return (tonumber(redis.call('GET', KEYS[1])) or 0) + 1";
+
 pub async fn connect(redis_uri: &str) -> Result<(ConnectionManager, MultiplexedRsmq)> {
     // Create regular redis client
     let client = redis::Client::open(redis_uri)?;
@@ -53,6 +57,29 @@ pub async fn connect(redis_uri: &str) -> Result<(ConnectionManager, MultiplexedR
     Ok((redis, rsmq))
 }
 
+/// Builds the commands to count a request in a fixed window, which return the new count.
+///
+/// The window starts with the first request and expires on its own.
+/// For dry runs, the counter is only read, giving what the count would have been.
+pub fn window_counter(key: &str, window_secs: u64, check_only: bool) -> redis::Pipeline {
+    let mut pipe = redis::pipe();
+    if check_only {
+        pipe.cmd("EVAL").arg(PEEK_COUNTER_SCRIPT).arg(1).arg(key);
+    } else {
+        pipe.atomic()
+            .cmd("SET")
+            .arg(key)
+            .arg(0)
+            .arg("EX")
+            .arg(window_secs)
+            .arg("NX")
+            .ignore()
+            .incr(key, 1);
+    }
+
+    pipe
+}
+
 async fn job_queue_exists(rsmq: &mut MultiplexedRsmq) -> Result<bool> {
     // NOTE: Effectively the same as rsmq.list_queues().await?.contains(JOB_QUEUE_NAME),
     //       except we don't have to deal with the "&String" type issue.
@@ -60,3 +87,23 @@ async fn job_queue_exists(rsmq: &mut MultiplexedRsmq) -> Result<bool> {
     let exists = queues.iter().any(|name| JOB_QUEUE_NAME == name);
     Ok(exists)
 }
+
+#[test]
+fn window_counter_dry_run() {
+    fn has_command(pipe: &redis::Pipeline, command: &str) -> bool {
+        let packed = String::from_utf8(pipe.get_packed_pipeline()).unwrap();
+        packed.split("\r\n").any(|part| part == command)
+    }
+
+    let pipe = window_counter("test:counter", 60, false);
+    assert!(has_command(&pipe, "SET"));
+    assert!(has_command(&pipe, "INCRBY") || has_command(&pipe, "INCR"));
+
+    // Dry runs must leave the counter and its expiry as they are
+    let pipe = window_counter("test:counter", 60, true);
+    assert!(has_command(&pipe, "EVAL"));
+    assert!(!has_command(&pipe, "SET"));
+    assert!(!has_command(&pipe, "INCRBY"));
+    assert!(!has_command(&pipe, "INCR"));
+    assert!(!has_command(&pipe, "EXPIRE"));
+}
@@ -0,0 +1,48 @@
+/*
+ * services/account_recovery/mod.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The account recovery service, for regaining access to a lost account.
+//!
+//! Recovery codes only help if they were kept safe. As a fallback, users can
+//! register backup email addresses and designate other users as trusted
+//! contacts. If they are locked out, they can request recovery, which must
+//! then be confirmed by enough of these contacts. Backup email addresses
+//! only count once they have been verified, by following a link sent to them.
+//!
+//! So that this cannot be used to quietly take over an account, everyone
+//! involved is notified when recovery is requested, and it cannot complete
+//! until a waiting period has passed. The account's own email is sent a link
+//! to cancel it, so the real owner has time to stop a recovery they did not
+//! ask for.
+//!
+//! Completing recovery resets the password, turns off MFA, and logs out all
+//! sessions. As with login links, DEEPWELL does not send email itself, so
+//! tokens which need to be emailed are returned to the caller.
+
+mod prelude {
+    pub use super::super::prelude::*;
+    pub use super::structs::*;
+}
+
+mod service;
+mod structs;
+
+pub use self::service::AccountRecoveryService;
+pub use self::structs::*;
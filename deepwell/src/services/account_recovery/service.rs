@@ -0,0 +1,672 @@
+/*
+ * services/account_recovery/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::constants::SYSTEM_USER_ID;
+use crate::models::account_recovery::{
+    self, Entity as AccountRecovery, Model as AccountRecoveryModel,
+};
+use crate::models::account_recovery_confirmation::{
+    self, Entity as AccountRecoveryConfirmation,
+};
+use crate::models::recovery_contact::{
+    self, Entity as RecoveryContact, Model as RecoveryContactModel,
+};
+use crate::models::user::{self, Entity as User, Model as UserModel};
+use crate::services::email::{EmailClassification, EmailService};
+use crate::services::message::CreateMessageDraft;
+use crate::services::user::UpdateUserBody;
+use crate::services::{MessageService, MfaService, SessionService, UserService};
+use crate::utils::{date_wikitext, hash_token, new_token};
+use fluent::{FluentArgs, FluentValue};
+use sea_query::SimpleExpr;
+use unic_langid::LanguageIdentifier;
+
+#[derive(Debug)]
+pub struct AccountRecoveryService;
+
+impl AccountRecoveryService {
+    /// Adds a backup email address or trusted contact for a user.
+    pub async fn add_contact(
+        ctx: &ServiceContext<'_>,
+        AddRecoveryContact {
+            user_id,
+            email,
+            contact_user,
+            ..
+        }: AddRecoveryContact<'_>,
+    ) -> Result<RecoveryContactModel> {
+        info!("Adding recovery contact for user ID {user_id}");
+
+        let user = UserService::get(ctx, Reference::Id(user_id)).await?;
+        let (email, contact_user_id) = match (email, contact_user) {
+            (Some(email), None) => {
+                if email == user.email {
+                    error!("Backup email cannot be the account's own email");
+                    return Err(Error::RecoveryContactInvalid);
+                }
+
+                match EmailService::validate(&email).await?.classification {
+                    EmailClassification::Normal | EmailClassification::Alias => (),
+                    EmailClassification::Disposable => {
+                        return Err(Error::DisallowedEmail)
+                    }
+                    EmailClassification::Invalid => return Err(Error::InvalidEmail),
+                }
+
+                (Some(email), None)
+            }
+            (None, Some(reference)) => {
+                let contact = UserService::get(ctx, reference).await?;
+                if contact.user_id == user_id || contact.deleted_at.is_some() {
+                    error!("Trusted contact must be another active user");
+                    return Err(Error::RecoveryContactInvalid);
+                }
+
+                (None, Some(contact.user_id))
+            }
+            _ => {
+                error!("Recovery contact must be exactly one of an email or a user");
+                return Err(Error::RecoveryContactInvalid);
+            }
+        };
+
+        // Trusted contacts are existing users, but emails must be verified
+        let verified_at = contact_user_id.map(|_| now());
+
+        let txn = ctx.transaction();
+        let model = recovery_contact::ActiveModel {
+            user_id: Set(user_id),
+            email: Set(email),
+            contact_user_id: Set(contact_user_id),
+            verified_at: Set(verified_at),
+            ..Default::default()
+        };
+
+        let contact = model.insert(txn).await?;
+        Ok(contact)
+    }
+
+    /// Issues a token to verify a backup email address.
+    ///
+    /// The caller is responsible for emailing a link with the token to the
+    /// address. Any token issued before for this contact stops working.
+    /// If the contact doesn't exist, isn't an email, or is already verified,
+    /// then `None` is returned.
+    pub async fn request_verification(
+        ctx: &ServiceContext<'_>,
+        RequestContactVerification {
+            user_id,
+            contact_id,
+        }: RequestContactVerification,
+    ) -> Result<Option<ContactVerificationOutput>> {
+        info!("Requesting verification of recovery contact ID {contact_id} for user ID {user_id}");
+
+        let txn = ctx.transaction();
+        let contact = RecoveryContact::find()
+            .filter(
+                Condition::all()
+                    .add(recovery_contact::Column::ContactId.eq(contact_id))
+                    .add(recovery_contact::Column::UserId.eq(user_id))
+                    .add(recovery_contact::Column::VerifiedAt.is_null())
+                    .add(recovery_contact::Column::DeletedAt.is_null()),
+            )
+            .one(txn)
+            .await?;
+
+        let email = match contact.and_then(|contact| contact.email) {
+            Some(email) => email,
+            None => {
+                debug!("No unverified backup email with this ID");
+                return Ok(None);
+            }
+        };
+
+        let token = new_token(ctx.config().session_token_length);
+        let model = recovery_contact::ActiveModel {
+            contact_id: Set(contact_id),
+            verification_token_hash: Set(Some(hash_token(&token))),
+            ..Default::default()
+        };
+        model.update(txn).await?;
+
+        Ok(Some(ContactVerificationOutput {
+            contact_id,
+            email,
+            token,
+        }))
+    }
+
+    /// Verifies a backup email address, using the token sent to it.
+    pub async fn verify_contact(ctx: &ServiceContext<'_>, token: &str) -> Result<()> {
+        let txn = ctx.transaction();
+        let contact = RecoveryContact::find()
+            .filter(
+                Condition::all()
+                    .add(
+                        recovery_contact::Column::VerificationTokenHash
+                            .eq(hash_token(token)),
+                    )
+                    .add(recovery_contact::Column::DeletedAt.is_null()),
+            )
+            .one(txn)
+            .await?
+            .ok_or(Error::RecoveryContactNotFound)?;
+
+        info!(
+            "Verified backup email for recovery contact ID {} of user ID {}",
+            contact.contact_id, contact.user_id,
+        );
+
+        let model = recovery_contact::ActiveModel {
+            contact_id: Set(contact.contact_id),
+            verified_at: Set(Some(now())),
+            verification_token_hash: Set(None),
+            ..Default::default()
+        };
+        model.update(txn).await?;
+        Ok(())
+    }
+
+    pub async fn remove_contact(
+        ctx: &ServiceContext<'_>,
+        user_id: i64,
+        contact_id: i64,
+    ) -> Result<()> {
+        info!("Removing recovery contact ID {contact_id} for user ID {user_id}");
+
+        let txn = ctx.transaction();
+        let contact = RecoveryContact::find()
+            .filter(
+                Condition::all()
+                    .add(recovery_contact::Column::ContactId.eq(contact_id))
+                    .add(recovery_contact::Column::UserId.eq(user_id))
+                    .add(recovery_contact::Column::DeletedAt.is_null()),
+            )
+            .one(txn)
+            .await?;
+
+        if contact.is_none() {
+            return Err(Error::RecoveryContactNotFound);
+        }
+
+        let model = recovery_contact::ActiveModel {
+            contact_id: Set(contact_id),
+            deleted_at: Set(Some(now())),
+            ..Default::default()
+        };
+        model.update(txn).await?;
+        Ok(())
+    }
+
+    pub async fn get_contacts(
+        ctx: &ServiceContext<'_>,
+        user_id: i64,
+    ) -> Result<Vec<RecoveryContactModel>> {
+        let txn = ctx.transaction();
+        let contacts = RecoveryContact::find()
+            .filter(
+                Condition::all()
+                    .add(recovery_contact::Column::UserId.eq(user_id))
+                    .add(recovery_contact::Column::DeletedAt.is_null()),
+            )
+            .order_by_asc(recovery_contact::Column::ContactId)
+            .all(txn)
+            .await?;
+
+        Ok(contacts)
+    }
+
+    /// Requests recovery of an account, notifying the owner and all its contacts.
+    ///
+    /// If there is no such user, they do not have enough recovery contacts,
+    /// or a recovery is already in progress, then `None` is returned. Callers
+    /// must respond the same way in every case, so that this does not reveal
+    /// anything about the account.
+    pub async fn start(
+        ctx: &ServiceContext<'_>,
+        StartAccountRecovery { name_or_email }: StartAccountRecovery,
+    ) -> Result<Option<StartAccountRecoveryOutput>> {
+        info!("Requesting account recovery for '{name_or_email}'");
+
+        let txn = ctx.transaction();
+        let config = ctx.config();
+        let user = User::find()
+            .filter(
+                Condition::all()
+                    .add(
+                        Condition::any()
+                            .add(user::Column::Name.eq(name_or_email.as_str()))
+                            .add(user::Column::Slug.eq(name_or_email.as_str()))
+                            .add(user::Column::Email.eq(name_or_email.as_str())),
+                    )
+                    .add(user::Column::DeletedAt.is_null()),
+            )
+            .one(txn)
+            .await?;
+
+        let user = match user {
+            Some(user) => user,
+            None => {
+                debug!("No user matches, not starting recovery");
+                return Ok(None);
+            }
+        };
+
+        let contacts = Self::get_contacts(ctx, user.user_id)
+            .await?
+            .into_iter()
+            .filter(|contact| contact.verified_at.is_some())
+            .collect::<Vec<_>>();
+
+        if contacts.len() < usize::from(config.recovery_required_confirmations) {
+            debug!("User ID {} has too few recovery contacts", user.user_id);
+            return Ok(None);
+        }
+
+        let active = AccountRecovery::find()
+            .filter(
+                active_condition().add(account_recovery::Column::UserId.eq(user.user_id)),
+            )
+            .one(txn)
+            .await?;
+
+        if active.is_some() {
+            warn!(
+                "User ID {} already has a recovery in progress",
+                user.user_id
+            );
+            return Ok(None);
+        }
+
+        // Create recovery
+        let recovery_token = new_token(config.session_token_length);
+        let cancel_token = new_token(config.session_token_length);
+        let created_at = now();
+        let ready_at = created_at + config.recovery_delay;
+        let expires_at = ready_at + config.recovery_expiry;
+        let model = account_recovery::ActiveModel {
+            user_id: Set(user.user_id),
+            token_hash: Set(hash_token(&recovery_token)),
+            cancel_token_hash: Set(hash_token(&cancel_token)),
+            created_at: Set(created_at),
+            ready_at: Set(ready_at),
+            expires_at: Set(expires_at),
+            required_confirmations: Set(i16::try_from(
+                config.recovery_required_confirmations,
+            )
+            .unwrap_or(i16::MAX)),
+            ..Default::default()
+        };
+        let recovery = model.insert(txn).await?;
+
+        // Add a pending confirmation for each contact
+        let mut backup_emails = Vec::new();
+        let mut contact_user_ids = Vec::new();
+        for contact in contacts {
+            let token_hash = match contact.email {
+                Some(email) => {
+                    let token = new_token(config.session_token_length);
+                    let token_hash = hash_token(&token);
+                    backup_emails.push(BackupEmailConfirmation { email, token });
+                    Some(token_hash)
+                }
+                None => {
+                    contact_user_ids.extend(contact.contact_user_id);
+                    None
+                }
+            };
+
+            let model = account_recovery_confirmation::ActiveModel {
+                recovery_id: Set(recovery.recovery_id),
+                contact_id: Set(contact.contact_id),
+                token_hash: Set(token_hash),
+                confirmed_at: Set(None),
+            };
+            model.insert(txn).await?;
+        }
+
+        // Notify the owner and trusted contacts
+        let build_args = || {
+            let mut args = FluentArgs::new();
+            args.set("user", fluent_str!(user.name));
+            args.set("ready", date_wikitext(ready_at));
+            args.set("required", recovery.required_confirmations);
+            args
+        };
+
+        Self::notify(ctx, &user, "account-recovery-started", build_args).await;
+        for contact_user_id in contact_user_ids {
+            let contact = UserService::get(ctx, Reference::Id(contact_user_id)).await?;
+            if contact.deleted_at.is_none() {
+                Self::notify(ctx, &contact, "account-recovery-contact", build_args).await;
+            }
+        }
+
+        Ok(Some(StartAccountRecoveryOutput {
+            recovery_id: recovery.recovery_id,
+            user_id: user.user_id,
+            recovery_token,
+            email: user.email,
+            cancel_token,
+            backup_emails,
+            ready_at,
+            expires_at,
+        }))
+    }
+
+    /// Confirms a recovery on behalf of a backup email address.
+    pub async fn confirm_email(ctx: &ServiceContext<'_>, token: &str) -> Result<()> {
+        let txn = ctx.transaction();
+        let confirmation = AccountRecoveryConfirmation::find()
+            .filter(
+                account_recovery_confirmation::Column::TokenHash.eq(hash_token(token)),
+            )
+            .one(txn)
+            .await?
+            .ok_or(Error::AccountRecoveryNotFound)?;
+
+        let recovery = Self::get_active(
+            ctx,
+            account_recovery::Column::RecoveryId.eq(confirmation.recovery_id),
+        )
+        .await?;
+
+        info!(
+            "Backup email confirmed recovery ID {} for user ID {}",
+            recovery.recovery_id, recovery.user_id,
+        );
+
+        Self::confirm(ctx, recovery.recovery_id, confirmation.contact_id).await
+    }
+
+    /// Confirms a recovery on behalf of a trusted contact.
+    pub async fn confirm_contact(
+        ctx: &ServiceContext<'_>,
+        ConfirmAccountRecovery {
+            recovery_id,
+            user_id,
+            ..
+        }: ConfirmAccountRecovery,
+    ) -> Result<()> {
+        let txn = ctx.transaction();
+        let recovery =
+            Self::get_active(ctx, account_recovery::Column::RecoveryId.eq(recovery_id))
+                .await?;
+
+        let contact = RecoveryContact::find()
+            .filter(
+                Condition::all()
+                    .add(recovery_contact::Column::UserId.eq(recovery.user_id))
+                    .add(recovery_contact::Column::ContactUserId.eq(user_id))
+                    .add(recovery_contact::Column::DeletedAt.is_null()),
+            )
+            .one(txn)
+            .await?
+            .ok_or(Error::RecoveryContactNotFound)?;
+
+        info!(
+            "Trusted contact user ID {user_id} confirmed recovery ID {recovery_id} for user ID {}",
+            recovery.user_id,
+        );
+
+        Self::confirm(ctx, recovery_id, contact.contact_id).await
+    }
+
+    async fn confirm(
+        ctx: &ServiceContext<'_>,
+        recovery_id: i64,
+        contact_id: i64,
+    ) -> Result<()> {
+        let txn = ctx.transaction();
+        let confirmation =
+            AccountRecoveryConfirmation::find_by_id((recovery_id, contact_id))
+                .one(txn)
+                .await?
+                .ok_or(Error::RecoveryContactNotFound)?;
+
+        // Confirming again does nothing
+        if confirmation.confirmed_at.is_none() {
+            let model = account_recovery_confirmation::ActiveModel {
+                recovery_id: Set(recovery_id),
+                contact_id: Set(contact_id),
+                confirmed_at: Set(Some(now())),
+                ..Default::default()
+            };
+            model.update(txn).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Cancels a recovery, using the token sent to the account's own email.
+    pub async fn cancel(ctx: &ServiceContext<'_>, cancel_token: &str) -> Result<()> {
+        let txn = ctx.transaction();
+        let recovery = Self::get_active(
+            ctx,
+            account_recovery::Column::CancelTokenHash.eq(hash_token(cancel_token)),
+        )
+        .await?;
+
+        info!(
+            "Cancelling recovery ID {} for user ID {}",
+            recovery.recovery_id, recovery.user_id,
+        );
+
+        let model = account_recovery::ActiveModel {
+            recovery_id: Set(recovery.recovery_id),
+            cancelled_at: Set(Some(now())),
+            ..Default::default()
+        };
+        model.update(txn).await?;
+        Ok(())
+    }
+
+    /// Gets the progress of a recovery, for the requester.
+    pub async fn status(
+        ctx: &ServiceContext<'_>,
+        recovery_token: &str,
+    ) -> Result<AccountRecoveryStatus> {
+        let recovery = Self::get_active(
+            ctx,
+            account_recovery::Column::TokenHash.eq(hash_token(recovery_token)),
+        )
+        .await?;
+
+        let confirmations = Self::count_confirmations(ctx, recovery.recovery_id).await?;
+        let ready = is_ready(&recovery, confirmations);
+
+        Ok(AccountRecoveryStatus {
+            recovery_id: recovery.recovery_id,
+            ready_at: recovery.ready_at,
+            expires_at: recovery.expires_at,
+            confirmations,
+            required_confirmations: recovery.required_confirmations,
+            ready,
+        })
+    }
+
+    /// Completes a recovery, resetting the account's password.
+    ///
+    /// MFA is turned off and all sessions are logged out,
+    /// so the account can be logged into with the new password.
+    pub async fn complete(
+        ctx: &ServiceContext<'_>,
+        CompleteAccountRecovery {
+            recovery_token,
+            new_password,
+        }: CompleteAccountRecovery,
+    ) -> Result<()> {
+        let txn = ctx.transaction();
+        let recovery = Self::get_active(
+            ctx,
+            account_recovery::Column::TokenHash.eq(hash_token(&recovery_token)),
+        )
+        .await?;
+
+        let confirmations = Self::count_confirmations(ctx, recovery.recovery_id).await?;
+        if !is_ready(&recovery, confirmations) {
+            error!(
+                "Recovery ID {} is not ready ({confirmations} of {} confirmations)",
+                recovery.recovery_id, recovery.required_confirmations,
+            );
+            return Err(Error::AccountRecoveryNotReady);
+        }
+
+        info!(
+            "Completing recovery ID {} for user ID {}",
+            recovery.recovery_id, recovery.user_id,
+        );
+
+        let user = UserService::update(
+            ctx,
+            Reference::Id(recovery.user_id),
+            UpdateUserBody {
+                password: ProvidedValue::Set(new_password),
+                ..Default::default()
+            },
+        )
+        .await?;
+
+        MfaService::disable(ctx, user.user_id).await?;
+        SessionService::invalidate_all(ctx, user.user_id).await?;
+
+        let model = account_recovery::ActiveModel {
+            recovery_id: Set(recovery.recovery_id),
+            completed_at: Set(Some(now())),
+            ..Default::default()
+        };
+        model.update(txn).await?;
+
+        Self::notify(ctx, &user, "account-recovery-completed", FluentArgs::new).await;
+        Ok(())
+    }
+
+    async fn get_active(
+        ctx: &ServiceContext<'_>,
+        condition: SimpleExpr,
+    ) -> Result<AccountRecoveryModel> {
+        let txn = ctx.transaction();
+        AccountRecovery::find()
+            .filter(active_condition().add(condition))
+            .one(txn)
+            .await?
+            .ok_or(Error::AccountRecoveryNotFound)
+    }
+
+    async fn count_confirmations(
+        ctx: &ServiceContext<'_>,
+        recovery_id: i64,
+    ) -> Result<u64> {
+        let txn = ctx.transaction();
+        let count = AccountRecoveryConfirmation::find()
+            .filter(
+                Condition::all()
+                    .add(
+                        account_recovery_confirmation::Column::RecoveryId.eq(recovery_id),
+                    )
+                    .add(
+                        account_recovery_confirmation::Column::ConfirmedAt.is_not_null(),
+                    ),
+            )
+            .count(txn)
+            .await?;
+
+        Ok(count)
+    }
+
+    /// Sends a notice about a recovery to the given user.
+    ///
+    /// Recipients are also told by email, so failing to send
+    /// one is logged rather than stopping the recovery.
+    async fn notify<'a, F>(
+        ctx: &ServiceContext<'_>,
+        recipient: &UserModel,
+        message_key: &str,
+        build_args: F,
+    ) where
+        F: FnOnce() -> FluentArgs<'a>,
+    {
+        if let Err(error) =
+            Self::send_notice(ctx, recipient, message_key, build_args).await
+        {
+            error!(
+                "Unable to send account recovery notice to user ID {}: {error}",
+                recipient.user_id,
+            );
+        }
+    }
+
+    async fn send_notice<'a, F>(
+        ctx: &ServiceContext<'_>,
+        recipient: &UserModel,
+        message_key: &str,
+        build_args: F,
+    ) -> Result<()>
+    where
+        F: FnOnce() -> FluentArgs<'a>,
+    {
+        let locale = recipient
+            .locales
+            .first()
+            .map(String::as_str)
+            .unwrap_or("en");
+        let locales = [locale.parse::<LanguageIdentifier>()?];
+        let args = build_args();
+
+        let localization = ctx.localization();
+        let subject =
+            localization.translate(&locales, &format!("{message_key}-subject"), &args)?;
+        let wikitext =
+            localization.translate(&locales, &format!("{message_key}-body"), &args)?;
+
+        let draft = MessageService::create_draft(
+            ctx,
+            CreateMessageDraft {
+                user_id: SYSTEM_USER_ID,
+                recipients: vec![recipient.user_id],
+                carbon_copy: vec![],
+                blind_carbon_copy: vec![],
+                locale: str!(locale),
+                subject: subject.to_string(),
+                wikitext: wikitext.to_string(),
+                reply_to: None,
+                forwarded_from: None,
+            },
+        )
+        .await?;
+
+        MessageService::send(ctx, &draft.external_id).await?;
+        Ok(())
+    }
+}
+
+/// Condition for recoveries which have not finished, been cancelled, or lapsed.
+fn active_condition() -> Condition {
+    Condition::all()
+        .add(account_recovery::Column::CompletedAt.is_null())
+        .add(account_recovery::Column::CancelledAt.is_null())
+        .add(account_recovery::Column::ExpiresAt.gt(now()))
+}
+
+fn is_ready(recovery: &AccountRecoveryModel, confirmations: u64) -> bool {
+    now() >= recovery.ready_at
+        && confirmations >= u64::try_from(recovery.required_confirmations).unwrap_or(0)
+}
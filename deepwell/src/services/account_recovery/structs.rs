@@ -0,0 +1,118 @@
+/*
+ * services/account_recovery/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::web::Reference;
+use time::OffsetDateTime;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct AddRecoveryContact<'a> {
+    pub user_id: i64,
+    pub session_token: String,
+
+    /// A backup email address. Exactly one of this and `contact_user` must be set.
+    #[serde(default)]
+    pub email: Option<String>,
+
+    /// A trusted contact.
+    #[serde(default)]
+    pub contact_user: Option<Reference<'a>>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct RemoveRecoveryContact {
+    pub user_id: i64,
+    pub session_token: String,
+    pub contact_id: i64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetRecoveryContacts {
+    pub user_id: i64,
+    pub session_token: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct RequestContactVerification {
+    pub user_id: i64,
+    pub contact_id: i64,
+}
+
+/// A token to verify a backup email address, which the caller must email to it.
+#[derive(Serialize, Debug, Clone)]
+pub struct ContactVerificationOutput {
+    pub contact_id: i64,
+    pub email: String,
+    pub token: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct StartAccountRecovery {
+    pub name_or_email: String,
+}
+
+/// A newly requested account recovery.
+///
+/// The caller must give the `recovery_token` to the requester, email the
+/// `cancel_token` to the account's own address, and email each backup
+/// address its confirmation token.
+#[derive(Serialize, Debug, Clone)]
+pub struct StartAccountRecoveryOutput {
+    pub recovery_id: i64,
+    pub user_id: i64,
+    pub recovery_token: String,
+    pub email: String,
+    pub cancel_token: String,
+    pub backup_emails: Vec<BackupEmailConfirmation>,
+    pub ready_at: OffsetDateTime,
+    pub expires_at: OffsetDateTime,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct BackupEmailConfirmation {
+    pub email: String,
+    pub token: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ConfirmAccountRecovery {
+    pub recovery_id: i64,
+
+    /// The trusted contact confirming the recovery.
+    pub user_id: i64,
+    pub session_token: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CompleteAccountRecovery {
+    pub recovery_token: String,
+    pub new_password: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct AccountRecoveryStatus {
+    pub recovery_id: i64,
+    pub ready_at: OffsetDateTime,
+    pub expires_at: OffsetDateTime,
+    pub confirmations: u64,
+    pub required_confirmations: i16,
+
+    /// Whether the recovery can be completed now.
+    pub ready: bool,
+}
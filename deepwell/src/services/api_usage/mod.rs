@@ -0,0 +1,41 @@
+/*
+ * services/api_usage/mod.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The API usage service, which tracks how much each user calls the API on each site.
+//!
+//! Every call which names both a site and a user is counted as it is made,
+//! along with whether it failed and how many bytes went in each direction.
+//! These counters are kept in Redis to avoid a database write per call,
+//! and a recurring job adds them into daily totals in the database.
+//!
+//! Site administrators can view these totals to find bots which are making
+//! excessive requests. They can then set a throttle, limiting how many calls
+//! that user may make on the site per minute, or ban them from the site.
+
+mod prelude {
+    pub use super::super::prelude::*;
+    pub use super::structs::*;
+}
+
+mod service;
+mod structs;
+
+pub use self::service::ApiUsageService;
+pub use self::structs::*;
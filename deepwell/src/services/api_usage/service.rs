@@ -0,0 +1,526 @@
+/*
+ * services/api_usage/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::api_throttle::{
+    self, Entity as ApiThrottleEntity, Model as ApiThrottleModel,
+};
+use crate::models::api_usage::{self, Entity as ApiUsage, Model as ApiUsageModel};
+use crate::models::user::{self, Entity as User};
+use crate::redis::window_counter;
+use crate::services::audit::CreateAuditEntry;
+use crate::services::relation::{CreateSiteBan, SiteBanData};
+use crate::services::{AuditService, RelationService, SiteService, UserService};
+use sea_orm::{DatabaseBackend, Statement};
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+use time::Date;
+
+/// Redis set containing the keys of all counters which haven't been flushed yet.
+const PENDING_KEY: &str = "api-usage:pending";
+
+/// How many counters to take from Redis at a time while flushing.
+const FLUSH_BATCH_SIZE: usize = 100;
+
+/// Adds counts to a day's usage total, skipping any for sites or users which don't exist.
+///
+/// The IDs come from call parameters, so may not refer to anything,
+/// and this avoids a foreign key violation failing the whole flush.
+const UPSERT_USAGE_SQL: &str = r#"
+INSERT INTO api_usage (
+    site_id, user_id, method, usage_date,
+    call_count, error_count, request_bytes, response_bytes
+)
+SELECT $1, $2, $3, $4, $5, $6, $7, $8
+WHERE EXISTS (SELECT 1 FROM site WHERE site_id = $1)
+AND EXISTS (SELECT 1 FROM "user" WHERE user_id = $2)
+ON CONFLICT (site_id, user_id, method, usage_date) DO UPDATE SET
+    call_count = api_usage.call_count + excluded.call_count,
+    error_count = api_usage.error_count + excluded.error_count,
+    request_bytes = api_usage.request_bytes + excluded.request_bytes,
+    response_bytes = api_usage.response_bytes + excluded.response_bytes
+"#;
+
+#[derive(Debug)]
+pub struct ApiUsageService;
+
+impl ApiUsageService {
+    /// Rejects the call if its user has been throttled on the site and is over the limit.
+    ///
+    /// Calls are counted in a fixed one-minute window, which expires
+    /// on its own rather than needing to be cleaned up. Dry runs are
+    /// checked against the limit, but not counted towards it.
+    pub async fn check_throttle(ctx: &ServiceContext<'_>, call: &ApiCall) -> Result<()> {
+        let (site_id, user_id) = match call.ids() {
+            Some(ids) => ids,
+            None => return Ok(()),
+        };
+
+        let txn = ctx.transaction();
+        let throttle = match ApiThrottleEntity::find_by_id((site_id, user_id))
+            .one(txn)
+            .await?
+        {
+            Some(throttle) => throttle,
+            None => return Ok(()),
+        };
+
+        let key = format!("api-throttle:{site_id}:{user_id}");
+        let (count,): (u64,) = ctx
+            .until_deadline(
+                window_counter(&key, 60, ctx.check_only()).query_async(&mut ctx.redis()),
+            )
+            .await?;
+
+        if count > u64::try_from(throttle.requests_per_minute).unwrap_or(0) {
+            warn!(
+                "User ID {user_id} is over their API throttle in site ID {site_id} ({} per minute)",
+                throttle.requests_per_minute,
+            );
+            return Err(Error::ApiThrottled);
+        }
+
+        Ok(())
+    }
+
+    /// Counts a finished call towards its user's usage of the site.
+    ///
+    /// This never fails, since usage tracking should not affect the call itself.
+    /// Any problems updating the counters are logged and otherwise ignored.
+    pub async fn record<T: Serialize>(
+        ctx: &ServiceContext<'_>,
+        call: &ApiCall,
+        result: &Result<T>,
+    ) {
+        if !ctx.config().api_usage_enabled {
+            return;
+        }
+
+        if ctx.check_only() {
+            debug!("Not recording API usage, this is a dry run");
+            return;
+        }
+
+        let (site_id, user_id) = match call.ids() {
+            Some(ids) => ids,
+            None => return,
+        };
+
+        let (errors, response_bytes) = match result {
+            Ok(output) => (0, serde_json::to_vec(output).map_or(0, |bytes| bytes.len())),
+            Err(_) => (1, 0),
+        };
+
+        let method = call.method;
+        let key = format!(
+            "api-usage:{site_id}:{user_id}:{}",
+            now().date().to_julian_day(),
+        );
+
+        let result: StdResult<(), redis::RedisError> = redis::pipe()
+            .atomic()
+            .hincr(&key, format!("{method}:calls"), 1)
+            .ignore()
+            .hincr(&key, format!("{method}:errors"), errors)
+            .ignore()
+            .hincr(&key, format!("{method}:request-bytes"), call.request_bytes)
+            .ignore()
+            .hincr(&key, format!("{method}:response-bytes"), response_bytes)
+            .ignore()
+            .sadd(PENDING_KEY, &key)
+            .ignore()
+            .query_async(&mut ctx.redis())
+            .await;
+
+        if let Err(error) = result {
+            warn!(
+                "Unable to record API usage for '{method}' in site ID {site_id}: {error}"
+            );
+        }
+    }
+
+    /// Adds all counters in Redis to the daily totals, and prunes expired totals.
+    ///
+    /// Counters are removed from Redis as they are read, so if this transaction
+    /// fails afterwards, those calls go uncounted. This is accepted in exchange
+    /// for not needing any coordination between the API and this job.
+    pub async fn flush(ctx: &ServiceContext<'_>) -> Result<()> {
+        let txn = ctx.transaction();
+        let mut redis = ctx.redis();
+
+        loop {
+            let keys: Vec<String> = redis::cmd("SPOP")
+                .arg(PENDING_KEY)
+                .arg(FLUSH_BATCH_SIZE)
+                .query_async(&mut redis)
+                .await?;
+
+            if keys.is_empty() {
+                break;
+            }
+
+            debug!("Flushing {} API usage counters", keys.len());
+            for key in keys {
+                let (site_id, user_id, date) = match parse_usage_key(&key) {
+                    Some(parts) => parts,
+                    None => {
+                        warn!("Skipping invalid API usage key '{key}'");
+                        continue;
+                    }
+                };
+
+                let (fields,): (HashMap<String, i64>,) = redis::pipe()
+                    .atomic()
+                    .hgetall(&key)
+                    .del(&key)
+                    .ignore()
+                    .query_async(&mut redis)
+                    .await?;
+
+                for (method, totals) in group_counters(fields) {
+                    txn.execute(Statement::from_sql_and_values(
+                        DatabaseBackend::Postgres,
+                        UPSERT_USAGE_SQL,
+                        [
+                            site_id.into(),
+                            user_id.into(),
+                            method.into(),
+                            date.into(),
+                            totals[0].into(),
+                            totals[1].into(),
+                            totals[2].into(),
+                            totals[3].into(),
+                        ],
+                    ))
+                    .await?;
+                }
+            }
+        }
+
+        let cutoff = (now() - ctx.config().api_usage_retention).date();
+        let DeleteResult { rows_affected, .. } = ApiUsage::delete_many()
+            .filter(api_usage::Column::UsageDate.lt(cutoff))
+            .exec(txn)
+            .await?;
+
+        debug!("Pruned {rows_affected} expired API usage totals");
+        Ok(())
+    }
+
+    /// Gets each user's API usage on a site over a range of days, inclusive.
+    pub async fn get_report(
+        ctx: &ServiceContext<'_>,
+        GetApiUsage {
+            site_id,
+            start_date,
+            end_date,
+        }: GetApiUsage,
+    ) -> Result<ApiUsageReport> {
+        let days = (end_date - start_date).whole_days() + 1;
+        let maximum_days = i64::from(ctx.config().api_usage_maximum_report_days);
+        if days < 1 || days > maximum_days {
+            error!("Invalid API usage report range: {start_date} to {end_date}");
+            return Err(Error::ApiUsageRangeInvalid);
+        }
+
+        info!("Getting API usage for site ID {site_id} from {start_date} to {end_date}");
+        let txn = ctx.transaction();
+        let rows = ApiUsage::find()
+            .filter(
+                Condition::all()
+                    .add(api_usage::Column::SiteId.eq(site_id))
+                    .add(api_usage::Column::UsageDate.between(start_date, end_date)),
+            )
+            .all(txn)
+            .await?;
+
+        let mut rows_by_user: BTreeMap<i64, Vec<ApiUsageModel>> = BTreeMap::new();
+        for row in rows {
+            rows_by_user.entry(row.user_id).or_default().push(row);
+        }
+
+        let (users, throttles) = try_join!(
+            User::find()
+                .filter(user::Column::UserId.is_in(rows_by_user.keys().copied()))
+                .all(txn),
+            ApiThrottleEntity::find()
+                .filter(api_throttle::Column::SiteId.eq(site_id))
+                .all(txn),
+        )?;
+
+        let throttles: HashMap<i64, i32> = throttles
+            .into_iter()
+            .map(|throttle| (throttle.user_id, throttle.requests_per_minute))
+            .collect();
+
+        let mut users: Vec<ApiUserUsage> = users
+            .into_iter()
+            .map(|user| {
+                let rows = rows_by_user.remove(&user.user_id).unwrap_or_default();
+                let throttle = throttles.get(&user.user_id).copied();
+                build_user_usage(user, throttle, rows)
+            })
+            .collect();
+
+        users.sort_by_key(|user| Reverse(user.call_count));
+        Ok(ApiUsageReport {
+            site_id,
+            start_date,
+            end_date,
+            users,
+        })
+    }
+
+    /// Sets or removes the limit on how often a user may call the API on a site.
+    pub async fn set_throttle(
+        ctx: &ServiceContext<'_>,
+        SetApiThrottle {
+            site_id,
+            user_id,
+            acting_user_id,
+            requests_per_minute,
+        }: SetApiThrottle,
+    ) -> Result<Option<ApiThrottle>> {
+        if matches!(requests_per_minute, Some(limit) if limit < 1) {
+            error!("Cannot set API throttle of less than one request per minute");
+            return Err(Error::ApiThrottleInvalid);
+        }
+
+        // Ensure the site and user exist
+        SiteService::get(ctx, Reference::Id(site_id)).await?;
+        UserService::get(ctx, Reference::Id(user_id)).await?;
+
+        let txn = ctx.transaction();
+        let existing = ApiThrottleEntity::find_by_id((site_id, user_id))
+            .one(txn)
+            .await?;
+
+        let output = match requests_per_minute {
+            None => {
+                info!("Removing API throttle for user ID {user_id} in site ID {site_id}");
+                if existing.is_some() {
+                    ApiThrottleEntity::delete_by_id((site_id, user_id))
+                        .exec(txn)
+                        .await?;
+                }
+
+                None
+            }
+            Some(requests_per_minute) => {
+                info!(
+                    "Throttling user ID {user_id} in site ID {site_id} to {requests_per_minute} API calls per minute",
+                );
+
+                let model = api_throttle::ActiveModel {
+                    site_id: Set(site_id),
+                    user_id: Set(user_id),
+                    created_at: Set(now()),
+                    created_by: Set(acting_user_id),
+                    requests_per_minute: Set(requests_per_minute),
+                };
+
+                let model = if existing.is_some() {
+                    model.update(txn).await?
+                } else {
+                    model.insert(txn).await?
+                };
+
+                Some(build_throttle(model))
+            }
+        };
+
+        AuditService::record(
+            ctx,
+            CreateAuditEntry {
+                actor_id: acting_user_id,
+                subject_id: user_id,
+                site_id: Some(site_id),
+                action: "api.throttle",
+                data: serde_json::json!({
+                    "requests_per_minute": requests_per_minute,
+                }),
+            },
+        )
+        .await?;
+
+        Ok(output)
+    }
+
+    /// Revokes a user's access to a site, by banning them from it.
+    ///
+    /// Since the user may be a bot used on other sites, their sessions
+    /// are left alone. Any throttle is kept in case the ban is lifted.
+    pub async fn revoke(
+        ctx: &ServiceContext<'_>,
+        RevokeApiAccess {
+            site_id,
+            user_id,
+            acting_user_id,
+            reason,
+        }: RevokeApiAccess,
+    ) -> Result<()> {
+        info!("Revoking API access for user ID {user_id} in site ID {site_id}");
+
+        RelationService::create_site_ban(
+            ctx,
+            CreateSiteBan {
+                site_id,
+                user_id,
+                created_by: acting_user_id,
+                metadata: SiteBanData {
+                    banned_until: None,
+                    reason: reason.clone(),
+                },
+            },
+        )
+        .await?;
+
+        AuditService::record(
+            ctx,
+            CreateAuditEntry {
+                actor_id: acting_user_id,
+                subject_id: user_id,
+                site_id: Some(site_id),
+                action: "api.revoke",
+                data: serde_json::json!({ "reason": reason }),
+            },
+        )
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// Parses a counter key, in the form `api-usage:{site_id}:{user_id}:{julian_day}`.
+fn parse_usage_key(key: &str) -> Option<(i64, i64, Date)> {
+    let mut parts = key.strip_prefix("api-usage:")?.split(':');
+    let site_id = parts.next()?.parse().ok()?;
+    let user_id = parts.next()?.parse().ok()?;
+    let julian_day = parts.next()?.parse().ok()?;
+    let date = Date::from_julian_day(julian_day).ok()?;
+    Some((site_id, user_id, date))
+}
+
+/// Groups counter hash fields, in the form `{method}:{counter}`, by method.
+///
+/// The totals are, in order: calls, errors, request bytes, and response bytes.
+fn group_counters(fields: HashMap<String, i64>) -> HashMap<String, [i64; 4]> {
+    let mut methods: HashMap<String, [i64; 4]> = HashMap::new();
+
+    for (field, value) in fields {
+        let (method, counter) = match field.rsplit_once(':') {
+            Some(parts) => parts,
+            None => continue,
+        };
+
+        let index = match counter {
+            "calls" => 0,
+            "errors" => 1,
+            "request-bytes" => 2,
+            "response-bytes" => 3,
+            _ => continue,
+        };
+
+        methods.entry(str!(method)).or_default()[index] += value;
+    }
+
+    methods
+}
+
+fn build_user_usage(
+    user: user::Model,
+    throttle: Option<i32>,
+    rows: Vec<ApiUsageModel>,
+) -> ApiUserUsage {
+    let mut methods: BTreeMap<String, ApiMethodUsage> = BTreeMap::new();
+    let mut days: BTreeMap<Date, ApiDailyUsage> = BTreeMap::new();
+
+    for row in &rows {
+        let method =
+            methods
+                .entry(row.method.clone())
+                .or_insert_with(|| ApiMethodUsage {
+                    method: row.method.clone(),
+                    call_count: 0,
+                    error_count: 0,
+                    request_bytes: 0,
+                    response_bytes: 0,
+                });
+
+        method.call_count += row.call_count;
+        method.error_count += row.error_count;
+        method.request_bytes += row.request_bytes;
+        method.response_bytes += row.response_bytes;
+
+        let day = days.entry(row.usage_date).or_insert(ApiDailyUsage {
+            date: row.usage_date,
+            call_count: 0,
+            error_count: 0,
+        });
+
+        day.call_count += row.call_count;
+        day.error_count += row.error_count;
+    }
+
+    let mut methods: Vec<ApiMethodUsage> = methods.into_values().collect();
+    methods.sort_by_key(|method| Reverse(method.call_count));
+
+    let call_count = rows.iter().map(|row| row.call_count).sum();
+    let error_count = rows.iter().map(|row| row.error_count).sum();
+    let error_rate = if call_count > 0 {
+        error_count as f64 / call_count as f64
+    } else {
+        0.0
+    };
+
+    ApiUserUsage {
+        user_id: user.user_id,
+        user_name: user.name,
+        user_slug: user.slug,
+        user_type: user.user_type,
+        call_count,
+        error_count,
+        error_rate,
+        request_bytes: rows.iter().map(|row| row.request_bytes).sum(),
+        response_bytes: rows.iter().map(|row| row.response_bytes).sum(),
+        throttle,
+        methods,
+        days: days.into_values().collect(),
+    }
+}
+
+fn build_throttle(
+    ApiThrottleModel {
+        site_id,
+        user_id,
+        created_at,
+        created_by,
+        requests_per_minute,
+    }: ApiThrottleModel,
+) -> ApiThrottle {
+    ApiThrottle {
+        site_id,
+        user_id,
+        created_at,
+        created_by,
+        requests_per_minute,
+    }
+}
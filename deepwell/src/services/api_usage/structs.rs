@@ -0,0 +1,149 @@
+/*
+ * services/api_usage/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::models::sea_orm_active_enums::UserType;
+use jsonrpsee::types::params::Params;
+use time::{Date, OffsetDateTime};
+
+/// An API call being made, with the details needed to track it.
+#[derive(Debug, Clone)]
+pub struct ApiCall {
+    pub method: &'static str,
+    pub site_id: Option<i64>,
+    pub user_id: Option<i64>,
+    pub request_bytes: usize,
+}
+
+impl ApiCall {
+    /// Gets the site and user for this call from its parameters, if present.
+    ///
+    /// This works with any endpoint which takes an object of parameters
+    /// using the usual `site_id` field, and either `acting_user_id` or
+    /// `user_id` for the user making the call.
+    pub fn from_params(method: &'static str, params: &Params) -> Self {
+        #[derive(Deserialize, Debug)]
+        struct CallIds {
+            site_id: Option<i64>,
+            acting_user_id: Option<i64>,
+            user_id: Option<i64>,
+        }
+
+        let (site_id, user_id) = match params.parse::<CallIds>() {
+            Ok(ids) => (ids.site_id, ids.acting_user_id.or(ids.user_id)),
+            Err(_) => (None, None),
+        };
+
+        ApiCall {
+            method,
+            site_id,
+            user_id,
+            request_bytes: params.as_str().map(str::len).unwrap_or(0),
+        }
+    }
+
+    /// Returns the site and user IDs, if this call can be tracked.
+    #[inline]
+    pub fn ids(&self) -> Option<(i64, i64)> {
+        self.site_id.zip(self.user_id)
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetApiUsage {
+    pub site_id: i64,
+    pub start_date: Date,
+    pub end_date: Date,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ApiUsageReport {
+    pub site_id: i64,
+    pub start_date: Date,
+    pub end_date: Date,
+
+    /// Each user who called the API in this period, most calls first.
+    pub users: Vec<ApiUserUsage>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ApiUserUsage {
+    pub user_id: i64,
+    pub user_name: String,
+    pub user_slug: String,
+    pub user_type: UserType,
+    pub call_count: i64,
+    pub error_count: i64,
+    pub error_rate: f64,
+    pub request_bytes: i64,
+    pub response_bytes: i64,
+
+    /// The current limit on this user's calls per minute, if any.
+    pub throttle: Option<i32>,
+
+    /// Totals for each method called, most calls first.
+    pub methods: Vec<ApiMethodUsage>,
+
+    /// Totals for each day in the period, in order.
+    pub days: Vec<ApiDailyUsage>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ApiMethodUsage {
+    pub method: String,
+    pub call_count: i64,
+    pub error_count: i64,
+    pub request_bytes: i64,
+    pub response_bytes: i64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ApiDailyUsage {
+    pub date: Date,
+    pub call_count: i64,
+    pub error_count: i64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SetApiThrottle {
+    pub site_id: i64,
+    pub user_id: i64,
+    pub acting_user_id: i64,
+
+    /// The most calls the user may make per minute.
+    /// If `None`, then any existing throttle is removed.
+    pub requests_per_minute: Option<i32>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ApiThrottle {
+    pub site_id: i64,
+    pub user_id: i64,
+    pub created_at: OffsetDateTime,
+    pub created_by: i64,
+    pub requests_per_minute: i32,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct RevokeApiAccess {
+    pub site_id: i64,
+    pub user_id: i64,
+    pub acting_user_id: i64,
+    pub reason: String,
+}
@@ -0,0 +1,40 @@
+/*
+ * services/audit/mod.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The audit service, for recording actions for later review by platform staff.
+//!
+//! Each entry has both an actor (who performed the action) and a subject
+//! (who the action was performed as). Normally these are the same user,
+//! but when an administrator is impersonating someone, the actor is the
+//! administrator and the subject is the user being impersonated.
+//!
+//! Actions taken on content within a site, such as hiding revisions, also
+//! record the site, so that the log can be filtered to one site.
+
+mod prelude {
+    pub use super::super::prelude::*;
+    pub use super::structs::*;
+}
+
+mod service;
+mod structs;
+
+pub use self::service::AuditService;
+pub use self::structs::*;
@@ -0,0 +1,165 @@
+/*
+ * services/audit/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::audit_log::{self, Entity as AuditLog, Model as AuditLogModel};
+use sea_orm::TransactionTrait;
+use time::OffsetDateTime;
+
+/// The maximum number of audit entries which can be retrieved at once.
+const MAXIMUM_AUDIT_LOG_LIMIT: u64 = 100;
+
+#[derive(Debug)]
+pub struct AuditService;
+
+impl AuditService {
+    pub async fn record(
+        ctx: &ServiceContext<'_>,
+        CreateAuditEntry {
+            actor_id,
+            subject_id,
+            site_id,
+            action,
+            data,
+        }: CreateAuditEntry<'_>,
+    ) -> Result<AuditLogModel> {
+        info!(
+            "Recording audit entry '{action}' (actor ID {actor_id}, subject ID {subject_id})",
+        );
+
+        // Entries are kept long-term, so apply the same scrubbing as logs.
+        let data = ctx.config().redaction_policy.scrub_json(data);
+        let txn = ctx.transaction();
+        let model = audit_log::ActiveModel {
+            created_at: Set(now()),
+            actor_id: Set(actor_id),
+            subject_id: Set(subject_id),
+            service_account_id: Set(ctx
+                .service_account()
+                .map(|service_account| service_account.service_account_id)),
+            site_id: Set(site_id),
+            action: Set(str!(action)),
+            data: Set(data),
+            ..Default::default()
+        };
+
+        let entry = model.insert(txn).await?;
+        Ok(entry)
+    }
+
+    /// Records an audit entry in its own transaction.
+    ///
+    /// This is for actions which are recorded because they were rejected,
+    /// since the entry is kept even though the caller's transaction
+    /// is rolled back. Nothing is recorded for dry runs.
+    pub async fn record_detached(
+        ctx: &ServiceContext<'_>,
+        entry: CreateAuditEntry<'_>,
+    ) -> Result<()> {
+        if ctx.check_only() {
+            debug!("Not recording detached audit entry, this is a dry run");
+            return Ok(());
+        }
+
+        let txn = ctx.database().begin().await?;
+        Self::record(&ctx.with_transaction(&txn), entry).await?;
+        txn.commit().await?;
+        Ok(())
+    }
+
+    /// Gets audit entries matching the given filters, newest first.
+    pub async fn get_all(
+        ctx: &ServiceContext<'_>,
+        GetAuditLog {
+            user_id,
+            site_id,
+            action,
+            before_id,
+            limit,
+        }: GetAuditLog,
+    ) -> Result<Vec<AuditLogModel>> {
+        info!(
+            "Getting audit entries for user ID {user_id:?}, site ID {site_id:?}, action {action:?}",
+        );
+
+        let txn = ctx.transaction();
+        let mut condition = Condition::all();
+
+        if let Some(user_id) = user_id {
+            condition = condition.add(
+                Condition::any()
+                    .add(audit_log::Column::ActorId.eq(user_id))
+                    .add(audit_log::Column::SubjectId.eq(user_id)),
+            );
+        }
+
+        if let Some(site_id) = site_id {
+            condition = condition.add(audit_log::Column::SiteId.eq(site_id));
+        }
+
+        if let Some(action) = action {
+            condition = condition.add(if action.ends_with('.') {
+                audit_log::Column::Action.starts_with(&action)
+            } else {
+                audit_log::Column::Action.eq(action)
+            });
+        }
+
+        if let Some(before_id) = before_id {
+            condition = condition.add(audit_log::Column::AuditId.lt(before_id));
+        }
+
+        let entries = AuditLog::find()
+            .filter(condition)
+            .order_by_desc(audit_log::Column::AuditId)
+            .limit(limit.min(MAXIMUM_AUDIT_LOG_LIMIT))
+            .all(txn)
+            .await?;
+
+        Ok(entries)
+    }
+
+    /// Counts the entries where one user acted as another since the given time.
+    ///
+    /// Entries whose action starts with `prefix_excluded` are not counted.
+    pub async fn count_since(
+        ctx: &ServiceContext<'_>,
+        actor_id: i64,
+        subject_id: i64,
+        since: OffsetDateTime,
+        prefix_excluded: &str,
+    ) -> Result<u64> {
+        let txn = ctx.transaction();
+        let count = AuditLog::find()
+            .filter(
+                Condition::all()
+                    .add(audit_log::Column::ActorId.eq(actor_id))
+                    .add(audit_log::Column::SubjectId.eq(subject_id))
+                    .add(audit_log::Column::CreatedAt.gte(since))
+                    .add(
+                        audit_log::Column::Action.not_like(format!("{prefix_excluded}%")),
+                    ),
+            )
+            .count(txn)
+            .await?;
+
+        Ok(count)
+    }
+}
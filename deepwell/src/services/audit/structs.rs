@@ -0,0 +1,51 @@
+/*
+ * services/audit/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+#[derive(Debug, Clone)]
+pub struct CreateAuditEntry<'a> {
+    pub actor_id: i64,
+    pub subject_id: i64,
+    pub site_id: Option<i64>,
+    pub action: &'a str,
+    pub data: serde_json::Value,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetAuditLog {
+    /// Entries where this user is either the actor or the subject.
+    #[serde(default)]
+    pub user_id: Option<i64>,
+
+    /// Entries for actions taken within this site.
+    #[serde(default)]
+    pub site_id: Option<i64>,
+
+    /// Entries for this action, such as `page.revision-hide`.
+    ///
+    /// If this ends with `.`, then it matches all actions with that prefix.
+    #[serde(default)]
+    pub action: Option<String>,
+
+    /// If set, only entries older than this audit ID are returned.
+    #[serde(default)]
+    pub before_id: Option<i64>,
+
+    pub limit: u64,
+}
@@ -19,8 +19,13 @@
  */
 
 use super::prelude::*;
+use crate::models::magic_link::{self, Entity as MagicLink};
 use crate::models::user::{self, Entity as User, Model as UserModel};
+use crate::redis::window_counter;
 use crate::services::{MfaService, PasswordService, SessionService};
+use crate::utils::{hash_token, new_token};
+use sea_orm::UpdateResult;
+use sea_query::Expr;
 
 #[derive(Debug)]
 pub struct AuthenticationService;
@@ -81,6 +86,189 @@ impl AuthenticationService {
         Ok(user)
     }
 
+    /// Issues a single-use login link for the user with this email.
+    ///
+    /// Only users who have opted in to passwordless login and verified
+    /// their email can receive links. If there is no such user, or they
+    /// have requested too many links recently, then `None` is returned.
+    /// Callers must respond the same way in either case, so that this
+    /// does not reveal which email addresses have accounts.
+    pub async fn request_magic_link(
+        ctx: &ServiceContext<'_>,
+        RequestMagicLink { email }: RequestMagicLink,
+    ) -> Result<Option<MagicLinkOutput>> {
+        info!("Requesting magic login link");
+
+        let txn = ctx.transaction();
+        let user = User::find()
+            .filter(
+                Condition::all()
+                    .add(user::Column::Email.eq(email.as_str()))
+                    .add(user::Column::MagicLinkLogin.eq(true))
+                    .add(user::Column::EmailVerifiedAt.is_not_null())
+                    .add(user::Column::DeletedAt.is_null()),
+            )
+            .one(txn)
+            .await?;
+
+        let user = match user {
+            Some(user) => user,
+            None => {
+                debug!("No user with this email has magic link login enabled");
+                return Ok(None);
+            }
+        };
+
+        if !Self::check_magic_link_rate_limit(ctx, user.user_id).await? {
+            return Ok(None);
+        }
+
+        let config = ctx.config();
+        let token = Self::new_magic_link_token(config);
+        let device_token = Self::new_magic_link_token(config);
+        let created_at = now();
+        let expires_at = created_at + config.magic_link_duration;
+
+        let model = magic_link::ActiveModel {
+            token_hash: Set(hash_token(&token)),
+            user_id: Set(user.user_id),
+            device_hash: Set(hash_token(&device_token)),
+            created_at: Set(created_at),
+            expires_at: Set(expires_at),
+            used_at: Set(None),
+        };
+        model.insert(txn).await?;
+
+        info!("Issued magic login link for user ID {}", user.user_id);
+        Ok(Some(MagicLinkOutput {
+            user_id: user.user_id,
+            email: user.email,
+            token,
+            device_token,
+            expires_at,
+        }))
+    }
+
+    /// Verifies a login link, consuming it.
+    ///
+    /// The device token must be the one issued alongside the link.
+    /// A link which is unknown, expired, already used, or opened
+    /// from another device fails with a generic authentication error.
+    pub async fn auth_magic_link(
+        ctx: &ServiceContext<'_>,
+        MagicLinkAuthenticateUser {
+            token,
+            device_token,
+        }: MagicLinkAuthenticateUser<'_>,
+    ) -> Result<AuthenticateUserOutput> {
+        let txn = ctx.transaction();
+        let token_hash = hash_token(token);
+        let link = MagicLink::find()
+            .filter(
+                Condition::all()
+                    .add(magic_link::Column::TokenHash.eq(token_hash.clone()))
+                    .add(magic_link::Column::ExpiresAt.gt(now()))
+                    .add(magic_link::Column::UsedAt.is_null()),
+            )
+            .one(txn)
+            .await?
+            .ok_or(Error::InvalidAuthentication)?;
+
+        if link.device_hash != hash_token(device_token) {
+            warn!(
+                "Magic login link for user ID {} was opened from a different device",
+                link.user_id,
+            );
+            return Err(Error::InvalidAuthentication);
+        }
+
+        // Only mark the link as used if nothing else has in the meantime
+        let UpdateResult { rows_affected } = MagicLink::update_many()
+            .col_expr(magic_link::Column::UsedAt, Expr::value(now()))
+            .filter(
+                Condition::all()
+                    .add(magic_link::Column::TokenHash.eq(token_hash))
+                    .add(magic_link::Column::UsedAt.is_null()),
+            )
+            .exec(txn)
+            .await?;
+
+        if rows_affected == 0 {
+            warn!("Magic login link was used concurrently");
+            return Err(Error::InvalidAuthentication);
+        }
+
+        // The user may have opted out or been deleted since the link was sent
+        let user = User::find()
+            .filter(
+                Condition::all()
+                    .add(user::Column::UserId.eq(link.user_id))
+                    .add(user::Column::MagicLinkLogin.eq(true))
+                    .add(user::Column::DeletedAt.is_null()),
+            )
+            .one(txn)
+            .await?
+            .ok_or(Error::InvalidAuthentication)?;
+
+        Ok(AuthenticateUserOutput {
+            needs_mfa: user.multi_factor_secret.is_some(),
+            user_id: user.user_id,
+        })
+    }
+
+    /// Deletes all expired or used login links.
+    ///
+    /// # Returns
+    /// The number of pruned links.
+    pub async fn prune_magic_links(ctx: &ServiceContext<'_>) -> Result<u64> {
+        info!("Pruning all expired magic login links");
+
+        let txn = ctx.transaction();
+        let DeleteResult { rows_affected } = MagicLink::delete_many()
+            .filter(
+                Condition::any()
+                    .add(magic_link::Column::ExpiresAt.lte(now()))
+                    .add(magic_link::Column::UsedAt.is_not_null()),
+            )
+            .exec(txn)
+            .await?;
+
+        debug!("{rows_affected} magic login links were pruned");
+        Ok(rows_affected)
+    }
+
+    /// Counts this request against the user's login link rate limit.
+    ///
+    /// Returns `false` if the limit has been exceeded.
+    /// Dry runs are checked, but not counted.
+    async fn check_magic_link_rate_limit(
+        ctx: &ServiceContext<'_>,
+        user_id: i64,
+    ) -> Result<bool> {
+        let config = ctx.config();
+        let key = format!("magic-link:rate:{user_id}");
+        let window = config.magic_link_rate_limit_window.as_secs();
+        let (count,): (u64,) = ctx
+            .until_deadline(
+                window_counter(&key, window, ctx.check_only())
+                    .query_async(&mut ctx.redis()),
+            )
+            .await?;
+
+        if count > config.magic_link_rate_limit_requests {
+            warn!("User ID {user_id} has exceeded the magic link rate limit");
+            return Ok(false);
+        }
+
+        Ok(true)
+    }
+
+    /// Securely generates a new token for a login link.
+    #[inline]
+    fn new_magic_link_token(config: &Config) -> String {
+        new_token(config.session_token_length)
+    }
+
     /// Gets user information from the database, or return a dummy.
     ///
     /// To avoid timing attacks, all aspects of authentication (finding the user,
@@ -101,10 +289,14 @@ impl AuthenticationService {
         let txn = ctx.transaction();
         let result = User::find()
             .filter(
-                Condition::any()
-                    .add(user::Column::Name.eq(name_or_email))
-                    .add(user::Column::Slug.eq(name_or_email))
-                    .add(user::Column::Email.eq(name_or_email)),
+                Condition::all()
+                    .add(
+                        Condition::any()
+                            .add(user::Column::Name.eq(name_or_email))
+                            .add(user::Column::Slug.eq(name_or_email))
+                            .add(user::Column::Email.eq(name_or_email)),
+                    )
+                    .add(user::Column::DeletedAt.is_null()),
             )
             .one(txn)
             .await?;
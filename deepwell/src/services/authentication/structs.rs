@@ -20,6 +20,7 @@
 
 use crate::models::user::Model as UserModel;
 use std::net::IpAddr;
+use time::OffsetDateTime;
 
 #[derive(Deserialize, Debug, Clone)]
 pub struct AuthenticateUser {
@@ -62,6 +63,39 @@ pub struct LoginUserMfa {
     pub user_agent: String,
 }
 
+#[derive(Deserialize, Debug, Clone)]
+pub struct RequestMagicLink {
+    pub email: String,
+}
+
+/// A newly issued login link.
+///
+/// The `token` goes in the link which is emailed to the user, and the
+/// `device_token` is kept by the browser which asked for it. Both are
+/// needed to log in, so the link cannot be used from another device.
+#[derive(Serialize, Debug, Clone)]
+pub struct MagicLinkOutput {
+    pub user_id: i64,
+    pub email: String,
+    pub token: String,
+    pub device_token: String,
+    pub expires_at: OffsetDateTime,
+}
+
+#[derive(Debug, Clone)]
+pub struct MagicLinkAuthenticateUser<'a> {
+    pub token: &'a str,
+    pub device_token: &'a str,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct LoginUserMagicLink {
+    pub token: String,
+    pub device_token: String,
+    pub ip_address: IpAddr,
+    pub user_agent: String,
+}
+
 /// Password hash to compute against when a user does not exist.
 ///
 /// It has similar settings to other passwords on Wikijump, but
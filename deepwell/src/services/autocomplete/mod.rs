@@ -0,0 +1,40 @@
+/*
+ * services/autocomplete/mod.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The autocomplete service, which provides typeahead suggestions for editors.
+//!
+//! Pages and tags are looked up from the search index, so suggestions follow
+//! the latest revision of each page, while users are looked up directly. All
+//! matching is by prefix, and is backed by dedicated prefix and trigram indexes.
+//! Pages, and the tags on them, are only suggested if the user can view them.
+//!
+//! Since these requests are made as the user types, results are kept small,
+//! and each user is rate limited using a fixed window counter in Redis.
+
+mod prelude {
+    pub use super::super::prelude::*;
+    pub use super::structs::*;
+}
+
+mod service;
+mod structs;
+
+pub use self::service::AutocompleteService;
+pub use self::structs::*;
@@ -0,0 +1,373 @@
+/*
+ * services/autocomplete/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::relation::{self, Entity as Relation};
+use crate::models::sea_orm_active_enums::{
+    RelationObjectType, SearchDocumentType, UserType,
+};
+use crate::models::search_document::{self, Entity as SearchDocument};
+use crate::models::user::{self, Entity as User};
+use crate::services::relation::{GetSiteBan, RelationType};
+use crate::services::{PermissionService, RelationService};
+use crate::utils::escape_like;
+use sea_orm::{DatabaseBackend, FromQueryResult, Statement};
+use sea_query::extension::postgres::PgExpr;
+use sea_query::{Expr, Query};
+use std::collections::BTreeSet;
+use wikidot_normalize::normalize;
+
+/// The most pages which are checked for a single autocomplete request.
+///
+/// Matching pages the user cannot view are skipped, so more than the
+/// requested number may need to be checked, but this must stay bounded.
+const MAXIMUM_CANDIDATES: u64 = 200;
+
+#[derive(Debug)]
+pub struct AutocompleteService;
+
+impl AutocompleteService {
+    /// Suggests pages in a site whose slug or title starts with the given prefix.
+    ///
+    /// Only pages the user can view are suggested.
+    pub async fn pages(
+        ctx: &ServiceContext<'_>,
+        input: Autocomplete,
+    ) -> Result<Vec<AutocompletePage>> {
+        let user_id = input.user_id;
+        let (site_id, prefix, limit) = match Self::prepare(ctx, input).await? {
+            Some(values) => values,
+            None => return Ok(Vec::new()),
+        };
+
+        let slug_prefix = {
+            let mut slug = prefix.clone();
+            normalize(&mut slug);
+            slug
+        };
+
+        let txn = ctx.transaction();
+        let mut pages = Vec::new();
+        let mut offset = 0;
+
+        // Fetch candidates in batches, until enough are visible to the user
+        while (pages.len() as u64) < limit && offset < MAXIMUM_CANDIDATES {
+            let documents = SearchDocument::find()
+                .filter(
+                    Condition::all()
+                        .add(search_document::Column::SiteId.eq(site_id))
+                        .add(
+                            search_document::Column::DocumentType
+                                .eq(SearchDocumentType::Page),
+                        )
+                        .add(
+                            Condition::any()
+                                .add(
+                                    Expr::col(search_document::Column::Name)
+                                        .like(like_prefix(&slug_prefix)),
+                                )
+                                .add(
+                                    Expr::col(search_document::Column::Title)
+                                        .ilike(like_prefix(&prefix)),
+                                ),
+                        ),
+                )
+                .order_by_asc(search_document::Column::Name)
+                .offset(offset)
+                .limit(limit)
+                .all(txn)
+                .await?;
+
+            let exhausted = (documents.len() as u64) < limit;
+            offset += documents.len() as u64;
+
+            for document in documents {
+                if pages.len() as u64 == limit {
+                    break;
+                }
+
+                if Self::can_view(ctx, site_id, document.document_id, user_id).await? {
+                    pages.push(AutocompletePage {
+                        page_id: document.document_id,
+                        slug: document.name,
+                        title: document.title,
+                    });
+                }
+            }
+
+            if exhausted {
+                break;
+            }
+        }
+
+        Ok(pages)
+    }
+
+    /// Suggests users whose name or slug starts with the given prefix.
+    ///
+    /// Users who are banned from the site, or who have blocked the requesting
+    /// user, are not suggested.
+    pub async fn users(
+        ctx: &ServiceContext<'_>,
+        input: Autocomplete,
+    ) -> Result<Vec<AutocompleteUser>> {
+        let user_id = input.user_id;
+        let (site_id, prefix, limit) = match Self::prepare(ctx, input).await? {
+            Some(values) => values,
+            None => return Ok(Vec::new()),
+        };
+
+        let slug_prefix = {
+            let mut slug = prefix.clone();
+            normalize(&mut slug);
+            slug
+        };
+
+        // Subquery of user IDs with an active relation of the given kind
+        let related_users =
+            |relation_type: RelationType, dest_type: RelationObjectType, dest_id: i64| {
+                Query::select()
+                    .column(relation::Column::FromId)
+                    .from(Relation)
+                    .and_where(relation::Column::RelationType.eq(relation_type.value()))
+                    .and_where(relation::Column::DestType.eq(dest_type))
+                    .and_where(relation::Column::DestId.eq(dest_id))
+                    .and_where(relation::Column::FromType.eq(RelationObjectType::User))
+                    .and_where(relation::Column::OverwrittenAt.is_null())
+                    .and_where(relation::Column::DeletedAt.is_null())
+                    .to_owned()
+            };
+
+        let txn = ctx.transaction();
+        let users = User::find()
+            .filter(
+                Condition::all()
+                    .add(user::Column::DeletedAt.is_null())
+                    .add(user::Column::UserType.is_in([UserType::Regular, UserType::Bot]))
+                    .add(
+                        Condition::any()
+                            .add(
+                                Expr::col(user::Column::Slug)
+                                    .like(like_prefix(&slug_prefix)),
+                            )
+                            .add(
+                                Expr::col(user::Column::Name).ilike(like_prefix(&prefix)),
+                            ),
+                    )
+                    .add(user::Column::UserId.not_in_subquery(related_users(
+                        RelationType::SiteBan,
+                        RelationObjectType::Site,
+                        site_id,
+                    )))
+                    .add(user::Column::UserId.not_in_subquery(related_users(
+                        RelationType::UserBlock,
+                        RelationObjectType::User,
+                        user_id,
+                    ))),
+            )
+            .order_by_asc(user::Column::Slug)
+            .limit(limit)
+            .all(txn)
+            .await?
+            .into_iter()
+            .map(|user| AutocompleteUser {
+                user_id: user.user_id,
+                name: user.name,
+                slug: user.slug,
+            })
+            .collect();
+
+        Ok(users)
+    }
+
+    /// Suggests tags used on pages in a site which start with the given prefix.
+    ///
+    /// Only tags on pages the user can view are suggested.
+    pub async fn tags(
+        ctx: &ServiceContext<'_>,
+        input: Autocomplete,
+    ) -> Result<Vec<String>> {
+        #[derive(FromQueryResult, Debug)]
+        struct TagRow {
+            document_id: i64,
+            tags: Vec<String>,
+        }
+
+        let user_id = input.user_id;
+        let (site_id, prefix, limit) = match Self::prepare(ctx, input).await? {
+            Some(values) => values,
+            None => return Ok(Vec::new()),
+        };
+
+        let txn = ctx.transaction();
+        let rows = TagRow::find_by_statement(Statement::from_sql_and_values(
+            DatabaseBackend::Postgres,
+            r#"
+            SELECT document_id, tags
+            FROM search_document
+            WHERE site_id = $1
+            AND document_type = 'page'
+            AND EXISTS (SELECT 1 FROM unnest(tags) AS tag WHERE tag LIKE $2)
+            ORDER BY document_id
+            LIMIT $3
+            "#,
+            [
+                site_id.into(),
+                like_prefix(&prefix).into(),
+                (MAXIMUM_CANDIDATES as i64).into(),
+            ],
+        ))
+        .all(txn)
+        .await?;
+
+        let mut pages = Vec::with_capacity(rows.len());
+        for TagRow { document_id, tags } in rows {
+            let visible = Self::can_view(ctx, site_id, document_id, user_id).await?;
+            pages.push((visible, tags));
+        }
+
+        Ok(matching_tags(&pages, &prefix, limit))
+    }
+
+    /// Performs the checks common to all autocomplete requests.
+    ///
+    /// # Returns
+    /// The site ID, trimmed prefix, and result limit, or `None`
+    /// if there can be no results.
+    async fn prepare(
+        ctx: &ServiceContext<'_>,
+        Autocomplete {
+            site_id,
+            user_id,
+            prefix,
+            limit,
+        }: Autocomplete,
+    ) -> Result<Option<(i64, String, u64)>> {
+        Self::check_rate_limit(ctx, user_id).await?;
+        RelationService::check_site_ban(
+            ctx,
+            GetSiteBan { site_id, user_id },
+            "autocomplete in",
+        )
+        .await?;
+
+        let maximum = ctx.config().autocomplete_maximum_results;
+        let limit = limit.unwrap_or(maximum).min(maximum);
+        let prefix = prefix.trim();
+        if prefix.is_empty() || limit == 0 {
+            return Ok(None);
+        }
+
+        debug!("Autocompleting '{prefix}' in site ID {site_id} (limit {limit})");
+        Ok(Some((site_id, str!(prefix), limit)))
+    }
+
+    async fn can_view(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        page_id: i64,
+        user_id: i64,
+    ) -> Result<bool> {
+        let permission = PermissionService::check(
+            ctx,
+            site_id,
+            Some(page_id),
+            Some(user_id),
+            "page_get",
+        )
+        .await?;
+
+        Ok(permission.allowed)
+    }
+
+    /// Counts this request against the user's rate limit.
+    ///
+    /// The counter is created with an expiry at the start of each window,
+    /// so it resets on its own rather than needing to be cleaned up.
+    async fn check_rate_limit(ctx: &ServiceContext<'_>, user_id: i64) -> Result<()> {
+        let config = ctx.config();
+        let key = format!("autocomplete:rate:{user_id}");
+        let (count,): (u64,) = ctx
+            .until_deadline(
+                redis::pipe()
+                    .atomic()
+                    .cmd("SET")
+                    .arg(&key)
+                    .arg(0)
+                    .arg("EX")
+                    .arg(config.autocomplete_rate_limit_window.as_secs())
+                    .arg("NX")
+                    .ignore()
+                    .incr(&key, 1)
+                    .query_async(&mut ctx.redis()),
+            )
+            .await?;
+
+        if count > config.autocomplete_rate_limit_requests {
+            warn!("User ID {user_id} has exceeded the autocomplete rate limit");
+            return Err(Error::AutocompleteRateLimited);
+        }
+
+        Ok(())
+    }
+}
+
+/// Builds a `LIKE` pattern matching anything starting with the given string.
+///
+/// Wildcards in the string are escaped with a backslash,
+/// which is the default escape character in PostgreSQL.
+fn like_prefix(prefix: &str) -> String {
+    let mut pattern = escape_like(prefix);
+    pattern.push('%');
+    pattern
+}
+
+/// Gathers the distinct tags starting with a prefix, in order.
+///
+/// Each page is given with whether the user can view it,
+/// and the tags of those they can't are never included.
+fn matching_tags(pages: &[(bool, Vec<String>)], prefix: &str, limit: u64) -> Vec<String> {
+    pages
+        .iter()
+        .filter(|(visible, _)| *visible)
+        .flat_map(|(_, tags)| tags)
+        .filter(|tag| tag.starts_with(prefix))
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .take(limit as usize)
+        .cloned()
+        .collect()
+}
+
+#[test]
+fn matching_tags_visibility() {
+    let tags = |values: &[&str]| values.iter().map(|&value| str!(value)).collect();
+    let pages = [
+        (true, tags(&["scp", "safe", "_cc"])),
+        (false, tags(&["secret-project", "scp"])),
+        (true, tags(&["scp", "euclid", "sandbox"])),
+    ];
+
+    // The restricted page's own tag is not suggested
+    assert_eq!(matching_tags(&pages, "s", 10), ["safe", "sandbox", "scp"]);
+    assert_eq!(matching_tags(&pages, "se", 10), Vec::<String>::new());
+    assert_eq!(matching_tags(&pages, "s", 2), ["safe", "sandbox"]);
+    assert_eq!(matching_tags(&pages, "x", 10), Vec::<String>::new());
+}
@@ -0,0 +1,45 @@
+/*
+ * services/autocomplete/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Autocomplete {
+    pub site_id: i64,
+
+    /// The user making the request, for rate limiting and filtering.
+    pub user_id: i64,
+    pub prefix: String,
+
+    /// How many results to return, capped at the configured maximum.
+    pub limit: Option<u64>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct AutocompletePage {
+    pub page_id: i64,
+    pub slug: String,
+    pub title: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct AutocompleteUser {
+    pub user_id: i64,
+    pub name: String,
+    pub slug: String,
+}
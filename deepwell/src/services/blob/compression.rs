@@ -0,0 +1,114 @@
+/*
+ * services/blob/compression.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Transparent compression of stored blobs.
+//!
+//! Blobs of compressible types, such as text, are stored in S3 compressed
+//! with zstd. They are still addressed by the hash of their original data,
+//! and are decompressed whenever they're read, so the rest of DEEPWELL
+//! never sees the compressed form. Which blobs are compressed is recorded
+//! in the `blob_compression` table, not in S3.
+
+use super::prelude::*;
+use std::io::Cursor;
+
+/// The algorithm recorded for compressed blobs.
+///
+/// This is the only one for now, but it's stored so others can be added later.
+pub const COMPRESSION_ALGORITHM: &str = "zstd";
+
+/// MIME types outside of `text/*` which compress well.
+const COMPRESSIBLE_MIME_TYPES: [&str; 6] = [
+    "application/json",
+    "application/javascript",
+    "application/xml",
+    "application/xhtml+xml",
+    "application/x-ndjson",
+    "image/svg+xml",
+];
+
+/// Whether blobs of this MIME type are worth compressing.
+///
+/// Any parameters, such as the charset, are ignored.
+pub fn is_compressible(mime: &str) -> bool {
+    let essence = mime.split(';').next().unwrap_or_default().trim();
+    essence.starts_with("text/") || COMPRESSIBLE_MIME_TYPES.contains(&essence)
+}
+
+/// Compresses this data, if doing so would make it any smaller.
+pub fn compress(data: &[u8], level: i32) -> Result<Option<Vec<u8>>> {
+    let compressed =
+        zstd::encode_all(Cursor::new(data), level).map_err(Error::BlobCompression)?;
+
+    if compressed.len() < data.len() {
+        Ok(Some(compressed))
+    } else {
+        Ok(None)
+    }
+}
+
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>> {
+    zstd::decode_all(Cursor::new(data)).map_err(Error::BlobCompression)
+}
+
+#[test]
+fn compressible() {
+    macro_rules! check {
+        ($mime:expr, $expected:expr $(,)?) => {
+            assert_eq!(
+                is_compressible($mime),
+                $expected,
+                "Unexpected compressibility for MIME type {:?}",
+                $mime,
+            );
+        };
+    }
+
+    check!("text/plain", true);
+    check!("text/html; charset=utf-8", true);
+    check!("application/json", true);
+    check!("image/svg+xml", true);
+    check!(" application/xml ; charset=utf-8", true);
+    check!("image/png", false);
+    check!("application/zip", false);
+    check!("application/octet-stream", false);
+    check!("", false);
+}
+
+#[test]
+fn round_trip() {
+    let data = "Lorem ipsum dolor sit amet. ".repeat(200);
+    let compressed = compress(data.as_bytes(), 3)
+        .expect("Unable to compress data")
+        .expect("Repetitive data was not compressed");
+
+    assert!(compressed.len() < data.len());
+    assert_eq!(
+        decompress(&compressed).expect("Unable to decompress data"),
+        data.as_bytes(),
+    );
+
+    // Data which doesn't shrink is left alone
+    let compressed = compress(b"x", 3).expect("Unable to compress data");
+    assert!(compressed.is_none(), "Tiny data was compressed");
+
+    // Data which isn't zstd is rejected
+    assert!(decompress(b"not compressed").is_err());
+}
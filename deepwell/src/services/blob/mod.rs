@@ -27,13 +27,16 @@
 mod prelude {
     pub use super::super::prelude::*;
     pub use super::structs::*;
-    pub use crate::hash::{blob_hash_to_hex, sha512_hash, BlobHash};
+    pub use crate::hash::{blob_hash_to_hex, sha512_hash, BlobHash, BLOB_HASH_LENGTH};
 }
 
+mod compression;
 mod mime;
 mod service;
+mod store;
 mod structs;
 
 pub use self::mime::MimeAnalyzer;
 pub use self::service::BlobService;
+pub use self::store::BlobStore;
 pub use self::structs::*;
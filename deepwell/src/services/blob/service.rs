@@ -21,11 +21,33 @@
 // TEMP, until https://scuttle.atlassian.net/browse/WJ-1032
 #![allow(dead_code)]
 
+use super::compression::{self, COMPRESSION_ALGORITHM};
 use super::prelude::*;
+use super::store::BlobStore;
+use crate::models::blob_collection::{
+    self, Entity as BlobCollection, Model as BlobCollectionModel,
+};
+use crate::models::blob_compression::{
+    self, Entity as BlobCompression, Model as BlobCompressionModel,
+};
+use crate::models::blob_migration::{
+    self, Entity as BlobMigration, Model as BlobMigrationModel,
+};
+use crate::models::blob_spool::{self, Entity as BlobSpool, Model as BlobSpoolModel};
+use crate::models::export::{self, Entity as Export};
+use crate::models::file_derivative::{self, Entity as FileDerivative};
+use crate::models::file_revision::{self, Entity as FileRevision};
+use crate::models::file_staging::{self, Entity as FileStaging};
+use crate::models::file_thumbnail::{self, Entity as FileThumbnail};
+use crate::models::user::{self, Entity as User};
+use crate::services::job::{Job, JobService};
 use s3::request_trait::ResponseData;
 use s3::serde_types::HeadObjectResult;
+use sea_orm::{DatabaseBackend, FromQueryResult, Statement};
+use std::collections::{HashMap, HashSet};
 use std::str;
-use time::format_description::well_known::Rfc2822;
+use std::time::Duration as StdDuration;
+use time::format_description::well_known::{Rfc2822, Rfc3339};
 use time::OffsetDateTime;
 
 /// Hash for empty blobs.
@@ -48,16 +70,93 @@ pub const EMPTY_BLOB_MIME: &str = "inode/x-empty; charset=binary";
 /// Timestamp is 2019/01/18 at midnight, the date of the first Wikijump commit.
 pub const EMPTY_BLOB_TIMESTAMP: i64 = 1547769600;
 
+/// Contents of the tombstone blob, which purged blobs are replaced with.
+pub const TOMBSTONE_BLOB_DATA: &[u8] = b"This file has been removed.\n";
+
+/// How many spooled blobs to load at a time while flushing.
+///
+/// Each is at most the configured spool blob size, so this bounds memory use.
+const SPOOL_FLUSH_BATCH_SIZE: u64 = 10;
+
+/// How many S3 objects to list at a time while collecting garbage.
+const COLLECTION_BATCH_SIZE: usize = 1000;
+
+/// Adds a blob to the spool, unless an upload of the same data already put it there.
+const INSERT_SPOOL_SQL: &str = r#"
+INSERT INTO blob_spool (s3_hash, mime, size, data)
+VALUES ($1, $2, $3, $4)
+ON CONFLICT (s3_hash) DO NOTHING
+"#;
+
+/// Records a blob as stored compressed, unless it already has been.
+const INSERT_COMPRESSION_SQL: &str = r#"
+INSERT INTO blob_compression (s3_hash, algorithm, logical_size, physical_size)
+VALUES ($1, $2, $3, $4)
+ON CONFLICT (s3_hash) DO NOTHING
+"#;
+
+/// Lists the files using a blob, across all sites.
+const FIND_FILE_REFERENCES_SQL: &str = r#"
+SELECT site_id, file_id, COUNT(*) AS revisions
+FROM file_revision
+WHERE s3_hash = $1
+GROUP BY site_id, file_id
+ORDER BY site_id, file_id
+"#;
+
+/// What happened to an object while migrating it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum MigrateOutcome {
+    Copied,
+    Skipped,
+    Failed,
+}
+
+#[derive(FromQueryResult, Debug)]
+struct SpoolTotals {
+    count: i64,
+    bytes: i64,
+    oldest: Option<OffsetDateTime>,
+}
+
+#[derive(FromQueryResult, Debug)]
+struct CompressionTotals {
+    count: i64,
+    logical_bytes: i64,
+    physical_bytes: i64,
+}
+
+/// Stores file contents and other binary data, addressed by their SHA-512 hash.
+///
+/// Because blobs are addressed by hash, uploading data which already exists
+/// anywhere on the platform reuses the existing blob instead of storing it
+/// again. Blobs aren't owned by whatever uploaded them, instead their
+/// references are counted from the rows which use them, see `find_duplicates()`.
+/// A blob should only be deleted via `release()` once the last of these is gone.
 #[derive(Debug)]
 pub struct BlobService;
 
 impl BlobService {
     /// Creates a blob with this data, if it does not already exist.
+    ///
+    /// If S3 is unavailable, small blobs are spooled in the database instead,
+    /// to be written to S3 later by `flush_spool()`. Callers can't tell the
+    /// difference, since reads of spooled blobs are served from the spool.
     pub async fn create<B: AsRef<[u8]>>(
         ctx: &ServiceContext<'_>,
         data: B,
     ) -> Result<CreateBlobOutput> {
         let data = data.as_ref();
+        match Self::create_s3(ctx, data).await {
+            Err(error) if s3_unavailable(&error) => Self::spool(ctx, data, error).await,
+            result => result,
+        }
+    }
+
+    async fn create_s3(
+        ctx: &ServiceContext<'_>,
+        data: &[u8],
+    ) -> Result<CreateBlobOutput> {
         info!("Creating blob (length {})", data.len());
 
         // Special handling for empty blobs
@@ -79,6 +178,17 @@ impl BlobService {
         // Convert size to correct integer type
         let size: i64 = data.len().try_into().expect("Buffer size exceeds i64");
 
+        // If a file already has this data, it must be stored, so S3 can be skipped
+        if let Some(mime) = Self::get_file_mime(ctx, &hash).await? {
+            debug!("Blob with hash {hex_hash} is already used by a file");
+            return Ok(CreateBlobOutput {
+                hash,
+                mime,
+                size,
+                created: false,
+            });
+        }
+
         match Self::head(ctx, &hex_hash).await? {
             // Blob exists, copy metadata and return that
             Some(result) => {
@@ -87,6 +197,14 @@ impl BlobService {
                 // Content-Type header should be passed in
                 let mime = result.content_type.ok_or(Error::S3Response)?;
 
+                // Blobs are only compressed if it made them smaller, so a
+                // size mismatch means this one was. The record of that may
+                // have been lost if the upload which stored it was rolled back.
+                let physical_size = result.content_length.ok_or(Error::S3Response)?;
+                if physical_size != size {
+                    Self::record_compression(ctx, &hash, size, physical_size).await?;
+                }
+
                 Ok(CreateBlobOutput {
                     hash,
                     mime,
@@ -102,25 +220,362 @@ impl BlobService {
                 // Determine MIME type for the new file
                 let mime = ctx.mime().get_mime_type(data.to_vec()).await?;
 
-                // Put into S3
-                let response = bucket
-                    .put_object_with_content_type(&hex_hash, data, &mime)
-                    .await?;
-
-                // We assume all unexpected statuses are errors, even if 1XX or 2XX
-                match response.status_code() {
-                    200 => Ok(CreateBlobOutput {
+                if ctx.check_only() {
+                    debug!("Not uploading blob, this is a dry run");
+                    return Ok(CreateBlobOutput {
                         hash,
                         mime,
                         size,
                         created: true,
-                    }),
-                    _ => s3_error(&response, "creating S3 blob"),
+                    });
+                }
+
+                // Compress for storage, if enabled and worthwhile
+                let compressed = Self::compress(ctx, data, &mime)?;
+                let stored = compressed.as_deref().unwrap_or(data);
+
+                // Put into S3
+                let response = ctx
+                    .until_deadline(
+                        bucket.put_object_with_content_type(&hex_hash, stored, &mime),
+                    )
+                    .await?;
+
+                // We assume all unexpected statuses are errors, even if 1XX or 2XX
+                if response.status_code() != 200 {
+                    return s3_error(&response, "creating S3 blob");
+                }
+
+                if let Some(compressed) = &compressed {
+                    let physical_size: i64 = compressed
+                        .len()
+                        .try_into()
+                        .expect("Buffer size exceeds i64");
+
+                    Self::record_compression(ctx, &hash, size, physical_size).await?;
                 }
+
+                Self::mirror(ctx, &hex_hash, stored, &mime).await;
+
+                Ok(CreateBlobOutput {
+                    hash,
+                    mime,
+                    size,
+                    created: true,
+                })
             }
         }
     }
 
+    /// Gets the MIME type recorded by a file revision using this blob, if any.
+    async fn get_file_mime(
+        ctx: &ServiceContext<'_>,
+        hash: &[u8],
+    ) -> Result<Option<String>> {
+        let txn = ctx.transaction();
+        let mime = FileRevision::find()
+            .select_only()
+            .column(file_revision::Column::MimeHint)
+            .filter(file_revision::Column::S3Hash.eq(hash.to_vec()))
+            .into_tuple()
+            .one(txn)
+            .await?;
+
+        Ok(mime)
+    }
+
+    /// Compresses this blob's data for storage, if it should be.
+    ///
+    /// Returns `None` if the blob should be stored as-is, either because
+    /// compression is disabled, the blob is too small or of an incompressible
+    /// type, or compressing it didn't make it any smaller.
+    fn compress(
+        ctx: &ServiceContext<'_>,
+        data: &[u8],
+        mime: &str,
+    ) -> Result<Option<Vec<u8>>> {
+        let config = ctx.config();
+        if !config.blob_compression_enabled
+            || (data.len() as u64) < config.blob_compression_minimum_bytes
+            || !compression::is_compressible(mime)
+        {
+            return Ok(None);
+        }
+
+        let compressed = compression::compress(data, config.blob_compression_level)?;
+        match compressed {
+            Some(ref compressed) => debug!(
+                "Compressed blob from {} to {} bytes",
+                data.len(),
+                compressed.len(),
+            ),
+            None => debug!("Compressing blob did not make it smaller, storing as-is"),
+        }
+
+        Ok(compressed)
+    }
+
+    async fn record_compression(
+        ctx: &ServiceContext<'_>,
+        hash: &[u8],
+        logical_size: i64,
+        physical_size: i64,
+    ) -> Result<()> {
+        let txn = ctx.transaction();
+        txn.execute(Statement::from_sql_and_values(
+            DatabaseBackend::Postgres,
+            INSERT_COMPRESSION_SQL,
+            [
+                hash.to_vec().into(),
+                COMPRESSION_ALGORITHM.into(),
+                logical_size.into(),
+                physical_size.into(),
+            ],
+        ))
+        .await?;
+
+        Ok(())
+    }
+
+    /// Gets how this blob is compressed in S3, if it is.
+    async fn get_compression(
+        ctx: &ServiceContext<'_>,
+        hash: &[u8],
+    ) -> Result<Option<BlobCompressionModel>> {
+        let txn = ctx.transaction();
+        let compression = BlobCompression::find_by_id(hash.to_vec()).one(txn).await?;
+        Ok(compression)
+    }
+
+    /// Stores a blob in the spool, because S3 couldn't be reached.
+    ///
+    /// If spooling is disabled, or this blob or the spool are too large,
+    /// then the original S3 error is returned.
+    async fn spool(
+        ctx: &ServiceContext<'_>,
+        data: &[u8],
+        error: Error,
+    ) -> Result<CreateBlobOutput> {
+        let config = ctx.config();
+        let size = data.len() as u64;
+        if !config.blob_spool_enabled || size > config.blob_spool_maximum_blob_bytes {
+            warn!("S3 is unavailable, and blob (length {size}) cannot be spooled");
+            return Err(error);
+        }
+
+        let txn = ctx.transaction();
+        let hash = sha512_hash(data);
+        if let Some(spooled) = Self::get_spooled(ctx, &hash).await? {
+            debug!("S3 is unavailable, but blob is already spooled");
+            return Ok(CreateBlobOutput {
+                hash,
+                mime: spooled.mime,
+                size: spooled.size,
+                created: false,
+            });
+        }
+
+        let totals = Self::spool_totals(ctx).await?;
+        if totals.bytes as u64 + size > config.blob_spool_maximum_total_bytes {
+            warn!(
+                "S3 is unavailable, and blob spool is full ({} bytes in {} blobs)",
+                totals.bytes, totals.count,
+            );
+            return Err(error);
+        }
+
+        warn!("S3 is unavailable, spooling blob (length {size})");
+        let mime = ctx.mime().get_mime_type(data.to_vec()).await?;
+        let size: i64 = data.len().try_into().expect("Buffer size exceeds i64");
+
+        if ctx.check_only() {
+            debug!("Not spooling blob, this is a dry run");
+        } else {
+            txn.execute(Statement::from_sql_and_values(
+                DatabaseBackend::Postgres,
+                INSERT_SPOOL_SQL,
+                [
+                    hash.to_vec().into(),
+                    mime.clone().into(),
+                    size.into(),
+                    data.to_vec().into(),
+                ],
+            ))
+            .await?;
+        }
+
+        Ok(CreateBlobOutput {
+            hash,
+            mime,
+            size,
+            created: true,
+        })
+    }
+
+    /// Writes spooled blobs to S3, removing each from the spool once it's there.
+    ///
+    /// If S3 is still unavailable, this stops and leaves the rest for the next run.
+    pub async fn flush_spool(ctx: &ServiceContext<'_>) -> Result<()> {
+        let txn = ctx.transaction();
+        let bucket = ctx.s3_bucket();
+
+        loop {
+            let spooled = BlobSpool::find()
+                .order_by_asc(blob_spool::Column::CreatedAt)
+                .limit(SPOOL_FLUSH_BATCH_SIZE)
+                .all(txn)
+                .await?;
+
+            if spooled.is_empty() {
+                return Ok(());
+            }
+
+            debug!("Flushing {} spooled blobs to S3", spooled.len());
+            for blob in spooled {
+                let hex_hash = blob_hash_to_hex(&blob.s3_hash);
+                let upload = bucket
+                    .put_object_with_content_type(&hex_hash, &blob.data, &blob.mime);
+                let flushed = match ctx.until_deadline(upload).await {
+                    Ok(response) if response.status_code() == 200 => true,
+                    Ok(response) => {
+                        warn!(
+                            "S3 returned HTTP {} while flushing spooled blob",
+                            response.status_code(),
+                        );
+                        false
+                    }
+                    Err(error) => {
+                        warn!("Unable to reach S3 to flush spooled blob: {error}");
+                        false
+                    }
+                };
+
+                if !flushed {
+                    warn!("Stopping spool flush, will retry on the next run");
+                    let model = blob_spool::ActiveModel {
+                        s3_hash: Set(blob.s3_hash),
+                        flush_attempts: Set(blob.flush_attempts + 1),
+                        last_attempt_at: Set(Some(now())),
+                        ..Default::default()
+                    };
+                    model.update(txn).await?;
+                    return Ok(());
+                }
+
+                debug!("Flushed spooled blob {hex_hash}");
+                Self::mirror(ctx, &hex_hash, &blob.data, &blob.mime).await;
+                BlobSpool::delete_by_id(blob.s3_hash).exec(txn).await?;
+            }
+        }
+    }
+
+    /// Reports whether S3 is reachable, and how much is waiting in the spool.
+    pub async fn storage_status(ctx: &ServiceContext<'_>) -> Result<BlobStorageStatus> {
+        // Any response to a request for a nonexistent object, even an error,
+        // means S3 is up. Only connection failures and 5XX are outages.
+        let bucket = ctx.s3_bucket();
+        let request = bucket.head_object("health-check");
+        let available = match ctx.until_deadline(request).await {
+            Ok((_, status)) => status < 500,
+            Err(error) => {
+                warn!("S3 health check failed: {error}");
+                false
+            }
+        };
+
+        let SpoolTotals {
+            count,
+            bytes,
+            oldest,
+        } = Self::spool_totals(ctx).await?;
+
+        let compression = Self::compression_totals(ctx).await?;
+
+        Ok(BlobStorageStatus {
+            available,
+            spool_enabled: ctx.config().blob_spool_enabled,
+            spooled_blobs: count as u64,
+            spooled_bytes: bytes as u64,
+            oldest_spooled_at: oldest,
+            compression_enabled: ctx.config().blob_compression_enabled,
+            compressed_blobs: compression.count as u64,
+            compressed_logical_bytes: compression.logical_bytes as u64,
+            compressed_physical_bytes: compression.physical_bytes as u64,
+        })
+    }
+
+    async fn compression_totals(ctx: &ServiceContext<'_>) -> Result<CompressionTotals> {
+        let txn = ctx.transaction();
+        let totals = CompressionTotals::find_by_statement(Statement::from_string(
+            DatabaseBackend::Postgres,
+            r#"
+            SELECT
+                COUNT(*) AS count,
+                COALESCE(SUM(logical_size), 0)::BIGINT AS logical_bytes,
+                COALESCE(SUM(physical_size), 0)::BIGINT AS physical_bytes
+            FROM blob_compression
+            "#,
+        ))
+        .one(txn)
+        .await?
+        .expect("Aggregate query returned no rows");
+
+        Ok(totals)
+    }
+
+    async fn spool_totals(ctx: &ServiceContext<'_>) -> Result<SpoolTotals> {
+        let txn = ctx.transaction();
+        let totals = SpoolTotals::find_by_statement(Statement::from_string(
+            DatabaseBackend::Postgres,
+            r#"
+            SELECT
+                COUNT(*) AS count,
+                COALESCE(SUM(size), 0)::BIGINT AS bytes,
+                MIN(created_at) AS oldest
+            FROM blob_spool
+            "#,
+        ))
+        .one(txn)
+        .await?
+        .expect("Aggregate query returned no rows");
+
+        Ok(totals)
+    }
+
+    async fn get_spooled(
+        ctx: &ServiceContext<'_>,
+        hash: &[u8],
+    ) -> Result<Option<BlobSpoolModel>> {
+        let txn = ctx.transaction();
+        let spooled = BlobSpool::find_by_id(hash.to_vec()).one(txn).await?;
+        Ok(spooled)
+    }
+
+    /// Falls back to the spool if a blob wasn't found in S3, or S3 is unavailable.
+    ///
+    /// Spooled blobs aren't in S3 yet, so this is needed even when S3 is up.
+    async fn with_spool<T, F>(
+        ctx: &ServiceContext<'_>,
+        hash: &[u8],
+        result: Result<Option<T>>,
+        map: F,
+    ) -> Result<Option<T>>
+    where
+        F: FnOnce(BlobSpoolModel) -> T,
+    {
+        match result {
+            Ok(Some(value)) => Ok(Some(value)),
+            Ok(None) => Ok(Self::get_spooled(ctx, hash).await?.map(map)),
+            Err(error) if s3_unavailable(&error) => {
+                match Self::get_spooled(ctx, hash).await? {
+                    Some(spooled) => Ok(Some(map(spooled))),
+                    None => Err(error),
+                }
+            }
+            Err(error) => Err(error),
+        }
+    }
+
     pub async fn get_optional(
         ctx: &ServiceContext<'_>,
         hash: &[u8],
@@ -134,13 +589,46 @@ impl BlobService {
         // Retrieve blob from S3
         let bucket = ctx.s3_bucket();
         let hex_hash = blob_hash_to_hex(hash);
-        let response = bucket.get_object(&hex_hash).await?;
+        let result = match ctx.until_deadline(bucket.get_object(&hex_hash)).await {
+            Ok(response) => match response.status_code() {
+                200 => Ok(Some(response.into())),
+                404 => Self::get_migrated(ctx, &hex_hash).await,
+                _ => s3_error(&response, "fetching S3 blob"),
+            },
+            Err(error) => Err(error),
+        };
 
-        match response.status_code() {
-            200 => Ok(Some(response.into())),
-            404 => Ok(None),
-            _ => s3_error(&response, "fetching S3 blob"),
+        let result = match result {
+            Ok(Some(data)) => match Self::get_compression(ctx, hash).await? {
+                Some(_) => compression::decompress(&data).map(Some),
+                None => Ok(Some(data)),
+            },
+            other => other,
+        };
+
+        Self::with_spool(ctx, hash, result, |spooled| spooled.data).await
+    }
+
+    /// Gets a blob which isn't in S3 from the migration target, if one is configured.
+    ///
+    /// After switching `S3_BUCKET` to the bucket a migration copied into,
+    /// the old bucket can be set as the target, so that anything the migration
+    /// missed is still served from it.
+    async fn get_migrated(
+        ctx: &ServiceContext<'_>,
+        hex_hash: &str,
+    ) -> Result<Option<Vec<u8>>> {
+        let target = match ctx.blob_migration_target() {
+            Some(target) => target,
+            None => return Ok(None),
+        };
+
+        let data = target.get(ctx, hex_hash).await?;
+        if data.is_some() {
+            debug!("Blob {hex_hash} was read from the migration target");
         }
+
+        Ok(data)
     }
 
     #[inline]
@@ -157,6 +645,7 @@ impl BlobService {
             return Ok(Some(BlobMetadata {
                 mime: str!(EMPTY_BLOB_MIME),
                 size: 0,
+                stored_size: 0,
                 created_at: OffsetDateTime::from_unix_timestamp(EMPTY_BLOB_TIMESTAMP)
                     .unwrap(),
             }));
@@ -164,11 +653,16 @@ impl BlobService {
 
         // Retrieve metadata from S3
         let hex_hash = blob_hash_to_hex(hash);
-        match Self::head(ctx, &hex_hash).await? {
-            None => Ok(None),
-            Some(result) => {
+        let result = match Self::head(ctx, &hex_hash).await {
+            Err(error) => Err(error),
+            Ok(None) => Ok(None),
+            Ok(Some(result)) => {
                 // Headers should be passed in
-                let size = result.content_length.ok_or(Error::S3Response)?;
+                let stored_size = result.content_length.ok_or(Error::S3Response)?;
+                let size = match Self::get_compression(ctx, hash).await? {
+                    Some(compression) => compression.logical_size,
+                    None => stored_size,
+                };
                 let mime = result.content_type.ok_or(Error::S3Response)?;
                 let created_at = {
                     let timestamp = result.last_modified.ok_or(Error::S3Response)?;
@@ -180,10 +674,19 @@ impl BlobService {
                 Ok(Some(BlobMetadata {
                     mime,
                     size,
+                    stored_size,
                     created_at,
                 }))
             }
-        }
+        };
+
+        Self::with_spool(ctx, hash, result, |spooled| BlobMetadata {
+            mime: spooled.mime,
+            size: spooled.size,
+            stored_size: spooled.size,
+            created_at: spooled.created_at,
+        })
+        .await
     }
 
     #[inline]
@@ -203,7 +706,18 @@ impl BlobService {
 
         // Fetch existence from S3
         let hex_hash = blob_hash_to_hex(hash);
-        let result = Self::head(ctx, &hex_hash).await?;
+        let result = match Self::head(ctx, &hex_hash).await {
+            Ok(Some(_)) => Ok(Some(())),
+            Ok(None) => match ctx.blob_migration_target() {
+                Some(target) => target
+                    .size(ctx, &hex_hash)
+                    .await
+                    .map(|size| size.map(|_| ())),
+                None => Ok(None),
+            },
+            Err(error) => Err(error),
+        };
+        let result = Self::with_spool(ctx, hash, result, |_| ()).await?;
         Ok(result.is_some())
     }
 
@@ -231,7 +745,7 @@ impl BlobService {
         hex_hash: &str,
     ) -> Result<Option<HeadObjectResult>> {
         let bucket = ctx.s3_bucket();
-        let (result, status) = bucket.head_object(hex_hash).await?;
+        let (result, status) = ctx.until_deadline(bucket.head_object(hex_hash)).await?;
 
         match status {
             200 | 204 => Ok(Some(result)),
@@ -240,6 +754,594 @@ impl BlobService {
         }
     }
 
+    /// Creates the tombstone blob, if it does not already exist.
+    ///
+    /// This is a regular blob, so references to it behave like any other file.
+    #[inline]
+    pub async fn create_tombstone(ctx: &ServiceContext<'_>) -> Result<CreateBlobOutput> {
+        Self::create(ctx, TOMBSTONE_BLOB_DATA).await
+    }
+
+    /// Determines if this blob has contents which can be purged.
+    ///
+    /// The empty blob and the tombstone blob have nothing to remove.
+    pub fn is_purgeable(hash: &[u8]) -> bool {
+        hash != EMPTY_BLOB_HASH && hash != sha512_hash(TOMBSTONE_BLOB_DATA)
+    }
+
+    /// Finds everything which uses this blob, across all sites.
+    ///
+    /// Each of these is a reference to the blob, so it may only be
+    /// deleted if this is empty.
+    pub async fn find_duplicates(
+        ctx: &ServiceContext<'_>,
+        hash: &[u8],
+    ) -> Result<BlobReferences> {
+        let txn = ctx.transaction();
+        let files = BlobFileReference::find_by_statement(Statement::from_sql_and_values(
+            DatabaseBackend::Postgres,
+            FIND_FILE_REFERENCES_SQL,
+            [hash.to_vec().into()],
+        ))
+        .all(txn)
+        .await?;
+
+        let user_avatars = User::find()
+            .select_only()
+            .column(user::Column::UserId)
+            .filter(user::Column::AvatarS3Hash.eq(hash.to_vec()))
+            .order_by_asc(user::Column::UserId)
+            .into_tuple()
+            .all(txn)
+            .await?;
+
+        let exports = Export::find()
+            .select_only()
+            .column(export::Column::ExportId)
+            .filter(export::Column::S3Hash.eq(hash.to_vec()))
+            .order_by_asc(export::Column::ExportId)
+            .into_tuple()
+            .all(txn)
+            .await?;
+
+        let staged_files = FileStaging::find()
+            .select_only()
+            .column(file_staging::Column::StagedFileId)
+            .filter(file_staging::Column::S3Hash.eq(hash.to_vec()))
+            .order_by_asc(file_staging::Column::StagedFileId)
+            .into_tuple()
+            .all(txn)
+            .await?;
+
+        let thumbnails = FileThumbnail::find()
+            .filter(file_thumbnail::Column::ThumbnailS3Hash.eq(hash.to_vec()))
+            .count(txn)
+            .await?;
+
+        let derivatives = FileDerivative::find()
+            .filter(file_derivative::Column::DerivativeS3Hash.eq(hash.to_vec()))
+            .count(txn)
+            .await?;
+
+        Ok(BlobReferences {
+            files,
+            user_avatars,
+            exports,
+            staged_files,
+            thumbnails: thumbnails as i64,
+            derivatives: derivatives as i64,
+        })
+    }
+
+    /// Deletes this blob if nothing uses it anymore.
+    ///
+    /// This should be called after a reference to a blob is dropped, such as
+    /// a user changing their avatar. The empty and tombstone blobs are shared
+    /// placeholders, and are never deleted.
+    ///
+    /// Returns whether the blob was deleted.
+    pub async fn release(ctx: &ServiceContext<'_>, hash: &[u8]) -> Result<bool> {
+        if !Self::is_purgeable(hash) {
+            debug!("Not releasing a placeholder blob");
+            return Ok(false);
+        }
+
+        let references = Self::find_duplicates(ctx, hash).await?;
+        if !references.is_empty() {
+            debug!(
+                "Blob is still used ({} references), not deleting",
+                references.count(),
+            );
+            return Ok(false);
+        }
+
+        debug!("Last reference to blob dropped, deleting");
+        Self::hard_delete(ctx, hash).await?;
+        Ok(true)
+    }
+
+    /// Deletes every blob in S3 which nothing refers to, and saves how much was reclaimed.
+    ///
+    /// Blobs are normally deleted by `release()`, but some can be missed, such as
+    /// one written for an upload whose transaction then failed. Recently written
+    /// blobs are skipped, since whatever refers to them may not be committed yet.
+    ///
+    /// If `dry_run` is set, nothing is deleted, and the report lists what would be.
+    /// `requested_by` is `None` when this is run by the recurring job.
+    pub async fn collect_garbage(
+        ctx: &ServiceContext<'_>,
+        requested_by: Option<i64>,
+        dry_run: bool,
+    ) -> Result<BlobCollectionModel> {
+        info!("Collecting unreferenced blobs (dry run: {dry_run})");
+
+        let bucket = ctx.s3_bucket();
+        let cutoff = now() - ctx.config().blob_collection_minimum_age;
+        let mut continuation_token = None;
+        let mut blobs_scanned = 0;
+        let mut blobs_deleted = 0;
+        let mut bytes_reclaimed = 0;
+
+        loop {
+            let request = bucket.list_page(
+                String::new(),
+                None,
+                continuation_token,
+                None,
+                Some(COLLECTION_BATCH_SIZE),
+            );
+            let (page, _) = ctx.until_deadline(request).await?;
+
+            // Only consider blobs old enough that anything using them is committed
+            let candidates = page
+                .contents
+                .iter()
+                .filter_map(|object| {
+                    let hash = parse_blob_key(&object.key)?;
+                    let modified =
+                        OffsetDateTime::parse(&object.last_modified, &Rfc3339).ok()?;
+
+                    if modified > cutoff || !Self::is_purgeable(&hash) {
+                        return None;
+                    }
+
+                    Some((hash, object.size))
+                })
+                .collect::<Vec<_>>();
+
+            blobs_scanned += page.contents.len() as i64;
+            debug!(
+                "Checking {} of {} listed blobs for references",
+                candidates.len(),
+                page.contents.len(),
+            );
+
+            let hashes = candidates
+                .iter()
+                .map(|(hash, _)| hash.clone())
+                .collect::<Vec<_>>();
+            let referenced = Self::find_referenced(ctx, hashes).await?;
+
+            for (hash, size) in candidates {
+                if referenced.contains(&hash) {
+                    continue;
+                }
+
+                debug!(
+                    "Blob {} is unreferenced ({size} bytes)",
+                    blob_hash_to_hex(&hash),
+                );
+
+                if !dry_run {
+                    Self::hard_delete(ctx, &hash).await?;
+
+                    // Its thumbnails and derivatives become unreferenced,
+                    // and are collected next time
+                    FileThumbnail::delete_many()
+                        .filter(file_thumbnail::Column::S3Hash.eq(hash.clone()))
+                        .exec(ctx.transaction())
+                        .await?;
+
+                    FileDerivative::delete_many()
+                        .filter(file_derivative::Column::S3Hash.eq(hash.clone()))
+                        .exec(ctx.transaction())
+                        .await?;
+                }
+
+                blobs_deleted += 1;
+                bytes_reclaimed += size as i64;
+            }
+
+            match page.next_continuation_token {
+                Some(token) if page.is_truncated => continuation_token = Some(token),
+                _ => break,
+            }
+        }
+
+        info!(
+            "Blob collection finished, {blobs_deleted} of {blobs_scanned} blobs unreferenced ({bytes_reclaimed} bytes)",
+        );
+
+        let txn = ctx.transaction();
+        let model = blob_collection::ActiveModel {
+            requested_by: Set(requested_by),
+            dry_run: Set(dry_run),
+            blobs_scanned: Set(blobs_scanned),
+            blobs_deleted: Set(blobs_deleted),
+            bytes_reclaimed: Set(bytes_reclaimed),
+            ..Default::default()
+        };
+
+        let collection = model.insert(txn).await?;
+        Ok(collection)
+    }
+
+    /// Gets the most recent garbage collection results, newest first.
+    pub async fn get_collections(
+        ctx: &ServiceContext<'_>,
+        limit: u64,
+    ) -> Result<Vec<BlobCollectionModel>> {
+        let txn = ctx.transaction();
+        let collections = BlobCollection::find()
+            .order_by_desc(blob_collection::Column::CreatedAt)
+            .order_by_desc(blob_collection::Column::CollectionId)
+            .limit(limit)
+            .all(txn)
+            .await?;
+
+        Ok(collections)
+    }
+
+    /// Starts copying every object in the S3 bucket to the migration target.
+    ///
+    /// Only one migration runs at a time. If one to the same target is already
+    /// in progress, its job is queued again and it's returned instead, which
+    /// resumes a migration whose job was lost. One to a different target is
+    /// abandoned, being marked as completed without finishing.
+    pub async fn start_migration(
+        ctx: &ServiceContext<'_>,
+        StartBlobMigration { acting_user_id }: StartBlobMigration,
+    ) -> Result<BlobMigrationModel> {
+        let target = ctx
+            .blob_migration_target()
+            .ok_or(Error::BlobMigrationNotConfigured)?
+            .describe();
+
+        let txn = ctx.transaction();
+        let current = BlobMigration::find()
+            .filter(blob_migration::Column::CompletedAt.is_null())
+            .one(txn)
+            .await?;
+
+        let migration = match current {
+            Some(migration) if migration.target == target => {
+                info!(
+                    "Resuming blob migration ID {} to {target}",
+                    migration.migration_id,
+                );
+                migration
+            }
+            current => {
+                if let Some(migration) = current {
+                    warn!(
+                        "Abandoning blob migration ID {} to {}, the target has changed",
+                        migration.migration_id, migration.target,
+                    );
+
+                    let model = blob_migration::ActiveModel {
+                        migration_id: Set(migration.migration_id),
+                        completed_at: Set(Some(now())),
+                        ..Default::default()
+                    };
+                    model.update(txn).await?;
+                }
+
+                info!("Starting blob migration to {target}");
+                let model = blob_migration::ActiveModel {
+                    requested_by: Set(acting_user_id),
+                    target: Set(target),
+                    ..Default::default()
+                };
+                model.insert(txn).await?
+            }
+        };
+
+        JobService::queue_job(
+            ctx,
+            &Job::MigrateBlobs {
+                migration_id: migration.migration_id,
+            },
+            None,
+        )
+        .await?;
+
+        Ok(migration)
+    }
+
+    pub async fn get_migration_optional(
+        ctx: &ServiceContext<'_>,
+        migration_id: i64,
+    ) -> Result<Option<BlobMigrationModel>> {
+        let txn = ctx.transaction();
+        let migration = BlobMigration::find_by_id(migration_id).one(txn).await?;
+        Ok(migration)
+    }
+
+    #[inline]
+    pub async fn get_migration(
+        ctx: &ServiceContext<'_>,
+        migration_id: i64,
+    ) -> Result<BlobMigrationModel> {
+        find_or_error!(
+            Self::get_migration_optional(ctx, migration_id),
+            BlobMigration
+        )
+    }
+
+    /// Copies the next batch of objects in a blob migration. Called by the job worker.
+    ///
+    /// Objects are copied in order of key, starting after the last one copied,
+    /// and those already in the target with the same size are skipped. Each copy
+    /// is read back and checked against the blob's hash, and is deleted if it
+    /// doesn't match, with its key recorded as failed so it can be looked into.
+    ///
+    /// A batch ends early once enough bytes have been copied, per the config.
+    ///
+    /// # Returns
+    /// Whether there are more objects remaining, in which case
+    /// this should be called again.
+    pub async fn migrate(ctx: &ServiceContext<'_>, migration_id: i64) -> Result<bool> {
+        let txn = ctx.transaction();
+        let config = ctx.config();
+
+        // Lock the run, in case its job was queued more than once
+        let migration = BlobMigration::find_by_id(migration_id)
+            .lock_exclusive()
+            .one(txn)
+            .await?
+            .ok_or(Error::BlobMigrationNotFound)?;
+
+        if migration.completed_at.is_some() {
+            warn!("Blob migration ID {migration_id} already completed");
+            return Ok(false);
+        }
+
+        let target = match ctx.blob_migration_target() {
+            Some(target) if target.describe() == migration.target => target,
+            _ => {
+                warn!(
+                    "Blob migration target is no longer {}, stopping migration ID {migration_id}",
+                    migration.target,
+                );
+                return Ok(false);
+            }
+        };
+
+        let bucket = ctx.s3_bucket();
+        let request = bucket.list_page(
+            String::new(),
+            None,
+            None,
+            migration.last_key.clone(),
+            Some(config.blob_migration_batch_size),
+        );
+        let (page, _) = ctx.until_deadline(request).await?;
+
+        let mut last_key = migration.last_key;
+        let mut objects_copied = 0;
+        let mut objects_skipped = 0;
+        let mut bytes_copied = 0;
+        let mut failed_keys = migration.failed_keys;
+        let mut stopped_early = false;
+
+        for object in &page.contents {
+            last_key = Some(object.key.clone());
+
+            // Uploads aren't blobs, and are only kept for a short time
+            let hash = match parse_migration_key(&object.key) {
+                Some(hash) => hash,
+                None => continue,
+            };
+
+            let outcome =
+                Self::migrate_object(ctx, target, &object.key, &hash, object.size)
+                    .await?;
+
+            match outcome {
+                MigrateOutcome::Copied => {
+                    objects_copied += 1;
+                    bytes_copied += object.size;
+                }
+                MigrateOutcome::Skipped => objects_skipped += 1,
+                MigrateOutcome::Failed => failed_keys.push(object.key.clone()),
+            }
+
+            if bytes_copied >= config.blob_migration_batch_bytes {
+                stopped_early = true;
+                break;
+            }
+        }
+
+        let done = !stopped_early && !page.is_truncated;
+        debug!(
+            "Copied {objects_copied} objects ({bytes_copied} bytes, {objects_skipped} skipped) in blob migration ID {migration_id}",
+        );
+
+        let model = blob_migration::ActiveModel {
+            migration_id: Set(migration_id),
+            updated_at: Set(Some(now())),
+            completed_at: Set(done.then(now)),
+            last_key: Set(last_key),
+            objects_copied: Set(migration.objects_copied + objects_copied),
+            objects_skipped: Set(migration.objects_skipped + objects_skipped),
+            bytes_copied: Set(migration.bytes_copied + bytes_copied as i64),
+            failed_keys: Set(failed_keys),
+            ..Default::default()
+        };
+        let migration = model.update(txn).await?;
+
+        if done {
+            info!(
+                "Blob migration ID {migration_id} completed, {} objects failed verification",
+                migration.failed_keys.len(),
+            );
+        }
+
+        Ok(!done)
+    }
+
+    /// Copies one object to the migration target, and verifies the copy.
+    async fn migrate_object(
+        ctx: &ServiceContext<'_>,
+        target: &BlobStore,
+        key: &str,
+        hash: &[u8],
+        size: u64,
+    ) -> Result<MigrateOutcome> {
+        if target.size(ctx, key).await? == Some(size as i64) {
+            return Ok(MigrateOutcome::Skipped);
+        }
+
+        // Deleted since it was listed
+        let mime = match Self::head(ctx, key).await? {
+            Some(result) => result.content_type.ok_or(Error::S3Response)?,
+            None => return Ok(MigrateOutcome::Skipped),
+        };
+
+        let bucket = ctx.s3_bucket();
+        let response = ctx.until_deadline(bucket.get_object(key)).await?;
+        let data: Vec<u8> = match response.status_code() {
+            200 => response.into(),
+            404 => return Ok(MigrateOutcome::Skipped),
+            _ => return s3_error(&response, "fetching S3 object to migrate"),
+        };
+
+        target.put(ctx, key, &data, &mime).await?;
+
+        // Read it back and check it against the hash, which is of the
+        // uncompressed data, so compressed blobs must be decompressed first
+        let copy = target.get(ctx, key).await?.unwrap_or_default();
+        let compressed = parse_blob_key(key).is_some()
+            && Self::get_compression(ctx, hash).await?.is_some();
+
+        let verified = if compressed {
+            match compression::decompress(&copy) {
+                Ok(copy) => sha512_hash(&copy).as_slice() == hash,
+                Err(_) => false,
+            }
+        } else {
+            sha512_hash(&copy).as_slice() == hash
+        };
+
+        if !verified {
+            error!("Copy of {key} in the migration target does not match its hash");
+            target.delete(ctx, key).await?;
+            return Ok(MigrateOutcome::Failed);
+        }
+
+        Ok(MigrateOutcome::Copied)
+    }
+
+    /// Writes an object to the migration target too, if one is configured.
+    ///
+    /// This keeps the target current with blobs written after a migration has
+    /// already listed past them. The bucket remains the source of truth, so
+    /// failures are only logged, and running the migration again copies
+    /// anything which was missed.
+    async fn mirror(ctx: &ServiceContext<'_>, key: &str, data: &[u8], mime: &str) {
+        let target = match ctx.blob_migration_target() {
+            Some(target) => target,
+            None => return,
+        };
+
+        if ctx.check_only() {
+            debug!("Not writing to migration target, this is a dry run");
+            return;
+        }
+
+        if let Err(error) = target.put(ctx, key, data, mime).await {
+            warn!("Unable to write {key} to migration target: {error}");
+        }
+    }
+
+    /// Of the given blobs, finds which are used by anything.
+    ///
+    /// This is the batched form of `find_duplicates()`, for when
+    /// only whether a blob is used matters, not where.
+    async fn find_referenced(
+        ctx: &ServiceContext<'_>,
+        hashes: Vec<Vec<u8>>,
+    ) -> Result<HashSet<Vec<u8>>> {
+        if hashes.is_empty() {
+            return Ok(HashSet::new());
+        }
+
+        let txn = ctx.transaction();
+        let mut referenced = HashSet::new();
+
+        let revisions: Vec<Vec<u8>> = FileRevision::find()
+            .select_only()
+            .column(file_revision::Column::S3Hash)
+            .distinct()
+            .filter(file_revision::Column::S3Hash.is_in(hashes.clone()))
+            .into_tuple()
+            .all(txn)
+            .await?;
+
+        let avatars: Vec<Vec<u8>> = User::find()
+            .select_only()
+            .column(user::Column::AvatarS3Hash)
+            .distinct()
+            .filter(user::Column::AvatarS3Hash.is_in(hashes.clone()))
+            .into_tuple()
+            .all(txn)
+            .await?;
+
+        let exports: Vec<Vec<u8>> = Export::find()
+            .select_only()
+            .column(export::Column::S3Hash)
+            .distinct()
+            .filter(export::Column::S3Hash.is_in(hashes.clone()))
+            .into_tuple()
+            .all(txn)
+            .await?;
+
+        let staged_files: Vec<Vec<u8>> = FileStaging::find()
+            .select_only()
+            .column(file_staging::Column::S3Hash)
+            .distinct()
+            .filter(file_staging::Column::S3Hash.is_in(hashes.clone()))
+            .into_tuple()
+            .all(txn)
+            .await?;
+
+        let thumbnails: Vec<Vec<u8>> = FileThumbnail::find()
+            .select_only()
+            .column(file_thumbnail::Column::ThumbnailS3Hash)
+            .distinct()
+            .filter(file_thumbnail::Column::ThumbnailS3Hash.is_in(hashes.clone()))
+            .into_tuple()
+            .all(txn)
+            .await?;
+
+        let derivatives: Vec<Vec<u8>> = FileDerivative::find()
+            .select_only()
+            .column(file_derivative::Column::DerivativeS3Hash)
+            .distinct()
+            .filter(file_derivative::Column::DerivativeS3Hash.is_in(hashes))
+            .into_tuple()
+            .all(txn)
+            .await?;
+
+        referenced.extend(revisions);
+        referenced.extend(avatars);
+        referenced.extend(exports);
+        referenced.extend(staged_files);
+        referenced.extend(thumbnails);
+        referenced.extend(derivatives);
+        Ok(referenced)
+    }
+
     pub async fn hard_delete(ctx: &ServiceContext<'_>, hash: &[u8]) -> Result<()> {
         // Special handling for empty blobs
         //
@@ -250,20 +1352,258 @@ impl BlobService {
             return Ok(());
         }
 
+        if ctx.check_only() {
+            debug!("Not deleting blob, this is a dry run");
+            return Ok(());
+        }
+
+        // Delete from the spool, in case it hasn't been flushed yet
+        let txn = ctx.transaction();
+        BlobSpool::delete_by_id(hash.to_vec()).exec(txn).await?;
+
         // Delete from S3
         let bucket = ctx.s3_bucket();
         let hex_hash = blob_hash_to_hex(hash);
 
-        let response = bucket.delete_object(&hex_hash).await?;
+        // Along with the uncompressed copy, if it was compressed
+        if let Some(compression) = Self::get_compression(ctx, hash).await? {
+            if compression.has_uncompressed_copy {
+                Self::delete_upload(ctx, &uncompressed_key(&hex_hash)).await?;
+            }
+
+            BlobCompression::delete_by_id(hash.to_vec())
+                .exec(txn)
+                .await?;
+        }
+
+        // And from the migration target, so it isn't still served from there
+        if let Some(target) = ctx.blob_migration_target() {
+            for key in [hex_hash.to_string(), uncompressed_key(&hex_hash)] {
+                if let Err(error) = target.delete(ctx, &key).await {
+                    warn!("Unable to delete {key} from migration target: {error}");
+                }
+            }
+        }
+
+        let response = ctx.until_deadline(bucket.delete_object(&hex_hash)).await?;
         match response.status_code() {
             204 => Ok(()),
             _ => s3_error(&response, "hard-deleting S3 blob"),
         }
     }
+
+    /// Creates a presigned URL which a client can upload an object to directly.
+    ///
+    /// The object is stored at the given path, not by hash, since its contents
+    /// can't be trusted until they are checked. Since the path isn't a blob hash,
+    /// garbage collection leaves it alone, so it must be removed explicitly.
+    pub fn presign_upload(
+        ctx: &ServiceContext<'_>,
+        path: &str,
+        expiry: StdDuration,
+    ) -> Result<String> {
+        let bucket = ctx.s3_bucket();
+        let expiry_secs = u32::try_from(expiry.as_secs())
+            .expect("Upload URL expiry exceeds u32 seconds");
+
+        let url = bucket.presign_put(path, expiry_secs, None)?;
+        Ok(url)
+    }
+
+    /// Creates a presigned URL which a client can download a blob from directly.
+    ///
+    /// The response is served with the given name and MIME type, rather than
+    /// the blob's hash. Returns `None` if the blob is not in S3, which is the
+    /// case for the empty blob, and for any still in the spool. Such blobs must
+    /// be fetched through DEEPWELL instead.
+    pub async fn presign_download(
+        ctx: &ServiceContext<'_>,
+        hash: &[u8],
+        name: &str,
+        mime: &str,
+        expiry: StdDuration,
+    ) -> Result<Option<String>> {
+        if hash == EMPTY_BLOB_HASH || Self::get_spooled(ctx, hash).await?.is_some() {
+            debug!("Blob is not in S3, cannot presign a download URL");
+            return Ok(None);
+        }
+
+        // S3 can't decompress blobs itself, so those are served from a copy
+        let hex_hash = blob_hash_to_hex(hash);
+        let key = match Self::get_compression(ctx, hash).await? {
+            None => hex_hash.to_string(),
+            Some(compression) => {
+                Self::ensure_uncompressed_copy(ctx, compression, mime).await?
+            }
+        };
+
+        let bucket = ctx.s3_bucket();
+        let expiry_secs = u32::try_from(expiry.as_secs())
+            .expect("Download URL expiry exceeds u32 seconds");
+
+        // SVGs opened directly can run scripts, so have them downloaded instead.
+        // This doesn't affect images shown in pages, which ignore this header.
+        let disposition = if mime.starts_with("image/svg+xml") {
+            "attachment"
+        } else {
+            "inline"
+        };
+
+        let name = name.replace('"', "");
+        let queries = HashMap::from([
+            (
+                str!("response-content-disposition"),
+                format!("{disposition}; filename=\"{name}\""),
+            ),
+            (str!("response-content-type"), str!(mime)),
+        ]);
+
+        let url = bucket.presign_get(key, expiry_secs, Some(queries))?;
+        Ok(Some(url))
+    }
+
+    /// Writes an uncompressed copy of a compressed blob, if there isn't one yet.
+    ///
+    /// Returns the S3 key of the copy. Like uploads, copies aren't blobs,
+    /// so they're removed by `hard_delete()` along with the blob itself.
+    async fn ensure_uncompressed_copy(
+        ctx: &ServiceContext<'_>,
+        compression: BlobCompressionModel,
+        mime: &str,
+    ) -> Result<String> {
+        let key = uncompressed_key(&blob_hash_to_hex(&compression.s3_hash));
+        if compression.has_uncompressed_copy {
+            return Ok(key);
+        }
+
+        if ctx.check_only() {
+            debug!("Not writing uncompressed blob copy, this is a dry run");
+            return Ok(key);
+        }
+
+        debug!("Writing uncompressed copy of blob to {key}");
+        let data = Self::get(ctx, &compression.s3_hash).await?;
+        let bucket = ctx.s3_bucket();
+        let response = ctx
+            .until_deadline(bucket.put_object_with_content_type(&key, &data, mime))
+            .await?;
+
+        if response.status_code() != 200 {
+            return s3_error(&response, "writing uncompressed S3 blob copy");
+        }
+
+        Self::mirror(ctx, &key, &data, mime).await;
+
+        let txn = ctx.transaction();
+        let model = blob_compression::ActiveModel {
+            s3_hash: Set(compression.s3_hash),
+            has_uncompressed_copy: Set(true),
+            ..Default::default()
+        };
+        model.update(txn).await?;
+
+        Ok(key)
+    }
+
+    /// Gets the size of an object uploaded via `presign_upload()`, if it exists.
+    pub async fn get_upload_size(
+        ctx: &ServiceContext<'_>,
+        path: &str,
+    ) -> Result<Option<i64>> {
+        match Self::head(ctx, path).await? {
+            Some(result) => Ok(Some(result.content_length.ok_or(Error::S3Response)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Gets the contents of an object uploaded via `presign_upload()`, if it exists.
+    pub async fn get_upload(
+        ctx: &ServiceContext<'_>,
+        path: &str,
+    ) -> Result<Option<Vec<u8>>> {
+        let bucket = ctx.s3_bucket();
+        let response = ctx.until_deadline(bucket.get_object(path)).await?;
+        match response.status_code() {
+            200 => Ok(Some(response.into())),
+            404 => Ok(None),
+            _ => s3_error(&response, "fetching uploaded S3 object"),
+        }
+    }
+
+    /// Stores one chunk of a chunked upload at the given path.
+    ///
+    /// Like objects sent to a presigned URL, these aren't blobs,
+    /// and must be removed with `delete_upload()` once they're used.
+    pub async fn put_upload(
+        ctx: &ServiceContext<'_>,
+        path: &str,
+        data: &[u8],
+    ) -> Result<()> {
+        if ctx.check_only() {
+            debug!("Not uploading chunk, this is a dry run");
+            return Ok(());
+        }
+
+        let bucket = ctx.s3_bucket();
+        let response = ctx.until_deadline(bucket.put_object(path, data)).await?;
+        match response.status_code() {
+            200 => Ok(()),
+            _ => s3_error(&response, "uploading chunk to S3"),
+        }
+    }
+
+    /// Deletes an object uploaded via `presign_upload()` or `put_upload()`.
+    ///
+    /// Objects which were never uploaded are ignored.
+    pub async fn delete_upload(ctx: &ServiceContext<'_>, path: &str) -> Result<()> {
+        if ctx.check_only() {
+            debug!("Not deleting uploaded object, this is a dry run");
+            return Ok(());
+        }
+
+        let bucket = ctx.s3_bucket();
+        let response = ctx.until_deadline(bucket.delete_object(path)).await?;
+        match response.status_code() {
+            204 | 404 => Ok(()),
+            _ => s3_error(&response, "deleting uploaded S3 object"),
+        }
+    }
+}
+
+/// Gets the blob hash from an S3 object key, if it is one.
+///
+/// Blobs are stored under their hex hash, so anything else
+/// in the bucket isn't a blob and must be left alone.
+fn parse_blob_key(key: &str) -> Option<Vec<u8>> {
+    match hex::decode(key) {
+        Ok(hash) if hash.len() == BLOB_HASH_LENGTH => Some(hash),
+        _ => None,
+    }
+}
+
+/// Gets the blob hash from an S3 object key, if it's one a migration copies.
+///
+/// This is either a blob, or the uncompressed copy of one.
+fn parse_migration_key(key: &str) -> Option<Vec<u8>> {
+    let hex_hash = key.strip_prefix("uncompressed/").unwrap_or(key);
+    parse_blob_key(hex_hash)
+}
+
+/// Gets the S3 key for the uncompressed copy of a compressed blob.
+///
+/// This isn't a valid hex hash, so garbage collection doesn't mistake it for a blob.
+fn uncompressed_key(hex_hash: &str) -> String {
+    format!("uncompressed/{hex_hash}")
+}
+
+/// Whether this error means S3 couldn't be reached or is failing,
+/// rather than it having rejected the request.
+fn s3_unavailable(error: &Error) -> bool {
+    matches!(error, Error::S3Service(_) | Error::S3Unavailable)
 }
 
 /// Helper method to parse out an S3 error response and print the message (if any).
-fn s3_error<T>(response: &ResponseData, action: &str) -> Result<T> {
+pub(super) fn s3_error<T>(response: &ResponseData, action: &str) -> Result<T> {
     let error_message = match str::from_utf8(response.bytes()) {
         Ok("") => "(no content)",
         Ok(m) => m,
@@ -278,5 +1618,9 @@ fn s3_error<T>(response: &ResponseData, action: &str) -> Result<T> {
     );
 
     // TODO replace with S3 backend-specific error
-    Err(Error::S3Response)
+    if response.status_code() >= 500 {
+        Err(Error::S3Unavailable)
+    } else {
+        Err(Error::S3Response)
+    }
 }
@@ -0,0 +1,188 @@
+/*
+ * services/blob/store.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Storage backends other than the main S3 bucket.
+//!
+//! These are used as the target of a blob migration. Objects are kept under
+//! the same keys as in the bucket, so a completed migration to another bucket
+//! can be switched to by changing `S3_BUCKET`.
+
+use super::prelude::*;
+use super::service::s3_error;
+use s3::bucket::Bucket;
+use std::io;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+#[derive(Debug)]
+pub enum BlobStore {
+    S3(Box<Bucket>),
+    Filesystem(PathBuf),
+}
+
+impl BlobStore {
+    /// Describes this backend, for recording which one a migration went to.
+    pub fn describe(&self) -> String {
+        match self {
+            BlobStore::S3(bucket) => format!("s3://{}", bucket.name),
+            BlobStore::Filesystem(root) => format!("file://{}", root.display()),
+        }
+    }
+
+    pub async fn get(
+        &self,
+        ctx: &ServiceContext<'_>,
+        key: &str,
+    ) -> Result<Option<Vec<u8>>> {
+        match self {
+            BlobStore::S3(bucket) => {
+                let response = ctx.until_deadline(bucket.get_object(key)).await?;
+                match response.status_code() {
+                    200 => Ok(Some(response.into())),
+                    404 => Ok(None),
+                    _ => s3_error(&response, "fetching object from migration target"),
+                }
+            }
+            BlobStore::Filesystem(root) => match fs::read(object_path(root, key)).await {
+                Ok(data) => Ok(Some(data)),
+                Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+                Err(error) => Err(Error::BlobFilesystem(error)),
+            },
+        }
+    }
+
+    /// Gets the stored size of an object, if it exists.
+    pub async fn size(&self, ctx: &ServiceContext<'_>, key: &str) -> Result<Option<i64>> {
+        match self {
+            BlobStore::S3(bucket) => {
+                let (result, status) =
+                    ctx.until_deadline(bucket.head_object(key)).await?;
+
+                match status {
+                    200 | 204 => {
+                        Ok(Some(result.content_length.ok_or(Error::S3Response)?))
+                    }
+                    404 => Ok(None),
+                    _ => Err(Error::S3Response),
+                }
+            }
+            BlobStore::Filesystem(root) => {
+                match fs::metadata(object_path(root, key)).await {
+                    Ok(metadata) => Ok(Some(metadata.len() as i64)),
+                    Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+                    Err(error) => Err(Error::BlobFilesystem(error)),
+                }
+            }
+        }
+    }
+
+    /// Stores an object, replacing any existing one.
+    ///
+    /// Files are written under a temporary name and then renamed,
+    /// so a partially written object is never visible.
+    pub async fn put(
+        &self,
+        ctx: &ServiceContext<'_>,
+        key: &str,
+        data: &[u8],
+        mime: &str,
+    ) -> Result<()> {
+        match self {
+            BlobStore::S3(bucket) => {
+                let response = ctx
+                    .until_deadline(bucket.put_object_with_content_type(key, data, mime))
+                    .await?;
+
+                match response.status_code() {
+                    200 => Ok(()),
+                    _ => s3_error(&response, "writing object to migration target"),
+                }
+            }
+            BlobStore::Filesystem(root) => {
+                let path = object_path(root, key);
+                let temp_path = path.with_extension("partial");
+                let write = async {
+                    if let Some(parent) = path.parent() {
+                        fs::create_dir_all(parent).await?;
+                    }
+
+                    fs::write(&temp_path, data).await?;
+                    fs::rename(&temp_path, &path).await
+                };
+
+                write.await.map_err(Error::BlobFilesystem)
+            }
+        }
+    }
+
+    /// Deletes an object. Objects which don't exist are ignored.
+    pub async fn delete(&self, ctx: &ServiceContext<'_>, key: &str) -> Result<()> {
+        match self {
+            BlobStore::S3(bucket) => {
+                let response = ctx.until_deadline(bucket.delete_object(key)).await?;
+                match response.status_code() {
+                    204 | 404 => Ok(()),
+                    _ => s3_error(&response, "deleting object from migration target"),
+                }
+            }
+            BlobStore::Filesystem(root) => {
+                match fs::remove_file(object_path(root, key)).await {
+                    Ok(()) => Ok(()),
+                    Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(()),
+                    Err(error) => Err(Error::BlobFilesystem(error)),
+                }
+            }
+        }
+    }
+}
+
+/// Gets the path of an object in a filesystem backend.
+///
+/// Objects are split into subdirectories by the first two characters of
+/// their name, to keep any one directory from holding millions of files.
+fn object_path(root: &Path, key: &str) -> PathBuf {
+    let (directory, name) = match key.rsplit_once('/') {
+        Some((directory, name)) => (Some(directory), name),
+        None => (None, key),
+    };
+
+    let mut path = root.to_path_buf();
+    if let Some(directory) = directory {
+        path.push(directory);
+    }
+
+    path.push(name.get(..2).unwrap_or(name));
+    path.push(name);
+    path
+}
+
+#[test]
+fn object_paths() {
+    let root = Path::new("/srv/blobs");
+
+    assert_eq!(
+        object_path(root, "abcdef"),
+        PathBuf::from("/srv/blobs/ab/abcdef"),
+    );
+    assert_eq!(
+        object_path(root, "uncompressed/abcdef"),
+        PathBuf::from("/srv/blobs/uncompressed/ab/abcdef"),
+    );
+}
@@ -19,6 +19,7 @@
  */
 
 use super::prelude::*;
+use sea_orm::FromQueryResult;
 use time::OffsetDateTime;
 
 #[derive(Debug)]
@@ -33,5 +34,91 @@ pub struct CreateBlobOutput {
 pub struct BlobMetadata {
     pub mime: String,
     pub size: i64,
+
+    /// How much space the blob takes up in S3.
+    ///
+    /// This is smaller than `size` if it is stored compressed.
+    pub stored_size: i64,
     pub created_at: OffsetDateTime,
 }
+
+#[derive(Serialize, Debug, Clone)]
+pub struct BlobStorageStatus {
+    /// Whether S3 responded to a request.
+    pub available: bool,
+    pub spool_enabled: bool,
+
+    /// Uploads made while S3 was unavailable which haven't been written to it yet.
+    pub spooled_blobs: u64,
+    pub spooled_bytes: u64,
+    pub oldest_spooled_at: Option<OffsetDateTime>,
+
+    /// Blobs stored compressed, and their sizes before and after.
+    pub compression_enabled: bool,
+    pub compressed_blobs: u64,
+    pub compressed_logical_bytes: u64,
+    pub compressed_physical_bytes: u64,
+}
+
+/// Everywhere a blob is used.
+///
+/// Blobs are addressed by hash, so the same data uploaded to
+/// different sites, or set as an avatar, shares a single blob.
+#[derive(Serialize, Debug, Default)]
+pub struct BlobReferences {
+    pub files: Vec<BlobFileReference>,
+    pub user_avatars: Vec<i64>,
+    pub exports: Vec<i64>,
+
+    /// Files in users' staging areas, which aren't on a page yet.
+    pub staged_files: Vec<i64>,
+
+    /// How many images have this blob as one of their thumbnails.
+    pub thumbnails: i64,
+
+    /// How many images have this blob as a copy converted to another format.
+    pub derivatives: i64,
+}
+
+impl BlobReferences {
+    /// The number of rows which refer to this blob.
+    pub fn count(&self) -> i64 {
+        let revisions: i64 = self.files.iter().map(|file| file.revisions).sum();
+        let others =
+            self.user_avatars.len() + self.exports.len() + self.staged_files.len();
+        revisions + others as i64 + self.thumbnails + self.derivatives
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+            && self.user_avatars.is_empty()
+            && self.exports.is_empty()
+            && self.staged_files.is_empty()
+            && self.thumbnails == 0
+            && self.derivatives == 0
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct RunBlobCollection {
+    pub acting_user_id: i64,
+
+    /// Whether to only report unreferenced blobs, rather than deleting them.
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct StartBlobMigration {
+    pub acting_user_id: i64,
+}
+
+#[derive(Serialize, FromQueryResult, Debug)]
+pub struct BlobFileReference {
+    pub site_id: i64,
+    pub file_id: i64,
+
+    /// How many revisions of this file have this blob as their contents.
+    pub revisions: i64,
+}
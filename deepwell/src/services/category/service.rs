@@ -22,6 +22,8 @@ use super::prelude::*;
 use crate::models::page_category::{
     self, Entity as PageCategory, Model as PageCategoryModel,
 };
+use crate::services::OutdateService;
+use crate::utils::is_http_url;
 
 #[derive(Debug)]
 pub struct CategoryService;
@@ -108,4 +110,136 @@ impl CategoryService {
 
         Ok(categories)
     }
+
+    /// Sets the default search engine indexing settings for pages in this category.
+    pub async fn edit_indexing(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        reference: Reference<'_>,
+        EditCategoryIndexingBody {
+            noindex,
+            nofollow,
+            canonical_url,
+            expected_version,
+        }: EditCategoryIndexingBody,
+    ) -> Result<PageCategoryModel> {
+        if let ProvidedValue::Set(Some(ref url)) = canonical_url {
+            if !is_http_url(url) {
+                error!("Canonical URL base for category is invalid: {url}");
+                return Err(Error::CanonicalUrlInvalid);
+            }
+        }
+
+        let txn = ctx.transaction();
+        let PageCategoryModel { category_id, .. } =
+            Self::get(ctx, site_id, reference).await?;
+
+        // Lock the row for the rest of the transaction, see UserService::update()
+        let category = PageCategory::find_by_id(category_id)
+            .lock_exclusive()
+            .one(txn)
+            .await?
+            .ok_or(Error::PageCategoryNotFound)?;
+
+        check_version(expected_version, category.version, &category)?;
+
+        let model = page_category::ActiveModel {
+            category_id: Set(category_id),
+            version: Set(category.version + 1),
+            noindex: noindex.into_active_value(),
+            nofollow: nofollow.into_active_value(),
+            canonical_url: canonical_url.into_active_value(),
+            updated_at: Set(Some(now())),
+            ..Default::default()
+        };
+
+        let category = model.update(txn).await?;
+        Ok(category)
+    }
+
+    /// Sets the rendering settings for pages in this category.
+    pub async fn edit_render(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        reference: Reference<'_>,
+        EditCategoryRenderBody {
+            parser_profile,
+            rating_module,
+            comments_module,
+            expected_version,
+        }: EditCategoryRenderBody,
+    ) -> Result<PageCategoryModel> {
+        let txn = ctx.transaction();
+        let PageCategoryModel { category_id, .. } =
+            Self::get(ctx, site_id, reference).await?;
+
+        // Lock the row for the rest of the transaction, see UserService::update()
+        let category = PageCategory::find_by_id(category_id)
+            .lock_exclusive()
+            .one(txn)
+            .await?
+            .ok_or(Error::PageCategoryNotFound)?;
+
+        check_version(expected_version, category.version, &category)?;
+
+        let model = page_category::ActiveModel {
+            category_id: Set(category_id),
+            version: Set(category.version + 1),
+            parser_profile: parser_profile.into_active_value(),
+            rating_module: rating_module.into_active_value(),
+            comments_module: comments_module.into_active_value(),
+            updated_at: Set(Some(now())),
+            ..Default::default()
+        };
+
+        let category = model.update(txn).await?;
+        Ok(category)
+    }
+
+    /// Sets the data form for pages in this category.
+    ///
+    /// The category's pages are rerendered with the new layout.
+    pub async fn edit_form(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        reference: Reference<'_>,
+        EditCategoryFormBody {
+            form,
+            expected_version,
+        }: EditCategoryFormBody,
+    ) -> Result<PageCategoryModel> {
+        let form = match form {
+            ProvidedValue::Set(Some(form)) => {
+                form.validate()?;
+                ProvidedValue::Set(Some(serde_json::to_value(&form)?))
+            }
+            ProvidedValue::Set(None) => ProvidedValue::Set(None),
+            ProvidedValue::Unset => ProvidedValue::Unset,
+        };
+
+        let txn = ctx.transaction();
+        let PageCategoryModel { category_id, .. } =
+            Self::get(ctx, site_id, reference).await?;
+
+        // Lock the row for the rest of the transaction, see UserService::update()
+        let category = PageCategory::find_by_id(category_id)
+            .lock_exclusive()
+            .one(txn)
+            .await?
+            .ok_or(Error::PageCategoryNotFound)?;
+
+        check_version(expected_version, category.version, &category)?;
+
+        let model = page_category::ActiveModel {
+            category_id: Set(category_id),
+            version: Set(category.version + 1),
+            form: form.into_active_value(),
+            updated_at: Set(Some(now())),
+            ..Default::default()
+        };
+
+        let category = model.update(txn).await?;
+        OutdateService::outdate_category(ctx, site_id, &category.slug, 0).await?;
+        Ok(category)
+    }
 }
@@ -18,10 +18,81 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
-use crate::web::Reference;
+use crate::models::sea_orm_active_enums::{ModuleState, ParserProfile};
+use crate::services::page_form::FormSchema;
+use crate::web::{ProvidedValue, Reference};
 
 #[derive(Deserialize, Debug, Clone)]
 pub struct GetCategory<'a> {
     pub site: Reference<'a>,
     pub category: Reference<'a>,
 }
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct EditCategoryIndexing<'a> {
+    pub site: Reference<'a>,
+    pub category: Reference<'a>,
+
+    #[serde(flatten)]
+    pub body: EditCategoryIndexingBody,
+}
+
+/// Default indexing settings for pages in a category.
+///
+/// The canonical URL here is a base, see `PageIndexing::resolve()`.
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct EditCategoryIndexingBody {
+    pub noindex: ProvidedValue<bool>,
+    pub nofollow: ProvidedValue<bool>,
+    pub canonical_url: ProvidedValue<Option<String>>,
+
+    /// If set, the update fails unless the category is still at this version.
+    pub expected_version: Option<i32>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct EditCategoryRender<'a> {
+    pub site: Reference<'a>,
+    pub category: Reference<'a>,
+
+    #[serde(flatten)]
+    pub body: EditCategoryRenderBody,
+}
+
+/// Rendering settings for pages in a category.
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct EditCategoryRenderBody {
+    /// Overrides the site's parser profile. If `None`, the site's is used.
+    pub parser_profile: ProvidedValue<Option<ParserProfile>>,
+
+    /// Overrides whether the site shows the rating module. If `None`, the site's setting is used.
+    pub rating_module: ProvidedValue<Option<ModuleState>>,
+
+    /// Overrides whether the site shows the comments module. If `None`, the site's setting is used.
+    pub comments_module: ProvidedValue<Option<ModuleState>>,
+
+    /// If set, the update fails unless the category is still at this version.
+    pub expected_version: Option<i32>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct EditCategoryForm<'a> {
+    pub site: Reference<'a>,
+    pub category: Reference<'a>,
+
+    #[serde(flatten)]
+    pub body: EditCategoryFormBody,
+}
+
+/// The data form for pages in a category.
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct EditCategoryFormBody {
+    /// The form, or `None` to make the category's pages ordinary wikitext again.
+    pub form: ProvidedValue<Option<FormSchema>>,
+
+    /// If set, the update fails unless the category is still at this version.
+    pub expected_version: Option<i32>,
+}
@@ -0,0 +1,42 @@
+/*
+ * services/cdn/mod.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The CDN service, for serving file contents under content-addressed paths.
+//!
+//! Files are served at `/cdn/<hash>/<name>`, where the hash is that of the
+//! blob being served. Since a path can only ever refer to the same contents,
+//! responses can be cached indefinitely by browsers and by a CDN in front of
+//! the site, and nothing ever needs to be purged. A new version of a file
+//! simply has a different path.
+//!
+//! Files which not everyone may view are instead served under signed paths,
+//! which have an expiry time and a signature appended as query parameters.
+//! These are only cached until they expire.
+
+mod prelude {
+    pub use super::super::prelude::*;
+    pub use super::structs::*;
+}
+
+mod service;
+mod structs;
+
+pub use self::service::CdnService;
+pub use self::structs::*;
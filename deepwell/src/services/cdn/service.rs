@@ -0,0 +1,331 @@
+/*
+ * services/cdn/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::hash::{blob_hash_to_hex, BLOB_HASH_LENGTH};
+use crate::models::file::{self, Entity as File};
+use crate::models::file_derivative::{self, Entity as FileDerivative};
+use crate::models::file_revision::{self, Entity as FileRevision};
+use crate::models::file_thumbnail::{self, Entity as FileThumbnail};
+use crate::services::file::{is_svg_mime, sanitize_stored_svg};
+use crate::services::{BlobService, PermissionService, ThumbnailService};
+use crate::utils::{encode_path_segment, sign_message};
+use crate::web::Bytes;
+use sea_query::Expr;
+use std::borrow::Cow;
+use std::collections::HashSet;
+use subtle::ConstantTimeEq;
+use time::OffsetDateTime;
+
+/// How many file revisions referring to a blob are checked when serving it.
+///
+/// Popular files may be uploaded many times, but one visible
+/// revision is all that is needed for the blob to be served.
+const MAXIMUM_SOURCES: u64 = 50;
+
+/// Which types of file may be displayed in the browser, rather than downloaded.
+///
+/// Blobs are served from the wiki's own domain, so anything which the browser
+/// could run script from, such as HTML or SVG, must only ever be downloaded.
+const INLINE_MIME_TYPES: [&str; 16] = [
+    // Raster images
+    "image/avif",
+    "image/bmp",
+    "image/gif",
+    "image/jpeg",
+    "image/png",
+    "image/webp",
+    // Audio
+    "audio/aac",
+    "audio/flac",
+    "audio/mp4",
+    "audio/mpeg",
+    "audio/ogg",
+    "audio/wav",
+    "audio/webm",
+    // Video
+    "video/mp4",
+    "video/ogg",
+    "video/webm",
+];
+
+#[derive(Debug)]
+pub struct CdnService;
+
+impl CdnService {
+    /// Gets the CDN path for a blob, as a file on the given page.
+    ///
+    /// If anyone can view files on the page, then the path is permanent.
+    /// Otherwise it is signed, and only valid for a limited time.
+    pub async fn get_path(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        page_id: i64,
+        hash: &[u8],
+        name: &str,
+    ) -> Result<CdnPath> {
+        let permission =
+            PermissionService::check(ctx, site_id, Some(page_id), None, "file_get")
+                .await?;
+        if permission.allowed {
+            Ok(CdnPath {
+                path: Self::public_path(hash, name),
+                expires_at: None,
+            })
+        } else {
+            Ok(Self::signed_path(ctx, hash, name))
+        }
+    }
+
+    fn public_path(hash: &[u8], name: &str) -> String {
+        format!(
+            "/cdn/{}/{}",
+            blob_hash_to_hex(hash),
+            encode_path_segment(name),
+        )
+    }
+
+    fn signed_path(ctx: &ServiceContext<'_>, hash: &[u8], name: &str) -> CdnPath {
+        // Round the expiry up, so everyone requesting this file
+        // in the same period gets the same, cacheable, path.
+        let period = ctx.config().cdn_signed_url_expiry.as_secs().max(1) as i64;
+        let expires = (now().unix_timestamp() / period + 2) * period;
+        let signature = Self::sign(ctx, hash, name, expires);
+
+        CdnPath {
+            path: format!(
+                "{}?expires={expires}&signature={}",
+                Self::public_path(hash, name),
+                hex::encode(signature),
+            ),
+            expires_at: OffsetDateTime::from_unix_timestamp(expires).ok(),
+        }
+    }
+
+    fn sign(ctx: &ServiceContext<'_>, hash: &[u8], name: &str, expires: i64) -> [u8; 32] {
+        let message = format!("{}/{name}/{expires}", blob_hash_to_hex(hash));
+        sign_message(ctx.cdn_signing_key(), message.as_bytes())
+    }
+
+    /// Gets the contents of a blob requested through a CDN path.
+    ///
+    /// The blob must belong to a file revision which isn't deleted or hidden.
+    /// Unless the path is signed, the file must also be in a site where
+    /// anyone can view files.
+    pub async fn get(
+        ctx: &ServiceContext<'_>,
+        GetCdnBlob {
+            hash,
+            name,
+            expires,
+            signature,
+        }: GetCdnBlob,
+    ) -> Result<CdnBlob> {
+        let hash = match hex::decode(&hash) {
+            Ok(hash) if hash.len() == BLOB_HASH_LENGTH => hash,
+            _ => {
+                error!("CDN path has an invalid blob hash");
+                return Err(Error::BadRequest);
+            }
+        };
+
+        let (public_only, max_age) = match (expires, signature) {
+            (None, None) => (true, ctx.config().cdn_max_age.as_secs()),
+            (Some(expires), Some(signature)) => {
+                let expected = Self::sign(ctx, &hash, &name, expires);
+                let signature = hex::decode(signature).unwrap_or_default();
+                if !bool::from(expected.as_slice().ct_eq(&signature)) {
+                    warn!("CDN path has an invalid signature");
+                    return Err(Error::CdnSignatureInvalid);
+                }
+
+                // The signature is valid, so any cached copy will be too,
+                // but only until the path expires.
+                let remaining = expires - now().unix_timestamp();
+                if remaining <= 0 {
+                    debug!("Signed CDN path expired {} seconds ago", -remaining);
+                    return Err(Error::CdnPathExpired);
+                }
+
+                (false, remaining as u64)
+            }
+            _ => {
+                error!("CDN path has only one of an expiry time and a signature");
+                return Err(Error::BadRequest);
+            }
+        };
+
+        let mime = Self::get_mime(ctx, &hash, public_only)
+            .await?
+            .ok_or(Error::BlobNotFound)?;
+
+        let disposition = content_disposition(&mime);
+        let mut data = BlobService::get(ctx, &hash).await?;
+
+        // Files uploaded before SVGs were sanitized may still have scripts
+        if is_svg_mime(&mime) {
+            let sanitized = sanitize_stored_svg(&data).map_err(|error| {
+                warn!("Unable to sanitize SVG blob before serving: {error}");
+                Error::FileSvgInvalid(error)
+            })?;
+
+            if let Cow::Owned(sanitized) = sanitized {
+                data = sanitized;
+            }
+        }
+
+        Ok(CdnBlob {
+            data: Bytes::from(data),
+            content_disposition: format!(
+                "{disposition}; filename=\"{}\"",
+                name.replace('"', ""),
+            ),
+            cache_control: format!("public, max-age={max_age}, immutable"),
+            mime,
+        })
+    }
+
+    /// Finds a file revision a blob can be served for, and gets its MIME type.
+    ///
+    /// Converted images and thumbnails aren't referred to by file revisions directly,
+    /// so the revisions of the images they were made from are used.
+    /// Returns `None` if there is no such revision which can be served.
+    async fn get_mime(
+        ctx: &ServiceContext<'_>,
+        hash: &[u8],
+        public_only: bool,
+    ) -> Result<Option<String>> {
+        let txn = ctx.transaction();
+        let derivatives = FileDerivative::find()
+            .filter(file_derivative::Column::DerivativeS3Hash.eq(hash))
+            .all(txn)
+            .await?;
+
+        let thumbnails = FileThumbnail::find()
+            .filter(file_thumbnail::Column::ThumbnailS3Hash.eq(hash))
+            .all(txn)
+            .await?;
+
+        let mut hashes = vec![hash.to_vec()];
+        hashes.extend(
+            derivatives
+                .iter()
+                .map(|derivative| derivative.s3_hash.clone()),
+        );
+        hashes.extend(thumbnails.iter().map(|thumbnail| thumbnail.s3_hash.clone()));
+
+        let revisions = FileRevision::find()
+            .inner_join(File)
+            .filter(
+                Condition::all()
+                    .add(file_revision::Column::S3Hash.is_in(hashes))
+                    .add(file::Column::DeletedAt.is_null())
+                    .add(Expr::cust("NOT ('blob' = ANY(file_revision.hidden))")),
+            )
+            .limit(MAXIMUM_SOURCES)
+            .all(txn)
+            .await?;
+
+        let mut checked_pages = HashSet::new();
+        for revision in revisions {
+            if public_only {
+                // Only check each page once
+                if !checked_pages.insert(revision.page_id) {
+                    continue;
+                }
+
+                let permission = PermissionService::check(
+                    ctx,
+                    revision.site_id,
+                    Some(revision.page_id),
+                    None,
+                    "file_get",
+                )
+                .await?;
+
+                if !permission.allowed {
+                    continue;
+                }
+            }
+
+            if revision.s3_hash == hash {
+                return Ok(Some(revision.mime_hint));
+            }
+
+            let derivative = derivatives
+                .iter()
+                .find(|derivative| derivative.s3_hash == revision.s3_hash);
+
+            if let Some(derivative) = derivative {
+                let mime = ThumbnailService::derivative_mime(derivative.format);
+                return Ok(Some(str!(mime)));
+            }
+
+            // Thumbnails keep the format of the image they were made from
+            if thumbnails
+                .iter()
+                .any(|thumbnail| thumbnail.s3_hash == revision.s3_hash)
+            {
+                return Ok(Some(revision.mime_hint));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+/// Gets whether a blob of this type is shown inline or downloaded.
+fn content_disposition(mime: &str) -> &'static str {
+    let essence = mime.split(';').next().unwrap_or_default().trim();
+    let inline = INLINE_MIME_TYPES
+        .iter()
+        .any(|inline_mime| inline_mime.eq_ignore_ascii_case(essence));
+
+    if inline {
+        "inline"
+    } else {
+        "attachment"
+    }
+}
+
+#[test]
+fn content_dispositions() {
+    macro_rules! check {
+        ($mime:expr, $disposition:expr $(,)?) => {
+            assert_eq!(
+                content_disposition($mime),
+                $disposition,
+                "Unexpected disposition for {}",
+                $mime,
+            );
+        };
+    }
+
+    check!("image/png", "inline");
+    check!("IMAGE/JPEG", "inline");
+    check!("audio/ogg; codecs=opus", "inline");
+    check!("video/webm", "inline");
+    check!("image/svg+xml", "attachment");
+    check!("text/html; charset=utf-8", "attachment");
+    check!("application/xhtml+xml", "attachment");
+    check!("text/xml", "attachment");
+    check!("application/pdf", "attachment");
+    check!("", "attachment");
+}
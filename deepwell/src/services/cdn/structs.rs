@@ -0,0 +1,62 @@
+/*
+ * services/cdn/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::web::Bytes;
+use time::OffsetDateTime;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetCdnBlob {
+    /// The hash of the blob, in hexadecimal.
+    pub hash: String,
+
+    /// The file name the blob is served under.
+    pub name: String,
+
+    /// For signed paths, when the path stops being valid, as a Unix timestamp.
+    #[serde(default)]
+    pub expires: Option<i64>,
+
+    /// For signed paths, the signature, in hexadecimal.
+    #[serde(default)]
+    pub signature: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct CdnBlob {
+    pub data: Bytes<'static>,
+    pub mime: String,
+
+    /// The value for the `Content-Disposition` header.
+    pub content_disposition: String,
+
+    /// The value for the `Cache-Control` header.
+    pub cache_control: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct CdnPath {
+    /// The path to request the file from, relative to the site's domain.
+    pub path: String,
+
+    /// When a signed path stops being valid.
+    ///
+    /// This is `None` for public files, whose paths never expire.
+    pub expires_at: Option<OffsetDateTime>,
+}
@@ -0,0 +1,42 @@
+/*
+ * services/consistency/mod.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The consistency service, for finding rows which break invariants.
+//!
+//! Some invariants span several tables and can't be expressed as database
+//! constraints, for instance every file having at least one revision, or a
+//! file being in the same site as its page. These are checked by a recurring
+//! job, and the results saved as a report for administrators to review.
+//!
+//! A few kinds of issues can be fixed without any risk of losing data, such as
+//! a page's cached latest revision ID being stale. These are repaired if the
+//! run asks for it. Everything else is only reported, since fixing it needs
+//! someone to decide what the data should have been.
+
+mod prelude {
+    pub use super::super::prelude::*;
+    pub use super::structs::*;
+}
+
+mod service;
+mod structs;
+
+pub use self::service::ConsistencyService;
+pub use self::structs::*;
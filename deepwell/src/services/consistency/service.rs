@@ -0,0 +1,278 @@
+/*
+ * services/consistency/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::consistency_report::{
+    self, Entity as ConsistencyReport, Model as ConsistencyReportModel,
+};
+use sea_orm::{DatabaseBackend, FromQueryResult, Statement};
+use strum::IntoEnumIterator;
+
+/// The most IDs to save in a report for each issue.
+///
+/// The count is still accurate past this, but listing every row
+/// would make the report enormous if something has gone badly wrong.
+const MAX_ISSUE_IDS: usize = 100;
+
+#[derive(FromQueryResult, Debug)]
+struct IdRow {
+    id: i64,
+}
+
+#[derive(Debug)]
+pub struct ConsistencyService;
+
+impl ConsistencyService {
+    /// Runs every check, optionally repairing what it safely can, and saves the results.
+    ///
+    /// `requested_by` is `None` when this is run by the recurring job.
+    pub async fn run(
+        ctx: &ServiceContext<'_>,
+        requested_by: Option<i64>,
+        repair: bool,
+    ) -> Result<ConsistencyReportModel> {
+        info!("Running consistency checks (repair: {repair})");
+
+        let txn = ctx.transaction();
+        let mut issues = Vec::new();
+
+        for check in ConsistencyCheck::iter() {
+            let rows = IdRow::find_by_statement(Statement::from_string(
+                DatabaseBackend::Postgres,
+                check_sql(check),
+            ))
+            .all(txn)
+            .await?;
+
+            if rows.is_empty() {
+                continue;
+            }
+
+            warn!("Consistency check {check:?} found {} issues", rows.len());
+
+            let repaired = repair && check.repairable();
+            if repaired {
+                for sql in repair_sql(check) {
+                    txn.execute(Statement::from_string(DatabaseBackend::Postgres, *sql))
+                        .await?;
+                }
+
+                info!("Repaired issues from consistency check {check:?}");
+            }
+
+            issues.push(ConsistencyIssue {
+                check,
+                count: rows.len() as u64,
+                ids: rows
+                    .into_iter()
+                    .take(MAX_ISSUE_IDS)
+                    .map(|row| row.id)
+                    .collect(),
+                repaired,
+            });
+        }
+
+        let issue_count = issues.len() as i32;
+        let repaired_count = issues.iter().filter(|issue| issue.repaired).count() as i32;
+        let model = consistency_report::ActiveModel {
+            requested_by: Set(requested_by),
+            repair: Set(repair),
+            issue_count: Set(issue_count),
+            repaired_count: Set(repaired_count),
+            issues: Set(serde_json::to_value(&issues)?),
+            ..Default::default()
+        };
+
+        let report = model.insert(txn).await?;
+        Ok(report)
+    }
+
+    /// Gets the most recent consistency reports, newest first.
+    pub async fn get_reports(
+        ctx: &ServiceContext<'_>,
+        limit: u64,
+    ) -> Result<Vec<ConsistencyReportModel>> {
+        let txn = ctx.transaction();
+        let reports = ConsistencyReport::find()
+            .order_by_desc(consistency_report::Column::CreatedAt)
+            .order_by_desc(consistency_report::Column::ReportId)
+            .limit(limit)
+            .all(txn)
+            .await?;
+
+        Ok(reports)
+    }
+}
+
+/// Gets the query which finds rows that break this check's invariant.
+///
+/// Each query yields a single `id` column, the page or file affected.
+fn check_sql(check: ConsistencyCheck) -> &'static str {
+    match check {
+        ConsistencyCheck::FileWithoutRevisions => {
+            r#"
+            SELECT file_id AS id FROM file
+            WHERE NOT EXISTS (
+                SELECT 1 FROM file_revision
+                WHERE file_revision.file_id = file.file_id
+            )
+            ORDER BY file_id
+            "#
+        }
+        ConsistencyCheck::PageRevisionGap => {
+            r#"
+            SELECT page_id AS id FROM page_revision
+            GROUP BY page_id
+            HAVING MIN(revision_number) <> 0
+            OR MAX(revision_number) <> COUNT(*) - 1
+            ORDER BY page_id
+            "#
+        }
+        ConsistencyCheck::FileRevisionGap => {
+            r#"
+            SELECT file_id AS id FROM file_revision
+            GROUP BY file_id
+            HAVING MIN(revision_number) <> 0
+            OR MAX(revision_number) <> COUNT(*) - 1
+            ORDER BY file_id
+            "#
+        }
+        ConsistencyCheck::LatestPageRevisionHidden => {
+            r#"
+            SELECT id FROM (
+                SELECT DISTINCT ON (page_id) page_id AS id, hidden
+                FROM page_revision
+                ORDER BY page_id, revision_number DESC
+            ) AS latest
+            WHERE 'wikitext' = ANY(hidden)
+            ORDER BY id
+            "#
+        }
+        ConsistencyCheck::LatestFileRevisionHidden => {
+            r#"
+            SELECT id FROM (
+                SELECT DISTINCT ON (file_id) file_id AS id, hidden
+                FROM file_revision
+                ORDER BY file_id, revision_number DESC
+            ) AS latest
+            WHERE cardinality(hidden) > 0
+            ORDER BY id
+            "#
+        }
+        ConsistencyCheck::StaleLatestRevision => {
+            r#"
+            SELECT page.page_id AS id FROM page
+            JOIN (
+                SELECT DISTINCT ON (page_id) page_id, revision_id
+                FROM page_revision
+                ORDER BY page_id, revision_number DESC
+            ) AS latest ON latest.page_id = page.page_id
+            WHERE page.latest_revision_id IS DISTINCT FROM latest.revision_id
+            ORDER BY page.page_id
+            "#
+        }
+        ConsistencyCheck::OrphanedLinks => {
+            r#"
+            SELECT page_id AS id FROM page
+            WHERE deleted_at IS NOT NULL
+            AND (
+                EXISTS (SELECT 1 FROM page_link WHERE page_link.page_id = page.page_id)
+                OR EXISTS (SELECT 1 FROM page_connection WHERE from_page_id = page.page_id)
+                OR EXISTS (SELECT 1 FROM page_connection_missing WHERE from_page_id = page.page_id)
+                OR EXISTS (SELECT 1 FROM page_file_reference WHERE from_page_id = page.page_id)
+                OR EXISTS (SELECT 1 FROM page_file_embed WHERE from_page_id = page.page_id)
+            )
+            ORDER BY page_id
+            "#
+        }
+        ConsistencyCheck::FileSiteMismatch => {
+            r#"
+            SELECT file.file_id AS id FROM file
+            JOIN page ON page.page_id = file.page_id
+            WHERE file.site_id <> page.site_id
+            UNION
+            SELECT file.file_id AS id FROM file
+            JOIN file_revision ON file_revision.file_id = file.file_id
+            WHERE file_revision.site_id <> file.site_id
+            OR file_revision.page_id <> file.page_id
+            ORDER BY id
+            "#
+        }
+    }
+}
+
+/// Gets the statements which repair every issue found by this check.
+///
+/// Only checks which are `repairable()` have any. Restoring the latest
+/// revision ID only points the page at a revision which already exists,
+/// and links from deleted pages are regenerated if the page is restored.
+fn repair_sql(check: ConsistencyCheck) -> &'static [&'static str] {
+    match check {
+        ConsistencyCheck::StaleLatestRevision => &[r#"
+            UPDATE page SET latest_revision_id = latest.revision_id
+            FROM (
+                SELECT DISTINCT ON (page_id) page_id, revision_id
+                FROM page_revision
+                ORDER BY page_id, revision_number DESC
+            ) AS latest
+            WHERE latest.page_id = page.page_id
+            AND page.latest_revision_id IS DISTINCT FROM latest.revision_id
+            "#],
+        ConsistencyCheck::OrphanedLinks => &[
+            r#"
+            DELETE FROM page_link USING page
+            WHERE page_link.page_id = page.page_id
+            AND page.deleted_at IS NOT NULL
+            "#,
+            r#"
+            DELETE FROM page_connection USING page
+            WHERE page_connection.from_page_id = page.page_id
+            AND page.deleted_at IS NOT NULL
+            "#,
+            r#"
+            DELETE FROM page_connection_missing USING page
+            WHERE page_connection_missing.from_page_id = page.page_id
+            AND page.deleted_at IS NOT NULL
+            "#,
+            r#"
+            DELETE FROM page_file_reference USING page
+            WHERE page_file_reference.from_page_id = page.page_id
+            AND page.deleted_at IS NOT NULL
+            "#,
+            r#"
+            DELETE FROM page_file_embed USING page
+            WHERE page_file_embed.from_page_id = page.page_id
+            AND page.deleted_at IS NOT NULL
+            "#,
+        ],
+        _ => &[],
+    }
+}
+
+#[test]
+fn repairable_checks() {
+    for check in ConsistencyCheck::iter() {
+        assert_eq!(
+            check.repairable(),
+            !repair_sql(check).is_empty(),
+            "Repair statements don't match repairability for {check:?}",
+        );
+    }
+}
@@ -0,0 +1,81 @@
+/*
+ * services/consistency/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use strum_macros::EnumIter;
+
+/// Each invariant which is checked.
+#[derive(EnumIter, Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConsistencyCheck {
+    /// A file has no revisions.
+    FileWithoutRevisions,
+
+    /// A page's revision numbers don't run from zero without gaps.
+    PageRevisionGap,
+
+    /// A file's revision numbers don't run from zero without gaps.
+    FileRevisionGap,
+
+    /// The wikitext of a page's latest revision is hidden.
+    LatestPageRevisionHidden,
+
+    /// Part of a file's latest revision is hidden.
+    LatestFileRevisionHidden,
+
+    /// A page's `latest_revision_id` is not its latest revision.
+    StaleLatestRevision,
+
+    /// A deleted page still has rows in the link tables.
+    OrphanedLinks,
+
+    /// A file is in a different site than its page, or its revisions.
+    FileSiteMismatch,
+}
+
+impl ConsistencyCheck {
+    /// Whether issues found by this check can be fixed without losing data.
+    pub fn repairable(self) -> bool {
+        matches!(
+            self,
+            ConsistencyCheck::StaleLatestRevision | ConsistencyCheck::OrphanedLinks,
+        )
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ConsistencyIssue {
+    pub check: ConsistencyCheck,
+
+    /// The number of rows affected.
+    pub count: u64,
+
+    /// The IDs of the affected pages or files, up to a limit.
+    pub ids: Vec<i64>,
+    pub repaired: bool,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct RunConsistencyCheck {
+    pub acting_user_id: i64,
+
+    /// Whether to repair the issues which can be safely repaired.
+    #[serde(default)]
+    pub repair: bool,
+}
@@ -21,17 +21,28 @@
 use crate::api::ServerState;
 use crate::config::Config;
 use crate::locales::Localizations;
-use crate::services::blob::MimeAnalyzer;
+use crate::services::blob::{BlobStore, MimeAnalyzer};
+use crate::services::render::RenderLimiter;
+use crate::services::service_account::ServicePrincipal;
+use crate::services::{Error, Result, StdResult};
 use redis::aio::ConnectionManager;
 use rsmq_async::MultiplexedRsmq;
 use s3::bucket::Bucket;
-use sea_orm::DatabaseTransaction;
+use sea_orm::{DatabaseConnection, DatabaseTransaction};
+use std::future::Future;
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::{self, Instant};
 
 #[derive(Debug, Clone)]
 pub struct ServiceContext<'txn> {
     state: ServerState,
     transaction: &'txn DatabaseTransaction,
+    check_only: bool,
+    service_account: Option<ServicePrincipal>,
+    caller_id: Option<i64>,
+    impersonator_id: Option<i64>,
+    deadline: Option<Instant>,
 }
 
 impl<'txn> ServiceContext<'txn> {
@@ -43,6 +54,80 @@ impl<'txn> ServiceContext<'txn> {
         ServiceContext {
             state: Arc::clone(state),
             transaction,
+            check_only: false,
+            service_account: None,
+            caller_id: None,
+            impersonator_id: None,
+            deadline: None,
+        }
+    }
+
+    /// Creates a context for a dry run of some operation.
+    ///
+    /// The caller rolls back the transaction afterwards, but services must
+    /// also skip any side effects which live outside the database, such as
+    /// queuing jobs or uploading to S3. See `check_only()`.
+    pub fn new_check_only(
+        state: &ServerState,
+        transaction: &'txn DatabaseTransaction,
+    ) -> Self {
+        ServiceContext {
+            state: Arc::clone(state),
+            transaction,
+            check_only: true,
+            service_account: None,
+            caller_id: None,
+            impersonator_id: None,
+            deadline: None,
+        }
+    }
+
+    /// Creates a context identical to this one, but made by the given service account.
+    pub fn with_service_account(self, service_account: Option<ServicePrincipal>) -> Self {
+        ServiceContext {
+            service_account,
+            ..self
+        }
+    }
+
+    /// Creates a context identical to this one, but on behalf of the given user.
+    pub fn with_caller(self, caller_id: Option<i64>) -> Self {
+        ServiceContext { caller_id, ..self }
+    }
+
+    /// Creates a context identical to this one, but made while impersonating the caller.
+    pub fn with_impersonator(self, impersonator_id: Option<i64>) -> Self {
+        ServiceContext {
+            impersonator_id,
+            ..self
+        }
+    }
+
+    /// Creates a context identical to this one, but which must finish by the given time.
+    ///
+    /// Contexts without a deadline, such as for jobs, may run indefinitely.
+    pub fn with_deadline(self, deadline: Instant) -> Self {
+        ServiceContext {
+            deadline: Some(deadline),
+            ..self
+        }
+    }
+
+    /// Creates a context identical to this one, but using a different transaction.
+    ///
+    /// This is useful for running some operations within a savepoint.
+    pub fn with_transaction<'t>(
+        &self,
+        transaction: &'t DatabaseTransaction,
+    ) -> ServiceContext<'t> {
+        ServiceContext {
+            state: Arc::clone(&self.state),
+            transaction,
+            check_only: self.check_only,
+            service_account: self.service_account.clone(),
+            caller_id: self.caller_id,
+            impersonator_id: self.impersonator_id,
+            deadline: self.deadline,
         }
     }
 
@@ -77,8 +162,103 @@ impl<'txn> ServiceContext<'txn> {
         &self.state.s3_bucket
     }
 
+    #[inline]
+    pub fn blob_migration_target(&self) -> Option<&BlobStore> {
+        self.state.blob_migration_target.as_ref()
+    }
+
+    #[inline]
+    pub fn render_limiter(&self) -> &RenderLimiter {
+        &self.state.render_limiter
+    }
+
+    #[inline]
+    pub fn cdn_signing_key(&self) -> &[u8] {
+        &self.state.cdn_signing_key
+    }
+
     #[inline]
     pub fn transaction(&self) -> &'txn DatabaseTransaction {
         self.transaction
     }
+
+    /// The database connection itself, outside of this context's transaction.
+    ///
+    /// Only use this for changes which must be kept even if the
+    /// transaction is rolled back, see `AuditService::record_detached()`.
+    #[inline]
+    pub fn database(&self) -> &DatabaseConnection {
+        &self.state.database
+    }
+
+    /// Whether this is a dry run, whose changes will not be kept.
+    #[inline]
+    pub fn check_only(&self) -> bool {
+        self.check_only
+    }
+
+    /// The service account which made this call, if any.
+    #[inline]
+    pub fn service_account(&self) -> Option<&ServicePrincipal> {
+        self.service_account.as_ref()
+    }
+
+    /// The user this call was made on behalf of, if any.
+    ///
+    /// Jobs and calls which don't name an acting user have no caller.
+    #[inline]
+    pub fn caller_id(&self) -> Option<i64> {
+        self.caller_id
+    }
+
+    /// The administrator impersonating the caller, if any.
+    ///
+    /// See `ImpersonationService::record_call()`.
+    #[inline]
+    pub fn impersonator_id(&self) -> Option<i64> {
+        self.impersonator_id
+    }
+
+    // Deadlines
+
+    /// How much time is left before the deadline, if there is one.
+    pub fn remaining_time(&self) -> Option<Duration> {
+        self.deadline
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+    }
+
+    /// Fails if the deadline has already passed.
+    ///
+    /// Long operations should call this before starting expensive work,
+    /// since work which doesn't yield to the runtime can't be interrupted.
+    pub fn check_deadline(&self) -> Result<()> {
+        match self.remaining_time() {
+            Some(remaining) if remaining.is_zero() => {
+                warn!("Request deadline exceeded, aborting");
+                Err(Error::DeadlineExceeded)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Runs a future, failing if it doesn't finish before the deadline.
+    ///
+    /// This is used for calls to remote services such as S3 and Redis,
+    /// so a slow dependency fails the request instead of stalling it.
+    pub async fn until_deadline<F, T, E>(&self, future: F) -> Result<T>
+    where
+        F: Future<Output = StdResult<T, E>>,
+        Error: From<E>,
+    {
+        match self.deadline {
+            None => Ok(future.await?),
+            Some(deadline) => match time::timeout_at(deadline, future).await {
+                Ok(result) => Ok(result?),
+                Err(_) => {
+                    warn!("Request deadline exceeded while waiting on remote service");
+                    Err(Error::DeadlineExceeded)
+                }
+            },
+        }
+    }
 }
@@ -0,0 +1,41 @@
+/*
+ * services/custom_block/mod.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The custom block service, for blocks which sites add to their wikitext.
+//!
+//! Beyond the blocks built into ftml, a site can define its own, such as
+//! `[[weather]]`, with the arguments it accepts and a template to render it
+//! with. Definitions are checked by ftml when they are created, so one which
+//! would shadow a built-in block or use unsafe HTML is rejected.
+//!
+//! Whenever one of the site's pages is rendered, its custom blocks are loaded
+//! into the parser settings. Pages using a block which has since been deleted
+//! render it as text, with a parse error.
+
+mod prelude {
+    pub use super::super::prelude::*;
+    pub use super::structs::*;
+}
+
+mod service;
+mod structs;
+
+pub use self::service::CustomBlockService;
+pub use self::structs::*;
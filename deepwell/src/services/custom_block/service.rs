@@ -0,0 +1,179 @@
+/*
+ * services/custom_block/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::custom_block::{
+    self, Entity as CustomBlock, Model as CustomBlockModel,
+};
+use crate::models::sea_orm_active_enums::CustomBlockBody as DbCustomBlockBody;
+use crate::services::RenderConfigService;
+use ftml::settings::{
+    CustomBlockBody, CustomBlockDefinition, CustomBlockError, CustomBlockSettings,
+};
+
+#[derive(Debug)]
+pub struct CustomBlockService;
+
+impl CustomBlockService {
+    pub async fn create(
+        ctx: &ServiceContext<'_>,
+        CreateCustomBlock {
+            site_id,
+            user_id,
+            name,
+            arguments,
+            body,
+            template,
+        }: CreateCustomBlock,
+    ) -> Result<CustomBlockModel> {
+        info!("Creating custom block '{name}' in site ID {site_id}");
+
+        let definition = CustomBlockDefinition {
+            name,
+            arguments,
+            body,
+            template,
+        };
+
+        // Have ftml check the definition against the site's existing blocks
+        let mut settings = Self::settings(ctx, site_id).await?;
+        match settings.register(definition.clone()) {
+            Ok(()) => (),
+            Err(CustomBlockError::AlreadyRegistered) => {
+                error!(
+                    "Custom block '{}' already exists in site ID {site_id}",
+                    definition.name,
+                );
+                return Err(Error::CustomBlockExists);
+            }
+            Err(error) => {
+                error!("Custom block definition is invalid: {}", error.name());
+                return Err(Error::CustomBlockInvalid(error));
+            }
+        }
+
+        let CustomBlockDefinition {
+            name,
+            arguments,
+            body,
+            template,
+        } = definition;
+
+        let txn = ctx.transaction();
+        let model = custom_block::ActiveModel {
+            site_id: Set(site_id),
+            created_by: Set(user_id),
+            name: Set(name),
+            arguments: Set(serde_json::to_value(&arguments)?),
+            body: Set(match body {
+                CustomBlockBody::None => DbCustomBlockBody::None,
+                CustomBlockBody::Text => DbCustomBlockBody::Text,
+                CustomBlockBody::Elements => DbCustomBlockBody::Elements,
+            }),
+            template: Set(template),
+            ..Default::default()
+        };
+
+        let block = model.insert(txn).await?;
+        RenderConfigService::invalidate(ctx, site_id).await?;
+        Ok(block)
+    }
+
+    pub async fn delete(
+        ctx: &ServiceContext<'_>,
+        DeleteCustomBlock { site_id, name }: DeleteCustomBlock,
+    ) -> Result<()> {
+        info!("Deleting custom block '{name}' in site ID {site_id}");
+
+        let txn = ctx.transaction();
+        let block = CustomBlock::find()
+            .filter(
+                Condition::all()
+                    .add(custom_block::Column::SiteId.eq(site_id))
+                    .add(custom_block::Column::Name.eq(name))
+                    .add(custom_block::Column::DeletedAt.is_null()),
+            )
+            .one(txn)
+            .await?
+            .ok_or(Error::CustomBlockNotFound)?;
+
+        let model = custom_block::ActiveModel {
+            custom_block_id: Set(block.custom_block_id),
+            deleted_at: Set(Some(now())),
+            ..Default::default()
+        };
+        model.update(txn).await?;
+        RenderConfigService::invalidate(ctx, site_id).await?;
+        Ok(())
+    }
+
+    pub async fn get_all(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+    ) -> Result<Vec<CustomBlockModel>> {
+        let txn = ctx.transaction();
+        let blocks = CustomBlock::find()
+            .filter(
+                Condition::all()
+                    .add(custom_block::Column::SiteId.eq(site_id))
+                    .add(custom_block::Column::DeletedAt.is_null()),
+            )
+            .order_by_asc(custom_block::Column::Name)
+            .all(txn)
+            .await?;
+
+        Ok(blocks)
+    }
+
+    /// Builds the parser settings for the site's custom blocks.
+    ///
+    /// Blocks which ftml no longer accepts, for instance because a built-in
+    /// block of the same name has since been added, are skipped. Pages using
+    /// them then render the block as text, rather than failing outright.
+    pub async fn settings(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+    ) -> Result<CustomBlockSettings> {
+        let mut settings = CustomBlockSettings::new();
+
+        for block in Self::get_all(ctx, site_id).await? {
+            let definition = CustomBlockDefinition {
+                name: block.name,
+                arguments: serde_json::from_value(block.arguments)?,
+                body: match block.body {
+                    DbCustomBlockBody::None => CustomBlockBody::None,
+                    DbCustomBlockBody::Text => CustomBlockBody::Text,
+                    DbCustomBlockBody::Elements => CustomBlockBody::Elements,
+                },
+                template: block.template,
+            };
+
+            if let Err(error) = settings.register(definition) {
+                warn!(
+                    "Skipping custom block ID {} in site ID {site_id}, no longer valid: {}",
+                    block.custom_block_id,
+                    error.name(),
+                );
+            }
+        }
+
+        Ok(settings)
+    }
+}
@@ -0,0 +1,45 @@
+/*
+ * services/custom_block/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use ftml::settings::{CustomBlockArgument, CustomBlockBody};
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CreateCustomBlock {
+    pub site_id: i64,
+    pub user_id: i64,
+
+    /// The name the block is invoked with, such as `weather`.
+    pub name: String,
+
+    #[serde(default)]
+    pub arguments: Vec<CustomBlockArgument>,
+
+    #[serde(default)]
+    pub body: CustomBlockBody,
+
+    /// The HTML template to render the block with.
+    pub template: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct DeleteCustomBlock {
+    pub site_id: i64,
+    pub name: String,
+}
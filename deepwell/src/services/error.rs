@@ -18,11 +18,16 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
+use super::limit::LimitType;
+use super::permission::PermissionReason;
+use super::upload_rule::UploadRestriction;
 use filemagic::FileMagicError;
+use ftml::settings::CustomBlockError;
 use jsonrpsee::types::error::ErrorObjectOwned;
 use reqwest::Error as ReqwestError;
 use s3::error::S3Error;
 use sea_orm::{error::DbErr, TransactionError};
+use serde::Serialize;
 use thiserror::Error as ThisError;
 use unic_langid::LanguageIdentifierError;
 
@@ -71,6 +76,27 @@ pub enum Error {
     #[error("Magic library error: {0}")]
     Magic(#[from] FileMagicError),
 
+    #[error("Image processing error: {0}")]
+    Image(#[from] image::ImageError),
+
+    #[error("Zip archive error: {0}")]
+    Zip(#[from] zip::result::ZipError),
+
+    #[error("Blob compression error: {0}")]
+    BlobCompression(std::io::Error),
+
+    #[error("Blob storage filesystem error: {0}")]
+    BlobFilesystem(std::io::Error),
+
+    #[error("Unable to build export: {0}")]
+    ExportBuild(String),
+
+    #[error("Git command failed: {0}")]
+    Git(String),
+
+    #[error("Edge cache purge request failed")]
+    EdgeCachePurge,
+
     #[error("One-time password error: {0}")]
     Otp(#[from] rust_otp::Error),
 
@@ -83,6 +109,12 @@ pub enum Error {
     #[error("S3 service failed to respond properly")]
     S3Response,
 
+    #[error("S3 service is unavailable")]
+    S3Unavailable,
+
+    #[error("File scanner could not be reached or gave an invalid response")]
+    FileScanUnavailable,
+
     #[error("Email verification error: {}", .0.as_ref().unwrap_or(&str!("<unspecified>")))]
     EmailVerification(Option<String>),
 
@@ -95,6 +127,18 @@ pub enum Error {
     #[error("Attempting to perform a wikitext parse and render has timed out")]
     RenderTimeout,
 
+    #[error("The request did not finish before its deadline")]
+    DeadlineExceeded,
+
+    #[error("Too many renders are running, retry after {retry_after_secs} seconds")]
+    RenderOverloaded { retry_after_secs: u64 },
+
+    #[error("Unable to generate an unused shortlink code")]
+    ShortlinkCodeExhausted,
+
+    #[error("Revision count is too large to be a revision number")]
+    RevisionCountOverflow,
+
     #[error("The user cannot rename as they do not have enough name change tokens")]
     InsufficientNameChanges,
 
@@ -113,6 +157,27 @@ pub enum Error {
         session_user_id: i64,
     },
 
+    #[error("This user is not permitted to impersonate the requested user")]
+    ImpersonationNotAllowed,
+
+    #[error("This action cannot be performed while impersonating a user")]
+    ImpersonationForbidden,
+
+    #[error("This method may only be called by a service account")]
+    ServiceAccountRequired,
+
+    #[error("Service account does not have the scope to call this method")]
+    ServiceAccountForbidden,
+
+    #[error("Upload is not permitted by the site's upload rules")]
+    UploadRestricted(UploadRestriction),
+
+    #[error("User is not permitted to download files in this site")]
+    FileDownloadForbidden,
+
+    #[error("User is not permitted to call this method")]
+    MethodForbidden(PermissionReason),
+
     #[error("A password is required")]
     EmptyPassword,
 
@@ -131,6 +196,9 @@ pub enum Error {
     #[error("Cannot hide the wikitext for the latest page revision")]
     CannotHideLatestRevision,
 
+    #[error("Cannot hide the contents of a revision marked as stable")]
+    CannotHideStableRevision,
+
     #[error("The regular expression found in the database is invalid")]
     FilterRegexInvalid(regex::Error),
 
@@ -164,6 +232,9 @@ pub enum Error {
     #[error("User slug cannot be empty")]
     UserSlugEmpty,
 
+    #[error("User name is reserved")]
+    UserNameReserved,
+
     #[error("User email cannot be empty")]
     UserEmailEmpty,
 
@@ -185,6 +256,206 @@ pub enum Error {
     #[error("Message has too many recipients")]
     MessageTooManyRecipients,
 
+    #[error("Canonical URL is not a valid absolute HTTP URL")]
+    CanonicalUrlInvalid,
+
+    #[error("Requested QR code is too large")]
+    QrCodeTooLarge,
+
+    #[error("URL is too long to encode as a QR code")]
+    QrCodeDataTooLong,
+
+    #[error("Export selection matched no pages")]
+    ExportEmpty,
+
+    #[error("Export selection has too many pages")]
+    ExportTooLarge,
+
+    #[error("Export has not been successfully completed")]
+    ExportNotCompleted,
+
+    #[error("Site has reached its limit of exports in this format for today")]
+    ExportQuotaExceeded,
+
+    #[error("Export format is not available on this instance")]
+    ExportFormatUnavailable,
+
+    #[error("Git mirrors are not enabled on this instance")]
+    GitMirrorDisabled,
+
+    #[error("Git remote URL is not valid")]
+    GitRemoteInvalid,
+
+    #[error("The event stream is not enabled on this instance")]
+    EventStreamDisabled,
+
+    #[error("Event stream consumer group name cannot be empty")]
+    EventStreamGroupNameEmpty,
+
+    #[error("Too many autocomplete requests, try again shortly")]
+    AutocompleteRateLimited,
+
+    #[error("API usage report date range is invalid or too long")]
+    ApiUsageRangeInvalid,
+
+    #[error("API throttle must allow at least one request per minute")]
+    ApiThrottleInvalid,
+
+    #[error("Too many API requests on this site, try again shortly")]
+    ApiThrottled,
+
+    #[error("Content exceeds the {limit} limit (maximum {maximum}, actual {actual})")]
+    LimitExceeded {
+        limit: LimitType,
+        maximum: u64,
+        actual: u64,
+    },
+
+    #[error("Site limits must be positive and cannot exceed the instance's")]
+    SiteLimitInvalid,
+
+    #[error("Revisions being compared must be different, and in order")]
+    RevisionComparisonInvalid,
+
+    #[error("Tag rule must add or remove at least one tag, and not both add and remove the same one")]
+    TagRuleInvalid,
+
+    #[error("Tag rule title regular expression is invalid: {0}")]
+    TagRuleRegexInvalid(regex::Error),
+
+    #[error("Permission overrides must have exactly one scope, and cannot be set for internal methods")]
+    PermissionAclInvalid,
+
+    #[error("Refresh token client identifier must be non-empty and at most 100 bytes")]
+    RefreshTokenClientInvalid,
+
+    #[error(
+        "Recovery contact must be one valid email or user, other than the account's own"
+    )]
+    RecoveryContactInvalid,
+
+    #[error("Account recovery does not have enough confirmations or is still in its waiting period")]
+    AccountRecoveryNotReady,
+
+    #[error("OAuth application must have a name, absolute redirect URIs, and only grantable scopes")]
+    OauthApplicationInvalid,
+
+    #[error("Requested OAuth scopes are not allowed for this application")]
+    OauthScopeInvalid,
+
+    #[error("Redirect URI is not registered for this OAuth application")]
+    OauthRedirectUriInvalid,
+
+    #[error("OAuth grant is invalid, expired, or was issued to another client")]
+    OauthGrantInvalid,
+
+    #[error(
+        "Service account must have a name and only scopes matching existing methods"
+    )]
+    ServiceAccountInvalid,
+
+    #[error("Upload rule MIME pattern must be '*', 'type/*', or 'type/subtype', and maximums must be positive")]
+    UploadRuleInvalid,
+
+    #[error("Revisions with hidden fields cannot be marked as stable")]
+    StableRevisionHidden,
+
+    #[error("Cannot revert to a revision with hidden fields")]
+    CannotRevertHiddenRevision,
+
+    #[error(
+        "Scheduled task has an invalid schedule or action, or runs more than hourly"
+    )]
+    ScheduledTaskInvalid,
+
+    #[error("Site has reached its limit of scheduled tasks")]
+    ScheduledTaskQuotaExceeded,
+
+    #[error("Cannot purge the contents of a revision which is not hidden")]
+    CannotPurgeVisibleRevision,
+
+    #[error("File licensing information is malformed")]
+    FileLicensingInvalid { fields: Vec<String> },
+
+    #[error("Requested thumbnail size is not one which is generated")]
+    ThumbnailSizeInvalid,
+
+    #[error("Pending upload has expired")]
+    PendingUploadExpired,
+
+    #[error("Pending upload has not been received by S3")]
+    PendingUploadMissing,
+
+    #[error("Uploaded file does not match its declared hash or size")]
+    PendingUploadMismatch,
+
+    #[error("User has reached their limit of pending uploads")]
+    PendingUploadQuotaExceeded,
+
+    #[error("Chunk offset does not match the amount of the upload received so far")]
+    PendingUploadWrongOffset,
+
+    #[error("Chunked upload has not received all of its data")]
+    PendingUploadIncomplete,
+
+    #[error("Upload chunk does not match its checksum")]
+    PendingUploadChunkMismatch,
+
+    #[error("Upload exceeds the site's storage quota (quota {quota}, used {used}, requested {requested})")]
+    StorageQuotaExceeded {
+        quota: u64,
+        used: u64,
+        requested: u64,
+    },
+
+    #[error("Uploaded file is infected: {0}")]
+    FileInfected(String),
+
+    #[error("Custom block definition is invalid: {}", .0.name())]
+    CustomBlockInvalid(CustomBlockError),
+
+    #[error("Contents of this file revision are hidden")]
+    FileBlobHidden,
+
+    #[error("Wikitext of this page revision is hidden")]
+    PageRevisionWikitextHidden,
+
+    #[error("SVG uploads are not allowed in this site")]
+    FileSvgDisallowed,
+
+    #[error("Uploaded SVG is not well-formed: {0}")]
+    FileSvgInvalid(String),
+
+    #[error("Signature on CDN path is not valid")]
+    CdnSignatureInvalid,
+
+    #[error("Signed CDN path has expired")]
+    CdnPathExpired,
+
+    #[error("No blob migration target is configured")]
+    BlobMigrationNotConfigured,
+
+    #[error("Data form schema is invalid: {0}")]
+    PageFormSchemaInvalid(String),
+
+    #[error("Data form values are invalid")]
+    PageFormInvalid { fields: Vec<String> },
+
+    #[error("Page template parameters do not match its placeholders")]
+    PageTemplateParametersInvalid {
+        missing: Vec<String>,
+        unknown: Vec<String>,
+    },
+
+    #[error("Pasted file is not a supported image")]
+    FilePasteNotImage,
+
+    #[error("Staged file has expired")]
+    StagedFileExpired,
+
+    #[error("User has reached their limit of staged files")]
+    StagingQuotaExceeded,
+
     #[error("Unspecified entity not found")]
     GeneralNotFound,
 
@@ -239,6 +510,75 @@ pub enum Error {
     #[error("Text item does not exist")]
     TextNotFound,
 
+    #[error("Special page override does not exist")]
+    SpecialPageOverrideNotFound,
+
+    #[error("Shortlink does not exist")]
+    ShortlinkNotFound,
+
+    #[error("Export does not exist")]
+    ExportNotFound,
+
+    #[error("Git mirror does not exist")]
+    GitMirrorNotFound,
+
+    #[error("Event stream consumer group does not exist")]
+    EventStreamGroupNotFound,
+
+    #[error("Link recomputation run does not exist")]
+    LinkRecomputeNotFound,
+
+    #[error("Revision comparison does not exist")]
+    RevisionComparisonNotFound,
+
+    #[error("Tag rule does not exist")]
+    TagRuleNotFound,
+
+    #[error("No API method with this name exists")]
+    PermissionMethodNotFound,
+
+    #[error("Account recovery does not exist or is no longer active")]
+    AccountRecoveryNotFound,
+
+    #[error("Recovery contact does not exist")]
+    RecoveryContactNotFound,
+
+    #[error("OAuth application does not exist")]
+    OauthApplicationNotFound,
+
+    #[error("OAuth application has not been authorized by this user")]
+    OauthAuthorizationNotFound,
+
+    #[error("Service account does not exist")]
+    ServiceAccountNotFound,
+
+    #[error("Upload rule does not exist")]
+    UploadRuleNotFound,
+
+    #[error("Scheduled task does not exist")]
+    ScheduledTaskNotFound,
+
+    #[error("Pending upload does not exist")]
+    PendingUploadNotFound,
+
+    #[error("Custom block does not exist")]
+    CustomBlockNotFound,
+
+    #[error("Staged file does not exist")]
+    StagedFileNotFound,
+
+    #[error("Blob migration does not exist")]
+    BlobMigrationNotFound,
+
+    #[error("Page draft does not exist")]
+    PageDraftNotFound,
+
+    #[error("Page template does not exist")]
+    PageTemplateNotFound,
+
+    #[error("Page field watch does not exist")]
+    PageFieldWatchNotFound,
+
     #[error("Cannot perform, user already exists")]
     UserExists,
 
@@ -266,6 +606,38 @@ pub enum Error {
     #[error("Cannot perform, custom domain already exists")]
     CustomDomainExists,
 
+    #[error("Event stream consumer group already exists")]
+    EventStreamGroupExists,
+
+    #[error("Service account with this name already exists")]
+    ServiceAccountExists,
+
+    #[error("Scheduled task with this name already exists")]
+    ScheduledTaskExists,
+
+    #[error("Custom block with this name already exists")]
+    CustomBlockExists,
+
+    #[error("Another file on this page already has this name")]
+    FileNameConflict,
+
+    #[error("Already watching this field in this category")]
+    PageFieldWatchExists,
+
+    #[error("Cannot perform, object was modified concurrently (expected version {expected_version}, current version {current_version})")]
+    VersionConflict {
+        expected_version: i32,
+        current_version: i32,
+        current: serde_json::Value,
+    },
+
+    #[error("Page was edited since revision {base_revision_id}, and the changes could not be merged")]
+    PageEditConflict {
+        base_revision_id: i64,
+        latest_revision_id: i64,
+        conflicts: serde_json::Value,
+    },
+
     #[error("Cannot perform this action because you are blocked by the user")]
     UserBlockedUser,
 
@@ -315,6 +687,29 @@ impl Error {
             Error::MessageDraftNotFound => 2015,
             Error::BlobNotFound => 2016,
             Error::TextNotFound => 2017,
+            Error::SpecialPageOverrideNotFound => 2018,
+            Error::ShortlinkNotFound => 2019,
+            Error::ExportNotFound => 2020,
+            Error::GitMirrorNotFound => 2021,
+            Error::EventStreamGroupNotFound => 2022,
+            Error::LinkRecomputeNotFound => 2023,
+            Error::RevisionComparisonNotFound => 2024,
+            Error::TagRuleNotFound => 2025,
+            Error::PermissionMethodNotFound => 2026,
+            Error::AccountRecoveryNotFound => 2027,
+            Error::RecoveryContactNotFound => 2028,
+            Error::OauthApplicationNotFound => 2029,
+            Error::OauthAuthorizationNotFound => 2030,
+            Error::ServiceAccountNotFound => 2031,
+            Error::UploadRuleNotFound => 2032,
+            Error::ScheduledTaskNotFound => 2033,
+            Error::PendingUploadNotFound => 2034,
+            Error::CustomBlockNotFound => 2035,
+            Error::StagedFileNotFound => 2036,
+            Error::BlobMigrationNotFound => 2037,
+            Error::PageDraftNotFound => 2038,
+            Error::PageTemplateNotFound => 2039,
+            Error::PageFieldWatchNotFound => 2040,
 
             // 2100 -- Existing data
             Error::UserExists => 2100,
@@ -326,17 +721,31 @@ impl Error {
             Error::FileExists => 2106,
             Error::FilterExists => 2107,
             Error::CustomDomainExists => 2108,
+            Error::EventStreamGroupExists => 2109,
+            Error::ServiceAccountExists => 2110,
+            Error::ScheduledTaskExists => 2111,
+            Error::CustomBlockExists => 2112,
+            Error::FileNameConflict => 2113,
+            Error::PageFieldWatchExists => 2114,
+
+            // 2200 -- Concurrent modification
+            Error::VersionConflict { .. } => 2200,
+            Error::PageEditConflict { .. } => 2201,
 
             // 3000 - Server errors, unexpected
             Error::RateLimited => 3000,
             Error::WebRequest(_) => 3001,
             Error::AuthenticationBackend(_) => 3002,
+            Error::DeadlineExceeded => 3003,
+            Error::RenderOverloaded { .. } => 3004,
 
             // 3100 -- Remote services
             Error::RenderTimeout => 3100,
             Error::EmailVerification(_) => 3101,
             Error::S3Service(_) => 3102,
             Error::S3Response => 3103,
+            Error::S3Unavailable => 3104,
+            Error::FileScanUnavailable => 3105,
 
             // 3200 -- Backend issues
             Error::Serde(_) => 3200,
@@ -346,6 +755,15 @@ impl Error {
             Error::Otp(_) => 3205,
             Error::Redis(_) => 3206,
             Error::Rsmq(_) => 3207,
+            Error::ShortlinkCodeExhausted => 3208,
+            Error::Image(_) => 3209,
+            Error::Zip(_) => 3210,
+            Error::ExportBuild(_) => 3211,
+            Error::Git(_) => 3212,
+            Error::EdgeCachePurge => 3213,
+            Error::RevisionCountOverflow => 3214,
+            Error::BlobCompression(_) => 3215,
+            Error::BlobFilesystem(_) => 3216,
 
             // 4000 - Client, request errors
             //        BadRequest is pretty general, avoid it except for rare weird cases
@@ -372,6 +790,69 @@ impl Error {
             Error::MessageBodyTooLong => 4019,
             Error::MessageNoRecipients => 4020,
             Error::MessageTooManyRecipients => 4021,
+            Error::CanonicalUrlInvalid => 4023,
+            Error::QrCodeTooLarge => 4024,
+            Error::QrCodeDataTooLong => 4025,
+            Error::ExportEmpty => 4026,
+            Error::ExportTooLarge => 4027,
+            Error::ExportNotCompleted => 4028,
+            Error::ExportQuotaExceeded => 4029,
+            Error::ExportFormatUnavailable => 4030,
+            Error::GitMirrorDisabled => 4031,
+            Error::GitRemoteInvalid => 4032,
+            Error::UserNameReserved => 4033,
+            Error::EventStreamDisabled => 4034,
+            Error::EventStreamGroupNameEmpty => 4035,
+            Error::AutocompleteRateLimited => 4036,
+            Error::ApiUsageRangeInvalid => 4037,
+            Error::ApiThrottleInvalid => 4038,
+            Error::ApiThrottled => 4039,
+            Error::LimitExceeded { .. } => 4040,
+            Error::SiteLimitInvalid => 4041,
+            Error::RevisionComparisonInvalid => 4042,
+            Error::TagRuleInvalid => 4043,
+            Error::TagRuleRegexInvalid(_) => 4044,
+            Error::PermissionAclInvalid => 4045,
+            Error::RefreshTokenClientInvalid => 4046,
+            Error::RecoveryContactInvalid => 4047,
+            Error::AccountRecoveryNotReady => 4048,
+            Error::OauthApplicationInvalid => 4049,
+            Error::OauthScopeInvalid => 4050,
+            Error::OauthRedirectUriInvalid => 4051,
+            Error::OauthGrantInvalid => 4052,
+            Error::ServiceAccountInvalid => 4053,
+            Error::UploadRuleInvalid => 4054,
+            Error::CannotHideStableRevision => 4055,
+            Error::StableRevisionHidden => 4056,
+            Error::CannotRevertHiddenRevision => 4057,
+            Error::ScheduledTaskInvalid => 4058,
+            Error::ScheduledTaskQuotaExceeded => 4059,
+            Error::CannotPurgeVisibleRevision => 4060,
+            Error::FileLicensingInvalid { .. } => 4061,
+            Error::ThumbnailSizeInvalid => 4062,
+            Error::PendingUploadExpired => 4063,
+            Error::PendingUploadMissing => 4064,
+            Error::PendingUploadMismatch => 4065,
+            Error::PendingUploadQuotaExceeded => 4066,
+            Error::PendingUploadWrongOffset => 4067,
+            Error::PendingUploadIncomplete => 4068,
+            Error::StorageQuotaExceeded { .. } => 4069,
+            Error::FileInfected(_) => 4070,
+            Error::CustomBlockInvalid(_) => 4071,
+            Error::StagedFileExpired => 4072,
+            Error::StagingQuotaExceeded => 4073,
+            Error::FilePasteNotImage => 4074,
+            Error::FileBlobHidden => 4075,
+            Error::PendingUploadChunkMismatch => 4076,
+            Error::PageRevisionWikitextHidden => 4077,
+            Error::FileSvgDisallowed => 4078,
+            Error::FileSvgInvalid(_) => 4079,
+            Error::CdnSignatureInvalid => 4080,
+            Error::CdnPathExpired => 4081,
+            Error::BlobMigrationNotConfigured => 4082,
+            Error::PageTemplateParametersInvalid { .. } => 4083,
+            Error::PageFormSchemaInvalid(_) => 4084,
+            Error::PageFormInvalid { .. } => 4085,
 
             // 4100 -- Localization
             Error::LocaleInvalid(_) => 4100,
@@ -394,6 +875,13 @@ impl Error {
             Error::InvalidAuthentication => 5000,
             Error::InvalidSessionToken => 5001,
             Error::SessionUserId { .. } => 5002,
+            Error::ImpersonationNotAllowed => 5003,
+            Error::ImpersonationForbidden => 5004,
+            Error::ServiceAccountRequired => 5005,
+            Error::ServiceAccountForbidden => 5006,
+            Error::UploadRestricted(_) => 5007,
+            Error::FileDownloadForbidden => 5008,
+            Error::MethodForbidden(_) => 5009,
             // TODO: permission errors (e.g. locked page, cannot apply bans)
         }
     }
@@ -421,20 +909,81 @@ impl Error {
                 "active_user_id": active_user_id,
                 "session_user_id": session_user_id,
             }),
+            Error::RenderOverloaded { retry_after_secs } => json!({
+                "retry_after_secs": retry_after_secs,
+            }),
+            Error::FileLicensingInvalid { fields }
+            | Error::PageFormInvalid { fields } => {
+                json!({
+                    "fields": fields,
+                })
+            }
+            Error::PageTemplateParametersInvalid { missing, unknown } => json!({
+                "missing": missing,
+                "unknown": unknown,
+            }),
+            Error::VersionConflict {
+                expected_version,
+                current_version,
+                current,
+            } => json!({
+                "expected_version": expected_version,
+                "current_version": current_version,
+                "current": current,
+            }),
+            Error::PageEditConflict {
+                base_revision_id,
+                latest_revision_id,
+                conflicts,
+            } => json!({
+                "base_revision_id": base_revision_id,
+                "latest_revision_id": latest_revision_id,
+                "conflicts": conflicts,
+            }),
+            Error::LimitExceeded {
+                limit,
+                maximum,
+                actual,
+            } => json!({
+                "limit": limit,
+                "maximum": maximum,
+                "actual": actual,
+            }),
+            Error::StorageQuotaExceeded {
+                quota,
+                used,
+                requested,
+            } => json!({
+                "quota": quota,
+                "used": used,
+                "requested": requested,
+            }),
 
             // Emit as-is
             Error::EmailVerification(value) => json!(value),
+            Error::ExportBuild(value) => json!(value),
+            Error::Git(value) => json!(value),
+            Error::UploadRestricted(value) => json!(value),
+            Error::MethodForbidden(value) => json!(value),
+            Error::FileInfected(value) => json!(value),
+            Error::CustomBlockInvalid(value) => json!(value.name()),
+            Error::PageFormSchemaInvalid(value) => json!(value),
 
             // Emit as a Debug string
             Error::Cryptography(value) => json!(format!("{value:?}")),
             Error::Database(value) => json!(format!("{value:?}")),
             Error::LocaleInvalid(value) => json!(format!("{value:?}")),
+            Error::Image(value) => json!(format!("{value:?}")),
+            Error::Zip(value) => json!(format!("{value:?}")),
+            Error::BlobCompression(value) => json!(format!("{value:?}")),
+            Error::BlobFilesystem(value) => json!(format!("{value:?}")),
             Error::Magic(value) => json!(format!("{value:?}")),
             Error::Otp(value) => json!(format!("{value:?}")),
             Error::Serde(value) => json!(format!("{value:?}")),
             Error::S3Service(value) => json!(format!("{value:?}")),
             Error::WebRequest(value) => json!(format!("{value:?}")),
             Error::FilterRegexInvalid(value) => json!(format!("{value:?}")),
+            Error::TagRuleRegexInvalid(value) => json!(format!("{value:?}")),
 
             // Other cases are null enums or the values are ignored
             _ => json!(null),
@@ -496,3 +1045,29 @@ pub fn into_rpc_error(error: TransactionError<ErrorObjectOwned>) -> ErrorObjectO
         TransactionError::Transaction(error) => error,
     }
 }
+
+/// Checks the version of an object against what the client last saw.
+///
+/// Used for optimistic locking on updates. If the client passed an expected
+/// version and it does not match, they are returned the current state of the
+/// object in the error, so they can merge their changes and try again.
+pub fn check_version<T: Serialize>(
+    expected_version: Option<i32>,
+    current_version: i32,
+    current: &T,
+) -> Result<()> {
+    match expected_version {
+        Some(expected_version) if expected_version != current_version => {
+            error!(
+                "Version conflict, expected {expected_version} but is currently {current_version}",
+            );
+
+            Err(Error::VersionConflict {
+                expected_version,
+                current_version,
+                current: serde_json::to_value(current)?,
+            })
+        }
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,46 @@
+/*
+ * services/event/mod.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The event service, which decouples services from the reactions to their changes.
+//!
+//! Rather than calling each other directly, services emit an `Event` describing
+//! what changed. Every `Subscriber` is then notified of it, and decides for itself
+//! whether it needs to do anything, such as outdating cached renders.
+//!
+//! Events use a transactional outbox: they are inserted into the `event_outbox`
+//! table as part of the same transaction as the change itself. This means they are
+//! only delivered if that change commits, and that an event is not lost if DEEPWELL
+//! dies before delivering it. Delivery happens in a job after the fact, and any
+//! stragglers are picked up by a recurring sweep job.
+//!
+//! Because an event may be delivered more than once (for instance, if the job
+//! runs but its transaction fails to commit), subscribers must be idempotent.
+
+mod prelude {
+    pub use super::super::prelude::*;
+    pub use super::structs::*;
+}
+
+mod service;
+mod structs;
+mod subscriber;
+
+pub use self::service::EventService;
+pub use self::structs::*;
@@ -0,0 +1,166 @@
+/*
+ * services/event/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use super::subscriber::SUBSCRIBERS;
+use crate::models::event_outbox::{
+    self, Entity as EventOutbox, Model as EventOutboxModel,
+};
+use crate::services::job::{Job, JobService};
+use sea_orm::TransactionTrait;
+use sea_query::{LockBehavior, LockType};
+use std::time::Duration;
+
+/// The maximum number of events delivered by a single dispatch job.
+const DISPATCH_BATCH_SIZE: u64 = 50;
+
+/// How long to wait after emitting an event before it is dispatched.
+///
+/// The dispatch job is queued before the emitting transaction commits,
+/// and the event cannot be seen until it does, so we give it a moment.
+/// If it isn't ready yet, the event will be caught by the sweep job instead.
+const DISPATCH_DELAY: Option<Duration> = Some(Duration::from_secs(1));
+
+#[derive(Debug)]
+pub struct EventService;
+
+impl EventService {
+    /// Records an event in the outbox, to be delivered once this transaction commits.
+    pub async fn emit(ctx: &ServiceContext<'_>, event: Event) -> Result<()> {
+        debug!("Emitting event {event:?}");
+
+        let txn = ctx.transaction();
+        let model = event_outbox::ActiveModel {
+            created_at: Set(now()),
+            event: Set(serde_json::to_value(&event)?),
+            attempts: Set(0),
+            last_error: Set(None),
+            ..Default::default()
+        };
+
+        model.insert(txn).await?;
+        JobService::queue_job(ctx, &Job::DispatchEvents, DISPATCH_DELAY).await
+    }
+
+    /// Delivers the oldest pending events in the outbox to all subscribers.
+    ///
+    /// Rows being delivered by another worker are skipped, so multiple
+    /// dispatch jobs can safely run at the same time.
+    ///
+    /// # Returns
+    /// Whether the batch was full, meaning more events may still be pending.
+    pub async fn dispatch(ctx: &ServiceContext<'_>) -> Result<bool> {
+        let txn = ctx.transaction();
+        let max_attempts =
+            i16::try_from(ctx.config().job_max_attempts).unwrap_or(i16::MAX);
+        let events = EventOutbox::find()
+            .filter(event_outbox::Column::Attempts.lt(max_attempts))
+            .order_by_asc(event_outbox::Column::EventId)
+            .limit(DISPATCH_BATCH_SIZE)
+            .lock_with_behavior(LockType::Update, LockBehavior::SkipLocked)
+            .all(txn)
+            .await?;
+
+        info!("Dispatching {} events from the outbox", events.len());
+        let full_batch = events.len() as u64 == DISPATCH_BATCH_SIZE;
+        for model in events {
+            Self::deliver(ctx, model).await?;
+        }
+
+        Ok(full_batch)
+    }
+
+    /// Counts the events in the outbox which have not yet been delivered.
+    ///
+    /// This includes events whose delivery has failed too many times,
+    /// which will not be retried.
+    pub async fn count_pending(ctx: &ServiceContext<'_>) -> Result<u64> {
+        let txn = ctx.transaction();
+        let count = EventOutbox::find().count(txn).await?;
+        Ok(count)
+    }
+
+    /// Delivers a single event, removing it from the outbox if successful.
+    ///
+    /// Subscribers run within a savepoint, so that if any of them fail,
+    /// none of their changes are kept. The event then remains in the outbox
+    /// with its error recorded, to be retried later.
+    async fn deliver(
+        ctx: &ServiceContext<'_>,
+        EventOutboxModel {
+            event_id,
+            event,
+            attempts,
+            ..
+        }: EventOutboxModel,
+    ) -> Result<()> {
+        debug!("Delivering event ID {event_id} (attempt {})", attempts + 1);
+
+        let txn = ctx.transaction();
+        let result = match serde_json::from_value(event) {
+            Ok(event) => {
+                let savepoint = txn.begin().await?;
+                let result =
+                    Self::notify_all(&ctx.with_transaction(&savepoint), event_id, &event)
+                        .await;
+
+                match result {
+                    Ok(()) => savepoint.commit().await?,
+                    Err(_) => savepoint.rollback().await?,
+                }
+
+                result
+            }
+            Err(error) => Err(Error::from(error)),
+        };
+
+        match result {
+            Ok(()) => {
+                EventOutbox::delete_by_id(event_id).exec(txn).await?;
+            }
+            Err(error) => {
+                warn!("Unable to deliver event ID {event_id}: {error}");
+
+                let model = event_outbox::ActiveModel {
+                    event_id: Set(event_id),
+                    attempts: Set(attempts + 1),
+                    last_error: Set(Some(error.to_string())),
+                    ..Default::default()
+                };
+
+                model.update(txn).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn notify_all(
+        ctx: &ServiceContext<'_>,
+        event_id: i64,
+        event: &Event,
+    ) -> Result<()> {
+        for subscriber in SUBSCRIBERS {
+            subscriber.notify(ctx, event_id, event).await?;
+        }
+
+        Ok(())
+    }
+}
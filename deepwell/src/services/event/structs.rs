@@ -0,0 +1,108 @@
+/*
+ * services/event/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::models::sea_orm_active_enums::{FileRevisionChange, PageRevisionChange};
+use crate::services::page_form::FormFieldChange;
+
+/// Something which has happened, which other services may want to react to.
+///
+/// Events describe the change, not what should be done about it.
+/// Each subscriber decides for itself which events it cares about.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "snake_case", tag = "event", content = "data")]
+pub enum Event {
+    PageCreated {
+        site_id: i64,
+        page_id: i64,
+        revision_id: i64,
+        slug: String,
+    },
+    PageEdited {
+        site_id: i64,
+        page_id: i64,
+        revision_id: i64,
+        slug: String,
+        changes: Vec<PageRevisionChange>,
+    },
+    PageMoved {
+        site_id: i64,
+        page_id: i64,
+        revision_id: i64,
+        old_slug: String,
+        new_slug: String,
+    },
+    PageDeleted {
+        site_id: i64,
+        page_id: i64,
+        revision_id: i64,
+        slug: String,
+    },
+    PageRestored {
+        site_id: i64,
+        page_id: i64,
+        revision_id: i64,
+        slug: String,
+    },
+    PageFieldsChanged {
+        site_id: i64,
+        page_id: i64,
+        category_id: i64,
+        changes: Vec<FormFieldChange>,
+    },
+    FileCreated {
+        site_id: i64,
+        page_id: i64,
+        file_id: i64,
+        page_slug: String,
+    },
+    FileEdited {
+        site_id: i64,
+        page_id: i64,
+        file_id: i64,
+        page_slug: String,
+        changes: Vec<FileRevisionChange>,
+    },
+    FileMoved {
+        site_id: i64,
+        file_id: i64,
+        old_page_id: i64,
+        new_page_id: i64,
+        old_page_slug: String,
+        new_page_slug: String,
+    },
+    FileDeleted {
+        site_id: i64,
+        page_id: i64,
+        file_id: i64,
+        page_slug: String,
+    },
+    FileRestored {
+        site_id: i64,
+        page_id: i64,
+        file_id: i64,
+        page_slug: String,
+    },
+    UserUpdated {
+        user_id: i64,
+    },
+    UserDeleted {
+        user_id: i64,
+    },
+}
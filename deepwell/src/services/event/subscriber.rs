@@ -0,0 +1,312 @@
+/*
+ * services/event/subscriber.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The listeners which react to events.
+//!
+//! To add a new listener, add a variant to `Subscriber`, include it in
+//! `SUBSCRIBERS`, and handle whichever events it cares about in `notify()`.
+
+use super::prelude::*;
+use crate::models::sea_orm_active_enums::FileRevisionChange;
+use crate::services::file_revision::FileRevisionTasks;
+use crate::services::page_revision::PageRevisionTasks;
+use crate::services::{
+    EventStreamService, GitMirrorService, OutdateService, PageFieldWatchService,
+    SearchService,
+};
+use crate::utils::split_category_name;
+
+macro_rules! conditional_future {
+    ($conditional:expr, $future:expr $(,)?) => {
+        async move {
+            if $conditional {
+                $future.await
+            } else {
+                Ok(())
+            }
+        }
+    };
+}
+
+/// Every subscriber, in the order they are notified.
+pub const SUBSCRIBERS: [Subscriber; 5] = [
+    Subscriber::Outdate,
+    Subscriber::GitMirror,
+    Subscriber::SearchIndex,
+    Subscriber::FieldWatch,
+    Subscriber::EventStream,
+];
+
+#[derive(Debug, Copy, Clone)]
+pub enum Subscriber {
+    /// Rerenders dependent pages and purges the public API cache.
+    Outdate,
+
+    /// Brings the site's Git mirror up to date with its pages.
+    GitMirror,
+
+    /// Keeps the search index in sync with pages and files.
+    SearchIndex,
+
+    /// Notifies users watching form fields which have changed.
+    FieldWatch,
+
+    /// Publishes every event to Redis, for consumers outside this process.
+    EventStream,
+}
+
+impl Subscriber {
+    pub async fn notify(
+        self,
+        ctx: &ServiceContext<'_>,
+        event_id: i64,
+        event: &Event,
+    ) -> Result<()> {
+        trace!("Notifying subscriber {self:?} of event ID {event_id}");
+
+        match self {
+            Subscriber::Outdate => Self::outdate(ctx, event).await,
+            Subscriber::GitMirror => Self::git_mirror(ctx, event).await,
+            Subscriber::SearchIndex => Self::search_index(ctx, event).await,
+            Subscriber::FieldWatch => Self::field_watch(ctx, event).await,
+            Subscriber::EventStream => {
+                EventStreamService::publish(ctx, event_id, event).await
+            }
+        }
+    }
+
+    async fn outdate(ctx: &ServiceContext<'_>, event: &Event) -> Result<()> {
+        match *event {
+            Event::PageCreated {
+                site_id,
+                page_id,
+                ref slug,
+                ..
+            }
+            | Event::PageDeleted {
+                site_id,
+                page_id,
+                ref slug,
+                ..
+            }
+            | Event::PageRestored {
+                site_id,
+                page_id,
+                ref slug,
+                ..
+            } => {
+                OutdateService::process_page_displace(ctx, site_id, page_id, slug, 0)
+                    .await
+            }
+            Event::PageEdited {
+                site_id,
+                page_id,
+                ref slug,
+                ref changes,
+                ..
+            } => {
+                // Only outdate what could have been affected by these changes.
+                // See PageRevisionTasks for more information.
+                let tasks = PageRevisionTasks::determine(changes);
+                let (category_slug, page_slug) = split_category_name(slug);
+
+                // This macro runs the given method (second value) if the condition (first value)
+                // is true, otherwise does nothing.
+                try_join!(
+                    conditional_future!(
+                        tasks.rerender_incoming_links,
+                        OutdateService::outdate_incoming_links(ctx, page_id, 0),
+                    ),
+                    conditional_future!(
+                        tasks.rerender_outgoing_includes,
+                        OutdateService::outdate_outgoing_includes(ctx, page_id, 0),
+                    ),
+                    conditional_future!(
+                        tasks.rerender_templates,
+                        OutdateService::outdate_templates(
+                            ctx,
+                            site_id,
+                            category_slug,
+                            page_slug,
+                            0,
+                        ),
+                    ),
+                    OutdateService::outdate_public_page(ctx, site_id, page_id),
+                )?;
+
+                Ok(())
+            }
+            Event::PageMoved {
+                site_id,
+                page_id,
+                ref old_slug,
+                ref new_slug,
+                ..
+            } => {
+                OutdateService::process_page_move(
+                    ctx, site_id, page_id, old_slug, new_slug, 0,
+                )
+                .await
+            }
+            // Pages refer to files by name, so these may have been shown as missing
+            Event::FileCreated {
+                site_id,
+                page_id,
+                file_id,
+                ref page_slug,
+            }
+            | Event::FileRestored {
+                site_id,
+                page_id,
+                file_id,
+                ref page_slug,
+            } => {
+                try_join!(
+                    OutdateService::outdate_file_references(
+                        ctx, site_id, page_slug, file_id, 0,
+                    ),
+                    OutdateService::outdate_public_page(ctx, site_id, page_id),
+                )?;
+
+                Ok(())
+            }
+            Event::FileEdited {
+                site_id,
+                page_id,
+                file_id,
+                ref page_slug,
+                ref changes,
+            } => {
+                // Pages displaying the file only need rerendering if its output changed,
+                // metadata-only edits just need the public cache purged.
+                // See FileRevisionTasks for more information.
+                let tasks = FileRevisionTasks::determine(changes);
+                let renamed = changes.contains(&FileRevisionChange::Name);
+
+                try_join!(
+                    conditional_future!(
+                        tasks.rerender_embedding_pages,
+                        OutdateService::outdate_file_embeds(ctx, file_id, 0),
+                    ),
+                    conditional_future!(
+                        renamed,
+                        OutdateService::outdate_file_references(
+                            ctx, site_id, page_slug, file_id, 0,
+                        ),
+                    ),
+                    OutdateService::outdate_public_page(ctx, site_id, page_id),
+                )?;
+
+                Ok(())
+            }
+            Event::FileDeleted {
+                site_id,
+                page_id,
+                file_id,
+                ..
+            } => {
+                try_join!(
+                    OutdateService::outdate_file_embeds(ctx, file_id, 0),
+                    OutdateService::outdate_public_page(ctx, site_id, page_id),
+                )?;
+
+                Ok(())
+            }
+            Event::FileMoved {
+                site_id,
+                file_id,
+                old_page_id,
+                new_page_id,
+                ref new_page_slug,
+                ..
+            } => {
+                try_join!(
+                    OutdateService::outdate_file_embeds(ctx, file_id, 0),
+                    OutdateService::outdate_file_references(
+                        ctx,
+                        site_id,
+                        new_page_slug,
+                        file_id,
+                        0,
+                    ),
+                    OutdateService::outdate_public_page(ctx, site_id, old_page_id),
+                    OutdateService::outdate_public_page(ctx, site_id, new_page_id),
+                )?;
+
+                Ok(())
+            }
+            Event::UserUpdated { user_id } | Event::UserDeleted { user_id } => {
+                OutdateService::outdate_public_user(ctx, user_id).await
+            }
+            // The page was already rerendered, this is only derived from it
+            Event::PageFieldsChanged { .. } => Ok(()),
+        }
+    }
+
+    async fn git_mirror(ctx: &ServiceContext<'_>, event: &Event) -> Result<()> {
+        match *event {
+            Event::PageCreated { site_id, .. }
+            | Event::PageEdited { site_id, .. }
+            | Event::PageMoved { site_id, .. }
+            | Event::PageDeleted { site_id, .. }
+            | Event::PageRestored { site_id, .. } => {
+                GitMirrorService::queue_sync(ctx, site_id).await
+            }
+            _ => Ok(()),
+        }
+    }
+
+    async fn search_index(ctx: &ServiceContext<'_>, event: &Event) -> Result<()> {
+        match *event {
+            Event::PageCreated { page_id, .. }
+            | Event::PageEdited { page_id, .. }
+            | Event::PageMoved { page_id, .. }
+            | Event::PageDeleted { page_id, .. }
+            | Event::PageRestored { page_id, .. } => {
+                SearchService::index_page(ctx, page_id).await
+            }
+            Event::FileCreated { file_id, .. }
+            | Event::FileEdited { file_id, .. }
+            | Event::FileMoved { file_id, .. }
+            | Event::FileDeleted { file_id, .. }
+            | Event::FileRestored { file_id, .. } => {
+                SearchService::index_file(ctx, file_id).await
+            }
+            Event::PageFieldsChanged { .. }
+            | Event::UserUpdated { .. }
+            | Event::UserDeleted { .. } => Ok(()),
+        }
+    }
+
+    async fn field_watch(ctx: &ServiceContext<'_>, event: &Event) -> Result<()> {
+        match *event {
+            Event::PageFieldsChanged {
+                site_id,
+                page_id,
+                category_id,
+                ref changes,
+            } => {
+                PageFieldWatchService::notify(ctx, site_id, page_id, category_id, changes)
+                    .await
+            }
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,43 @@
+/*
+ * services/event_stream/mod.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The event stream service, which publishes domain events to a Redis stream.
+//!
+//! This bridges the event outbox (see `EventService`) to other DEEPWELL instances
+//! and external workers. Each consumer creates a consumer group on the stream,
+//! and Redis tracks which entries have been delivered to and acknowledged by it.
+//!
+//! Entries are published after the change which produced them commits, but an
+//! entry may be published twice if delivery is retried. Each entry includes the
+//! outbox event ID so consumers can discard any duplicates.
+//!
+//! Consumer groups can be rewound to replay retained events, which is used to
+//! rebuild read models (such as a search index) from scratch.
+
+mod prelude {
+    pub use super::super::prelude::*;
+    pub use super::structs::*;
+}
+
+mod service;
+mod structs;
+
+pub use self::service::EventStreamService;
+pub use self::structs::*;
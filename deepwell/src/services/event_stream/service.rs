@@ -0,0 +1,236 @@
+/*
+ * services/event_stream/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::services::event::{Event, EventService};
+use redis::{FromRedisValue, Value};
+use std::collections::HashMap;
+
+#[derive(Debug)]
+pub struct EventStreamService;
+
+impl EventStreamService {
+    /// Adds an event to the stream, if it is enabled.
+    pub async fn publish(
+        ctx: &ServiceContext<'_>,
+        event_id: i64,
+        event: &Event,
+    ) -> Result<()> {
+        let config = ctx.config();
+        if !config.event_stream_enabled {
+            return Ok(());
+        }
+
+        debug!("Publishing event ID {event_id} to stream");
+        let data = serde_json::to_string(event)?;
+        let _: String = ctx
+            .until_deadline(
+                redis::cmd("XADD")
+                    .arg(&config.event_stream_key)
+                    .arg("MAXLEN")
+                    .arg("~")
+                    .arg(config.event_stream_max_length)
+                    .arg("*")
+                    .arg("event_id")
+                    .arg(event_id)
+                    .arg("event")
+                    .arg(data)
+                    .query_async(&mut ctx.redis()),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    /// Gets the state of the stream and the progress of each consumer group.
+    pub async fn get_info(ctx: &ServiceContext<'_>) -> Result<EventStreamInfo> {
+        let key = Self::key(ctx)?;
+        info!("Getting information for event stream '{key}'");
+
+        let length = ctx
+            .until_deadline(redis::cmd("XLEN").arg(key).query_async(&mut ctx.redis()))
+            .await?;
+
+        let outbox_pending = EventService::count_pending(ctx).await?;
+        let groups = Self::get_groups(ctx).await?;
+        Ok(EventStreamInfo {
+            key: str!(key),
+            length,
+            outbox_pending,
+            groups,
+        })
+    }
+
+    pub async fn create_group(
+        ctx: &ServiceContext<'_>,
+        CreateEventStreamGroup {
+            name,
+            from_beginning,
+        }: CreateEventStreamGroup,
+    ) -> Result<()> {
+        let key = Self::key(ctx)?;
+        info!("Creating consumer group '{name}' on event stream '{key}'");
+
+        if name.is_empty() {
+            error!("Consumer group name is empty");
+            return Err(Error::EventStreamGroupNameEmpty);
+        }
+
+        if Self::get_group(ctx, &name).await?.is_some() {
+            error!("Consumer group '{name}' already exists");
+            return Err(Error::EventStreamGroupExists);
+        }
+
+        // "0" is before all entries, "$" is after the latest one
+        let start = if from_beginning { "0" } else { "$" };
+        let _: String = ctx
+            .until_deadline(
+                redis::cmd("XGROUP")
+                    .arg("CREATE")
+                    .arg(key)
+                    .arg(&name)
+                    .arg(start)
+                    .arg("MKSTREAM")
+                    .query_async(&mut ctx.redis()),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete_group(ctx: &ServiceContext<'_>, name: &str) -> Result<()> {
+        let key = Self::key(ctx)?;
+        info!("Deleting consumer group '{name}' from event stream '{key}'");
+
+        if Self::get_group(ctx, name).await?.is_none() {
+            error!("Consumer group '{name}' does not exist");
+            return Err(Error::EventStreamGroupNotFound);
+        }
+
+        let _: u64 = ctx
+            .until_deadline(
+                redis::cmd("XGROUP")
+                    .arg("DESTROY")
+                    .arg(key)
+                    .arg(name)
+                    .query_async(&mut ctx.redis()),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    /// Rewinds a consumer group, so its consumers receive retained events again.
+    ///
+    /// Entries which were already trimmed from the stream cannot be replayed.
+    pub async fn replay_group(
+        ctx: &ServiceContext<'_>,
+        ReplayEventStreamGroup { name, from_id }: ReplayEventStreamGroup,
+    ) -> Result<()> {
+        let key = Self::key(ctx)?;
+        let from_id = from_id.as_deref().unwrap_or("0");
+        info!(
+            "Replaying consumer group '{name}' on event stream '{key}' after {from_id}"
+        );
+
+        if Self::get_group(ctx, &name).await?.is_none() {
+            error!("Consumer group '{name}' does not exist");
+            return Err(Error::EventStreamGroupNotFound);
+        }
+
+        let _: String = ctx
+            .until_deadline(
+                redis::cmd("XGROUP")
+                    .arg("SETID")
+                    .arg(key)
+                    .arg(&name)
+                    .arg(from_id)
+                    .query_async(&mut ctx.redis()),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_group(
+        ctx: &ServiceContext<'_>,
+        name: &str,
+    ) -> Result<Option<EventStreamGroup>> {
+        let groups = Self::get_groups(ctx).await?;
+        let group = groups.into_iter().find(|group| group.name == name);
+        Ok(group)
+    }
+
+    async fn get_groups(ctx: &ServiceContext<'_>) -> Result<Vec<EventStreamGroup>> {
+        let key = Self::key(ctx)?;
+        let mut redis = ctx.redis();
+
+        // XINFO fails if the stream doesn't exist yet
+        let exists: bool = ctx
+            .until_deadline(redis::cmd("EXISTS").arg(key).query_async(&mut redis))
+            .await?;
+
+        if !exists {
+            return Ok(vec![]);
+        }
+
+        let replies: Vec<HashMap<String, Value>> = ctx
+            .until_deadline(
+                redis::cmd("XINFO")
+                    .arg("GROUPS")
+                    .arg(key)
+                    .query_async(&mut redis),
+            )
+            .await?;
+
+        let mut groups = Vec::with_capacity(replies.len());
+        for reply in replies {
+            groups.push(EventStreamGroup {
+                name: field(&reply, "name")?,
+                consumers: field(&reply, "consumers")?,
+                pending: field(&reply, "pending")?,
+                last_delivered_id: field(&reply, "last-delivered-id")?,
+                lag: field(&reply, "lag")?,
+            });
+        }
+
+        Ok(groups)
+    }
+
+    fn key<'a>(ctx: &'a ServiceContext<'_>) -> Result<&'a str> {
+        let config = ctx.config();
+        if !config.event_stream_enabled {
+            error!("Event stream is disabled");
+            return Err(Error::EventStreamDisabled);
+        }
+
+        Ok(&config.event_stream_key)
+    }
+}
+
+/// Extracts a field from a Redis map reply.
+///
+/// Missing fields are treated as nil, so use `Option<T>` for
+/// any which are not present in all Redis versions.
+fn field<T: FromRedisValue>(reply: &HashMap<String, Value>, key: &str) -> Result<T> {
+    let value = reply.get(key).unwrap_or(&Value::Nil);
+    let value = T::from_redis_value(value)?;
+    Ok(value)
+}
@@ -0,0 +1,67 @@
+/*
+ * services/event_stream/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+#[derive(Serialize, Debug, Clone)]
+pub struct EventStreamInfo {
+    pub key: String,
+    pub length: u64,
+
+    /// Events in the outbox which have not yet been published.
+    pub outbox_pending: u64,
+
+    pub groups: Vec<EventStreamGroup>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct EventStreamGroup {
+    pub name: String,
+    pub consumers: u64,
+
+    /// Entries delivered to a consumer, but not yet acknowledged.
+    pub pending: u64,
+
+    pub last_delivered_id: String,
+
+    /// Entries which have not yet been delivered to this group.
+    ///
+    /// This is `None` if Redis is unable to determine it, such as after
+    /// entries have been trimmed or deleted, or on Redis versions before 7.
+    pub lag: Option<u64>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CreateEventStreamGroup {
+    pub name: String,
+
+    /// If true, the group starts by receiving all retained events.
+    /// Otherwise it only receives events published after its creation.
+    #[serde(default)]
+    pub from_beginning: bool,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ReplayEventStreamGroup {
+    pub name: String,
+
+    /// The stream entry ID to replay after.
+    /// If not set, all retained events are replayed.
+    #[serde(default)]
+    pub from_id: Option<String>,
+}
@@ -0,0 +1,245 @@
+/*
+ * services/export/epub.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Assembly of EPUB 3 archives.
+//!
+//! This only concerns itself with the container format. The contents of each
+//! chapter must already be valid XHTML fragments, see `to_xhtml()`.
+
+use std::io::{Cursor, Write};
+use time::OffsetDateTime;
+use zip::result::ZipResult;
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+#[derive(Debug)]
+pub struct EpubBook<'a> {
+    pub identifier: &'a str,
+    pub title: &'a str,
+    pub language: &'a str,
+    pub modified: OffsetDateTime,
+    pub contents_title: &'a str,
+    pub chapters: Vec<EpubChapter>,
+    pub images: Vec<EpubImage>,
+}
+
+#[derive(Debug)]
+pub struct EpubChapter {
+    pub title: String,
+    pub body: String,
+}
+
+#[derive(Debug)]
+pub struct EpubImage {
+    /// Path of the image, relative to the chapters.
+    pub path: String,
+    pub mime: String,
+    pub data: Vec<u8>,
+}
+
+pub fn build_epub(book: &EpubBook) -> ZipResult<Vec<u8>> {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let stored = FileOptions::default().compression_method(CompressionMethod::Stored);
+    let deflated = FileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    // The MIME type must be first and uncompressed, so it can be sniffed
+    zip.start_file("mimetype", stored)?;
+    zip.write_all(b"application/epub+zip")?;
+
+    zip.start_file("META-INF/container.xml", deflated)?;
+    zip.write_all(CONTAINER_XML.as_bytes())?;
+
+    zip.start_file("OEBPS/content.opf", deflated)?;
+    zip.write_all(package_document(book).as_bytes())?;
+
+    zip.start_file("OEBPS/nav.xhtml", deflated)?;
+    zip.write_all(navigation_document(book).as_bytes())?;
+
+    for (index, chapter) in book.chapters.iter().enumerate() {
+        let body = format!("<h1>{}</h1>{}", escape(&chapter.title), chapter.body);
+        zip.start_file(format!("OEBPS/{}", chapter_path(index)), deflated)?;
+        zip.write_all(xhtml_document(book.language, &chapter.title, &body).as_bytes())?;
+    }
+
+    // Images are already compressed
+    for image in &book.images {
+        zip.start_file(format!("OEBPS/{}", image.path), stored)?;
+        zip.write_all(&image.data)?;
+    }
+
+    let output = zip.finish()?;
+    Ok(output.into_inner())
+}
+
+const CONTAINER_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles>
+    <rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/>
+  </rootfiles>
+</container>
+"#;
+
+fn chapter_path(index: usize) -> String {
+    format!("text/chapter-{}.xhtml", index + 1)
+}
+
+fn package_document(book: &EpubBook) -> String {
+    let modified = book.modified.to_offset(time::UtcOffset::UTC);
+    let mut manifest = String::new();
+    let mut spine = String::new();
+
+    for index in 0..book.chapters.len() {
+        let id = format!("chapter-{}", index + 1);
+        manifest.push_str(&format!(
+            r#"<item id="{id}" href="{}" media-type="application/xhtml+xml"/>"#,
+            chapter_path(index),
+        ));
+        spine.push_str(&format!(r#"<itemref idref="{id}"/>"#));
+    }
+
+    for (index, image) in book.images.iter().enumerate() {
+        manifest.push_str(&format!(
+            r#"<item id="image-{}" href="{}" media-type="{}"/>"#,
+            index + 1,
+            escape(&image.path),
+            escape(&image.mime),
+        ));
+    }
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="3.0" unique-identifier="book-id" xml:lang="{language}">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+    <dc:identifier id="book-id">{identifier}</dc:identifier>
+    <dc:title>{title}</dc:title>
+    <dc:language>{language}</dc:language>
+    <meta property="dcterms:modified">{year:04}-{month:02}-{day:02}T{hour:02}:{minute:02}:{second:02}Z</meta>
+  </metadata>
+  <manifest><item id="nav" href="nav.xhtml" media-type="application/xhtml+xml" properties="nav"/>{manifest}</manifest>
+  <spine><itemref idref="nav"/>{spine}</spine>
+</package>
+"#,
+        language = escape(book.language),
+        identifier = escape(book.identifier),
+        title = escape(book.title),
+        year = modified.year(),
+        month = u8::from(modified.month()),
+        day = modified.day(),
+        hour = modified.hour(),
+        minute = modified.minute(),
+        second = modified.second(),
+    )
+}
+
+fn navigation_document(book: &EpubBook) -> String {
+    let mut body = format!(
+        r#"<nav epub:type="toc" id="toc"><h1>{}</h1><ol>"#,
+        escape(book.contents_title),
+    );
+
+    for (index, chapter) in book.chapters.iter().enumerate() {
+        body.push_str(&format!(
+            r#"<li><a href="{}">{}</a></li>"#,
+            chapter_path(index),
+            escape(&chapter.title),
+        ));
+    }
+
+    body.push_str("</ol></nav>");
+    xhtml_document(book.language, book.contents_title, &body)
+}
+
+fn xhtml_document(language: &str, title: &str, body: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE html>
+<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops" xml:lang="{language}" lang="{language}">
+<head><meta charset="UTF-8"/><title>{title}</title></head>
+<body>{body}</body>
+</html>
+"#,
+        language = escape(language),
+        title = escape(title),
+    )
+}
+
+/// Escapes text for inclusion in XML.
+pub fn escape(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => output.push_str("&amp;"),
+            '<' => output.push_str("&lt;"),
+            '>' => output.push_str("&gt;"),
+            '"' => output.push_str("&quot;"),
+            '\'' => output.push_str("&#39;"),
+            _ => output.push(ch),
+        }
+    }
+    output
+}
+
+#[test]
+fn epub() {
+    use zip::ZipArchive;
+
+    let book = EpubBook {
+        identifier: "urn:test:1",
+        title: "Test <Book>",
+        language: "en",
+        modified: OffsetDateTime::UNIX_EPOCH,
+        contents_title: "Contents",
+        chapters: vec![
+            EpubChapter {
+                title: str!("First"),
+                body: str!("<p>Apple</p>"),
+            },
+            EpubChapter {
+                title: str!("Second"),
+                body: str!("<p>Banana</p>"),
+            },
+        ],
+        images: vec![EpubImage {
+            path: str!("images/1.png"),
+            mime: str!("image/png"),
+            data: vec![0, 1, 2, 3],
+        }],
+    };
+
+    let data = build_epub(&book).expect("Unable to build EPUB");
+    let mut archive = ZipArchive::new(Cursor::new(data)).expect("Invalid zip archive");
+
+    let names: Vec<&str> = archive.file_names().collect();
+    for name in [
+        "mimetype",
+        "META-INF/container.xml",
+        "OEBPS/content.opf",
+        "OEBPS/nav.xhtml",
+        "OEBPS/text/chapter-1.xhtml",
+        "OEBPS/text/chapter-2.xhtml",
+        "OEBPS/images/1.png",
+    ] {
+        assert!(names.contains(&name), "Missing file {name} in EPUB");
+    }
+
+    let mimetype = archive.by_index(0).expect("No first file");
+    assert_eq!(mimetype.name(), "mimetype");
+    assert_eq!(mimetype.compression(), CompressionMethod::Stored);
+}
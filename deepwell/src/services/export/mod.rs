@@ -0,0 +1,48 @@
+/*
+ * services/export/mod.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The export service, for bundling pages into downloadable documents.
+//!
+//! An export is requested for some selection of pages, and then built
+//! in the background by the job worker, since fetching every page and
+//! its images can take a while. Once completed, the document is stored
+//! as a blob and can be downloaded from the site under `/-/export/`.
+//!
+//! Both EPUB and PDF documents can be produced. EPUB files are assembled
+//! here directly, whereas PDFs are rendered from HTML by an external headless
+//! browser service, whose URL is set in the configuration.
+//!
+//! Each export pins the revision of every page it was requested with, so an
+//! identical request made before any of those pages change reuses the same
+//! export instead of building a new one.
+
+mod prelude {
+    pub use super::super::prelude::*;
+    pub use super::structs::*;
+}
+
+mod epub;
+mod pdf;
+mod service;
+mod structs;
+mod xhtml;
+
+pub use self::service::ExportService;
+pub use self::structs::*;
@@ -0,0 +1,209 @@
+/*
+ * services/export/pdf.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Rendering of exports into PDF documents.
+//!
+//! Laying out paged media is a substantial task, so rather than doing it here,
+//! a standalone HTML document is sent to a headless browser service (such as
+//! Gotenberg or a Chromium wrapper), which responds with the PDF.
+//!
+//! The service is expected to accept a `POST` with the HTML as the body
+//! and return the PDF bytes with a successful status code.
+//!
+//! Page contents are written by users, so the document carries a policy which
+//! blocks the renderer from loading anything not embedded in it, except from
+//! the configured origins. Otherwise a page could have the renderer make
+//! requests to services which are only reachable from inside the network.
+
+use super::epub::escape;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::time::Duration as StdDuration;
+
+/// Matches elements which a headless renderer is unlikely to print.
+static UNCONVERTIBLE_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)<(audio|canvas|embed|iframe|object|script|video)\b[^>]*>").unwrap()
+});
+
+/// How much of an unconvertible element or error response to report.
+const DIAGNOSTIC_LENGTH: usize = 100;
+
+const STYLESHEET: &str = "
+.wj-export-page { break-after: page; }
+.wj-export-page:last-child { break-after: auto; }
+img { max-width: 100%; }
+";
+
+/// Builds a standalone HTML document, with each section on its own page.
+///
+/// Relative URLs in the sections are resolved against `base_url`, though
+/// they can only be loaded if they are from one of the `allowed_origins`.
+pub fn build_document(
+    language: &str,
+    title: &str,
+    base_url: &str,
+    allowed_origins: &[String],
+    sections: &[(&str, &str)],
+) -> String {
+    let mut html = format!(
+        "<!DOCTYPE html><html lang=\"{language}\"><head><meta charset=\"utf-8\"><base href=\"{base_url}\"><meta http-equiv=\"Content-Security-Policy\" content=\"{policy}\"><title>{title}</title><style>{STYLESHEET}</style></head><body>",
+        language = escape(language),
+        base_url = escape(base_url),
+        policy = escape(&content_security_policy(allowed_origins)),
+        title = escape(title),
+    );
+
+    for (title, body) in sections {
+        html.push_str("<section class=\"wj-export-page\"><h1>");
+        html.push_str(&escape(title));
+        html.push_str("</h1>");
+        html.push_str(body);
+        html.push_str("</section>");
+    }
+
+    html.push_str("</body></html>");
+    html
+}
+
+/// Builds the policy which limits what the renderer may load for a document.
+///
+/// Scripts, frames, and other network requests are forbidden entirely.
+fn content_security_policy(allowed_origins: &[String]) -> String {
+    let mut sources = str!("data:");
+    for origin in allowed_origins {
+        sources.push(' ');
+        sources.push_str(origin);
+    }
+
+    format!(
+        "default-src 'none'; img-src {sources}; style-src 'unsafe-inline' {sources}; font-src {sources}",
+    )
+}
+
+/// Sends a document to the renderer service, returning the PDF.
+///
+/// On failure, returns a description of the problem suitable for showing to users.
+pub async fn render_pdf(
+    renderer_url: &str,
+    timeout: StdDuration,
+    html: String,
+) -> Result<Vec<u8>, String> {
+    let client = reqwest::Client::builder()
+        .timeout(timeout)
+        .build()
+        .map_err(|error| error.to_string())?;
+
+    let response = client
+        .post(renderer_url)
+        .header("Content-Type", "text/html; charset=utf-8")
+        .body(html)
+        .send()
+        .await
+        .map_err(|error| {
+            if error.is_timeout() {
+                format!("renderer timed out after {} seconds", timeout.as_secs())
+            } else {
+                format!("renderer could not be reached: {error}")
+            }
+        })?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(format!(
+            "renderer returned {status}: {}",
+            truncate(body.trim()),
+        ));
+    }
+
+    match response.bytes().await {
+        Ok(data) if data.starts_with(b"%PDF") => Ok(data.to_vec()),
+        Ok(_) => Err(str!("renderer did not return a PDF document")),
+        Err(error) => Err(format!("renderer response could not be read: {error}")),
+    }
+}
+
+/// Lists the elements in a page's HTML which may have caused rendering to fail.
+pub fn find_unconvertible(html: &str) -> Vec<String> {
+    UNCONVERTIBLE_REGEX
+        .find_iter(html)
+        .map(|mtch| truncate(mtch.as_str()))
+        .collect()
+}
+
+fn truncate(text: &str) -> String {
+    match text.char_indices().nth(DIAGNOSTIC_LENGTH) {
+        Some((index, _)) => format!("{}...", &text[..index]),
+        None => str!(text),
+    }
+}
+
+#[test]
+fn document_policy() {
+    assert_eq!(
+        content_security_policy(&[]),
+        "default-src 'none'; img-src data:; style-src 'unsafe-inline' data:; font-src data:",
+    );
+    assert_eq!(
+        content_security_policy(&[str!("https://*.wikijump.com")]),
+        "default-src 'none'; img-src data: https://*.wikijump.com; \
+         style-src 'unsafe-inline' data: https://*.wikijump.com; \
+         font-src data: https://*.wikijump.com",
+    );
+
+    let html = build_document(
+        "en",
+        "Test",
+        "https://test.wikijump.com/",
+        &[],
+        &[("Page", "<img src=\"/-/files/image.png\">")],
+    );
+    assert!(html.contains("<base href=\"https://test.wikijump.com/\">"));
+    assert!(html.contains("content=\"default-src &#39;none&#39;; img-src data:;"));
+}
+
+#[test]
+fn unconvertible() {
+    macro_rules! check {
+        ($html:expr, $expected:expr $(,)?) => {{
+            let expected: &[&str] = &$expected;
+            assert_eq!(
+                find_unconvertible($html),
+                expected,
+                "Actual unconvertible elements didn't match expected",
+            );
+        }};
+    }
+
+    check!("<p>Nothing to see here</p>", []);
+    check!(
+        r#"<p>Video:</p><iframe src="https://example.com/embed"></iframe>"#,
+        [r#"<iframe src="https://example.com/embed">"#],
+    );
+    check!(
+        "<VIDEO controls><source src=\"a.webm\"></VIDEO><script>alert(1)</script>",
+        ["<VIDEO controls>", "<script>"],
+    );
+    check!("<objection>", []);
+    check!(
+        &format!("<embed data-x=\"{}\">", "a".repeat(200)),
+        [&*format!("<embed data-x=\"{}...", "a".repeat(85))],
+    );
+}
@@ -0,0 +1,765 @@
+/*
+ * services/export/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::epub::{build_epub, escape, EpubBook, EpubChapter, EpubImage};
+use super::pdf;
+use super::prelude::*;
+use super::xhtml::to_xhtml;
+use crate::models::export::{self, Entity as Export, Model as ExportModel};
+use crate::models::file::{self, Entity as File};
+use crate::models::page::Model as PageModel;
+use crate::models::page_attribution::{self, Entity as PageAttribution};
+use crate::models::page_revision::{
+    self, Entity as PageRevision, Model as PageRevisionModel,
+};
+use crate::models::sea_orm_active_enums::{ExportFormat, ExportStatus};
+use crate::models::site::Model as SiteModel;
+use crate::services::file::sanitize_stored_svg;
+use crate::services::job::Job;
+use crate::services::{
+    BlobService, DomainService, JobService, PageRevisionService, PageService,
+    SiteService, StableRevisionService, TextService, UserService,
+};
+use crate::web::{Bytes, PageOrder};
+use fluent::{FluentArgs, FluentValue};
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use sea_query::Expr;
+use std::collections::HashMap;
+use time::Duration;
+use unic_langid::LanguageIdentifier;
+use wikidot_normalize::normalize;
+
+/// Matches image sources pointing to files attached to a page.
+static LOCAL_FILE_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"src="[^"]*/local--files/([^/"]+)/([^"]+)""#).unwrap());
+
+/// The contents of a page being exported.
+#[derive(Debug)]
+struct ExportPage {
+    page: PageModel,
+    revision: PageRevisionModel,
+    html: String,
+    url: String,
+    authors: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct ExportService;
+
+impl ExportService {
+    /// Requests an export of some pages, which is built in the background.
+    ///
+    /// The selection is resolved into a list of pages immediately, and each
+    /// is pinned to its current revision, so later changes to (for instance)
+    /// tags or page contents do not affect the export.
+    ///
+    /// If the user has already requested an export of exactly these revisions,
+    /// and it has not failed, it is returned instead of building another.
+    pub async fn create(
+        ctx: &ServiceContext<'_>,
+        CreateExport {
+            site_id,
+            user_id,
+            export_format,
+            selection,
+            title,
+        }: CreateExport<'_>,
+    ) -> Result<ExportOutput> {
+        info!("Creating {export_format:?} export for site ID {site_id}");
+
+        let txn = ctx.transaction();
+        let site = SiteService::get(ctx, Reference::Id(site_id)).await?;
+        let pages = Self::select_pages(ctx, site_id, selection).await?;
+
+        if pages.is_empty() {
+            error!("No pages were selected for export");
+            return Err(Error::ExportEmpty);
+        }
+
+        if pages.len() > ctx.config().maximum_export_pages {
+            error!(
+                "Too many pages were selected for export ({} > {})",
+                pages.len(),
+                ctx.config().maximum_export_pages,
+            );
+            return Err(Error::ExportTooLarge);
+        }
+
+        let mut revisions = Vec::with_capacity(pages.len());
+        for page in &pages {
+            revisions
+                .push(PageRevisionService::get_latest(ctx, site_id, page.page_id).await?);
+        }
+
+        let title = match title {
+            Some(title) if !title.is_empty() => title,
+            _ if revisions.len() == 1 => revisions[0].title.clone(),
+            _ => site.name.clone(),
+        };
+
+        let page_ids: Vec<i64> = pages.iter().map(|page| page.page_id).collect();
+        let revision_ids: Vec<i64> = revisions
+            .iter()
+            .map(|revision| revision.revision_id)
+            .collect();
+
+        // Reuse an existing export, if nothing has changed since.
+        // Only the user who requested an export may get it, so only theirs are reused.
+        let existing = Export::find()
+            .filter(
+                Condition::all()
+                    .add(export::Column::SiteId.eq(site_id))
+                    .add(export::Column::UserId.eq(user_id))
+                    .add(export::Column::ExportFormat.eq(export_format))
+                    .add(export::Column::Title.eq(title.as_str()))
+                    .add(export::Column::PageIds.eq(page_ids.clone()))
+                    .add(export::Column::RevisionIds.eq(revision_ids.clone()))
+                    .add(export::Column::Status.ne(ExportStatus::Failed)),
+            )
+            .order_by_desc(export::Column::CreatedAt)
+            .one(txn)
+            .await?;
+
+        if let Some(export) = existing {
+            info!(
+                "Reusing export ID {} with the same page revisions",
+                export.export_id,
+            );
+            return Ok(Self::to_output(ctx, &site, export));
+        }
+
+        if export_format == ExportFormat::Pdf {
+            Self::check_pdf_available(ctx, site_id).await?;
+        }
+
+        let model = export::ActiveModel {
+            site_id: Set(site_id),
+            user_id: Set(user_id),
+            export_format: Set(export_format),
+            title: Set(title),
+            page_ids: Set(page_ids),
+            revision_ids: Set(revision_ids),
+            ..Default::default()
+        };
+
+        let export = model.insert(txn).await?;
+        JobService::queue_job(
+            ctx,
+            &Job::BuildExport {
+                export_id: export.export_id,
+            },
+            None,
+        )
+        .await?;
+
+        Ok(ExportOutput {
+            export,
+            download_url: None,
+        })
+    }
+
+    pub async fn get_optional(
+        ctx: &ServiceContext<'_>,
+        export_id: i64,
+    ) -> Result<Option<ExportModel>> {
+        let txn = ctx.transaction();
+        let export = Export::find_by_id(export_id).one(txn).await?;
+        Ok(export)
+    }
+
+    #[inline]
+    pub async fn get(ctx: &ServiceContext<'_>, export_id: i64) -> Result<ExportModel> {
+        find_or_error!(Self::get_optional(ctx, export_id), Export)
+    }
+
+    /// Gets an export on behalf of a user, who must be the one who requested it.
+    ///
+    /// Export IDs are sequential, so anyone else is told that it doesn't exist,
+    /// rather than being able to find and download others' exports.
+    async fn get_owned(
+        ctx: &ServiceContext<'_>,
+        GetExport { export_id, user_id }: GetExport,
+    ) -> Result<ExportModel> {
+        match Self::get_optional(ctx, export_id).await? {
+            Some(export) if export.user_id == user_id => Ok(export),
+            Some(_) => {
+                warn!("User ID {user_id} cannot access export ID {export_id}");
+                Err(Error::ExportNotFound)
+            }
+            None => Err(Error::ExportNotFound),
+        }
+    }
+
+    /// Gets the export, along with its download link if it has been completed.
+    pub async fn get_output(
+        ctx: &ServiceContext<'_>,
+        input: GetExport,
+    ) -> Result<ExportOutput> {
+        let export = Self::get_owned(ctx, input).await?;
+        let site = SiteService::get(ctx, Reference::Id(export.site_id)).await?;
+        Ok(Self::to_output(ctx, &site, export))
+    }
+
+    fn to_output(
+        ctx: &ServiceContext<'_>,
+        site: &SiteModel,
+        export: ExportModel,
+    ) -> ExportOutput {
+        let download_url = match export.status {
+            ExportStatus::Completed => {
+                let domain = DomainService::domain_for_site(ctx.config(), site);
+                Some(format!("https://{domain}/-/export/{}", export.export_id))
+            }
+            _ => None,
+        };
+
+        ExportOutput {
+            export,
+            download_url,
+        }
+    }
+
+    /// Ensures a new PDF export can be requested for this site.
+    ///
+    /// PDFs are comparatively expensive to render, so each site has
+    /// a limited number of them it can request per day.
+    async fn check_pdf_available(ctx: &ServiceContext<'_>, site_id: i64) -> Result<()> {
+        let config = ctx.config();
+        if config.export_pdf_renderer_url.is_none() {
+            error!("PDF export requested, but no renderer is configured");
+            return Err(Error::ExportFormatUnavailable);
+        }
+
+        let txn = ctx.transaction();
+        let recent = Export::find()
+            .filter(
+                Condition::all()
+                    .add(export::Column::SiteId.eq(site_id))
+                    .add(export::Column::ExportFormat.eq(ExportFormat::Pdf))
+                    .add(export::Column::CreatedAt.gte(now() - Duration::days(1))),
+            )
+            .count(txn)
+            .await?;
+
+        if recent >= config.maximum_daily_pdf_exports {
+            error!(
+                "Site ID {site_id} has reached its daily PDF export limit ({})",
+                config.maximum_daily_pdf_exports,
+            );
+            return Err(Error::ExportQuotaExceeded);
+        }
+
+        Ok(())
+    }
+
+    /// Gets the produced document for a completed export.
+    pub async fn download(
+        ctx: &ServiceContext<'_>,
+        input: GetExport,
+    ) -> Result<DownloadExportOutput> {
+        let export = Self::get_owned(ctx, input).await?;
+        let s3_hash = match (export.status, export.s3_hash) {
+            (ExportStatus::Completed, Some(s3_hash)) => s3_hash,
+            _ => return Err(Error::ExportNotCompleted),
+        };
+
+        let mut filename = export.title;
+        normalize(&mut filename);
+
+        let (extension, mime) = match export.export_format {
+            ExportFormat::Epub => ("epub", "application/epub+zip"),
+            ExportFormat::Pdf => ("pdf", "application/pdf"),
+        };
+
+        let data = BlobService::get(ctx, &s3_hash).await?;
+        Ok(DownloadExportOutput {
+            filename: format!("{filename}.{extension}"),
+            mime,
+            data: Bytes::from(data),
+        })
+    }
+
+    /// Builds the document for an export. Called by the job worker.
+    ///
+    /// If the document cannot be built, the export is marked as failed
+    /// with a description of the problem, rather than retrying the job.
+    pub async fn build(ctx: &ServiceContext<'_>, export_id: i64) -> Result<()> {
+        info!("Building export ID {export_id}");
+
+        let txn = ctx.transaction();
+        let export = Self::get(ctx, export_id).await?;
+        if export.status != ExportStatus::Queued {
+            warn!("Export ID {export_id} has already been built, skipping");
+            return Ok(());
+        }
+
+        let result = match export.export_format {
+            ExportFormat::Epub => Self::build_epub(ctx, &export).await,
+            ExportFormat::Pdf => Self::build_pdf(ctx, &export).await,
+        };
+
+        let mut model = export::ActiveModel {
+            export_id: Set(export_id),
+            completed_at: Set(Some(now())),
+            ..Default::default()
+        };
+
+        match result {
+            Ok(data) => {
+                let size = i64::try_from(data.len()).expect("Buffer size exceeds i64");
+                let output = BlobService::create(ctx, &data).await?;
+                model.status = Set(ExportStatus::Completed);
+                model.s3_hash = Set(Some(output.hash.to_vec()));
+                model.size = Set(Some(size));
+            }
+            Err(error) => {
+                error!("Unable to build export ID {export_id}: {error}");
+                model.status = Set(ExportStatus::Failed);
+                model.error = Set(Some(error.to_string()));
+            }
+        }
+
+        model.update(txn).await?;
+        Ok(())
+    }
+
+    async fn build_epub(
+        ctx: &ServiceContext<'_>,
+        export: &ExportModel,
+    ) -> Result<Vec<u8>> {
+        let site = SiteService::get(ctx, Reference::Id(export.site_id)).await?;
+        let locales = [site.locale.parse::<LanguageIdentifier>()?];
+        let pages = Self::get_export_pages(ctx, &site, export).await?;
+
+        let mut chapters = Vec::with_capacity(pages.len() + 1);
+        let mut images = Vec::new();
+
+        for ExportPage {
+            page,
+            revision,
+            html,
+            ..
+        } in &pages
+        {
+            let image_paths = Self::collect_images(ctx, &site, page, &mut images).await?;
+
+            // Point images at the copies bundled in the EPUB
+            let html = LOCAL_FILE_REGEX.replace_all(html, |captures: &Captures| {
+                let key = (captures[1].to_string(), captures[2].to_string());
+                match image_paths.get(&key) {
+                    Some(path) => format!(r#"src="../{path}""#),
+                    None => captures[0].to_string(),
+                }
+            });
+
+            chapters.push(EpubChapter {
+                title: revision.title.clone(),
+                body: to_xhtml(&html),
+            });
+        }
+
+        let (title, body) = Self::back_matter(ctx, &locales, &pages)?;
+        chapters.push(EpubChapter { title, body });
+
+        let args = FluentArgs::new();
+        let contents_title =
+            ctx.localization()
+                .translate(&locales, "export-contents", &args)?;
+
+        let identifier = format!(
+            "https://{}/-/export/{}",
+            DomainService::domain_for_site(ctx.config(), &site),
+            export.export_id,
+        );
+
+        let book = EpubBook {
+            identifier: &identifier,
+            title: &export.title,
+            language: &site.locale,
+            modified: now(),
+            contents_title: &contents_title,
+            chapters,
+            images,
+        };
+
+        let data = build_epub(&book)?;
+        Ok(data)
+    }
+
+    async fn build_pdf(
+        ctx: &ServiceContext<'_>,
+        export: &ExportModel,
+    ) -> Result<Vec<u8>> {
+        let config = ctx.config();
+        let renderer_url = match config.export_pdf_renderer_url {
+            Some(ref url) => url,
+            None => {
+                return Err(Error::ExportBuild(str!(
+                    "No PDF renderer has been configured",
+                )));
+            }
+        };
+
+        let site = SiteService::get(ctx, Reference::Id(export.site_id)).await?;
+        let locales = [site.locale.parse::<LanguageIdentifier>()?];
+        let pages = Self::get_export_pages(ctx, &site, export).await?;
+        let base_url = format!(
+            "https://{}/",
+            DomainService::domain_for_site(ctx.config(), &site),
+        );
+
+        // Rendered again in print mode, so that the renderer is sent a
+        // self-contained document, with attached images embedded in it.
+        let mut bodies = Vec::with_capacity(pages.len());
+        for page in &pages {
+            let output = PageRevisionService::render_print(ctx, &page.revision).await?;
+            bodies.push(output.html_output.body);
+        }
+
+        let mut sections: Vec<(&str, &str)> = pages
+            .iter()
+            .zip(&bodies)
+            .map(|(page, body)| (page.revision.title.as_str(), body.as_str()))
+            .collect();
+
+        let (back_matter_title, back_matter_body) =
+            Self::back_matter(ctx, &locales, &pages)?;
+        sections.push((back_matter_title.as_str(), back_matter_body.as_str()));
+
+        let timeout = config.export_pdf_renderer_timeout;
+        let origins = &config.export_pdf_allowed_origins;
+        let document = pdf::build_document(
+            &site.locale,
+            &export.title,
+            &base_url,
+            origins,
+            &sections,
+        );
+        let message = match pdf::render_pdf(renderer_url, timeout, document).await {
+            Ok(data) => return Ok(data),
+            Err(message) => message,
+        };
+
+        // Find which page could not be converted, by rendering each on its own
+        warn!(
+            "Unable to render PDF for export ID {}: {message}",
+            export.export_id
+        );
+        for (page, &(title, html)) in pages.iter().zip(&sections) {
+            let document = pdf::build_document(
+                &site.locale,
+                title,
+                &base_url,
+                origins,
+                &[(title, html)],
+            );
+            if let Err(message) = pdf::render_pdf(renderer_url, timeout, document).await {
+                let mut diagnostic = format!(
+                    "Unable to convert page '{}' (revision {}): {message}",
+                    page.page.slug, page.revision.revision_number,
+                );
+
+                let elements = pdf::find_unconvertible(html);
+                if !elements.is_empty() {
+                    diagnostic.push_str(". Elements which may not be convertible: ");
+                    diagnostic.push_str(&elements.join(", "));
+                }
+
+                return Err(Error::ExportBuild(diagnostic));
+            }
+        }
+
+        // Each page works individually, so the problem is with the whole document
+        Err(Error::ExportBuild(format!(
+            "Unable to convert document: {message}",
+        )))
+    }
+
+    /// Gets the contents of each page in an export, at the pinned revision.
+    ///
+    /// Pages which have been deleted since the export was requested are skipped.
+    async fn get_export_pages(
+        ctx: &ServiceContext<'_>,
+        site: &SiteModel,
+        export: &ExportModel,
+    ) -> Result<Vec<ExportPage>> {
+        let mut pages = Vec::with_capacity(export.page_ids.len());
+
+        for (&page_id, &revision_id) in export.page_ids.iter().zip(&export.revision_ids) {
+            let page = match PageService::get_direct_optional(ctx, page_id, false).await?
+            {
+                Some(page) => page,
+                None => {
+                    warn!("Page ID {page_id} was deleted since export was requested");
+                    continue;
+                }
+            };
+
+            let revision = PageRevisionService::get_direct(ctx, revision_id).await?;
+            let html = TextService::get(ctx, &revision.compiled_hash).await?;
+            let authors = Self::get_authors(ctx, site, &page).await?;
+            let url = format!(
+                "https://{}/{}",
+                DomainService::domain_for_site(ctx.config(), site),
+                page.slug,
+            );
+
+            pages.push(ExportPage {
+                page,
+                revision,
+                html,
+                url,
+                authors,
+            });
+        }
+
+        Ok(pages)
+    }
+
+    /// Produces the licensing and attribution section at the end of an export.
+    ///
+    /// Returns the section's title and its body, which is valid XHTML.
+    fn back_matter(
+        ctx: &ServiceContext<'_>,
+        locales: &[LanguageIdentifier],
+        pages: &[ExportPage],
+    ) -> Result<(String, String)> {
+        let mut body = str!("<ul>");
+
+        for ExportPage {
+            revision,
+            url,
+            authors,
+            ..
+        } in pages
+        {
+            let authors = authors.join(", ");
+            let mut args = FluentArgs::new();
+            args.set("authors", fluent_str!(authors));
+            let by_line = ctx.localization().translate(
+                locales,
+                "export-attribution-authors",
+                &args,
+            )?;
+
+            body.push_str(&format!(
+                r#"<li><p><a href="{url}">{title}</a></p><p>{by_line}</p></li>"#,
+                url = escape(url),
+                title = escape(&revision.title),
+                by_line = escape(&by_line),
+            ));
+        }
+
+        let mut args = FluentArgs::new();
+        args.set("license", fluent_str!(ctx.config().export_license));
+        let license = ctx
+            .localization()
+            .translate(locales, "export-license", &args)?;
+
+        body.push_str("</ul><p>");
+        body.push_str(&escape(&license));
+        body.push_str("</p>");
+
+        let args = FluentArgs::new();
+        let title = ctx
+            .localization()
+            .translate(locales, "export-attribution", &args)?;
+
+        Ok((title.to_string(), body))
+    }
+
+    /// Adds all images attached to a page to the EPUB.
+    ///
+    /// The revision of each file shown to readers is used, and any whose
+    /// contents are hidden are left out.
+    ///
+    /// Returns a mapping of page slug and file name to the image's path.
+    async fn collect_images(
+        ctx: &ServiceContext<'_>,
+        site: &SiteModel,
+        page: &PageModel,
+        images: &mut Vec<EpubImage>,
+    ) -> Result<HashMap<(String, String), String>> {
+        let txn = ctx.transaction();
+        let files = File::find()
+            .filter(
+                Condition::all()
+                    .add(file::Column::PageId.eq(page.page_id))
+                    .add(file::Column::DeletedAt.is_null()),
+            )
+            .order_by_asc(file::Column::FileId)
+            .all(txn)
+            .await?;
+
+        let mut paths = HashMap::new();
+        for file in files {
+            let revision =
+                StableRevisionService::get_file_revision(ctx, site, &file).await?;
+            if revision.hidden.iter().any(|field| field == "blob") {
+                debug!(
+                    "Contents of file ID {} are hidden, leaving it out",
+                    file.file_id,
+                );
+                continue;
+            }
+
+            // Only the image types EPUB readers are required to support
+            let mime = revision.mime_hint.split(';').next().unwrap_or("").trim();
+            let extension = match mime {
+                "image/gif" => "gif",
+                "image/jpeg" => "jpg",
+                "image/png" => "png",
+                "image/svg+xml" => "svg",
+                "image/webp" => "webp",
+                _ => continue,
+            };
+
+            let path = format!("images/{}.{extension}", file.file_id);
+            let data = BlobService::get(ctx, &revision.s3_hash).await?;
+
+            // Some readers run scripts in SVGs, and files uploaded
+            // before sanitization was added may still have them.
+            let data = if mime == "image/svg+xml" {
+                match sanitize_stored_svg(&data) {
+                    Ok(svg) => svg.into_owned(),
+                    Err(error) => {
+                        warn!(
+                            "Unable to sanitize SVG file ID {}, leaving it out: {error}",
+                            file.file_id
+                        );
+                        continue;
+                    }
+                }
+            } else {
+                data
+            };
+
+            images.push(EpubImage {
+                path: path.clone(),
+                mime: str!(mime),
+                data,
+            });
+            paths.insert((page.slug.clone(), file.name), path);
+        }
+
+        Ok(paths)
+    }
+
+    /// Gets the names of the users credited for a page.
+    ///
+    /// If no attribution has been set, this is the page's creator.
+    async fn get_authors(
+        ctx: &ServiceContext<'_>,
+        site: &SiteModel,
+        page: &PageModel,
+    ) -> Result<Vec<String>> {
+        let txn = ctx.transaction();
+        let mut user_ids: Vec<i64> = PageAttribution::find()
+            .select_only()
+            .column(page_attribution::Column::UserId)
+            .distinct()
+            .filter(page_attribution::Column::PageId.eq(page.page_id))
+            .into_tuple()
+            .all(txn)
+            .await?;
+
+        if user_ids.is_empty() {
+            let revision =
+                PageRevisionService::get(ctx, site.site_id, page.page_id, 0).await?;
+
+            user_ids.push(revision.user_id);
+        }
+
+        let mut names = Vec::with_capacity(user_ids.len());
+        for user_id in user_ids {
+            let user = UserService::get(ctx, Reference::Id(user_id)).await?;
+            names.push(user.name);
+        }
+
+        Ok(names)
+    }
+
+    /// Resolves which pages are in an export, in order.
+    async fn select_pages(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        selection: ExportSelection<'_>,
+    ) -> Result<Vec<PageModel>> {
+        match selection {
+            ExportSelection::Pages(references) => {
+                let mut pages = Vec::with_capacity(references.len());
+                for reference in references {
+                    pages.push(PageService::get(ctx, site_id, reference).await?);
+                }
+
+                Ok(pages)
+            }
+            ExportSelection::Category(reference) => {
+                PageService::get_all(
+                    ctx,
+                    site_id,
+                    Some(reference),
+                    Some(false),
+                    PageOrder::default(),
+                )
+                .await
+            }
+            ExportSelection::Tag(tag) => {
+                // Any page which has ever had this tag
+                let txn = ctx.transaction();
+                let mut page_ids: Vec<i64> = PageRevision::find()
+                    .select_only()
+                    .column(page_revision::Column::PageId)
+                    .distinct()
+                    .filter(
+                        Condition::all()
+                            .add(page_revision::Column::SiteId.eq(site_id))
+                            .add(Expr::cust_with_values("? = ANY(tags)", [tag.as_str()])),
+                    )
+                    .into_tuple()
+                    .all(txn)
+                    .await?;
+
+                page_ids.sort_unstable();
+
+                // Filter to extant pages which currently have this tag
+                let mut pages = Vec::new();
+                for page_id in page_ids {
+                    let page = match PageService::get_direct_optional(ctx, page_id, false)
+                        .await?
+                    {
+                        Some(page) => page,
+                        None => continue,
+                    };
+
+                    let revision =
+                        PageRevisionService::get_latest(ctx, site_id, page_id).await?;
+
+                    if revision.tags.contains(&tag) {
+                        pages.push(page);
+                    }
+                }
+
+                Ok(pages)
+            }
+        }
+    }
+}
@@ -0,0 +1,78 @@
+/*
+ * services/export/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::models::export::Model as ExportModel;
+use crate::models::sea_orm_active_enums::ExportFormat;
+use crate::web::{Bytes, Reference};
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CreateExport<'a> {
+    pub site_id: i64,
+    pub user_id: i64,
+    pub export_format: ExportFormat,
+    pub selection: ExportSelection<'a>,
+
+    /// The title of the document.
+    ///
+    /// If not set, this is the page's title when exporting a single page,
+    /// otherwise the site's name.
+    #[serde(default)]
+    pub title: Option<String>,
+}
+
+/// Which pages to include in an export.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case", tag = "type", content = "value")]
+pub enum ExportSelection<'a> {
+    /// These pages, in this order.
+    ///
+    /// This can be a single page, or a series of pages meant to be read together.
+    Pages(Vec<Reference<'a>>),
+
+    /// All pages in this category, in order of creation.
+    Category(Reference<'a>),
+
+    /// All pages with this tag, in order of creation.
+    Tag(String),
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetExport {
+    pub export_id: i64,
+
+    /// The user requesting the export, who must be the one who created it.
+    pub user_id: i64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ExportOutput {
+    #[serde(flatten)]
+    pub export: ExportModel,
+
+    /// Where the produced document can be downloaded, once completed.
+    pub download_url: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct DownloadExportOutput {
+    pub filename: String,
+    pub mime: &'static str,
+    pub data: Bytes<'static>,
+}
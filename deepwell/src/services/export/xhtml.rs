@@ -0,0 +1,115 @@
+/*
+ * services/export/xhtml.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Conversion of rendered HTML into XHTML, as required by EPUB.
+//!
+//! ftml emits HTML5, which permits void elements without a closing slash
+//! and attributes without values, neither of which are valid XML. This
+//! rewrites each opening tag so that void elements are self-closing and
+//! every attribute has a quoted value.
+//!
+//! This is not a general-purpose HTML parser. It relies on ftml escaping
+//! text contents, so that anything resembling a tag is actually one.
+
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+
+static TAG_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"<([a-zA-Z][a-zA-Z0-9-]*)((?:\s+[^\s"'<>/=]+(?:\s*=\s*(?:"[^"]*"|'[^']*'|[^\s"'=<>`]+))?)*)\s*/?>"#,
+    )
+    .unwrap()
+});
+
+static ATTRIBUTE_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"([^\s"'<>/=]+)(?:\s*=\s*("[^"]*"|'[^']*'|[^\s"'=<>`]+))?"#).unwrap()
+});
+
+const VOID_ELEMENTS: [&str; 13] = [
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source",
+    "track", "wbr",
+];
+
+pub fn to_xhtml(html: &str) -> String {
+    let output = TAG_REGEX.replace_all(html, |captures: &Captures| {
+        let tag = &captures[1];
+        let mut output = format!("<{tag}");
+
+        for attribute in ATTRIBUTE_REGEX.captures_iter(&captures[2]) {
+            let key = &attribute[1];
+            output.push(' ');
+            output.push_str(key);
+            output.push('=');
+
+            match attribute.get(2).map(|value| value.as_str()) {
+                // Valueless attribute, such as "checked"
+                None => {
+                    output.push('"');
+                    output.push_str(key);
+                    output.push('"');
+                }
+
+                // Already quoted
+                Some(value) if value.starts_with(['"', '\'']) => output.push_str(value),
+
+                // Unquoted
+                Some(value) => {
+                    output.push('"');
+                    output.push_str(value);
+                    output.push('"');
+                }
+            }
+        }
+
+        if VOID_ELEMENTS.contains(&tag.to_ascii_lowercase().as_str()) {
+            output.push_str(" />");
+        } else {
+            output.push('>');
+        }
+
+        output
+    });
+
+    output.into_owned()
+}
+
+#[test]
+fn xhtml() {
+    macro_rules! check {
+        ($input:expr, $expected:expr $(,)?) => {
+            assert_eq!(to_xhtml($input), $expected, "Unexpected XHTML output");
+        };
+    }
+
+    check!("", "");
+    check!("<p>Text</p>", "<p>Text</p>");
+    check!("a<br>b", "a<br />b");
+    check!("<hr/>", "<hr />");
+    check!(
+        r#"<img class="wj-image" src="a.png" crossorigin>"#,
+        r#"<img class="wj-image" src="a.png" crossorigin="crossorigin" />"#,
+    );
+    check!(
+        r#"<details class="wj-collapsible" open data-show-top>"#,
+        r#"<details class="wj-collapsible" open="open" data-show-top="data-show-top">"#,
+    );
+    check!("<td colspan=2>", r#"<td colspan="2">"#);
+    check!("&lt;br&gt;", "&lt;br&gt;");
+}
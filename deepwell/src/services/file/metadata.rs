@@ -0,0 +1,199 @@
+/*
+ * services/file/metadata.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Removal of identifying metadata from uploaded images.
+//!
+//! Photos often carry EXIF data, such as where they were taken and on which
+//! camera, which users may not realize they are publishing. This works on the
+//! container format directly, dropping metadata segments while leaving the
+//! image data untouched, so there is no loss of quality from re-encoding.
+//!
+//! Color profiles are kept, since they affect how the image is displayed.
+//! The EXIF orientation is not, so photos relying on it may appear rotated.
+
+use std::borrow::Cow;
+
+const JPEG_SIGNATURE: &[u8] = b"\xff\xd8";
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// PNG chunks which hold metadata rather than image data.
+const PNG_METADATA_CHUNKS: [&[u8; 4]; 5] = [b"eXIf", b"tEXt", b"zTXt", b"iTXt", b"tIME"];
+
+/// Strips metadata from a JPEG or PNG image.
+///
+/// Returns `None` if the data is not one of these formats, or is malformed,
+/// in which case it should be stored unchanged. If the image has no metadata,
+/// the original data is returned.
+pub fn strip_image_metadata(data: &[u8]) -> Option<Cow<'_, [u8]>> {
+    let output = if data.starts_with(JPEG_SIGNATURE) {
+        strip_jpeg(data)?
+    } else if data.starts_with(PNG_SIGNATURE) {
+        strip_png(data)?
+    } else {
+        return None;
+    };
+
+    if output.len() == data.len() {
+        Some(Cow::Borrowed(data))
+    } else {
+        Some(Cow::Owned(output))
+    }
+}
+
+fn strip_jpeg(data: &[u8]) -> Option<Vec<u8>> {
+    let mut output = Vec::with_capacity(data.len());
+    output.extend_from_slice(JPEG_SIGNATURE);
+
+    let mut offset = JPEG_SIGNATURE.len();
+    loop {
+        let marker = match data.get(offset..offset + 2)? {
+            &[0xff, marker] => marker,
+            _ => return None,
+        };
+
+        // Markers without a length field
+        if marker == 0x01 || (0xd0..=0xd8).contains(&marker) {
+            output.extend_from_slice(&[0xff, marker]);
+            offset += 2;
+            continue;
+        }
+
+        // Start of scan, everything from here on is image data
+        if marker == 0xda {
+            output.extend_from_slice(&data[offset..]);
+            return Some(output);
+        }
+
+        // The length includes its own two bytes, but not the marker
+        let length = data.get(offset + 2..offset + 4)?.try_into().ok()?;
+        let length = usize::from(u16::from_be_bytes(length));
+
+        if length < 2 {
+            return None;
+        }
+
+        let end = offset + 2 + length;
+        let segment = data.get(offset..end)?;
+        if keep_jpeg_segment(marker, &segment[4..]) {
+            output.extend_from_slice(segment);
+        }
+
+        offset = end;
+    }
+}
+
+fn keep_jpeg_segment(marker: u8, contents: &[u8]) -> bool {
+    match marker {
+        // JFIF header and Adobe color transform
+        0xe0 | 0xee => true,
+
+        // Also used for other data, only keep color profiles
+        0xe2 => contents.starts_with(b"ICC_PROFILE\0"),
+
+        // All other application segments (EXIF, XMP, IPTC, maker notes, etc.)
+        0xe1..=0xef => false,
+
+        // Comments
+        0xfe => false,
+
+        // Tables and frame headers needed to decode the image
+        _ => true,
+    }
+}
+
+fn strip_png(data: &[u8]) -> Option<Vec<u8>> {
+    let mut output = Vec::with_capacity(data.len());
+    output.extend_from_slice(PNG_SIGNATURE);
+
+    let mut offset = PNG_SIGNATURE.len();
+    loop {
+        let length = data.get(offset..offset + 4)?.try_into().ok()?;
+        let length = u32::from_be_bytes(length);
+
+        // Length, type, data, and CRC
+        let end = offset
+            .checked_add(12)?
+            .checked_add(usize::try_from(length).ok()?)?;
+        let chunk = data.get(offset..end)?;
+        let kind = &chunk[4..8];
+
+        if !PNG_METADATA_CHUNKS.iter().any(|name| kind == &name[..]) {
+            output.extend_from_slice(chunk);
+        }
+
+        if kind == b"IEND" {
+            return Some(output);
+        }
+
+        offset = end;
+    }
+}
+
+#[test]
+fn strip_jpeg_metadata() {
+    const JPEG: &[u8] = b"\xff\xd8\
+        \xff\xe0\x00\x06JFIF\
+        \xff\xe1\x00\x0cExif\0\0GPS!\
+        \xff\xe2\x00\x0eICC_PROFILE\0\
+        \xff\xe2\x00\x06MPF\0\
+        \xff\xfe\x00\x08camera\
+        \xff\xdb\x00\x04\x01\x02\
+        \xff\xda\x00\x04\x00\x00\x12\x34\xff\xd9";
+
+    const STRIPPED: &[u8] = b"\xff\xd8\
+        \xff\xe0\x00\x06JFIF\
+        \xff\xe2\x00\x0eICC_PROFILE\0\
+        \xff\xdb\x00\x04\x01\x02\
+        \xff\xda\x00\x04\x00\x00\x12\x34\xff\xd9";
+
+    let output = strip_image_metadata(JPEG).expect("Unable to strip JPEG");
+    assert_eq!(output.as_ref(), STRIPPED);
+    assert!(matches!(output, Cow::Owned(_)));
+
+    let output = strip_image_metadata(STRIPPED).expect("Unable to strip JPEG");
+    assert!(matches!(output, Cow::Borrowed(_)));
+
+    // Segment runs past the end of the data
+    assert!(strip_image_metadata(b"\xff\xd8\xff\xe1\x00\x10Exif").is_none());
+}
+
+#[test]
+fn strip_png_metadata() {
+    const PNG: &[u8] = b"\x89PNG\r\n\x1a\n\
+        \x00\x00\x00\x02IHDRab1234\
+        \x00\x00\x00\x04tEXtnote1234\
+        \x00\x00\x00\x03eXIfgps1234\
+        \x00\x00\x00\x01IDATz1234\
+        \x00\x00\x00\x00IEND1234";
+
+    const STRIPPED: &[u8] = b"\x89PNG\r\n\x1a\n\
+        \x00\x00\x00\x02IHDRab1234\
+        \x00\x00\x00\x01IDATz1234\
+        \x00\x00\x00\x00IEND1234";
+
+    let output = strip_image_metadata(PNG).expect("Unable to strip PNG");
+    assert_eq!(output.as_ref(), STRIPPED);
+
+    // Missing IEND chunk
+    assert!(strip_image_metadata(&PNG[..PNG.len() - 12]).is_none());
+
+    // Not an image
+    assert!(strip_image_metadata(b"plain text").is_none());
+}
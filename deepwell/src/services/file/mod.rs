@@ -23,8 +23,12 @@ mod prelude {
     pub use super::structs::*;
 }
 
+mod metadata;
+mod paste;
 mod service;
 mod structs;
+mod svg;
 
 pub use self::service::FileService;
 pub use self::structs::*;
+pub use self::svg::{is_svg_mime, sanitize_stored_svg};
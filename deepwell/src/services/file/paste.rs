@@ -0,0 +1,126 @@
+/*
+ * services/file/paste.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Naming of images pasted into the editor.
+//!
+//! Pasted images, such as screenshots, don't have a meaningful name, so one
+//! is generated from the date, like `pasted-2024-05-01-001.png`, with the
+//! extension taken from the detected MIME type.
+
+use time::Date;
+
+/// Gets the file extension for a pasted image of this MIME type.
+///
+/// Returns `None` if it isn't an image type which can be pasted.
+pub fn paste_extension(mime: &str) -> Option<&'static str> {
+    let extension = match mime.split(';').next().unwrap_or_default().trim() {
+        "image/png" => "png",
+        "image/jpeg" => "jpg",
+        "image/gif" => "gif",
+        "image/webp" => "webp",
+        "image/avif" => "avif",
+        "image/bmp" => "bmp",
+        _ => return None,
+    };
+
+    Some(extension)
+}
+
+/// The prefix shared by the names of all images pasted on this date.
+#[inline]
+pub fn paste_prefix(date: Date) -> String {
+    format!("pasted-{date}-")
+}
+
+/// Generates the name for the next image pasted on this date.
+///
+/// Pasted images are numbered in order for each day, continuing after
+/// the highest number among `existing`, regardless of extension.
+pub fn next_paste_name<S: AsRef<str>>(
+    date: Date,
+    existing: &[S],
+    extension: &str,
+) -> String {
+    let prefix = paste_prefix(date);
+    let number = existing
+        .iter()
+        .filter_map(|name| {
+            let rest = name.as_ref().strip_prefix(&prefix)?;
+            let (number, _) = rest.split_once('.')?;
+            number.parse::<u32>().ok()
+        })
+        .max()
+        .unwrap_or(0)
+        + 1;
+
+    format!("{prefix}{number:03}.{extension}")
+}
+
+#[test]
+fn extension() {
+    assert_eq!(paste_extension("image/png"), Some("png"));
+    assert_eq!(paste_extension("image/jpeg"), Some("jpg"));
+    assert_eq!(paste_extension("image/webp; charset=binary"), Some("webp"));
+    assert_eq!(paste_extension("image/svg+xml"), None);
+    assert_eq!(paste_extension("text/plain"), None);
+    assert_eq!(paste_extension(""), None);
+}
+
+#[test]
+fn name() {
+    use time::macros::date;
+
+    const DATE: Date = date!(2024 - 05 - 01);
+
+    macro_rules! check {
+        ($existing:expr, $extension:expr, $expected:expr $(,)?) => {
+            assert_eq!(
+                next_paste_name(DATE, &$existing, $extension),
+                $expected,
+                "Actual pasted image name doesn't match expected",
+            );
+        };
+    }
+
+    check!([] as [&str; 0], "png", "pasted-2024-05-01-001.png");
+    check!(
+        ["pasted-2024-05-01-001.png", "pasted-2024-05-01-002.jpg"],
+        "png",
+        "pasted-2024-05-01-003.png",
+    );
+
+    // Numbering continues after the highest, even with gaps
+    check!(
+        ["pasted-2024-05-01-007.gif", "pasted-2024-05-01-002.png"],
+        "webp",
+        "pasted-2024-05-01-008.webp",
+    );
+
+    // Names which don't follow the pattern are ignored
+    check!(
+        [
+            "pasted-2024-05-01-notes.png",
+            "pasted-2024-04-30-009.png",
+            "other.png"
+        ],
+        "png",
+        "pasted-2024-05-01-001.png",
+    );
+}
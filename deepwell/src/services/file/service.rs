@@ -18,15 +18,31 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
+use super::metadata::strip_image_metadata;
+use super::paste::{next_paste_name, paste_extension, paste_prefix};
 use super::prelude::*;
+use super::svg::{is_svg_mime, sanitize_stored_svg, sanitize_svg, SanitizedSvg};
+use crate::hash::sha512_hash;
 use crate::models::file::{self, Entity as File, Model as FileModel};
+use crate::models::page::Entity as Page;
+use crate::services::audit::CreateAuditEntry;
 use crate::services::blob::CreateBlobOutput;
 use crate::services::file_revision::{
     CreateFileRevision, CreateFileRevisionBody, CreateFirstFileRevision,
     CreateResurrectionFileRevision, CreateTombstoneFileRevision, FileBlob,
+    GetFileRevision,
 };
+use crate::services::file_scan::ScanFile;
+use crate::services::file_staging::StagedFileReference;
 use crate::services::filter::{FilterClass, FilterType};
-use crate::services::{BlobService, FileRevisionService, FilterService};
+use crate::services::upload_rule::UploadRuleTarget;
+use crate::services::{
+    AuditService, BlobService, CdnService, FileRevisionService, FileScanService,
+    FileStagingService, FilterService, LimitService, PageService, PermissionService,
+    SiteService, StableRevisionService, StorageQuotaService, ThumbnailService,
+    UploadRuleService,
+};
+use std::borrow::Cow;
 
 #[derive(Debug)]
 pub struct FileService;
@@ -57,6 +73,14 @@ impl FileService {
             data.len(),
         );
 
+        // Check size limits
+        let limits = LimitService::get(ctx, site_id).await?;
+        limits.check_upload(data.as_ref())?;
+        limits.check_comment(&revision_comments)?;
+
+        // Check licensing now, so a bad request doesn't leave an orphaned blob
+        FileRevisionService::validate_licensing(&licensing)?;
+
         // Ensure row consistency
         Self::check_conflicts(ctx, page_id, &name, "create").await?;
 
@@ -65,7 +89,22 @@ impl FileService {
             Self::run_filter(ctx, site_id, Some(&name)).await?;
         }
 
+        // Scan for malware before anything else reads the contents
+        FileScanService::check(
+            ctx,
+            ScanFile {
+                site_id,
+                user_id,
+                name: &name,
+                data: data.as_ref(),
+            },
+        )
+        .await?;
+
         // Upload to S3, get derived metadata
+        let (data, original_s3_hash) =
+            Self::sanitize_upload(ctx, site_id, user_id, &name, data.as_ref()).await?;
+        StorageQuotaService::check(ctx, site_id, data.len() as u64).await?;
         let CreateBlobOutput {
             hash,
             mime,
@@ -73,6 +112,18 @@ impl FileService {
             created: _,
         } = BlobService::create(ctx, &data).await?;
 
+        // Check the site's upload rules, now that the file type is known
+        UploadRuleService::check(
+            ctx,
+            UploadRuleTarget {
+                site_id,
+                user_id,
+                mime: &mime,
+                size: size as u64,
+            },
+        )
+        .await?;
+
         // Add new file
         let model = file::ActiveModel {
             name: Set(name.clone()),
@@ -92,6 +143,7 @@ impl FileService {
                 user_id,
                 name,
                 s3_hash: hash,
+                original_s3_hash,
                 size_hint: size,
                 mime_hint: mime,
                 licensing,
@@ -103,6 +155,170 @@ impl FileService {
         Ok(revision_output)
     }
 
+    /// Uploads an image pasted into the editor, naming it automatically.
+    ///
+    /// Pasted images, such as screenshots, don't have a meaningful name, so
+    /// one is generated from the date, like `pasted-2024-05-01-001.png`.
+    /// Otherwise this is a regular upload. The wikitext to show the image
+    /// is returned, for the editor to insert.
+    pub async fn paste(
+        ctx: &ServiceContext<'_>,
+        PasteFile {
+            site_id,
+            page_id,
+            user_id,
+            data,
+            revision_comments,
+            licensing,
+        }: PasteFile,
+    ) -> Result<PasteFileOutput> {
+        info!(
+            "Pasting image into page ID {} in site ID {}, content length {}",
+            page_id,
+            site_id,
+            data.len(),
+        );
+
+        // The extension comes from the contents, since there's no name to take it from
+        let mime = ctx.mime().get_mime_type(data.as_ref().to_vec()).await?;
+        let extension = match paste_extension(&mime) {
+            Some(extension) => extension,
+            None => {
+                error!("Pasted file has MIME type {mime}, not an image");
+                return Err(Error::FilePasteNotImage);
+            }
+        };
+
+        let name = Self::next_paste_name(ctx, page_id, extension).await?;
+        let UploadFileOutput {
+            file_id,
+            file_revision_id,
+        } = Self::upload(
+            ctx,
+            UploadFile {
+                site_id,
+                page_id,
+                name: name.clone(),
+                revision_comments,
+                user_id,
+                data,
+                licensing,
+                bypass_filter: false,
+            },
+        )
+        .await?;
+
+        Ok(PasteFileOutput {
+            file_id,
+            file_revision_id,
+            wikitext: format!("[[image {name}]]"),
+            name,
+        })
+    }
+
+    /// Attaches a file from the user's staging area to a page.
+    ///
+    /// The staged contents were already checked and stored when they were
+    /// staged, so this only creates the file and its first revision. Checks
+    /// which depend on the page or site's current state are made again here.
+    pub async fn attach(
+        ctx: &ServiceContext<'_>,
+        AttachFile {
+            site_id,
+            page_id,
+            user_id,
+            staged_file_id,
+            name,
+            revision_comments,
+            licensing,
+            bypass_filter,
+        }: AttachFile,
+    ) -> Result<AttachFileOutput> {
+        let txn = ctx.transaction();
+
+        info!("Attaching staged file ID {staged_file_id} to page ID {page_id}");
+
+        let staged = FileStagingService::take(
+            ctx,
+            StagedFileReference {
+                site_id,
+                user_id,
+                staged_file_id,
+            },
+        )
+        .await?;
+
+        // Perform filter validation, if the name is new
+        let name = match name {
+            Some(name) => {
+                if !bypass_filter {
+                    Self::run_filter(ctx, site_id, Some(&name)).await?;
+                }
+
+                name
+            }
+            None => staged.name,
+        };
+
+        // Check size limits
+        LimitService::get(ctx, site_id)
+            .await?
+            .check_comment(&revision_comments)?;
+
+        // Ensure the page exists in this site, and row consistency
+        PageService::get(ctx, site_id, Reference::Id(page_id)).await?;
+        Self::check_conflicts(ctx, page_id, &name, "attach").await?;
+
+        // Rules and quotas may have changed since the file was staged
+        StorageQuotaService::check(ctx, site_id, staged.size_hint as u64).await?;
+        UploadRuleService::check(
+            ctx,
+            UploadRuleTarget {
+                site_id,
+                user_id,
+                mime: &staged.mime_hint,
+                size: staged.size_hint as u64,
+            },
+        )
+        .await?;
+
+        // Add new file
+        let model = file::ActiveModel {
+            name: Set(name.clone()),
+            site_id: Set(site_id),
+            page_id: Set(page_id),
+            ..Default::default()
+        };
+        let file = model.insert(txn).await?;
+
+        // Add new file revision
+        let s3_hash = staged
+            .s3_hash
+            .as_slice()
+            .try_into()
+            .expect("Stored blob hash is the wrong length");
+
+        let revision_output = FileRevisionService::create_first(
+            ctx,
+            CreateFirstFileRevision {
+                site_id,
+                page_id,
+                file_id: file.file_id,
+                user_id,
+                name,
+                s3_hash,
+                original_s3_hash: staged.original_s3_hash,
+                size_hint: staged.size_hint,
+                mime_hint: staged.mime_hint,
+                licensing,
+                comments: revision_comments,
+            },
+        )
+        .await?;
+
+        Ok(revision_output)
+    }
+
     /// Edits a file, including the ability to upload a new version.
     pub async fn edit(
         ctx: &ServiceContext<'_>,
@@ -126,15 +342,15 @@ impl FileService {
             name,
             data,
             licensing,
+            overwrite,
         } = body;
 
         // Verify name change
         //
         // If the name isn't changing, then we already verified this
-        // when the file was originally created.
+        // when the file was originally created. Conflicts with other
+        // files are checked when the revision is created.
         if let ProvidedValue::Set(ref name) = name {
-            Self::check_conflicts(ctx, page_id, name, "update").await?;
-
             if !bypass_filter {
                 Self::run_filter(ctx, site_id, Some(name)).await?;
             }
@@ -144,6 +360,36 @@ impl FileService {
         let blob = match data {
             ProvidedValue::Unset => ProvidedValue::Unset,
             ProvidedValue::Set(bytes) => {
+                LimitService::get(ctx, site_id)
+                    .await?
+                    .check_upload(bytes.as_ref())?;
+
+                let file_name = match name {
+                    ProvidedValue::Set(ref name) => name,
+                    ProvidedValue::Unset => &last_revision.name,
+                };
+
+                FileScanService::check(
+                    ctx,
+                    ScanFile {
+                        site_id,
+                        user_id,
+                        name: file_name,
+                        data: bytes.as_ref(),
+                    },
+                )
+                .await?;
+
+                let (bytes, original_s3_hash) = Self::sanitize_upload(
+                    ctx,
+                    site_id,
+                    user_id,
+                    file_name,
+                    bytes.as_ref(),
+                )
+                .await?;
+                StorageQuotaService::check(ctx, site_id, bytes.len() as u64).await?;
+
                 let CreateBlobOutput {
                     hash,
                     mime,
@@ -151,10 +397,22 @@ impl FileService {
                     created: _,
                 } = BlobService::create(ctx, &bytes).await?;
 
+                UploadRuleService::check(
+                    ctx,
+                    UploadRuleTarget {
+                        site_id,
+                        user_id,
+                        mime: &mime,
+                        size: size as u64,
+                    },
+                )
+                .await?;
+
                 ProvidedValue::Set(FileBlob {
                     s3_hash: hash,
                     size_hint: size,
                     mime_hint: mime,
+                    original_s3_hash,
                 })
             }
         };
@@ -165,6 +423,7 @@ impl FileService {
         let model = file::ActiveModel {
             file_id: Set(file_id),
             updated_at: Set(Some(now())),
+            name: name.clone().into_active_value(),
             ..Default::default()
         };
         model.update(txn).await?;
@@ -182,6 +441,7 @@ impl FileService {
                     name,
                     blob,
                     licensing,
+                    overwrite,
                     ..Default::default()
                 },
             },
@@ -192,8 +452,12 @@ impl FileService {
         Ok(revision_output)
     }
 
-    /// Moves a file from from one page to another.
-    pub async fn r#move(
+    /// Moves a file from one page to another.
+    ///
+    /// The file may also be renamed as part of the move. The change is
+    /// recorded as a `page` revision on the file, and both the source
+    /// and destination pages are rerendered.
+    pub async fn move_file(
         ctx: &ServiceContext<'_>,
         MoveFile {
             name,
@@ -203,6 +467,7 @@ impl FileService {
             file_id,
             user_id,
             revision_comments,
+            overwrite,
         }: MoveFile,
     ) -> Result<Option<MoveFileOutput>> {
         let txn = ctx.transaction();
@@ -218,14 +483,14 @@ impl FileService {
             file_id, current_page_id, destination_page_id,
         );
 
-        // Ensure there isn't a file with this name on the destination page
-        Self::check_conflicts(ctx, destination_page_id, &name, "move").await?;
+        // Ensure the destination page exists in this site
+        PageService::get(ctx, site_id, Reference::Id(destination_page_id)).await?;
 
         // Update file metadata
         let model = file::ActiveModel {
             file_id: Set(file_id),
             updated_at: Set(Some(now())),
-            name: Set(name),
+            name: Set(name.clone()),
             page_id: Set(destination_page_id),
             ..Default::default()
         };
@@ -242,6 +507,8 @@ impl FileService {
                 comments: revision_comments,
                 body: CreateFileRevisionBody {
                     page_id: ProvidedValue::Set(destination_page_id),
+                    name: ProvidedValue::Set(name),
+                    overwrite,
                     ..Default::default()
                 },
             },
@@ -387,6 +654,249 @@ impl FileService {
         })
     }
 
+    /// Unhides a file revision and makes its contents the latest again.
+    ///
+    /// This is for revisions which were hidden for abuse, but were later
+    /// cleared. Rather than unhiding and then reverting separately, both are
+    /// done together, along with an entry in the audit log.
+    pub async fn restore_hidden_revision(
+        ctx: &ServiceContext<'_>,
+        RestoreHiddenFileRevision {
+            revision_comments,
+            site_id,
+            page_id,
+            file_id,
+            revision_id,
+            user_id,
+        }: RestoreHiddenFileRevision,
+    ) -> Result<RestoreHiddenFileRevisionOutput> {
+        info!("Restoring hidden revision ID {revision_id} for file ID {file_id}");
+
+        let txn = ctx.transaction();
+        let file = Self::get_direct(ctx, file_id, false).await?;
+        if file.page_id != page_id {
+            warn!("File's page ID and passed page ID do not match");
+            return Err(Error::FileNotFound);
+        }
+
+        let last_revision =
+            FileRevisionService::get_latest(ctx, site_id, page_id, file_id).await?;
+        let target =
+            FileRevisionService::unhide(ctx, site_id, page_id, file_id, revision_id)
+                .await?;
+
+        // The restored contents are checked as if they were being uploaded anew
+        if target.name != last_revision.name {
+            Self::check_conflicts(ctx, page_id, &target.name, "restore").await?;
+        }
+
+        UploadRuleService::check(
+            ctx,
+            UploadRuleTarget {
+                site_id,
+                user_id,
+                mime: &target.mime_hint,
+                size: target.size_hint as u64,
+            },
+        )
+        .await?;
+
+        // Create a new revision copying the target's contents
+        let s3_hash = target
+            .s3_hash
+            .as_slice()
+            .try_into()
+            .expect("Stored blob hash is the wrong length");
+
+        let revision_output = FileRevisionService::create(
+            ctx,
+            CreateFileRevision {
+                site_id,
+                page_id,
+                file_id,
+                user_id,
+                comments: revision_comments,
+                body: CreateFileRevisionBody {
+                    name: ProvidedValue::Set(target.name.clone()),
+                    blob: ProvidedValue::Set(FileBlob {
+                        s3_hash,
+                        size_hint: target.size_hint,
+                        mime_hint: target.mime_hint,
+                        original_s3_hash: target.original_s3_hash,
+                    }),
+                    licensing: ProvidedValue::Set(target.licensing),
+                    ..Default::default()
+                },
+            },
+            last_revision,
+        )
+        .await?;
+
+        // Update file metadata
+        let model = file::ActiveModel {
+            file_id: Set(file_id),
+            updated_at: Set(Some(now())),
+            name: Set(target.name),
+            ..Default::default()
+        };
+        model.update(txn).await?;
+
+        AuditService::record(
+            ctx,
+            CreateAuditEntry {
+                actor_id: user_id,
+                subject_id: target.user_id,
+                site_id: Some(site_id),
+                action: "file.restore-hidden",
+                data: serde_json::json!({
+                    "page_id": page_id,
+                    "file_id": file_id,
+                    "revision_id": revision_id,
+                    "revision_number": target.revision_number,
+                    "unhidden_fields": target.hidden,
+                    "new_revision_id": revision_output
+                        .as_ref()
+                        .map(|output| output.file_revision_id),
+                }),
+            },
+        )
+        .await?;
+
+        Ok(RestoreHiddenFileRevisionOutput {
+            unhidden_fields: target.hidden,
+            revision: revision_output,
+        })
+    }
+
+    /// Gets a short-lived URL to download a file's contents from directly.
+    ///
+    /// This way callers don't need to know how blobs are laid out in S3.
+    /// The user must be able to view files in the site, and the contents
+    /// of the revision must not be hidden.
+    pub async fn download_url(
+        ctx: &ServiceContext<'_>,
+        GetFileDownloadUrl {
+            site_id,
+            page_id,
+            file_id,
+            user_id,
+            revision_number,
+            original,
+        }: GetFileDownloadUrl,
+    ) -> Result<FileDownloadUrl> {
+        info!("Getting download URL for file ID {file_id} in site ID {site_id}");
+
+        let permission =
+            PermissionService::check(ctx, site_id, Some(page_id), user_id, "file_get")
+                .await?;
+        if !permission.allowed {
+            warn!(
+                "User ID {user_id:?} cannot download files in site ID {site_id} ({:?})",
+                permission.reason,
+            );
+            return Err(Error::FileDownloadForbidden);
+        }
+
+        let site = SiteService::get(ctx, Reference::Id(site_id)).await?;
+        let revision = match revision_number {
+            Some(revision_number) => {
+                FileRevisionService::get(
+                    ctx,
+                    GetFileRevision {
+                        site_id,
+                        page_id,
+                        file_id,
+                        revision_number,
+                    },
+                )
+                .await?
+            }
+            None => {
+                let file = Self::get(
+                    ctx,
+                    GetFile {
+                        site_id,
+                        page_id,
+                        file: Reference::Id(file_id),
+                    },
+                )
+                .await?;
+
+                StableRevisionService::get_file_revision(ctx, &site, &file).await?
+            }
+        };
+
+        if revision.hidden.iter().any(|field| field == "blob") {
+            warn!(
+                "Contents of file revision ID {} are hidden, not providing a download URL",
+                revision.revision_id,
+            );
+            return Err(Error::FileBlobHidden);
+        }
+
+        // Serve the converted copy of images, if there is one
+        let derivative = if original {
+            None
+        } else {
+            ThumbnailService::get_derivative(ctx, &site, &revision.s3_hash).await?
+        };
+
+        let (s3_hash, name, mime, size) = match derivative {
+            Some(derivative) => (
+                derivative.s3_hash.clone(),
+                derivative.file_name(&revision.name),
+                derivative.mime,
+                derivative.size,
+            ),
+            None => (
+                revision.s3_hash,
+                revision.name,
+                revision.mime_hint,
+                revision.size_hint,
+            ),
+        };
+
+        // Files uploaded before SVGs were sanitized may still have scripts,
+        // so any which do are only served through the CDN, which removes them.
+        let safe = if is_svg_mime(&mime) {
+            let data = BlobService::get(ctx, &s3_hash).await?;
+            match sanitize_stored_svg(&data) {
+                Ok(svg) => matches!(svg, Cow::Borrowed(_)),
+                Err(error) => {
+                    warn!("Unable to sanitize SVG file ID {file_id}: {error}");
+                    return Err(Error::FileSvgInvalid(error));
+                }
+            }
+        } else {
+            true
+        };
+
+        let expiry = ctx.config().file_download_url_expiry;
+        let (url, cdn) = try_join!(
+            async {
+                if safe {
+                    BlobService::presign_download(ctx, &s3_hash, &name, &mime, expiry)
+                        .await
+                } else {
+                    debug!("SVG has content to remove, not presigning a download URL");
+                    Ok(None)
+                }
+            },
+            CdnService::get_path(ctx, site_id, page_id, &s3_hash, &name),
+        )?;
+
+        Ok(FileDownloadUrl {
+            revision_id: revision.revision_id,
+            revision_number: revision.revision_number,
+            url,
+            expires_at: now() + expiry,
+            cdn,
+            name,
+            mime,
+            size,
+        })
+    }
+
     pub async fn get_optional(
         ctx: &ServiceContext<'_>,
         GetFile {
@@ -510,7 +1020,7 @@ impl FileService {
     /// Checks to see if a file already exists at the name specified.
     ///
     /// If so, this method fails with `Error::FileExists`. Otherwise it returns nothing.
-    async fn check_conflicts(
+    pub(crate) async fn check_conflicts(
         ctx: &ServiceContext<'_>,
         page_id: i64,
         name: &str,
@@ -541,11 +1051,134 @@ impl FileService {
         }
     }
 
+    /// Generates the name for the next image pasted into a page today.
+    async fn next_paste_name(
+        ctx: &ServiceContext<'_>,
+        page_id: i64,
+        extension: &str,
+    ) -> Result<String> {
+        let txn = ctx.transaction();
+
+        // Lock the page for the rest of the transaction, so concurrent
+        // pastes wait for this one rather than picking the same name.
+        //
+        // The unique index on file names doesn't catch this, since it
+        // includes deleted_at, which is NULL for both files.
+        Page::find_by_id(page_id)
+            .lock_exclusive()
+            .one(txn)
+            .await?
+            .ok_or(Error::PageNotFound)?;
+
+        let today = now().date();
+        let prefix = paste_prefix(today);
+        let names: Vec<String> = File::find()
+            .select_only()
+            .column(file::Column::Name)
+            .filter(
+                Condition::all()
+                    .add(file::Column::PageId.eq(page_id))
+                    .add(file::Column::Name.starts_with(&prefix))
+                    .add(file::Column::DeletedAt.is_null()),
+            )
+            .into_tuple()
+            .all(txn)
+            .await?;
+
+        Ok(next_paste_name(today, &names, extension))
+    }
+
+    /// Removes unwanted content from an upload before it is stored.
+    ///
+    /// SVGs always have scripts and external references removed, or are
+    /// rejected if the site does not allow them. Metadata is removed from
+    /// other images if the site has enabled it.
+    ///
+    /// Returns the data to store, along with the hash of the upload
+    /// as it was received if anything was removed. Only the hash of the
+    /// original is kept, so what was stripped can be audited but not recovered.
+    pub(crate) async fn sanitize_upload<'a>(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        user_id: i64,
+        name: &str,
+        data: &'a [u8],
+    ) -> Result<(Cow<'a, [u8]>, Option<Vec<u8>>)> {
+        let site = SiteService::get(ctx, Reference::Id(site_id)).await?;
+
+        if let Some(result) = sanitize_svg(data) {
+            if !site.allow_svg_uploads {
+                warn!("SVG uploads are disabled in site ID {site_id}");
+                return Err(Error::FileSvgDisallowed);
+            }
+
+            let SanitizedSvg {
+                data: output,
+                removed,
+            } = result.map_err(|error| {
+                warn!("Uploaded SVG '{name}' is not well-formed: {error}");
+                Error::FileSvgInvalid(error.to_string())
+            })?;
+
+            if removed.is_empty() {
+                debug!("Uploaded SVG has nothing to remove");
+                return Ok((output, None));
+            }
+
+            info!(
+                "Removed {} scripts or external references from uploaded SVG '{name}'",
+                removed.len(),
+            );
+
+            AuditService::record(
+                ctx,
+                CreateAuditEntry {
+                    actor_id: user_id,
+                    subject_id: user_id,
+                    site_id: Some(site_id),
+                    action: "file.svg-sanitize",
+                    data: serde_json::json!({
+                        "name": name,
+                        "removed": removed,
+                    }),
+                },
+            )
+            .await?;
+
+            let original_s3_hash = sha512_hash(data).to_vec();
+            return Ok((output, Some(original_s3_hash)));
+        }
+
+        if !site.strip_image_metadata {
+            return Ok((Cow::Borrowed(data), None));
+        }
+
+        match strip_image_metadata(data) {
+            Some(Cow::Owned(stripped)) => {
+                info!(
+                    "Stripped {} bytes of image metadata from upload",
+                    data.len() - stripped.len(),
+                );
+
+                let original_s3_hash = sha512_hash(data).to_vec();
+                Ok((Cow::Owned(stripped), Some(original_s3_hash)))
+            }
+            Some(Cow::Borrowed(_)) => {
+                debug!("Uploaded image has no metadata to strip");
+                Ok((Cow::Borrowed(data), None))
+            }
+            None => {
+                debug!("Upload is not a supported image, storing unchanged");
+                Ok((Cow::Borrowed(data), None))
+            }
+        }
+    }
+
     /// This runs the regular expression-based text filters against a file's name.
     ///
     /// It does not check the file's contents, as that is a binary blob.
     /// Such a hash filter would need to be implemented through a separate system.
-    async fn run_filter(
+    pub(crate) async fn run_filter(
         ctx: &ServiceContext<'_>,
         site_id: i64,
         name: Option<&str>,
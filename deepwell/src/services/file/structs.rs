@@ -19,11 +19,13 @@
  */
 
 use crate::models::sea_orm_active_enums::FileRevisionType;
+use crate::services::cdn::CdnPath;
 use crate::services::file_revision::{
     CreateFileRevisionOutput, CreateFirstFileRevisionOutput,
 };
+use crate::services::thumbnail::ImageDerivative;
 use crate::web::{Bytes, FileDetails, ProvidedValue, Reference};
-use serde_json::Value as JsonValue;
+use serde_json::{json, Value as JsonValue};
 use time::OffsetDateTime;
 
 #[derive(Deserialize, Debug, Clone)]
@@ -42,6 +44,55 @@ pub struct UploadFile {
 
 pub type UploadFileOutput = CreateFirstFileRevisionOutput;
 
+#[derive(Deserialize, Debug, Clone)]
+pub struct PasteFile {
+    pub site_id: i64,
+    pub page_id: i64,
+    pub user_id: i64,
+    pub data: Bytes<'static>,
+
+    #[serde(default)]
+    pub revision_comments: String,
+
+    /// Pasted images rarely come with licensing details, so if none
+    /// are given, then the uploader is taken to reserve all rights.
+    #[serde(default = "default_paste_licensing")]
+    pub licensing: JsonValue,
+}
+
+fn default_paste_licensing() -> JsonValue {
+    json!({ "license": "all-rights-reserved" })
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct PasteFileOutput {
+    pub file_id: i64,
+    pub file_revision_id: i64,
+    pub name: String,
+
+    /// The wikitext to insert into the page to show the image.
+    pub wikitext: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct AttachFile {
+    pub site_id: i64,
+    pub page_id: i64,
+    pub user_id: i64,
+    pub staged_file_id: i64,
+
+    /// The name to give the file, if not the one it was staged with.
+    #[serde(default)]
+    pub name: Option<String>,
+    pub revision_comments: String,
+    pub licensing: JsonValue, // TODO
+
+    #[serde(default)]
+    pub bypass_filter: bool,
+}
+
+pub type AttachFileOutput = CreateFirstFileRevisionOutput;
+
 #[derive(Deserialize, Debug, Clone)]
 pub struct GetFile<'a> {
     pub site_id: i64,
@@ -56,6 +107,10 @@ pub struct GetFileDetails<'a> {
 
     #[serde(default)]
     pub details: FileDetails,
+
+    /// Get the latest revision, even if the site is serving a stable one.
+    #[serde(default)]
+    pub latest: bool,
 }
 
 #[derive(Serialize, Debug, Clone)]
@@ -68,7 +123,7 @@ pub struct GetFileOutput {
     pub revision_id: i64,
     pub revision_type: FileRevisionType,
     pub revision_created_at: OffsetDateTime,
-    pub revision_number: i32,
+    pub revision_number: i64,
     pub revision_user_id: i64,
     pub name: String,
     pub data: Option<Bytes<'static>>,
@@ -77,6 +132,53 @@ pub struct GetFileOutput {
     pub licensing: JsonValue,
     pub revision_comments: String,
     pub hidden_fields: Vec<String>,
+
+    /// The converted copy of this image which is served instead, if any.
+    ///
+    /// See the site's image conversion policy.
+    pub derivative: Option<ImageDerivative>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetFileDownloadUrl {
+    pub site_id: i64,
+    pub page_id: i64,
+    pub file_id: i64,
+
+    /// The user downloading the file, if logged in.
+    #[serde(default)]
+    pub user_id: Option<i64>,
+
+    /// Which revision to download. If not set, this is
+    /// the one the site is serving, as with `file_get`.
+    #[serde(default)]
+    pub revision_number: Option<i64>,
+
+    /// Whether to download the image as uploaded,
+    /// rather than its converted derivative.
+    #[serde(default)]
+    pub original: bool,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct FileDownloadUrl {
+    pub revision_id: i64,
+    pub revision_number: i64,
+
+    /// The presigned URL to download the file from.
+    ///
+    /// This is `None` if the contents aren't in S3 yet,
+    /// in which case they must be fetched with `file_get`.
+    /// It is also `None` for SVGs which still have scripts or
+    /// external references, which are only served through `cdn`.
+    pub url: Option<String>,
+    pub expires_at: OffsetDateTime,
+
+    /// The content-addressed path to serve the file from, such as through a CDN.
+    pub cdn: CdnPath,
+    pub name: String,
+    pub mime: String,
+    pub size: i64,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -100,6 +202,9 @@ pub struct EditFileBody {
     pub name: ProvidedValue<String>,
     pub data: ProvidedValue<Bytes<'static>>,
     pub licensing: ProvidedValue<serde_json::Value>,
+
+    /// Whether to replace another file which already has the new name.
+    pub overwrite: bool,
 }
 
 pub type EditFileOutput = CreateFileRevisionOutput;
@@ -113,6 +218,10 @@ pub struct MoveFile {
     pub name: Option<String>,
     pub current_page_id: i64,
     pub destination_page_id: i64,
+
+    /// Whether to replace a file on the destination page which has the same name.
+    #[serde(default)]
+    pub overwrite: bool,
 }
 
 pub type MoveFileOutput = CreateFileRevisionOutput;
@@ -141,7 +250,7 @@ pub struct RestoreFile {
 pub struct DeleteFileOutput {
     pub file_id: i64,
     pub file_revision_id: i64,
-    pub file_revision_number: i32,
+    pub file_revision_number: i64,
 }
 
 #[derive(Serialize, Debug, Clone)]
@@ -150,5 +259,44 @@ pub struct RestoreFileOutput {
     pub file_id: i64,
     pub name: String,
     pub file_revision_id: i64,
-    pub file_revision_number: i32,
+    pub file_revision_number: i64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct RestoreHiddenFileRevision {
+    pub revision_comments: String,
+    pub site_id: i64,
+    pub page_id: i64,
+    pub file_id: i64,
+    pub revision_id: i64,
+    pub user_id: i64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct RestoreHiddenFileRevisionOutput {
+    /// The fields which were hidden on the restored revision.
+    pub unhidden_fields: Vec<String>,
+
+    /// The new latest revision, or `None` if the file already matched it.
+    pub revision: Option<CreateFileRevisionOutput>,
+}
+
+#[test]
+fn paste_default_licensing() {
+    use crate::services::file_revision::FileLicensing;
+
+    let input = json!({
+        "site_id": 1,
+        "page_id": 2,
+        "user_id": 3,
+        "data": "89504e47",
+    });
+
+    let paste: PasteFile =
+        serde_json::from_value(input).expect("Unable to deserialize paste");
+
+    assert!(
+        FileLicensing::validate(&paste.licensing).is_ok(),
+        "Default paste licensing is not valid",
+    );
 }
@@ -0,0 +1,472 @@
+/*
+ * services/file/svg.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Removal of active content from uploaded SVG images.
+//!
+//! Unlike other image formats, SVGs are XML documents which can contain
+//! scripts, event handlers, and references to other resources. These run
+//! if the file is opened directly rather than shown through an `<img>`,
+//! so anything which could execute or load something is dropped here.
+//!
+//! Files which are unchanged are stored as-is. Otherwise the document is
+//! written back out with only the offending elements and attributes removed.
+
+use quick_xml::events::attributes::Attribute;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::{Reader, Writer};
+use serde::Serialize;
+use std::borrow::Cow;
+
+/// Elements which are removed along with everything inside them.
+const REMOVED_ELEMENTS: [&str; 8] = [
+    "script",
+    "foreignobject",
+    "iframe",
+    "embed",
+    "object",
+    "handler",
+    "listener",
+    "audio",
+];
+
+/// Animation elements, which can change attributes after sanitization.
+const ANIMATION_ELEMENTS: [&str; 4] =
+    ["animate", "animatemotion", "animatetransform", "set"];
+
+/// Embedded image formats which may be referenced through `data:` URLs.
+const DATA_URL_PREFIXES: [&str; 4] = [
+    "data:image/png",
+    "data:image/jpeg",
+    "data:image/gif",
+    "data:image/webp",
+];
+
+/// Something which was removed from an SVG.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum SvgRemoval {
+    Element { name: String },
+    Attribute { element: String, name: String },
+    Doctype,
+    ProcessingInstruction,
+}
+
+#[derive(Debug)]
+pub struct SanitizedSvg<'a> {
+    pub data: Cow<'a, [u8]>,
+    pub removed: Vec<SvgRemoval>,
+}
+
+/// Removes scripts and external references from an SVG image.
+///
+/// Returns `None` if the data is not an SVG, and an error if it is,
+/// but is not well-formed. If nothing was removed, the original data is returned.
+pub fn sanitize_svg(data: &[u8]) -> Option<Result<SanitizedSvg<'_>, quick_xml::Error>> {
+    if !is_svg(data) {
+        return None;
+    }
+
+    let mut removed = Vec::new();
+    let output = match sanitize(data, &mut removed) {
+        Ok(output) => output,
+        Err(error) => return Some(Err(error)),
+    };
+
+    let data = if removed.is_empty() {
+        Cow::Borrowed(data)
+    } else {
+        Cow::Owned(output)
+    };
+
+    Some(Ok(SanitizedSvg { data, removed }))
+}
+
+/// Checks whether a MIME type is that of an SVG image, ignoring any parameters.
+pub fn is_svg_mime(mime: &str) -> bool {
+    let essence = mime.split(';').next().unwrap_or_default().trim();
+    essence.eq_ignore_ascii_case("image/svg+xml")
+}
+
+/// Removes scripts and external references from an SVG image being served.
+///
+/// Files uploaded before sanitization was added may still contain them,
+/// so this is applied whenever an SVG is served, not only on upload.
+/// Data which isn't a well-formed SVG cannot be made safe, so it is rejected.
+pub fn sanitize_stored_svg(data: &[u8]) -> Result<Cow<'_, [u8]>, String> {
+    match sanitize_svg(data) {
+        Some(Ok(svg)) => Ok(svg.data),
+        Some(Err(error)) => Err(error.to_string()),
+        None => Err(str!("Root element is not <svg>")),
+    }
+}
+
+/// Checks whether the root element of an XML document is `<svg>`.
+fn is_svg(data: &[u8]) -> bool {
+    let mut reader = Reader::from_reader(data);
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(element) | Event::Empty(element)) => {
+                return element.local_name().as_ref().eq_ignore_ascii_case(b"svg");
+            }
+            Ok(Event::Eof) | Err(_) => return false,
+            Ok(_) => (),
+        }
+    }
+}
+
+fn sanitize(
+    data: &[u8],
+    removed: &mut Vec<SvgRemoval>,
+) -> Result<Vec<u8>, quick_xml::Error> {
+    let mut reader = Reader::from_reader(data);
+    let mut writer = Writer::new(Vec::with_capacity(data.len()));
+
+    // How deep we are inside an element being removed
+    let mut skip_depth = 0;
+
+    // The contents of a <style> element, held until it is known to be safe
+    let mut style: Option<Vec<Event>> = None;
+
+    loop {
+        let event = reader.read_event()?;
+
+        if skip_depth > 0 {
+            match event {
+                Event::Start(_) => skip_depth += 1,
+                Event::End(_) => skip_depth -= 1,
+                Event::Eof => break,
+                _ => (),
+            }
+            continue;
+        }
+
+        if let Some(events) = &mut style {
+            match event {
+                Event::End(_) => {
+                    events.push(event);
+                    let events = style.take().unwrap_or_default();
+                    if style_is_safe(&events) {
+                        write_all(&mut writer, events);
+                    } else {
+                        removed.push(SvgRemoval::Element {
+                            name: str!("style"),
+                        });
+                    }
+                }
+                Event::Start(_) => {
+                    // Stylesheets don't have child elements
+                    removed.push(SvgRemoval::Element {
+                        name: str!("style"),
+                    });
+                    style = None;
+                    skip_depth = 2;
+                }
+                Event::Eof => break,
+                _ => events.push(event),
+            }
+            continue;
+        }
+
+        match event {
+            Event::Start(element) => {
+                let name = element_name(&element);
+                if removes_element(&element, &name) {
+                    removed.push(SvgRemoval::Element { name });
+                    skip_depth = 1;
+                    continue;
+                }
+
+                let element = sanitize_attributes(element, &name, removed)?;
+                if name == "style" {
+                    style = Some(vec![Event::Start(element)]);
+                } else {
+                    write(&mut writer, Event::Start(element));
+                }
+            }
+            Event::Empty(element) => {
+                let name = element_name(&element);
+                if removes_element(&element, &name) {
+                    removed.push(SvgRemoval::Element { name });
+                    continue;
+                }
+
+                let element = sanitize_attributes(element, &name, removed)?;
+                write(&mut writer, Event::Empty(element));
+            }
+            Event::DocType(_) => removed.push(SvgRemoval::Doctype),
+            Event::PI(_) => removed.push(SvgRemoval::ProcessingInstruction),
+            Event::Eof => break,
+            event => write(&mut writer, event),
+        }
+    }
+
+    Ok(writer.into_inner())
+}
+
+fn element_name(element: &BytesStart) -> String {
+    String::from_utf8_lossy(element.local_name().as_ref()).to_ascii_lowercase()
+}
+
+fn removes_element(element: &BytesStart, name: &str) -> bool {
+    if REMOVED_ELEMENTS.contains(&name) {
+        return true;
+    }
+
+    // Animations can set an attribute to anything, including a script URL
+    if ANIMATION_ELEMENTS.contains(&name) {
+        return element.attributes().flatten().any(|attribute| {
+            attribute.key.local_name().as_ref() == b"attributeName"
+                && is_animation_target_unsafe(&attribute.value)
+        });
+    }
+
+    false
+}
+
+/// Rebuilds an element, leaving out any attributes which aren't safe.
+///
+/// The element is returned unchanged if all of its attributes are kept.
+fn sanitize_attributes<'a>(
+    mut element: BytesStart<'a>,
+    name: &str,
+    removed: &mut Vec<SvgRemoval>,
+) -> Result<BytesStart<'a>, quick_xml::Error> {
+    let mut kept = Vec::new();
+    let mut changed = false;
+
+    for attribute in element.attributes() {
+        let attribute = attribute?;
+        let value = attribute.unescape_value()?;
+        if is_safe_attribute(&attribute, &value) {
+            let key = String::from_utf8_lossy(attribute.key.as_ref()).into_owned();
+            kept.push((key, value.into_owned()));
+        } else {
+            removed.push(SvgRemoval::Attribute {
+                element: str!(name),
+                name: String::from_utf8_lossy(attribute.key.as_ref()).into_owned(),
+            });
+            changed = true;
+        }
+    }
+
+    if !changed {
+        return Ok(element);
+    }
+
+    element.clear_attributes();
+    for (key, value) in &kept {
+        element.push_attribute(Attribute::from((key.as_str(), value.as_str())));
+    }
+
+    Ok(element)
+}
+
+fn is_safe_attribute(attribute: &Attribute, value: &str) -> bool {
+    let key = attribute.key.local_name();
+    if is_event_handler(key.as_ref()) {
+        return false;
+    }
+
+    // Browsers ignore whitespace and case in URL schemes
+    let value = value
+        .chars()
+        .filter(|c| !c.is_whitespace() && !c.is_control())
+        .collect::<String>()
+        .to_ascii_lowercase();
+
+    if value.contains("javascript:") || value.contains("expression(") {
+        return false;
+    }
+
+    // Links may only point within the document, or to embedded raster images
+    if matches!(key.as_ref(), b"href" | b"src") {
+        return value.starts_with('#')
+            || DATA_URL_PREFIXES
+                .iter()
+                .any(|prefix| value.starts_with(prefix));
+    }
+
+    !has_external_reference(&value)
+}
+
+fn is_event_handler(name: &[u8]) -> bool {
+    name.len() > 2 && name[..2].eq_ignore_ascii_case(b"on")
+}
+
+/// Whether an animation changing this attribute could run a script or load something.
+fn is_animation_target_unsafe(name: &[u8]) -> bool {
+    let name = match name.iter().rposition(|&b| b == b':') {
+        Some(index) => &name[index + 1..],
+        None => name,
+    };
+
+    is_event_handler(name)
+        || name.eq_ignore_ascii_case(b"href")
+        || name.eq_ignore_ascii_case(b"src")
+        || name.eq_ignore_ascii_case(b"style")
+}
+
+/// Checks CSS for anything loaded from outside the document.
+///
+/// The input is expected to be lowercase, with whitespace removed.
+fn has_external_reference(css: &str) -> bool {
+    if css.contains("@import") {
+        return true;
+    }
+
+    css.split("url(").skip(1).any(|reference| {
+        let reference = reference.trim_start_matches(['"', '\'']);
+        !reference.starts_with('#')
+    })
+}
+
+fn style_is_safe(events: &[Event]) -> bool {
+    let mut css = String::new();
+    for event in events {
+        match event {
+            Event::Text(text) => css.push_str(&String::from_utf8_lossy(text)),
+            Event::CData(text) => css.push_str(&String::from_utf8_lossy(text)),
+            _ => (),
+        }
+    }
+
+    let css = css
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>()
+        .to_ascii_lowercase();
+
+    !has_external_reference(&css)
+        && !css.contains("javascript:")
+        && !css.contains("expression(")
+}
+
+fn write(writer: &mut Writer<Vec<u8>>, event: Event) {
+    writer
+        .write_event(event)
+        .expect("Writing to a buffer cannot fail");
+}
+
+fn write_all(writer: &mut Writer<Vec<u8>>, events: Vec<Event>) {
+    for event in events {
+        write(writer, event);
+    }
+}
+
+#[test]
+fn sanitize_svg_content() {
+    macro_rules! check {
+        ($input:expr, $output:expr, $removed:expr $(,)?) => {{
+            let output = sanitize_svg($input.as_bytes())
+                .expect("Input is not an SVG")
+                .expect("Unable to sanitize SVG");
+
+            assert_eq!(String::from_utf8_lossy(&output.data), $output);
+            assert_eq!(output.removed, $removed);
+        }};
+    }
+
+    macro_rules! element {
+        ($name:expr) => {
+            SvgRemoval::Element { name: str!($name) }
+        };
+    }
+
+    macro_rules! attribute {
+        ($element:expr, $name:expr) => {
+            SvgRemoval::Attribute {
+                element: str!($element),
+                name: str!($name),
+            }
+        };
+    }
+
+    // Not SVGs
+    assert!(sanitize_svg(b"plain text").is_none());
+    assert!(sanitize_svg(b"<html><body></body></html>").is_none());
+    assert!(sanitize_svg(b"\x89PNG\r\n\x1a\n").is_none());
+
+    // Malformed
+    assert!(matches!(sanitize_svg(b"<svg><g></svg>"), Some(Err(_))));
+
+    // Already safe
+    let input = r##"<?xml version="1.0"?><svg xmlns="http://www.w3.org/2000/svg"><rect fill="url(#grad)" width='10'/><use href="#shape"/></svg>"##;
+    let output = sanitize_svg(input.as_bytes()).unwrap().unwrap();
+    assert!(matches!(output.data, Cow::Borrowed(_)));
+    assert!(output.removed.is_empty());
+
+    check!(
+        r#"<svg><script>alert(1)</script><g onclick="alert(1)" id="a"><circle r="5"/></g></svg>"#,
+        r#"<svg><g id="a"><circle r="5"/></g></svg>"#,
+        [element!("script"), attribute!("g", "onclick")],
+    );
+
+    check!(
+        r#"<svg><foreignObject><div><p>hi</p></div></foreignObject><a xlink:href="javascript:alert(1)"><text>x</text></a></svg>"#,
+        r#"<svg><a><text>x</text></a></svg>"#,
+        [element!("foreignobject"), attribute!("a", "xlink:href")],
+    );
+
+    check!(
+        r#"<svg><image href="https://example.com/track.png"/><image href="data:image/png;base64,AAAA"/></svg>"#,
+        r#"<svg><image/><image href="data:image/png;base64,AAAA"/></svg>"#,
+        [attribute!("image", "href")],
+    );
+
+    check!(
+        r#"<svg><style>@import url(https://example.com/a.css);</style><style>rect { fill: red }</style><rect style="fill: url( 'https://example.com/x' )"/></svg>"#,
+        r#"<svg><style>rect { fill: red }</style><rect/></svg>"#,
+        [element!("style"), attribute!("rect", "style")],
+    );
+
+    check!(
+        r#"<!DOCTYPE svg [<!ENTITY x "y">]><?xml-stylesheet href="a.css"?><svg><set attributeName="onmouseover" to="alert(1)"/><animate attributeName="opacity"/></svg>"#,
+        r#"<svg><animate attributeName="opacity"/></svg>"#,
+        [
+            SvgRemoval::Doctype,
+            SvgRemoval::ProcessingInstruction,
+            element!("set"),
+        ],
+    );
+
+    // Values are re-escaped when elements are rebuilt
+    check!(
+        r#"<svg><text title='a "quote" &amp; more' onload="x">hi</text></svg>"#,
+        r#"<svg><text title="a &quot;quote&quot; &amp; more">hi</text></svg>"#,
+        [attribute!("text", "onload")],
+    );
+}
+
+#[test]
+fn sanitize_stored_svg_content() {
+    let input = br#"<svg><script>alert(1)</script><rect width="10"/></svg>"#;
+    let output = sanitize_stored_svg(input).expect("Unable to sanitize SVG");
+    assert_eq!(&*output, br#"<svg><rect width="10"/></svg>"#);
+
+    let input = br#"<svg><rect width="10"/></svg>"#;
+    let output = sanitize_stored_svg(input).expect("Unable to sanitize SVG");
+    assert!(matches!(output, Cow::Borrowed(_)));
+
+    // Anything else served as an SVG could still run scripts
+    assert!(sanitize_stored_svg(b"<svg><g></svg>").is_err());
+    assert!(
+        sanitize_stored_svg(br#"<html xmlns="http://www.w3.org/1999/xhtml"/>"#).is_err()
+    );
+}
@@ -0,0 +1,42 @@
+/*
+ * services/file_retention/mod.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The file retention service, for expiring the contents of old file revisions.
+//!
+//! Sites with many frequently-updated files can choose to only keep the
+//! contents of intermediate revisions for so many days. Once expired, a
+//! revision's blob is replaced with the tombstone blob and hidden, the same
+//! as if it had been purged, but its history entry is kept, so revision
+//! numbers and counts are unaffected.
+//!
+//! The first, latest, and stable revisions of each file are always kept, as are
+//! milestone revisions, which are those other than regular updates, such as a
+//! file being deleted or restored. This is applied by a recurring job, and each
+//! file squashed this way is recorded in the audit log.
+
+mod prelude {
+    pub use super::super::prelude::*;
+    pub use super::structs::*;
+}
+
+mod service;
+mod structs;
+
+pub use self::service::FileRetentionService;
@@ -0,0 +1,231 @@
+/*
+ * services/file_retention/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::constants::SYSTEM_USER_ID;
+use crate::models::file::{Entity as File, Model as FileModel};
+use crate::models::file_revision::{
+    self, Entity as FileRevision, Model as FileRevisionModel,
+};
+use crate::models::sea_orm_active_enums::FileRevisionType;
+use crate::models::site::{self, Entity as Site, Model as SiteModel};
+use crate::services::audit::CreateAuditEntry;
+use crate::services::{AuditService, BlobService, StorageQuotaService, ThumbnailService};
+use sea_query::Expr;
+use serde_json::json;
+use std::collections::BTreeMap;
+use time::Duration;
+
+/// The most revisions to expire in a site each time the job runs.
+///
+/// This keeps each run's transaction a reasonable size. When a site
+/// first sets a retention policy, its backlog is worked through over
+/// several runs instead.
+const MAXIMUM_REVISIONS_PER_RUN: u64 = 1000;
+
+#[derive(Debug)]
+pub struct FileRetentionService;
+
+impl FileRetentionService {
+    /// Applies the retention policy of every site which has one.
+    pub async fn run(ctx: &ServiceContext<'_>) -> Result<FileRetentionOutput> {
+        let txn = ctx.transaction();
+        let sites = Site::find()
+            .filter(site::Column::FileRevisionRetentionDays.is_not_null())
+            .filter(site::Column::DeletedAt.is_null())
+            .all(txn)
+            .await?;
+
+        let mut output = FileRetentionOutput::default();
+        for site in sites {
+            let site_output = Self::apply(ctx, &site).await?;
+            output.files_squashed += site_output.files_squashed;
+            output.revisions_expired += site_output.revisions_expired;
+            output.blobs_deleted += site_output.blobs_deleted;
+        }
+
+        info!(
+            "Expired {} file revisions in {} files, deleting {} blobs",
+            output.revisions_expired, output.files_squashed, output.blobs_deleted,
+        );
+
+        Ok(output)
+    }
+
+    /// Expires the contents of a site's file revisions which are past its retention period.
+    pub async fn apply(
+        ctx: &ServiceContext<'_>,
+        site: &SiteModel,
+    ) -> Result<FileRetentionOutput> {
+        let mut output = FileRetentionOutput::default();
+        let days = match site.file_revision_retention_days {
+            Some(days) => days,
+            None => return Ok(output),
+        };
+
+        debug!(
+            "Applying {days} day file revision retention to site ID {}",
+            site.site_id,
+        );
+
+        // Only regular updates can expire, the rest are milestones.
+        // Revisions whose contents are already hidden are left alone.
+        let txn = ctx.transaction();
+        let cutoff = now() - Duration::days(i64::from(days));
+        let candidates = FileRevision::find()
+            .filter(
+                Condition::all()
+                    .add(file_revision::Column::SiteId.eq(site.site_id))
+                    .add(file_revision::Column::RevisionType.eq(FileRevisionType::Update))
+                    .add(file_revision::Column::CreatedAt.lt(cutoff))
+                    .add(Expr::cust("NOT ('blob' = ANY(hidden))")),
+            )
+            .order_by_asc(file_revision::Column::RevisionId)
+            .limit(MAXIMUM_REVISIONS_PER_RUN)
+            .all(txn)
+            .await?;
+
+        let mut by_file: BTreeMap<i64, Vec<FileRevisionModel>> = BTreeMap::new();
+        for revision in candidates {
+            by_file.entry(revision.file_id).or_default().push(revision);
+        }
+
+        for (file_id, revisions) in by_file {
+            let file = File::find_by_id(file_id)
+                .one(txn)
+                .await?
+                .ok_or(Error::FileNotFound)?;
+
+            let latest_revision_number = Self::get_latest_number(ctx, file_id).await?;
+            let revisions: Vec<_> = revisions
+                .into_iter()
+                .filter(|revision| {
+                    revision.revision_number != latest_revision_number
+                        && Some(revision.revision_id) != file.stable_revision_id
+                })
+                .collect();
+
+            if revisions.is_empty() {
+                continue;
+            }
+
+            let blobs_deleted = Self::squash(ctx, &file, &revisions).await?;
+            output.files_squashed += 1;
+            output.revisions_expired += revisions.len() as u64;
+            output.blobs_deleted += blobs_deleted;
+        }
+
+        Ok(output)
+    }
+
+    /// Replaces the contents of these revisions of a file with the tombstone blob.
+    ///
+    /// Returns how many blobs were deleted as a result.
+    async fn squash(
+        ctx: &ServiceContext<'_>,
+        file: &FileModel,
+        revisions: &[FileRevisionModel],
+    ) -> Result<u64> {
+        info!(
+            "Expiring {} revisions of file ID {} in site ID {}",
+            revisions.len(),
+            file.file_id,
+            file.site_id,
+        );
+
+        let txn = ctx.transaction();
+        let tombstone = BlobService::create_tombstone(ctx).await?;
+        let mut released_bytes = 0;
+
+        for revision in revisions {
+            if StorageQuotaService::is_counted(revision) {
+                released_bytes += revision.size_hint;
+            }
+
+            let mut hidden = revision.hidden.clone();
+            hidden.push(str!("blob"));
+
+            let model = file_revision::ActiveModel {
+                revision_id: Set(revision.revision_id),
+                s3_hash: Set(tombstone.hash.to_vec()),
+                mime_hint: Set(tombstone.mime.clone()),
+                size_hint: Set(tombstone.size),
+                hidden: Set(hidden),
+                ..Default::default()
+            };
+            model.update(txn).await?;
+        }
+
+        StorageQuotaService::remove(ctx, file.site_id, released_bytes).await?;
+
+        // Blobs are shared, so they are only deleted if these were their last uses
+        let mut blobs_deleted = 0;
+        let mut released_hashes: Vec<&[u8]> = revisions
+            .iter()
+            .map(|revision| revision.s3_hash.as_slice())
+            .collect();
+        released_hashes.sort_unstable();
+        released_hashes.dedup();
+
+        for hash in released_hashes {
+            if BlobService::release(ctx, hash).await? {
+                ThumbnailService::delete_for_blob(ctx, hash).await?;
+                blobs_deleted += 1;
+            }
+        }
+
+        AuditService::record(
+            ctx,
+            CreateAuditEntry {
+                actor_id: SYSTEM_USER_ID,
+                subject_id: SYSTEM_USER_ID,
+                site_id: Some(file.site_id),
+                action: "file.revision-retention",
+                data: json!({
+                    "page_id": file.page_id,
+                    "file_id": file.file_id,
+                    "revision_numbers": revisions
+                        .iter()
+                        .map(|revision| revision.revision_number)
+                        .collect::<Vec<_>>(),
+                    "released_bytes": released_bytes,
+                    "blobs_deleted": blobs_deleted,
+                }),
+            },
+        )
+        .await?;
+
+        Ok(blobs_deleted)
+    }
+
+    async fn get_latest_number(ctx: &ServiceContext<'_>, file_id: i64) -> Result<i64> {
+        let txn = ctx.transaction();
+        let revision_number: Option<i64> = FileRevision::find()
+            .select_only()
+            .column(file_revision::Column::RevisionNumber)
+            .filter(file_revision::Column::FileId.eq(file_id))
+            .order_by_desc(file_revision::Column::RevisionNumber)
+            .into_tuple()
+            .one(txn)
+            .await?;
+
+        revision_number.ok_or(Error::FileRevisionNotFound)
+    }
+}
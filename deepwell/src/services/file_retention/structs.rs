@@ -0,0 +1,31 @@
+/*
+ * services/file_retention/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+#[derive(Serialize, Debug, Default, Clone)]
+pub struct FileRetentionOutput {
+    /// How many files had revisions expired.
+    pub files_squashed: u64,
+
+    /// How many revisions had their contents removed.
+    pub revisions_expired: u64,
+
+    /// How many blobs were deleted, as nothing else used them.
+    pub blobs_deleted: u64,
+}
@@ -0,0 +1,398 @@
+/*
+ * services/file_revision/licensing.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The licensing information attached to each file revision.
+//!
+//! This is stored as JSON, and looks like:
+//!
+//! ```json
+//! {
+//!     "license": "cc-by-sa-4.0",
+//!     "attribution": {
+//!         "author": "Jane Doe",
+//!         "source": "https://example.com/image.png",
+//!         "text": "Photograph by Jane Doe"
+//!     }
+//! }
+//! ```
+//!
+//! The `attribution` object and each of its fields are optional, except that
+//! a `custom` license must include the license text as `attribution.text`.
+
+use serde_json::{Map, Value as JsonValue};
+
+const MAX_AUTHOR_LENGTH: usize = 256;
+const MAX_SOURCE_LENGTH: usize = 2048;
+const MAX_TEXT_LENGTH: usize = 4096;
+
+/// A license which a file may be made available under.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum License {
+    #[serde(rename = "cc0-1.0")]
+    Cc0,
+
+    #[serde(rename = "cc-by-3.0")]
+    CcBy3,
+
+    #[serde(rename = "cc-by-4.0")]
+    CcBy4,
+
+    #[serde(rename = "cc-by-sa-3.0")]
+    CcBySa3,
+
+    #[serde(rename = "cc-by-sa-4.0")]
+    CcBySa4,
+
+    #[serde(rename = "cc-by-nc-4.0")]
+    CcByNc4,
+
+    #[serde(rename = "cc-by-nc-sa-4.0")]
+    CcByNcSa4,
+
+    #[serde(rename = "cc-by-nd-4.0")]
+    CcByNd4,
+
+    #[serde(rename = "cc-by-nc-nd-4.0")]
+    CcByNcNd4,
+
+    #[serde(rename = "public-domain")]
+    PublicDomain,
+
+    #[serde(rename = "fair-use")]
+    FairUse,
+
+    #[serde(rename = "all-rights-reserved")]
+    AllRightsReserved,
+
+    #[serde(rename = "custom")]
+    Custom,
+}
+
+impl License {
+    pub fn parse(value: &str) -> Option<Self> {
+        let license = match value {
+            "cc0-1.0" => License::Cc0,
+            "cc-by-3.0" => License::CcBy3,
+            "cc-by-4.0" => License::CcBy4,
+            "cc-by-sa-3.0" => License::CcBySa3,
+            "cc-by-sa-4.0" => License::CcBySa4,
+            "cc-by-nc-4.0" => License::CcByNc4,
+            "cc-by-nc-sa-4.0" => License::CcByNcSa4,
+            "cc-by-nd-4.0" => License::CcByNd4,
+            "cc-by-nc-nd-4.0" => License::CcByNcNd4,
+            "public-domain" => License::PublicDomain,
+            "fair-use" => License::FairUse,
+            "all-rights-reserved" => License::AllRightsReserved,
+            "custom" => License::Custom,
+            _ => return None,
+        };
+
+        Some(license)
+    }
+
+    /// The name of this license, as shown to readers.
+    pub fn name(self) -> &'static str {
+        match self {
+            License::Cc0 => "CC0 1.0",
+            License::CcBy3 => "CC BY 3.0",
+            License::CcBy4 => "CC BY 4.0",
+            License::CcBySa3 => "CC BY-SA 3.0",
+            License::CcBySa4 => "CC BY-SA 4.0",
+            License::CcByNc4 => "CC BY-NC 4.0",
+            License::CcByNcSa4 => "CC BY-NC-SA 4.0",
+            License::CcByNd4 => "CC BY-ND 4.0",
+            License::CcByNcNd4 => "CC BY-NC-ND 4.0",
+            License::PublicDomain => "Public domain",
+            License::FairUse => "Fair use",
+            License::AllRightsReserved => "All rights reserved",
+            License::Custom => "Custom license",
+        }
+    }
+}
+
+/// Credit for the creator of a file, or the terms of a custom license.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct Attribution {
+    pub author: Option<String>,
+    pub source: Option<String>,
+    pub text: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct FileLicensing {
+    pub license: License,
+
+    #[serde(default)]
+    pub attribution: Option<Attribution>,
+}
+
+impl FileLicensing {
+    /// Checks a licensing object submitted for a file revision.
+    ///
+    /// Rather than stopping at the first problem, this returns the path
+    /// of every field which is malformed, such as `attribution.source`.
+    /// Unknown fields are reported too, so typos are not silently kept.
+    pub fn validate(value: &JsonValue) -> Result<Self, Vec<String>> {
+        let mut invalid = Vec::new();
+
+        let Some(object) = value.as_object() else {
+            return Err(vec![str!("licensing")]);
+        };
+
+        check_unknown_fields(object, &["license", "attribution"], "", &mut invalid);
+
+        let license = match object.get("license") {
+            Some(JsonValue::String(value)) => License::parse(value),
+            _ => None,
+        };
+
+        if license.is_none() {
+            invalid.push(str!("license"));
+        }
+
+        let attribution = match object.get("attribution") {
+            None | Some(JsonValue::Null) => None,
+            Some(JsonValue::Object(object)) => {
+                Some(validate_attribution(object, &mut invalid))
+            }
+            Some(_) => {
+                invalid.push(str!("attribution"));
+                None
+            }
+        };
+
+        // A custom license has no meaning without its terms
+        if license == Some(License::Custom) {
+            let has_text = attribution
+                .as_ref()
+                .map(|attribution| attribution.text.is_some())
+                .unwrap_or(false);
+
+            if !has_text && !invalid.iter().any(|field| field == "attribution.text") {
+                invalid.push(str!("attribution.text"));
+            }
+        }
+
+        match license {
+            Some(license) if invalid.is_empty() => Ok(FileLicensing {
+                license,
+                attribution,
+            }),
+            _ => Err(invalid),
+        }
+    }
+
+    /// Describes the license, such as on file cards.
+    ///
+    /// Custom licenses are described by their terms instead of their name.
+    pub fn describe(&self) -> &str {
+        match (self.license, &self.attribution) {
+            (
+                License::Custom,
+                Some(Attribution {
+                    text: Some(text), ..
+                }),
+            ) => text,
+            (license, _) => license.name(),
+        }
+    }
+}
+
+fn validate_attribution(
+    object: &Map<String, JsonValue>,
+    invalid: &mut Vec<String>,
+) -> Attribution {
+    check_unknown_fields(
+        object,
+        &["author", "source", "text"],
+        "attribution.",
+        invalid,
+    );
+
+    let author = get_string(object, "author", MAX_AUTHOR_LENGTH, invalid);
+    let text = get_string(object, "text", MAX_TEXT_LENGTH, invalid);
+    let source =
+        get_string(object, "source", MAX_SOURCE_LENGTH, invalid).and_then(|source| {
+            if is_valid_url(&source) {
+                Some(source)
+            } else {
+                invalid.push(str!("attribution.source"));
+                None
+            }
+        });
+
+    Attribution {
+        author,
+        source,
+        text,
+    }
+}
+
+/// Gets an optional, non-empty string from the attribution object.
+fn get_string(
+    object: &Map<String, JsonValue>,
+    key: &str,
+    max_length: usize,
+    invalid: &mut Vec<String>,
+) -> Option<String> {
+    match object.get(key) {
+        None | Some(JsonValue::Null) => None,
+        Some(JsonValue::String(value))
+            if !value.trim().is_empty() && value.len() <= max_length =>
+        {
+            Some(value.clone())
+        }
+        Some(_) => {
+            invalid.push(format!("attribution.{key}"));
+            None
+        }
+    }
+}
+
+fn check_unknown_fields(
+    object: &Map<String, JsonValue>,
+    known: &[&str],
+    prefix: &str,
+    invalid: &mut Vec<String>,
+) {
+    for key in object.keys() {
+        if !known.contains(&key.as_str()) {
+            invalid.push(format!("{prefix}{key}"));
+        }
+    }
+}
+
+fn is_valid_url(value: &str) -> bool {
+    let rest = match value.split_once("://") {
+        Some(("http" | "https", rest)) => rest,
+        _ => return false,
+    };
+
+    !rest.is_empty() && !value.chars().any(char::is_whitespace)
+}
+
+#[test]
+fn validate() {
+    use serde_json::json;
+
+    macro_rules! check {
+        ($value:expr, $expected:expr $(,)?) => {{
+            let expected: &[&str] = &$expected;
+            let actual = match FileLicensing::validate(&$value) {
+                Ok(_) => vec![],
+                Err(fields) => fields,
+            };
+
+            assert_eq!(
+                actual, expected,
+                "Unexpected malformed fields for licensing {}",
+                $value,
+            );
+        }};
+    }
+
+    check!(json!({ "license": "cc-by-sa-4.0" }), []);
+    check!(json!({ "license": "cc0-1.0", "attribution": null }), []);
+    check!(
+        json!({
+            "license": "cc-by-4.0",
+            "attribution": {
+                "author": "Jane Doe",
+                "source": "https://example.com/image.png",
+            },
+        }),
+        [],
+    );
+    check!(
+        json!({
+            "license": "custom",
+            "attribution": { "text": "Free for non-commercial use" },
+        }),
+        [],
+    );
+
+    check!(json!(null), ["licensing"]);
+    check!(json!("cc-by-sa-4.0"), ["licensing"]);
+    check!(json!({}), ["license"]);
+    check!(json!({ "license": "gpl" }), ["license"]);
+    check!(json!({ "license": 4 }), ["license"]);
+    check!(json!({ "license": "custom" }), ["attribution.text"]);
+    check!(
+        json!({ "license": "cc-by-4.0", "attribution": "Jane Doe" }),
+        ["attribution"],
+    );
+    check!(
+        json!({ "license": "cc-by-4.0", "author": "Jane Doe" }),
+        ["author"],
+    );
+    check!(
+        json!({
+            "license": "custom",
+            "attribution": {
+                "author": "",
+                "source": "ftp://example.com",
+                "text": 10,
+                "year": 2020,
+            },
+        }),
+        [
+            "attribution.year",
+            "attribution.author",
+            "attribution.text",
+            "attribution.source",
+        ],
+    );
+}
+
+#[test]
+fn describe() {
+    use serde_json::json;
+
+    macro_rules! check {
+        ($value:expr, $expected:expr $(,)?) => {{
+            let licensing =
+                FileLicensing::validate(&$value).expect("Licensing in test is invalid");
+
+            assert_eq!(
+                licensing.describe(),
+                $expected,
+                "Unexpected description for licensing {}",
+                $value,
+            );
+        }};
+    }
+
+    check!(json!({ "license": "cc-by-sa-3.0" }), "CC BY-SA 3.0");
+    check!(json!({ "license": "public-domain" }), "Public domain");
+    check!(
+        json!({
+            "license": "cc-by-4.0",
+            "attribution": { "text": "Photograph by Jane Doe" },
+        }),
+        "CC BY 4.0",
+    );
+    check!(
+        json!({
+            "license": "custom",
+            "attribution": { "text": "Free for non-commercial use" },
+        }),
+        "Free for non-commercial use",
+    );
+}
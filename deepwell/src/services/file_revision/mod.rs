@@ -20,13 +20,18 @@
 
 mod prelude {
     pub use super::super::prelude::*;
+    pub use super::licensing::FileLicensing;
     pub use super::structs::*;
     pub use crate::hash::BlobHash;
-    pub use crate::models::sea_orm_active_enums::FileRevisionType;
+    pub use crate::models::sea_orm_active_enums::{FileRevisionChange, FileRevisionType};
 }
 
+mod licensing;
 mod service;
 mod structs;
+mod tasks;
 
+pub use self::licensing::FileLicensing;
 pub use self::service::FileRevisionService;
 pub use self::structs::*;
+pub use self::tasks::FileRevisionTasks;
@@ -19,27 +19,48 @@
  */
 
 use super::prelude::*;
+use crate::hash::blob_hash_to_hex;
+use crate::models::file::{self, Entity as File};
 use crate::models::file_revision::{
     self, Entity as FileRevision, Model as FileRevisionModel,
 };
-use crate::services::{OutdateService, PageService};
+use crate::models::user::{self, Entity as User};
+use crate::services::audit::CreateAuditEntry;
+use crate::services::event::{Event, EventService};
+use crate::services::file::DeleteFile;
+use crate::services::job::Job;
+use crate::services::page_revision::RevisionSummary;
+use crate::services::revision_comparison::MetadataDelta;
+use crate::services::upload_rule::UploadRuleTarget;
+use crate::services::{
+    AuditService, BlobService, FileService, JobService, LimitService, PageService,
+    SiteService, StorageQuotaService, ThumbnailService, UploadRuleService,
+};
+use crate::utils::format_byte_delta;
 use crate::web::FetchDirection;
-use once_cell::sync::Lazy;
-use std::num::NonZeroI32;
+use sea_query::Expr;
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
+use std::num::NonZeroI64;
+use std::time::Duration;
 
 /// The changes for the first revision.
 /// The first revision is always considered to have changed everything.
 ///
 /// See `services/page_revision/service.rs`.
-static ALL_CHANGES: Lazy<Vec<String>> = Lazy::new(|| {
-    vec![
-        str!("page"),
-        str!("name"),
-        str!("blob"),
-        str!("mime"),
-        str!("licensing"),
-    ]
-});
+const ALL_CHANGES: [FileRevisionChange; 5] = [
+    FileRevisionChange::Page,
+    FileRevisionChange::Name,
+    FileRevisionChange::Blob,
+    FileRevisionChange::Mime,
+    FileRevisionChange::Licensing,
+];
+
+/// How long to wait before generating thumbnails for a new revision.
+///
+/// Revisions are created within a transaction, so this gives it time to commit.
+/// If it still hasn't, the job fails and is retried later.
+const THUMBNAIL_DELAY: Duration = Duration::from_secs(5);
 
 #[derive(Debug)]
 pub struct FileRevisionService;
@@ -66,11 +87,19 @@ impl FileRevisionService {
         let txn = ctx.transaction();
         let revision_number = next_revision_number(&previous, page_id, file_id);
 
+        LimitService::get(ctx, site_id)
+            .await?
+            .check_comment(&comments)?;
+
+        let previous_page_id = page_id;
+        let overwrite = body.overwrite;
+
         // Fields to create in the revision
         let mut changes = Vec::new();
         let FileRevisionModel {
             mut name,
             mut s3_hash,
+            mut original_s3_hash,
             mut mime_hint,
             mut size_hint,
             mut licensing,
@@ -84,14 +113,14 @@ impl FileRevisionService {
 
         if let ProvidedValue::Set(new_page_id) = body.page_id {
             if page_id != new_page_id {
-                changes.push(str!("page"));
+                changes.push(FileRevisionChange::Page);
                 page_id = new_page_id;
             }
         }
 
         if let ProvidedValue::Set(new_name) = body.name {
             if name != new_name {
-                changes.push(str!("name"));
+                changes.push(FileRevisionChange::Name);
                 name = new_name;
             }
         }
@@ -101,8 +130,9 @@ impl FileRevisionService {
                 || size_hint != new_blob.size_hint
                 || mime_hint != new_blob.mime_hint
             {
-                changes.push(str!("blob"));
+                changes.push(FileRevisionChange::Blob);
                 s3_hash = new_blob.s3_hash.to_vec();
+                original_s3_hash = new_blob.original_s3_hash;
                 size_hint = new_blob.size_hint;
                 mime_hint = new_blob.mime_hint;
             }
@@ -110,7 +140,7 @@ impl FileRevisionService {
 
         if let ProvidedValue::Set(new_licensing) = body.licensing {
             if licensing != new_licensing {
-                changes.push(str!("licensing"));
+                changes.push(FileRevisionChange::Licensing);
                 licensing = new_licensing;
             }
         }
@@ -137,13 +167,51 @@ impl FileRevisionService {
             return Err(Error::FileMimeEmpty);
         }
 
-        // TODO validate licensing field
+        // Only check licensing when it changes, older revisions may predate validation
+        if changes.contains(&FileRevisionChange::Licensing) {
+            Self::validate_licensing(&licensing)?;
+        }
 
-        // Run outdater
+        // Ensure the name isn't taken on the page the file ends up on
+        if changes.contains(&FileRevisionChange::Name)
+            || changes.contains(&FileRevisionChange::Page)
+        {
+            Self::check_name_conflict(
+                ctx, site_id, page_id, file_id, user_id, &name, overwrite,
+            )
+            .await?;
+        }
+
+        // Notify subscribers
+        //
+        // If the file moved, then both pages need to be updated.
         let page_slug = Self::get_page_slug(ctx, site_id, page_id).await?;
-        OutdateService::process_page_edit(ctx, site_id, page_id, &page_slug, 0).await?;
+        let event = if changes.contains(&FileRevisionChange::Page) {
+            let old_page_slug =
+                Self::get_page_slug(ctx, site_id, previous_page_id).await?;
+
+            Event::FileMoved {
+                site_id,
+                file_id,
+                old_page_id: previous_page_id,
+                new_page_id: page_id,
+                old_page_slug,
+                new_page_slug: page_slug,
+            }
+        } else {
+            Event::FileEdited {
+                site_id,
+                page_id,
+                file_id,
+                page_slug,
+                changes: changes.clone(),
+            }
+        };
+
+        EventService::emit(ctx, event).await?;
 
         // Insert the new revision into the table
+        let blob_changed = changes.contains(&FileRevisionChange::Blob);
         let model = file_revision::ActiveModel {
             revision_type: Set(FileRevisionType::Update),
             revision_number: Set(0),
@@ -153,6 +221,7 @@ impl FileRevisionService {
             user_id: Set(user_id),
             name: Set(name),
             s3_hash: Set(s3_hash.to_vec()),
+            original_s3_hash: Set(original_s3_hash),
             size_hint: Set(size_hint),
             mime_hint: Set(mime_hint),
             licensing: Set(licensing),
@@ -162,7 +231,17 @@ impl FileRevisionService {
             ..Default::default()
         };
 
-        let FileRevisionModel { revision_id, .. } = model.insert(txn).await?;
+        let FileRevisionModel {
+            revision_id,
+            mime_hint,
+            ..
+        } = model.insert(txn).await?;
+
+        if blob_changed {
+            StorageQuotaService::add(ctx, site_id, size_hint).await?;
+            Self::queue_thumbnails(ctx, site_id, revision_id, &mime_hint).await?;
+        }
+
         Ok(Some(CreateFileRevisionOutput {
             file_revision_id: revision_id,
             file_revision_number: revision_number,
@@ -184,6 +263,7 @@ impl FileRevisionService {
             user_id,
             name,
             s3_hash,
+            original_s3_hash,
             size_hint,
             mime_hint,
             licensing,
@@ -192,10 +272,24 @@ impl FileRevisionService {
     ) -> Result<CreateFirstFileRevisionOutput> {
         let txn = ctx.transaction();
 
-        // Run outdater
+        LimitService::get(ctx, site_id)
+            .await?
+            .check_comment(&comments)?;
+
+        Self::validate_licensing(&licensing)?;
+
+        // Notify subscribers
         let page_slug = Self::get_page_slug(ctx, site_id, page_id).await?;
-        OutdateService::process_page_displace(ctx, site_id, page_id, &page_slug, 0)
-            .await?;
+        EventService::emit(
+            ctx,
+            Event::FileCreated {
+                site_id,
+                page_id,
+                file_id,
+                page_slug,
+            },
+        )
+        .await?;
 
         // Insert the first revision into the table
         let model = file_revision::ActiveModel {
@@ -207,16 +301,25 @@ impl FileRevisionService {
             user_id: Set(user_id),
             name: Set(name),
             s3_hash: Set(s3_hash.to_vec()),
+            original_s3_hash: Set(original_s3_hash),
             mime_hint: Set(mime_hint),
             size_hint: Set(size_hint),
             licensing: Set(licensing),
-            changes: Set(ALL_CHANGES.clone()),
+            changes: Set(ALL_CHANGES.to_vec()),
             comments: Set(comments),
             hidden: Set(vec![]),
             ..Default::default()
         };
 
-        let FileRevisionModel { revision_id, .. } = model.insert(txn).await?;
+        let FileRevisionModel {
+            revision_id,
+            mime_hint,
+            ..
+        } = model.insert(txn).await?;
+
+        StorageQuotaService::add(ctx, site_id, size_hint).await?;
+        Self::queue_thumbnails(ctx, site_id, revision_id, &mime_hint).await?;
+
         Ok(CreateFirstFileRevisionOutput {
             file_id,
             file_revision_id: revision_id,
@@ -246,18 +349,32 @@ impl FileRevisionService {
         let txn = ctx.transaction();
         let revision_number = next_revision_number(&previous, page_id, file_id);
 
+        LimitService::get(ctx, site_id)
+            .await?
+            .check_comment(&comments)?;
+
         let FileRevisionModel {
             name,
             s3_hash,
+            original_s3_hash,
             mime_hint,
             size_hint,
             licensing,
             ..
         } = previous;
 
-        // Run outdater
+        // Notify subscribers
         let page_slug = Self::get_page_slug(ctx, site_id, page_id).await?;
-        OutdateService::process_page_edit(ctx, site_id, page_id, &page_slug, 0).await?;
+        EventService::emit(
+            ctx,
+            Event::FileDeleted {
+                site_id,
+                page_id,
+                file_id,
+                page_slug,
+            },
+        )
+        .await?;
 
         // Insert the tombstone revision into the table
         let model = file_revision::ActiveModel {
@@ -269,6 +386,7 @@ impl FileRevisionService {
             user_id: Set(user_id),
             name: Set(name),
             s3_hash: Set(s3_hash),
+            original_s3_hash: Set(original_s3_hash),
             mime_hint: Set(mime_hint),
             size_hint: Set(size_hint),
             licensing: Set(licensing),
@@ -318,9 +436,14 @@ impl FileRevisionService {
         let txn = ctx.transaction();
         let revision_number = next_revision_number(&previous, old_page_id, file_id);
 
+        LimitService::get(ctx, site_id)
+            .await?
+            .check_comment(&comments)?;
+
         let FileRevisionModel {
             name: old_name,
             s3_hash,
+            original_s3_hash,
             mime_hint,
             size_hint,
             licensing,
@@ -331,20 +454,28 @@ impl FileRevisionService {
             let mut changes = vec![];
 
             if old_page_id != new_page_id {
-                changes.push(str!("page"));
+                changes.push(FileRevisionChange::Page);
             }
 
             if old_name != new_name {
-                changes.push(str!("name"));
+                changes.push(FileRevisionChange::Name);
             }
 
             changes
         };
 
-        // Run outdater
-        let new_page_slug = Self::get_page_slug(ctx, site_id, new_page_id).await?;
-        OutdateService::process_page_edit(ctx, site_id, new_page_id, &new_page_slug, 0)
-            .await?;
+        // Notify subscribers
+        let page_slug = Self::get_page_slug(ctx, site_id, new_page_id).await?;
+        EventService::emit(
+            ctx,
+            Event::FileRestored {
+                site_id,
+                page_id: new_page_id,
+                file_id,
+                page_slug,
+            },
+        )
+        .await?;
 
         // Insert the resurrection revision into the table
         let model = file_revision::ActiveModel {
@@ -356,6 +487,7 @@ impl FileRevisionService {
             user_id: Set(user_id),
             name: Set(new_name),
             s3_hash: Set(s3_hash),
+            original_s3_hash: Set(original_s3_hash),
             mime_hint: Set(mime_hint),
             size_hint: Set(size_hint),
             licensing: Set(licensing),
@@ -386,6 +518,7 @@ impl FileRevisionService {
             revision_id,
             user_id,
             hidden,
+            reason,
         }: UpdateFileRevision,
     ) -> Result<FileRevisionModel> {
         // The latest file revision cannot be hidden, because
@@ -399,8 +532,24 @@ impl FileRevisionService {
             return Err(Error::CannotHideLatestRevision);
         }
 
-        // TODO: record revision edit in audit log
-        let _ = user_id;
+        let file = FileService::get_direct(ctx, file_id, true).await?;
+        if file.stable_revision_id == Some(revision_id) {
+            warn!("Attempting to edit stable revision, denying request");
+            return Err(Error::CannotHideStableRevision);
+        }
+
+        let revision = FileRevision::find()
+            .filter(
+                Condition::all()
+                    .add(file_revision::Column::RevisionId.eq(revision_id))
+                    .add(file_revision::Column::SiteId.eq(site_id))
+                    .add(file_revision::Column::FileId.eq(file_id)),
+            )
+            .one(txn)
+            .await?
+            .ok_or(Error::FileRevisionNotFound)?;
+
+        Self::record_hide(ctx, user_id, &revision, &hidden, &reason).await?;
 
         // Update the revision
 
@@ -415,6 +564,358 @@ impl FileRevisionService {
         Ok(revision)
     }
 
+    /// Modifies several revisions of the same file at once.
+    ///
+    /// This sets the `hidden` column of each revision, with the same
+    /// restrictions as `update()`. If any revision cannot be modified,
+    /// then none of them are.
+    pub async fn update_many(
+        ctx: &ServiceContext<'_>,
+        UpdateFileRevisions {
+            site_id,
+            page_id,
+            file_id,
+            revision_ids,
+            user_id,
+            hidden,
+            reason,
+        }: UpdateFileRevisions,
+    ) -> Result<Vec<FileRevisionModel>> {
+        info!(
+            "Editing {} revisions for file ID {file_id}",
+            revision_ids.len(),
+        );
+
+        let txn = ctx.transaction();
+
+        // Lock the file for the rest of the transaction, so that no new
+        // revisions are made while checking which one is the latest.
+        let file = File::find_by_id(file_id)
+            .lock_exclusive()
+            .one(txn)
+            .await?
+            .ok_or(Error::FileNotFound)?;
+
+        if file.site_id != site_id || file.page_id != page_id {
+            warn!("File's site or page ID and passed IDs do not match");
+            return Err(Error::FileNotFound);
+        }
+
+        let revision_ids = revision_ids.into_iter().collect::<HashSet<_>>();
+        let revisions = FileRevision::find()
+            .filter(
+                Condition::all()
+                    .add(file_revision::Column::FileId.eq(file_id))
+                    .add(file_revision::Column::RevisionId.is_in(revision_ids.clone())),
+            )
+            .all(txn)
+            .await?;
+
+        if revisions.len() != revision_ids.len() {
+            warn!("Not all revision IDs belong to this file, denying request");
+            return Err(Error::FileRevisionNotFound);
+        }
+
+        // See update() for why these revisions can't be hidden
+        let latest = Self::get_latest(ctx, site_id, page_id, file_id).await?;
+        if revision_ids.contains(&latest.revision_id) {
+            warn!("Attempting to edit latest revision, denying request");
+            return Err(Error::CannotHideLatestRevision);
+        }
+
+        if let Some(stable_revision_id) = file.stable_revision_id {
+            if revision_ids.contains(&stable_revision_id) {
+                warn!("Attempting to edit stable revision, denying request");
+                return Err(Error::CannotHideStableRevision);
+            }
+        }
+
+        for revision in &revisions {
+            Self::record_hide(ctx, user_id, revision, &hidden, &reason).await?;
+        }
+
+        // Update the revisions, all in one statement
+        FileRevision::update_many()
+            .col_expr(file_revision::Column::Hidden, Expr::value(hidden.clone()))
+            .filter(file_revision::Column::RevisionId.is_in(revision_ids))
+            .exec(txn)
+            .await?;
+
+        let revisions = revisions
+            .into_iter()
+            .map(|revision| FileRevisionModel {
+                hidden: hidden.clone(),
+                ..revision
+            })
+            .collect();
+
+        Ok(revisions)
+    }
+
+    /// Permanently removes the contents of a hidden revision from storage.
+    ///
+    /// This is meant for legal takedowns, where hiding is not sufficient.
+    /// Blobs are shared by content, so every revision and avatar using the
+    /// same blob is changed, either to reference the tombstone blob or to
+    /// have no avatar. Only then is the original blob deleted.
+    ///
+    /// The space the blob took up is released from each site's storage quota.
+    pub async fn purge_blob(
+        ctx: &ServiceContext<'_>,
+        PurgeFileRevisionBlob {
+            site_id,
+            page_id,
+            file_id,
+            revision_id,
+            user_id,
+            reason,
+        }: PurgeFileRevisionBlob,
+    ) -> Result<PurgeFileRevisionBlobOutput> {
+        info!("Purging blob for file revision ID {revision_id} in file ID {file_id}");
+
+        let txn = ctx.transaction();
+        let revision = FileRevision::find()
+            .filter(
+                Condition::all()
+                    .add(file_revision::Column::RevisionId.eq(revision_id))
+                    .add(file_revision::Column::SiteId.eq(site_id))
+                    .add(file_revision::Column::PageId.eq(page_id))
+                    .add(file_revision::Column::FileId.eq(file_id)),
+            )
+            .one(txn)
+            .await?
+            .ok_or(Error::FileRevisionNotFound)?;
+
+        // Visible revisions must be hidden first, which also
+        // guarantees this is not the latest or stable revision.
+        if revision.hidden.is_empty() {
+            warn!("Attempting to purge a visible revision, denying request");
+            return Err(Error::CannotPurgeVisibleRevision);
+        }
+
+        if !BlobService::is_purgeable(&revision.s3_hash) {
+            debug!("Revision's blob has no contents to purge, nothing to do");
+            return Ok(PurgeFileRevisionBlobOutput {
+                revisions_updated: 0,
+                avatars_cleared: 0,
+            });
+        }
+
+        // Release the storage each site was using for this blob
+        let mut released = HashMap::new();
+        let stored = FileRevision::find()
+            .filter(file_revision::Column::S3Hash.eq(revision.s3_hash.clone()))
+            .all(txn)
+            .await?;
+
+        for stored in stored {
+            if StorageQuotaService::is_counted(&stored) {
+                *released.entry(stored.site_id).or_insert(0) += stored.size_hint;
+            }
+        }
+
+        for (site_id, bytes) in released {
+            StorageQuotaService::remove(ctx, site_id, bytes).await?;
+        }
+
+        // Point everything using this blob elsewhere
+        let tombstone = BlobService::create_tombstone(ctx).await?;
+        let revisions_updated = FileRevision::update_many()
+            .col_expr(
+                file_revision::Column::S3Hash,
+                Expr::value(tombstone.hash.to_vec()),
+            )
+            .col_expr(file_revision::Column::MimeHint, Expr::value(tombstone.mime))
+            .col_expr(file_revision::Column::SizeHint, Expr::value(tombstone.size))
+            .filter(file_revision::Column::S3Hash.eq(revision.s3_hash.clone()))
+            .exec(txn)
+            .await?
+            .rows_affected;
+
+        let avatars_cleared = User::update_many()
+            .col_expr(
+                user::Column::AvatarS3Hash,
+                Expr::value(Option::<Vec<u8>>::None),
+            )
+            .filter(user::Column::AvatarS3Hash.eq(revision.s3_hash.clone()))
+            .exec(txn)
+            .await?
+            .rows_affected;
+
+        AuditService::record(
+            ctx,
+            CreateAuditEntry {
+                actor_id: user_id,
+                subject_id: revision.user_id,
+                site_id: Some(site_id),
+                action: "file.revision-purge",
+                data: json!({
+                    "page_id": page_id,
+                    "file_id": file_id,
+                    "revision_id": revision_id,
+                    "revision_number": revision.revision_number,
+                    "s3_hash": blob_hash_to_hex(&revision.s3_hash).as_str(),
+                    "revisions_updated": revisions_updated,
+                    "avatars_cleared": avatars_cleared,
+                    "reason": reason,
+                }),
+            },
+        )
+        .await?;
+
+        // Delete last, so a storage failure rolls back the above
+        ThumbnailService::delete_for_blob(ctx, &revision.s3_hash).await?;
+        BlobService::hard_delete(ctx, &revision.s3_hash).await?;
+
+        Ok(PurgeFileRevisionBlobOutput {
+            revisions_updated,
+            avatars_cleared,
+        })
+    }
+
+    /// Rolls back a file to be the same as it was in a previous revision.
+    ///
+    /// This creates a new revision copying the name, blob, and licensing
+    /// of the target revision, leaving any other changes since in history.
+    /// Revisions with hidden fields can't be reverted to, since that would
+    /// expose their contents again. They must be restored instead.
+    ///
+    /// See `PageService::rollback()`.
+    pub async fn revert(
+        ctx: &ServiceContext<'_>,
+        RevertFileRevision {
+            site_id,
+            page_id,
+            file_id,
+            revision_number,
+            user_id,
+            revision_comments,
+        }: RevertFileRevision,
+    ) -> Result<Option<CreateFileRevisionOutput>> {
+        info!("Reverting file ID {file_id} to revision {revision_number}");
+
+        let txn = ctx.transaction();
+        let file = FileService::get_direct(ctx, file_id, false).await?;
+        if file.page_id != page_id {
+            warn!("File's page ID and passed page ID do not match");
+            return Err(Error::FileNotFound);
+        }
+
+        // Get target revision and latest revision
+        let (target, last_revision) = try_join!(
+            Self::get(
+                ctx,
+                GetFileRevision {
+                    site_id,
+                    page_id,
+                    file_id,
+                    revision_number,
+                },
+            ),
+            Self::get_latest(ctx, site_id, page_id, file_id),
+        )?;
+
+        if !target.hidden.is_empty() {
+            warn!("Attempting to revert to a hidden revision, denying request");
+            return Err(Error::CannotRevertHiddenRevision);
+        }
+
+        // Upload rules may have changed since, so check again.
+        // Whether the name has been taken is checked when creating the revision.
+        UploadRuleService::check(
+            ctx,
+            UploadRuleTarget {
+                site_id,
+                user_id,
+                mime: &target.mime_hint,
+                size: target.size_hint as u64,
+            },
+        )
+        .await?;
+
+        // Create new revision
+        //
+        // Copy the body of the target revision
+        let s3_hash = target
+            .s3_hash
+            .as_slice()
+            .try_into()
+            .expect("Stored blob hash is the wrong length");
+
+        let revision_output = Self::create(
+            ctx,
+            CreateFileRevision {
+                site_id,
+                page_id,
+                file_id,
+                user_id,
+                comments: revision_comments,
+                body: CreateFileRevisionBody {
+                    name: ProvidedValue::Set(target.name.clone()),
+                    blob: ProvidedValue::Set(FileBlob {
+                        s3_hash,
+                        size_hint: target.size_hint,
+                        mime_hint: target.mime_hint,
+                        original_s3_hash: target.original_s3_hash,
+                    }),
+                    licensing: ProvidedValue::Set(target.licensing),
+                    ..Default::default()
+                },
+            },
+            last_revision,
+        )
+        .await?;
+
+        // Update file metadata
+        if revision_output.is_some() {
+            let model = file::ActiveModel {
+                file_id: Set(file_id),
+                updated_at: Set(Some(now())),
+                name: Set(target.name),
+                ..Default::default()
+            };
+            model.update(txn).await?;
+        }
+
+        Ok(revision_output)
+    }
+
+    /// Clears the hidden fields of a file revision.
+    ///
+    /// Returns the revision as it was before being unhidden,
+    /// so callers can see which fields were hidden.
+    pub async fn unhide(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        page_id: i64,
+        file_id: i64,
+        revision_id: i64,
+    ) -> Result<FileRevisionModel> {
+        let txn = ctx.transaction();
+        let revision = FileRevision::find()
+            .filter(
+                Condition::all()
+                    .add(file_revision::Column::RevisionId.eq(revision_id))
+                    .add(file_revision::Column::SiteId.eq(site_id))
+                    .add(file_revision::Column::PageId.eq(page_id))
+                    .add(file_revision::Column::FileId.eq(file_id)),
+            )
+            .one(txn)
+            .await?
+            .ok_or(Error::FileRevisionNotFound)?;
+
+        if !revision.hidden.is_empty() {
+            let model = file_revision::ActiveModel {
+                revision_id: Set(revision_id),
+                hidden: Set(vec![]),
+                ..Default::default()
+            };
+            model.update(txn).await?;
+        }
+
+        Ok(revision)
+    }
+
     /// Get the latest revision for this file.
     ///
     /// See `RevisionService::get_latest()`.
@@ -474,7 +975,6 @@ impl FileRevisionService {
     ///
     /// See `RevisionService::get()`.
     #[inline]
-    #[allow(dead_code)]
     pub async fn get(
         ctx: &ServiceContext<'_>,
         input: GetFileRevision,
@@ -482,6 +982,88 @@ impl FileRevisionService {
         find_or_error!(Self::get_optional(ctx, input), FileRevision)
     }
 
+    /// Compares the metadata of two revisions of a file.
+    ///
+    /// Files can be moved between pages, so the revisions are looked up
+    /// by file alone, and the page they were on is one of the compared fields.
+    pub async fn diff(
+        ctx: &ServiceContext<'_>,
+        GetFileRevisionDiff {
+            site_id,
+            file_id,
+            from_revision_number,
+            to_revision_number,
+        }: GetFileRevisionDiff,
+    ) -> Result<FileRevisionDiff> {
+        let (from, to) = try_join!(
+            Self::get_any_page(ctx, site_id, file_id, from_revision_number),
+            Self::get_any_page(ctx, site_id, file_id, to_revision_number),
+        )?;
+
+        let is_hidden = |field: &str| {
+            from.hidden.iter().any(|hidden| hidden == field)
+                || to.hidden.iter().any(|hidden| hidden == field)
+        };
+
+        let mut changes = Vec::new();
+
+        macro_rules! compare {
+            ($hidden:expr, $name:expr, $from:expr, $to:expr $(,)?) => {
+                if !is_hidden($hidden) && $from != $to {
+                    changes.push(MetadataDelta {
+                        field: $name,
+                        from: json!($from),
+                        to: json!($to),
+                    });
+                }
+            };
+        }
+
+        compare!("name", "name", from.name, to.name);
+        compare!("page", "page_id", from.page_id, to.page_id);
+        compare!(
+            "blob",
+            "blob_hash",
+            blob_hash_to_hex(&from.s3_hash).as_str(),
+            blob_hash_to_hex(&to.s3_hash).as_str(),
+        );
+        compare!("blob", "size", from.size_hint, to.size_hint);
+        compare!("mime", "mime", from.mime_hint, to.mime_hint);
+        compare!("licensing", "licensing", from.licensing, to.licensing);
+
+        Ok(FileRevisionDiff {
+            site_id,
+            file_id,
+            from_revision_id: from.revision_id,
+            from_revision_number,
+            to_revision_id: to.revision_id,
+            to_revision_number,
+            changes,
+        })
+    }
+
+    /// Gets a revision for a file, regardless of which page it was on at the time.
+    async fn get_any_page(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        file_id: i64,
+        revision_number: i64,
+    ) -> Result<FileRevisionModel> {
+        let txn = ctx.transaction();
+        let revision = FileRevision::find()
+            .filter(
+                Condition::all()
+                    .add(file_revision::Column::SiteId.eq(site_id))
+                    .add(file_revision::Column::FileId.eq(file_id))
+                    .add(file_revision::Column::RevisionNumber.eq(revision_number)),
+            )
+            .one(txn)
+            .await?
+            .ok_or(Error::FileRevisionNotFound)?;
+
+        Ok(revision)
+    }
+
     /// Counts the number of revisions for a file.
     ///
     /// See `RevisionService::count()`.
@@ -489,7 +1071,7 @@ impl FileRevisionService {
         ctx: &ServiceContext<'_>,
         page_id: i64,
         file_id: i64,
-    ) -> Result<NonZeroI32> {
+    ) -> Result<NonZeroI64> {
         let txn = ctx.transaction();
         let row_count = FileRevision::find()
             .filter(
@@ -500,15 +1082,17 @@ impl FileRevisionService {
             .count(txn)
             .await?;
 
-        // We store revision_number in INT, which is i32.
-        // So even though this row count is usize, it
-        // should always fit inside an i32.
-        let row_count = i32::try_from(row_count)
-            .expect("Revision row count greater than revision_number integer size");
+        // We store revision_number in BIGINT, which is i64.
+        // No table will realistically have more rows than that,
+        // but report an error rather than panicking if one does.
+        let row_count = i64::try_from(row_count).map_err(|_| {
+            error!("Revision row count {row_count} is greater than i64::MAX");
+            Error::RevisionCountOverflow
+        })?;
 
         // All pages have at least one revision, so if there are none
         // that means this page does not exist, and we should return an error.
-        match NonZeroI32::new(row_count) {
+        match NonZeroI64::new(row_count) {
             Some(count) => Ok(count),
             None => Err(Error::FileNotFound),
         }
@@ -525,6 +1109,10 @@ impl FileRevisionService {
             revision_number,
             revision_direction,
             limit,
+            user_id,
+            revision_type,
+            created_after,
+            created_before,
         }: GetFileRevisionRange,
     ) -> Result<Vec<FileRevisionModel>> {
         let revision_condition = {
@@ -535,7 +1123,7 @@ impl FileRevisionService {
             let revision_number = if revision_number >= 0 {
                 revision_number
             } else {
-                i32::MAX
+                i64::MAX
             };
 
             // Get correct database condition based on requested ordering
@@ -545,14 +1133,35 @@ impl FileRevisionService {
             }
         };
 
+        // Filters are only applied if specified.
+        // Like revision numbers, the date bounds are inclusive.
+        let mut condition = Condition::all()
+            .add(file_revision::Column::PageId.eq(page_id))
+            .add(file_revision::Column::FileId.eq(file_id))
+            .add(revision_condition);
+
+        if let Some(user_id) = user_id {
+            condition = condition.add(file_revision::Column::UserId.eq(user_id));
+        }
+
+        if let Some(revision_type) = revision_type {
+            condition =
+                condition.add(file_revision::Column::RevisionType.eq(revision_type));
+        }
+
+        if let Some(created_after) = created_after {
+            condition =
+                condition.add(file_revision::Column::CreatedAt.gte(created_after));
+        }
+
+        if let Some(created_before) = created_before {
+            condition =
+                condition.add(file_revision::Column::CreatedAt.lte(created_before));
+        }
+
         let txn = ctx.transaction();
         let revisions = FileRevision::find()
-            .filter(
-                Condition::all()
-                    .add(file_revision::Column::PageId.eq(page_id))
-                    .add(file_revision::Column::FileId.eq(file_id))
-                    .add(revision_condition),
-            )
+            .filter(condition)
             .order_by_asc(file_revision::Column::RevisionNumber)
             .limit(limit)
             .all(txn)
@@ -561,6 +1170,69 @@ impl FileRevisionService {
         Ok(revisions)
     }
 
+    /// Describes what a file revision changed compared to the previous one.
+    ///
+    /// See `PageRevisionService::summarize()`.
+    pub async fn summarize(
+        ctx: &ServiceContext<'_>,
+        revision: &FileRevisionModel,
+    ) -> Result<RevisionSummary> {
+        // Files can be moved between pages, so don't filter by page ID here.
+        let previous = match revision.revision_number {
+            0 => None,
+            number => {
+                let txn = ctx.transaction();
+                FileRevision::find()
+                    .filter(
+                        Condition::all()
+                            .add(file_revision::Column::FileId.eq(revision.file_id))
+                            .add(file_revision::Column::RevisionNumber.eq(number - 1)),
+                    )
+                    .one(txn)
+                    .await?
+            }
+        };
+
+        let size_delta = match previous {
+            Some(ref previous) => revision.size_hint - previous.size_hint,
+            None => revision.size_hint,
+        };
+
+        let with_size = |description: &str| {
+            format!("{description} ({})", format_byte_delta(size_delta))
+        };
+
+        let mut parts = Vec::new();
+        match revision.revision_type {
+            FileRevisionType::Create => parts.push(with_size("uploaded file")),
+            FileRevisionType::Delete => parts.push(str!("deleted file")),
+            FileRevisionType::Undelete => parts.push(str!("restored file")),
+            FileRevisionType::Update => (),
+        }
+
+        if let Some(ref previous) = previous {
+            for change in &revision.changes {
+                let part = match change {
+                    FileRevisionChange::Page => str!("moved to another page"),
+                    FileRevisionChange::Name => {
+                        format!("renamed from \"{}\"", previous.name)
+                    }
+                    FileRevisionChange::Blob => with_size("replaced contents"),
+                    FileRevisionChange::Mime => str!("changed file type"),
+                    FileRevisionChange::Licensing => str!("changed licensing"),
+                };
+
+                parts.push(part);
+            }
+        }
+
+        let summary = parts.join(", ");
+        Ok(RevisionSummary {
+            size_delta: Some(size_delta),
+            summary,
+        })
+    }
+
     async fn get_page_slug(
         ctx: &ServiceContext<'_>,
         site_id: i64,
@@ -569,9 +1241,145 @@ impl FileRevisionService {
         let page = PageService::get(ctx, site_id, Reference::Id(page_id)).await?;
         Ok(page.slug)
     }
+
+    /// Ensures no other file on the page has the given name.
+    ///
+    /// If `overwrite` is set, the other file is deleted instead of failing.
+    #[allow(clippy::too_many_arguments)]
+    async fn check_name_conflict(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        page_id: i64,
+        file_id: i64,
+        user_id: i64,
+        name: &str,
+        overwrite: bool,
+    ) -> Result<()> {
+        let txn = ctx.transaction();
+        let other = File::find()
+            .filter(
+                Condition::all()
+                    .add(file::Column::PageId.eq(page_id))
+                    .add(file::Column::Name.eq(name))
+                    .add(file::Column::FileId.ne(file_id))
+                    .add(file::Column::DeletedAt.is_null()),
+            )
+            .one(txn)
+            .await?;
+
+        let other = match other {
+            None => return Ok(()),
+            Some(other) => other,
+        };
+
+        if !overwrite {
+            error!(
+                "File ID {} on page ID {} is already named '{}', cannot rename file ID {}",
+                other.file_id, page_id, name, file_id,
+            );
+            return Err(Error::FileNameConflict);
+        }
+
+        info!(
+            "Deleting file ID {} on page ID {}, it is being overwritten by file ID {}",
+            other.file_id, page_id, file_id,
+        );
+
+        FileService::delete(
+            ctx,
+            DeleteFile {
+                revision_comments: format!("Overwritten by file ID {file_id}"),
+                site_id,
+                page_id,
+                file: Reference::Id(other.file_id),
+                user_id,
+            },
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Queues thumbnail generation for a revision with a new blob, if it's an image.
+    ///
+    /// The same job converts the image, if the site has a conversion policy.
+    async fn queue_thumbnails(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        revision_id: i64,
+        mime_hint: &str,
+    ) -> Result<()> {
+        if !ThumbnailService::supports(mime_hint) {
+            return Ok(());
+        }
+
+        if ctx.config().thumbnail_sizes.is_empty() {
+            let site = SiteService::get(ctx, Reference::Id(site_id)).await?;
+            if site.image_conversion.is_none() {
+                return Ok(());
+            }
+        }
+
+        debug!("Queuing thumbnail generation for file revision ID {revision_id}");
+        JobService::queue_job(
+            ctx,
+            &Job::GenerateThumbnails { revision_id },
+            Some(THUMBNAIL_DELAY),
+        )
+        .await
+    }
+
+    /// Records a change to which fields of a revision are hidden in the audit log.
+    async fn record_hide(
+        ctx: &ServiceContext<'_>,
+        user_id: i64,
+        revision: &FileRevisionModel,
+        hidden: &[String],
+        reason: &str,
+    ) -> Result<()> {
+        AuditService::record(
+            ctx,
+            CreateAuditEntry {
+                actor_id: user_id,
+                subject_id: revision.user_id,
+                site_id: Some(revision.site_id),
+                action: if hidden.is_empty() {
+                    "file.revision-unhide"
+                } else {
+                    "file.revision-hide"
+                },
+                data: json!({
+                    "page_id": revision.page_id,
+                    "file_id": revision.file_id,
+                    "revision_id": revision.revision_id,
+                    "revision_number": revision.revision_number,
+                    "hidden": hidden,
+                    "previously_hidden": revision.hidden,
+                    "reason": reason,
+                }),
+            },
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Checks the licensing submitted for a file revision.
+    ///
+    /// This is exposed so that callers can reject bad licensing before
+    /// doing any expensive or irreversible work, such as uploading to S3.
+    pub fn validate_licensing(licensing: &serde_json::Value) -> Result<()> {
+        match FileLicensing::validate(licensing) {
+            Ok(_) => Ok(()),
+            Err(fields) => {
+                error!("File licensing has malformed fields: {fields:?}");
+                Err(Error::FileLicensingInvalid { fields })
+            }
+        }
+    }
 }
 
-fn next_revision_number(previous: &FileRevisionModel, page_id: i64, file_id: i64) -> i32 {
+fn next_revision_number(previous: &FileRevisionModel, page_id: i64, file_id: i64) -> i64 {
     // Check for basic consistency
     assert_eq!(
         previous.file_id, file_id,
@@ -20,8 +20,11 @@
 
 use super::prelude::*;
 use crate::hash::BlobHash;
-use crate::services::page_revision::PageRevisionCountOutput;
+use crate::models::file_revision::Model as FileRevisionModel;
+use crate::services::page_revision::{PageRevisionCountOutput, RevisionSummary};
+use crate::services::revision_comparison::MetadataDelta;
 use crate::web::FetchDirection;
+use time::OffsetDateTime;
 
 #[derive(Debug, Clone)]
 pub struct CreateFileRevision {
@@ -39,6 +42,12 @@ pub struct CreateFileRevisionBody {
     pub name: ProvidedValue<String>,
     pub blob: ProvidedValue<FileBlob>,
     pub licensing: ProvidedValue<serde_json::Value>,
+
+    /// Whether to delete any other file on the page which has the new name.
+    ///
+    /// If unset, then renaming or moving onto a name which is already
+    /// taken fails with `Error::FileNameConflict`.
+    pub overwrite: bool,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -46,12 +55,15 @@ pub struct FileBlob {
     pub s3_hash: BlobHash,
     pub size_hint: i64,
     pub mime_hint: String,
+
+    /// If the upload was sanitized or had metadata stripped, the hash of the data as uploaded.
+    pub original_s3_hash: Option<Vec<u8>>,
 }
 
 #[derive(Serialize, Debug, Clone, Default)]
 pub struct CreateFileRevisionOutput {
     pub file_revision_id: i64,
-    pub file_revision_number: i32,
+    pub file_revision_number: i64,
 }
 
 #[derive(Debug, Clone)]
@@ -62,6 +74,7 @@ pub struct CreateFirstFileRevision {
     pub user_id: i64,
     pub name: String,
     pub s3_hash: BlobHash,
+    pub original_s3_hash: Option<Vec<u8>>,
     pub size_hint: i64,
     pub mime_hint: String,
     pub licensing: serde_json::Value,
@@ -99,7 +112,7 @@ pub struct GetFileRevision {
     pub site_id: i64,
     pub page_id: i64,
     pub file_id: i64,
-    pub revision_number: i32,
+    pub revision_number: i64,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -110,15 +123,105 @@ pub struct UpdateFileRevision {
     pub revision_id: i64,
     pub user_id: i64,
     pub hidden: Vec<String>,
+
+    /// Why the revision is being changed, for the audit log.
+    #[serde(default)]
+    pub reason: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct UpdateFileRevisions {
+    pub site_id: i64,
+    pub page_id: i64,
+    pub file_id: i64,
+    pub revision_ids: Vec<i64>,
+    pub user_id: i64,
+    pub hidden: Vec<String>,
+
+    #[serde(default)]
+    pub reason: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct PurgeFileRevisionBlob {
+    pub site_id: i64,
+    pub page_id: i64,
+    pub file_id: i64,
+    pub revision_id: i64,
+    pub user_id: i64,
+
+    /// Why the blob is being purged, for the audit log.
+    #[serde(default)]
+    pub reason: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct PurgeFileRevisionBlobOutput {
+    pub revisions_updated: u64,
+    pub avatars_cleared: u64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct RevertFileRevision {
+    pub site_id: i64,
+    pub page_id: i64,
+    pub file_id: i64,
+    pub revision_number: i64,
+    pub user_id: i64,
+    pub revision_comments: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetFileRevisionDiff {
+    pub site_id: i64,
+    pub file_id: i64,
+    pub from_revision_number: i64,
+    pub to_revision_number: i64,
+}
+
+/// The metadata fields which differ between two revisions of a file.
+#[derive(Serialize, Debug, Clone)]
+pub struct FileRevisionDiff {
+    pub site_id: i64,
+    pub file_id: i64,
+    pub from_revision_id: i64,
+    pub from_revision_number: i64,
+    pub to_revision_id: i64,
+    pub to_revision_number: i64,
+
+    /// Fields hidden on either revision are never included.
+    pub changes: Vec<MetadataDelta>,
 }
 
 #[derive(Deserialize, Debug, Clone)]
 pub struct GetFileRevisionRange {
     pub page_id: i64,
     pub file_id: i64,
-    pub revision_number: i32,
+    pub revision_number: i64,
     pub revision_direction: FetchDirection,
     pub limit: u64,
+
+    // Optional filters, to narrow down the file's history
+    #[serde(default)]
+    pub user_id: Option<i64>,
+
+    #[serde(default)]
+    pub revision_type: Option<FileRevisionType>,
+
+    #[serde(default)]
+    pub created_after: Option<OffsetDateTime>,
+
+    #[serde(default)]
+    pub created_before: Option<OffsetDateTime>,
 }
 
 pub type FileRevisionCountOutput = PageRevisionCountOutput;
+
+#[derive(Serialize, Debug, Clone)]
+pub struct SummarizedFileRevision {
+    #[serde(flatten)]
+    pub revision: FileRevisionModel,
+
+    #[serde(flatten)]
+    pub summary: RevisionSummary,
+}
@@ -0,0 +1,55 @@
+/*
+ * services/file_revision/tasks.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::models::sea_orm_active_enums::FileRevisionChange;
+
+/// A representation of the updating tasks to do for a file revision.
+///
+/// This is the file counterpart to `PageRevisionTasks`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct FileRevisionTasks {
+    pub rerender_embedding_pages: bool,
+}
+
+impl FileRevisionTasks {
+    /// Determine what tasks need to be performed based on the found changes.
+    pub fn determine(changes: &[FileRevisionChange]) -> Self {
+        let mut tasks = FileRevisionTasks::default();
+
+        for change in changes {
+            match change {
+                // These affect how the file is rendered where it is used,
+                // or whether a reference to it still resolves at all.
+                FileRevisionChange::Blob
+                | FileRevisionChange::Mime
+                | FileRevisionChange::Name
+                | FileRevisionChange::Page => {
+                    tasks.rerender_embedding_pages = true;
+                }
+
+                // Licensing is only shown on the file's own info,
+                // so no page output depends on it.
+                FileRevisionChange::Licensing => (),
+            }
+        }
+
+        tasks
+    }
+}
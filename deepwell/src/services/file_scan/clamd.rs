@@ -0,0 +1,93 @@
+/*
+ * services/file_scan/clamd.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Scanning via ClamAV's daemon, using its `INSTREAM` command.
+//!
+//! The file is streamed as chunks, each prefixed with its length as a
+//! big-endian 32-bit integer, followed by a zero-length chunk. The daemon
+//! then replies with one line, such as `stream: OK`.
+//!
+//! See `clamd(8)` for the protocol.
+
+use super::prelude::*;
+use std::io;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time;
+
+/// Size of each chunk of the file sent to the daemon.
+///
+/// This must be below the daemon's `StreamMaxLength`.
+const CHUNK_SIZE: usize = 65536;
+
+pub async fn scan(config: &Config, data: &[u8]) -> Result<ScanOutcome> {
+    let address = &config.file_scan_clamd_address;
+    debug!("Sending {} bytes to clamd at {address}", data.len());
+
+    let reply = match time::timeout(config.file_scan_timeout, send(address, data)).await {
+        Ok(Ok(reply)) => reply,
+        Ok(Err(error)) => {
+            error!("Unable to scan file with clamd: {error}");
+            return Err(Error::FileScanUnavailable);
+        }
+        Err(_) => {
+            error!("Timed out waiting for clamd to scan file");
+            return Err(Error::FileScanUnavailable);
+        }
+    };
+
+    parse_reply(&reply).ok_or_else(|| {
+        error!("Unexpected reply from clamd: {reply:?}");
+        Error::FileScanUnavailable
+    })
+}
+
+async fn send(address: &str, data: &[u8]) -> io::Result<String> {
+    let mut stream = TcpStream::connect(address).await?;
+
+    // The "z" prefix means commands and replies are null-terminated
+    stream.write_all(b"zINSTREAM\0").await?;
+    for chunk in data.chunks(CHUNK_SIZE) {
+        let length = u32::try_from(chunk.len()).expect("Chunk size does not fit in u32");
+        stream.write_all(&length.to_be_bytes()).await?;
+        stream.write_all(chunk).await?;
+    }
+    stream.write_all(&0u32.to_be_bytes()).await?;
+    stream.flush().await?;
+
+    let mut reply = Vec::new();
+    stream.read_to_end(&mut reply).await?;
+    Ok(String::from_utf8_lossy(&reply).into_owned())
+}
+
+fn parse_reply(reply: &str) -> Option<ScanOutcome> {
+    let result = reply
+        .trim_end_matches('\0')
+        .trim_end()
+        .strip_prefix("stream: ")?;
+
+    if result == "OK" {
+        return Some(ScanOutcome::Clean);
+    }
+
+    result
+        .strip_suffix(" FOUND")
+        .map(|signature| ScanOutcome::Infected(str!(signature)))
+}
@@ -0,0 +1,44 @@
+/*
+ * services/file_scan/mod.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The file scan service, for checking uploads for malware.
+//!
+//! Uploaded files are scanned before being stored as blobs, so an infected
+//! file never reaches blob storage. Until then it is held in quarantine,
+//! either as a pending upload's object or in the request itself.
+//!
+//! The scanner is set in the configuration. Currently this is either no
+//! scanning at all, or a ClamAV daemon reached over TCP. Other scanners can
+//! be added as another `FileScanner` variant with its own module here.
+//!
+//! Infected uploads fail with `Error::FileInfected`, which gives the name
+//! of the detected signature, and are recorded in the audit log.
+
+mod prelude {
+    pub use super::super::prelude::*;
+    pub use super::structs::*;
+}
+
+mod clamd;
+mod service;
+mod structs;
+
+pub use self::service::FileScanService;
+pub use self::structs::*;
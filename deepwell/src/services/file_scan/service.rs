@@ -0,0 +1,80 @@
+/*
+ * services/file_scan/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::clamd;
+use super::prelude::*;
+use crate::hash::{blob_hash_to_hex, sha512_hash};
+use crate::services::audit::CreateAuditEntry;
+use crate::services::AuditService;
+use serde_json::json;
+
+#[derive(Debug)]
+pub struct FileScanService;
+
+impl FileScanService {
+    /// Scans an uploaded file, failing if it is infected.
+    ///
+    /// This must be called before the file is stored as a blob.
+    pub async fn check(
+        ctx: &ServiceContext<'_>,
+        ScanFile {
+            site_id,
+            user_id,
+            name,
+            data,
+        }: ScanFile<'_>,
+    ) -> Result<()> {
+        let config = ctx.config();
+        let outcome = match config.file_scanner {
+            FileScanner::None => return Ok(()),
+            FileScanner::Clamd => clamd::scan(config, data).await?,
+        };
+
+        let signature = match outcome {
+            ScanOutcome::Clean => {
+                debug!("Uploaded file '{name}' is clean");
+                return Ok(());
+            }
+            ScanOutcome::Infected(signature) => signature,
+        };
+
+        warn!("Uploaded file '{name}' in site ID {site_id} is infected: {signature}");
+
+        // The upload is rejected, so this would be rolled back with everything else
+        AuditService::record_detached(
+            ctx,
+            CreateAuditEntry {
+                actor_id: user_id,
+                subject_id: user_id,
+                site_id: Some(site_id),
+                action: "file.upload-infected",
+                data: json!({
+                    "name": name,
+                    "size": data.len(),
+                    "s3_hash": blob_hash_to_hex(&sha512_hash(data)).as_str(),
+                    "signature": signature,
+                }),
+            },
+        )
+        .await?;
+
+        Err(Error::FileInfected(signature))
+    }
+}
@@ -0,0 +1,47 @@
+/*
+ * services/file_scan/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+/// Which scanner uploaded files are checked with.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum FileScanner {
+    /// Files are not scanned.
+    None,
+
+    /// Files are sent to a ClamAV daemon.
+    Clamd,
+}
+
+/// The result of scanning a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScanOutcome {
+    Clean,
+
+    /// The file matched a signature, whose name is given.
+    Infected(String),
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct ScanFile<'a> {
+    pub site_id: i64,
+    pub user_id: i64,
+    pub name: &'a str,
+    pub data: &'a [u8],
+}
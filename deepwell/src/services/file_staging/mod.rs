@@ -0,0 +1,41 @@
+/*
+ * services/file_staging/mod.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The file staging service, for uploading files before the page they're for exists.
+//!
+//! Each user has a personal staging area, which files can be uploaded to
+//! from places such as the editor. Staged files go through the same checks
+//! as regular uploads, and are stored as blobs, but are not on any page.
+//! Once the page exists, a staged file can be attached to it with
+//! `FileService::attach()`, which creates the file and its first revision.
+//!
+//! Each user may only stage so many files, and staged files which are
+//! never attached expire, and are removed by a recurring job.
+
+mod prelude {
+    pub use super::super::prelude::*;
+    pub use super::structs::*;
+}
+
+mod service;
+mod structs;
+
+pub use self::service::FileStagingService;
+pub use self::structs::*;
@@ -0,0 +1,255 @@
+/*
+ * services/file_staging/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::file_staging::{
+    self, Entity as FileStaging, Model as FileStagingModel,
+};
+use crate::services::blob::CreateBlobOutput;
+use crate::services::file_scan::ScanFile;
+use crate::services::upload_rule::UploadRuleTarget;
+use crate::services::{
+    BlobService, FileScanService, FileService, LimitService, UploadRuleService,
+};
+
+#[derive(Debug)]
+pub struct FileStagingService;
+
+impl FileStagingService {
+    /// Uploads a file to the user's staging area.
+    ///
+    /// The file is checked and stored the same way as a regular upload,
+    /// except for anything which depends on the page it will be on.
+    /// Those checks are made when it is attached to a page.
+    pub async fn stage(
+        ctx: &ServiceContext<'_>,
+        StageFile {
+            site_id,
+            user_id,
+            name,
+            data,
+            bypass_filter,
+        }: StageFile,
+    ) -> Result<StagedFile> {
+        info!(
+            "Staging file with name '{}' for user ID {} in site ID {}, content length {}",
+            name,
+            user_id,
+            site_id,
+            data.len(),
+        );
+
+        // Check size limits
+        LimitService::get(ctx, site_id)
+            .await?
+            .check_upload(data.as_ref())?;
+
+        // Perform filter validation
+        if !bypass_filter {
+            FileService::run_filter(ctx, site_id, Some(&name)).await?;
+        }
+
+        // Scan for malware before anything else reads the contents
+        FileScanService::check(
+            ctx,
+            ScanFile {
+                site_id,
+                user_id,
+                name: &name,
+                data: data.as_ref(),
+            },
+        )
+        .await?;
+
+        // Check the staging area has room, then upload to S3
+        let (data, original_s3_hash) =
+            FileService::sanitize_upload(ctx, site_id, user_id, &name, data.as_ref())
+                .await?;
+        Self::check_quota(ctx, user_id, data.len() as u64).await?;
+        let CreateBlobOutput {
+            hash,
+            mime,
+            size,
+            created: _,
+        } = BlobService::create(ctx, &data).await?;
+
+        // Check the site's upload rules, now that the file type is known
+        UploadRuleService::check(
+            ctx,
+            UploadRuleTarget {
+                site_id,
+                user_id,
+                mime: &mime,
+                size: size as u64,
+            },
+        )
+        .await?;
+
+        let txn = ctx.transaction();
+        let model = file_staging::ActiveModel {
+            expires_at: Set(now() + ctx.config().file_staging_expiry),
+            site_id: Set(site_id),
+            user_id: Set(user_id),
+            name: Set(name),
+            s3_hash: Set(hash.to_vec()),
+            original_s3_hash: Set(original_s3_hash),
+            size_hint: Set(size),
+            mime_hint: Set(mime),
+            ..Default::default()
+        };
+        let staged = model.insert(txn).await?;
+        Ok(StagedFile::from(staged))
+    }
+
+    /// Gets all of the user's unexpired staged files in this site.
+    pub async fn get_all(
+        ctx: &ServiceContext<'_>,
+        GetStagedFiles { site_id, user_id }: GetStagedFiles,
+    ) -> Result<Vec<StagedFile>> {
+        let txn = ctx.transaction();
+        let staged = FileStaging::find()
+            .filter(
+                Condition::all()
+                    .add(file_staging::Column::SiteId.eq(site_id))
+                    .add(file_staging::Column::UserId.eq(user_id))
+                    .add(file_staging::Column::ExpiresAt.gt(now())),
+            )
+            .order_by_asc(file_staging::Column::CreatedAt)
+            .all(txn)
+            .await?;
+
+        Ok(staged.into_iter().map(StagedFile::from).collect())
+    }
+
+    /// Removes a staged file, without attaching it to anything.
+    pub async fn remove(
+        ctx: &ServiceContext<'_>,
+        StagedFileReference {
+            site_id,
+            user_id,
+            staged_file_id,
+        }: StagedFileReference,
+    ) -> Result<()> {
+        info!("Removing staged file ID {staged_file_id} in site ID {site_id}");
+
+        let txn = ctx.transaction();
+        let staged = FileStaging::find_by_id(staged_file_id)
+            .filter(file_staging::Column::SiteId.eq(site_id))
+            .filter(file_staging::Column::UserId.eq(user_id))
+            .one(txn)
+            .await?
+            .ok_or(Error::StagedFileNotFound)?;
+
+        FileStaging::delete_by_id(staged_file_id).exec(txn).await?;
+        BlobService::release(ctx, &staged.s3_hash).await?;
+        Ok(())
+    }
+
+    /// Takes an unexpired staged file out of the staging area, so it can be attached.
+    ///
+    /// The row is deleted, but the blob is left in place, since the caller
+    /// is about to refer to it from a file revision in the same transaction.
+    pub(crate) async fn take(
+        ctx: &ServiceContext<'_>,
+        StagedFileReference {
+            site_id,
+            user_id,
+            staged_file_id,
+        }: StagedFileReference,
+    ) -> Result<FileStagingModel> {
+        let txn = ctx.transaction();
+        let staged = FileStaging::find_by_id(staged_file_id)
+            .filter(file_staging::Column::SiteId.eq(site_id))
+            .filter(file_staging::Column::UserId.eq(user_id))
+            .lock_exclusive()
+            .one(txn)
+            .await?
+            .ok_or(Error::StagedFileNotFound)?;
+
+        if staged.expires_at < now() {
+            error!(
+                "Staged file ID {staged_file_id} expired at {}",
+                staged.expires_at,
+            );
+            return Err(Error::StagedFileExpired);
+        }
+
+        FileStaging::delete_by_id(staged_file_id).exec(txn).await?;
+        Ok(staged)
+    }
+
+    /// Checks that the user can stage another file of the given size.
+    ///
+    /// Staging areas are per-user rather than per-site, so this
+    /// counts the user's unexpired staged files across all sites.
+    async fn check_quota(
+        ctx: &ServiceContext<'_>,
+        user_id: i64,
+        requested: u64,
+    ) -> Result<()> {
+        let txn = ctx.transaction();
+        let sizes: Vec<i64> = FileStaging::find()
+            .select_only()
+            .column(file_staging::Column::SizeHint)
+            .filter(file_staging::Column::UserId.eq(user_id))
+            .filter(file_staging::Column::ExpiresAt.gt(now()))
+            .into_tuple()
+            .all(txn)
+            .await?;
+
+        let config = ctx.config();
+        let count = sizes.len() as u64;
+        let bytes = sizes.iter().sum::<i64>() as u64;
+
+        if count >= config.file_staging_maximum_files
+            || bytes + requested > config.file_staging_maximum_bytes
+        {
+            error!(
+                "User ID {user_id} has {count} files ({bytes} bytes) staged, cannot stage {requested} more bytes",
+            );
+            return Err(Error::StagingQuotaExceeded);
+        }
+
+        Ok(())
+    }
+
+    /// Removes all expired staged files, along with their blobs if nothing else uses them.
+    pub async fn prune(ctx: &ServiceContext<'_>) -> Result<()> {
+        let txn = ctx.transaction();
+        let staged = FileStaging::find()
+            .filter(file_staging::Column::ExpiresAt.lte(now()))
+            .all(txn)
+            .await?;
+
+        if staged.is_empty() {
+            return Ok(());
+        }
+
+        debug!("Pruning {} expired staged files", staged.len());
+        for staged in staged {
+            FileStaging::delete_by_id(staged.staged_file_id)
+                .exec(txn)
+                .await?;
+            BlobService::release(ctx, &staged.s3_hash).await?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,72 @@
+/*
+ * services/file_staging/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::models::file_staging::Model as FileStagingModel;
+use crate::web::Bytes;
+use time::OffsetDateTime;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct StageFile {
+    pub site_id: i64,
+    pub user_id: i64,
+
+    /// The name the file will have once attached, unless another is given then.
+    pub name: String,
+    pub data: Bytes<'static>,
+
+    #[serde(default)]
+    pub bypass_filter: bool,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetStagedFiles {
+    pub site_id: i64,
+    pub user_id: i64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct StagedFileReference {
+    pub site_id: i64,
+    pub user_id: i64,
+    pub staged_file_id: i64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct StagedFile {
+    pub staged_file_id: i64,
+    pub created_at: OffsetDateTime,
+    pub expires_at: OffsetDateTime,
+    pub name: String,
+    pub mime: String,
+    pub size: i64,
+}
+
+impl From<FileStagingModel> for StagedFile {
+    fn from(staged: FileStagingModel) -> StagedFile {
+        StagedFile {
+            staged_file_id: staged.staged_file_id,
+            created_at: staged.created_at,
+            expires_at: staged.expires_at,
+            name: staged.name,
+            mime: staged.mime_hint,
+            size: staged.size_hint,
+        }
+    }
+}
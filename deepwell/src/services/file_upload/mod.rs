@@ -0,0 +1,48 @@
+/*
+ * services/file_upload/mod.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The file upload service, for uploading files directly to S3.
+//!
+//! Files are normally sent in the API request itself, which is
+//! impractical for large files. Instead, a client can start an upload,
+//! declaring the file's hash and size, and receive a presigned URL to
+//! send it to S3 with. Once the client finalizes the upload, the object
+//! is checked against what was declared, and becomes a file revision
+//! just as if it had been uploaded normally.
+//!
+//! Alternatively, a client on an unreliable connection can start a
+//! chunked upload, and send the file through the API in pieces.
+//! Each chunk is verified against its checksum and stored as it arrives,
+//! so an interrupted upload can be resumed, and once all of it has been
+//! received it is finalized in the same way.
+//!
+//! Uploads which are never finalized expire, and are removed
+//! along with their objects by a recurring job.
+
+mod prelude {
+    pub use super::super::prelude::*;
+    pub use super::structs::*;
+}
+
+mod service;
+mod structs;
+
+pub use self::service::FileUploadService;
+pub use self::structs::*;
@@ -0,0 +1,619 @@
+/*
+ * services/file_upload/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::hash::{sha512_hash, BLOB_HASH_LENGTH};
+use crate::models::file_pending_upload::{
+    self, Entity as FilePendingUpload, Model as FilePendingUploadModel,
+};
+use crate::models::file_pending_upload_chunk::{
+    self, Entity as FilePendingUploadChunk, Model as FilePendingUploadChunkModel,
+};
+use crate::services::file::{EditFile, EditFileBody, UploadFile};
+use crate::services::{BlobService, FileService, LimitService, StorageQuotaService};
+use crate::utils::new_token;
+
+/// Length of the random part of the S3 path for uploaded objects.
+const UPLOAD_PATH_LENGTH: usize = 32;
+
+#[derive(Debug)]
+pub struct FileUploadService;
+
+impl FileUploadService {
+    /// Starts a direct upload, returning a presigned URL to send the file to.
+    ///
+    /// Anything which can be checked without the file's contents is checked
+    /// now, so that the client doesn't upload a file which will be rejected.
+    pub async fn start(
+        ctx: &ServiceContext<'_>,
+        input: StartFileUpload,
+    ) -> Result<StartFileUploadOutput> {
+        info!(
+            "Starting direct upload of {} bytes to page ID {} in site ID {}",
+            input.size, input.page_id, input.site_id,
+        );
+
+        let upload = Self::create(ctx, input, false).await?;
+        let upload_url = BlobService::presign_upload(
+            ctx,
+            &upload.s3_path,
+            ctx.config().file_upload_url_expiry,
+        )?;
+
+        Ok(StartFileUploadOutput {
+            upload_id: upload.upload_id,
+            upload_url,
+            expires_at: upload.expires_at,
+        })
+    }
+
+    /// Starts a chunked upload, where the file is sent through DEEPWELL in pieces.
+    ///
+    /// This is for clients on unreliable connections. If a chunk fails to send,
+    /// only that chunk needs to be retried, and an interrupted upload can be
+    /// resumed from wherever `status()` says it left off. Each chunk is sent
+    /// with its checksum, so a chunk corrupted in transit is rejected.
+    ///
+    /// The same checks as `start()` are performed, including the site's
+    /// maximum upload size, which chunks are not permitted to exceed.
+    pub async fn start_chunked(
+        ctx: &ServiceContext<'_>,
+        input: StartFileUpload,
+    ) -> Result<FileUploadStatus> {
+        info!(
+            "Starting chunked upload of {} bytes to page ID {} in site ID {}",
+            input.size, input.page_id, input.site_id,
+        );
+
+        let upload = Self::create(ctx, input, true).await?;
+        Ok(FileUploadStatus::new(upload, vec![]))
+    }
+
+    async fn create(
+        ctx: &ServiceContext<'_>,
+        StartFileUpload {
+            site_id,
+            page_id,
+            user_id,
+            file_id,
+            name,
+            licensing,
+            revision_comments,
+            s3_hash,
+            size,
+        }: StartFileUpload,
+        chunked: bool,
+    ) -> Result<FilePendingUploadModel> {
+        let s3_hash = Vec::from(s3_hash);
+        if s3_hash.len() != BLOB_HASH_LENGTH {
+            error!(
+                "Declared upload hash is {} bytes, not SHA-512",
+                s3_hash.len()
+            );
+            return Err(Error::BadRequest);
+        }
+
+        let limits = LimitService::get(ctx, site_id).await?;
+        limits.check_upload_size(usize::try_from(size).unwrap_or(usize::MAX))?;
+        limits.check_comment(&revision_comments)?;
+        StorageQuotaService::check(ctx, site_id, size).await?;
+
+        match (file_id, &name) {
+            (Some(file_id), _) => {
+                let file = FileService::get_direct(ctx, file_id, false).await?;
+                if file.site_id != site_id || file.page_id != page_id {
+                    error!("File ID {file_id} is not on page ID {page_id}");
+                    return Err(Error::FileNotFound);
+                }
+            }
+            (None, Some(name)) => {
+                FileService::check_conflicts(ctx, page_id, name, "create").await?;
+            }
+            (None, None) => {
+                error!("New files must have a name");
+                return Err(Error::BadRequest);
+            }
+        }
+
+        let config = ctx.config();
+        let pending = Self::count_pending(ctx, user_id).await?;
+        if pending >= config.file_upload_maximum_pending {
+            error!("User ID {user_id} already has {pending} pending uploads");
+            return Err(Error::PendingUploadQuotaExceeded);
+        }
+
+        let s3_path = format!("pending-upload/{}", new_upload_path());
+        let expires_at = if chunked {
+            now() + config.file_upload_chunked_expiry
+        } else {
+            now() + config.file_upload_url_expiry
+        };
+
+        let txn = ctx.transaction();
+        let model = file_pending_upload::ActiveModel {
+            expires_at: Set(expires_at),
+            site_id: Set(site_id),
+            page_id: Set(page_id),
+            file_id: Set(file_id),
+            user_id: Set(user_id),
+            name: Set(name),
+            licensing: Set(licensing),
+            revision_comments: Set(revision_comments),
+            s3_path: Set(s3_path),
+            s3_hash: Set(s3_hash),
+            size: Set(i64::try_from(size).map_err(|_| Error::BadRequest)?),
+            chunked: Set(chunked),
+            ..Default::default()
+        };
+        let upload = model.insert(txn).await?;
+        Ok(upload)
+    }
+
+    /// Adds the next chunk of data to a chunked upload.
+    ///
+    /// The chunk's offset must be the number of bytes received so far,
+    /// and its data must match the checksum sent with it. A chunk which
+    /// was already received, such as one resent because the response to
+    /// it was lost, is ignored if it is the same as the one received.
+    ///
+    /// Each chunk pushes back the upload's expiry, so a slow
+    /// upload which is still making progress isn't discarded.
+    pub async fn append(
+        ctx: &ServiceContext<'_>,
+        AppendFileUploadChunk {
+            site_id,
+            upload_id,
+            user_id,
+            offset,
+            data,
+            checksum,
+        }: AppendFileUploadChunk,
+    ) -> Result<FileUploadStatus> {
+        let txn = ctx.transaction();
+        let upload = Self::get_pending(ctx, site_id, upload_id, user_id).await?;
+        if !upload.chunked {
+            error!("Upload ID {upload_id} is a direct upload, cannot append chunks");
+            return Err(Error::BadRequest);
+        }
+
+        let data = Vec::from(data);
+        let checksum = Vec::from(checksum);
+        if checksum.len() != BLOB_HASH_LENGTH {
+            error!("Chunk checksum is {} bytes, not SHA-512", checksum.len());
+            return Err(Error::BadRequest);
+        }
+
+        let offset = i64::try_from(offset).map_err(|_| Error::BadRequest)?;
+        let end = offset + data.len() as i64;
+
+        if sha512_hash(&data) != *checksum {
+            error!("Chunk at offset {offset} does not match its checksum");
+            return Err(Error::PendingUploadChunkMismatch);
+        }
+
+        if offset < upload.received_size && end <= upload.received_size {
+            let received = FilePendingUploadChunk::find()
+                .filter(file_pending_upload_chunk::Column::UploadId.eq(upload_id))
+                .filter(file_pending_upload_chunk::Column::StartOffset.eq(offset))
+                .one(txn)
+                .await?;
+
+            return match received {
+                Some(chunk)
+                    if chunk.size == data.len() as i64 && chunk.checksum == checksum =>
+                {
+                    debug!("Chunk at offset {offset} was already received, ignoring");
+                    Self::build_status(ctx, upload).await
+                }
+                _ => {
+                    error!(
+                        "Chunk at offset {offset} differs from the data already received"
+                    );
+                    Err(Error::PendingUploadChunkMismatch)
+                }
+            };
+        }
+
+        if offset != upload.received_size {
+            error!(
+                "Chunk is at offset {offset}, but {} bytes have been received",
+                upload.received_size,
+            );
+            return Err(Error::PendingUploadWrongOffset);
+        }
+
+        if data.is_empty() {
+            error!("Chunk for upload ID {upload_id} is empty");
+            return Err(Error::BadRequest);
+        }
+
+        if end > upload.size {
+            error!(
+                "Chunk would make upload {end} bytes, but {} were declared",
+                upload.size,
+            );
+            return Err(Error::PendingUploadMismatch);
+        }
+
+        debug!(
+            "Appending chunk of {} bytes to upload ID {upload_id} at offset {offset}",
+            data.len(),
+        );
+
+        let chunk_path = chunk_path(&upload.s3_path, upload.chunk_count);
+        BlobService::put_upload(ctx, &chunk_path, &data).await?;
+
+        let model = file_pending_upload_chunk::ActiveModel {
+            upload_id: Set(upload_id),
+            chunk_number: Set(upload.chunk_count),
+            start_offset: Set(offset),
+            size: Set(data.len() as i64),
+            checksum: Set(checksum),
+            ..Default::default()
+        };
+        model.insert(txn).await?;
+
+        let model = file_pending_upload::ActiveModel {
+            upload_id: Set(upload_id),
+            expires_at: Set(now() + ctx.config().file_upload_chunked_expiry),
+            received_size: Set(end),
+            chunk_count: Set(upload.chunk_count + 1),
+            ..Default::default()
+        };
+        let upload = model.update(txn).await?;
+        Self::build_status(ctx, upload).await
+    }
+
+    /// Gets how much of an upload has been received.
+    ///
+    /// A client resuming an interrupted chunked upload uses this to
+    /// find the offset to continue from, and which chunks were received.
+    pub async fn status(
+        ctx: &ServiceContext<'_>,
+        FileUploadReference {
+            site_id,
+            upload_id,
+            user_id,
+        }: FileUploadReference,
+    ) -> Result<FileUploadStatus> {
+        let upload = Self::get_pending(ctx, site_id, upload_id, user_id).await?;
+        Self::build_status(ctx, upload).await
+    }
+
+    /// Cancels an upload, removing anything which was uploaded for it.
+    pub async fn abort(
+        ctx: &ServiceContext<'_>,
+        FileUploadReference {
+            site_id,
+            upload_id,
+            user_id,
+        }: FileUploadReference,
+    ) -> Result<()> {
+        info!("Aborting upload ID {upload_id} in site ID {site_id}");
+
+        let txn = ctx.transaction();
+        let upload = FilePendingUpload::find_by_id(upload_id)
+            .filter(file_pending_upload::Column::SiteId.eq(site_id))
+            .filter(file_pending_upload::Column::UserId.eq(user_id))
+            .one(txn)
+            .await?
+            .ok_or(Error::PendingUploadNotFound)?;
+
+        FilePendingUpload::delete_by_id(upload_id).exec(txn).await?;
+        Self::delete_objects(ctx, &upload).await
+    }
+
+    /// Finalizes an upload, once the client has sent all of the file.
+    ///
+    /// The object, or chunks put together, is checked against the hash and size declared when the
+    /// upload was started, and then stored as a file revision the same way
+    /// as a regular upload, so all the usual checks and processing apply.
+    pub async fn finish(
+        ctx: &ServiceContext<'_>,
+        FinishFileUpload {
+            site_id,
+            upload_id,
+            user_id,
+            bypass_filter,
+        }: FinishFileUpload,
+    ) -> Result<FinishFileUploadOutput> {
+        info!("Finishing upload ID {upload_id} in site ID {site_id}");
+
+        let txn = ctx.transaction();
+        let upload = Self::get_pending(ctx, site_id, upload_id, user_id).await?;
+        let data = if upload.chunked {
+            Self::get_chunked_data(ctx, &upload).await?
+        } else {
+            Self::get_direct_data(ctx, &upload).await?
+        };
+
+        if data.len() as i64 != upload.size || sha512_hash(&data) != *upload.s3_hash {
+            error!("Uploaded object does not match its declared hash");
+            return Err(Error::PendingUploadMismatch);
+        }
+
+        // Until now, the file has been quarantined in the pending upload.
+        // If it's infected, remove it rather than waiting for it to expire.
+        let output = match Self::store(ctx, upload.clone(), data, bypass_filter).await {
+            Err(Error::FileInfected(signature)) => {
+                Self::delete_objects(ctx, &upload).await?;
+                return Err(Error::FileInfected(signature));
+            }
+            result => result?,
+        };
+
+        // The file has been stored as a blob, so the uploaded objects are no longer needed
+        FilePendingUpload::delete_by_id(upload_id).exec(txn).await?;
+        Self::delete_objects(ctx, &upload).await?;
+        Ok(output)
+    }
+
+    /// Stores a finished upload as a file revision, like a regular upload.
+    async fn store(
+        ctx: &ServiceContext<'_>,
+        upload: FilePendingUploadModel,
+        data: Vec<u8>,
+        bypass_filter: bool,
+    ) -> Result<FinishFileUploadOutput> {
+        let FilePendingUploadModel {
+            site_id,
+            page_id,
+            file_id,
+            user_id,
+            name,
+            licensing,
+            revision_comments,
+            ..
+        } = upload;
+
+        let output = match file_id {
+            None => {
+                let output = FileService::upload(
+                    ctx,
+                    UploadFile {
+                        site_id,
+                        page_id,
+                        name: name.ok_or(Error::BadRequest)?,
+                        revision_comments,
+                        user_id,
+                        data: data.into(),
+                        licensing: licensing.unwrap_or_default(),
+                        bypass_filter,
+                    },
+                )
+                .await?;
+
+                FinishFileUploadOutput {
+                    file_id: output.file_id,
+                    file_revision_id: Some(output.file_revision_id),
+                }
+            }
+            Some(file_id) => {
+                let output = FileService::edit(
+                    ctx,
+                    EditFile {
+                        site_id,
+                        page_id,
+                        file_id,
+                        user_id,
+                        revision_comments,
+                        bypass_filter,
+                        body: EditFileBody {
+                            name: provided(name),
+                            data: ProvidedValue::Set(data.into()),
+                            licensing: provided(licensing),
+                            overwrite: false,
+                        },
+                    },
+                )
+                .await?;
+
+                FinishFileUploadOutput {
+                    file_id,
+                    file_revision_id: output.map(|output| output.file_revision_id),
+                }
+            }
+        };
+
+        Ok(output)
+    }
+
+    /// Gets an unexpired upload, locking it for the rest of the transaction.
+    ///
+    /// Expired uploads are left for pruning, which also removes their objects.
+    async fn get_pending(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        upload_id: i64,
+        user_id: i64,
+    ) -> Result<FilePendingUploadModel> {
+        let txn = ctx.transaction();
+        let upload = FilePendingUpload::find_by_id(upload_id)
+            .filter(file_pending_upload::Column::SiteId.eq(site_id))
+            .filter(file_pending_upload::Column::UserId.eq(user_id))
+            .lock_exclusive()
+            .one(txn)
+            .await?
+            .ok_or(Error::PendingUploadNotFound)?;
+
+        if upload.expires_at < now() {
+            error!("Upload ID {upload_id} expired at {}", upload.expires_at);
+            return Err(Error::PendingUploadExpired);
+        }
+
+        Ok(upload)
+    }
+
+    async fn get_direct_data(
+        ctx: &ServiceContext<'_>,
+        upload: &FilePendingUploadModel,
+    ) -> Result<Vec<u8>> {
+        // Check the size before fetching, so a larger object isn't read into memory
+        match BlobService::get_upload_size(ctx, &upload.s3_path).await? {
+            None => return Err(Error::PendingUploadMissing),
+            Some(size) if size != upload.size => {
+                error!(
+                    "Uploaded object is {size} bytes, but {} were declared",
+                    upload.size,
+                );
+                return Err(Error::PendingUploadMismatch);
+            }
+            Some(_) => (),
+        }
+
+        BlobService::get_upload(ctx, &upload.s3_path)
+            .await?
+            .ok_or(Error::PendingUploadMissing)
+    }
+
+    async fn get_chunked_data(
+        ctx: &ServiceContext<'_>,
+        upload: &FilePendingUploadModel,
+    ) -> Result<Vec<u8>> {
+        if upload.received_size != upload.size {
+            error!(
+                "Chunked upload has received {} of {} bytes",
+                upload.received_size, upload.size,
+            );
+            return Err(Error::PendingUploadIncomplete);
+        }
+
+        // Each chunk is checked again, in case it was altered in storage
+        let mut data = Vec::with_capacity(upload.size as usize);
+        for chunk in Self::get_chunks(ctx, upload.upload_id).await? {
+            let chunk_path = chunk_path(&upload.s3_path, chunk.chunk_number);
+            let chunk_data = BlobService::get_upload(ctx, &chunk_path)
+                .await?
+                .ok_or(Error::PendingUploadMissing)?;
+
+            if sha512_hash(&chunk_data) != *chunk.checksum {
+                error!(
+                    "Stored chunk {} of upload ID {} does not match its checksum",
+                    chunk.chunk_number, upload.upload_id,
+                );
+                return Err(Error::PendingUploadChunkMismatch);
+            }
+
+            data.extend(chunk_data);
+        }
+
+        Ok(data)
+    }
+
+    /// Gets the chunks received for an upload, in order.
+    async fn get_chunks(
+        ctx: &ServiceContext<'_>,
+        upload_id: i64,
+    ) -> Result<Vec<FilePendingUploadChunkModel>> {
+        let txn = ctx.transaction();
+        let chunks = FilePendingUploadChunk::find()
+            .filter(file_pending_upload_chunk::Column::UploadId.eq(upload_id))
+            .order_by_asc(file_pending_upload_chunk::Column::ChunkNumber)
+            .all(txn)
+            .await?;
+
+        Ok(chunks)
+    }
+
+    async fn build_status(
+        ctx: &ServiceContext<'_>,
+        upload: FilePendingUploadModel,
+    ) -> Result<FileUploadStatus> {
+        let chunks = if upload.chunked {
+            Self::get_chunks(ctx, upload.upload_id).await?
+        } else {
+            vec![]
+        };
+
+        Ok(FileUploadStatus::new(upload, chunks))
+    }
+
+    /// Deletes the object or chunks uploaded for an upload.
+    async fn delete_objects(
+        ctx: &ServiceContext<'_>,
+        upload: &FilePendingUploadModel,
+    ) -> Result<()> {
+        if !upload.chunked {
+            return BlobService::delete_upload(ctx, &upload.s3_path).await;
+        }
+
+        for chunk_number in 0..upload.chunk_count {
+            let chunk_path = chunk_path(&upload.s3_path, chunk_number);
+            BlobService::delete_upload(ctx, &chunk_path).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn count_pending(ctx: &ServiceContext<'_>, user_id: i64) -> Result<u64> {
+        let txn = ctx.transaction();
+        let count = FilePendingUpload::find()
+            .filter(file_pending_upload::Column::UserId.eq(user_id))
+            .filter(file_pending_upload::Column::ExpiresAt.gt(now()))
+            .count(txn)
+            .await?;
+
+        Ok(count)
+    }
+
+    /// Removes all expired uploads, along with any objects uploaded for them.
+    pub async fn prune(ctx: &ServiceContext<'_>) -> Result<()> {
+        let txn = ctx.transaction();
+        let uploads = FilePendingUpload::find()
+            .filter(file_pending_upload::Column::ExpiresAt.lte(now()))
+            .all(txn)
+            .await?;
+
+        if uploads.is_empty() {
+            return Ok(());
+        }
+
+        debug!("Pruning {} expired uploads", uploads.len());
+        for upload in uploads {
+            Self::delete_objects(ctx, &upload).await?;
+            FilePendingUpload::delete_by_id(upload.upload_id)
+                .exec(txn)
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// For new versions, fields which weren't given are left unchanged.
+fn provided<T>(value: Option<T>) -> ProvidedValue<T> {
+    match value {
+        Some(value) => ProvidedValue::Set(value),
+        None => ProvidedValue::Unset,
+    }
+}
+
+/// Gets the S3 path for one chunk of a chunked upload.
+fn chunk_path(s3_path: &str, chunk_number: i32) -> String {
+    format!("{s3_path}/{chunk_number}")
+}
+
+/// Securely generates the random part of an upload's S3 path.
+///
+/// This keeps the paths of other users' uploads from being guessed.
+#[inline]
+fn new_upload_path() -> String {
+    new_token(UPLOAD_PATH_LENGTH)
+}
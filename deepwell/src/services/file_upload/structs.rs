@@ -0,0 +1,156 @@
+/*
+ * services/file_upload/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::models::file_pending_upload::Model as FilePendingUploadModel;
+use crate::models::file_pending_upload_chunk::Model as FilePendingUploadChunkModel;
+use crate::web::Bytes;
+use serde_json::Value as JsonValue;
+use time::OffsetDateTime;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct StartFileUpload {
+    pub site_id: i64,
+    pub page_id: i64,
+    pub user_id: i64,
+
+    /// If set, the upload is a new version of this file.
+    /// Otherwise, a new file is created.
+    #[serde(default)]
+    pub file_id: Option<i64>,
+
+    /// The name of the file.
+    ///
+    /// Required for new files. For new versions, this renames
+    /// the file if set, or otherwise keeps its current name.
+    #[serde(default)]
+    pub name: Option<String>,
+
+    /// The file's licensing. For new versions, this may be
+    /// omitted to keep the file's current licensing.
+    #[serde(default)]
+    pub licensing: Option<JsonValue>, // TODO
+    pub revision_comments: String,
+
+    /// The SHA-512 hash of the file being uploaded.
+    pub s3_hash: Bytes<'static>,
+
+    /// The size of the file being uploaded, in bytes.
+    pub size: u64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct StartFileUploadOutput {
+    pub upload_id: i64,
+    pub upload_url: String,
+    pub expires_at: OffsetDateTime,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct AppendFileUploadChunk {
+    pub site_id: i64,
+    pub upload_id: i64,
+    pub user_id: i64,
+
+    /// Where in the file this chunk begins, in bytes.
+    pub offset: u64,
+    pub data: Bytes<'static>,
+
+    /// The SHA-512 hash of this chunk's data.
+    pub checksum: Bytes<'static>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct FileUploadReference {
+    pub site_id: i64,
+    pub upload_id: i64,
+    pub user_id: i64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct FileUploadStatus {
+    pub upload_id: i64,
+    pub size: i64,
+
+    /// How many bytes of a chunked upload have been received.
+    ///
+    /// This is always `0` for direct uploads.
+    pub received_size: i64,
+    pub expires_at: OffsetDateTime,
+
+    /// The chunks of a chunked upload which have been received, in order.
+    ///
+    /// This is always empty for direct uploads.
+    pub chunks: Vec<FileUploadChunk>,
+}
+
+impl FileUploadStatus {
+    pub fn new(
+        upload: FilePendingUploadModel,
+        chunks: Vec<FilePendingUploadChunkModel>,
+    ) -> Self {
+        FileUploadStatus {
+            upload_id: upload.upload_id,
+            size: upload.size,
+            received_size: upload.received_size,
+            expires_at: upload.expires_at,
+            chunks: chunks.into_iter().map(FileUploadChunk::from).collect(),
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct FileUploadChunk {
+    pub chunk_number: i32,
+    pub offset: i64,
+    pub size: i64,
+    pub checksum: Bytes<'static>,
+}
+
+impl From<FilePendingUploadChunkModel> for FileUploadChunk {
+    fn from(chunk: FilePendingUploadChunkModel) -> FileUploadChunk {
+        FileUploadChunk {
+            chunk_number: chunk.chunk_number,
+            offset: chunk.start_offset,
+            size: chunk.size,
+            checksum: Bytes::from(chunk.checksum),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct FinishFileUpload {
+    pub site_id: i64,
+    pub upload_id: i64,
+    pub user_id: i64,
+
+    #[serde(default)]
+    pub bypass_filter: bool,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct FinishFileUploadOutput {
+    pub file_id: i64,
+
+    /// The revision created by the upload.
+    ///
+    /// This is `None` if it was a new version of a file,
+    /// which was the same as the current one.
+    pub file_revision_id: Option<i64>,
+}
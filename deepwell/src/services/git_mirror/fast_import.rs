@@ -0,0 +1,218 @@
+/*
+ * services/git_mirror/fast_import.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Generation of input for `git fast-import`.
+//!
+//! This lets an entire batch of revisions be committed in one process,
+//! directly into the object database, without needing a working tree.
+//! See `git-fast-import(1)` for the format.
+
+use std::fmt::Write;
+
+/// A single commit to be written to the mirror.
+#[derive(Debug)]
+pub struct MirrorCommit {
+    pub author_name: String,
+    pub author_email: String,
+    pub timestamp: i64,
+    pub message: String,
+    pub changes: Vec<FileChange>,
+}
+
+#[derive(Debug)]
+pub enum FileChange {
+    Modify { path: String, contents: String },
+    Rename { from: String, to: String },
+    Delete { path: String },
+}
+
+/// Produces the fast-import stream for these commits to the given branch.
+///
+/// If `incremental` is set, then the first commit continues from the branch's
+/// existing head, rather than starting a new history.
+pub fn build_stream(
+    branch: &str,
+    incremental: bool,
+    commits: &[MirrorCommit],
+) -> Vec<u8> {
+    let mut stream = String::new();
+
+    for (index, commit) in commits.iter().enumerate() {
+        let identity = format!(
+            "{} <{}> {} +0000",
+            sanitize(&commit.author_name),
+            sanitize(&commit.author_email),
+            commit.timestamp,
+        );
+
+        writeln!(&mut stream, "commit refs/heads/{branch}").unwrap();
+        writeln!(&mut stream, "author {identity}").unwrap();
+        writeln!(&mut stream, "committer {identity}").unwrap();
+        write_data(&mut stream, &commit.message);
+
+        // The branch tip is already loaded when fast-import starts,
+        // so it cannot be the parent of itself without the ^0.
+        if index == 0 && incremental {
+            writeln!(&mut stream, "from refs/heads/{branch}^0").unwrap();
+        }
+
+        for change in &commit.changes {
+            match change {
+                FileChange::Modify { path, contents } => {
+                    writeln!(&mut stream, "M 100644 inline {}", quote_path(path))
+                        .unwrap();
+                    write_data(&mut stream, contents);
+                }
+                FileChange::Rename { from, to } => {
+                    writeln!(&mut stream, "R {} {}", quote_path(from), quote_path(to))
+                        .unwrap();
+                }
+                FileChange::Delete { path } => {
+                    writeln!(&mut stream, "D {}", quote_path(path)).unwrap();
+                }
+            }
+        }
+
+        stream.push('\n');
+    }
+
+    stream.push_str("done\n");
+    stream.into_bytes()
+}
+
+/// Writes a block of data with an exact byte count.
+fn write_data(stream: &mut String, data: &str) {
+    writeln!(stream, "data {}", data.len()).unwrap();
+    stream.push_str(data);
+    stream.push('\n');
+}
+
+/// Removes characters which cannot appear in an author identity.
+fn sanitize(value: &str) -> String {
+    value
+        .chars()
+        .filter(|&c| !matches!(c, '<' | '>' | '\n'))
+        .collect()
+}
+
+/// Quotes a path if it contains characters fast-import treats specially.
+///
+/// The source path of a rename is space-delimited, so spaces are included.
+/// Page slugs are normalized, so this is not expected in practice.
+fn quote_path(path: &str) -> String {
+    if !path.starts_with('"') && !path.contains(['\n', ' ']) {
+        return path.to_string();
+    }
+
+    let mut quoted = String::from('"');
+    for c in path.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            _ => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[test]
+fn stream() {
+    let commits = [
+        MirrorCommit {
+            author_name: str!("Some <User>"),
+            author_email: str!("1+some-user@users.noreply.example.com"),
+            timestamp: 1700000000,
+            message: str!("Create apple"),
+            changes: vec![FileChange::Modify {
+                path: str!("_default/apple.ftml"),
+                contents: str!("Ünicode text"),
+            }],
+        },
+        MirrorCommit {
+            author_name: str!("Other"),
+            author_email: str!("2+other@users.noreply.example.com"),
+            timestamp: 1700000100,
+            message: str!("Move apple to fruit:apple"),
+            changes: vec![
+                FileChange::Rename {
+                    from: str!("_default/apple.ftml"),
+                    to: str!("fruit/apple.ftml"),
+                },
+                FileChange::Modify {
+                    path: str!("fruit/apple.ftml"),
+                    contents: String::new(),
+                },
+            ],
+        },
+        MirrorCommit {
+            author_name: str!("Other"),
+            author_email: str!("2+other@users.noreply.example.com"),
+            timestamp: 1700000200,
+            message: str!("Delete fruit:apple"),
+            changes: vec![FileChange::Delete {
+                path: str!("fruit/apple.ftml"),
+            }],
+        },
+    ];
+
+    let expected = "\
+commit refs/heads/main
+author Some User <1+some-user@users.noreply.example.com> 1700000000 +0000
+committer Some User <1+some-user@users.noreply.example.com> 1700000000 +0000
+data 12
+Create apple
+from refs/heads/main^0
+M 100644 inline _default/apple.ftml
+data 13
+Ünicode text
+
+commit refs/heads/main
+author Other <2+other@users.noreply.example.com> 1700000100 +0000
+committer Other <2+other@users.noreply.example.com> 1700000100 +0000
+data 25
+Move apple to fruit:apple
+R _default/apple.ftml fruit/apple.ftml
+M 100644 inline fruit/apple.ftml
+data 0
+
+
+commit refs/heads/main
+author Other <2+other@users.noreply.example.com> 1700000200 +0000
+committer Other <2+other@users.noreply.example.com> 1700000200 +0000
+data 18
+Delete fruit:apple
+D fruit/apple.ftml
+
+done
+";
+
+    let actual = build_stream("main", true, &commits);
+    assert_eq!(
+        String::from_utf8(actual).unwrap(),
+        expected,
+        "Generated fast-import stream didn't match expected",
+    );
+
+    assert_eq!(quote_path("a/b.ftml"), "a/b.ftml");
+    assert_eq!(quote_path("a b"), r#""a b""#);
+    assert_eq!(quote_path("\"a\nb"), r#""\"a\nb""#);
+}
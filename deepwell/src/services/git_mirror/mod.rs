@@ -0,0 +1,46 @@
+/*
+ * services/git_mirror/mod.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The Git mirror service, for keeping a copy of a site's page sources in Git.
+//!
+//! A site can opt into having its page wikitext mirrored into a repository,
+//! as an external audit trail. Each page is a file at `{category}/{page}.ftml`,
+//! and each page revision becomes a commit attributed to the user who made it,
+//! with the time it was originally made.
+//!
+//! Syncing is done in a job queued after each new revision, which commits all
+//! revisions not yet in the repository. Afterwards, the repository can be pushed to
+//! a remote, and if the mirror is public, it can be cloned from the site over
+//! Git's "dumb" HTTP protocol under `/-/git/`.
+//!
+//! Git history cannot be rewritten here, so hiding a revision's wikitext only
+//! affects the mirror if it was hidden before that revision was synced.
+
+mod prelude {
+    pub use super::super::prelude::*;
+    pub use super::structs::*;
+}
+
+mod fast_import;
+mod service;
+mod structs;
+
+pub use self::service::GitMirrorService;
+pub use self::structs::*;
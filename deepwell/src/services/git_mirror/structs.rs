@@ -0,0 +1,50 @@
+/*
+ * services/git_mirror/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::web::Bytes;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SetGitMirror {
+    pub site_id: i64,
+
+    /// Where to push the repository after each sync, if anywhere.
+    ///
+    /// Any credentials needed should be included in the URL.
+    #[serde(default)]
+    pub remote_url: Option<String>,
+
+    /// Whether the repository can be cloned from the site itself.
+    #[serde(default)]
+    pub public: bool,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetGitMirrorFile {
+    /// The domain the repository was requested from.
+    pub domain: String,
+
+    /// The path of the file within the bare repository.
+    pub path: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct GitMirrorFileOutput {
+    pub data: Bytes<'static>,
+}
@@ -0,0 +1,44 @@
+/*
+ * services/impersonation/mod.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The impersonation service, for administrators acting as another user.
+//!
+//! When debugging a user's problem it is sometimes necessary to see the platform
+//! exactly as they do. Instance administrators (as listed in the configuration)
+//! can open a session as another user, which is marked with their own user ID
+//! as the impersonator. Such sessions are short-lived and cannot be renewed.
+//!
+//! While impersonating, every call made with the session is recorded in the
+//! audit log as it is dispatched, with the administrator as the actor and the
+//! user as the subject. Sensitive actions, such as changing the user's password
+//! or MFA settings, are refused outright.
+//! Once the session ends, whether by logging out or expiring, the user is sent
+//! a message letting them know their account was accessed.
+
+mod prelude {
+    pub use super::super::prelude::*;
+    pub use super::structs::*;
+}
+
+mod service;
+mod structs;
+
+pub use self::service::ImpersonationService;
+pub use self::structs::*;
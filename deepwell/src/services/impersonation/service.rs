@@ -0,0 +1,333 @@
+/*
+ * services/impersonation/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::constants::SYSTEM_USER_ID;
+use crate::models::sea_orm_active_enums::UserType;
+use crate::models::session::Model as SessionModel;
+use crate::models::user::Model as UserModel;
+use crate::services::api_usage::ApiCall;
+use crate::services::audit::CreateAuditEntry;
+use crate::services::message::CreateMessageDraft;
+use crate::services::permission::Requirement;
+use crate::services::session::CreateSession;
+use crate::services::{
+    AuditService, MessageService, PermissionService, SessionService, UserService,
+};
+use crate::utils::date_wikitext;
+use fluent::{FluentArgs, FluentValue};
+use jsonrpsee::types::params::Params;
+use sea_orm::TransactionTrait;
+use std::cmp;
+use unic_langid::LanguageIdentifier;
+
+/// Actions which cannot be taken while impersonating a user.
+///
+/// These either affect the user's ability to access their own account,
+/// or would let the administrator persist access beyond the session.
+const SENSITIVE_ACTIONS: [&str; 10] = [
+    "impersonation.start",
+    "refresh-token.create",
+    "session.invalidate-others",
+    "session.renew",
+    "user.delete",
+    "user.email",
+    "user.mfa",
+    "user.oauth",
+    "user.password",
+    "user.recovery",
+];
+
+#[derive(Debug)]
+pub struct ImpersonationService;
+
+impl ImpersonationService {
+    /// Opens a new session as the given user, on behalf of an administrator.
+    ///
+    /// # Returns
+    /// The generated session token.
+    pub async fn start(
+        ctx: &ServiceContext<'_>,
+        StartImpersonation {
+            session_token,
+            user: reference,
+            reason,
+            ip_address,
+            user_agent,
+        }: StartImpersonation<'_>,
+    ) -> Result<String> {
+        let config = ctx.config();
+        let session = SessionService::get(ctx, &session_token).await?;
+        let admin_id = session.user_id;
+        Self::check(&session, "impersonation.start")?;
+
+        if session.restricted || !config.impersonation_admins.contains(&admin_id) {
+            error!("User ID {admin_id} is not an instance administrator");
+            return Err(Error::ImpersonationNotAllowed);
+        }
+
+        if reason.trim().is_empty() {
+            error!("No reason given for impersonation");
+            return Err(Error::BadRequest);
+        }
+
+        let user = UserService::get(ctx, reference).await?;
+        info!(
+            "Administrator ID {admin_id} is impersonating user ID {}",
+            user.user_id,
+        );
+
+        // Only ordinary accounts can be impersonated, and never other administrators.
+        let allowed = user.deleted_at.is_none()
+            && matches!(user.user_type, UserType::Regular | UserType::Bot)
+            && !config.impersonation_admins.contains(&user.user_id);
+
+        if !allowed {
+            error!("User ID {} cannot be impersonated", user.user_id);
+            return Err(Error::ImpersonationNotAllowed);
+        }
+
+        let session_token = SessionService::create(
+            ctx,
+            CreateSession {
+                user_id: user.user_id,
+                ip_address,
+                user_agent,
+                restricted: false,
+                impersonator_id: Some(admin_id),
+            },
+        )
+        .await?;
+
+        AuditService::record(
+            ctx,
+            CreateAuditEntry {
+                actor_id: admin_id,
+                subject_id: user.user_id,
+                site_id: None,
+                action: "impersonation.start",
+                data: serde_json::json!({ "reason": reason }),
+            },
+        )
+        .await?;
+
+        Ok(session_token)
+    }
+
+    /// Records an API call made using an impersonation session.
+    ///
+    /// Calls identify their session by passing `"session_token"`. If it is an
+    /// impersonation, the call is recorded in the audit log with the administrator
+    /// as the actor, and their ID is returned so the call's context can refuse
+    /// sensitive actions (see `check_context()`). This is done for every call as
+    /// it is dispatched, before the endpoint runs.
+    ///
+    /// Calls anyone could make, such as viewing pages, are not recorded.
+    pub async fn record_call(
+        ctx: &ServiceContext<'_>,
+        call: &ApiCall,
+        params: &Params<'_>,
+    ) -> Result<Option<i64>> {
+        #[derive(Deserialize, Debug)]
+        struct SessionToken {
+            #[serde(default)]
+            session_token: Option<String>,
+        }
+
+        let session_token = match params.parse::<SessionToken>() {
+            Ok(SessionToken {
+                session_token: Some(session_token),
+            }) => session_token,
+            _ => return Ok(None),
+        };
+
+        // Invalid tokens are reported by the endpoint itself, if it needs a session
+        let session = match SessionService::get_optional(ctx, &session_token).await? {
+            Some(session) => session,
+            None => return Ok(None),
+        };
+
+        let impersonator_id = match session.impersonator_id {
+            Some(impersonator_id) => impersonator_id,
+            None => return Ok(None),
+        };
+
+        if PermissionService::requirement(call.method) != Some(Requirement::Anyone) {
+            AuditService::record(
+                ctx,
+                CreateAuditEntry {
+                    actor_id: impersonator_id,
+                    subject_id: session.user_id,
+                    site_id: call.site_id,
+                    action: call.method,
+                    data: serde_json::json!({}),
+                },
+            )
+            .await?;
+        }
+
+        Ok(Some(impersonator_id))
+    }
+
+    /// Ensures the given action is permitted with this session.
+    ///
+    /// Sensitive actions are refused if the session is an impersonation.
+    pub fn check(session: &SessionModel, action: &str) -> Result<()> {
+        check_action(session.impersonator_id, session.user_id, action)
+    }
+
+    /// Ensures the given action is permitted in this call.
+    ///
+    /// This is for services which are not passed the session itself,
+    /// and relies on the impersonation being found by `record_call()`.
+    pub fn check_context(
+        ctx: &ServiceContext<'_>,
+        user_id: i64,
+        action: &str,
+    ) -> Result<()> {
+        check_action(ctx.impersonator_id(), user_id, action)
+    }
+
+    /// Finishes an impersonation session which is about to be removed.
+    ///
+    /// This records the end in the audit log, and notifies the user that
+    /// their account was accessed. Does nothing for regular sessions.
+    pub async fn end(ctx: &ServiceContext<'_>, session: &SessionModel) -> Result<()> {
+        let impersonator_id = match session.impersonator_id {
+            Some(impersonator_id) => impersonator_id,
+            None => return Ok(()),
+        };
+
+        info!(
+            "Ending impersonation of user ID {} by administrator ID {impersonator_id}",
+            session.user_id,
+        );
+
+        let (admin, user, actions) = try_join!(
+            UserService::get(ctx, Reference::Id(impersonator_id)),
+            UserService::get(ctx, Reference::Id(session.user_id)),
+            AuditService::count_since(
+                ctx,
+                impersonator_id,
+                session.user_id,
+                session.created_at,
+                "impersonation.",
+            ),
+        )?;
+
+        AuditService::record(
+            ctx,
+            CreateAuditEntry {
+                actor_id: impersonator_id,
+                subject_id: session.user_id,
+                site_id: None,
+                action: "impersonation.end",
+                data: serde_json::json!({ "actions": actions }),
+            },
+        )
+        .await?;
+
+        if user.deleted_at.is_some() {
+            debug!("User was deleted, not sending impersonation notice");
+            return Ok(());
+        }
+
+        // The notice is a courtesy, so failing to send it
+        // (e.g. if the user blocked the system user) is not fatal.
+        //
+        // It is sent within a savepoint, since a failed query would otherwise
+        // abort the whole transaction, including ending the impersonation.
+        let savepoint = ctx.transaction().begin().await?;
+        let result = Self::notify(
+            &ctx.with_transaction(&savepoint),
+            &admin,
+            &user,
+            session,
+            actions,
+        )
+        .await;
+
+        match result {
+            Ok(()) => savepoint.commit().await?,
+            Err(error) => {
+                error!("Unable to send impersonation notice: {error}");
+                savepoint.rollback().await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn notify(
+        ctx: &ServiceContext<'_>,
+        admin: &UserModel,
+        user: &UserModel,
+        session: &SessionModel,
+        actions: u64,
+    ) -> Result<()> {
+        let locale = user.locales.first().map(String::as_str).unwrap_or("en");
+        let locales = [locale.parse::<LanguageIdentifier>()?];
+
+        let localization = ctx.localization();
+        let mut args = FluentArgs::new();
+        args.set("admin", fluent_str!(admin.name));
+        args.set("start", date_wikitext(session.created_at));
+        args.set("end", date_wikitext(cmp::min(now(), session.expires_at)));
+        args.set("actions", actions);
+
+        let subject =
+            localization.translate(&locales, "impersonation-notice-subject", &args)?;
+        let wikitext =
+            localization.translate(&locales, "impersonation-notice-body", &args)?;
+
+        let draft = MessageService::create_draft(
+            ctx,
+            CreateMessageDraft {
+                user_id: SYSTEM_USER_ID,
+                recipients: vec![user.user_id],
+                carbon_copy: vec![],
+                blind_carbon_copy: vec![],
+                locale: str!(locale),
+                subject: subject.to_string(),
+                wikitext: wikitext.to_string(),
+                reply_to: None,
+                forwarded_from: None,
+            },
+        )
+        .await?;
+
+        MessageService::send(ctx, &draft.external_id).await?;
+        Ok(())
+    }
+}
+
+fn check_action(impersonator_id: Option<i64>, user_id: i64, action: &str) -> Result<()> {
+    if let Some(impersonator_id) = impersonator_id {
+        if SENSITIVE_ACTIONS.contains(&action) {
+            error!(
+                "Administrator ID {impersonator_id} attempted '{action}' while impersonating user ID {user_id}",
+            );
+
+            return Err(Error::ImpersonationForbidden);
+        }
+    }
+
+    Ok(())
+}
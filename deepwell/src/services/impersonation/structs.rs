@@ -0,0 +1,37 @@
+/*
+ * services/impersonation/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::web::Reference;
+use std::net::IpAddr;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct StartImpersonation<'a> {
+    /// The session token of the administrator.
+    pub session_token: String,
+
+    /// The user to impersonate.
+    pub user: Reference<'a>,
+
+    /// Why the administrator needs to access this account.
+    pub reason: String,
+
+    pub ip_address: IpAddr,
+    pub user_agent: String,
+}
@@ -42,7 +42,7 @@ pub const JOB_QUEUE_DELAY: Option<Duration> = None;
 /// The maximum size, in bytes, that a job payload is allowed to be
 ///
 /// Presently, our jobs are mostly unit types, and the biggest variant
-/// is composed of three integers, so this is more than large enough.
+/// is a handful of short cache keys, so this is more than large enough.
 /// If larger jobs become a thing in the future, this may need to be updated.
 ///
 /// (But as a general code principle there shouldn't be huge jobs, they should
@@ -58,6 +58,11 @@ impl JobService {
         job: &Job,
         delay: Option<Duration>,
     ) -> Result<()> {
+        if ctx.check_only() {
+            debug!("Not queuing job {job:?}, this is a dry run");
+            return Ok(());
+        }
+
         info!("Queuing job {job:?} (delay {delay:?})");
         let payload = serde_json::to_vec(job)?;
         ctx.rsmq()
@@ -18,6 +18,8 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
+use crate::models::sea_orm_active_enums::SearchDocumentType;
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "snake_case", tag = "job", content = "data")]
 pub enum Job {
@@ -30,4 +32,45 @@ pub enum Job {
     PruneText,
     NameChangeRefill,
     LiftExpiredPunishments,
+    BuildExport {
+        export_id: i64,
+    },
+    SyncGitMirror {
+        site_id: i64,
+    },
+    PurgeEdgeCache {
+        keys: Vec<String>,
+    },
+    DispatchEvents,
+    SweepEvents,
+    FlushApiUsage,
+    CheckConsistency,
+    FlushBlobSpool,
+    CollectBlobGarbage,
+    PruneUploads,
+    ApplyFileRetention,
+    RunScheduledTasks,
+    RebuildSearchIndex {
+        site_id: i64,
+        document_type: SearchDocumentType,
+        after_id: i64,
+    },
+    NotifyRenderErrors {
+        site_id: i64,
+        page_id: i64,
+    },
+    RecomputeLinks {
+        recompute_id: i64,
+    },
+    GenerateThumbnails {
+        revision_id: i64,
+    },
+    MigrateBlobs {
+        migration_id: i64,
+    },
+    NotifyScheduledPublish {
+        site_id: i64,
+        page_id: i64,
+        revision_id: i64,
+    },
 }
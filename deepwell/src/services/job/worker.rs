@@ -22,7 +22,13 @@
 
 use super::prelude::*;
 use crate::api::ServerState;
-use crate::services::{PageRevisionService, SessionService, TextService};
+use crate::services::{
+    ApiUsageService, AuthenticationService, BlobService, ConsistencyService,
+    EventService, ExportService, FileRetentionService, FileStagingService,
+    FileUploadService, GitMirrorService, LinkService, OauthService, PageDraftService,
+    PageRevisionService, PublicApiService, RefreshTokenService, RenderErrorService,
+    ScheduledTaskService, SearchService, SessionService, TextService, ThumbnailService,
+};
 use rsmq_async::{MultiplexedRsmq, RsmqConnection, RsmqMessage};
 use sea_orm::TransactionTrait;
 use std::convert::Infallible;
@@ -187,6 +193,9 @@ impl JobWorker {
             Job::PruneSessions => {
                 debug!("Pruning all expired sesions from database");
                 SessionService::prune(ctx).await?;
+                AuthenticationService::prune_magic_links(ctx).await?;
+                RefreshTokenService::prune(ctx).await?;
+                OauthService::prune(ctx).await?;
                 NextJob::Next {
                     job: Job::PruneSessions,
                     delay: Some(self.state.config.job_prune_session),
@@ -226,6 +235,171 @@ impl JobWorker {
                     delay: Some(self.state.config.job_lift_expired_punishments),
                 }
             }
+            Job::BuildExport { export_id } => {
+                debug!("Building document for export ID {export_id}");
+                ExportService::build(ctx, export_id).await?;
+                NextJob::Done
+            }
+            Job::SyncGitMirror { site_id } => {
+                debug!("Syncing Git mirror for site ID {site_id}");
+                if GitMirrorService::sync(ctx, site_id).await? {
+                    NextJob::Next {
+                        job: Job::SyncGitMirror { site_id },
+                        delay: None,
+                    }
+                } else {
+                    NextJob::Done
+                }
+            }
+            Job::PurgeEdgeCache { keys } => {
+                debug!("Purging {} keys from edge cache", keys.len());
+                PublicApiService::purge(ctx, &keys).await?;
+                NextJob::Done
+            }
+            Job::DispatchEvents => {
+                debug!("Dispatching pending events from the outbox");
+                if EventService::dispatch(ctx).await? {
+                    NextJob::Next {
+                        job: Job::DispatchEvents,
+                        delay: None,
+                    }
+                } else {
+                    NextJob::Done
+                }
+            }
+            Job::SweepEvents => {
+                debug!("Sweeping outbox for undelivered events");
+                if EventService::dispatch(ctx).await? {
+                    JobService::queue_job(ctx, &Job::DispatchEvents, None).await?;
+                }
+
+                NextJob::Next {
+                    job: Job::SweepEvents,
+                    delay: Some(self.state.config.job_sweep_events),
+                }
+            }
+            Job::FlushApiUsage => {
+                debug!("Flushing API usage counters into daily totals");
+                ApiUsageService::flush(ctx).await?;
+                NextJob::Next {
+                    job: Job::FlushApiUsage,
+                    delay: Some(self.state.config.job_flush_api_usage),
+                }
+            }
+            Job::CheckConsistency => {
+                debug!("Checking database consistency");
+                let repair = self.state.config.job_consistency_repair;
+                ConsistencyService::run(ctx, None, repair).await?;
+                NextJob::Next {
+                    job: Job::CheckConsistency,
+                    delay: Some(self.state.config.job_consistency_check),
+                }
+            }
+            Job::FlushBlobSpool => {
+                debug!("Flushing spooled blobs to S3");
+                BlobService::flush_spool(ctx).await?;
+                NextJob::Next {
+                    job: Job::FlushBlobSpool,
+                    delay: Some(self.state.config.job_flush_blob_spool),
+                }
+            }
+            Job::CollectBlobGarbage => {
+                debug!("Collecting unreferenced blobs from S3");
+                let dry_run = self.state.config.blob_collection_dry_run;
+                BlobService::collect_garbage(ctx, None, dry_run).await?;
+                NextJob::Next {
+                    job: Job::CollectBlobGarbage,
+                    delay: Some(self.state.config.job_collect_blob_garbage),
+                }
+            }
+            Job::PruneUploads => {
+                debug!("Pruning expired uploads and staged files");
+                FileUploadService::prune(ctx).await?;
+                FileStagingService::prune(ctx).await?;
+                NextJob::Next {
+                    job: Job::PruneUploads,
+                    delay: Some(self.state.config.job_prune_uploads),
+                }
+            }
+            Job::ApplyFileRetention => {
+                debug!("Expiring old file revisions in sites with a retention policy");
+                FileRetentionService::run(ctx).await?;
+                NextJob::Next {
+                    job: Job::ApplyFileRetention,
+                    delay: Some(self.state.config.job_file_retention),
+                }
+            }
+            Job::RunScheduledTasks => {
+                debug!("Running any scheduled tasks and publications which are due");
+                ScheduledTaskService::run_due(ctx).await?;
+                PageDraftService::publish_due(ctx).await?;
+                NextJob::Next {
+                    job: Job::RunScheduledTasks,
+                    delay: Some(self.state.config.job_scheduled_tasks),
+                }
+            }
+            Job::RebuildSearchIndex {
+                site_id,
+                document_type,
+                after_id,
+            } => {
+                debug!("Rebuilding search index for site ID {site_id}");
+                match SearchService::rebuild(ctx, site_id, document_type, after_id)
+                    .await?
+                {
+                    Some((document_type, after_id)) => NextJob::Next {
+                        job: Job::RebuildSearchIndex {
+                            site_id,
+                            document_type,
+                            after_id,
+                        },
+                        delay: None,
+                    },
+                    None => NextJob::Done,
+                }
+            }
+            Job::NotifyRenderErrors { site_id, page_id } => {
+                debug!("Sending render error notices for page ID {page_id}");
+                RenderErrorService::notify(ctx, site_id, page_id).await?;
+                NextJob::Done
+            }
+            Job::RecomputeLinks { recompute_id } => {
+                debug!("Recomputing page links for run ID {recompute_id}");
+                if LinkService::recompute(ctx, recompute_id).await? {
+                    NextJob::Next {
+                        job: Job::RecomputeLinks { recompute_id },
+                        delay: None,
+                    }
+                } else {
+                    NextJob::Done
+                }
+            }
+            Job::GenerateThumbnails { revision_id } => {
+                debug!("Generating thumbnails for file revision ID {revision_id}");
+                ThumbnailService::generate_for_revision(ctx, revision_id).await?;
+                NextJob::Done
+            }
+            Job::MigrateBlobs { migration_id } => {
+                debug!("Copying next batch of blobs for migration ID {migration_id}");
+                if BlobService::migrate(ctx, migration_id).await? {
+                    NextJob::Next {
+                        job: Job::MigrateBlobs { migration_id },
+                        delay: Some(self.state.config.blob_migration_batch_delay),
+                    }
+                } else {
+                    NextJob::Done
+                }
+            }
+            Job::NotifyScheduledPublish {
+                site_id,
+                page_id,
+                revision_id,
+            } => {
+                debug!("Sending scheduled publication notices for page ID {page_id}");
+                PageDraftService::notify_published(ctx, site_id, page_id, revision_id)
+                    .await?;
+                NextJob::Done
+            }
         };
 
         // Don't delete more than once
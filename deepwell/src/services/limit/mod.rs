@@ -0,0 +1,42 @@
+/*
+ * services/limit/mod.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The limit service, for resolving size limits on user-submitted content.
+//!
+//! The instance configuration sets default limits on the size of page wikitext,
+//! revision comments, page tags, file uploads, and total file storage. Each site
+//! may lower these for itself, which are stored as overrides in `site_limit`.
+//!
+//! Content going over a limit fails with `Error::LimitExceeded`, which reports
+//! which limit it was, and both the maximum and actual sizes, so that clients
+//! can explain what went wrong instead of showing a generic failure. The storage
+//! limit is instead enforced by the storage quota service, since it depends on
+//! how much the site has already stored.
+
+mod prelude {
+    pub use super::super::prelude::*;
+    pub use super::structs::*;
+}
+
+mod service;
+mod structs;
+
+pub use self::service::LimitService;
+pub use self::structs::*;
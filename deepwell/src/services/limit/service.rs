@@ -0,0 +1,131 @@
+/*
+ * services/limit/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::site_limit::{self, Entity as SiteLimit, Model as SiteLimitModel};
+
+#[derive(Debug)]
+pub struct LimitService;
+
+impl LimitService {
+    /// Gets the limits currently in effect for a site.
+    pub async fn get(ctx: &ServiceContext<'_>, site_id: i64) -> Result<SiteLimits> {
+        let txn = ctx.transaction();
+        let model = SiteLimit::find_by_id(site_id).one(txn).await?;
+        Ok(Self::resolve(ctx.config(), model.as_ref()))
+    }
+
+    /// Sets the site's own limits, replacing any it had before.
+    ///
+    /// These can only be lower than the instance defaults, since those
+    /// are what the rest of the system is set up to handle.
+    pub async fn set(
+        ctx: &ServiceContext<'_>,
+        SetSiteLimits {
+            site_id,
+            wikitext_bytes,
+            comment_bytes,
+            tags,
+            upload_bytes,
+            storage_bytes,
+        }: SetSiteLimits,
+    ) -> Result<SiteLimits> {
+        info!("Setting limits for site ID {site_id}");
+
+        let config = ctx.config();
+        let wikitext_bytes =
+            Self::check_override(wikitext_bytes, 1, config.maximum_wikitext_bytes)?;
+        let comment_bytes =
+            Self::check_override(comment_bytes, 1, config.maximum_comment_bytes)?;
+        let tags = Self::check_override(tags, 0, config.maximum_tags)?;
+        let upload_bytes =
+            Self::check_override(upload_bytes, 1, config.maximum_upload_bytes)?;
+        let storage_bytes =
+            Self::check_override(storage_bytes, 1, config.maximum_storage_bytes)?;
+
+        let txn = ctx.transaction();
+        let exists = SiteLimit::find_by_id(site_id).one(txn).await?.is_some();
+        let mut model = site_limit::ActiveModel {
+            site_id: Set(site_id),
+            wikitext_bytes: Set(wikitext_bytes),
+            comment_bytes: Set(comment_bytes),
+            tags: Set(tags),
+            upload_bytes: Set(upload_bytes),
+            storage_bytes: Set(storage_bytes),
+            ..Default::default()
+        };
+
+        let model = if exists {
+            model.updated_at = Set(Some(now()));
+            model.update(txn).await?
+        } else {
+            model.insert(txn).await?
+        };
+
+        Ok(Self::resolve(config, Some(&model)))
+    }
+
+    fn check_override(
+        value: Option<u64>,
+        minimum: u64,
+        maximum: u64,
+    ) -> Result<Option<i64>> {
+        match value {
+            None => Ok(None),
+            Some(value) if value >= minimum && value <= maximum => {
+                // Config values fit comfortably, so this cannot overflow
+                Ok(Some(value as i64))
+            }
+            Some(value) => {
+                error!("Site limit of {value} is outside the permitted range");
+                Err(Error::SiteLimitInvalid)
+            }
+        }
+    }
+
+    fn resolve(config: &Config, model: Option<&SiteLimitModel>) -> SiteLimits {
+        // Still capped at the instance default, in case it was lowered
+        // after the site set its own.
+        let resolve = |value: Option<i64>, maximum: u64| match value {
+            Some(value) => maximum.min(value as u64),
+            None => maximum,
+        };
+
+        SiteLimits {
+            wikitext_bytes: resolve(
+                model.and_then(|model| model.wikitext_bytes),
+                config.maximum_wikitext_bytes,
+            ),
+            comment_bytes: resolve(
+                model.and_then(|model| model.comment_bytes),
+                config.maximum_comment_bytes,
+            ),
+            tags: resolve(model.and_then(|model| model.tags), config.maximum_tags),
+            upload_bytes: resolve(
+                model.and_then(|model| model.upload_bytes),
+                config.maximum_upload_bytes,
+            ),
+            storage_bytes: resolve(
+                model.and_then(|model| model.storage_bytes),
+                config.maximum_storage_bytes,
+            ),
+        }
+    }
+}
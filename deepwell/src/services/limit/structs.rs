@@ -0,0 +1,129 @@
+/*
+ * services/limit/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use std::fmt::{self, Display};
+
+/// Which limit some content is being checked against.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum LimitType {
+    WikitextBytes,
+    CommentBytes,
+    Tags,
+    UploadBytes,
+}
+
+impl LimitType {
+    pub fn name(self) -> &'static str {
+        match self {
+            LimitType::WikitextBytes => "wikitext-bytes",
+            LimitType::CommentBytes => "comment-bytes",
+            LimitType::Tags => "tags",
+            LimitType::UploadBytes => "upload-bytes",
+        }
+    }
+}
+
+impl Display for LimitType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// The limits in effect for a site.
+///
+/// These are the site's own limits where it has set them,
+/// and the instance defaults otherwise.
+#[derive(Serialize, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SiteLimits {
+    pub wikitext_bytes: u64,
+    pub comment_bytes: u64,
+    pub tags: u64,
+    pub upload_bytes: u64,
+    pub storage_bytes: u64,
+}
+
+impl SiteLimits {
+    #[inline]
+    pub fn check_wikitext(&self, wikitext: &str) -> Result<()> {
+        check(
+            LimitType::WikitextBytes,
+            self.wikitext_bytes,
+            wikitext.len(),
+        )
+    }
+
+    #[inline]
+    pub fn check_comment(&self, comments: &str) -> Result<()> {
+        check(LimitType::CommentBytes, self.comment_bytes, comments.len())
+    }
+
+    #[inline]
+    pub fn check_tags(&self, tags: &[String]) -> Result<()> {
+        check(LimitType::Tags, self.tags, tags.len())
+    }
+
+    #[inline]
+    pub fn check_upload(&self, data: &[u8]) -> Result<()> {
+        check(LimitType::UploadBytes, self.upload_bytes, data.len())
+    }
+
+    /// Like `check_upload()`, for when only the size is known ahead of time.
+    #[inline]
+    pub fn check_upload_size(&self, size: usize) -> Result<()> {
+        check(LimitType::UploadBytes, self.upload_bytes, size)
+    }
+}
+
+fn check(limit: LimitType, maximum: u64, actual: usize) -> Result<()> {
+    let actual = actual as u64;
+    if actual > maximum {
+        warn!("Content exceeds {limit} limit ({actual} > {maximum})");
+        return Err(Error::LimitExceeded {
+            limit,
+            maximum,
+            actual,
+        });
+    }
+
+    Ok(())
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SetSiteLimits {
+    pub site_id: i64,
+
+    /// The site's own limits. Any which are `None` use the instance default.
+    #[serde(default)]
+    pub wikitext_bytes: Option<u64>,
+
+    #[serde(default)]
+    pub comment_bytes: Option<u64>,
+
+    #[serde(default)]
+    pub tags: Option<u64>,
+
+    #[serde(default)]
+    pub upload_bytes: Option<u64>,
+
+    #[serde(default)]
+    pub storage_bytes: Option<u64>,
+}
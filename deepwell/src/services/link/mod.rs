@@ -22,7 +22,7 @@
 //!
 //! This includes "page connections" (a generic term for a relation between pages,
 //! such as includes, links, redirects, etc), which can either be present or missing,
-//! as well as external links, which are string URLs.
+//! references to files attached to pages, as well as external links, which are string URLs.
 //!
 //! Whenever a page is updated, a list of its backlinks is gathered by the parser,
 //! which is then presented here for processing. A diff is needed:
@@ -34,6 +34,14 @@
 //! While the logic here is similar for each case, the slight differences in keys,
 //! types, and tables make it hard to modularize. Instead, the logic is hopefully
 //! clear enough to be acceptable when repeated over a few slightly distinct cases.
+//!
+//! Since missing connections and file references are kept, they are also used
+//! to report broken includes and file references in a site.
+//!
+//! Links are only extracted when a page is rendered, so if a new ftml version
+//! changes what is extracted, existing pages can be brought up to date with a
+//! recompute run. This goes through pages in batches as a job, re-rendering
+//! each one and applying the diff above, and records how many pages changed.
 
 mod prelude {
     pub use super::super::prelude::*;
@@ -19,15 +19,44 @@
  */
 
 use super::prelude::*;
-use crate::models::page;
+use crate::models::file::{self, Entity as File};
+use crate::models::link_recompute::{
+    self, Entity as LinkRecompute, Model as LinkRecomputeModel,
+};
+use crate::models::page::{self, Entity as Page};
 use crate::models::page_connection::{self, Entity as PageConnection};
 use crate::models::page_connection_missing::{self, Entity as PageConnectionMissing};
+use crate::models::page_file_embed::{self, Entity as PageFileEmbed};
+use crate::models::page_file_reference::{self, Entity as PageFileReference};
 use crate::models::page_link::{self, Entity as PageLink, Model as PageLinkModel};
-use crate::services::{PageService, SiteService};
+use crate::models::site::Model as SiteModel;
+use crate::services::file::GetFile;
+use crate::services::job::Job;
+use crate::services::render::RenderOutput;
+use crate::services::{
+    DomainService, FileService, JobService, PageRevisionService, PageService, SiteService,
+};
 use crate::web::ConnectionType;
-use ftml::data::{Backlinks, PageRef};
+use ftml::data::PageRef;
+use ftml::info::VERSION as FTML_VERSION;
+use once_cell::sync::Lazy;
+use regex::Regex;
 use sea_orm::NotSet;
-use std::collections::HashMap;
+use std::collections::hash_map::{Entry, HashMap};
+
+/// Matches image sources and file card links pointing to files attached to a page.
+///
+/// The captures are the site slug (from the files subdomain),
+/// the page slug, and the filename.
+static FILE_SOURCE_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"(?:src|href)="https?://([a-z0-9\-]+)\.[^"/]+/local--files/([^/"]+)/([^"]+)""#,
+    )
+    .unwrap()
+});
+
+/// How many pages to re-extract links from in each recompute job.
+const RECOMPUTE_BATCH_SIZE: u64 = 50;
 
 /// Forms an optional `Condition` from a list of connection types.
 ///
@@ -223,18 +252,312 @@ impl LinkService {
         Ok(GetLinksExternalToOutput { links })
     }
 
+    /// Gets the IDs of pages which displayed this file when they were last rendered.
+    ///
+    /// Deleted pages are excluded, since they aren't shown anywhere.
+    pub async fn get_file_embeds(
+        ctx: &ServiceContext<'_>,
+        file_id: i64,
+    ) -> Result<Vec<i64>> {
+        let txn = ctx.transaction();
+        let page_ids = PageFileEmbed::find()
+            .select_only()
+            .column(page_file_embed::Column::FromPageId)
+            .join(JoinType::InnerJoin, page_file_embed::Relation::Page.def())
+            .filter(
+                Condition::all()
+                    .add(page_file_embed::Column::FileId.eq(file_id))
+                    .add(page::Column::DeletedAt.is_null()),
+            )
+            .order_by_asc(page_file_embed::Column::FromPageId)
+            .into_tuple()
+            .all(txn)
+            .await?;
+
+        Ok(page_ids)
+    }
+
+    /// Gets the IDs of pages which refer to a file by name, whether it exists or not.
+    ///
+    /// Deleted pages are excluded, since they aren't shown anywhere.
+    pub async fn get_file_references_to(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        page_slug: &str,
+        filename: &str,
+    ) -> Result<Vec<i64>> {
+        let txn = ctx.transaction();
+        let page_ids = PageFileReference::find()
+            .select_only()
+            .column(page_file_reference::Column::FromPageId)
+            .join(
+                JoinType::InnerJoin,
+                page_file_reference::Relation::Page.def(),
+            )
+            .filter(
+                Condition::all()
+                    .add(page_file_reference::Column::ToSiteId.eq(site_id))
+                    .add(page_file_reference::Column::ToPageSlug.eq(page_slug))
+                    .add(page_file_reference::Column::ToFilename.eq(filename))
+                    .add(page::Column::DeletedAt.is_null()),
+            )
+            .order_by_asc(page_file_reference::Column::FromPageId)
+            .into_tuple()
+            .all(txn)
+            .await?;
+
+        Ok(page_ids)
+    }
+
+    /// Finds all includes of missing pages and references to missing files in a site.
+    ///
+    /// This uses the connections recorded when each page was last rendered,
+    /// so changes since then (such as a file being deleted) are checked here.
+    pub async fn get_broken_references(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+    ) -> Result<BrokenReferencesReport> {
+        info!("Getting broken references for site ID {site_id}");
+
+        let txn = ctx.transaction();
+        let mut pages = HashMap::new();
+        let mut sites = HashMap::new();
+
+        // Includes of pages which don't exist
+        let includes = PageConnectionMissing::find()
+            .join(
+                JoinType::InnerJoin,
+                page_connection_missing::Relation::Page2.def(),
+            )
+            .filter(
+                Condition::all()
+                    .add(page::Column::SiteId.eq(site_id))
+                    .add(page::Column::DeletedAt.is_null())
+                    .add(page_connection_missing::Column::ConnectionType.is_in([
+                        ConnectionType::IncludeMessy.name(),
+                        ConnectionType::IncludeElements.name(),
+                    ])),
+            )
+            .order_by_asc(page_connection_missing::Column::FromPageId)
+            .all(txn)
+            .await?;
+
+        let mut missing_includes = Vec::new();
+        for include in includes {
+            let page =
+                get_referencing_page(ctx, &mut pages, include.from_page_id).await?;
+            let to_site = get_site(ctx, &mut sites, include.to_site_id).await?;
+            let to_page_url = page_url(ctx, to_site, &include.to_page_slug);
+
+            missing_includes.push(BrokenInclude {
+                page,
+                to_site_id: include.to_site_id,
+                to_page_slug: include.to_page_slug,
+                to_page_url,
+                count: include.count,
+            });
+        }
+
+        // References to files which don't exist
+        let references = PageFileReference::find()
+            .join(
+                JoinType::InnerJoin,
+                page_file_reference::Relation::Page.def(),
+            )
+            .filter(
+                Condition::all()
+                    .add(page::Column::SiteId.eq(site_id))
+                    .add(page::Column::DeletedAt.is_null()),
+            )
+            .order_by_asc(page_file_reference::Column::FromPageId)
+            .all(txn)
+            .await?;
+
+        let mut missing_files = Vec::new();
+        for reference in references {
+            let reason = match check_file_reference(ctx, &reference).await? {
+                Some(reason) => reason,
+                None => continue,
+            };
+
+            let page =
+                get_referencing_page(ctx, &mut pages, reference.from_page_id).await?;
+            let to_site = get_site(ctx, &mut sites, reference.to_site_id).await?;
+            let to_page_url = page_url(ctx, to_site, &reference.to_page_slug);
+
+            missing_files.push(BrokenFileReference {
+                page,
+                to_site_id: reference.to_site_id,
+                to_page_slug: reference.to_page_slug,
+                to_page_url,
+                to_filename: reference.to_filename,
+                reason,
+                count: reference.count,
+            });
+        }
+
+        debug!(
+            "Found {} broken includes and {} broken file references in site ID {site_id}",
+            missing_includes.len(),
+            missing_files.len(),
+        );
+
+        Ok(BrokenReferencesReport {
+            site_id,
+            missing_includes,
+            missing_files,
+        })
+    }
+
+    /// Starts re-extracting the links of every page, or every page in a site.
+    ///
+    /// Links are normally only extracted when a page is edited or rerendered,
+    /// so after an ftml upgrade changes what is found, the stored links are
+    /// stale until this is run.
+    pub async fn start_recompute(
+        ctx: &ServiceContext<'_>,
+        StartLinkRecompute { site_id }: StartLinkRecompute,
+    ) -> Result<LinkRecomputeModel> {
+        let txn = ctx.transaction();
+
+        match site_id {
+            Some(site_id) => {
+                info!("Starting link recomputation for site ID {site_id}");
+
+                // Ensure the site exists
+                SiteService::get(ctx, Reference::Id(site_id)).await?;
+            }
+            None => info!("Starting link recomputation for all sites"),
+        }
+
+        let model = link_recompute::ActiveModel {
+            site_id: Set(site_id),
+            generator: Set(FTML_VERSION.clone()),
+            ..Default::default()
+        };
+        let recompute = model.insert(txn).await?;
+
+        JobService::queue_job(
+            ctx,
+            &Job::RecomputeLinks {
+                recompute_id: recompute.recompute_id,
+            },
+            None,
+        )
+        .await?;
+
+        Ok(recompute)
+    }
+
+    pub async fn get_recompute_optional(
+        ctx: &ServiceContext<'_>,
+        recompute_id: i64,
+    ) -> Result<Option<LinkRecomputeModel>> {
+        let txn = ctx.transaction();
+        let recompute = LinkRecompute::find_by_id(recompute_id).one(txn).await?;
+        Ok(recompute)
+    }
+
+    #[inline]
+    pub async fn get_recompute(
+        ctx: &ServiceContext<'_>,
+        recompute_id: i64,
+    ) -> Result<LinkRecomputeModel> {
+        find_or_error!(
+            Self::get_recompute_optional(ctx, recompute_id),
+            LinkRecompute
+        )
+    }
+
+    /// Re-extracts the links of the next batch of pages in a recompute run.
+    /// Called by the job worker.
+    ///
+    /// The run's counts are updated as it goes, and it is marked
+    /// as completed once every page has been processed.
+    ///
+    /// # Returns
+    /// Whether there are more pages remaining, in which case
+    /// this should be called again.
+    pub async fn recompute(ctx: &ServiceContext<'_>, recompute_id: i64) -> Result<bool> {
+        let txn = ctx.transaction();
+        let recompute = Self::get_recompute(ctx, recompute_id).await?;
+        if recompute.completed_at.is_some() {
+            warn!("Link recomputation run ID {recompute_id} already completed");
+            return Ok(false);
+        }
+
+        let mut condition = Condition::all()
+            .add(page::Column::DeletedAt.is_null())
+            .add(page::Column::PageId.gt(recompute.last_page_id));
+
+        if let Some(site_id) = recompute.site_id {
+            condition = condition.add(page::Column::SiteId.eq(site_id));
+        }
+
+        let pages = Page::find()
+            .filter(condition)
+            .order_by_asc(page::Column::PageId)
+            .limit(RECOMPUTE_BATCH_SIZE)
+            .all(txn)
+            .await?;
+
+        let mut pages_changed = 0;
+        for page in &pages {
+            if PageRevisionService::refresh_links(ctx, page.site_id, page.page_id).await?
+            {
+                pages_changed += 1;
+            }
+        }
+
+        let done = (pages.len() as u64) < RECOMPUTE_BATCH_SIZE;
+        debug!(
+            "Recomputed links for {} pages ({pages_changed} changed) in run ID {recompute_id}",
+            pages.len(),
+        );
+
+        let model = link_recompute::ActiveModel {
+            recompute_id: Set(recompute_id),
+            completed_at: Set(done.then(now)),
+            last_page_id: Set(pages
+                .last()
+                .map(|page| page.page_id)
+                .unwrap_or(recompute.last_page_id)),
+            pages_checked: Set(recompute.pages_checked + pages.len() as i64),
+            pages_changed: Set(recompute.pages_changed + pages_changed),
+            ..Default::default()
+        };
+        model.update(txn).await?;
+
+        if done {
+            info!("Link recomputation run ID {recompute_id} completed");
+        }
+
+        Ok(!done)
+    }
+
+    /// Updates the stored links of a page from its render output.
+    ///
+    /// # Returns
+    /// Whether any of the page's stored links changed.
     pub async fn update(
         ctx: &ServiceContext<'_>,
         site_id: i64,
         page_id: i64,
-        backlinks: &Backlinks<'_>,
-    ) -> Result<()> {
+        output: &RenderOutput,
+    ) -> Result<bool> {
+        let backlinks = &output.html_output.backlinks;
         let mut connections = HashMap::new();
         let mut connections_missing = HashMap::new();
+        let mut file_references = HashMap::new();
+        let mut file_embeds = HashMap::new();
         let mut external_links = HashMap::new();
 
         // Get include stats
-        for include in &backlinks.included_pages {
+        for include in backlinks
+            .included_pages
+            .iter()
+            .chain(&output.included_pages)
+        {
             count_connections(
                 ctx,
                 site_id,
@@ -249,6 +572,26 @@ impl LinkService {
 
         // Get internal page link stats
         for link in &backlinks.internal_links {
+            // Links to files, such as [/local--files/page/file.pdf],
+            // are parsed as page links, so separate them here.
+            if let Some((page_slug, filename)) = link
+                .page
+                .strip_prefix("local--files/")
+                .and_then(|path| path.split_once('/'))
+            {
+                count_file_references(
+                    ctx,
+                    site_id,
+                    link.site.as_deref(),
+                    page_slug,
+                    filename,
+                    &mut file_references,
+                )
+                .await?;
+
+                continue;
+            }
+
             count_connections(
                 ctx,
                 site_id,
@@ -260,6 +603,27 @@ impl LinkService {
             .await?;
         }
 
+        // Get embedded file stats
+        //
+        // These are both references by name, and embeds of whichever file they resolve to.
+        let mut embedded_files = HashMap::new();
+        for captures in FILE_SOURCE_REGEX.captures_iter(&output.html_output.body) {
+            count_file_references(
+                ctx,
+                site_id,
+                Some(&captures[1]),
+                &captures[2],
+                &captures[3],
+                &mut embedded_files,
+            )
+            .await?;
+        }
+
+        count_file_embeds(ctx, &embedded_files, &mut file_embeds).await?;
+        for (key, count) in embedded_files {
+            *file_references.entry(key).or_insert(0) += count;
+        }
+
         // Gather external URL link stats
         for url in &backlinks.external_links {
             let entry = external_links.entry(str!(url)).or_insert(0);
@@ -267,13 +631,25 @@ impl LinkService {
         }
 
         // Update records
-        try_join!(
+        let (
+            connections_changed,
+            connections_missing_changed,
+            file_references_changed,
+            file_embeds_changed,
+            external_links_changed,
+        ) = try_join!(
             update_connections(ctx, page_id, &mut connections),
             update_connections_missing(ctx, page_id, &mut connections_missing),
+            update_file_references(ctx, page_id, &mut file_references),
+            update_file_embeds(ctx, page_id, &mut file_embeds),
             update_external_links(ctx, page_id, &mut external_links),
         )?;
 
-        Ok(())
+        Ok(connections_changed
+            || connections_missing_changed
+            || file_references_changed
+            || file_embeds_changed
+            || external_links_changed)
     }
 }
 
@@ -283,8 +659,9 @@ async fn update_connections(
     ctx: &ServiceContext<'_>,
     from_page_id: i64,
     counts: &mut HashMap<(i64, ConnectionType), i32>,
-) -> Result<()> {
+) -> Result<bool> {
     let txn = ctx.transaction();
+    let mut changed = false;
 
     // Get existing connections
     let mut connection_chunks = PageConnection::find()
@@ -308,12 +685,14 @@ async fn update_connections(
                     model.count = Set(count);
                     model.updated_at = Set(Some(now()));
                     model.update(txn).await?;
+                    changed = true;
                 }
 
                 // Connection existed, but has no further counts. Remove it.
                 None => {
                     let model: page_connection::ActiveModel = connection.into();
                     model.delete(txn).await?;
+                    changed = true;
                 }
             }
         }
@@ -335,18 +714,20 @@ async fn update_connections(
         .collect::<Vec<_>>();
 
     if !to_insert.is_empty() {
+        changed = true;
         PageConnection::insert_many(to_insert).exec(txn).await?;
     }
 
-    Ok(())
+    Ok(changed)
 }
 
 async fn update_connections_missing(
     ctx: &ServiceContext<'_>,
     from_page_id: i64,
     counts: &mut HashMap<(i64, String, ConnectionType), i32>,
-) -> Result<()> {
+) -> Result<bool> {
     let txn = ctx.transaction();
+    let mut changed = false;
 
     // Get existing connections
     let mut connection_chunks = PageConnectionMissing::find()
@@ -372,12 +753,14 @@ async fn update_connections_missing(
                     model.count = Set(count);
                     model.updated_at = Set(Some(now()));
                     model.update(txn).await?;
+                    changed = true;
                 }
 
                 // Connection existed, but has no further counts. Remove it.
                 None => {
                     let model: page_connection_missing::ActiveModel = connection.into();
                     model.delete(txn).await?;
+                    changed = true;
                 }
             }
         }
@@ -402,20 +785,152 @@ async fn update_connections_missing(
         .collect::<Vec<_>>();
 
     if !to_insert.is_empty() {
+        changed = true;
         PageConnectionMissing::insert_many(to_insert)
             .exec(txn)
             .await?;
     }
 
-    Ok(())
+    Ok(changed)
+}
+
+async fn update_file_references(
+    ctx: &ServiceContext<'_>,
+    from_page_id: i64,
+    counts: &mut HashMap<(i64, String, String), i32>,
+) -> Result<bool> {
+    let txn = ctx.transaction();
+    let mut changed = false;
+
+    // Get existing references
+    let mut reference_chunks = PageFileReference::find()
+        .filter(page_file_reference::Column::FromPageId.eq(from_page_id))
+        .order_by_asc(page_file_reference::Column::CreatedAt)
+        .paginate(txn, 100);
+
+    // Update and delete references
+    while let Some(references) = reference_chunks.fetch_and_next().await? {
+        for reference in references {
+            let key = (
+                reference.to_site_id,
+                reference.to_page_slug.clone(),
+                reference.to_filename.clone(),
+            );
+
+            match counts.remove(&key) {
+                // Reference exists, count is the same. Do nothing.
+                Some(count) if reference.count == count => (),
+
+                // Reference exists, update count.
+                Some(count) => {
+                    let mut model: page_file_reference::ActiveModel = reference.into();
+                    model.count = Set(count);
+                    model.updated_at = Set(Some(now()));
+                    model.update(txn).await?;
+                    changed = true;
+                }
+
+                // Reference existed, but has no further counts. Remove it.
+                None => {
+                    let model: page_file_reference::ActiveModel = reference.into();
+                    model.delete(txn).await?;
+                    changed = true;
+                }
+            }
+        }
+    }
+
+    // Insert new references
+    let to_insert = counts
+        .iter()
+        .map(|((to_site_id, to_page_slug, to_filename), count)| {
+            page_file_reference::ActiveModel {
+                from_page_id: Set(from_page_id),
+                to_site_id: Set(*to_site_id),
+                to_page_slug: Set(str!(to_page_slug)),
+                to_filename: Set(str!(to_filename)),
+                created_at: NotSet,
+                updated_at: NotSet,
+                count: Set(*count),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    if !to_insert.is_empty() {
+        changed = true;
+        PageFileReference::insert_many(to_insert).exec(txn).await?;
+    }
+
+    Ok(changed)
+}
+
+async fn update_file_embeds(
+    ctx: &ServiceContext<'_>,
+    from_page_id: i64,
+    counts: &mut HashMap<i64, i32>,
+) -> Result<bool> {
+    let txn = ctx.transaction();
+    let mut changed = false;
+
+    // Get existing embeds
+    let mut embed_chunks = PageFileEmbed::find()
+        .filter(page_file_embed::Column::FromPageId.eq(from_page_id))
+        .order_by_asc(page_file_embed::Column::CreatedAt)
+        .paginate(txn, 100);
+
+    // Update and delete embeds
+    while let Some(embeds) = embed_chunks.fetch_and_next().await? {
+        for embed in embeds {
+            match counts.remove(&embed.file_id) {
+                // Embed exists, count is the same. Do nothing.
+                Some(count) if embed.count == count => (),
+
+                // Embed exists, update count.
+                Some(count) => {
+                    let mut model: page_file_embed::ActiveModel = embed.into();
+                    model.count = Set(count);
+                    model.updated_at = Set(Some(now()));
+                    model.update(txn).await?;
+                    changed = true;
+                }
+
+                // Embed existed, but has no further counts. Remove it.
+                None => {
+                    let model: page_file_embed::ActiveModel = embed.into();
+                    model.delete(txn).await?;
+                    changed = true;
+                }
+            }
+        }
+    }
+
+    // Insert new embeds
+    let to_insert = counts
+        .iter()
+        .map(|(file_id, count)| page_file_embed::ActiveModel {
+            from_page_id: Set(from_page_id),
+            file_id: Set(*file_id),
+            created_at: NotSet,
+            updated_at: NotSet,
+            count: Set(*count),
+        })
+        .collect::<Vec<_>>();
+
+    if !to_insert.is_empty() {
+        changed = true;
+        PageFileEmbed::insert_many(to_insert).exec(txn).await?;
+    }
+
+    Ok(changed)
 }
 
 async fn update_external_links(
     ctx: &ServiceContext<'_>,
     from_page_id: i64,
     counts: &mut HashMap<String, i32>,
-) -> Result<()> {
+) -> Result<bool> {
     let txn = ctx.transaction();
+    let mut changed = false;
 
     // Get existing links
     let mut link_chunks = PageLink::find()
@@ -436,12 +951,14 @@ async fn update_external_links(
                     model.count = Set(count);
                     model.updated_at = Set(Some(now()));
                     model.update(txn).await?;
+                    changed = true;
                 }
 
                 // Link existed, but has no further counts. Remove it.
                 None => {
                     let model: page_link::ActiveModel = link.into();
                     model.delete(txn).await?;
+                    changed = true;
                 }
             }
         }
@@ -460,10 +977,11 @@ async fn update_external_links(
         .collect::<Vec<_>>();
 
     if !to_insert.is_empty() {
+        changed = true;
         PageLink::insert_many(to_insert).exec(txn).await?;
     }
 
-    Ok(())
+    Ok(changed)
 }
 
 async fn count_connections(
@@ -509,3 +1027,171 @@ async fn count_connections(
 
     Ok(())
 }
+
+async fn count_file_references(
+    ctx: &ServiceContext<'_>,
+    site_id: i64,
+    site_slug: Option<&str>,
+    page_slug: &str,
+    filename: &str,
+    file_references: &mut HashMap<(i64, String, String), i32>,
+) -> Result<()> {
+    let to_site_id = match site_slug {
+        None => site_id,
+        Some(slug) => {
+            let reference = Reference::Slug(cow!(slug));
+            match SiteService::get_optional(ctx, reference).await? {
+                Some(site) => site.site_id,
+                None => {
+                    // Not on this instance, so not ours to track
+                    debug!("Skipping file reference to unknown site '{slug}'");
+                    return Ok(());
+                }
+            }
+        }
+    };
+
+    let entry = file_references
+        .entry((to_site_id, str!(page_slug), str!(filename)))
+        .or_insert(0);
+
+    *entry += 1;
+    Ok(())
+}
+
+/// Resolves embedded file references to the files they display.
+///
+/// References to pages or files which don't exist are skipped,
+/// since those are only tracked by name, in `page_file_reference`.
+async fn count_file_embeds(
+    ctx: &ServiceContext<'_>,
+    file_references: &HashMap<(i64, String, String), i32>,
+    file_embeds: &mut HashMap<i64, i32>,
+) -> Result<()> {
+    for ((site_id, page_slug, filename), count) in file_references {
+        let page =
+            PageService::get_optional(ctx, *site_id, Reference::Slug(cow!(page_slug)))
+                .await?;
+
+        let page_id = match page {
+            Some(page) => page.page_id,
+            None => continue,
+        };
+
+        let file = FileService::get_optional(
+            ctx,
+            GetFile {
+                site_id: *site_id,
+                page_id,
+                file: Reference::Slug(cow!(filename)),
+            },
+        )
+        .await?;
+
+        if let Some(file) = file {
+            let entry = file_embeds.entry(file.file_id).or_insert(0);
+            *entry += count;
+        }
+    }
+
+    Ok(())
+}
+
+// Broken reference report helpers
+
+/// Determines if the file a page refers to is missing.
+///
+/// # Returns
+/// Why the reference is broken, or `None` if the file exists.
+async fn check_file_reference(
+    ctx: &ServiceContext<'_>,
+    reference: &page_file_reference::Model,
+) -> Result<Option<BrokenFileReason>> {
+    let page = PageService::get_optional(
+        ctx,
+        reference.to_site_id,
+        Reference::Slug(cow!(&reference.to_page_slug)),
+    )
+    .await?;
+
+    let page_id = match page {
+        Some(page) => page.page_id,
+        None => return Ok(Some(BrokenFileReason::PageMissing)),
+    };
+
+    let file = FileService::get_optional(
+        ctx,
+        GetFile {
+            site_id: reference.to_site_id,
+            page_id,
+            file: Reference::Slug(cow!(&reference.to_filename)),
+        },
+    )
+    .await?;
+
+    if file.is_some() {
+        return Ok(None);
+    }
+
+    // Distinguish between files which were deleted and never existed
+    let txn = ctx.transaction();
+    let deleted = File::find()
+        .filter(
+            Condition::all()
+                .add(file::Column::PageId.eq(page_id))
+                .add(file::Column::Name.eq(reference.to_filename.as_str()))
+                .add(file::Column::DeletedAt.is_not_null()),
+        )
+        .count(txn)
+        .await?;
+
+    if deleted > 0 {
+        Ok(Some(BrokenFileReason::FileDeleted))
+    } else {
+        Ok(Some(BrokenFileReason::FileMissing))
+    }
+}
+
+/// Gets the details of a page with broken references, caching the result.
+async fn get_referencing_page(
+    ctx: &ServiceContext<'_>,
+    pages: &mut HashMap<i64, BrokenReferencePage>,
+    page_id: i64,
+) -> Result<BrokenReferencePage> {
+    if let Some(page) = pages.get(&page_id) {
+        return Ok(page.clone());
+    }
+
+    let page = PageService::get_direct(ctx, page_id, false).await?;
+    let revision = PageRevisionService::get_latest(ctx, page.site_id, page_id).await?;
+    let site = SiteService::get(ctx, Reference::Id(page.site_id)).await?;
+    let output = BrokenReferencePage {
+        page_id,
+        url: page_url(ctx, &site, &page.slug),
+        slug: page.slug,
+        last_rendered_at: revision.compiled_at,
+    };
+
+    pages.insert(page_id, output.clone());
+    Ok(output)
+}
+
+/// Gets a site by ID, caching the result.
+async fn get_site<'a>(
+    ctx: &ServiceContext<'_>,
+    sites: &'a mut HashMap<i64, SiteModel>,
+    site_id: i64,
+) -> Result<&'a SiteModel> {
+    match sites.entry(site_id) {
+        Entry::Occupied(entry) => Ok(entry.into_mut()),
+        Entry::Vacant(entry) => {
+            let site = SiteService::get(ctx, Reference::Id(site_id)).await?;
+            Ok(entry.insert(site))
+        }
+    }
+}
+
+fn page_url(ctx: &ServiceContext<'_>, site: &SiteModel, slug: &str) -> String {
+    let domain = DomainService::domain_for_site(ctx.config(), site);
+    format!("https://{domain}/{slug}")
+}
@@ -24,6 +24,13 @@ use crate::models::page_link::Model as PageLinkModel;
 use crate::web::Reference;
 use time::OffsetDateTime;
 
+#[derive(Deserialize, Debug, Clone)]
+pub struct StartLinkRecompute {
+    /// Which site to recompute links for. If `None`, then all sites are.
+    #[serde(default)]
+    pub site_id: Option<i64>,
+}
+
 #[derive(Deserialize, Debug, Clone)]
 pub struct GetLinksFrom<'a> {
     pub site_id: i64,
@@ -94,3 +101,62 @@ pub struct ToExternalLink {
     pub page_id: i64,
     pub count: i32,
 }
+
+#[derive(Serialize, Debug, Clone)]
+pub struct BrokenReferencesReport {
+    pub site_id: i64,
+
+    /// Includes of pages which do not exist.
+    pub missing_includes: Vec<BrokenInclude>,
+
+    /// References to files which do not exist, or were deleted.
+    pub missing_files: Vec<BrokenFileReference>,
+}
+
+/// A page containing a broken reference.
+#[derive(Serialize, Debug, Clone)]
+pub struct BrokenReferencePage {
+    pub page_id: i64,
+    pub slug: String,
+    pub url: String,
+
+    /// When the page was last rendered, which is when its references were recorded.
+    pub last_rendered_at: OffsetDateTime,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct BrokenInclude {
+    pub page: BrokenReferencePage,
+    pub to_site_id: i64,
+    pub to_page_slug: String,
+    pub to_page_url: String,
+
+    /// How many times the page includes the missing page.
+    pub count: i32,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct BrokenFileReference {
+    pub page: BrokenReferencePage,
+    pub to_site_id: i64,
+    pub to_page_slug: String,
+    pub to_page_url: String,
+    pub to_filename: String,
+    pub reason: BrokenFileReason,
+
+    /// How many times the page refers to the missing file.
+    pub count: i32,
+}
+
+#[derive(Serialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum BrokenFileReason {
+    /// The page the file would be attached to does not exist.
+    PageMissing,
+
+    /// The page exists, but has no file with this name.
+    FileMissing,
+
+    /// The file was attached to the page, but has been deleted.
+    FileDeleted,
+}
@@ -28,7 +28,7 @@ use crate::models::message_record::{
     self, Entity as MessageRecord, Model as MessageRecordModel,
 };
 use crate::models::sea_orm_active_enums::{MessageRecipientType, UserType};
-use crate::services::render::{RenderOutput, RenderService};
+use crate::services::render::{RenderOutput, RenderPriority, RenderService};
 use crate::services::{RelationService, TextService, UserService};
 use crate::utils::validate_locale;
 use cuid2::cuid;
@@ -165,7 +165,9 @@ impl MessageService {
         };
 
         for recipient_id in recipients.iter() {
-            if !UserService::exists(ctx, Reference::Id(recipient_id)).await? {
+            let user =
+                UserService::get_optional(ctx, Reference::Id(recipient_id)).await?;
+            if !matches!(user, Some(ref user) if user.deleted_at.is_none()) {
                 error!("Recipient user ID {recipient_id} does not exist");
                 return Err(Error::UserNotFound);
             }
@@ -180,9 +182,11 @@ impl MessageService {
             html_output: _,
             // TODO: use ftml errors
             errors: _,
+            included_pages: _,
             compiled_hash,
             compiled_at,
             compiled_generator,
+            content_stats: _,
         } = Self::render(ctx, wikitext, &locale).await?;
 
         Ok(message_draft::ActiveModel {
@@ -275,8 +279,14 @@ impl MessageService {
             )
             .await?;
 
-            // If recipient is a site user, then forward to corresponding site staff.
+            // Deleted users cannot receive new messages.
             let user = UserService::get(ctx, Reference::Id(recipient_user_id)).await?;
+            if user.deleted_at.is_some() {
+                error!("Recipient user ID {recipient_user_id} is deleted");
+                return Err(Error::UserNotFound);
+            }
+
+            // If recipient is a site user, then forward to corresponding site staff.
             if user.user_type == UserType::Site {
                 // TODO what to do if user is banned from site? needs to be possible to block
                 //      permabanned bad actors, but also allow normal banned users to message
@@ -592,9 +602,17 @@ impl MessageService {
             score: ScoreValue::Integer(0),
             tags: vec![],
             language: cow!(locale),
+            utc_offset: None,
         };
 
-        RenderService::render(ctx, wikitext, &page_info, &settings).await
+        RenderService::render(
+            ctx,
+            wikitext,
+            &page_info,
+            &settings,
+            RenderPriority::Normal,
+        )
+        .await
     }
 }
 
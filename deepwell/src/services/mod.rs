@@ -54,69 +54,149 @@ mod macros;
 mod context;
 mod error;
 
+pub mod account_recovery;
 pub mod alias;
+pub mod api_usage;
+pub mod audit;
 pub mod authentication;
+pub mod autocomplete;
 pub mod blob;
 pub mod category;
+pub mod cdn;
+pub mod consistency;
+pub mod custom_block;
 pub mod domain;
 pub mod email;
+pub mod event;
+pub mod event_stream;
+pub mod export;
 pub mod file;
+pub mod file_retention;
 pub mod file_revision;
+pub mod file_scan;
+pub mod file_staging;
+pub mod file_upload;
 pub mod filter;
+pub mod git_mirror;
+pub mod impersonation;
 pub mod import;
 pub mod job;
+pub mod limit;
 pub mod link;
 pub mod message;
 pub mod message_report;
 pub mod mfa;
+pub mod oauth;
 pub mod outdate;
 pub mod page;
+pub mod page_draft;
+pub mod page_field_watch;
+pub mod page_form;
 pub mod page_query;
 pub mod page_revision;
+pub mod page_template;
 pub mod parent;
 pub mod password;
+pub mod permission;
+pub mod public_api;
+pub mod qr_code;
+pub mod refresh_token;
 pub mod relation;
 pub mod render;
+pub mod render_config;
+pub mod render_error;
+pub mod revision_comparison;
+pub mod revision_search;
+pub mod scheduled_task;
 pub mod score;
+pub mod search;
+pub mod service_account;
 pub mod session;
+pub mod shortlink;
 pub mod site;
 pub mod special_page;
+pub mod stable_revision;
+pub mod storage_quota;
+pub mod tag_rule;
 pub mod text;
+pub mod thumbnail;
+pub mod upload_rule;
 pub mod user;
 pub mod user_bot_owner;
 pub mod view;
 pub mod vote;
+pub mod watchlist;
 
+pub use self::account_recovery::AccountRecoveryService;
 pub use self::alias::AliasService;
+pub use self::api_usage::ApiUsageService;
+pub use self::audit::AuditService;
 pub use self::authentication::AuthenticationService;
+pub use self::autocomplete::AutocompleteService;
 pub use self::blob::BlobService;
 pub use self::category::CategoryService;
+pub use self::cdn::CdnService;
+pub use self::consistency::ConsistencyService;
 pub use self::context::ServiceContext;
+pub use self::custom_block::CustomBlockService;
 pub use self::domain::DomainService;
 pub use self::error::*;
+pub use self::event::EventService;
+pub use self::event_stream::EventStreamService;
+pub use self::export::ExportService;
 pub use self::file::FileService;
+pub use self::file_retention::FileRetentionService;
 pub use self::file_revision::FileRevisionService;
+pub use self::file_scan::FileScanService;
+pub use self::file_staging::FileStagingService;
+pub use self::file_upload::FileUploadService;
 pub use self::filter::FilterService;
+pub use self::git_mirror::GitMirrorService;
+pub use self::impersonation::ImpersonationService;
 pub use self::job::JobService;
+pub use self::limit::LimitService;
 pub use self::link::LinkService;
 pub use self::message::MessageService;
 pub use self::message_report::MessageReportService;
 pub use self::mfa::MfaService;
+pub use self::oauth::OauthService;
 pub use self::outdate::OutdateService;
 pub use self::page::PageService;
+pub use self::page_draft::PageDraftService;
+pub use self::page_field_watch::PageFieldWatchService;
+pub use self::page_form::PageFormService;
 // TODO convert page attribution to a type of relation
 pub use self::page_query::PageQueryService;
 pub use self::page_revision::PageRevisionService;
+pub use self::page_template::PageTemplateService;
 pub use self::parent::ParentService;
 pub use self::password::PasswordService;
+pub use self::permission::PermissionService;
+pub use self::public_api::PublicApiService;
+pub use self::qr_code::QrCodeService;
+pub use self::refresh_token::RefreshTokenService;
 pub use self::relation::RelationService;
 pub use self::render::RenderService;
+pub use self::render_config::RenderConfigService;
+pub use self::render_error::RenderErrorService;
+pub use self::revision_comparison::RevisionComparisonService;
+pub use self::revision_search::RevisionSearchService;
+pub use self::scheduled_task::ScheduledTaskService;
 pub use self::score::ScoreService;
+pub use self::search::SearchService;
+pub use self::service_account::ServiceAccountService;
 pub use self::session::SessionService;
+pub use self::shortlink::ShortlinkService;
 pub use self::site::SiteService;
 pub use self::special_page::SpecialPageService;
+pub use self::stable_revision::StableRevisionService;
+pub use self::storage_quota::StorageQuotaService;
+pub use self::tag_rule::TagRuleService;
 pub use self::text::TextService;
+pub use self::thumbnail::ThumbnailService;
+pub use self::upload_rule::UploadRuleService;
 pub use self::user::UserService;
 pub use self::user_bot_owner::UserBotOwnerService;
 pub use self::view::ViewService;
 pub use self::vote::VoteService;
+pub use self::watchlist::WatchlistService;
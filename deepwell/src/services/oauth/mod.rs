@@ -0,0 +1,48 @@
+/*
+ * services/oauth/mod.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The OAuth service, which lets third-party applications act for users.
+//!
+//! This is an OAuth 2.0 authorization server using the authorization code
+//! flow. Users register applications, which ask other users for consent to
+//! a set of scopes. Once granted, the application exchanges the code it was
+//! given for an access token and a refresh token. Each code can only be used
+//! once, and if it is presented again, every token issued for the authorization
+//! is revoked, since the code may have been intercepted.
+//!
+//! Scopes are the names of API methods. Methods which are internal, only
+//! for administrators, or which manage logins and credentials can never be
+//! granted. Whoever serves the API checks each access token here, which also
+//! evaluates the user's own permissions for the method, so an application can
+//! never do more than the user who authorized it.
+//!
+//! Users can list the applications they have authorized and revoke any of
+//! them, which immediately invalidates all of its tokens.
+
+mod prelude {
+    pub use super::super::prelude::*;
+    pub use super::structs::*;
+}
+
+mod service;
+mod structs;
+
+pub use self::service::OauthService;
+pub use self::structs::*;
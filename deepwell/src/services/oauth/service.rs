@@ -0,0 +1,907 @@
+/*
+ * services/oauth/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::oauth_application::{
+    self, Entity as OauthApplicationEntity, Model as OauthApplicationModel,
+};
+use crate::models::oauth_authorization::{
+    self, Entity as OauthAuthorization, Model as OauthAuthorizationModel,
+};
+use crate::models::oauth_code::{self, Entity as OauthCode, Model as OauthCodeModel};
+use crate::models::oauth_token::{self, Entity as OauthToken};
+use crate::services::permission::Requirement;
+use crate::services::{PermissionService, UserService};
+use crate::utils::{hash_token, is_http_url, new_token};
+use data_encoding::BASE64URL_NOPAD;
+use sea_orm::{TransactionTrait, UpdateResult};
+use sea_query::Expr;
+use std::collections::BTreeSet;
+use subtle::ConstantTimeEq;
+use time::OffsetDateTime;
+
+/// The length of generated client identifiers.
+const CLIENT_ID_LENGTH: usize = 32;
+
+/// The longest application name which can be registered.
+const MAXIMUM_NAME_LENGTH: usize = 100;
+
+/// API methods which can never be granted to an application.
+///
+/// These manage logins and credentials, which should
+/// only ever be done by the user themselves.
+const UNGRANTABLE_METHOD_PREFIXES: [&str; 11] = [
+    "impersonation_",
+    "login",
+    "logout",
+    "magic_link_",
+    "mfa_",
+    "oauth_",
+    "recovery_",
+    "refresh_token_",
+    "session_",
+    "user_delete",
+    "user_edit",
+];
+
+#[derive(Debug)]
+pub struct OauthService;
+
+impl OauthService {
+    /// Registers a new application, owned by the given user.
+    pub async fn create_application(
+        ctx: &ServiceContext<'_>,
+        CreateOauthApplication {
+            user_id,
+            name,
+            redirect_uris,
+            scopes,
+            ..
+        }: CreateOauthApplication,
+    ) -> Result<CreateOauthApplicationOutput> {
+        info!("Registering OAuth application '{name}' for user ID {user_id}");
+
+        if name.is_empty() || name.len() > MAXIMUM_NAME_LENGTH {
+            error!("OAuth application name is empty or too long");
+            return Err(Error::OauthApplicationInvalid);
+        }
+
+        if redirect_uris.is_empty() || !redirect_uris.iter().all(|uri| is_http_url(uri)) {
+            error!("OAuth application redirect URIs are missing or not absolute URLs");
+            return Err(Error::OauthApplicationInvalid);
+        }
+
+        if let Some(scope) = scopes.iter().find(|scope| !is_grantable(scope)) {
+            error!("OAuth scope '{scope}' cannot be granted to applications");
+            return Err(Error::OauthApplicationInvalid);
+        }
+
+        let txn = ctx.transaction();
+        let client_id = new_token(CLIENT_ID_LENGTH);
+        let client_secret = new_token(ctx.config().session_token_length);
+        let model = oauth_application::ActiveModel {
+            owner_user_id: Set(user_id),
+            name: Set(name),
+            client_id: Set(client_id.clone()),
+            client_secret_hash: Set(hash_token(&client_secret)),
+            redirect_uris: Set(dedup(redirect_uris)),
+            scopes: Set(dedup(scopes)),
+            ..Default::default()
+        };
+        let application = model.insert(txn).await?;
+
+        Ok(CreateOauthApplicationOutput {
+            application_id: application.application_id,
+            client_id,
+            client_secret,
+        })
+    }
+
+    /// Gets an application by its client identifier, for showing on its consent page.
+    pub async fn get_application(
+        ctx: &ServiceContext<'_>,
+        client_id: &str,
+    ) -> Result<OauthApplication> {
+        let application = Self::get_application_model(ctx, client_id).await?;
+        Ok(application.into())
+    }
+
+    /// Gets all the applications registered by a user.
+    pub async fn get_applications(
+        ctx: &ServiceContext<'_>,
+        user_id: i64,
+    ) -> Result<Vec<OauthApplication>> {
+        let txn = ctx.transaction();
+        let applications = OauthApplicationEntity::find()
+            .filter(
+                Condition::all()
+                    .add(oauth_application::Column::OwnerUserId.eq(user_id))
+                    .add(oauth_application::Column::DeletedAt.is_null()),
+            )
+            .order_by_asc(oauth_application::Column::ApplicationId)
+            .all(txn)
+            .await?;
+
+        Ok(applications
+            .into_iter()
+            .map(OauthApplication::from)
+            .collect())
+    }
+
+    /// Deletes an application, revoking every authorization it was given.
+    pub async fn delete_application(
+        ctx: &ServiceContext<'_>,
+        user_id: i64,
+        application_id: i64,
+    ) -> Result<()> {
+        info!("Deleting OAuth application ID {application_id} for user ID {user_id}");
+
+        let txn = ctx.transaction();
+        let application = OauthApplicationEntity::find()
+            .filter(
+                Condition::all()
+                    .add(oauth_application::Column::ApplicationId.eq(application_id))
+                    .add(oauth_application::Column::OwnerUserId.eq(user_id))
+                    .add(oauth_application::Column::DeletedAt.is_null()),
+            )
+            .one(txn)
+            .await?;
+
+        if application.is_none() {
+            return Err(Error::OauthApplicationNotFound);
+        }
+
+        let model = oauth_application::ActiveModel {
+            application_id: Set(application_id),
+            deleted_at: Set(Some(now())),
+            ..Default::default()
+        };
+        model.update(txn).await?;
+
+        Self::revoke_where(
+            ctx,
+            oauth_authorization::Column::ApplicationId.eq(application_id),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Records a user's consent for an application, returning an authorization code.
+    ///
+    /// If the user already authorized the application, the new scopes are
+    /// added to those they granted before.
+    pub async fn authorize(
+        ctx: &ServiceContext<'_>,
+        AuthorizeOauthApplication {
+            user_id,
+            client_id,
+            redirect_uri,
+            scopes,
+            code_challenge,
+            ..
+        }: AuthorizeOauthApplication,
+    ) -> Result<AuthorizeOauthApplicationOutput> {
+        let txn = ctx.transaction();
+        let application = Self::get_application_model(ctx, &client_id).await?;
+
+        info!(
+            "User ID {user_id} is authorizing OAuth application ID {}",
+            application.application_id,
+        );
+
+        if !application.redirect_uris.contains(&redirect_uri) {
+            error!(
+                "Redirect URI '{redirect_uri}' is not registered for this application"
+            );
+            return Err(Error::OauthRedirectUriInvalid);
+        }
+
+        if scopes.is_empty()
+            || !scopes
+                .iter()
+                .all(|scope| application.scopes.contains(scope))
+        {
+            error!("Requested scopes are empty or not allowed for this application");
+            return Err(Error::OauthScopeInvalid);
+        }
+
+        if let Some(ref code_challenge) = code_challenge {
+            if !is_code_challenge(code_challenge) {
+                error!("PKCE code challenge is not a base64url SHA-256 hash");
+                return Err(Error::OauthGrantInvalid);
+            }
+        }
+
+        // Create or update the user's consent
+        let scopes = dedup(scopes);
+        let existing = OauthAuthorization::find()
+            .filter(
+                Condition::all()
+                    .add(
+                        oauth_authorization::Column::ApplicationId
+                            .eq(application.application_id),
+                    )
+                    .add(oauth_authorization::Column::UserId.eq(user_id)),
+            )
+            .one(txn)
+            .await?;
+
+        let authorization_id = match existing {
+            Some(authorization) => {
+                let mut granted = scopes.clone();
+                if authorization.revoked_at.is_none() {
+                    granted.extend(authorization.scopes);
+                }
+
+                let model = oauth_authorization::ActiveModel {
+                    authorization_id: Set(authorization.authorization_id),
+                    updated_at: Set(Some(now())),
+                    revoked_at: Set(None),
+                    scopes: Set(dedup(granted)),
+                    ..Default::default()
+                };
+                model.update(txn).await?;
+                authorization.authorization_id
+            }
+            None => {
+                let model = oauth_authorization::ActiveModel {
+                    application_id: Set(application.application_id),
+                    user_id: Set(user_id),
+                    scopes: Set(scopes.clone()),
+                    ..Default::default()
+                };
+                let authorization = model.insert(txn).await?;
+                authorization.authorization_id
+            }
+        };
+
+        // Issue authorization code
+        let code = new_token(ctx.config().session_token_length);
+        let created_at = now();
+        let expires_at = created_at + ctx.config().oauth_code_duration;
+        let model = oauth_code::ActiveModel {
+            code_hash: Set(hash_token(&code)),
+            authorization_id: Set(authorization_id),
+            redirect_uri: Set(redirect_uri.clone()),
+            scopes: Set(scopes),
+            code_challenge: Set(code_challenge),
+            created_at: Set(created_at),
+            expires_at: Set(expires_at),
+            used_at: Set(None),
+        };
+        model.insert(txn).await?;
+
+        Ok(AuthorizeOauthApplicationOutput {
+            code,
+            redirect_uri,
+            expires_at,
+        })
+    }
+
+    /// Issues tokens to an application, for an authorization code or refresh token.
+    ///
+    /// Any problem with the grant or client credentials is reported the same
+    /// way, so this does not reveal which part of the request was wrong.
+    pub async fn token(
+        ctx: &ServiceContext<'_>,
+        request: OauthTokenRequest,
+    ) -> Result<OauthTokenOutput> {
+        match request {
+            OauthTokenRequest::AuthorizationCode {
+                client_id,
+                client_secret,
+                code,
+                redirect_uri,
+                code_verifier,
+            } => {
+                let application =
+                    Self::authenticate_client(ctx, &client_id, &client_secret).await?;
+
+                Self::exchange_code(
+                    ctx,
+                    &application,
+                    &code,
+                    &redirect_uri,
+                    code_verifier.as_deref(),
+                )
+                .await
+            }
+            OauthTokenRequest::RefreshToken {
+                client_id,
+                client_secret,
+                refresh_token,
+            } => {
+                let application =
+                    Self::authenticate_client(ctx, &client_id, &client_secret).await?;
+
+                Self::exchange_refresh_token(ctx, &application, &refresh_token).await
+            }
+        }
+    }
+
+    async fn exchange_code(
+        ctx: &ServiceContext<'_>,
+        application: &OauthApplicationModel,
+        code: &str,
+        redirect_uri: &str,
+        code_verifier: Option<&str>,
+    ) -> Result<OauthTokenOutput> {
+        let txn = ctx.transaction();
+        let code_hash = hash_token(code);
+        let code = OauthCode::find_by_id(code_hash.clone())
+            .one(txn)
+            .await?
+            .ok_or(Error::OauthGrantInvalid)?;
+
+        match code_state(&code, now()) {
+            CodeState::Usable => (),
+            CodeState::Expired => {
+                debug!("Authorization code is expired");
+                return Err(Error::OauthGrantInvalid);
+            }
+            CodeState::Replayed => {
+                warn!("Authorization code was reused, revoking its tokens");
+                Self::revoke_tokens_detached(ctx, code.authorization_id).await?;
+                return Err(Error::OauthGrantInvalid);
+            }
+        }
+
+        if code.redirect_uri != redirect_uri {
+            warn!("Authorization code was exchanged with a different redirect URI");
+            return Err(Error::OauthGrantInvalid);
+        }
+
+        match (&code.code_challenge, code_verifier) {
+            (None, _) => (),
+            (Some(challenge), Some(verifier))
+                if BASE64URL_NOPAD.encode(&hash_token(verifier)) == *challenge => {}
+            (Some(_), _) => {
+                warn!("PKCE code verifier does not match its challenge");
+                return Err(Error::OauthGrantInvalid);
+            }
+        }
+
+        let authorization =
+            Self::get_active_authorization(ctx, code.authorization_id, application)
+                .await?;
+
+        // Only use the code if nothing else has in the meantime
+        let UpdateResult { rows_affected } = OauthCode::update_many()
+            .col_expr(oauth_code::Column::UsedAt, Expr::value(now()))
+            .filter(
+                Condition::all()
+                    .add(oauth_code::Column::CodeHash.eq(code_hash))
+                    .add(oauth_code::Column::UsedAt.is_null()),
+            )
+            .exec(txn)
+            .await?;
+
+        if rows_affected == 0 {
+            warn!("Authorization code was used concurrently, revoking its tokens");
+            Self::revoke_tokens_detached(ctx, code.authorization_id).await?;
+            return Err(Error::OauthGrantInvalid);
+        }
+
+        info!(
+            "Issuing OAuth tokens to application ID {} for user ID {}",
+            application.application_id, authorization.user_id,
+        );
+
+        Self::insert_token(ctx, authorization.authorization_id, code.scopes).await
+    }
+
+    /// Revokes every token issued for an authorization, in its own transaction.
+    ///
+    /// This is for when an authorization code is used more than once, which
+    /// means it may have been stolen (see RFC 6749 section 4.1.2). The exchange
+    /// is rejected, so the revocation must be kept even though the caller's
+    /// transaction is rolled back. Nothing is revoked for dry runs.
+    async fn revoke_tokens_detached(
+        ctx: &ServiceContext<'_>,
+        authorization_id: i64,
+    ) -> Result<()> {
+        if ctx.check_only() {
+            debug!("Not revoking OAuth tokens, this is a dry run");
+            return Ok(());
+        }
+
+        let txn = ctx.database().begin().await?;
+        let UpdateResult { rows_affected } = OauthToken::update_many()
+            .col_expr(oauth_token::Column::RevokedAt, Expr::value(now()))
+            .filter(
+                Condition::all()
+                    .add(oauth_token::Column::AuthorizationId.eq(authorization_id))
+                    .add(oauth_token::Column::RevokedAt.is_null()),
+            )
+            .exec(&txn)
+            .await?;
+        txn.commit().await?;
+
+        info!("Revoked {rows_affected} OAuth tokens for authorization ID {authorization_id}");
+        Ok(())
+    }
+
+    async fn exchange_refresh_token(
+        ctx: &ServiceContext<'_>,
+        application: &OauthApplicationModel,
+        refresh_token: &str,
+    ) -> Result<OauthTokenOutput> {
+        let txn = ctx.transaction();
+        let token = OauthToken::find()
+            .filter(oauth_token::Column::RefreshTokenHash.eq(hash_token(refresh_token)))
+            .one(txn)
+            .await?
+            .ok_or(Error::OauthGrantInvalid)?;
+
+        if token.revoked_at.is_some() || token.refresh_expires_at <= now() {
+            debug!("OAuth refresh token is revoked or expired");
+            return Err(Error::OauthGrantInvalid);
+        }
+
+        let authorization =
+            Self::get_active_authorization(ctx, token.authorization_id, application)
+                .await?;
+
+        // Revoke the old token, unless something else already has
+        let UpdateResult { rows_affected } = OauthToken::update_many()
+            .col_expr(oauth_token::Column::RevokedAt, Expr::value(now()))
+            .filter(
+                Condition::all()
+                    .add(oauth_token::Column::TokenId.eq(token.token_id))
+                    .add(oauth_token::Column::RevokedAt.is_null()),
+            )
+            .exec(txn)
+            .await?;
+
+        if rows_affected == 0 {
+            warn!("OAuth refresh token was used concurrently");
+            return Err(Error::OauthGrantInvalid);
+        }
+
+        info!(
+            "Refreshing OAuth tokens for application ID {} and user ID {}",
+            application.application_id, authorization.user_id,
+        );
+
+        // Drop any scopes the user no longer grants
+        let scopes = token
+            .scopes
+            .into_iter()
+            .filter(|scope| authorization.scopes.contains(scope))
+            .collect();
+
+        Self::insert_token(ctx, authorization.authorization_id, scopes).await
+    }
+
+    /// Checks an access token for calling an API method on a site.
+    ///
+    /// Both the scopes granted to the application and the user's
+    /// own permissions for the method must allow it.
+    pub async fn check_token(
+        ctx: &ServiceContext<'_>,
+        CheckOauthToken {
+            access_token,
+            site_id,
+            method,
+        }: CheckOauthToken,
+    ) -> Result<OauthTokenCheck> {
+        let txn = ctx.transaction();
+        let token = OauthToken::find()
+            .filter(
+                Condition::all()
+                    .add(
+                        oauth_token::Column::AccessTokenHash
+                            .eq(hash_token(&access_token)),
+                    )
+                    .add(oauth_token::Column::RevokedAt.is_null())
+                    .add(oauth_token::Column::AccessExpiresAt.gt(now())),
+            )
+            .one(txn)
+            .await?
+            .ok_or(Error::InvalidAuthentication)?;
+
+        let (authorization, application) =
+            OauthAuthorization::find_by_id(token.authorization_id)
+                .find_also_related(OauthApplicationEntity)
+                .one(txn)
+                .await?
+                .ok_or(Error::InvalidAuthentication)?;
+
+        let application_id = match application {
+            Some(application)
+                if authorization.revoked_at.is_none()
+                    && application.deleted_at.is_none() =>
+            {
+                application.application_id
+            }
+            _ => {
+                debug!("OAuth authorization was revoked or its application deleted");
+                return Err(Error::InvalidAuthentication);
+            }
+        };
+
+        let model = oauth_token::ActiveModel {
+            token_id: Set(token.token_id),
+            last_used_at: Set(Some(now())),
+            ..Default::default()
+        };
+        model.update(txn).await?;
+
+        let granted = token.scopes.contains(&method);
+        let permission = PermissionService::check(
+            ctx,
+            site_id,
+            None,
+            Some(authorization.user_id),
+            &method,
+        )
+        .await?;
+
+        Ok(OauthTokenCheck {
+            user_id: authorization.user_id,
+            application_id,
+            allowed: granted && permission.allowed,
+            granted,
+            permission,
+        })
+    }
+
+    /// Gets all the applications a user has authorized.
+    pub async fn get_authorizations(
+        ctx: &ServiceContext<'_>,
+        user_id: i64,
+    ) -> Result<Vec<AuthorizedOauthApplication>> {
+        let txn = ctx.transaction();
+        let authorizations = OauthAuthorization::find()
+            .find_also_related(OauthApplicationEntity)
+            .filter(
+                Condition::all()
+                    .add(oauth_authorization::Column::UserId.eq(user_id))
+                    .add(oauth_authorization::Column::RevokedAt.is_null())
+                    .add(oauth_application::Column::DeletedAt.is_null()),
+            )
+            .order_by_asc(oauth_authorization::Column::CreatedAt)
+            .all(txn)
+            .await?;
+
+        let authorized = authorizations
+            .into_iter()
+            .filter_map(|(authorization, application)| {
+                application.map(|application| AuthorizedOauthApplication {
+                    application_id: application.application_id,
+                    name: application.name,
+                    client_id: application.client_id,
+                    scopes: authorization.scopes,
+                    authorized_at: authorization.created_at,
+                    updated_at: authorization.updated_at,
+                })
+            })
+            .collect();
+
+        Ok(authorized)
+    }
+
+    /// Revokes a user's authorization of an application, and all of its tokens.
+    pub async fn revoke_authorization(
+        ctx: &ServiceContext<'_>,
+        user_id: i64,
+        application_id: i64,
+    ) -> Result<()> {
+        info!("Revoking OAuth application ID {application_id} for user ID {user_id}");
+
+        let revoked = Self::revoke_where(
+            ctx,
+            Condition::all()
+                .add(oauth_authorization::Column::ApplicationId.eq(application_id))
+                .add(oauth_authorization::Column::UserId.eq(user_id)),
+        )
+        .await?;
+
+        if revoked == 0 {
+            return Err(Error::OauthAuthorizationNotFound);
+        }
+
+        Ok(())
+    }
+
+    /// Revokes every application a user has authorized.
+    ///
+    /// # Returns
+    /// The number of revoked authorizations.
+    pub async fn revoke_all(ctx: &ServiceContext<'_>, user_id: i64) -> Result<u64> {
+        info!("Revoking all OAuth authorizations for user ID {user_id}");
+        Self::revoke_where(ctx, oauth_authorization::Column::UserId.eq(user_id)).await
+    }
+
+    /// Deletes all expired authorization codes and tokens.
+    ///
+    /// # Returns
+    /// The number of pruned codes and tokens.
+    pub async fn prune(ctx: &ServiceContext<'_>) -> Result<u64> {
+        info!("Pruning all expired OAuth codes and tokens");
+
+        let txn = ctx.transaction();
+        let DeleteResult {
+            rows_affected: codes,
+        } = OauthCode::delete_many()
+            .filter(oauth_code::Column::ExpiresAt.lte(now()))
+            .exec(txn)
+            .await?;
+
+        let DeleteResult {
+            rows_affected: tokens,
+        } = OauthToken::delete_many()
+            .filter(oauth_token::Column::RefreshExpiresAt.lte(now()))
+            .exec(txn)
+            .await?;
+
+        debug!(
+            "{codes} expired OAuth codes and {tokens} expired OAuth tokens were pruned"
+        );
+        Ok(codes + tokens)
+    }
+
+    /// Revokes the matching active authorizations and their tokens.
+    async fn revoke_where<C>(ctx: &ServiceContext<'_>, condition: C) -> Result<u64>
+    where
+        C: Into<sea_query::ConditionExpression>,
+    {
+        let txn = ctx.transaction();
+        let authorization_ids: Vec<i64> = OauthAuthorization::find()
+            .select_only()
+            .column(oauth_authorization::Column::AuthorizationId)
+            .filter(
+                Condition::all()
+                    .add(condition)
+                    .add(oauth_authorization::Column::RevokedAt.is_null()),
+            )
+            .into_tuple()
+            .all(txn)
+            .await?;
+
+        if authorization_ids.is_empty() {
+            return Ok(0);
+        }
+
+        let UpdateResult { rows_affected } = OauthAuthorization::update_many()
+            .col_expr(oauth_authorization::Column::RevokedAt, Expr::value(now()))
+            .filter(
+                oauth_authorization::Column::AuthorizationId
+                    .is_in(authorization_ids.iter().copied()),
+            )
+            .exec(txn)
+            .await?;
+
+        OauthToken::update_many()
+            .col_expr(oauth_token::Column::RevokedAt, Expr::value(now()))
+            .filter(
+                Condition::all()
+                    .add(oauth_token::Column::AuthorizationId.is_in(authorization_ids))
+                    .add(oauth_token::Column::RevokedAt.is_null()),
+            )
+            .exec(txn)
+            .await?;
+
+        Ok(rows_affected)
+    }
+
+    async fn get_application_model(
+        ctx: &ServiceContext<'_>,
+        client_id: &str,
+    ) -> Result<OauthApplicationModel> {
+        let txn = ctx.transaction();
+        OauthApplicationEntity::find()
+            .filter(
+                Condition::all()
+                    .add(oauth_application::Column::ClientId.eq(client_id))
+                    .add(oauth_application::Column::DeletedAt.is_null()),
+            )
+            .one(txn)
+            .await?
+            .ok_or(Error::OauthApplicationNotFound)
+    }
+
+    async fn authenticate_client(
+        ctx: &ServiceContext<'_>,
+        client_id: &str,
+        client_secret: &str,
+    ) -> Result<OauthApplicationModel> {
+        let application = match Self::get_application_model(ctx, client_id).await {
+            Ok(application) => application,
+            Err(Error::OauthApplicationNotFound) => return Err(Error::OauthGrantInvalid),
+            Err(error) => return Err(error),
+        };
+
+        let secret_hash = hash_token(client_secret);
+        if !bool::from(secret_hash.ct_eq(&application.client_secret_hash)) {
+            warn!(
+                "Invalid client secret for OAuth application ID {}",
+                application.application_id,
+            );
+            return Err(Error::OauthGrantInvalid);
+        }
+
+        Ok(application)
+    }
+
+    /// Gets an authorization which is still in effect for the given application.
+    async fn get_active_authorization(
+        ctx: &ServiceContext<'_>,
+        authorization_id: i64,
+        application: &OauthApplicationModel,
+    ) -> Result<OauthAuthorizationModel> {
+        let txn = ctx.transaction();
+        let authorization = OauthAuthorization::find_by_id(authorization_id)
+            .one(txn)
+            .await?
+            .ok_or(Error::OauthGrantInvalid)?;
+
+        if authorization.application_id != application.application_id {
+            warn!(
+                "OAuth grant for application ID {} was presented by application ID {}",
+                authorization.application_id, application.application_id,
+            );
+            return Err(Error::OauthGrantInvalid);
+        }
+
+        if authorization.revoked_at.is_some() {
+            debug!("OAuth authorization has been revoked");
+            return Err(Error::OauthGrantInvalid);
+        }
+
+        // Ensure the user can still log in
+        let user = UserService::get(ctx, Reference::Id(authorization.user_id)).await?;
+        if user.deleted_at.is_some() {
+            debug!("User who authorized the application has been deleted");
+            return Err(Error::OauthGrantInvalid);
+        }
+
+        Ok(authorization)
+    }
+
+    /// Inserts a new access and refresh token pair.
+    async fn insert_token(
+        ctx: &ServiceContext<'_>,
+        authorization_id: i64,
+        scopes: Vec<String>,
+    ) -> Result<OauthTokenOutput> {
+        let txn = ctx.transaction();
+        let config = ctx.config();
+        let access_token = new_token(config.session_token_length);
+        let refresh_token = new_token(config.session_token_length);
+
+        let created_at = now();
+        let model = oauth_token::ActiveModel {
+            authorization_id: Set(authorization_id),
+            access_token_hash: Set(hash_token(&access_token)),
+            refresh_token_hash: Set(hash_token(&refresh_token)),
+            scopes: Set(scopes.clone()),
+            created_at: Set(created_at),
+            access_expires_at: Set(created_at + config.oauth_access_token_duration),
+            refresh_expires_at: Set(created_at + config.oauth_refresh_token_duration),
+            ..Default::default()
+        };
+        model.insert(txn).await?;
+
+        Ok(OauthTokenOutput {
+            access_token,
+            token_type: "Bearer",
+            expires_in: config.oauth_access_token_duration.whole_seconds(),
+            refresh_token,
+            scope: scopes.join(" "),
+        })
+    }
+}
+
+/// Whether an authorization code can be exchanged for tokens.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum CodeState {
+    Usable,
+    Expired,
+
+    /// The code was already exchanged, so whoever presented it may have stolen it.
+    Replayed,
+}
+
+fn code_state(code: &OauthCodeModel, now: OffsetDateTime) -> CodeState {
+    if code.used_at.is_some() {
+        CodeState::Replayed
+    } else if code.expires_at <= now {
+        CodeState::Expired
+    } else {
+        CodeState::Usable
+    }
+}
+
+/// Determines if an API method can be granted to applications as a scope.
+fn is_grantable(method: &str) -> bool {
+    match PermissionService::requirement(method) {
+        None | Some(Requirement::Internal | Requirement::InstanceAdmin) => false,
+        Some(_) => !UNGRANTABLE_METHOD_PREFIXES
+            .iter()
+            .any(|prefix| method.starts_with(prefix)),
+    }
+}
+
+/// Determines if the string is an `S256` PKCE challenge.
+fn is_code_challenge(value: &str) -> bool {
+    matches!(BASE64URL_NOPAD.decode(value.as_bytes()), Ok(bytes) if bytes.len() == 32)
+}
+
+fn dedup(values: Vec<String>) -> Vec<String> {
+    values
+        .into_iter()
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+#[test]
+fn grantable_scopes() {
+    assert!(is_grantable("page_get"));
+    assert!(is_grantable("vote_set"));
+    assert!(!is_grantable("login"));
+    assert!(!is_grantable("mfa_disable"));
+    assert!(!is_grantable("session_renew"));
+    assert!(!is_grantable("user_edit"));
+    assert!(!is_grantable("oauth_authorize"));
+    assert!(!is_grantable("audit_log_get"));
+    assert!(!is_grantable("not_a_method"));
+}
+
+#[test]
+fn code_challenges() {
+    let challenge = BASE64URL_NOPAD.encode(&hash_token("verifier"));
+    assert!(is_code_challenge(&challenge));
+    assert!(!is_code_challenge("short"));
+    assert!(!is_code_challenge(&format!("{challenge}=")));
+    assert!(!is_code_challenge(""));
+}
+
+#[test]
+fn code_states() {
+    use time::Duration;
+
+    let now = now();
+    let mut code = OauthCodeModel {
+        code_hash: hash_token("code"),
+        authorization_id: 1,
+        redirect_uri: str!("https://example.com/callback"),
+        scopes: vec![str!("page_get")],
+        code_challenge: None,
+        created_at: now - Duration::minutes(1),
+        expires_at: now + Duration::minutes(9),
+        used_at: None,
+    };
+    assert_eq!(code_state(&code, now), CodeState::Usable);
+    assert_eq!(
+        code_state(&code, now + Duration::minutes(9)),
+        CodeState::Expired
+    );
+
+    // Reuse takes precedence, so tokens are revoked even once the code expires
+    code.used_at = Some(now);
+    assert_eq!(code_state(&code, now), CodeState::Replayed);
+    assert_eq!(
+        code_state(&code, now + Duration::hours(1)),
+        CodeState::Replayed
+    );
+}
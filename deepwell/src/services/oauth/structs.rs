@@ -0,0 +1,182 @@
+/*
+ * services/oauth/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::models::oauth_application::Model as OauthApplicationModel;
+use crate::services::permission::OperationPermission;
+use time::OffsetDateTime;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CreateOauthApplication {
+    pub user_id: i64,
+    pub session_token: String,
+    pub name: String,
+    pub redirect_uris: Vec<String>,
+
+    /// The API methods the application may ask users for.
+    pub scopes: Vec<String>,
+}
+
+/// A newly registered application.
+///
+/// The client secret is only available here, and must be kept by the application.
+#[derive(Serialize, Debug, Clone)]
+pub struct CreateOauthApplicationOutput {
+    pub application_id: i64,
+    pub client_id: String,
+    pub client_secret: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct DeleteOauthApplication {
+    pub user_id: i64,
+    pub session_token: String,
+    pub application_id: i64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetOauthApplications {
+    pub user_id: i64,
+    pub session_token: String,
+}
+
+/// Public information about an application, as shown on its consent page.
+#[derive(Serialize, Debug, Clone)]
+pub struct OauthApplication {
+    pub application_id: i64,
+    pub owner_user_id: i64,
+    pub created_at: OffsetDateTime,
+    pub updated_at: Option<OffsetDateTime>,
+    pub name: String,
+    pub client_id: String,
+    pub redirect_uris: Vec<String>,
+    pub scopes: Vec<String>,
+}
+
+impl From<OauthApplicationModel> for OauthApplication {
+    fn from(model: OauthApplicationModel) -> Self {
+        OauthApplication {
+            application_id: model.application_id,
+            owner_user_id: model.owner_user_id,
+            created_at: model.created_at,
+            updated_at: model.updated_at,
+            name: model.name,
+            client_id: model.client_id,
+            redirect_uris: model.redirect_uris,
+            scopes: model.scopes,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct AuthorizeOauthApplication {
+    pub user_id: i64,
+    pub session_token: String,
+    pub client_id: String,
+    pub redirect_uri: String,
+    pub scopes: Vec<String>,
+
+    /// The PKCE code challenge, using the `S256` method.
+    #[serde(default)]
+    pub code_challenge: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct AuthorizeOauthApplicationOutput {
+    pub code: String,
+    pub redirect_uri: String,
+    pub expires_at: OffsetDateTime,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "grant_type", rename_all = "snake_case")]
+pub enum OauthTokenRequest {
+    AuthorizationCode {
+        client_id: String,
+        client_secret: String,
+        code: String,
+        redirect_uri: String,
+
+        #[serde(default)]
+        code_verifier: Option<String>,
+    },
+    RefreshToken {
+        client_id: String,
+        client_secret: String,
+        refresh_token: String,
+    },
+}
+
+/// A token response, with the fields named as in RFC 6749.
+#[derive(Serialize, Debug, Clone)]
+pub struct OauthTokenOutput {
+    pub access_token: String,
+    pub token_type: &'static str,
+
+    /// How many seconds until the access token expires.
+    pub expires_in: i64,
+    pub refresh_token: String,
+
+    /// The granted scopes, separated by spaces.
+    pub scope: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CheckOauthToken {
+    pub access_token: String,
+    pub site_id: i64,
+    pub method: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct OauthTokenCheck {
+    pub user_id: i64,
+    pub application_id: i64,
+
+    /// Whether the token may be used for this method, and the user may call it.
+    pub allowed: bool,
+
+    /// Whether the user granted the application this method as a scope.
+    pub granted: bool,
+    pub permission: OperationPermission,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetOauthAuthorizations {
+    pub user_id: i64,
+    pub session_token: String,
+}
+
+/// An application the user has authorized, for listing on their settings page.
+#[derive(Serialize, Debug, Clone)]
+pub struct AuthorizedOauthApplication {
+    pub application_id: i64,
+    pub name: String,
+    pub client_id: String,
+    pub scopes: Vec<String>,
+    pub authorized_at: OffsetDateTime,
+    pub updated_at: Option<OffsetDateTime>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct RevokeOauthAuthorization {
+    pub user_id: i64,
+    pub session_token: String,
+    pub application_id: i64,
+}
@@ -19,8 +19,10 @@
  */
 
 use super::prelude::*;
+use crate::models::file::Entity as File;
 use crate::models::page::Model as PageModel;
-use crate::services::{JobService, LinkService, PageService};
+use crate::services::job::Job;
+use crate::services::{JobService, LinkService, PageService, PublicApiService};
 use crate::utils::split_category_name;
 use crate::web::{ConnectionType, PageOrder};
 
@@ -46,6 +48,7 @@ impl OutdateService {
                 page_slug,
                 depth,
             ),
+            OutdateService::outdate_public_page(ctx, site_id, page_id),
         )?;
 
         Ok(())
@@ -62,6 +65,7 @@ impl OutdateService {
         try_join!(
             Self::process_page_edit(ctx, site_id, page_id, slug, depth),
             Self::outdate_incoming_links(ctx, page_id, depth),
+            Self::outdate_public_slug(ctx, site_id, slug),
         )?;
 
         Ok(())
@@ -150,31 +154,157 @@ impl OutdateService {
         // If a template page has been updated,
         // we need to recompile everything in that category.
         if page_slug == "_template" {
-            let category_select = if category_slug == "_default" {
-                // If the category is _default, we need to recompile everything.
-                // All other categories may inherit from _default.
-                //
-                // Specifying "None" here means that we aren't filtering by category.
-                None
-            } else {
-                // Otherwise, filter by whatever category slug we have here.
-                Some(category_slug.into())
-            };
-
-            let pages = PageService::get_all(
-                ctx,
-                site_id,
-                category_select,
-                Some(false),
-                PageOrder::default(),
-            )
-            .await?;
-
-            for page in pages {
-                Self::outdate(ctx, page.page_id, depth).await?;
-            }
+            Self::outdate_category(ctx, site_id, category_slug, depth).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Queues all the pages in a category for re-rendering.
+    pub async fn outdate_category(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        category_slug: &str,
+        depth: u32,
+    ) -> Result<()> {
+        let category_select = if category_slug == "_default" {
+            // If the category is _default, we need to recompile everything.
+            // All other categories may inherit from _default.
+            //
+            // Specifying "None" here means that we aren't filtering by category.
+            None
+        } else {
+            // Otherwise, filter by whatever category slug we have here.
+            Some(category_slug.into())
+        };
+
+        let pages = PageService::get_all(
+            ctx,
+            site_id,
+            category_select,
+            Some(false),
+            PageOrder::default(),
+        )
+        .await?;
+
+        for page in pages {
+            Self::outdate(ctx, page.page_id, depth).await?;
         }
 
         Ok(())
     }
+
+    /// Queues the pages displaying this file for re-rendering.
+    ///
+    /// These are the pages it was embedded in when they were last rendered,
+    /// such as by an image or file card, wherever they are.
+    pub async fn outdate_file_embeds(
+        ctx: &ServiceContext<'_>,
+        file_id: i64,
+        depth: u32,
+    ) -> Result<()> {
+        for page_id in LinkService::get_file_embeds(ctx, file_id).await? {
+            Self::outdate(ctx, page_id, depth).await?;
+        }
+        Ok(())
+    }
+
+    /// Queues the pages referring to this file by its current name for re-rendering.
+    ///
+    /// This is needed when a file appears at a name, since pages which referred
+    /// to it while it was missing have no embed of it to be found by.
+    pub async fn outdate_file_references(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        page_slug: &str,
+        file_id: i64,
+        depth: u32,
+    ) -> Result<()> {
+        let txn = ctx.transaction();
+        let file = File::find_by_id(file_id)
+            .one(txn)
+            .await?
+            .ok_or(Error::FileNotFound)?;
+
+        for page_id in
+            LinkService::get_file_references_to(ctx, site_id, page_slug, &file.name)
+                .await?
+        {
+            Self::outdate(ctx, page_id, depth).await?;
+        }
+        Ok(())
+    }
+
+    /// Purges a page's contents and its site's listing from the public API cache.
+    pub async fn outdate_public_page(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        page_id: i64,
+    ) -> Result<()> {
+        let keys = vec![
+            PublicApiService::page_key(page_id),
+            PublicApiService::listing_key(site_id),
+        ];
+
+        Self::purge_public_cache(ctx, keys).await
+    }
+
+    /// Purges every page and listing in a site from the public API cache.
+    ///
+    /// This is needed when who may view the site's pages changes.
+    pub async fn outdate_public_site(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+    ) -> Result<()> {
+        let keys = vec![PublicApiService::site_key(site_id)];
+        Self::purge_public_cache(ctx, keys).await
+    }
+
+    /// Purges several pages in a site from the public API cache at once.
+    pub async fn outdate_public_pages(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        page_ids: &[i64],
+    ) -> Result<()> {
+        let mut keys = page_ids
+            .iter()
+            .map(|&page_id| PublicApiService::page_key(page_id))
+            .collect::<Vec<_>>();
+
+        keys.push(PublicApiService::listing_key(site_id));
+        Self::purge_public_cache(ctx, keys).await
+    }
+
+    /// Purges any response for this slug from the public API cache.
+    ///
+    /// This is needed when a page appears or disappears at a slug,
+    /// since a missing page has no page ID to be purged by.
+    pub async fn outdate_public_slug(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        slug: &str,
+    ) -> Result<()> {
+        let keys = vec![PublicApiService::slug_key(site_id, slug)];
+        Self::purge_public_cache(ctx, keys).await
+    }
+
+    /// Purges a user's profile from the public API cache.
+    pub async fn outdate_public_user(
+        ctx: &ServiceContext<'_>,
+        user_id: i64,
+    ) -> Result<()> {
+        let keys = vec![PublicApiService::user_key(user_id)];
+        Self::purge_public_cache(ctx, keys).await
+    }
+
+    async fn purge_public_cache(
+        ctx: &ServiceContext<'_>,
+        keys: Vec<String>,
+    ) -> Result<()> {
+        if ctx.config().public_api_purge_url.is_none() {
+            return Ok(());
+        }
+
+        JobService::queue_job(ctx, &Job::PurgeEdgeCache { keys }, None).await
+    }
 }
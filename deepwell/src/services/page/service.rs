@@ -20,19 +20,27 @@
 
 use super::prelude::*;
 use crate::models::page::{self, Entity as Page, Model as PageModel};
-use crate::models::page_category::Model as PageCategoryModel;
+use crate::models::page_category::{self, Model as PageCategoryModel};
+use crate::models::page_revision::Model as PageRevisionModel;
 use crate::services::filter::{FilterClass, FilterType};
 use crate::services::page_revision::{
-    CreateFirstPageRevision, CreateFirstPageRevisionOutput, CreatePageRevision,
-    CreatePageRevisionBody, CreatePageRevisionOutput, CreateResurrectionPageRevision,
-    CreateTombstonePageRevision,
+    merge_tags, merge_value, merge_wikitext, CreateFirstPageRevision,
+    CreateFirstPageRevisionOutput, CreatePageRevision, CreatePageRevisionBody,
+    CreatePageRevisionOutput, CreateResurrectionPageRevision,
+    CreateTombstonePageRevision, EditConflict,
 };
-use crate::services::{CategoryService, FilterService, PageRevisionService, TextService};
-use crate::utils::{get_category_name, trim_default};
+use crate::services::{
+    CategoryService, FilterService, PageFormService, PageRevisionService, TextService,
+};
+use crate::utils::{get_category_name, is_http_url, trim_default};
 use crate::web::PageOrder;
 use sea_orm::ActiveValue;
+use std::mem;
 use wikidot_normalize::normalize;
 
+/// The maximum number of indexable pages which can be retrieved at once.
+const MAXIMUM_INDEXABLE_LIMIT: u64 = 1000;
+
 #[derive(Debug)]
 pub struct PageService;
 
@@ -48,6 +56,7 @@ impl PageService {
             revision_comments: comments,
             user_id,
             bypass_filter,
+            form_data,
         }: CreatePage,
     ) -> Result<CreatePageOutput> {
         let txn = ctx.transaction();
@@ -56,6 +65,13 @@ impl PageService {
         normalize(&mut slug);
         Self::check_conflicts(ctx, site_id, &slug, "create").await?;
 
+        // Pages with a data form may be created from their field values
+        let wikitext = Some(wikitext).filter(|wikitext| !wikitext.is_empty());
+        let wikitext =
+            PageFormService::build_source(ctx, site_id, &slug, wikitext, form_data)
+                .await?
+                .unwrap_or_default();
+
         // Perform filter validation
         if !bypass_filter {
             Self::run_filter(
@@ -123,17 +139,43 @@ impl PageService {
             page: reference,
             revision_comments: comments,
             user_id,
-            body:
-                EditPageBody {
-                    wikitext,
-                    title,
-                    alt_title,
-                    tags,
-                },
+            base_revision_id,
+            mut body,
         }: EditPage<'_>,
     ) -> Result<Option<EditPageOutput>> {
         let txn = ctx.transaction();
-        let PageModel { page_id, .. } = Self::get(ctx, site_id, reference).await?;
+        let PageModel { page_id, slug, .. } = Self::get(ctx, site_id, reference).await?;
+
+        // Pages with a data form may be edited by their field values
+        body.wikitext = PageFormService::build_source(
+            ctx,
+            site_id,
+            &slug,
+            body.wikitext.into(),
+            mem::take(&mut body.form_data).into(),
+        )
+        .await?
+        .into();
+
+        // Get latest revision
+        let last_revision =
+            PageRevisionService::get_latest(ctx, site_id, page_id).await?;
+
+        // If someone else saved since this edit was started,
+        // merge with their changes instead of overwriting them.
+        let EditPageBody {
+            wikitext,
+            title,
+            alt_title,
+            tags,
+            ..
+        } = match base_revision_id {
+            Some(base_revision_id) if base_revision_id != last_revision.revision_id => {
+                Self::merge_edit(ctx, page_id, base_revision_id, &last_revision, body)
+                    .await?
+            }
+            _ => body,
+        };
 
         // Perform filter validation
         Self::run_filter(
@@ -149,10 +191,6 @@ impl PageService {
         )
         .await?;
 
-        // Get latest revision
-        let last_revision =
-            PageRevisionService::get_latest(ctx, site_id, page_id).await?;
-
         // Create new revision
         //
         // A response of None means no revision was created
@@ -201,6 +239,118 @@ impl PageService {
         Ok(revision_output)
     }
 
+    /// Merges an edit made against an older revision with the changes since.
+    ///
+    /// Only the fields the edit sets are merged. If the edit and the latest
+    /// revision changed the same wikitext lines or title differently, this
+    /// fails, returning each conflict so the user can resolve them.
+    async fn merge_edit(
+        ctx: &ServiceContext<'_>,
+        page_id: i64,
+        base_revision_id: i64,
+        last_revision: &PageRevisionModel,
+        body: EditPageBody,
+    ) -> Result<EditPageBody> {
+        info!(
+            "Merging edit of page ID {page_id} from revision ID {base_revision_id} into revision ID {}",
+            last_revision.revision_id,
+        );
+
+        let base_revision =
+            PageRevisionService::get_direct(ctx, base_revision_id).await?;
+        if base_revision.page_id != page_id {
+            error!("Base revision ID {base_revision_id} is not of page ID {page_id}",);
+            return Err(Error::PageRevisionNotFound);
+        }
+
+        let mut conflicts = Vec::new();
+
+        let wikitext = match body.wikitext {
+            ProvidedValue::Set(wikitext) => {
+                let (base_wikitext, last_wikitext) = try_join!(
+                    TextService::get(ctx, &base_revision.wikitext_hash),
+                    TextService::get(ctx, &last_revision.wikitext_hash),
+                )?;
+
+                match merge_wikitext(&base_wikitext, &last_wikitext, &wikitext) {
+                    Ok(merged) => ProvidedValue::Set(merged),
+                    Err(mut wikitext_conflicts) => {
+                        conflicts.append(&mut wikitext_conflicts);
+                        ProvidedValue::Unset
+                    }
+                }
+            }
+            ProvidedValue::Unset => ProvidedValue::Unset,
+        };
+
+        let title = match body.title {
+            ProvidedValue::Set(title) => {
+                match merge_value(&base_revision.title, &last_revision.title, &title) {
+                    Some(title) => ProvidedValue::Set(title.clone()),
+                    None => {
+                        conflicts.push(EditConflict::Title {
+                            base: base_revision.title.clone(),
+                            current: last_revision.title.clone(),
+                            edit: title,
+                        });
+                        ProvidedValue::Unset
+                    }
+                }
+            }
+            ProvidedValue::Unset => ProvidedValue::Unset,
+        };
+
+        let alt_title = match body.alt_title {
+            ProvidedValue::Set(alt_title) => match merge_value(
+                &base_revision.alt_title,
+                &last_revision.alt_title,
+                &alt_title,
+            ) {
+                Some(alt_title) => ProvidedValue::Set(alt_title.clone()),
+                None => {
+                    conflicts.push(EditConflict::AltTitle {
+                        base: base_revision.alt_title.clone(),
+                        current: last_revision.alt_title.clone(),
+                        edit: alt_title,
+                    });
+                    ProvidedValue::Unset
+                }
+            },
+            ProvidedValue::Unset => ProvidedValue::Unset,
+        };
+
+        let tags = match body.tags {
+            ProvidedValue::Set(tags) => ProvidedValue::Set(merge_tags(
+                &base_revision.tags,
+                &last_revision.tags,
+                &tags,
+            )),
+            ProvidedValue::Unset => ProvidedValue::Unset,
+        };
+
+        if !conflicts.is_empty() {
+            warn!(
+                "Edit of page ID {page_id} has {} conflict(s) with revision ID {}",
+                conflicts.len(),
+                last_revision.revision_id,
+            );
+
+            return Err(Error::PageEditConflict {
+                base_revision_id,
+                latest_revision_id: last_revision.revision_id,
+                conflicts: serde_json::to_value(&conflicts)?,
+            });
+        }
+
+        Ok(EditPageBody {
+            wikitext,
+            title,
+            alt_title,
+            tags,
+            form_data: ProvidedValue::Unset,
+        })
+    }
+
     /// Moves a page from from one slug to another.
     pub async fn r#move(
         ctx: &ServiceContext<'_>,
@@ -497,7 +647,7 @@ impl PageService {
         _ctx: &ServiceContext<'_>,
         _site_id: i64,
         _page_id: i64,
-        _revision_number: i32,
+        _revision_number: i64,
     ) -> Result<EditPageOutput> {
         todo!()
     }
@@ -685,10 +835,102 @@ impl PageService {
         Ok(pages)
     }
 
+    /// Sets the search engine indexing settings for this page.
+    ///
+    /// These are not page revision fields, so this does not create a revision.
+    pub async fn edit_indexing(
+        ctx: &ServiceContext<'_>,
+        EditPageIndexing {
+            site_id,
+            page: reference,
+            body:
+                EditPageIndexingBody {
+                    noindex,
+                    nofollow,
+                    canonical_url,
+                },
+        }: EditPageIndexing<'_>,
+    ) -> Result<PageIndexing> {
+        if let ProvidedValue::Set(Some(ref url)) = canonical_url {
+            if !is_http_url(url) {
+                error!("Canonical URL for page is invalid: {url}");
+                return Err(Error::CanonicalUrlInvalid);
+            }
+        }
+
+        let txn = ctx.transaction();
+        let PageModel { page_id, .. } = Self::get(ctx, site_id, reference).await?;
+
+        let model = page::ActiveModel {
+            page_id: Set(page_id),
+            noindex: noindex.into_active_value(),
+            nofollow: nofollow.into_active_value(),
+            canonical_url: canonical_url.into_active_value(),
+            updated_at: Set(Some(now())),
+            ..Default::default()
+        };
+
+        let page = model.update(txn).await?;
+        Self::get_indexing(ctx, &page).await
+    }
+
+    /// Gets the effective indexing settings for a page, after category inheritance.
+    pub async fn get_indexing(
+        ctx: &ServiceContext<'_>,
+        page: &PageModel,
+    ) -> Result<PageIndexing> {
+        let category = CategoryService::get(
+            ctx,
+            page.site_id,
+            Reference::from(page.page_category_id),
+        )
+        .await?;
+
+        Ok(PageIndexing::resolve(page, &category))
+    }
+
+    /// Gets extant pages in a site which may be indexed by search engines.
+    ///
+    /// This is what sitemaps and feeds should be built from, so that
+    /// `noindex` pages are not advertised. Pages are ordered by slug,
+    /// and the last slug returned is the cursor for the next set.
+    pub async fn get_indexable(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        after_slug: Option<&str>,
+        limit: u64,
+    ) -> Result<Vec<PageModel>> {
+        let mut condition = Condition::all()
+            .add(page::Column::SiteId.eq(site_id))
+            .add(page::Column::DeletedAt.is_null())
+            .add(
+                Condition::any().add(page::Column::Noindex.eq(false)).add(
+                    Condition::all()
+                        .add(page::Column::Noindex.is_null())
+                        .add(page_category::Column::Noindex.eq(false)),
+                ),
+            );
+
+        if let Some(after_slug) = after_slug {
+            condition = condition.add(page::Column::Slug.gt(after_slug));
+        }
+
+        let txn = ctx.transaction();
+        let pages = Page::find()
+            .inner_join(page_category::Entity)
+            .filter(condition)
+            .order_by_asc(page::Column::Slug)
+            .limit(limit.min(MAXIMUM_INDEXABLE_LIMIT))
+            .all(txn)
+            .await?;
+
+        Ok(pages)
+    }
+
     /// Checks to see if a page already exists at the slug specified.
     ///
     /// If so, this method fails with `Error::PageExists`. Otherwise it returns nothing.
-    async fn check_conflicts(
+    pub(crate) async fn check_conflicts(
         ctx: &ServiceContext<'_>,
         site_id: i64,
         slug: &str,
@@ -19,16 +19,22 @@
  */
 
 use super::prelude::*;
+use crate::models::page::Model as PageModel;
+use crate::models::page_category::Model as PageCategoryModel;
 use crate::models::sea_orm_active_enums::PageRevisionType;
+use crate::services::page_form::FormValues;
 use crate::services::page_revision::CreatePageRevisionOutput;
 use crate::services::score::ScoreValue;
 use crate::web::PageDetails;
 use ftml::parsing::ParseError;
+use serde_json::Value as JsonValue;
 use time::OffsetDateTime;
 
 #[derive(Deserialize, Debug, Clone)]
 pub struct CreatePage {
     pub site_id: i64,
+
+    #[serde(default)]
     pub wikitext: String,
     pub title: String,
     pub alt_title: Option<String>,
@@ -38,6 +44,12 @@ pub struct CreatePage {
 
     #[serde(default)]
     pub bypass_filter: bool,
+
+    /// Field values, for pages in a category with a data form.
+    ///
+    /// These are written out as the page's wikitext, so it can't also be given.
+    #[serde(default)]
+    pub form_data: Option<FormValues>,
 }
 
 #[derive(Serialize, Debug, Clone)]
@@ -87,7 +99,7 @@ pub struct GetPageOutput {
     pub page_created_at: OffsetDateTime,
     pub page_updated_at: Option<OffsetDateTime>,
     pub page_deleted_at: Option<OffsetDateTime>,
-    pub page_revision_count: i32,
+    pub page_revision_count: i64,
     pub site_id: i64,
     pub page_category_id: i64,
     pub page_category_slug: String,
@@ -95,12 +107,14 @@ pub struct GetPageOutput {
     pub revision_id: i64,
     pub revision_type: PageRevisionType,
     pub revision_created_at: OffsetDateTime,
-    pub revision_number: i32,
+    pub revision_number: i64,
     pub revision_user_id: i64,
     pub wikitext: Option<String>,
     pub compiled_html: Option<String>,
     pub compiled_at: OffsetDateTime,
     pub compiled_generator: String,
+    pub content_stats: JsonValue,
+    pub compiled_csp: JsonValue,
     pub revision_comments: String,
     pub hidden_fields: Vec<String>,
     pub title: String,
@@ -117,6 +131,13 @@ pub struct EditPage<'a> {
     pub revision_comments: String,
     pub user_id: i64,
 
+    /// The revision this edit was made against.
+    ///
+    /// If the page has been edited since, the changes are merged.
+    /// When absent, the edit is applied over the latest revision as-is.
+    #[serde(default)]
+    pub base_revision_id: Option<i64>,
+
     #[serde(flatten)]
     pub body: EditPageBody,
 }
@@ -128,6 +149,9 @@ pub struct EditPageBody {
     pub title: ProvidedValue<String>,
     pub alt_title: ProvidedValue<Option<String>>,
     pub tags: ProvidedValue<Vec<String>>,
+
+    /// Field values, replacing the wikitext. See `CreatePage`.
+    pub form_data: ProvidedValue<FormValues>,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -145,7 +169,7 @@ pub struct MovePageOutput {
     pub old_slug: String,
     pub new_slug: String,
     pub revision_id: i64,
-    pub revision_number: i32,
+    pub revision_number: i64,
     pub parser_errors: Option<Vec<ParseError>>,
 }
 
@@ -161,7 +185,7 @@ pub struct DeletePage<'a> {
 pub struct DeletePageOutput {
     page_id: i64,
     revision_id: i64,
-    revision_number: i32,
+    revision_number: i64,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -177,7 +201,7 @@ pub struct RestorePage {
 pub struct RestorePageOutput {
     slug: String,
     revision_id: i64,
-    revision_number: i32,
+    revision_number: i64,
     parser_errors: Vec<ParseError>,
 }
 
@@ -185,11 +209,75 @@ pub struct RestorePageOutput {
 pub struct RollbackPage<'a> {
     pub site_id: i64,
     pub page: Reference<'a>,
-    pub revision_number: i32,
+    pub revision_number: i64,
     pub revision_comments: String,
     pub user_id: i64,
 }
 
+#[derive(Deserialize, Debug, Clone)]
+pub struct EditPageIndexing<'a> {
+    pub site_id: i64,
+    pub page: Reference<'a>,
+
+    #[serde(flatten)]
+    pub body: EditPageIndexingBody,
+}
+
+/// Indexing settings for a page.
+///
+/// Setting `noindex` or `nofollow` to `None` means the page
+/// uses whatever its category has.
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct EditPageIndexingBody {
+    pub noindex: ProvidedValue<Option<bool>>,
+    pub nofollow: ProvidedValue<Option<bool>>,
+    pub canonical_url: ProvidedValue<Option<String>>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetIndexablePages<'a> {
+    pub site: Reference<'a>,
+
+    /// If set, only pages with a later slug are returned.
+    #[serde(default)]
+    pub after_slug: Option<String>,
+
+    pub limit: u64,
+}
+
+/// The effective search engine indexing settings for a page.
+///
+/// This is what the frontend uses to emit `robots` and `canonical` tags.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct PageIndexing {
+    pub noindex: bool,
+    pub nofollow: bool,
+    pub canonical_url: Option<String>,
+}
+
+impl PageIndexing {
+    /// Combines the page's settings with its category's.
+    ///
+    /// Page settings take priority. A category canonical URL is
+    /// a base, to which the page's slug is appended.
+    pub fn resolve(page: &PageModel, category: &PageCategoryModel) -> Self {
+        let canonical_url = match (&page.canonical_url, &category.canonical_url) {
+            (Some(url), _) => Some(url.clone()),
+            (None, Some(base)) => {
+                Some(format!("{}/{}", base.trim_end_matches('/'), page.slug))
+            }
+            (None, None) => None,
+        };
+
+        PageIndexing {
+            noindex: page.noindex.unwrap_or(category.noindex),
+            nofollow: page.nofollow.unwrap_or(category.nofollow),
+            canonical_url,
+        }
+    }
+}
+
 pub type EditPageOutput = CreatePageRevisionOutput;
 
 impl From<(CreatePageRevisionOutput, i64)> for DeletePageOutput {
@@ -242,3 +330,63 @@ impl From<(CreatePageRevisionOutput, String)> for RestorePageOutput {
         }
     }
 }
+
+#[test]
+fn page_indexing_resolve() {
+    let category = PageCategoryModel {
+        category_id: 1,
+        created_at: now(),
+        updated_at: None,
+        version: 0,
+        site_id: 1,
+        slug: str!("archive"),
+        noindex: true,
+        nofollow: false,
+        canonical_url: Some(str!("https://mirror.example.com/")),
+        parser_profile: None,
+        rating_module: None,
+        comments_module: None,
+        form: None,
+    };
+
+    let mut page = PageModel {
+        page_id: 1,
+        created_at: now(),
+        updated_at: None,
+        deleted_at: None,
+        from_wikidot: false,
+        site_id: 1,
+        latest_revision_id: Some(1),
+        stable_revision_id: None,
+        page_category_id: 1,
+        slug: str!("archive:old-page"),
+        discussion_thread_id: None,
+        noindex: None,
+        nofollow: None,
+        canonical_url: None,
+    };
+
+    assert_eq!(
+        PageIndexing::resolve(&page, &category),
+        PageIndexing {
+            noindex: true,
+            nofollow: false,
+            canonical_url: Some(str!("https://mirror.example.com/archive:old-page")),
+        },
+        "Page did not inherit category indexing settings",
+    );
+
+    page.noindex = Some(false);
+    page.nofollow = Some(true);
+    page.canonical_url = Some(str!("https://example.com/new-page"));
+
+    assert_eq!(
+        PageIndexing::resolve(&page, &category),
+        PageIndexing {
+            noindex: false,
+            nofollow: true,
+            canonical_url: Some(str!("https://example.com/new-page")),
+        },
+        "Page indexing settings did not override category",
+    );
+}
@@ -0,0 +1,46 @@
+/*
+ * services/page_draft/mod.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The page draft service, for saving edits without publishing them.
+//!
+//! A draft holds everything a revision would, but isn't part of the page's
+//! history, and is only visible to the user who saved it. It can be of an
+//! existing page, or of one which hasn't been created yet.
+//!
+//! Publishing a draft edits or creates the page as usual, so filters, limits
+//! and permissions apply at that point. A draft remembers which revision was
+//! latest when it was started, so that publishing it merges with changes made
+//! since rather than silently replacing them, unless this is forced.
+//!
+//! A draft can also be scheduled to publish at a given time, which the
+//! recurring scheduled tasks job does once it's due. Watchers of the page are
+//! told when this happens. If it can't be published then, say because of a
+//! conflict, it's unscheduled and the error kept on the draft for its author.
+
+mod prelude {
+    pub use super::super::prelude::*;
+    pub use super::structs::*;
+}
+
+mod service;
+mod structs;
+
+pub use self::service::PageDraftService;
+pub use self::structs::*;
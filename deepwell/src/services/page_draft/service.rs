@@ -0,0 +1,561 @@
+/*
+ * services/page_draft/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::constants::SYSTEM_USER_ID;
+use crate::models::page::Model as PageModel;
+use crate::models::page_draft::{self, Entity as PageDraft, Model as PageDraftModel};
+use crate::models::page_revision::Model as PageRevisionModel;
+use crate::models::site::Model as SiteModel;
+use crate::services::job::{Job, JobService};
+use crate::services::message::CreateMessageDraft;
+use crate::services::page::{CreatePage, CreatePageOutput, EditPage, EditPageBody};
+use crate::services::relation::{RelationDirection, RelationObject, RelationType};
+use crate::services::{
+    DomainService, LimitService, MessageService, PageRevisionService, PageService,
+    RelationService, SiteService, TextService, UserService,
+};
+use fluent::{FluentArgs, FluentValue};
+use sea_orm::TransactionTrait;
+use unic_langid::LanguageIdentifier;
+use wikidot_normalize::normalize;
+
+/// How many due drafts are published in one go by the recurring job.
+const PUBLISH_BATCH_SIZE: u64 = 50;
+
+#[derive(Debug)]
+pub struct PageDraftService;
+
+impl PageDraftService {
+    pub async fn create(
+        ctx: &ServiceContext<'_>,
+        CreatePageDraft {
+            site_id,
+            user_id,
+            target,
+            wikitext,
+            title,
+            alt_title,
+            tags,
+            revision_comments: comments,
+        }: CreatePageDraft<'_>,
+    ) -> Result<PageDraftModel> {
+        info!("Creating page draft for user ID {user_id} in site ID {site_id}");
+
+        let limits = LimitService::get(ctx, site_id).await?;
+        limits.check_wikitext(&wikitext)?;
+        limits.check_tags(&tags)?;
+        limits.check_comment(&comments)?;
+
+        let (page_id, slug, base_revision_id) = match target {
+            PageDraftTarget::Page(reference) => {
+                let page = PageService::get(ctx, site_id, reference).await?;
+                (Some(page.page_id), None, page.latest_revision_id)
+            }
+            PageDraftTarget::Slug(mut slug) => {
+                normalize(&mut slug);
+                PageService::check_conflicts(ctx, site_id, &slug, "draft").await?;
+                (None, Some(slug), None)
+            }
+        };
+
+        let txn = ctx.transaction();
+        let wikitext_hash = TextService::create(ctx, wikitext).await?;
+        let model = page_draft::ActiveModel {
+            site_id: Set(site_id),
+            page_id: Set(page_id),
+            slug: Set(slug),
+            user_id: Set(user_id),
+            base_revision_id: Set(base_revision_id),
+            wikitext_hash: Set(wikitext_hash.to_vec()),
+            title: Set(title),
+            alt_title: Set(alt_title),
+            tags: Set(tags),
+            comments: Set(comments),
+            ..Default::default()
+        };
+
+        let draft = model.insert(txn).await?;
+        Ok(draft)
+    }
+
+    pub async fn update(
+        ctx: &ServiceContext<'_>,
+        UpdatePageDraft {
+            site_id,
+            draft_id,
+            user_id,
+            body:
+                UpdatePageDraftBody {
+                    wikitext,
+                    title,
+                    alt_title,
+                    tags,
+                    revision_comments: comments,
+                },
+        }: UpdatePageDraft,
+    ) -> Result<PageDraftModel> {
+        info!("Updating page draft ID {draft_id} for user ID {user_id}");
+
+        let txn = ctx.transaction();
+        let draft = Self::get(
+            ctx,
+            GetPageDraft {
+                site_id,
+                draft_id,
+                user_id,
+            },
+        )
+        .await?;
+
+        let limits = LimitService::get(ctx, draft.site_id).await?;
+        let mut model = page_draft::ActiveModel {
+            draft_id: Set(draft_id),
+            updated_at: Set(Some(now())),
+            ..Default::default()
+        };
+
+        if let ProvidedValue::Set(wikitext) = wikitext {
+            limits.check_wikitext(&wikitext)?;
+            let wikitext_hash = TextService::create(ctx, wikitext).await?;
+            model.wikitext_hash = Set(wikitext_hash.to_vec());
+        }
+
+        if let ProvidedValue::Set(ref tags) = tags {
+            limits.check_tags(tags)?;
+        }
+
+        if let ProvidedValue::Set(ref comments) = comments {
+            limits.check_comment(comments)?;
+        }
+
+        model.title = title.into_active_value();
+        model.alt_title = alt_title.into_active_value();
+        model.tags = tags.into_active_value();
+        model.comments = comments.into_active_value();
+
+        let draft = model.update(txn).await?;
+        Ok(draft)
+    }
+
+    pub async fn delete(ctx: &ServiceContext<'_>, input: GetPageDraft) -> Result<()> {
+        info!(
+            "Deleting page draft ID {} for user ID {}",
+            input.draft_id, input.user_id,
+        );
+
+        let txn = ctx.transaction();
+        let draft = Self::get(ctx, input).await?;
+        PageDraft::delete_by_id(draft.draft_id).exec(txn).await?;
+        Ok(())
+    }
+
+    /// Gets a draft, if it exists and was saved by the given user.
+    ///
+    /// Drafts are private, so one saved by someone else is treated as missing.
+    pub async fn get_optional(
+        ctx: &ServiceContext<'_>,
+        GetPageDraft {
+            site_id,
+            draft_id,
+            user_id,
+        }: GetPageDraft,
+    ) -> Result<Option<PageDraftModel>> {
+        let txn = ctx.transaction();
+        let draft = PageDraft::find()
+            .filter(
+                Condition::all()
+                    .add(page_draft::Column::DraftId.eq(draft_id))
+                    .add(page_draft::Column::SiteId.eq(site_id))
+                    .add(page_draft::Column::UserId.eq(user_id)),
+            )
+            .one(txn)
+            .await?;
+
+        Ok(draft)
+    }
+
+    #[inline]
+    pub async fn get(
+        ctx: &ServiceContext<'_>,
+        input: GetPageDraft,
+    ) -> Result<PageDraftModel> {
+        find_or_error!(Self::get_optional(ctx, input), PageDraft)
+    }
+
+    /// Gets all of a user's drafts in a site, most recently started first.
+    pub async fn get_all(
+        ctx: &ServiceContext<'_>,
+        GetPageDrafts { site_id, user_id }: GetPageDrafts,
+    ) -> Result<Vec<PageDraftModel>> {
+        let txn = ctx.transaction();
+        let drafts = PageDraft::find()
+            .filter(
+                Condition::all()
+                    .add(page_draft::Column::SiteId.eq(site_id))
+                    .add(page_draft::Column::UserId.eq(user_id)),
+            )
+            .order_by_desc(page_draft::Column::DraftId)
+            .all(txn)
+            .await?;
+
+        Ok(drafts)
+    }
+
+    /// Publishes a draft, editing or creating its page, then deletes the draft.
+    ///
+    /// Unless forced, edits since the draft was started are merged with it,
+    /// failing if they conflict.
+    ///
+    /// Pages are created without tags, so if a draft of a new page has any,
+    /// they are added by a second revision immediately after.
+    pub async fn publish(
+        ctx: &ServiceContext<'_>,
+        PublishPageDraft {
+            site_id,
+            draft_id,
+            user_id,
+            force,
+        }: PublishPageDraft,
+    ) -> Result<PublishPageDraftOutput> {
+        info!("Publishing page draft ID {draft_id} for user ID {user_id}");
+
+        let txn = ctx.transaction();
+        let draft = Self::get(
+            ctx,
+            GetPageDraft {
+                site_id,
+                draft_id,
+                user_id,
+            },
+        )
+        .await?;
+
+        let wikitext = TextService::get(ctx, &draft.wikitext_hash).await?;
+        let output = match draft.page_id {
+            Some(page_id) => {
+                let page = PageService::get(ctx, site_id, Reference::Id(page_id)).await?;
+                let revision = PageService::edit(
+                    ctx,
+                    EditPage {
+                        site_id,
+                        page: Reference::Id(page_id),
+                        revision_comments: draft.comments,
+                        user_id,
+                        base_revision_id: if force {
+                            None
+                        } else {
+                            draft.base_revision_id
+                        },
+                        body: EditPageBody {
+                            wikitext: ProvidedValue::Set(wikitext),
+                            title: ProvidedValue::Set(draft.title),
+                            alt_title: ProvidedValue::Set(draft.alt_title),
+                            tags: ProvidedValue::Set(draft.tags),
+                            form_data: ProvidedValue::Unset,
+                        },
+                    },
+                )
+                .await?;
+
+                PublishPageDraftOutput {
+                    page_id,
+                    slug: page.slug,
+                    revision_id: revision.as_ref().map(|output| output.revision_id),
+                    revision_number: revision.map(|output| output.revision_number),
+                }
+            }
+            None => {
+                let slug = draft.slug.ok_or(Error::BadRequest)?;
+                let CreatePageOutput {
+                    page_id,
+                    slug,
+                    revision_id,
+                    ..
+                } = PageService::create(
+                    ctx,
+                    CreatePage {
+                        site_id,
+                        wikitext,
+                        title: draft.title,
+                        alt_title: draft.alt_title,
+                        slug,
+                        revision_comments: draft.comments,
+                        user_id,
+                        bypass_filter: false,
+                        form_data: None,
+                    },
+                )
+                .await?;
+
+                let mut output = PublishPageDraftOutput {
+                    page_id,
+                    slug,
+                    revision_id: Some(revision_id),
+                    revision_number: Some(0),
+                };
+
+                if !draft.tags.is_empty() {
+                    let revision = PageService::edit(
+                        ctx,
+                        EditPage {
+                            site_id,
+                            page: Reference::Id(page_id),
+                            revision_comments: String::new(),
+                            user_id,
+                            base_revision_id: None,
+                            body: EditPageBody {
+                                tags: ProvidedValue::Set(draft.tags),
+                                ..Default::default()
+                            },
+                        },
+                    )
+                    .await?;
+
+                    if let Some(revision) = revision {
+                        output.revision_id = Some(revision.revision_id);
+                        output.revision_number = Some(revision.revision_number);
+                    }
+                }
+
+                output
+            }
+        };
+
+        PageDraft::delete_by_id(draft_id).exec(txn).await?;
+        Ok(output)
+    }
+
+    /// Schedules a draft to be published automatically.
+    ///
+    /// Setting `publish_at` to `None` cancels this, leaving it as an ordinary draft.
+    pub async fn schedule(
+        ctx: &ServiceContext<'_>,
+        input: SchedulePageDraft,
+    ) -> Result<PageDraftModel> {
+        let SchedulePageDraft {
+            site_id,
+            draft_id,
+            user_id,
+            publish_at,
+        } = input;
+
+        info!("Scheduling page draft ID {draft_id} to publish at {publish_at:?}");
+
+        if !input.is_valid_at(now()) {
+            error!("Cannot schedule page draft ID {draft_id} to publish in the past");
+            return Err(Error::BadRequest);
+        }
+
+        let txn = ctx.transaction();
+        let draft = Self::get(
+            ctx,
+            GetPageDraft {
+                site_id,
+                draft_id,
+                user_id,
+            },
+        )
+        .await?;
+
+        let model = page_draft::ActiveModel {
+            draft_id: Set(draft.draft_id),
+            updated_at: Set(Some(now())),
+            publish_at: Set(publish_at),
+            publish_error: Set(None),
+            ..Default::default()
+        };
+
+        let draft = model.update(txn).await?;
+        Ok(draft)
+    }
+
+    /// Publishes the scheduled drafts which are due.
+    ///
+    /// Each is published within a savepoint, so one which fails, such as by
+    /// conflicting with edits made since, doesn't affect the others. A draft
+    /// which fails is kept, but unscheduled, with the reason recorded on it.
+    pub async fn publish_due(ctx: &ServiceContext<'_>) -> Result<()> {
+        let txn = ctx.transaction();
+        let drafts = PageDraft::find()
+            .filter(page_draft::Column::PublishAt.lte(now()))
+            .order_by_asc(page_draft::Column::PublishAt)
+            .limit(PUBLISH_BATCH_SIZE)
+            .all(txn)
+            .await?;
+
+        debug!("Found {} scheduled page drafts to publish", drafts.len());
+        for PageDraftModel {
+            draft_id,
+            site_id,
+            user_id,
+            ..
+        } in drafts
+        {
+            let savepoint = txn.begin().await?;
+            let result = Self::publish(
+                &ctx.with_transaction(&savepoint),
+                PublishPageDraft {
+                    site_id,
+                    draft_id,
+                    user_id,
+                    force: false,
+                },
+            )
+            .await;
+
+            match result {
+                Ok(PublishPageDraftOutput {
+                    page_id,
+                    revision_id,
+                    ..
+                }) => {
+                    savepoint.commit().await?;
+                    info!("Published scheduled page draft ID {draft_id} to page ID {page_id}");
+
+                    if let Some(revision_id) = revision_id {
+                        JobService::queue_job(
+                            ctx,
+                            &Job::NotifyScheduledPublish {
+                                site_id,
+                                page_id,
+                                revision_id,
+                            },
+                            None,
+                        )
+                        .await?;
+                    }
+                }
+                Err(error) => {
+                    savepoint.rollback().await?;
+                    warn!("Scheduled page draft ID {draft_id} could not be published: {error}");
+
+                    let model = page_draft::ActiveModel {
+                        draft_id: Set(draft_id),
+                        publish_at: Set(None),
+                        publish_error: Set(Some(error.to_string())),
+                        ..Default::default()
+                    };
+
+                    model.update(txn).await?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Tells the watchers of a page that a scheduled revision of it was published.
+    ///
+    /// The revision's author isn't notified, since they scheduled it.
+    pub async fn notify_published(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        page_id: i64,
+        revision_id: i64,
+    ) -> Result<()> {
+        let page = match PageService::get_direct_optional(ctx, page_id, false).await? {
+            Some(page) => page,
+            None => return Ok(()),
+        };
+
+        let revision = PageRevisionService::get_direct(ctx, revision_id).await?;
+        let site = SiteService::get(ctx, Reference::Id(site_id)).await?;
+        let watchers = RelationService::get_entries(
+            ctx,
+            RelationType::PageWatch,
+            RelationObject::Page(page_id),
+            RelationDirection::Dest,
+        )
+        .await?;
+
+        let recipients = watchers
+            .into_iter()
+            .map(|relation| relation.from_id)
+            .filter(|user_id| *user_id != revision.user_id)
+            .collect::<Vec<_>>();
+
+        info!(
+            "Notifying {} watchers of scheduled revision ID {revision_id} of page ID {page_id}",
+            recipients.len(),
+        );
+
+        for user_id in recipients {
+            if let Err(error) =
+                Self::send_published_notice(ctx, user_id, &site, &page, &revision).await
+            {
+                error!(
+                    "Unable to send scheduled revision notice to user ID {user_id}: {error}"
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn send_published_notice(
+        ctx: &ServiceContext<'_>,
+        user_id: i64,
+        site: &SiteModel,
+        page: &PageModel,
+        revision: &PageRevisionModel,
+    ) -> Result<()> {
+        let user = UserService::get(ctx, Reference::Id(user_id)).await?;
+        let locale = user.locales.first().map(String::as_str).unwrap_or("en");
+        let locales = [locale.parse::<LanguageIdentifier>()?];
+
+        let domain = DomainService::domain_for_site(ctx.config(), site);
+        let url = format!("https://{domain}/{}", page.slug);
+        let mut args = FluentArgs::new();
+        args.set("page", fluent_str!(page.slug));
+        args.set("title", fluent_str!(revision.title));
+        args.set("site", fluent_str!(site.name));
+        args.set("url", fluent_str!(url));
+        args.set("revision", revision.revision_number);
+
+        let localization = ctx.localization();
+        let subject = localization.translate(
+            &locales,
+            "scheduled-publish-notice-subject",
+            &args,
+        )?;
+        let wikitext =
+            localization.translate(&locales, "scheduled-publish-notice-body", &args)?;
+
+        let draft = MessageService::create_draft(
+            ctx,
+            CreateMessageDraft {
+                user_id: SYSTEM_USER_ID,
+                recipients: vec![user_id],
+                carbon_copy: vec![],
+                blind_carbon_copy: vec![],
+                locale: str!(locale),
+                subject: subject.to_string(),
+                wikitext: wikitext.to_string(),
+                reply_to: None,
+                forwarded_from: None,
+            },
+        )
+        .await?;
+
+        MessageService::send(ctx, &draft.external_id).await?;
+        Ok(())
+    }
+}
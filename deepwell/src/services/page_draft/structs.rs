@@ -0,0 +1,196 @@
+/*
+ * services/page_draft/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::page_draft::Model as PageDraftModel;
+use time::OffsetDateTime;
+
+/// What page a draft is of.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PageDraftTarget<'a> {
+    /// A page which already exists.
+    Page(Reference<'a>),
+
+    /// A page which doesn't exist yet, to be created at this slug.
+    Slug(String),
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CreatePageDraft<'a> {
+    pub site_id: i64,
+    pub user_id: i64,
+    pub target: PageDraftTarget<'a>,
+    pub wikitext: String,
+    pub title: String,
+
+    #[serde(default)]
+    pub alt_title: Option<String>,
+
+    #[serde(default)]
+    pub tags: Vec<String>,
+
+    #[serde(default)]
+    pub revision_comments: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct UpdatePageDraft {
+    pub site_id: i64,
+    pub draft_id: i64,
+    pub user_id: i64,
+
+    #[serde(flatten)]
+    pub body: UpdatePageDraftBody,
+}
+
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct UpdatePageDraftBody {
+    pub wikitext: ProvidedValue<String>,
+    pub title: ProvidedValue<String>,
+    pub alt_title: ProvidedValue<Option<String>>,
+    pub tags: ProvidedValue<Vec<String>>,
+    pub revision_comments: ProvidedValue<String>,
+}
+
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct GetPageDraft {
+    pub site_id: i64,
+    pub draft_id: i64,
+    pub user_id: i64,
+}
+
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct GetPageDrafts {
+    pub site_id: i64,
+    pub user_id: i64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct GetPageDraftOutput {
+    #[serde(flatten)]
+    pub draft: PageDraftModel,
+    pub wikitext: String,
+}
+
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct PublishPageDraft {
+    pub site_id: i64,
+    pub draft_id: i64,
+    pub user_id: i64,
+
+    /// Whether to replace changes made to the page since the
+    /// draft was started, rather than merging with them.
+    #[serde(default)]
+    pub force: bool,
+}
+
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct SchedulePageDraft {
+    pub site_id: i64,
+    pub draft_id: i64,
+    pub user_id: i64,
+
+    /// When to publish the draft, or `None` to stop it being published automatically.
+    #[serde(default)]
+    pub publish_at: Option<OffsetDateTime>,
+}
+
+impl SchedulePageDraft {
+    /// Whether this can be applied at the given time.
+    ///
+    /// Drafts can only be scheduled to publish in the future,
+    /// but can be unscheduled at any time.
+    pub fn is_valid_at(&self, now: OffsetDateTime) -> bool {
+        match self.publish_at {
+            Some(publish_at) => publish_at > now,
+            None => true,
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct PublishPageDraftOutput {
+    pub page_id: i64,
+    pub slug: String,
+
+    /// The revision which was created, if any.
+    ///
+    /// This is `None` if the draft is of an existing page,
+    /// and is the same as the current revision.
+    pub revision_id: Option<i64>,
+    pub revision_number: Option<i64>,
+}
+
+#[test]
+fn page_draft_target() {
+    use serde_json::json;
+
+    macro_rules! check {
+        ($value:expr, $expected:expr $(,)?) => {{
+            let target: PageDraftTarget =
+                serde_json::from_value($value).expect("Unable to deserialize JSON");
+
+            assert_eq!(
+                target, $expected,
+                "Actual draft target doesn't match expected"
+            );
+        }};
+    }
+
+    check!(
+        json!({ "page": 42 }),
+        PageDraftTarget::Page(Reference::Id(42))
+    );
+    check!(
+        json!({ "page": "scp-001" }),
+        PageDraftTarget::Page(Reference::from("scp-001")),
+    );
+    check!(
+        json!({ "slug": "new-page" }),
+        PageDraftTarget::Slug(str!("new-page")),
+    );
+
+    assert!(
+        serde_json::from_value::<PageDraftTarget>(json!("new-page")).is_err(),
+        "Draft target without a kind was accepted",
+    );
+}
+
+#[test]
+fn schedule_page_draft() {
+    use time::macros::datetime;
+
+    let now = datetime!(2024-06-01 12:00 UTC);
+    let schedule = |publish_at| SchedulePageDraft {
+        site_id: 1,
+        draft_id: 1,
+        user_id: 1,
+        publish_at,
+    };
+
+    assert!(schedule(Some(datetime!(2024-06-02 00:00 UTC))).is_valid_at(now));
+    assert!(!schedule(Some(now)).is_valid_at(now));
+    assert!(!schedule(Some(datetime!(2024-05-01 00:00 UTC))).is_valid_at(now));
+
+    // Unscheduling is always allowed
+    assert!(schedule(None).is_valid_at(now));
+}
@@ -0,0 +1,38 @@
+/*
+ * services/page_field_watch/mod.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The page field watch service, for following changes to form values.
+//!
+//! In categories with a data form (see the page form service), a user can
+//! watch a field by name, such as `status`. Whenever that field's value
+//! changes on any page in the category, they're sent a message listing the
+//! old and new values. This is driven by the `PageFieldsChanged` event,
+//! which is emitted when a page's stored values are updated.
+
+mod prelude {
+    pub use super::super::prelude::*;
+    pub use super::structs::*;
+}
+
+mod service;
+mod structs;
+
+pub use self::service::PageFieldWatchService;
+pub use self::structs::*;
@@ -0,0 +1,287 @@
+/*
+ * services/page_field_watch/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::constants::SYSTEM_USER_ID;
+use crate::models::page::Model as PageModel;
+use crate::models::page_field_watch::{
+    self, Entity as PageFieldWatch, Model as PageFieldWatchModel,
+};
+use crate::models::site::Model as SiteModel;
+use crate::services::message::CreateMessageDraft;
+use crate::services::page_form::FormFieldChange;
+use crate::services::{
+    DomainService, MessageService, PageFormService, PageRevisionService, PageService,
+    SiteService, UserService,
+};
+use fluent::{FluentArgs, FluentValue};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use unic_langid::LanguageIdentifier;
+
+#[derive(Debug)]
+pub struct PageFieldWatchService;
+
+impl PageFieldWatchService {
+    pub async fn create(
+        ctx: &ServiceContext<'_>,
+        CreatePageFieldWatch {
+            site_id,
+            user_id,
+            category,
+            field,
+        }: CreatePageFieldWatch,
+    ) -> Result<PageFieldWatchModel> {
+        info!(
+            "Creating watch on field '{field}' in category '{category}' for user ID {user_id}",
+        );
+
+        let (category_id, schema) =
+            match PageFormService::get_schema(ctx, site_id, &category).await? {
+                Some(form) => form,
+                None => {
+                    error!("Category '{category}' in site ID {site_id} has no form");
+                    return Err(Error::BadRequest);
+                }
+            };
+
+        if !schema
+            .fields
+            .iter()
+            .any(|form_field| form_field.name == field)
+        {
+            error!("Category '{category}' has no field '{field}' to watch");
+            return Err(Error::BadRequest);
+        }
+
+        let txn = ctx.transaction();
+        let existing = PageFieldWatch::find()
+            .filter(
+                Condition::all()
+                    .add(page_field_watch::Column::UserId.eq(user_id))
+                    .add(page_field_watch::Column::CategoryId.eq(category_id))
+                    .add(page_field_watch::Column::Field.eq(field.as_str())),
+            )
+            .one(txn)
+            .await?;
+
+        if existing.is_some() {
+            error!("User ID {user_id} is already watching field '{field}'");
+            return Err(Error::PageFieldWatchExists);
+        }
+
+        let model = page_field_watch::ActiveModel {
+            user_id: Set(user_id),
+            site_id: Set(site_id),
+            category_id: Set(category_id),
+            field: Set(field),
+            ..Default::default()
+        };
+
+        let watch = model.insert(txn).await?;
+        Ok(watch)
+    }
+
+    pub async fn delete(
+        ctx: &ServiceContext<'_>,
+        DeletePageFieldWatch {
+            site_id,
+            user_id,
+            watch_id,
+        }: DeletePageFieldWatch,
+    ) -> Result<()> {
+        info!("Deleting field watch ID {watch_id} for user ID {user_id}");
+
+        let txn = ctx.transaction();
+        let result = PageFieldWatch::delete_many()
+            .filter(
+                Condition::all()
+                    .add(page_field_watch::Column::WatchId.eq(watch_id))
+                    .add(page_field_watch::Column::SiteId.eq(site_id))
+                    .add(page_field_watch::Column::UserId.eq(user_id)),
+            )
+            .exec(txn)
+            .await?;
+
+        if result.rows_affected == 0 {
+            return Err(Error::PageFieldWatchNotFound);
+        }
+
+        Ok(())
+    }
+
+    pub async fn get_all(
+        ctx: &ServiceContext<'_>,
+        GetPageFieldWatches { site_id, user_id }: GetPageFieldWatches,
+    ) -> Result<Vec<PageFieldWatchModel>> {
+        let txn = ctx.transaction();
+        let watches = PageFieldWatch::find()
+            .filter(
+                Condition::all()
+                    .add(page_field_watch::Column::SiteId.eq(site_id))
+                    .add(page_field_watch::Column::UserId.eq(user_id)),
+            )
+            .order_by_asc(page_field_watch::Column::WatchId)
+            .all(txn)
+            .await?;
+
+        Ok(watches)
+    }
+
+    /// Messages the users watching any of the fields which changed on a page.
+    ///
+    /// Each user gets one message covering all the fields they watch. The
+    /// author of the page's latest revision is skipped, since they made the change.
+    pub async fn notify(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        page_id: i64,
+        category_id: i64,
+        changes: &[FormFieldChange],
+    ) -> Result<()> {
+        let txn = ctx.transaction();
+        let watches = PageFieldWatch::find()
+            .filter(
+                Condition::all()
+                    .add(page_field_watch::Column::CategoryId.eq(category_id))
+                    .add(
+                        page_field_watch::Column::Field
+                            .is_in(changes.iter().map(|change| change.field.as_str())),
+                    ),
+            )
+            .all(txn)
+            .await?;
+
+        if watches.is_empty() {
+            return Ok(());
+        }
+
+        let page = match PageService::get_direct_optional(ctx, page_id, false).await? {
+            Some(page) => page,
+            None => return Ok(()),
+        };
+
+        let site = SiteService::get(ctx, Reference::Id(site_id)).await?;
+        let revision = PageRevisionService::get_latest(ctx, site_id, page_id).await?;
+
+        let mut watched = BTreeMap::<i64, Vec<&FormFieldChange>>::new();
+        for watch in watches {
+            if watch.user_id == revision.user_id {
+                continue;
+            }
+
+            if let Some(change) =
+                changes.iter().find(|change| change.field == watch.field)
+            {
+                watched.entry(watch.user_id).or_default().push(change);
+            }
+        }
+
+        info!(
+            "Notifying {} users of changed fields on page ID {page_id}",
+            watched.len(),
+        );
+
+        for (user_id, changes) in watched {
+            if let Err(error) =
+                Self::send_notice(ctx, user_id, &site, &page, &changes).await
+            {
+                error!(
+                    "Unable to send field change notice to user ID {user_id}: {error}"
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn send_notice(
+        ctx: &ServiceContext<'_>,
+        user_id: i64,
+        site: &SiteModel,
+        page: &PageModel,
+        changes: &[&FormFieldChange],
+    ) -> Result<()> {
+        let user = UserService::get(ctx, Reference::Id(user_id)).await?;
+        let locale = user.locales.first().map(String::as_str).unwrap_or("en");
+        let locales = [locale.parse::<LanguageIdentifier>()?];
+
+        // One list item per field, with values as raw text so they aren't rendered
+        let mut list = String::new();
+        for FormFieldChange { field, old, new } in changes {
+            str_writeln!(
+                &mut list,
+                "* **{field}**: {} → {}",
+                format_value(old),
+                format_value(new),
+            );
+        }
+
+        let domain = DomainService::domain_for_site(ctx.config(), site);
+        let url = format!("https://{domain}/{}", page.slug);
+        let mut args = FluentArgs::new();
+        args.set("page", fluent_str!(page.slug));
+        args.set("site", fluent_str!(site.name));
+        args.set("url", fluent_str!(url));
+        args.set("fields", changes.len());
+        args.set("changes", fluent_str!(list));
+
+        let localization = ctx.localization();
+        let subject =
+            localization.translate(&locales, "field-watch-notice-subject", &args)?;
+        let wikitext =
+            localization.translate(&locales, "field-watch-notice-body", &args)?;
+
+        let draft = MessageService::create_draft(
+            ctx,
+            CreateMessageDraft {
+                user_id: SYSTEM_USER_ID,
+                recipients: vec![user_id],
+                carbon_copy: vec![],
+                blind_carbon_copy: vec![],
+                locale: str!(locale),
+                subject: subject.to_string(),
+                wikitext: wikitext.to_string(),
+                reply_to: None,
+                forwarded_from: None,
+            },
+        )
+        .await?;
+
+        MessageService::send(ctx, &draft.external_id).await?;
+        Ok(())
+    }
+}
+
+/// Formats a field value for a notice, or a dash if it's unset.
+///
+/// Multi-line values are shortened to their first line.
+fn format_value(value: &Option<Value>) -> String {
+    let value = match value {
+        None => return str!("—"),
+        Some(Value::String(value)) => value.clone(),
+        Some(value) => value.to_string(),
+    };
+
+    match value.split_once('\n') {
+        Some((line, _)) => format!("@@{line}@@ …"),
+        None => format!("@@{value}@@"),
+    }
+}
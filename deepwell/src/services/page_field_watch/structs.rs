@@ -0,0 +1,42 @@
+/*
+ * services/page_field_watch/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CreatePageFieldWatch {
+    pub site_id: i64,
+    pub user_id: i64,
+
+    /// The slug of the category, such as `_default`.
+    pub category: String,
+    pub field: String,
+}
+
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct DeletePageFieldWatch {
+    pub site_id: i64,
+    pub user_id: i64,
+    pub watch_id: i64,
+}
+
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct GetPageFieldWatches {
+    pub site_id: i64,
+    pub user_id: i64,
+}
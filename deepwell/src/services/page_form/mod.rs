@@ -0,0 +1,47 @@
+/*
+ * services/page_form/mod.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The page form service, for categories of structured pages.
+//!
+//! Like Wikidot's data forms, a category can have a form, listing the fields
+//! its pages have, with their types. The wikitext of such a page holds only
+//! its field values, one per line as `name: value`, with multi-line values
+//! written as an indented block after `name: |`. Keeping the values in the
+//! wikitext means they have history, diffs and merging like any other edit.
+//!
+//! When the page is rendered, its values are substituted into the form's
+//! layout, which is ordinary wikitext with a `{$name}` placeholder for each
+//! field, and that is what ftml renders. The values are also stored in the
+//! `page_form_data` table, so they can be queried.
+//!
+//! Pages can be created and edited by passing the field values instead of
+//! wikitext, which are validated and written out in the format above.
+
+mod prelude {
+    pub use super::super::prelude::*;
+    pub use super::structs::*;
+}
+
+mod schema;
+mod service;
+mod structs;
+
+pub use self::service::PageFormService;
+pub use self::structs::*;
@@ -0,0 +1,357 @@
+/*
+ * services/page_form/schema.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+static PLACEHOLDER_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\{\$([a-zA-Z0-9_\-]+)\}").unwrap());
+
+static FIELD_NAME_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^[a-zA-Z0-9_\-]+$").unwrap());
+
+/// Field values as they are written in wikitext, keyed by field name.
+pub type RawValues = BTreeMap<String, String>;
+
+/// Reads the field values from a page's wikitext.
+///
+/// Lines which aren't a field are ignored, as are fields with empty values.
+pub fn parse_source(wikitext: &str) -> RawValues {
+    let mut values = RawValues::new();
+    let mut lines = wikitext.lines().peekable();
+
+    while let Some(line) = lines.next() {
+        let (name, value) = match line.split_once(':') {
+            Some((name, value)) => (name.trim(), value.trim()),
+            None => continue,
+        };
+
+        let value = if value == "|" {
+            let mut block = Vec::new();
+            while let Some(next) = lines.peek() {
+                if let Some(next) = next.strip_prefix("  ") {
+                    block.push(next);
+                } else if next.trim().is_empty() {
+                    block.push("");
+                } else {
+                    break;
+                }
+
+                lines.next();
+            }
+
+            block.join("\n").trim_end().to_owned()
+        } else {
+            str!(value)
+        };
+
+        if !name.is_empty() && !value.is_empty() {
+            values.insert(str!(name), value);
+        }
+    }
+
+    values
+}
+
+/// Converts values passed through the API into how they are written.
+pub fn raw_values(values: FormValues) -> RawValues {
+    values
+        .into_iter()
+        .filter_map(|(name, value)| {
+            let value = match value {
+                Value::Null => return None,
+                Value::String(value) => value,
+                value => value.to_string(),
+            };
+
+            (!value.is_empty()).then_some((name, value))
+        })
+        .collect()
+}
+
+/// Lists the fields whose values differ, in field name order.
+pub fn diff_values(old: &FormValues, new: &FormValues) -> Vec<FormFieldChange> {
+    let mut fields = old.keys().chain(new.keys()).collect::<Vec<_>>();
+    fields.sort();
+    fields.dedup();
+
+    fields
+        .into_iter()
+        .filter_map(|field| {
+            let (old, new) = (old.get(field), new.get(field));
+            (old != new).then(|| FormFieldChange {
+                field: str!(field),
+                old: old.cloned(),
+                new: new.cloned(),
+            })
+        })
+        .collect()
+}
+
+impl FormSchema {
+    /// Checks that the form itself is usable.
+    pub fn validate(&self) -> Result<()> {
+        macro_rules! invalid {
+            ($($arg:tt)*) => {
+                return Err(Error::PageFormSchemaInvalid(format!($($arg)*)))
+            };
+        }
+
+        if self.fields.is_empty() {
+            invalid!("Form has no fields");
+        }
+
+        for (index, field) in self.fields.iter().enumerate() {
+            let name = &field.name;
+            if !FIELD_NAME_REGEX.is_match(name) {
+                invalid!("Field name '{name}' is not valid");
+            }
+
+            if self.fields[..index].iter().any(|other| &other.name == name) {
+                invalid!("Field '{name}' is listed more than once");
+            }
+
+            match field.kind {
+                FormFieldType::Select { ref options } if options.is_empty() => {
+                    invalid!("Select field '{name}' has no options");
+                }
+                FormFieldType::Number {
+                    min: Some(min),
+                    max: Some(max),
+                } if min > max => {
+                    invalid!("Number field '{name}' has a minimum above its maximum");
+                }
+                _ => (),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks the values against the form, returning them with their proper types.
+    ///
+    /// Fails with the names of all invalid, missing and unknown fields.
+    pub fn check(&self, raw: &RawValues) -> Result<FormValues> {
+        let mut invalid = raw
+            .keys()
+            .filter(|name| self.field(name).is_none())
+            .cloned()
+            .collect::<Vec<_>>();
+
+        let mut values = FormValues::new();
+        for field in &self.fields {
+            match raw.get(&field.name) {
+                Some(value) => match field.convert(value) {
+                    Some(value) => {
+                        values.insert(field.name.clone(), value);
+                    }
+                    None => invalid.push(field.name.clone()),
+                },
+                None if field.required => invalid.push(field.name.clone()),
+                None => (),
+            }
+        }
+
+        if !invalid.is_empty() {
+            error!("Data form values invalid for fields {invalid:?}");
+            return Err(Error::PageFormInvalid { fields: invalid });
+        }
+
+        Ok(values)
+    }
+
+    /// Like `check()`, but leaves out invalid values instead of failing.
+    ///
+    /// This is for existing pages, which may predate changes to the form.
+    pub fn values(&self, raw: &RawValues) -> FormValues {
+        self.fields
+            .iter()
+            .filter_map(|field| {
+                let value = field.convert(raw.get(&field.name)?)?;
+                Some((field.name.clone(), value))
+            })
+            .collect()
+    }
+
+    /// Writes the values out as wikitext, in the order of the form's fields.
+    pub fn to_source(&self, raw: &RawValues) -> String {
+        let mut source = String::new();
+        for field in &self.fields {
+            let name = &field.name;
+            let value = match raw.get(name) {
+                Some(value) => value,
+                None => continue,
+            };
+
+            if value.contains('\n') || value == "|" {
+                str_writeln!(source, "{name}: |");
+                for line in value.lines() {
+                    if line.is_empty() {
+                        source.push('\n');
+                    } else {
+                        str_writeln!(source, "  {line}");
+                    }
+                }
+            } else {
+                str_writeln!(source, "{name}: {value}");
+            }
+        }
+
+        source
+    }
+
+    /// Substitutes the values into the form's layout.
+    ///
+    /// Placeholders for fields without a value are removed,
+    /// and any which aren't fields are left as they are.
+    pub fn layout(&self, raw: &RawValues) -> String {
+        PLACEHOLDER_REGEX
+            .replace_all(&self.layout, |captures: &Captures| {
+                let name = &captures[1];
+                match (self.field(name), raw.get(name)) {
+                    (Some(_), Some(value)) => value.clone(),
+                    (Some(_), None) => String::new(),
+                    (None, _) => str!(&captures[0]),
+                }
+            })
+            .into_owned()
+    }
+
+    fn field(&self, name: &str) -> Option<&FormField> {
+        self.fields.iter().find(|field| field.name == name)
+    }
+}
+
+impl FormField {
+    /// Converts a value as written to its proper type, if it's valid for this field.
+    fn convert(&self, value: &str) -> Option<Value> {
+        match self.kind {
+            FormFieldType::Text { max_length } => {
+                let too_long = max_length.is_some_and(|max| value.chars().count() > max);
+                if value.contains('\n') || too_long {
+                    return None;
+                }
+
+                Some(Value::String(str!(value)))
+            }
+            FormFieldType::Wikitext => Some(Value::String(str!(value))),
+            FormFieldType::Number { min, max } => {
+                let number = value.parse::<f64>().ok()?;
+                if min.is_some_and(|min| number < min)
+                    || max.is_some_and(|max| number > max)
+                {
+                    return None;
+                }
+
+                // Keep whole numbers as integers
+                match value.parse::<i64>() {
+                    Ok(integer) => Some(Value::from(integer)),
+                    Err(_) => serde_json::Number::from_f64(number).map(Value::Number),
+                }
+            }
+            FormFieldType::Select { ref options } => options
+                .iter()
+                .any(|option| option == value)
+                .then(|| Value::String(str!(value))),
+            FormFieldType::Boolean => match value {
+                "true" => Some(Value::Bool(true)),
+                "false" => Some(Value::Bool(false)),
+                _ => None,
+            },
+        }
+    }
+}
+
+#[test]
+fn form_values() {
+    use serde_json::json;
+
+    let schema: FormSchema = serde_json::from_value(json!({
+        "fields": [
+            { "name": "name", "type": "text", "required": true },
+            { "name": "age", "type": "number", "min": 0 },
+            { "name": "class", "type": "select", "options": ["safe", "euclid"] },
+            { "name": "description", "type": "wikitext" },
+        ],
+        "layout": "++ {$name}\n\n**Class:** {$class}\n\n{$description}\n{$other}",
+    }))
+    .expect("Unable to deserialize form schema");
+
+    assert!(schema.validate().is_ok());
+
+    let values = json!({
+        "name": "Apple",
+        "age": 3,
+        "class": "safe",
+        "description": "First line\n\nThird line",
+    });
+
+    let raw = raw_values(serde_json::from_value(values.clone()).unwrap());
+    let source = schema.to_source(&raw);
+    assert_eq!(
+        source,
+        "name: Apple\nage: 3\nclass: safe\ndescription: |\n  First line\n\n  Third line\n",
+    );
+
+    let parsed = parse_source(&source);
+    assert_eq!(parsed, raw);
+    assert_eq!(json!(schema.check(&parsed).unwrap()), values);
+    assert_eq!(
+        schema.layout(&parsed),
+        "++ Apple\n\n**Class:** safe\n\nFirst line\n\nThird line\n{$other}",
+    );
+
+    let parsed = parse_source("age: -1\nclass: keter\ncolour: red\n");
+    match schema.check(&parsed) {
+        Err(Error::PageFormInvalid { fields }) => {
+            assert_eq!(fields, vec!["colour", "name", "age", "class"]);
+        }
+        result => panic!("Unexpected result: {result:?}"),
+    }
+
+    assert_eq!(schema.values(&parsed), FormValues::new());
+
+    let old = serde_json::from_value(values).unwrap();
+    let new = serde_json::from_value(json!({
+        "name": "Apple",
+        "age": 4,
+        "description": "First line\n\nThird line",
+    }))
+    .unwrap();
+
+    assert_eq!(
+        diff_values(&old, &new),
+        vec![
+            FormFieldChange {
+                field: str!("age"),
+                old: Some(json!(3)),
+                new: Some(json!(4)),
+            },
+            FormFieldChange {
+                field: str!("class"),
+                old: Some(json!("safe")),
+                new: None,
+            },
+        ],
+    );
+}
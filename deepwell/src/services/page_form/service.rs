@@ -0,0 +1,209 @@
+/*
+ * services/page_form/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use super::schema::{diff_values, parse_source, raw_values};
+use crate::models::page_category::Model as PageCategoryModel;
+use crate::models::page_form_data::{self, Entity as PageFormData};
+use crate::services::event::{Event, EventService};
+use crate::services::{CategoryService, PageRevisionService, PageService, TextService};
+use crate::utils::get_category_name;
+
+#[derive(Debug)]
+pub struct PageFormService;
+
+impl PageFormService {
+    /// Gets the form for pages in a category, if it has one, with the category's ID.
+    pub async fn get_schema(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        category_slug: &str,
+    ) -> Result<Option<(i64, FormSchema)>> {
+        let category =
+            CategoryService::get_optional(ctx, site_id, Reference::from(category_slug))
+                .await?;
+
+        match category {
+            Some(PageCategoryModel {
+                category_id,
+                form: Some(form),
+                ..
+            }) => Ok(Some((category_id, serde_json::from_value(form)?))),
+            _ => Ok(None),
+        }
+    }
+
+    /// Gets the wikitext to save for a page, from either its wikitext or field values.
+    ///
+    /// If the page's category has a form, then wikitext must be valid field
+    /// values for it. Otherwise field values can't be given.
+    pub async fn build_source(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        slug: &str,
+        wikitext: Option<String>,
+        values: Option<FormValues>,
+    ) -> Result<Option<String>> {
+        if wikitext.is_none() && values.is_none() {
+            return Ok(None);
+        }
+
+        let schema = Self::get_schema(ctx, site_id, get_category_name(slug)).await?;
+        match (schema, wikitext, values) {
+            (_, Some(_), Some(_)) => {
+                error!("Both wikitext and form values given for page '{slug}'");
+                Err(Error::BadRequest)
+            }
+            (None, _, Some(_)) => {
+                error!(
+                    "Form values given for page '{slug}', but its category has no form"
+                );
+                Err(Error::BadRequest)
+            }
+            (None, wikitext, None) => Ok(wikitext),
+            (Some((_, schema)), None, Some(values)) => {
+                let raw = raw_values(values);
+                schema.check(&raw)?;
+                Ok(Some(schema.to_source(&raw)))
+            }
+            (Some((_, schema)), Some(wikitext), None) => {
+                schema.check(&parse_source(&wikitext))?;
+                Ok(Some(wikitext))
+            }
+            (Some(_), None, None) => unreachable!(),
+        }
+    }
+
+    /// Lays out a page's field values with its category's form, if it has one.
+    ///
+    /// Returns the wikitext to render, and the category ID and values to record.
+    pub async fn expand(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        slug: &str,
+        wikitext: String,
+    ) -> Result<(String, Option<(i64, FormValues)>)> {
+        match Self::get_schema(ctx, site_id, get_category_name(slug)).await? {
+            None => Ok((wikitext, None)),
+            Some((category_id, schema)) => {
+                let raw = parse_source(&wikitext);
+                let values = schema.values(&raw);
+                Ok((schema.layout(&raw), Some((category_id, values))))
+            }
+        }
+    }
+
+    /// Stores the field values from a page's latest render.
+    ///
+    /// If its category has no form, then any previously stored values are removed.
+    ///
+    /// If any values differ from those stored before, within the same category,
+    /// a `PageFieldsChanged` event is emitted for them. Values being stored for the
+    /// first time don't count as changes, otherwise giving a category a form
+    /// would report every field of every page in it.
+    pub async fn record(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        page_id: i64,
+        data: Option<(i64, FormValues)>,
+    ) -> Result<()> {
+        let txn = ctx.transaction();
+        let existing = PageFormData::find_by_id(page_id).one(txn).await?;
+        let (category_id, values) = match data {
+            Some(data) => data,
+            None => {
+                if existing.is_some() {
+                    debug!("Page ID {page_id} no longer has a form, clearing its values");
+                    PageFormData::delete_by_id(page_id).exec(txn).await?;
+                }
+
+                return Ok(());
+            }
+        };
+
+        let model = page_form_data::ActiveModel {
+            page_id: Set(page_id),
+            site_id: Set(site_id),
+            category_id: Set(category_id),
+            updated_at: Set(now()),
+            data: Set(serde_json::to_value(&values)?),
+        };
+
+        let changes = match existing {
+            None => {
+                model.insert(txn).await?;
+                return Ok(());
+            }
+            Some(existing) => {
+                model.update(txn).await?;
+
+                if existing.category_id != category_id {
+                    return Ok(());
+                }
+
+                let previous: FormValues = serde_json::from_value(existing.data)?;
+                diff_values(&previous, &values)
+            }
+        };
+
+        if !changes.is_empty() {
+            EventService::emit(
+                ctx,
+                Event::PageFieldsChanged {
+                    site_id,
+                    page_id,
+                    category_id,
+                    changes,
+                },
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Gets the form for a page, and its current values, for editing.
+    ///
+    /// Returns `None` if the page's category has no form.
+    pub async fn get(
+        ctx: &ServiceContext<'_>,
+        GetPageForm {
+            site_id,
+            page: reference,
+        }: GetPageForm<'_>,
+    ) -> Result<Option<GetPageFormOutput>> {
+        let page = PageService::get(ctx, site_id, reference).await?;
+        let schema =
+            match Self::get_schema(ctx, site_id, get_category_name(&page.slug)).await? {
+                Some((_, schema)) => schema,
+                None => return Ok(None),
+            };
+
+        let revision =
+            PageRevisionService::get_latest(ctx, site_id, page.page_id).await?;
+        let wikitext = TextService::get(ctx, &revision.wikitext_hash).await?;
+        let values = schema.values(&parse_source(&wikitext));
+
+        Ok(Some(GetPageFormOutput {
+            form: schema,
+            values,
+        }))
+    }
+}
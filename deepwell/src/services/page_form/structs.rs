@@ -0,0 +1,103 @@
+/*
+ * services/page_form/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use std::collections::BTreeMap;
+
+/// Field values for a page, keyed by field name.
+pub type FormValues = BTreeMap<String, serde_json::Value>;
+
+/// The form for pages in a category.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FormSchema {
+    pub fields: Vec<FormField>,
+
+    /// The wikitext pages are rendered with, containing a `{$name}` placeholder for each field.
+    pub layout: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FormField {
+    pub name: String,
+
+    /// What the editor shows for this field. If unset, the name is used.
+    #[serde(default)]
+    pub label: Option<String>,
+
+    #[serde(default)]
+    pub required: bool,
+
+    #[serde(flatten)]
+    pub kind: FormFieldType,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum FormFieldType {
+    /// A single line of text.
+    Text {
+        #[serde(default)]
+        max_length: Option<usize>,
+    },
+
+    /// Any amount of wikitext, which may span multiple lines.
+    Wikitext,
+
+    Number {
+        #[serde(default)]
+        min: Option<f64>,
+
+        #[serde(default)]
+        max: Option<f64>,
+    },
+
+    /// One of a fixed set of values.
+    Select {
+        options: Vec<String>,
+    },
+
+    Boolean,
+}
+
+/// A field whose value differs between two sets of values.
+///
+/// A value of `None` means the field was unset.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FormFieldChange {
+    pub field: String,
+    pub old: Option<serde_json::Value>,
+    pub new: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetPageForm<'a> {
+    pub site_id: i64,
+    pub page: Reference<'a>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct GetPageFormOutput {
+    pub form: FormSchema,
+
+    /// The values in the page's latest revision.
+    ///
+    /// Any which are no longer valid for the form are left out.
+    pub values: FormValues,
+}
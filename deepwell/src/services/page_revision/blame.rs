@@ -0,0 +1,112 @@
+/*
+ * services/page_revision/blame.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use similar::{DiffTag, TextDiff};
+
+/// Which revision a line of wikitext was last changed in.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BlameOrigin {
+    pub revision_number: i64,
+    pub user_id: i64,
+}
+
+/// The origin of each line of a page's wikitext as of some revision.
+///
+/// This is what gets cached, so that later revisions can be applied
+/// on top of it instead of starting over from the page's first revision.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BlameState {
+    pub revision_number: i64,
+    pub wikitext_hash: Vec<u8>,
+    pub lines: Vec<BlameOrigin>,
+}
+
+/// Carries line origins across one change in wikitext.
+///
+/// Lines which are unchanged keep their existing origin, and
+/// any inserted or replaced lines are attributed to `origin`.
+pub fn apply_revision(
+    lines: &[BlameOrigin],
+    from: &str,
+    to: &str,
+    origin: BlameOrigin,
+) -> Vec<BlameOrigin> {
+    let diff = TextDiff::from_lines(from, to);
+    let mut result = Vec::with_capacity(diff.new_slices().len());
+
+    debug_assert_eq!(
+        lines.len(),
+        diff.old_slices().len(),
+        "Blame lines do not match the previous wikitext",
+    );
+
+    for op in diff.ops() {
+        let (tag, old_range, new_range) = op.as_tag_tuple();
+        match tag {
+            DiffTag::Equal => result.extend_from_slice(&lines[old_range]),
+            DiffTag::Insert | DiffTag::Replace => {
+                result.extend(new_range.map(|_| origin));
+            }
+            DiffTag::Delete => (),
+        }
+    }
+
+    result
+}
+
+#[test]
+fn blame() {
+    const A: BlameOrigin = BlameOrigin {
+        revision_number: 0,
+        user_id: 1,
+    };
+    const B: BlameOrigin = BlameOrigin {
+        revision_number: 1,
+        user_id: 2,
+    };
+    const C: BlameOrigin = BlameOrigin {
+        revision_number: 2,
+        user_id: 1,
+    };
+
+    let lines = apply_revision(&[], "", "apple\nbanana\ncherry\n", A);
+    assert_eq!(lines, [A, A, A]);
+
+    let lines = apply_revision(
+        &lines,
+        "apple\nbanana\ncherry\n",
+        "apple\nblueberry\ncherry\ndurian\n",
+        B,
+    );
+    assert_eq!(lines, [A, B, A, B]);
+
+    let lines = apply_revision(
+        &lines,
+        "apple\nblueberry\ncherry\ndurian\n",
+        "blueberry\ncherry\ndurian\n",
+        C,
+    );
+    assert_eq!(lines, [B, A, B]);
+
+    // Matches the number of lines from str::lines()
+    let text = "one\r\ntwo\r\nthree";
+    let lines = apply_revision(&[], "", text, A);
+    assert_eq!(lines.len(), text.lines().count());
+}
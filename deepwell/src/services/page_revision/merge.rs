@@ -0,0 +1,267 @@
+/*
+ * services/page_revision/merge.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use similar::{DiffTag, TextDiff};
+use std::ops::Range;
+
+/// A part of an edit which conflicts with changes made since its base revision.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case", tag = "field")]
+pub enum EditConflict {
+    /// Lines which the edit and the latest revision both changed, differently.
+    Wikitext {
+        /// The line in the base revision where the region starts, counting from 1.
+        line: usize,
+        base: String,
+        current: String,
+        edit: String,
+    },
+
+    Title {
+        base: String,
+        current: String,
+        edit: String,
+    },
+
+    AltTitle {
+        base: Option<String>,
+        current: Option<String>,
+        edit: Option<String>,
+    },
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Side {
+    Current,
+    Edit,
+}
+
+/// A range of lines in the base which one side replaced.
+#[derive(Debug, Clone)]
+struct Change {
+    side: Side,
+    start: usize,
+    end: usize,
+    lines: Range<usize>,
+}
+
+/// Performs a three-way merge of wikitext, line by line.
+///
+/// Changes made by only one of `current` or `edit` relative to `base` are
+/// kept. Where both changed the same or adjacent lines, the region is only
+/// merged if they made the same change, otherwise it's returned as a conflict.
+pub fn merge_wikitext(
+    base: &str,
+    current: &str,
+    edit: &str,
+) -> StdResult<String, Vec<EditConflict>> {
+    let current_diff = TextDiff::from_lines(base, current);
+    let edit_diff = TextDiff::from_lines(base, edit);
+    let base_lines = current_diff.old_slices();
+    let current_lines = current_diff.new_slices();
+    let edit_lines = edit_diff.new_slices();
+
+    let mut changes = get_changes(&current_diff, Side::Current);
+    changes.extend(get_changes(&edit_diff, Side::Edit));
+    changes.sort_by_key(|change| (change.start, change.end));
+
+    let mut merged = String::with_capacity(edit.len());
+    let mut conflicts = Vec::new();
+    let mut position = 0;
+    let mut index = 0;
+
+    while index < changes.len() {
+        // Group every change which overlaps or touches this one
+        let start = changes[index].start;
+        let mut end = changes[index].end;
+        let mut next = index + 1;
+        while next < changes.len() && changes[next].start <= end {
+            end = end.max(changes[next].end);
+            next += 1;
+        }
+
+        let region = &changes[index..next];
+        index = next;
+
+        merged.push_str(&base_lines[position..start].concat());
+        position = end;
+
+        let current_text =
+            apply_changes(base_lines, current_lines, region, Side::Current, start..end);
+        let edit_text =
+            apply_changes(base_lines, edit_lines, region, Side::Edit, start..end);
+
+        let changed_by_current = region.iter().any(|change| change.side == Side::Current);
+        let changed_by_edit = region.iter().any(|change| change.side == Side::Edit);
+
+        if !changed_by_edit {
+            merged.push_str(&current_text);
+        } else if !changed_by_current || current_text == edit_text {
+            merged.push_str(&edit_text);
+        } else {
+            conflicts.push(EditConflict::Wikitext {
+                line: start + 1,
+                base: base_lines[start..end].concat(),
+                current: current_text,
+                edit: edit_text,
+            });
+        }
+    }
+
+    merged.push_str(&base_lines[position..].concat());
+
+    if conflicts.is_empty() {
+        Ok(merged)
+    } else {
+        Err(conflicts)
+    }
+}
+
+/// Merges a single value, such as the title.
+///
+/// Returns `None` if both sides changed it, to different values.
+pub fn merge_value<'a, T>(base: &'a T, current: &'a T, edit: &'a T) -> Option<&'a T>
+where
+    T: PartialEq + ?Sized,
+{
+    if edit == base {
+        Some(current)
+    } else if current == base || current == edit {
+        Some(edit)
+    } else {
+        None
+    }
+}
+
+/// Applies the tags an edit added and removed to the latest revision's tags.
+///
+/// Unlike other fields, tags can't conflict, since each is either present or not.
+pub fn merge_tags(base: &[String], current: &[String], edit: &[String]) -> Vec<String> {
+    let mut tags = current
+        .iter()
+        .filter(|tag| !base.contains(tag) || edit.contains(tag))
+        .cloned()
+        .collect::<Vec<_>>();
+
+    for tag in edit {
+        if !base.contains(tag) && !tags.contains(tag) {
+            tags.push(tag.clone());
+        }
+    }
+
+    tags
+}
+
+fn get_changes(diff: &TextDiff<str>, side: Side) -> Vec<Change> {
+    diff.ops()
+        .iter()
+        .filter_map(|op| {
+            let (tag, old_range, new_range) = op.as_tag_tuple();
+            match tag {
+                DiffTag::Equal => None,
+                _ => Some(Change {
+                    side,
+                    start: old_range.start,
+                    end: old_range.end,
+                    lines: new_range,
+                }),
+            }
+        })
+        .collect()
+}
+
+/// Gets the text of a region of the base, with one side's changes applied.
+fn apply_changes(
+    base_lines: &[&str],
+    side_lines: &[&str],
+    region: &[Change],
+    side: Side,
+    range: Range<usize>,
+) -> String {
+    let mut text = String::new();
+    let mut position = range.start;
+
+    for change in region.iter().filter(|change| change.side == side) {
+        text.push_str(&base_lines[position..change.start].concat());
+        text.push_str(&side_lines[change.lines.clone()].concat());
+        position = change.end;
+    }
+
+    text.push_str(&base_lines[position..range.end].concat());
+    text
+}
+
+#[test]
+fn merge() {
+    const BASE: &str = "apple\nbanana\ncherry\ndurian\nelderberry\n";
+
+    // Changes to different lines are both kept
+    let merged = merge_wikitext(
+        BASE,
+        "apricot\nbanana\ncherry\ndurian\nelderberry\n",
+        "apple\nbanana\ncherry\ndurian\nelderberry\nfig\n",
+    );
+    assert_eq!(
+        merged,
+        Ok(str!("apricot\nbanana\ncherry\ndurian\nelderberry\nfig\n")),
+    );
+
+    // The same change made by both is not a conflict
+    let merged = merge_wikitext(
+        BASE,
+        "apple\nbanana\ncoconut\ndurian\nelderberry\n",
+        "apple\nbanana\ncoconut\ndurian\nelderberry\n",
+    );
+    assert_eq!(
+        merged,
+        Ok(str!("apple\nbanana\ncoconut\ndurian\nelderberry\n"))
+    );
+
+    // Different changes to the same line are
+    let merged = merge_wikitext(
+        BASE,
+        "apple\nbanana\ncoconut\ndurian\nelderberry\n",
+        "apple\nbanana\ncranberry\ndurian\nelderberry\n",
+    );
+    assert_eq!(
+        merged,
+        Err(vec![EditConflict::Wikitext {
+            line: 3,
+            base: str!("cherry\n"),
+            current: str!("coconut\n"),
+            edit: str!("cranberry\n"),
+        }]),
+    );
+
+    assert_eq!(merge_value("a", "a", "b"), Some("b"));
+    assert_eq!(merge_value("a", "b", "a"), Some("b"));
+    assert_eq!(merge_value("a", "b", "c"), None);
+
+    let tags = |tags: &[&str]| tags.iter().map(|tag| tag.to_string()).collect::<Vec<_>>();
+    assert_eq!(
+        merge_tags(
+            &tags(&["a", "b"]),
+            &tags(&["a", "b", "c"]),
+            &tags(&["b", "d"])
+        ),
+        tags(&["b", "c", "d"]),
+    );
+}
@@ -20,13 +20,20 @@
 
 mod prelude {
     pub use super::super::prelude::*;
+    pub use super::blame::{BlameOrigin, BlameState};
+    pub use super::revert::{RevertAction, RevertWindow, RevisionStamp};
     pub use super::structs::*;
     pub use super::tasks::PageRevisionTasks;
 }
 
+mod blame;
+mod merge;
+mod revert;
 mod service;
 mod structs;
 mod tasks;
 
+pub use self::merge::{merge_tags, merge_value, merge_wikitext, EditConflict};
 pub use self::service::PageRevisionService;
 pub use self::structs::*;
+pub use self::tasks::PageRevisionTasks;
@@ -0,0 +1,165 @@
+/*
+ * services/page_revision/revert.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use time::OffsetDateTime;
+
+/// The window of one user's edits being mass reverted.
+#[derive(Debug, Copy, Clone)]
+pub struct RevertWindow {
+    pub author_id: i64,
+    pub start: OffsetDateTime,
+    pub end: OffsetDateTime,
+}
+
+/// The parts of a page revision needed to decide how to revert it.
+#[derive(Debug, Copy, Clone)]
+pub struct RevisionStamp {
+    pub revision_number: i64,
+    pub user_id: i64,
+    pub created_at: OffsetDateTime,
+}
+
+/// What a mass revert should do with one page.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RevertAction {
+    /// Roll the page back to this revision number.
+    Rollback(i64),
+
+    /// The page was created in the window, so there's nothing to roll back to.
+    Skip,
+
+    /// Leave the page as it is, since it's deleted or was edited afterwards.
+    Ignore,
+}
+
+impl RevertWindow {
+    #[inline]
+    fn contains(&self, revision: &RevisionStamp) -> bool {
+        revision.user_id == self.author_id
+            && revision.created_at >= self.start
+            && revision.created_at <= self.end
+    }
+
+    /// Decides what to do with a page, given its revisions in order.
+    pub fn plan(&self, deleted: bool, revisions: &[RevisionStamp]) -> RevertAction {
+        if deleted {
+            return RevertAction::Ignore;
+        }
+
+        // Only revert pages which haven't been edited by anyone else since
+        match revisions.last() {
+            Some(latest) if self.contains(latest) => (),
+            _ => return RevertAction::Ignore,
+        }
+
+        // Go back to the latest revision not made by the user in the window,
+        // which keeps any other users' edits made in between.
+        match revisions
+            .iter()
+            .rev()
+            .find(|revision| !self.contains(revision))
+        {
+            Some(target) => RevertAction::Rollback(target.revision_number),
+            None => RevertAction::Skip,
+        }
+    }
+}
+
+#[test]
+fn plan() {
+    use time::macros::datetime;
+
+    const AUTHOR: i64 = 1;
+    const OTHER: i64 = 2;
+
+    let window = RevertWindow {
+        author_id: AUTHOR,
+        start: datetime!(2024-01-01 00:00 UTC),
+        end: datetime!(2024-01-02 00:00 UTC),
+    };
+
+    macro_rules! revisions {
+        ($(($user_id:expr, $created_at:expr)),* $(,)?) => {
+            [$(($user_id, $created_at)),*]
+                .into_iter()
+                .enumerate()
+                .map(|(revision_number, (user_id, created_at))| RevisionStamp {
+                    revision_number: revision_number as i64,
+                    user_id,
+                    created_at,
+                })
+                .collect::<Vec<_>>()
+        };
+    }
+
+    macro_rules! check {
+        ($deleted:expr, $revisions:expr, $expected:expr $(,)?) => {
+            assert_eq!(
+                window.plan($deleted, &$revisions),
+                $expected,
+                "Actual revert action doesn't match expected",
+            );
+        };
+    }
+
+    let before = datetime!(2023-12-31 12:00 UTC);
+    let during = datetime!(2024-01-01 12:00 UTC);
+    let after = datetime!(2024-01-03 12:00 UTC);
+
+    // Only the user's edits in the window are undone
+    let simple = revisions![(OTHER, before), (AUTHOR, before), (AUTHOR, during)];
+    check!(false, simple, RevertAction::Rollback(1));
+
+    // Interleaved edits by other users are kept
+    check!(
+        false,
+        revisions![
+            (OTHER, before),
+            (AUTHOR, during),
+            (OTHER, during),
+            (AUTHOR, during),
+            (AUTHOR, during),
+        ],
+        RevertAction::Rollback(2),
+    );
+
+    // Pages edited by someone else afterwards are left alone
+    check!(
+        false,
+        revisions![(OTHER, before), (AUTHOR, during), (OTHER, during)],
+        RevertAction::Ignore,
+    );
+    check!(
+        false,
+        revisions![(OTHER, before), (AUTHOR, during), (AUTHOR, after)],
+        RevertAction::Ignore,
+    );
+
+    // Pages created in the window are skipped
+    check!(
+        false,
+        revisions![(AUTHOR, during), (AUTHOR, during)],
+        RevertAction::Skip,
+    );
+
+    // Deleted pages are never touched
+    check!(true, simple, RevertAction::Ignore);
+    check!(true, revisions![(AUTHOR, during)], RevertAction::Ignore);
+}
@@ -18,50 +18,49 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
+use super::blame::apply_revision;
 use super::prelude::*;
 use crate::models::page_revision::{
     self, Entity as PageRevision, Model as PageRevisionModel,
 };
-use crate::models::sea_orm_active_enums::PageRevisionType;
-use crate::services::render::RenderOutput;
+use crate::models::sea_orm_active_enums::{PageRevisionChange, PageRevisionType};
+use crate::services::audit::CreateAuditEntry;
+use crate::services::event::{Event, EventService};
+use crate::services::page::{GetPageReference, RollbackPage};
+use crate::services::render::{RenderOutput, RenderPriority};
+use crate::services::revision_comparison::{diff_lines, diff_lines_refined};
 use crate::services::score::ScoreValue;
+use crate::services::tag_rule::TagRuleTarget;
 use crate::services::{
-    LinkService, OutdateService, ParentService, RenderService, ScoreService, SiteService,
-    TextService,
+    AuditService, LimitService, LinkService, OutdateService, PageFormService,
+    PageService, ParentService, RenderConfigService, RenderErrorService, RenderService,
+    ScoreService, SiteService, StableRevisionService, TagRuleService, TextService,
 };
-use crate::utils::{split_category, split_category_name};
+use crate::utils::{format_byte_delta, split_category};
 use crate::web::FetchDirection;
 use ftml::data::PageInfo;
-use ftml::settings::{WikitextMode, WikitextSettings};
-use once_cell::sync::Lazy;
+use ftml::settings::WikitextMode;
+use redis::AsyncCommands;
 use ref_map::*;
-use std::num::NonZeroI32;
+use std::collections::HashMap;
+use std::num::NonZeroI64;
 
 /// The changes for the first revision.
 /// The first revision is always considered to have changed everything.
 ///
 /// See `services/file_revision/service.rs`.
-static ALL_CHANGES: Lazy<Vec<String>> = Lazy::new(|| {
-    vec![
-        str!("wikitext"),
-        str!("title"),
-        str!("alt_title"),
-        str!("slug"),
-        str!("tags"),
-    ]
-});
-
-macro_rules! conditional_future {
-    ($conditional:expr, $future:expr $(,)?) => {
-        async move {
-            if $conditional {
-                $future.await
-            } else {
-                Ok(())
-            }
-        }
-    };
-}
+const ALL_CHANGES: [PageRevisionChange; 5] = [
+    PageRevisionChange::Wikitext,
+    PageRevisionChange::Title,
+    PageRevisionChange::AltTitle,
+    PageRevisionChange::Slug,
+    PageRevisionChange::Tags,
+];
+
+/// The largest attached image which is embedded in printed pages.
+///
+/// Larger images are linked to instead.
+const PRINT_IMAGE_MAX_SIZE: usize = 5 * 1024 * 1024;
 
 #[derive(Debug)]
 pub struct PageRevisionService;
@@ -106,6 +105,18 @@ impl PageRevisionService {
         let txn = ctx.transaction();
         let revision_number = next_revision_number(&previous, site_id, page_id);
 
+        // Check size limits before doing any work
+        let limits = LimitService::get(ctx, site_id).await?;
+        limits.check_comment(&comments)?;
+
+        if let ProvidedValue::Set(ref wikitext) = body.wikitext {
+            limits.check_wikitext(wikitext)?;
+        }
+
+        if let ProvidedValue::Set(ref tags) = body.tags {
+            limits.check_tags(tags)?;
+        }
+
         // Fields to create in the revision
         let mut parser_errors = None;
         let mut old_slug = None;
@@ -115,6 +126,8 @@ impl PageRevisionService {
             mut compiled_hash,
             mut compiled_at,
             mut compiled_generator,
+            mut content_stats,
+            mut compiled_csp,
             hidden,
             mut title,
             mut alt_title,
@@ -122,6 +135,7 @@ impl PageRevisionService {
             mut tags,
             ..
         } = previous;
+        let previous_tags = tags.clone();
 
         // Update fields from input
         //
@@ -130,21 +144,21 @@ impl PageRevisionService {
 
         if let ProvidedValue::Set(new_title) = body.title {
             if title != new_title {
-                changes.push(str!("title"));
+                changes.push(PageRevisionChange::Title);
                 title = new_title;
             }
         }
 
         if let ProvidedValue::Set(new_alt_title) = body.alt_title {
             if alt_title != new_alt_title {
-                changes.push(str!("alt_title"));
+                changes.push(PageRevisionChange::AltTitle);
                 alt_title = new_alt_title;
             }
         }
 
         if let ProvidedValue::Set(new_slug) = body.slug {
             if slug != new_slug {
-                changes.push(str!("slug"));
+                changes.push(PageRevisionChange::Slug);
                 old_slug = Some(slug);
                 slug = new_slug;
             }
@@ -152,14 +166,11 @@ impl PageRevisionService {
 
         if let ProvidedValue::Set(new_tags) = body.tags {
             if tags != new_tags {
-                changes.push(str!("tags"));
+                changes.push(PageRevisionChange::Tags);
                 tags = new_tags;
             }
         }
 
-        // Get slug strings for the new location
-        let (category_slug, page_slug) = split_category_name(&slug);
-
         // Get wikitext, set wikitext hash
         let wikitext = match body.wikitext {
             // Insert new wikitext and update hash
@@ -167,7 +178,7 @@ impl PageRevisionService {
                 let new_hash = TextService::create(ctx, new_wikitext.clone()).await?;
 
                 if wikitext_hash != new_hash {
-                    changes.push(str!("wikitext"));
+                    changes.push(PageRevisionChange::Wikitext);
                     replace_hash(&mut wikitext_hash, &new_hash);
                 }
 
@@ -178,6 +189,30 @@ impl PageRevisionService {
             ProvidedValue::Unset => TextService::get(ctx, &wikitext_hash).await?,
         };
 
+        // Apply automatic tagging rules, but only if this is a real edit.
+        //
+        // The rules may undo the editor's tag changes, or change tags
+        // themselves, so check against the previous revision's tags again.
+        let mut applied_tag_rules = Vec::new();
+        if !changes.is_empty() {
+            let target = TagRuleTarget {
+                site_id,
+                user_id,
+                slug: &slug,
+                title: &title,
+            };
+
+            applied_tag_rules = TagRuleService::apply(ctx, target, &mut tags).await?;
+            if !applied_tag_rules.is_empty() {
+                limits.check_tags(&tags)?;
+            }
+
+            changes.retain(|change| *change != PageRevisionChange::Tags);
+            if tags != previous_tags {
+                changes.push(PageRevisionChange::Tags);
+            }
+        }
+
         // If nothing has changed, then don't create a new revision
         if changes.is_empty() {
             Self::rerender(ctx, site_id, page_id, 0).await?;
@@ -221,59 +256,16 @@ impl PageRevisionService {
             parser_errors = Some(render_output.errors);
             replace_hash(&mut compiled_hash, &render_output.compiled_hash);
             compiled_generator = render_output.compiled_generator;
+            content_stats = serde_json::to_value(&render_output.content_stats)?;
+            compiled_csp = serde_json::to_value(&render_output.html_output.csp)?;
             compiled_at = now();
         }
 
-        // Perform outdating based on changes made.
-        //
-        // Also, determine the revision type.
+        // Determine the revision type.
         // If the slug changes it's "move", otherwise "regular".
         let revision_type = match old_slug {
-            Some(ref old_slug) => {
-                // If there's an "old slug" set, then this is a page rename / move.
-                // Thus we should invoke the OutdateService for both the source
-                // and destination.
-                //
-                // This is equivalent to the three outdate calls below, but for
-                // the source and destination slugs, which is why we don't
-                // also run those again.
-
-                OutdateService::process_page_move(
-                    ctx, site_id, page_id, old_slug, &slug, 0,
-                )
-                .await?;
-
-                PageRevisionType::Move
-            }
-            None => {
-                // Run all outdating tasks in parallel.
-                //
-                // This macro runs the given method (second value) if the condition (first value)
-                // is true, otherwise does nothing.
-
-                try_join!(
-                    conditional_future!(
-                        tasks.rerender_incoming_links,
-                        OutdateService::outdate_incoming_links(ctx, page_id, 0),
-                    ),
-                    conditional_future!(
-                        tasks.rerender_outgoing_includes,
-                        OutdateService::outdate_outgoing_includes(ctx, page_id, 0),
-                    ),
-                    conditional_future!(
-                        tasks.rerender_templates,
-                        OutdateService::outdate_templates(
-                            ctx,
-                            site_id,
-                            category_slug,
-                            page_slug,
-                            0,
-                        ),
-                    ),
-                )?;
-
-                PageRevisionType::Regular
-            }
+            Some(_) => PageRevisionType::Move,
+            None => PageRevisionType::Regular,
         };
 
         // Insert the new revision into the table
@@ -283,21 +275,47 @@ impl PageRevisionService {
             page_id: Set(page_id),
             site_id: Set(site_id),
             user_id: Set(user_id),
-            changes: Set(changes),
+            changes: Set(changes.clone()),
             wikitext_hash: Set(wikitext_hash),
             compiled_hash: Set(compiled_hash),
             compiled_at: Set(compiled_at),
             compiled_generator: Set(compiled_generator),
+            content_stats: Set(content_stats),
+            compiled_csp: Set(compiled_csp),
             comments: Set(comments),
             hidden: Set(hidden),
             title: Set(title),
             alt_title: Set(alt_title),
-            slug: Set(slug),
+            slug: Set(slug.clone()),
             tags: Set(tags),
+            applied_tag_rules: Set(applied_tag_rules),
             ..Default::default()
         };
 
         let PageRevisionModel { revision_id, .. } = model.insert(txn).await?;
+
+        // Notify subscribers, who handle outdating based on the changes made.
+        //
+        // If there's an "old slug" set, then this is a page rename / move,
+        // which affects both the source and destination.
+        let event = match old_slug {
+            Some(old_slug) => Event::PageMoved {
+                site_id,
+                page_id,
+                revision_id,
+                old_slug,
+                new_slug: slug,
+            },
+            None => Event::PageEdited {
+                site_id,
+                page_id,
+                revision_id,
+                slug,
+                changes,
+            },
+        };
+
+        EventService::emit(ctx, event).await?;
         Ok(Some(CreatePageRevisionOutput {
             revision_id,
             revision_number,
@@ -327,9 +345,27 @@ impl PageRevisionService {
     ) -> Result<CreateFirstPageRevisionOutput> {
         let txn = ctx.transaction();
 
+        // Check size limits
+        let limits = LimitService::get(ctx, site_id).await?;
+        limits.check_comment(&comments)?;
+        limits.check_wikitext(&wikitext)?;
+
         // Add wikitext
         let wikitext_hash = TextService::create(ctx, wikitext.clone()).await?;
 
+        // Apply automatic tagging rules.
+        // Otherwise the initial revision always has empty tags.
+        let mut tags = Vec::new();
+        let target = TagRuleTarget {
+            site_id,
+            user_id,
+            slug: &slug,
+            title: &title,
+        };
+
+        let applied_tag_rules = TagRuleService::apply(ctx, target, &mut tags).await?;
+        limits.check_tags(&tags)?;
+
         // Calculate score
         let score = ScoreService::score(ctx, page_id).await?;
 
@@ -339,21 +375,22 @@ impl PageRevisionService {
             title: &title,
             alt_title: alt_title.ref_map(|s| s.as_str()),
             score,
-            tags: &[], // Initial revision always has empty tags
+            tags: &tags,
         };
 
         let RenderOutput {
-            // TODO: use html_output
-            html_output: _,
+            html_output,
             errors,
+            included_pages: _,
             compiled_hash,
             compiled_at,
             compiled_generator,
+            content_stats,
         } = Self::render_and_update_links(ctx, site_id, page_id, wikitext, render_input)
             .await?;
 
-        // Run outdater
-        OutdateService::process_page_displace(ctx, site_id, page_id, &slug, 0).await?;
+        let content_stats = serde_json::to_value(&content_stats)?;
+        let compiled_csp = serde_json::to_value(&html_output.csp)?;
 
         // Insert the first revision into the table
         let model = page_revision::ActiveModel {
@@ -362,21 +399,35 @@ impl PageRevisionService {
             page_id: Set(page_id),
             site_id: Set(site_id),
             user_id: Set(user_id),
-            changes: Set(ALL_CHANGES.clone()),
+            changes: Set(ALL_CHANGES.to_vec()),
             wikitext_hash: Set(wikitext_hash.to_vec()),
             compiled_hash: Set(compiled_hash.to_vec()),
             compiled_at: Set(compiled_at),
             compiled_generator: Set(compiled_generator),
+            content_stats: Set(content_stats),
+            compiled_csp: Set(compiled_csp),
             comments: Set(comments),
             hidden: Set(vec![]),
             title: Set(title),
             alt_title: Set(alt_title),
-            slug: Set(slug),
-            tags: Set(vec![]),
+            slug: Set(slug.clone()),
+            tags: Set(tags),
+            applied_tag_rules: Set(applied_tag_rules),
             ..Default::default()
         };
 
         let PageRevisionModel { revision_id, .. } = model.insert(txn).await?;
+        EventService::emit(
+            ctx,
+            Event::PageCreated {
+                site_id,
+                page_id,
+                revision_id,
+                slug,
+            },
+        )
+        .await?;
+
         Ok(CreateFirstPageRevisionOutput {
             revision_id,
             parser_errors: errors,
@@ -403,11 +454,17 @@ impl PageRevisionService {
         let txn = ctx.transaction();
         let revision_number = next_revision_number(&previous, site_id, page_id);
 
+        LimitService::get(ctx, site_id)
+            .await?
+            .check_comment(&comments)?;
+
         let PageRevisionModel {
             wikitext_hash,
             compiled_hash,
             compiled_at,
             compiled_generator,
+            content_stats,
+            compiled_csp,
             title,
             alt_title,
             slug,
@@ -415,9 +472,6 @@ impl PageRevisionService {
             ..
         } = previous;
 
-        // Run outdater
-        OutdateService::process_page_displace(ctx, site_id, page_id, &slug, 0).await?;
-
         // Delete parent-child relationships, if any
         ParentService::remove_all(ctx, page_id).await?;
 
@@ -433,16 +487,29 @@ impl PageRevisionService {
             compiled_hash: Set(compiled_hash),
             compiled_at: Set(compiled_at),
             compiled_generator: Set(compiled_generator),
+            content_stats: Set(content_stats),
+            compiled_csp: Set(compiled_csp),
             comments: Set(comments),
             hidden: Set(vec![]),
             title: Set(title),
             alt_title: Set(alt_title),
-            slug: Set(slug),
+            slug: Set(slug.clone()),
             tags: Set(tags),
             ..Default::default()
         };
 
         let PageRevisionModel { revision_id, .. } = model.insert(txn).await?;
+        EventService::emit(
+            ctx,
+            Event::PageDeleted {
+                site_id,
+                page_id,
+                revision_id,
+                slug,
+            },
+        )
+        .await?;
+
         Ok(CreatePageRevisionOutput {
             revision_id,
             revision_number,
@@ -479,6 +546,10 @@ impl PageRevisionService {
         let txn = ctx.transaction();
         let revision_number = next_revision_number(&previous, site_id, page_id);
 
+        LimitService::get(ctx, site_id)
+            .await?
+            .check_comment(&comments)?;
+
         let PageRevisionModel {
             wikitext_hash,
             mut compiled_hash,
@@ -493,7 +564,7 @@ impl PageRevisionService {
         let changes = if old_slug == new_slug {
             vec![]
         } else {
-            vec![str!("slug")]
+            vec![PageRevisionChange::Slug]
         };
 
         // Calculate score
@@ -510,20 +581,20 @@ impl PageRevisionService {
 
         let wikitext = TextService::get(ctx, &wikitext_hash).await?;
         let RenderOutput {
-            // TODO: use html_output
-            html_output: _,
+            html_output,
             errors,
+            included_pages: _,
             compiled_hash: new_compiled_hash,
             compiled_at,
             compiled_generator,
+            content_stats,
         } = Self::render_and_update_links(ctx, site_id, page_id, wikitext, render_input)
             .await?;
 
-        replace_hash(&mut compiled_hash, &new_compiled_hash);
+        let content_stats = serde_json::to_value(&content_stats)?;
+        let compiled_csp = serde_json::to_value(&html_output.csp)?;
 
-        // Run outdater
-        OutdateService::process_page_displace(ctx, site_id, page_id, &new_slug, 0)
-            .await?;
+        replace_hash(&mut compiled_hash, &new_compiled_hash);
 
         // Insert the resurrection revision into the table
         let model = page_revision::ActiveModel {
@@ -537,16 +608,29 @@ impl PageRevisionService {
             compiled_hash: Set(compiled_hash),
             compiled_at: Set(compiled_at),
             compiled_generator: Set(compiled_generator),
+            content_stats: Set(content_stats),
+            compiled_csp: Set(compiled_csp),
             comments: Set(comments),
             hidden: Set(hidden),
             title: Set(title),
             alt_title: Set(alt_title),
-            slug: Set(new_slug),
+            slug: Set(new_slug.clone()),
             tags: Set(tags),
             ..Default::default()
         };
 
         let PageRevisionModel { revision_id, .. } = model.insert(txn).await?;
+        EventService::emit(
+            ctx,
+            Event::PageRestored {
+                site_id,
+                page_id,
+                revision_id,
+                slug: new_slug,
+            },
+        )
+        .await?;
+
         Ok(CreatePageRevisionOutput {
             revision_id,
             revision_number,
@@ -564,6 +648,130 @@ impl PageRevisionService {
         site_id: i64,
         page_id: i64,
         wikitext: String,
+        render_input: RenderPageInfo<'_>,
+    ) -> Result<RenderOutput> {
+        let (wikitext, form_data) =
+            PageFormService::expand(ctx, site_id, render_input.slug, wikitext).await?;
+
+        let priority = RenderService::caller_priority(ctx).await?;
+        let output = Self::render(
+            ctx,
+            site_id,
+            wikitext,
+            render_input,
+            WikitextMode::Page,
+            priority,
+        )
+        .await?;
+
+        // Update backlinks, render status, and form values
+        LinkService::update(ctx, site_id, page_id, &output).await?;
+        RenderErrorService::record(ctx, site_id, page_id, &output.errors).await?;
+        PageFormService::record(ctx, site_id, page_id, form_data).await?;
+
+        Ok(output)
+    }
+
+    /// Renders wikitext which hasn't been saved, such as while editing a page.
+    ///
+    /// Since this may be anyone's draft, it uses the safe preview mode, so
+    /// nothing is included or fetched. Previews can be retried, so they are
+    /// low priority, and are turned away when too many renders are running.
+    pub async fn preview(
+        ctx: &ServiceContext<'_>,
+        PreviewPage {
+            site_id,
+            slug,
+            title,
+            alt_title,
+            tags,
+            wikitext,
+        }: PreviewPage,
+    ) -> Result<RenderOutput> {
+        let render_input = RenderPageInfo {
+            slug: &slug,
+            title: &title,
+            alt_title: alt_title.ref_map(|s| s.as_str()),
+            score: ScoreValue::Integer(0),
+            tags: &tags,
+        };
+
+        let (wikitext, _) =
+            PageFormService::expand(ctx, site_id, &slug, wikitext).await?;
+        Self::render(
+            ctx,
+            site_id,
+            wikitext,
+            render_input,
+            WikitextMode::SafePreview,
+            RenderPriority::Low,
+        )
+        .await
+    }
+
+    /// Renders a page as a standalone HTML document, such as for printing.
+    ///
+    /// This is the revision shown to readers, rendered with `render_print()`.
+    pub async fn print(
+        ctx: &ServiceContext<'_>,
+        GetPageReference { site_id, page }: GetPageReference<'_>,
+    ) -> Result<PrintPageOutput> {
+        let site = SiteService::get(ctx, Reference::Id(site_id)).await?;
+        let page = PageService::get(ctx, site_id, page).await?;
+        let revision =
+            StableRevisionService::get_page_revision(ctx, &site, &page).await?;
+
+        let RenderOutput {
+            html_output,
+            errors,
+            ..
+        } = Self::render_print(ctx, &revision).await?;
+
+        let html = html_output.to_document(&revision.title, &site.locale, "");
+        Ok(PrintPageOutput { html, errors })
+    }
+
+    /// Renders a revision's wikitext in print mode, without saving anything.
+    ///
+    /// The output has no interactive elements and is self-contained:
+    /// included pages are inlined, and attached images are embedded
+    /// unless they are larger than `PRINT_IMAGE_MAX_SIZE`.
+    pub async fn render_print(
+        ctx: &ServiceContext<'_>,
+        revision: &PageRevisionModel,
+    ) -> Result<RenderOutput> {
+        let wikitext = TextService::get(ctx, &revision.wikitext_hash).await?;
+        let score = ScoreService::score(ctx, revision.page_id).await?;
+        let render_input = RenderPageInfo {
+            slug: &revision.slug,
+            title: &revision.title,
+            alt_title: revision.alt_title.ref_map(|s| s.as_str()),
+            score,
+            tags: &revision.tags,
+        };
+
+        let (wikitext, _) =
+            PageFormService::expand(ctx, revision.site_id, &revision.slug, wikitext)
+                .await?;
+        let priority = RenderService::caller_priority(ctx).await?;
+        Self::render(
+            ctx,
+            revision.site_id,
+            wikitext,
+            render_input,
+            WikitextMode::Print,
+            priority,
+        )
+        .await
+    }
+
+    /// Helper method to render a revision's wikitext, without saving anything.
+    ///
+    /// For pages with a data form, this is the wikitext after `PageFormService::expand()`.
+    async fn render(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        wikitext: String,
         RenderPageInfo {
             slug,
             title,
@@ -571,13 +779,30 @@ impl PageRevisionService {
             score,
             tags,
         }: RenderPageInfo<'_>,
+        mode: WikitextMode,
+        priority: RenderPriority,
     ) -> Result<RenderOutput> {
         // Get site
         let site = SiteService::get(ctx, Reference::from(site_id)).await?;
 
         // Set up parse context
-        let settings = WikitextSettings::from_mode(WikitextMode::Page);
         let (category_slug, page_slug) = split_category(slug);
+        let mut settings = RenderConfigService::wikitext_settings(
+            ctx,
+            site_id,
+            category_slug.unwrap_or("_default"),
+            mode,
+        )
+        .await?;
+
+        // Printed output is standalone, so nothing can be fetched from it later
+        let wikitext = if mode == WikitextMode::Print {
+            settings.embed_image_max_size = Some(PRINT_IMAGE_MAX_SIZE);
+            RenderService::inline_includes(ctx, &site.slug, wikitext, &settings).await?
+        } else {
+            wikitext
+        };
+
         let page_info = PageInfo {
             page: cow!(page_slug),
             category: cow_opt!(category_slug),
@@ -587,15 +812,54 @@ impl PageRevisionService {
             score,
             tags: tags.iter().map(|s| cow!(s)).collect(),
             language: cow!(&site.locale),
+
+            // Revisions are rendered once and shown to everyone,
+            // so dates are left in UTC for the client to adjust.
+            utc_offset: None,
         };
 
         // Parse and render
-        let output = RenderService::render(ctx, wikitext, &page_info, &settings).await?;
+        RenderService::render(ctx, wikitext, &page_info, &settings, priority).await
+    }
+
+    /// Re-extracts the links from the latest revision of a page.
+    ///
+    /// Unlike `rerender()`, the compiled output is not saved and pages
+    /// depending on this one are left alone, only the link tables are
+    /// brought up to date.
+    ///
+    /// # Returns
+    /// Whether any of the page's stored links changed.
+    pub async fn refresh_links(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        page_id: i64,
+    ) -> Result<bool> {
+        let revision = Self::get_latest(ctx, site_id, page_id).await?;
+        let wikitext = TextService::get(ctx, &revision.wikitext_hash).await?;
+        let score = ScoreService::score(ctx, page_id).await?;
+        let render_input = RenderPageInfo {
+            slug: &revision.slug,
+            title: &revision.title,
+            alt_title: revision.alt_title.ref_map(|s| s.as_str()),
+            score,
+            tags: &revision.tags,
+        };
 
-        // Update backlinks
-        LinkService::update(ctx, site_id, page_id, &output.html_output.backlinks).await?;
+        let (wikitext, _) =
+            PageFormService::expand(ctx, site_id, &revision.slug, wikitext).await?;
+        let priority = RenderService::caller_priority(ctx).await?;
+        let output = Self::render(
+            ctx,
+            site_id,
+            wikitext,
+            render_input,
+            WikitextMode::Page,
+            priority,
+        )
+        .await?;
 
-        Ok(output)
+        LinkService::update(ctx, site_id, page_id, &output).await
     }
 
     /// Re-renders a page.
@@ -655,10 +919,11 @@ impl PageRevisionService {
             tags: &revision.tags,
         };
 
-        // TODO use html_output
         let RenderOutput {
+            html_output,
             compiled_hash,
             compiled_generator,
+            content_stats,
             ..
         } = Self::render_and_update_links(ctx, site_id, page_id, wikitext, render_input)
             .await?;
@@ -672,6 +937,8 @@ impl PageRevisionService {
             revision_id: Set(revision.revision_id),
             compiled_hash: Set(compiled_hash.to_vec()),
             compiled_generator: Set(compiled_generator),
+            content_stats: Set(serde_json::to_value(&content_stats)?),
+            compiled_csp: Set(serde_json::to_value(&html_output.csp)?),
             ..Default::default()
         };
 
@@ -694,6 +961,7 @@ impl PageRevisionService {
             revision_id,
             user_id,
             hidden,
+            reason,
         }: UpdatePageRevision,
     ) -> Result<()> {
         let txn = ctx.transaction();
@@ -721,8 +989,40 @@ impl PageRevisionService {
             return Err(Error::CannotHideLatestRevision);
         }
 
-        // TODO: record revision edit in audit log
-        let _ = user_id;
+        // The same goes for the stable revision, if it is served instead.
+        let page = PageService::get_direct(ctx, page_id, true).await?;
+        if page.stable_revision_id == Some(revision_id) && contains(&hidden, "wikitext") {
+            return Err(Error::CannotHideStableRevision);
+        }
+
+        let revision = Self::get_direct(ctx, revision_id).await?;
+        if revision.site_id != site_id || revision.page_id != page_id {
+            warn!("Revision's site or page ID and passed IDs do not match");
+            return Err(Error::PageRevisionNotFound);
+        }
+
+        AuditService::record(
+            ctx,
+            CreateAuditEntry {
+                actor_id: user_id,
+                subject_id: revision.user_id,
+                site_id: Some(site_id),
+                action: if hidden.is_empty() {
+                    "page.revision-unhide"
+                } else {
+                    "page.revision-hide"
+                },
+                data: serde_json::json!({
+                    "page_id": page_id,
+                    "revision_id": revision_id,
+                    "revision_number": revision.revision_number,
+                    "hidden": hidden,
+                    "previously_hidden": revision.hidden,
+                    "reason": reason,
+                }),
+            },
+        )
+        .await?;
 
         // Update the revision
 
@@ -738,6 +1038,149 @@ impl PageRevisionService {
         Ok(())
     }
 
+    /// Reverts every page in a site whose latest edit was by a user within a time window.
+    ///
+    /// Each page is rolled back to its latest revision not made by that user
+    /// within the window, so any legitimate edits in between are kept. Pages
+    /// the user created in the window have nothing to go back to, so they're
+    /// skipped and returned separately to be dealt with, such as by deletion.
+    ///
+    /// The whole operation is recorded as a single audit log entry.
+    pub async fn mass_revert(
+        ctx: &ServiceContext<'_>,
+        MassRevertPages {
+            site_id,
+            author_id,
+            start,
+            end,
+            user_id,
+            revision_comments,
+            reason,
+        }: MassRevertPages,
+    ) -> Result<MassRevertOutput> {
+        info!(
+            "Reverting edits by user ID {author_id} in site ID {site_id} between {start} and {end}",
+        );
+
+        if start > end {
+            error!("Mass revert window ends before it starts");
+            return Err(Error::BadRequest);
+        }
+
+        // Every page the user edited in the window
+        let txn = ctx.transaction();
+
+        let page_ids: Vec<i64> = PageRevision::find()
+            .select_only()
+            .column(page_revision::Column::PageId)
+            .distinct()
+            .filter(page_revision::Column::SiteId.eq(site_id))
+            .filter(page_revision::Column::UserId.eq(author_id))
+            .filter(page_revision::Column::CreatedAt.gte(start))
+            .filter(page_revision::Column::CreatedAt.lte(end))
+            .order_by_asc(page_revision::Column::PageId)
+            .into_tuple()
+            .all(txn)
+            .await?;
+
+        let window = RevertWindow {
+            author_id,
+            start,
+            end,
+        };
+
+        let mut output = MassRevertOutput::default();
+        for page_id in page_ids {
+            let deleted = PageService::get_direct_optional(ctx, page_id, false)
+                .await?
+                .is_none();
+
+            let revisions = PageRevision::find()
+                .select_only()
+                .column(page_revision::Column::RevisionNumber)
+                .column(page_revision::Column::UserId)
+                .column(page_revision::Column::CreatedAt)
+                .filter(page_revision::Column::PageId.eq(page_id))
+                .order_by_asc(page_revision::Column::RevisionNumber)
+                .into_tuple()
+                .all(txn)
+                .await?
+                .into_iter()
+                .map(|(revision_number, user_id, created_at)| RevisionStamp {
+                    revision_number,
+                    user_id,
+                    created_at,
+                })
+                .collect::<Vec<_>>();
+
+            let target = match window.plan(deleted, &revisions) {
+                RevertAction::Rollback(revision_number) => revision_number,
+                RevertAction::Skip => {
+                    debug!("Page ID {page_id} was created in the window, skipping");
+                    output.skipped.push(page_id);
+                    continue;
+                }
+                RevertAction::Ignore => {
+                    debug!("Page ID {page_id} is deleted or edited since, not reverting");
+                    continue;
+                }
+            };
+
+            let revision = PageService::rollback(
+                ctx,
+                RollbackPage {
+                    site_id,
+                    page: Reference::Id(page_id),
+                    revision_number: target,
+                    revision_comments: revision_comments.clone(),
+                    user_id,
+                },
+            )
+            .await?;
+
+            // If the edits cancelled out, then there's nothing to revert
+            if let Some(CreatePageRevisionOutput {
+                revision_id,
+                revision_number,
+                ..
+            }) = revision
+            {
+                output.reverted.push(MassRevertedPage {
+                    page_id,
+                    reverted_to: target,
+                    revision_id,
+                    revision_number,
+                });
+            }
+        }
+
+        AuditService::record(
+            ctx,
+            CreateAuditEntry {
+                actor_id: user_id,
+                subject_id: author_id,
+                site_id: Some(site_id),
+                action: "page.mass-revert",
+                data: serde_json::json!({
+                    "start": start,
+                    "end": end,
+                    "reverted": output.reverted,
+                    "skipped": output.skipped,
+                    "reason": reason,
+                }),
+            },
+        )
+        .await?;
+
+        info!(
+            "Reverted {} pages, skipped {} created by user ID {author_id}",
+            output.reverted.len(),
+            output.skipped.len(),
+        );
+
+        Ok(output)
+    }
+
     pub async fn get_latest(
         ctx: &ServiceContext<'_>,
         site_id: i64,
@@ -765,7 +1208,7 @@ impl PageRevisionService {
         ctx: &ServiceContext<'_>,
         site_id: i64,
         page_id: i64,
-        revision_number: i32,
+        revision_number: i64,
     ) -> Result<Option<PageRevisionModel>> {
         let txn = ctx.transaction();
         let revision = PageRevision::find()
@@ -786,7 +1229,7 @@ impl PageRevisionService {
         ctx: &ServiceContext<'_>,
         site_id: i64,
         page_id: i64,
-        revision_number: i32,
+        revision_number: i64,
     ) -> Result<PageRevisionModel> {
         find_or_error!(
             Self::get_optional(ctx, site_id, page_id, revision_number),
@@ -814,7 +1257,7 @@ impl PageRevisionService {
         ctx: &ServiceContext<'_>,
         site_id: i64,
         page_id: i64,
-    ) -> Result<NonZeroI32> {
+    ) -> Result<NonZeroI64> {
         let txn = ctx.transaction();
         let row_count = PageRevision::find()
             .filter(
@@ -825,20 +1268,139 @@ impl PageRevisionService {
             .count(txn)
             .await?;
 
-        // We store revision_number in INT, which is i32.
-        // So even though this row count is usize, it
-        // should always fit inside an i32.
-        let row_count = i32::try_from(row_count)
-            .expect("Revision row count greater than revision_number integer size");
+        // We store revision_number in BIGINT, which is i64.
+        // No table will realistically have more rows than that,
+        // but report an error rather than panicking if one does.
+        let row_count = i64::try_from(row_count).map_err(|_| {
+            error!("Revision row count {row_count} is greater than i64::MAX");
+            Error::RevisionCountOverflow
+        })?;
 
         // All pages have at least one revision, so if there are none
         // that means this page does not exist, and we should return an error.
-        match NonZeroI32::new(row_count) {
+        match NonZeroI64::new(row_count) {
             Some(count) => Ok(count),
             None => Err(Error::PageNotFound),
         }
     }
 
+    /// Describes what a revision changed, compared to the one before it.
+    ///
+    /// Fields which are hidden on either revision are left out, so
+    /// the summary does not reveal anything the revisions themselves don't.
+    pub async fn summarize(
+        ctx: &ServiceContext<'_>,
+        revision: &PageRevisionModel,
+    ) -> Result<RevisionSummary> {
+        let previous = match revision.revision_number {
+            0 => None,
+            number => {
+                Self::get_optional(ctx, revision.site_id, revision.page_id, number - 1)
+                    .await?
+            }
+        };
+
+        let mut hashes = vec![revision.wikitext_hash.as_slice()];
+        if let Some(ref previous) = previous {
+            hashes.push(&previous.wikitext_hash);
+        }
+
+        let sizes = TextService::sizes(ctx, &hashes).await?;
+        Ok(Self::summarize_with(revision, previous.as_ref(), &sizes))
+    }
+
+    /// Like `summarize()`, but using an already-fetched previous revision and text sizes.
+    ///
+    /// This is for summarizing many revisions at once, where fetching these
+    /// individually would be one query per revision.
+    pub fn summarize_with(
+        revision: &PageRevisionModel,
+        previous: Option<&PageRevisionModel>,
+        sizes: &HashMap<Vec<u8>, i64>,
+    ) -> RevisionSummary {
+        let is_hidden = |field: &str| {
+            revision.hidden.iter().any(|hidden| hidden == field)
+                || previous.is_some_and(|previous| {
+                    previous.hidden.iter().any(|hidden| hidden == field)
+                })
+        };
+
+        let size = |hash: &Vec<u8>| sizes.get(hash).copied().unwrap_or(0);
+
+        // Get wikitext size change
+        let size_delta = if is_hidden("wikitext") {
+            None
+        } else {
+            match previous {
+                None => Some(size(&revision.wikitext_hash)),
+                Some(previous) if previous.wikitext_hash == revision.wikitext_hash => {
+                    Some(0)
+                }
+                Some(previous) => {
+                    Some(size(&revision.wikitext_hash) - size(&previous.wikitext_hash))
+                }
+            }
+        };
+
+        // Describe each change
+        let with_size = |description: &str| match size_delta {
+            Some(delta) => format!("{description} ({})", format_byte_delta(delta)),
+            None => str!(description),
+        };
+
+        let mut parts = Vec::new();
+        match revision.revision_type {
+            PageRevisionType::Create => parts.push(with_size("created page")),
+            PageRevisionType::Delete => parts.push(str!("deleted page")),
+            PageRevisionType::Undelete => parts.push(str!("restored page")),
+            PageRevisionType::Move | PageRevisionType::Regular => (),
+        }
+
+        if let Some(previous) = previous {
+            for change in &revision.changes {
+                let part = match change {
+                    PageRevisionChange::Wikitext => with_size("edited contents"),
+                    PageRevisionChange::Title if is_hidden("title") => str!("retitled"),
+                    PageRevisionChange::Title => {
+                        format!("retitled from \"{}\"", previous.title)
+                    }
+                    PageRevisionChange::AltTitle => str!("changed alternate title"),
+                    PageRevisionChange::Slug if is_hidden("slug") => str!("moved"),
+                    PageRevisionChange::Slug => {
+                        format!("moved from \"{}\"", previous.slug)
+                    }
+                    PageRevisionChange::Tags if is_hidden("tags") => {
+                        str!("changed tags")
+                    }
+                    PageRevisionChange::Tags => {
+                        let added = revision
+                            .tags
+                            .iter()
+                            .filter(|tag| !previous.tags.contains(tag))
+                            .map(|tag| format!("+{tag}"));
+
+                        let removed = previous
+                            .tags
+                            .iter()
+                            .filter(|tag| !revision.tags.contains(tag))
+                            .map(|tag| format!("-{tag}"));
+
+                        let tags = added.chain(removed).collect::<Vec<_>>();
+                        format!("changed tags ({})", tags.join(", "))
+                    }
+                };
+
+                parts.push(part);
+            }
+        }
+
+        let summary = parts.join(", ");
+        RevisionSummary {
+            size_delta,
+            summary,
+        }
+    }
+
     pub async fn get_range(
         ctx: &ServiceContext<'_>,
         GetPageRevisionRange {
@@ -857,7 +1419,7 @@ impl PageRevisionService {
             let revision_number = if revision_number >= 0 {
                 revision_number
             } else {
-                i32::MAX
+                i64::MAX
             };
 
             // Get correct database condition based on requested ordering
@@ -882,6 +1444,214 @@ impl PageRevisionService {
 
         Ok(revisions)
     }
+
+    /// Produces a line diff of the wikitext between any two revisions of a page.
+    ///
+    /// Unlike revision comparisons, this doesn't create a permalink, and the
+    /// revisions don't need to be in order, so going backwards shows a reversion.
+    pub async fn diff(
+        ctx: &ServiceContext<'_>,
+        GetPageRevisionDiff {
+            site_id,
+            page_id,
+            from_revision_number,
+            to_revision_number,
+            words,
+        }: GetPageRevisionDiff,
+    ) -> Result<PageRevisionDiff> {
+        info!(
+            "Diffing revisions {from_revision_number} and {to_revision_number} of page ID {page_id}",
+        );
+
+        let (from, to) = try_join!(
+            Self::get(ctx, site_id, page_id, from_revision_number),
+            Self::get(ctx, site_id, page_id, to_revision_number),
+        )?;
+
+        let is_hidden = |revision: &PageRevisionModel| {
+            revision.hidden.iter().any(|field| field == "wikitext")
+        };
+
+        if is_hidden(&from) || is_hidden(&to) {
+            warn!("Wikitext is hidden on one of the revisions, cannot diff");
+            return Err(Error::PageRevisionWikitextHidden);
+        }
+
+        let hunks = if from.wikitext_hash == to.wikitext_hash {
+            Vec::new()
+        } else {
+            let (from_wikitext, to_wikitext) = try_join!(
+                TextService::get(ctx, &from.wikitext_hash),
+                TextService::get(ctx, &to.wikitext_hash),
+            )?;
+
+            if words {
+                diff_lines_refined(&from_wikitext, &to_wikitext)
+            } else {
+                diff_lines(&from_wikitext, &to_wikitext)
+            }
+        };
+
+        Ok(PageRevisionDiff {
+            from_revision_number,
+            to_revision_number,
+            hunks,
+        })
+    }
+
+    /// Annotates each line of a revision's wikitext with the revision which last changed it.
+    ///
+    /// This is built by replaying the page's history one revision at a time.
+    /// The result for the newest revision requested is cached, so that
+    /// later calls only need to apply whatever revisions came after it.
+    pub async fn blame(
+        ctx: &ServiceContext<'_>,
+        GetPageBlame {
+            site_id,
+            page_id,
+            revision_number,
+        }: GetPageBlame,
+    ) -> Result<PageBlame> {
+        let target = match revision_number {
+            Some(revision_number) => {
+                Self::get(ctx, site_id, page_id, revision_number).await?
+            }
+            None => Self::get_latest(ctx, site_id, page_id).await?,
+        };
+
+        if target.hidden.iter().any(|field| field == "wikitext") {
+            return Err(Error::PageRevisionWikitextHidden);
+        }
+
+        // Start from the cached state if it isn't past the requested revision
+        let cached = Self::get_cached_blame(ctx, page_id).await?;
+        let cached_revision = cached.as_ref().map(|state| state.revision_number);
+        let (state, mut wikitext) = match cached {
+            Some(state) if state.revision_number <= target.revision_number => {
+                let wikitext = TextService::get(ctx, &state.wikitext_hash).await?;
+                if state.lines.len() == wikitext.lines().count() {
+                    (Some(state), wikitext)
+                } else {
+                    warn!("Cached blame for page ID {page_id} does not match its wikitext, rebuilding");
+                    (None, String::new())
+                }
+            }
+            _ => (None, String::new()),
+        };
+
+        let mut state = state.unwrap_or_else(|| BlameState {
+            revision_number: -1,
+            wikitext_hash: Vec::new(),
+            lines: Vec::new(),
+        });
+
+        if state.revision_number < target.revision_number {
+            debug!(
+                "Applying revisions {} to {} to blame for page ID {page_id}",
+                state.revision_number + 1,
+                target.revision_number,
+            );
+
+            let txn = ctx.transaction();
+            let revisions = PageRevision::find()
+                .filter(
+                    Condition::all()
+                        .add(page_revision::Column::SiteId.eq(site_id))
+                        .add(page_revision::Column::PageId.eq(page_id))
+                        .add(
+                            page_revision::Column::RevisionNumber
+                                .gt(state.revision_number),
+                        )
+                        .add(
+                            page_revision::Column::RevisionNumber
+                                .lte(target.revision_number),
+                        ),
+                )
+                .order_by_asc(page_revision::Column::RevisionNumber)
+                .all(txn)
+                .await?;
+
+            for revision in revisions {
+                // Only revisions which touched the wikitext affect the blame
+                if revision.wikitext_hash != state.wikitext_hash {
+                    let new_wikitext =
+                        TextService::get(ctx, &revision.wikitext_hash).await?;
+
+                    let origin = BlameOrigin {
+                        revision_number: revision.revision_number,
+                        user_id: revision.user_id,
+                    };
+
+                    state.lines =
+                        apply_revision(&state.lines, &wikitext, &new_wikitext, origin);
+                    state.wikitext_hash = revision.wikitext_hash;
+                    wikitext = new_wikitext;
+                }
+
+                state.revision_number = revision.revision_number;
+            }
+
+            // Don't replace a cached state for a newer revision
+            if cached_revision < Some(state.revision_number) {
+                Self::set_cached_blame(ctx, page_id, &state).await?;
+            }
+        }
+
+        let lines = state
+            .lines
+            .iter()
+            .zip(wikitext.lines())
+            .map(|(origin, text)| BlameLine {
+                revision_number: origin.revision_number,
+                user_id: origin.user_id,
+                text: str!(text),
+            })
+            .collect();
+
+        Ok(PageBlame {
+            revision_number: state.revision_number,
+            lines,
+        })
+    }
+
+    async fn get_cached_blame(
+        ctx: &ServiceContext<'_>,
+        page_id: i64,
+    ) -> Result<Option<BlameState>> {
+        let mut redis = ctx.redis();
+        let cached: Option<String> = ctx
+            .until_deadline(redis.get(blame_cache_key(page_id)))
+            .await?;
+
+        match cached {
+            None => Ok(None),
+            Some(data) => match serde_json::from_str(&data) {
+                Ok(state) => Ok(Some(state)),
+                Err(error) => {
+                    warn!("Cached blame for page ID {page_id} is invalid, rebuilding: {error}");
+                    Ok(None)
+                }
+            },
+        }
+    }
+
+    async fn set_cached_blame(
+        ctx: &ServiceContext<'_>,
+        page_id: i64,
+        state: &BlameState,
+    ) -> Result<()> {
+        let data = serde_json::to_string(state)?;
+        let ttl = ctx.config().page_blame_cache_ttl.as_secs() as usize;
+        let mut redis = ctx.redis();
+        ctx.until_deadline(redis.set_ex::<_, _, ()>(
+            blame_cache_key(page_id),
+            &data,
+            ttl,
+        ))
+        .await?;
+
+        Ok(())
+    }
 }
 
 #[derive(Debug)]
@@ -893,6 +1663,11 @@ struct RenderPageInfo<'a> {
     tags: &'a [String],
 }
 
+#[inline]
+fn blame_cache_key(page_id: i64) -> String {
+    format!("page-blame:{page_id}")
+}
+
 #[inline]
 fn replace_hash(dest: &mut Vec<u8>, src: &[u8]) {
     debug_assert_eq!(
@@ -904,7 +1679,7 @@ fn replace_hash(dest: &mut Vec<u8>, src: &[u8]) {
     dest.as_mut_slice().copy_from_slice(src);
 }
 
-fn next_revision_number(previous: &PageRevisionModel, site_id: i64, page_id: i64) -> i32 {
+fn next_revision_number(previous: &PageRevisionModel, site_id: i64, page_id: i64) -> i64 {
     // Check for basic consistency
     assert_eq!(
         previous.site_id, site_id,
@@ -19,10 +19,12 @@
  */
 
 use super::prelude::*;
-use crate::models::sea_orm_active_enums::PageRevisionType;
+use crate::models::sea_orm_active_enums::{PageRevisionChange, PageRevisionType};
+use crate::services::revision_comparison::DiffHunk;
 use crate::web::{FetchDirection, PageDetails};
 use ftml::parsing::ParseError;
-use std::num::NonZeroI32;
+use serde_json::Value as JsonValue;
+use std::num::NonZeroI64;
 use time::OffsetDateTime;
 
 #[derive(Deserialize, Debug, Clone)]
@@ -74,7 +76,7 @@ pub struct CreateResurrectionPageRevision {
 #[derive(Serialize, Debug, Clone)]
 pub struct CreatePageRevisionOutput {
     pub revision_id: i64,
-    pub revision_number: i32,
+    pub revision_number: i64,
     pub parser_errors: Option<Vec<ParseError>>,
 }
 
@@ -88,7 +90,7 @@ pub struct CreateFirstPageRevisionOutput {
 pub struct GetPageRevision {
     pub site_id: i64,
     pub page_id: i64,
-    pub revision_number: i32,
+    pub revision_number: i64,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -107,6 +109,10 @@ pub struct UpdatePageRevision {
     pub revision_id: i64,
     pub user_id: i64,
     pub hidden: Vec<String>,
+
+    /// Why the revision is being changed, for the audit log.
+    #[serde(default)]
+    pub reason: String,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -118,11 +124,56 @@ pub struct UpdatePageRevisionDetails {
     pub details: PageDetails,
 }
 
+#[derive(Deserialize, Debug, Clone)]
+pub struct MassRevertPages {
+    pub site_id: i64,
+
+    /// The user whose edits are being reverted.
+    pub author_id: i64,
+
+    /// The start of the window of edits to revert, inclusive.
+    pub start: OffsetDateTime,
+
+    /// The end of the window of edits to revert, inclusive.
+    pub end: OffsetDateTime,
+
+    /// The user performing the revert.
+    pub user_id: i64,
+
+    #[serde(default)]
+    pub revision_comments: String,
+
+    /// Why the edits are being reverted, for the audit log.
+    #[serde(default)]
+    pub reason: String,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct MassRevertOutput {
+    pub reverted: Vec<MassRevertedPage>,
+
+    /// Pages which were created by the author within the window,
+    /// so there is no earlier state to revert them to.
+    pub skipped: Vec<i64>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct MassRevertedPage {
+    pub page_id: i64,
+
+    /// The revision number the page was reverted to.
+    pub reverted_to: i64,
+
+    /// The revision created by the revert.
+    pub revision_id: i64,
+    pub revision_number: i64,
+}
+
 #[derive(Deserialize, Debug, Clone)]
 pub struct GetPageRevisionRange {
     pub site_id: i64,
     pub page_id: i64,
-    pub revision_number: i32,
+    pub revision_number: i64,
     pub revision_direction: FetchDirection,
     pub limit: u64,
 }
@@ -147,9 +198,9 @@ pub struct GetPageRevisionRangeDetails {
 /// in other places, and also so that API consumers have the relevant information.
 #[derive(Serialize, Debug, Clone)]
 pub struct PageRevisionCountOutput {
-    pub revision_count: NonZeroI32,
-    pub first_revision: i32,
-    pub last_revision: i32,
+    pub revision_count: NonZeroI64,
+    pub first_revision: i64,
+    pub last_revision: i64,
 }
 
 #[derive(Serialize, Debug, Clone)]
@@ -159,19 +210,103 @@ pub struct PageRevisionModelFiltered {
     pub created_at: OffsetDateTime,
     pub updated_at: Option<OffsetDateTime>,
     pub from_wikidot: bool,
-    pub revision_number: i32,
+    pub revision_number: i64,
     pub page_id: i64,
     pub site_id: i64,
     pub user_id: i64,
-    pub changes: Vec<String>,
+    pub changes: Vec<PageRevisionChange>,
+    pub size_delta: Option<i64>,
+    pub summary: String,
     pub wikitext: Option<String>,
     pub compiled_html: Option<String>,
     pub compiled_at: OffsetDateTime,
     pub compiled_generator: String,
+    pub content_stats: JsonValue,
+    pub compiled_csp: JsonValue,
     pub comments: Option<String>,
     pub hidden: Vec<String>,
     pub title: Option<String>,
     pub alt_title: Option<String>,
     pub slug: Option<String>,
     pub tags: Option<Vec<String>>,
+    pub applied_tag_rules: Vec<i64>,
+}
+
+/// What a revision changed compared to the one before it, for history listings.
+#[derive(Serialize, Debug, Clone)]
+pub struct RevisionSummary {
+    /// How much larger or smaller the wikitext or file became, in bytes.
+    ///
+    /// For the first revision, this is the full size. It is `None` if
+    /// the wikitext is hidden on either revision being compared.
+    pub size_delta: Option<i64>,
+
+    /// Short English description of the changes,
+    /// such as `moved from "old-page", edited contents (+2.3 kB)`.
+    pub summary: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetPageRevisionDiff {
+    pub site_id: i64,
+    pub page_id: i64,
+
+    /// The revisions to compare. These may be in either order.
+    pub from_revision_number: i64,
+    pub to_revision_number: i64,
+
+    /// Whether to also mark which words changed within modified lines.
+    #[serde(default)]
+    pub words: bool,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct PageRevisionDiff {
+    pub from_revision_number: i64,
+    pub to_revision_number: i64,
+    pub hunks: Vec<DiffHunk>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetPageBlame {
+    pub site_id: i64,
+    pub page_id: i64,
+
+    /// Which revision to annotate. If absent, the latest revision is used.
+    #[serde(default)]
+    pub revision_number: Option<i64>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct PageBlame {
+    pub revision_number: i64,
+    pub lines: Vec<BlameLine>,
+}
+
+/// A line of wikitext, along with the revision which last changed it.
+#[derive(Serialize, Debug, Clone)]
+pub struct BlameLine {
+    pub revision_number: i64,
+    pub user_id: i64,
+    pub text: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct PreviewPage {
+    pub site_id: i64,
+    pub slug: String,
+    pub title: String,
+
+    #[serde(default)]
+    pub alt_title: Option<String>,
+
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub wikitext: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct PrintPageOutput {
+    pub html: String,
+    pub errors: Vec<ParseError>,
 }
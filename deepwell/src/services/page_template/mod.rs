@@ -0,0 +1,41 @@
+/*
+ * services/page_template/mod.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The page template service, for creating pages from templates.
+//!
+//! A template is an ordinary page in the configured template category, such
+//! as `template:character`, whose wikitext and title contain placeholders
+//! like `{$name}`. These use the same syntax as include variables.
+//!
+//! Instantiating a template creates a new page with a value substituted for
+//! each placeholder. Every placeholder must be given a value, and values for
+//! placeholders the template doesn't have are rejected, so that callers such
+//! as bots find out when a template they rely on has changed.
+
+mod prelude {
+    pub use super::super::prelude::*;
+    pub use super::structs::*;
+}
+
+mod service;
+mod structs;
+
+pub use self::service::PageTemplateService;
+pub use self::structs::*;
@@ -0,0 +1,258 @@
+/*
+ * services/page_template/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::page::Model as PageModel;
+use crate::models::page_revision::Model as PageRevisionModel;
+use crate::services::page::{CreatePage, CreatePageOutput, EditPage, EditPageBody};
+use crate::services::{PageRevisionService, PageService, TextService};
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use std::collections::HashMap;
+use wikidot_normalize::normalize;
+
+static PLACEHOLDER_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\{\$([a-zA-Z0-9_\-]+)\}").unwrap());
+
+#[derive(Debug)]
+pub struct PageTemplateService;
+
+impl PageTemplateService {
+    pub async fn get(
+        ctx: &ServiceContext<'_>,
+        GetPageTemplate { site_id, name }: GetPageTemplate,
+    ) -> Result<GetPageTemplateOutput> {
+        let (page, revision) = Self::get_template(ctx, site_id, &name).await?;
+        let wikitext = TextService::get(ctx, &revision.wikitext_hash).await?;
+
+        let parameters = placeholders(&[wikitext.as_str(), revision.title.as_str()]);
+
+        Ok(GetPageTemplateOutput {
+            page_id: page.page_id,
+            slug: page.slug,
+            parameters,
+        })
+    }
+
+    /// Creates a new page from a template.
+    ///
+    /// Pages are created without tags, so if any are given,
+    /// they are added by a second revision immediately after.
+    pub async fn instantiate(
+        ctx: &ServiceContext<'_>,
+        InstantiatePageTemplate {
+            site_id,
+            user_id,
+            template,
+            slug,
+            parameters,
+            title,
+            tags,
+            revision_comments,
+        }: InstantiatePageTemplate,
+    ) -> Result<InstantiatePageTemplateOutput> {
+        info!(
+            "Instantiating page template '{template}' as '{slug}' in site ID {site_id}",
+        );
+
+        let (_, revision) = Self::get_template(ctx, site_id, &template).await?;
+        let wikitext = TextService::get(ctx, &revision.wikitext_hash).await?;
+
+        // Only check the title's placeholders if it's being used
+        let expected = match title {
+            Some(_) => placeholders(&[wikitext.as_str()]),
+            None => placeholders(&[wikitext.as_str(), revision.title.as_str()]),
+        };
+
+        check_parameters(&expected, &parameters)?;
+
+        let title = match title {
+            Some(title) => title,
+            None => substitute(&revision.title, &parameters),
+        };
+
+        let CreatePageOutput {
+            page_id,
+            slug,
+            revision_id,
+            parser_errors,
+        } = PageService::create(
+            ctx,
+            CreatePage {
+                site_id,
+                wikitext: substitute(&wikitext, &parameters),
+                title,
+                alt_title: revision
+                    .alt_title
+                    .as_ref()
+                    .map(|alt_title| substitute(alt_title, &parameters)),
+                slug,
+                revision_comments,
+                user_id,
+                bypass_filter: false,
+                form_data: None,
+            },
+        )
+        .await?;
+
+        let mut output = InstantiatePageTemplateOutput {
+            page_id,
+            slug,
+            revision_id,
+            revision_number: 0,
+            parser_errors,
+        };
+
+        if !tags.is_empty() {
+            let revision = PageService::edit(
+                ctx,
+                EditPage {
+                    site_id,
+                    page: Reference::Id(page_id),
+                    revision_comments: String::new(),
+                    user_id,
+                    base_revision_id: None,
+                    body: EditPageBody {
+                        tags: ProvidedValue::Set(tags),
+                        ..Default::default()
+                    },
+                },
+            )
+            .await?;
+
+            if let Some(revision) = revision {
+                output.revision_id = revision.revision_id;
+                output.revision_number = revision.revision_number;
+            }
+        }
+
+        Ok(output)
+    }
+
+    /// Gets the template page with the given name, and its latest revision.
+    async fn get_template(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        name: &str,
+    ) -> Result<(PageModel, PageRevisionModel)> {
+        let mut slug = format!("{}:{name}", ctx.config().special_page_template_category);
+        normalize(&mut slug);
+
+        let page =
+            match PageService::get_optional(ctx, site_id, Reference::Slug(cow!(&slug)))
+                .await?
+            {
+                Some(page) => page,
+                None => {
+                    error!("No page template '{slug}' in site ID {site_id}");
+                    return Err(Error::PageTemplateNotFound);
+                }
+            };
+
+        let revision =
+            PageRevisionService::get_latest(ctx, site_id, page.page_id).await?;
+
+        Ok((page, revision))
+    }
+}
+
+/// Gets the names of the placeholders in each text, in order of first appearance.
+fn placeholders(texts: &[&str]) -> Vec<String> {
+    let mut names = Vec::new();
+    for text in texts {
+        for captures in PLACEHOLDER_REGEX.captures_iter(text) {
+            let name = &captures[1];
+            if !names.iter().any(|existing| existing == name) {
+                names.push(str!(name));
+            }
+        }
+    }
+    names
+}
+
+/// Ensures that there is a value for exactly each of the placeholders.
+fn check_parameters(
+    expected: &[String],
+    parameters: &HashMap<String, String>,
+) -> Result<()> {
+    let missing = expected
+        .iter()
+        .filter(|name| !parameters.contains_key(*name))
+        .cloned()
+        .collect::<Vec<_>>();
+
+    let mut unknown = parameters
+        .keys()
+        .filter(|name| !expected.contains(name))
+        .cloned()
+        .collect::<Vec<_>>();
+
+    if missing.is_empty() && unknown.is_empty() {
+        return Ok(());
+    }
+
+    // Map ordering is arbitrary, so sort for a stable response
+    unknown.sort();
+
+    error!("Page template parameters invalid, missing {missing:?}, unknown {unknown:?}",);
+    Err(Error::PageTemplateParametersInvalid { missing, unknown })
+}
+
+/// Replaces each placeholder with its value.
+///
+/// Placeholders without a value are left as they are.
+fn substitute(text: &str, parameters: &HashMap<String, String>) -> String {
+    PLACEHOLDER_REGEX
+        .replace_all(text, |captures: &Captures| {
+            match parameters.get(&captures[1]) {
+                Some(value) => value.clone(),
+                None => str!(&captures[0]),
+            }
+        })
+        .into_owned()
+}
+
+#[test]
+fn template_placeholders() {
+    const TEMPLATE: &str = "**Name:** {$name}\n**Age:** {$age}\n{$name} is {$ }{$age}.";
+
+    assert_eq!(placeholders(&[TEMPLATE]), vec![str!("name"), str!("age")]);
+
+    let mut parameters = HashMap::new();
+    parameters.insert(str!("name"), str!("Apple"));
+    parameters.insert(str!("age"), str!("3"));
+
+    assert!(check_parameters(&placeholders(&[TEMPLATE]), &parameters).is_ok());
+    assert_eq!(
+        substitute(TEMPLATE, &parameters),
+        "**Name:** Apple\n**Age:** 3\nApple is {$ }3.",
+    );
+
+    parameters.remove("age");
+    parameters.insert(str!("colour"), str!("red"));
+
+    match check_parameters(&placeholders(&[TEMPLATE]), &parameters) {
+        Err(Error::PageTemplateParametersInvalid { missing, unknown }) => {
+            assert_eq!(missing, vec![str!("age")]);
+            assert_eq!(unknown, vec![str!("colour")]);
+        }
+        result => panic!("Unexpected result: {result:?}"),
+    }
+}
@@ -0,0 +1,71 @@
+/*
+ * services/page_template/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use ftml::parsing::ParseError;
+use std::collections::HashMap;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetPageTemplate {
+    pub site_id: i64,
+
+    /// The template's name, that is its slug without the category.
+    pub name: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct GetPageTemplateOutput {
+    pub page_id: i64,
+    pub slug: String,
+
+    /// The placeholders in the template, in order of first appearance.
+    pub parameters: Vec<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct InstantiatePageTemplate {
+    pub site_id: i64,
+    pub user_id: i64,
+    pub template: String,
+    pub slug: String,
+
+    #[serde(default)]
+    pub parameters: HashMap<String, String>,
+
+    /// The title of the new page.
+    ///
+    /// If unset, the template's title is used, with its placeholders substituted.
+    #[serde(default)]
+    pub title: Option<String>,
+
+    #[serde(default)]
+    pub tags: Vec<String>,
+
+    #[serde(default)]
+    pub revision_comments: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct InstantiatePageTemplateOutput {
+    pub page_id: i64,
+    pub slug: String,
+    pub revision_id: i64,
+    pub revision_number: i64,
+    pub parser_errors: Vec<ParseError>,
+}
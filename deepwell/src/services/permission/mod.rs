@@ -0,0 +1,49 @@
+/*
+ * services/permission/mod.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The permission service, for describing what a user may do on a site.
+//!
+//! Each API method has a requirement, such as needing to be logged in or
+//! being a member of the site. Given a user and a site, the requirements
+//! are evaluated into a matrix, with the reason each method is allowed or
+//! denied, so clients can hide or disable actions ahead of time. The same
+//! requirements are enforced whenever a method is called.
+//!
+//! A site may override a method's requirement for a category or a single
+//! page. When checking a page, the most specific override wins: the page
+//! itself, then its category, then the `_default` category, and only then
+//! the method's usual requirement. This applies to any call which names a
+//! page, and the explain endpoint reports each of these levels, so it's
+//! clear where a decision came from.
+//!
+//! To check that a particular call would succeed, any endpoint taking an
+//! object of parameters can be called with `"check_only": true`. It is then
+//! run as normal, but its transaction is rolled back instead of committed.
+
+mod prelude {
+    pub use super::super::prelude::*;
+    pub use super::structs::*;
+}
+
+mod service;
+mod structs;
+
+pub use self::service::PermissionService;
+pub use self::structs::*;
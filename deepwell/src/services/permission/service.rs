@@ -0,0 +1,1150 @@
+/*
+ * services/permission/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::page::Model as PageModel;
+use crate::models::page_category::{self, Entity as PageCategory};
+use crate::models::permission_acl::{
+    self, Entity as PermissionAcl, Model as PermissionAclModel,
+};
+use crate::services::api_usage::ApiCall;
+use crate::services::relation::{GetSiteBan, GetSiteMember};
+use crate::services::{
+    CategoryService, OutdateService, PageService, RelationService, SessionService,
+    SiteService, UserService,
+};
+use jsonrpsee::types::params::Params;
+
+/// The requirement for each API method, in the order they are registered.
+///
+/// Every method in `api.rs` must be listed here.
+const METHOD_REQUIREMENTS: [(&str, Requirement); 236] = [
+    // Miscellaneous
+    ("ping", Requirement::Anyone),
+    ("health", Requirement::Internal),
+    ("error", Requirement::Internal),
+    ("version", Requirement::Anyone),
+    ("version_full", Requirement::Internal),
+    ("hostname", Requirement::Internal),
+    ("config", Requirement::Internal),
+    ("config_path", Requirement::Internal),
+    ("normalize", Requirement::Anyone),
+    // Localization
+    ("locale", Requirement::Anyone),
+    ("translate", Requirement::Anyone),
+    // Web server
+    ("page_view", Requirement::Anyone),
+    ("user_view", Requirement::Anyone),
+    // Authentication
+    ("login", Requirement::Internal),
+    ("magic_link_request", Requirement::Internal),
+    ("magic_link_login", Requirement::Internal),
+    ("logout", Requirement::Anyone),
+    ("session_get", Requirement::Anyone),
+    ("session_refresh", Requirement::Anyone),
+    ("session_get_others", Requirement::User),
+    ("session_invalidate_others", Requirement::User),
+    ("session_renew", Requirement::Anyone),
+    ("refresh_token_create", Requirement::User),
+    ("refresh_token_exchange", Requirement::Anyone),
+    ("refresh_token_revoke", Requirement::Anyone),
+    ("mfa_verify", Requirement::Internal),
+    ("mfa_setup", Requirement::User),
+    ("mfa_disable", Requirement::User),
+    ("mfa_reset_recovery", Requirement::User),
+    ("impersonation_start", Requirement::InstanceAdmin),
+    // Account recovery
+    ("recovery_contact_add", Requirement::User),
+    ("recovery_contact_remove", Requirement::User),
+    ("recovery_contact_get_all", Requirement::User),
+    ("recovery_contact_token", Requirement::Internal),
+    ("recovery_contact_verify", Requirement::Anyone),
+    ("recovery_start", Requirement::Internal),
+    ("recovery_confirm_email", Requirement::Anyone),
+    ("recovery_confirm_contact", Requirement::User),
+    ("recovery_cancel", Requirement::Anyone),
+    ("recovery_status", Requirement::Anyone),
+    ("recovery_complete", Requirement::Anyone),
+    // OAuth
+    ("oauth_application_create", Requirement::User),
+    ("oauth_application_get", Requirement::Anyone),
+    ("oauth_application_get_all", Requirement::User),
+    ("oauth_application_delete", Requirement::User),
+    ("oauth_authorize", Requirement::User),
+    ("oauth_token", Requirement::Anyone),
+    ("oauth_token_check", Requirement::Internal),
+    ("oauth_authorization_get_all", Requirement::User),
+    ("oauth_authorization_revoke", Requirement::User),
+    // Audit log
+    ("audit_log_get", Requirement::InstanceAdmin),
+    // API usage
+    ("api_usage_get", Requirement::SiteMember),
+    ("api_throttle_set", Requirement::SiteMember),
+    ("api_access_revoke", Requirement::SiteMember),
+    // Service accounts
+    ("service_account_create", Requirement::InstanceAdmin),
+    ("service_account_get_all", Requirement::InstanceAdmin),
+    ("service_account_update", Requirement::InstanceAdmin),
+    ("service_account_rotate_key", Requirement::InstanceAdmin),
+    ("service_account_revoke", Requirement::InstanceAdmin),
+    // Permissions
+    ("permission_matrix_get", Requirement::Anyone),
+    ("permission_acl_set", Requirement::SiteMember),
+    ("permission_acl_get_all", Requirement::Anyone),
+    ("permission_explain", Requirement::Anyone),
+    // Event stream
+    ("event_stream_get", Requirement::InstanceAdmin),
+    ("event_stream_group_create", Requirement::InstanceAdmin),
+    ("event_stream_group_delete", Requirement::InstanceAdmin),
+    ("event_stream_group_replay", Requirement::InstanceAdmin),
+    // Search index
+    ("search_index_rebuild", Requirement::InstanceAdmin),
+    ("search_index_check", Requirement::InstanceAdmin),
+    // Link recomputation
+    ("link_recompute_start", Requirement::InstanceAdmin),
+    ("link_recompute_get", Requirement::InstanceAdmin),
+    // Consistency checks
+    ("consistency_check_run", Requirement::InstanceAdmin),
+    ("consistency_report_get_all", Requirement::InstanceAdmin),
+    // Blob garbage collection
+    ("blob_collection_run", Requirement::InstanceAdmin),
+    ("blob_collection_get_all", Requirement::InstanceAdmin),
+    // Blob migration
+    ("blob_migration_start", Requirement::InstanceAdmin),
+    ("blob_migration_get", Requirement::InstanceAdmin),
+    // Autocomplete
+    ("autocomplete_page", Requirement::SiteUser),
+    ("autocomplete_user", Requirement::SiteUser),
+    ("autocomplete_tag", Requirement::SiteUser),
+    // Site
+    ("site_create", Requirement::User),
+    ("site_get", Requirement::Anyone),
+    ("site_update", Requirement::SiteMember),
+    ("site_from_domain", Requirement::Anyone),
+    // Site custom domain
+    ("custom_domain_create", Requirement::SiteMember),
+    ("custom_domain_get", Requirement::Anyone),
+    ("custom_domain_delete", Requirement::SiteMember),
+    // Site limits
+    ("site_limits_get", Requirement::Anyone),
+    ("site_limits_set", Requirement::SiteMember),
+    ("site_storage_usage", Requirement::SiteMember),
+    // Tag rules
+    ("tag_rule_create", Requirement::SiteMember),
+    ("tag_rule_get_all", Requirement::Anyone),
+    ("tag_rule_delete", Requirement::SiteMember),
+    // Custom blocks
+    ("custom_block_create", Requirement::SiteMember),
+    ("custom_block_get_all", Requirement::Anyone),
+    ("custom_block_delete", Requirement::SiteMember),
+    // Upload rules
+    ("upload_rule_create", Requirement::SiteMember),
+    ("upload_rule_get_all", Requirement::Anyone),
+    ("upload_rule_delete", Requirement::SiteMember),
+    ("scheduled_task_create", Requirement::SiteMember),
+    ("scheduled_task_get_all", Requirement::SiteMember),
+    ("scheduled_task_update", Requirement::SiteMember),
+    ("scheduled_task_delete", Requirement::SiteMember),
+    ("scheduled_task_run_get_all", Requirement::SiteMember),
+    // Site membership
+    ("member_set", Requirement::SiteUser),
+    ("member_get", Requirement::Anyone),
+    ("member_delete", Requirement::SiteMember),
+    // Special pages
+    ("special_page_get", Requirement::Anyone),
+    ("special_page_override_get", Requirement::Anyone),
+    ("special_page_override_set", Requirement::SiteMember),
+    ("special_page_override_delete", Requirement::SiteMember),
+    // Category
+    ("category_get", Requirement::Anyone),
+    ("category_get_all", Requirement::Anyone),
+    ("category_indexing_edit", Requirement::SiteMember),
+    ("category_render_edit", Requirement::SiteMember),
+    ("category_form_edit", Requirement::SiteMember),
+    // Page
+    ("page_create", Requirement::SiteUser),
+    ("page_get", Requirement::Anyone),
+    ("page_get_direct", Requirement::Internal),
+    ("page_edit", Requirement::SiteUser),
+    ("page_delete", Requirement::SiteUser),
+    ("page_move", Requirement::SiteUser),
+    ("page_rollback", Requirement::SiteUser),
+    ("page_rerender", Requirement::Internal),
+    ("page_preview", Requirement::Anyone),
+    ("page_print", Requirement::Anyone),
+    ("page_restore", Requirement::SiteUser),
+    ("page_indexing_edit", Requirement::SiteMember),
+    ("page_get_indexable", Requirement::Internal),
+    // Page drafts
+    ("page_draft_create", Requirement::SiteUser),
+    ("page_draft_get", Requirement::SiteUser),
+    ("page_draft_get_all", Requirement::SiteUser),
+    ("page_draft_edit", Requirement::SiteUser),
+    ("page_draft_delete", Requirement::SiteUser),
+    ("page_draft_publish", Requirement::SiteUser),
+    ("page_draft_schedule", Requirement::SiteUser),
+    // Page forms
+    ("page_form_get", Requirement::Anyone),
+    // Page field watches
+    ("page_field_watch_create", Requirement::SiteUser),
+    ("page_field_watch_delete", Requirement::SiteUser),
+    ("page_field_watch_get_all", Requirement::SiteUser),
+    // Page templates
+    ("page_template_get", Requirement::Anyone),
+    ("page_template_instantiate", Requirement::SiteUser),
+    // Page revisions
+    ("page_revision_create", Requirement::SiteUser),
+    ("page_revision_get", Requirement::Anyone),
+    ("page_revision_count", Requirement::Anyone),
+    ("page_revision_range", Requirement::Anyone),
+    ("page_revision_diff", Requirement::Anyone),
+    ("page_revision_blame", Requirement::Anyone),
+    ("page_revision_mass_revert", Requirement::SiteMember),
+    ("page_revision_compare", Requirement::SiteUser),
+    ("page_revision_comparison_get", Requirement::Anyone),
+    // Revision search
+    ("revision_comment_search", Requirement::SiteMember),
+    // Page links
+    ("page_get_links_from", Requirement::Anyone),
+    ("page_get_links_to", Requirement::Anyone),
+    ("page_get_links_to_missing", Requirement::Anyone),
+    ("page_get_urls_from", Requirement::Anyone),
+    ("page_get_urls_to", Requirement::Anyone),
+    ("site_get_broken_references", Requirement::SiteMember),
+    ("page_get_render_errors", Requirement::Anyone),
+    ("site_get_render_errors", Requirement::SiteMember),
+    // Shortlinks
+    ("shortlink_create", Requirement::SiteUser),
+    ("shortlink_get_page", Requirement::Anyone),
+    ("shortlink_resolve", Requirement::Anyone),
+    // QR codes
+    ("qr_code_get", Requirement::Anyone),
+    // Exports
+    ("export_create", Requirement::SiteUser),
+    ("export_get", Requirement::User),
+    ("export_download", Requirement::User),
+    // Public API
+    ("public_page_get", Requirement::Anyone),
+    ("public_page_list", Requirement::Anyone),
+    ("public_user_get", Requirement::Anyone),
+    // CDN
+    ("cdn_get", Requirement::Internal),
+    // Git mirrors
+    ("git_mirror_get", Requirement::Anyone),
+    ("git_mirror_set", Requirement::SiteMember),
+    ("git_mirror_remove", Requirement::SiteMember),
+    ("git_mirror_file", Requirement::Anyone),
+    // Page parents
+    ("parent_set", Requirement::SiteUser),
+    ("parent_get", Requirement::Anyone),
+    ("parent_remove", Requirement::SiteUser),
+    ("parent_relationships_get", Requirement::Anyone),
+    // Files
+    ("file_upload", Requirement::SiteUser),
+    ("file_paste", Requirement::SiteUser),
+    ("file_upload_start", Requirement::SiteUser),
+    ("file_upload_start_chunked", Requirement::SiteUser),
+    ("file_upload_append", Requirement::SiteUser),
+    ("file_upload_status", Requirement::SiteUser),
+    ("file_upload_abort", Requirement::SiteUser),
+    ("file_upload_finish", Requirement::SiteUser),
+    ("file_stage", Requirement::SiteUser),
+    ("file_staged_get_all", Requirement::SiteUser),
+    ("file_staged_remove", Requirement::SiteUser),
+    ("file_attach", Requirement::SiteUser),
+    ("file_get", Requirement::Anyone),
+    ("file_download_url", Requirement::Anyone),
+    ("file_edit", Requirement::SiteUser),
+    ("file_delete", Requirement::SiteUser),
+    ("file_move", Requirement::SiteUser),
+    ("file_restore", Requirement::SiteUser),
+    ("file_hard_delete", Requirement::InstanceAdmin),
+    // File revisions
+    ("file_revision_get", Requirement::Anyone),
+    ("file_revision_edit", Requirement::SiteUser),
+    ("file_revision_edit_many", Requirement::SiteUser),
+    ("file_revision_purge_blob", Requirement::InstanceAdmin),
+    ("file_revision_count", Requirement::Anyone),
+    ("file_revision_range", Requirement::Anyone),
+    ("file_revision_diff", Requirement::Anyone),
+    ("file_revision_restore", Requirement::SiteMember),
+    ("file_revision_revert", Requirement::SiteUser),
+    ("file_thumbnail_get", Requirement::Anyone),
+    // Stable revisions
+    ("page_stable_set", Requirement::SiteMember),
+    ("page_pending_changes_get", Requirement::SiteMember),
+    ("file_stable_set", Requirement::SiteMember),
+    // Text
+    ("text_create", Requirement::Internal),
+    ("text_get", Requirement::Internal),
+    // User
+    ("user_create", Requirement::Internal),
+    ("user_import", Requirement::Internal),
+    ("user_get", Requirement::Internal),
+    ("user_edit", Requirement::User),
+    ("user_delete", Requirement::User),
+    ("user_add_name_change", Requirement::InstanceAdmin),
+    // Bot user
+    ("bot_user_create", Requirement::User),
+    ("bot_user_get", Requirement::Anyone),
+    ("bot_user_owner_set", Requirement::User),
+    ("bot_user_owner_remove", Requirement::User),
+    // Direct messages
+    ("message_draft_create", Requirement::User),
+    ("message_draft_edit", Requirement::User),
+    ("message_draft_delete", Requirement::User),
+    ("message_draft_send", Requirement::User),
+    // Email
+    ("email_validate", Requirement::Internal),
+    // Votes
+    ("vote_set", Requirement::SiteUser),
+    ("vote_get", Requirement::Anyone),
+    ("vote_remove", Requirement::SiteUser),
+    ("vote_action", Requirement::SiteUser),
+    ("vote_list", Requirement::Anyone),
+    ("vote_list_count", Requirement::Anyone),
+    // Watchlist
+    ("watchlist_get", Requirement::User),
+    ("watchlist_mark_seen", Requirement::User),
+];
+
+#[derive(Debug)]
+pub struct PermissionService;
+
+impl PermissionService {
+    /// Evaluates which API methods the given user may call on a site.
+    pub async fn get_matrix(
+        ctx: &ServiceContext<'_>,
+        GetPermissionMatrix { user_id, site_id }: GetPermissionMatrix,
+    ) -> Result<PermissionMatrix> {
+        info!("Getting permission matrix for user ID {user_id:?} in site ID {site_id}");
+
+        // Ensure the site exists
+        SiteService::get(ctx, Reference::Id(site_id)).await?;
+
+        let facts = Self::get_facts(ctx, site_id, user_id).await?;
+        let roles = get_roles(facts);
+
+        let operations = METHOD_REQUIREMENTS
+            .iter()
+            .map(|&(method, requirement)| {
+                let (allowed, reason) = evaluate(requirement, facts);
+                OperationPermission {
+                    method,
+                    allowed,
+                    reason,
+                }
+            })
+            .collect();
+
+        Ok(PermissionMatrix {
+            user_id,
+            site_id,
+            roles,
+            operations,
+        })
+    }
+
+    /// Evaluates whether the given user may call one API method on a site.
+    ///
+    /// If a page is given, then any override for it or its category is used
+    /// instead of the site-wide requirement, as described in `explain()`.
+    pub async fn check(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        page_id: Option<i64>,
+        user_id: Option<i64>,
+        method: &str,
+    ) -> Result<OperationPermission> {
+        let (method, site_requirement) = METHOD_REQUIREMENTS
+            .iter()
+            .find(|&&(name, _)| name == method)
+            .copied()
+            .ok_or(Error::PermissionMethodNotFound)?;
+
+        let requirement = match page_id {
+            Some(page_id) => {
+                Self::page_reference_requirement(
+                    ctx,
+                    site_id,
+                    Reference::Id(page_id),
+                    method,
+                    site_requirement,
+                )
+                .await?
+            }
+            None => site_requirement,
+        };
+
+        let facts = Self::get_facts(ctx, site_id, user_id).await?;
+        let (allowed, reason) = evaluate(requirement, facts);
+
+        Ok(OperationPermission {
+            method,
+            allowed,
+            reason,
+        })
+    }
+
+    /// Evaluates whether the given user meets a requirement on a site.
+    ///
+    /// This is for checks which are not tied to a particular API method.
+    pub async fn check_requirement(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        user_id: Option<i64>,
+        requirement: Requirement,
+    ) -> Result<(bool, PermissionReason)> {
+        let facts = Self::get_facts(ctx, site_id, user_id).await?;
+        Ok(evaluate(requirement, facts))
+    }
+
+    /// Ensures the caller may call an API method, before it is run.
+    ///
+    /// If the call names a page, by `"page_id"` or a `"page"` reference, then
+    /// any override for it or its category is used, as with `check()`. Service
+    /// accounts are limited by their scopes instead, which are checked when
+    /// they authenticate, as is the restriction on internal methods.
+    ///
+    /// Besides the IDs in the call, the caller may be given by a `"session_token"`
+    /// and the site by a `"site"` reference. Calls which name neither a user nor
+    /// a site can only be made by a service account, unless the method is public.
+    pub async fn authorize(
+        ctx: &ServiceContext<'_>,
+        call: &ApiCall,
+        params: &Params<'_>,
+    ) -> Result<()> {
+        #[derive(Deserialize, Debug, Default)]
+        struct CallScope<'a> {
+            #[serde(default)]
+            session_token: Option<String>,
+
+            #[serde(default)]
+            site: Option<Reference<'a>>,
+        }
+
+        // Parsed separately, since not every method's "page" is a reference
+        #[derive(Deserialize, Debug, Default)]
+        struct PageScope<'a> {
+            #[serde(default)]
+            page_id: Option<i64>,
+
+            #[serde(default)]
+            page: Option<Reference<'a>>,
+        }
+
+        if ctx.service_account().is_some() {
+            return Ok(());
+        }
+
+        let site_requirement =
+            method_requirement(call.method).ok_or(Error::PermissionMethodNotFound)?;
+
+        if site_requirement == Requirement::Internal {
+            return Ok(());
+        }
+
+        let scope: CallScope = params.parse().unwrap_or_default();
+        let page_scope: PageScope = params.parse().unwrap_or_default();
+        let page = page_scope.page_id.map(Reference::Id).or(page_scope.page);
+
+        // Without a page, there are no overrides to look up
+        if site_requirement == Requirement::Anyone && page.is_none() {
+            return Ok(());
+        }
+
+        let site_id = match (call.site_id, scope.site) {
+            (Some(site_id), _) => Some(site_id),
+            (None, Some(site)) => Some(SiteService::get_id(ctx, site).await?),
+            (None, None) => None,
+        };
+
+        let requirement = match (site_id, page) {
+            (Some(site_id), Some(page)) => {
+                Self::page_reference_requirement(
+                    ctx,
+                    site_id,
+                    page,
+                    call.method,
+                    site_requirement,
+                )
+                .await?
+            }
+            _ => site_requirement,
+        };
+
+        if requirement == Requirement::Anyone {
+            return Ok(());
+        }
+
+        let user_id = match (call.user_id, scope.session_token) {
+            (Some(user_id), _) => Some(user_id),
+            (None, Some(session_token)) => {
+                Some(SessionService::get(ctx, &session_token).await?.user_id)
+            }
+            (None, None) => None,
+        };
+
+        // Calls which don't name a site can only be judged on the user
+        let facts = match site_id {
+            Some(site_id) => Self::get_facts(ctx, site_id, user_id).await?,
+            None => Self::get_user_facts(ctx, user_id).await?,
+        };
+
+        match evaluate(requirement, facts) {
+            (true, _) => Ok(()),
+            (false, reason) => {
+                warn!(
+                    "User ID {user_id:?} may not call '{}' in site ID {site_id:?}: {reason:?}",
+                    call.method,
+                );
+                Err(Error::MethodForbidden(reason))
+            }
+        }
+    }
+
+    /// Gets the site-wide requirement for an API method, if it exists.
+    #[inline]
+    pub fn requirement(method: &str) -> Option<Requirement> {
+        method_requirement(method)
+    }
+
+    /// Gets the names of all API methods.
+    pub fn methods() -> impl Iterator<Item = &'static str> {
+        METHOD_REQUIREMENTS.iter().map(|&(method, _)| method)
+    }
+
+    /// Sets or removes the requirement override for a method on a category or page.
+    ///
+    /// Returns the override, or `None` if it was removed.
+    pub async fn set_acl(
+        ctx: &ServiceContext<'_>,
+        SetPermissionAcl {
+            site_id,
+            category_id,
+            page_id,
+            method,
+            requirement,
+        }: SetPermissionAcl,
+    ) -> Result<Option<PermissionAclModel>> {
+        info!(
+            "Setting permission override for '{method}' in site ID {site_id} (category ID {category_id:?}, page ID {page_id:?}) to {requirement:?}",
+        );
+
+        match method_requirement(&method) {
+            None => return Err(Error::PermissionMethodNotFound),
+            Some(Requirement::Internal) => {
+                error!("Cannot override the requirement of internal method '{method}'");
+                return Err(Error::PermissionAclInvalid);
+            }
+            Some(_) => (),
+        }
+
+        let scope_condition = match (category_id, page_id) {
+            (Some(category_id), None) => {
+                CategoryService::get(ctx, site_id, Reference::Id(category_id)).await?;
+                permission_acl::Column::CategoryId.eq(category_id)
+            }
+            (None, Some(page_id)) => {
+                PageService::get(ctx, site_id, Reference::Id(page_id)).await?;
+                permission_acl::Column::PageId.eq(page_id)
+            }
+            _ => {
+                error!("Permission override must be for exactly one category or page");
+                return Err(Error::PermissionAclInvalid);
+            }
+        };
+
+        // The public API only serves pages guests may view
+        if method == "page_get" {
+            OutdateService::outdate_public_site(ctx, site_id).await?;
+        }
+
+        let txn = ctx.transaction();
+        let existing = PermissionAcl::find()
+            .filter(
+                Condition::all()
+                    .add(permission_acl::Column::SiteId.eq(site_id))
+                    .add(permission_acl::Column::Method.eq(method.as_str()))
+                    .add(scope_condition),
+            )
+            .one(txn)
+            .await?;
+
+        match (existing, requirement) {
+            (Some(acl), Some(requirement)) => {
+                let model = permission_acl::ActiveModel {
+                    acl_id: Set(acl.acl_id),
+                    updated_at: Set(Some(now())),
+                    requirement: Set(str!(requirement.name())),
+                    ..Default::default()
+                };
+
+                let acl = model.update(txn).await?;
+                Ok(Some(acl))
+            }
+            (None, Some(requirement)) => {
+                let model = permission_acl::ActiveModel {
+                    site_id: Set(site_id),
+                    category_id: Set(category_id),
+                    page_id: Set(page_id),
+                    method: Set(method),
+                    requirement: Set(str!(requirement.name())),
+                    ..Default::default()
+                };
+
+                let acl = model.insert(txn).await?;
+                Ok(Some(acl))
+            }
+            (Some(acl), None) => {
+                PermissionAcl::delete_by_id(acl.acl_id).exec(txn).await?;
+                Ok(None)
+            }
+            (None, None) => Ok(None),
+        }
+    }
+
+    /// Gets all the category and page requirement overrides on a site.
+    pub async fn get_acls(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+    ) -> Result<Vec<PermissionAclModel>> {
+        let txn = ctx.transaction();
+        let acls = PermissionAcl::find()
+            .filter(permission_acl::Column::SiteId.eq(site_id))
+            .order_by_asc(permission_acl::Column::AclId)
+            .all(txn)
+            .await?;
+
+        Ok(acls)
+    }
+
+    /// Explains whether a user may call a method on a page, and why.
+    ///
+    /// The requirement is taken from the most specific level which sets one.
+    /// This is checked in order: the page, its category, the `_default`
+    /// category, and finally the site-wide requirement for the method.
+    pub async fn explain(
+        ctx: &ServiceContext<'_>,
+        ExplainPermission {
+            user_id,
+            site_id,
+            page: reference,
+            method,
+        }: ExplainPermission<'_>,
+    ) -> Result<PermissionExplanation> {
+        info!(
+            "Explaining permission for '{method}' for user ID {user_id:?} in site ID {site_id}",
+        );
+
+        let site_requirement = match method_requirement(&method) {
+            Some(requirement) => requirement,
+            None => return Err(Error::PermissionMethodNotFound),
+        };
+
+        let page = PageService::get(ctx, site_id, reference).await?;
+        let (requirement, level, trace) =
+            Self::page_requirement(ctx, site_id, &page, &method, site_requirement)
+                .await?;
+
+        let facts = Self::get_facts(ctx, site_id, user_id).await?;
+        let (allowed, reason) = evaluate(requirement, facts);
+
+        Ok(PermissionExplanation {
+            user_id,
+            site_id,
+            page_id: page.page_id,
+            method,
+            allowed,
+            reason,
+            requirement,
+            level,
+            roles: get_roles(facts),
+            trace,
+        })
+    }
+
+    /// Gets the requirement for a method on a page, given a reference to it.
+    ///
+    /// Falls back to the site-wide requirement if the page doesn't exist,
+    /// since the method itself will fail in that case.
+    async fn page_reference_requirement(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        page: Reference<'_>,
+        method: &str,
+        site_requirement: Requirement,
+    ) -> Result<Requirement> {
+        match PageService::get_optional(ctx, site_id, page).await? {
+            Some(page) => {
+                let (requirement, _, _) =
+                    Self::page_requirement(ctx, site_id, &page, method, site_requirement)
+                        .await?;
+
+                Ok(requirement)
+            }
+            None => Ok(site_requirement),
+        }
+    }
+
+    /// Gets the requirement for a method on a page, and which level it came from.
+    ///
+    /// See `explain()` for the order in which the levels are checked.
+    async fn page_requirement(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        page: &PageModel,
+        method: &str,
+        site_requirement: Requirement,
+    ) -> Result<(Requirement, PermissionLevel, Vec<PermissionTraceStep>)> {
+        // Internal methods cannot be overridden
+        if site_requirement == Requirement::Internal {
+            return Ok(resolve_requirement(&[], site_requirement));
+        }
+
+        let txn = ctx.transaction();
+        let categories = PageCategory::find()
+            .filter(
+                Condition::all()
+                    .add(page_category::Column::SiteId.eq(site_id))
+                    .add(
+                        Condition::any()
+                            .add(
+                                page_category::Column::CategoryId
+                                    .eq(page.page_category_id),
+                            )
+                            .add(page_category::Column::Slug.eq("_default")),
+                    ),
+            )
+            .all(txn)
+            .await?;
+
+        let category_ids = categories.iter().map(|category| category.category_id);
+        let acls = PermissionAcl::find()
+            .filter(
+                Condition::all()
+                    .add(permission_acl::Column::SiteId.eq(site_id))
+                    .add(permission_acl::Column::Method.eq(method))
+                    .add(
+                        Condition::any()
+                            .add(permission_acl::Column::PageId.eq(page.page_id))
+                            .add(permission_acl::Column::CategoryId.is_in(category_ids)),
+                    ),
+            )
+            .all(txn)
+            .await?;
+
+        let default_category_id = categories
+            .iter()
+            .find(|category| category.slug == "_default")
+            .map(|category| category.category_id);
+
+        let levels = acl_levels(
+            page.page_id,
+            page.page_category_id,
+            default_category_id,
+            &acls,
+        )?;
+
+        Ok(resolve_requirement(&levels, site_requirement))
+    }
+
+    /// Gets the roles a user holds on a site.
+    pub async fn get_roles(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        user_id: i64,
+    ) -> Result<Vec<PermissionRole>> {
+        let facts = Self::get_facts(ctx, site_id, Some(user_id)).await?;
+        Ok(get_roles(facts))
+    }
+
+    async fn get_facts(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        user_id: Option<i64>,
+    ) -> Result<PermissionFacts> {
+        let user_id = match user_id {
+            Some(user_id) => user_id,
+            None => return Ok(PermissionFacts::default()),
+        };
+
+        let (facts, site_member, site_banned) = try_join!(
+            Self::get_user_facts(ctx, Some(user_id)),
+            RelationService::site_member_exists(ctx, GetSiteMember { site_id, user_id }),
+            RelationService::site_ban_exists(ctx, GetSiteBan { site_id, user_id }),
+        )?;
+
+        Ok(PermissionFacts {
+            site_member,
+            site_banned,
+            ..facts
+        })
+    }
+
+    async fn get_user_facts(
+        ctx: &ServiceContext<'_>,
+        user_id: Option<i64>,
+    ) -> Result<PermissionFacts> {
+        let user_id = match user_id {
+            Some(user_id) => user_id,
+            None => return Ok(PermissionFacts::default()),
+        };
+
+        let user = UserService::get(ctx, Reference::Id(user_id)).await?;
+        Ok(PermissionFacts {
+            logged_in: true,
+            deleted: user.deleted_at.is_some(),
+            instance_admin: ctx.config().impersonation_admins.contains(&user_id),
+            ..Default::default()
+        })
+    }
+}
+
+/// A level where a requirement can be set, its page or category ID, and its requirement.
+type LevelRequirement = (PermissionLevel, Option<i64>, Option<Requirement>);
+
+fn method_requirement(method: &str) -> Option<Requirement> {
+    METHOD_REQUIREMENTS
+        .iter()
+        .find(|&&(name, _)| name == method)
+        .map(|&(_, requirement)| requirement)
+}
+
+fn acl_requirement<F>(
+    acls: &[PermissionAclModel],
+    predicate: F,
+) -> Result<Option<Requirement>>
+where
+    F: Fn(&PermissionAclModel) -> bool,
+{
+    acls.iter()
+        .find(|acl| predicate(acl))
+        .map(|acl| acl.requirement.parse())
+        .transpose()
+}
+
+/// Gathers the requirement set at each level for a page, if any.
+fn acl_levels(
+    page_id: i64,
+    category_id: i64,
+    default_category_id: Option<i64>,
+    acls: &[PermissionAclModel],
+) -> Result<Vec<LevelRequirement>> {
+    let mut levels = vec![(
+        PermissionLevel::Page,
+        Some(page_id),
+        acl_requirement(acls, |acl| acl.page_id == Some(page_id))?,
+    )];
+
+    if default_category_id != Some(category_id) {
+        levels.push((
+            PermissionLevel::Category,
+            Some(category_id),
+            acl_requirement(acls, |acl| acl.category_id == Some(category_id))?,
+        ));
+    }
+
+    levels.push((
+        PermissionLevel::DefaultCategory,
+        default_category_id,
+        match default_category_id {
+            Some(category_id) => {
+                acl_requirement(acls, |acl| acl.category_id == Some(category_id))?
+            }
+            None => None,
+        },
+    ));
+
+    Ok(levels)
+}
+
+fn get_roles(facts: PermissionFacts) -> Vec<PermissionRole> {
+    let mut roles = Vec::new();
+    if facts.logged_in && !facts.deleted {
+        roles.push(PermissionRole::User);
+
+        if facts.site_member {
+            roles.push(PermissionRole::SiteMember);
+        }
+
+        if facts.instance_admin {
+            roles.push(PermissionRole::InstanceAdmin);
+        }
+    }
+
+    roles
+}
+
+/// Picks the most specific requirement out of the given levels,
+/// falling back to the site-wide requirement if none are set.
+fn resolve_requirement(
+    levels: &[LevelRequirement],
+    site_requirement: Requirement,
+) -> (Requirement, PermissionLevel, Vec<PermissionTraceStep>) {
+    let mut decided = None;
+    let mut trace = Vec::with_capacity(levels.len() + 1);
+
+    for &(level, scope_id, requirement) in levels {
+        let decisive = decided.is_none() && requirement.is_some();
+        if decisive {
+            decided = requirement.map(|requirement| (requirement, level));
+        }
+
+        trace.push(PermissionTraceStep {
+            level,
+            scope_id,
+            requirement,
+            decisive,
+        });
+    }
+
+    trace.push(PermissionTraceStep {
+        level: PermissionLevel::Site,
+        scope_id: None,
+        requirement: Some(site_requirement),
+        decisive: decided.is_none(),
+    });
+
+    let (requirement, level) =
+        decided.unwrap_or((site_requirement, PermissionLevel::Site));
+
+    (requirement, level, trace)
+}
+
+fn evaluate(
+    requirement: Requirement,
+    facts: PermissionFacts,
+) -> (bool, PermissionReason) {
+    match requirement {
+        Requirement::Internal => return (false, PermissionReason::Internal),
+        Requirement::Anyone => return (true, PermissionReason::Anyone),
+        _ => (),
+    }
+
+    if !facts.logged_in {
+        return (false, PermissionReason::NotLoggedIn);
+    }
+
+    if facts.deleted {
+        return (false, PermissionReason::UserDeleted);
+    }
+
+    match requirement {
+        Requirement::User => (true, PermissionReason::User),
+        Requirement::SiteUser if !facts.site_banned => (true, PermissionReason::User),
+        Requirement::SiteMember if facts.site_member => {
+            (true, PermissionReason::SiteMember)
+        }
+
+        // Administrators can act on any site, even without being a member
+        _ if facts.instance_admin => (true, PermissionReason::InstanceAdmin),
+
+        Requirement::SiteUser => (false, PermissionReason::SiteBanned),
+        Requirement::SiteMember if facts.site_banned => {
+            (false, PermissionReason::SiteBanned)
+        }
+        Requirement::SiteMember => (false, PermissionReason::NotSiteMember),
+        Requirement::InstanceAdmin => (false, PermissionReason::NotInstanceAdmin),
+        Requirement::Anyone | Requirement::Internal => unreachable!(),
+    }
+}
+
+#[test]
+fn all_methods_listed() {
+    use once_cell::sync::Lazy;
+    use regex::Regex;
+
+    static REGISTER_REGEX: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r#"register!\("([a-z_]+)""#).unwrap());
+
+    let registered = REGISTER_REGEX
+        .captures_iter(include_str!("../../api.rs"))
+        .map(|captures| captures.get(1).unwrap().as_str())
+        .collect::<Vec<_>>();
+
+    let listed = METHOD_REQUIREMENTS
+        .iter()
+        .map(|&(method, _)| method)
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        registered, listed,
+        "Method requirements do not match API methods"
+    );
+}
+
+#[test]
+fn evaluate_requirements() {
+    macro_rules! check {
+        ($requirement:ident, $facts:expr, $allowed:expr, $reason:ident $(,)?) => {
+            assert_eq!(
+                evaluate(Requirement::$requirement, $facts),
+                ($allowed, PermissionReason::$reason),
+                "Unexpected evaluation for {:?}",
+                Requirement::$requirement,
+            );
+        };
+    }
+
+    let guest = PermissionFacts::default();
+    let user = PermissionFacts {
+        logged_in: true,
+        ..Default::default()
+    };
+    let member = PermissionFacts {
+        site_member: true,
+        ..user
+    };
+    let banned = PermissionFacts {
+        site_banned: true,
+        ..user
+    };
+    let deleted = PermissionFacts {
+        deleted: true,
+        ..member
+    };
+    let admin = PermissionFacts {
+        instance_admin: true,
+        ..banned
+    };
+
+    check!(Anyone, guest, true, Anyone);
+    check!(Internal, admin, false, Internal);
+    check!(User, guest, false, NotLoggedIn);
+    check!(User, user, true, User);
+    check!(User, deleted, false, UserDeleted);
+    check!(SiteUser, banned, false, SiteBanned);
+    check!(SiteUser, member, true, User);
+    check!(SiteMember, user, false, NotSiteMember);
+    check!(SiteMember, member, true, SiteMember);
+    check!(SiteMember, banned, false, SiteBanned);
+    check!(SiteMember, admin, true, InstanceAdmin);
+    check!(InstanceAdmin, member, false, NotInstanceAdmin);
+    check!(InstanceAdmin, admin, true, InstanceAdmin);
+}
+
+#[test]
+fn resolve_requirements() {
+    use PermissionLevel::*;
+
+    let decisive_levels = |trace: Vec<PermissionTraceStep>| {
+        trace
+            .into_iter()
+            .filter(|step| step.decisive)
+            .map(|step| step.level)
+            .collect::<Vec<_>>()
+    };
+
+    // No overrides, falls back to the site
+    let (requirement, level, trace) = resolve_requirement(
+        &[(Page, Some(1), None), (DefaultCategory, Some(10), None)],
+        Requirement::SiteUser,
+    );
+    assert_eq!(requirement, Requirement::SiteUser);
+    assert_eq!(level, Site);
+    assert_eq!(trace.len(), 3);
+    assert_eq!(decisive_levels(trace), [Site]);
+
+    // Category overrides the default category
+    let (requirement, level, trace) = resolve_requirement(
+        &[
+            (Page, Some(1), None),
+            (Category, Some(11), Some(Requirement::SiteMember)),
+            (DefaultCategory, Some(10), Some(Requirement::Anyone)),
+        ],
+        Requirement::SiteUser,
+    );
+    assert_eq!(requirement, Requirement::SiteMember);
+    assert_eq!(level, Category);
+    assert_eq!(decisive_levels(trace), [Category]);
+
+    // Page overrides everything
+    let (requirement, level, trace) = resolve_requirement(
+        &[
+            (Page, Some(1), Some(Requirement::User)),
+            (Category, Some(11), Some(Requirement::SiteMember)),
+            (DefaultCategory, Some(10), None),
+        ],
+        Requirement::SiteUser,
+    );
+    assert_eq!(requirement, Requirement::User);
+    assert_eq!(level, Page);
+    assert_eq!(decisive_levels(trace), [Page]);
+}
+
+#[test]
+fn page_overrides() {
+    let acl = |category_id, page_id, requirement: Requirement| PermissionAclModel {
+        acl_id: 0,
+        created_at: now(),
+        updated_at: None,
+        site_id: 1,
+        category_id,
+        page_id,
+        method: str!("page_edit"),
+        requirement: str!(requirement.name()),
+    };
+
+    let user = PermissionFacts {
+        logged_in: true,
+        ..Default::default()
+    };
+
+    // Page 1 is in category 11, and the default category is 10
+    let decide = |acls: &[PermissionAclModel]| {
+        let levels = acl_levels(1, 11, Some(10), acls).expect("Invalid ACL requirement");
+        let (requirement, _, _) = resolve_requirement(&levels, Requirement::SiteUser);
+        evaluate(requirement, user)
+    };
+
+    // No overrides, so any user may edit
+    assert_eq!(decide(&[]), (true, PermissionReason::User));
+
+    // Overrides on other pages and categories are ignored
+    assert_eq!(
+        decide(&[
+            acl(None, Some(2), Requirement::SiteMember),
+            acl(Some(12), None, Requirement::SiteMember),
+        ]),
+        (true, PermissionReason::User),
+    );
+
+    // The page override blocks the call
+    assert_eq!(
+        decide(&[
+            acl(Some(10), None, Requirement::User),
+            acl(None, Some(1), Requirement::SiteMember),
+        ]),
+        (false, PermissionReason::NotSiteMember),
+    );
+
+    // The category override blocks the call
+    assert_eq!(
+        decide(&[acl(Some(11), None, Requirement::InstanceAdmin)]),
+        (false, PermissionReason::NotInstanceAdmin),
+    );
+
+    // The default category applies to pages in any category
+    assert_eq!(
+        decide(&[acl(Some(10), None, Requirement::SiteMember)]),
+        (false, PermissionReason::NotSiteMember),
+    );
+}
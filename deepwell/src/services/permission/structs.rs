@@ -0,0 +1,276 @@
+/*
+ * services/permission/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::services::Error;
+use crate::web::Reference;
+use std::str::FromStr;
+use strum_macros::EnumIter;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetPermissionMatrix {
+    /// The user to evaluate, or `None` for a guest.
+    #[serde(default)]
+    pub user_id: Option<i64>,
+    pub site_id: i64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct PermissionMatrix {
+    pub user_id: Option<i64>,
+    pub site_id: i64,
+    pub roles: Vec<PermissionRole>,
+    pub operations: Vec<OperationPermission>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct OperationPermission {
+    pub method: &'static str,
+    pub allowed: bool,
+    pub reason: PermissionReason,
+}
+
+/// What is needed to be able to call a method.
+#[derive(EnumIter, Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Requirement {
+    /// Any caller, including guests.
+    Anyone,
+
+    /// Any logged-in user.
+    User,
+
+    /// Any logged-in user who is not banned from the site.
+    SiteUser,
+
+    /// Members of the site.
+    SiteMember,
+
+    /// Instance administrators.
+    InstanceAdmin,
+
+    /// Not available to users, only called by the platform itself.
+    Internal,
+}
+
+impl Requirement {
+    pub fn name(self) -> &'static str {
+        match self {
+            Requirement::Anyone => "anyone",
+            Requirement::User => "user",
+            Requirement::SiteUser => "site_user",
+            Requirement::SiteMember => "site_member",
+            Requirement::InstanceAdmin => "instance_admin",
+            Requirement::Internal => "internal",
+        }
+    }
+}
+
+impl FromStr for Requirement {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Requirement, Error> {
+        match value {
+            "anyone" => Ok(Requirement::Anyone),
+            "user" => Ok(Requirement::User),
+            "site_user" => Ok(Requirement::SiteUser),
+            "site_member" => Ok(Requirement::SiteMember),
+            "instance_admin" => Ok(Requirement::InstanceAdmin),
+            "internal" => Ok(Requirement::Internal),
+            _ => Err(Error::InvalidEnumValue),
+        }
+    }
+}
+
+/// A role held by a user, ordered from least to most trusted.
+#[derive(
+    EnumIter, Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum PermissionRole {
+    User,
+    SiteMember,
+    InstanceAdmin,
+}
+
+impl PermissionRole {
+    pub fn name(self) -> &'static str {
+        match self {
+            PermissionRole::User => "user",
+            PermissionRole::SiteMember => "site_member",
+            PermissionRole::InstanceAdmin => "instance_admin",
+        }
+    }
+}
+
+impl FromStr for PermissionRole {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<PermissionRole, Error> {
+        match value {
+            "user" => Ok(PermissionRole::User),
+            "site_member" => Ok(PermissionRole::SiteMember),
+            "instance_admin" => Ok(PermissionRole::InstanceAdmin),
+            _ => Err(Error::InvalidEnumValue),
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PermissionReason {
+    // Allowed
+    Anyone,
+    User,
+    SiteMember,
+    InstanceAdmin,
+
+    // Denied
+    NotLoggedIn,
+    UserDeleted,
+    SiteBanned,
+    NotSiteMember,
+    NotInstanceAdmin,
+    Internal,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SetPermissionAcl {
+    pub site_id: i64,
+
+    /// The category to set the override for. Exactly one of this and `page_id` must be set.
+    #[serde(default)]
+    pub category_id: Option<i64>,
+
+    #[serde(default)]
+    pub page_id: Option<i64>,
+
+    pub method: String,
+
+    /// The new requirement for the method, or `None` to remove the override.
+    pub requirement: Option<Requirement>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ExplainPermission<'a> {
+    /// The user to evaluate, or `None` for a guest.
+    #[serde(default)]
+    pub user_id: Option<i64>,
+    pub site_id: i64,
+    pub page: Reference<'a>,
+    pub method: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct PermissionExplanation {
+    pub user_id: Option<i64>,
+    pub site_id: i64,
+    pub page_id: i64,
+    pub method: String,
+    pub allowed: bool,
+    pub reason: PermissionReason,
+    pub requirement: Requirement,
+
+    /// Which level the requirement was taken from.
+    pub level: PermissionLevel,
+    pub roles: Vec<PermissionRole>,
+
+    /// Every level checked, from most to least specific.
+    pub trace: Vec<PermissionTraceStep>,
+}
+
+/// Where a method requirement can be set, from most to least specific.
+#[derive(Serialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PermissionLevel {
+    Page,
+    Category,
+    DefaultCategory,
+    Site,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct PermissionTraceStep {
+    pub level: PermissionLevel,
+
+    /// The page or category ID for this level, if it has one.
+    pub scope_id: Option<i64>,
+
+    /// The requirement set at this level, if any.
+    pub requirement: Option<Requirement>,
+
+    /// Whether this level's requirement is the one which was used.
+    pub decisive: bool,
+}
+
+/// What is known about the user with respect to the site.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct PermissionFacts {
+    pub logged_in: bool,
+    pub deleted: bool,
+    pub site_member: bool,
+    pub site_banned: bool,
+    pub instance_admin: bool,
+}
+
+/// Ensure `Requirement::name()` produces the same output as serde.
+#[test]
+fn requirement_name_serde() {
+    use strum::IntoEnumIterator;
+
+    for variant in Requirement::iter() {
+        let output = serde_json::to_string(&variant).expect("Unable to serialize JSON");
+        let serde_name: String =
+            serde_json::from_str(&output).expect("Unable to deserialize JSON");
+
+        assert_eq!(
+            &serde_name,
+            variant.name(),
+            "Serde name does not match variant name",
+        );
+
+        let converted: Requirement =
+            serde_name.as_str().parse().expect("Could not convert item");
+
+        assert_eq!(converted, variant, "Converted item does not match variant");
+    }
+}
+
+/// Ensure `PermissionRole::name()` produces the same output as serde.
+#[test]
+fn role_name_serde() {
+    use strum::IntoEnumIterator;
+
+    for variant in PermissionRole::iter() {
+        let output = serde_json::to_string(&variant).expect("Unable to serialize JSON");
+        let serde_name: String =
+            serde_json::from_str(&output).expect("Unable to deserialize JSON");
+
+        assert_eq!(
+            &serde_name,
+            variant.name(),
+            "Serde name does not match variant name",
+        );
+
+        let converted: PermissionRole =
+            serde_name.as_str().parse().expect("Could not convert item");
+
+        assert_eq!(converted, variant, "Converted item does not match variant");
+    }
+}
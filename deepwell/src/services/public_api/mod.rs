@@ -0,0 +1,43 @@
+/*
+ * services/public_api/mod.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The public API service, for anonymous read-only access to wiki data.
+//!
+//! This is separate from the rest of the API, which is meant for authenticated
+//! use by the web server. It only exposes data anyone could see on the site
+//! (page contents, page listings, and user profiles), so that responses can
+//! be cached aggressively by a CDN in front of the site, sparing DEEPWELL when
+//! a page suddenly gets a lot of traffic. Sites and pages which guests may
+//! not view, per `PermissionService`, are treated as if they don't exist.
+//!
+//! Each response is tagged with cache keys naming what it was built from.
+//! When one of those changes, `OutdateService` purges its key from the edge
+//! cache, so long cache lifetimes don't result in stale data being served.
+
+mod prelude {
+    pub use super::super::prelude::*;
+    pub use super::structs::*;
+}
+
+mod service;
+mod structs;
+
+pub use self::service::PublicApiService;
+pub use self::structs::*;
@@ -0,0 +1,297 @@
+/*
+ * services/public_api/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::page::{self, Entity as Page};
+use crate::models::page_category::Model as PageCategoryModel;
+use crate::models::site::Model as SiteModel;
+use crate::services::{
+    CategoryService, DomainService, PageService, PermissionService,
+    StableRevisionService, TextService, UserService,
+};
+
+#[derive(Debug)]
+pub struct PublicApiService;
+
+impl PublicApiService {
+    pub async fn get_page(
+        ctx: &ServiceContext<'_>,
+        GetPublicPage { domain, slug }: GetPublicPage,
+    ) -> Result<PublicOutput<PublicPage>> {
+        let site = match Self::get_site(ctx, &domain).await? {
+            Some(site) => site,
+            None => return Ok(Self::output(ctx.config(), None, vec![])),
+        };
+
+        // Keyed on the slug too, so that if the page is created,
+        // a cached "not found" response gets purged.
+        let mut cache_keys = vec![
+            Self::site_key(site.site_id),
+            Self::slug_key(site.site_id, &slug),
+        ];
+        let page = match PageService::get_optional(
+            ctx,
+            site.site_id,
+            Reference::Slug(cow!(slug)),
+        )
+        .await?
+        {
+            Some(page) => page,
+            None => return Ok(Self::output(ctx.config(), None, cache_keys)),
+        };
+
+        if !Self::can_view(ctx, site.site_id, Some(page.page_id)).await? {
+            debug!("Guests cannot view page ID {}", page.page_id);
+            return Ok(Self::output(ctx.config(), None, vec![]));
+        }
+
+        let revision =
+            StableRevisionService::get_page_revision(ctx, &site, &page).await?;
+        let html = TextService::get(ctx, &revision.compiled_hash).await?;
+        cache_keys.push(Self::page_key(page.page_id));
+
+        let data = PublicPage {
+            page_id: page.page_id,
+            slug: page.slug,
+            title: revision.title,
+            alt_title: revision.alt_title,
+            tags: revision.tags,
+            revision_number: revision.revision_number,
+            created_at: page.created_at,
+            updated_at: revision.created_at,
+            html,
+        };
+
+        Ok(Self::output(ctx.config(), Some(data), cache_keys))
+    }
+
+    pub async fn get_page_list(
+        ctx: &ServiceContext<'_>,
+        GetPublicPageList {
+            domain,
+            category,
+            offset,
+        }: GetPublicPageList,
+    ) -> Result<PublicOutput<PublicPageList>> {
+        let site = match Self::get_site(ctx, &domain).await? {
+            Some(site) => site,
+            None => return Ok(Self::output(ctx.config(), None, vec![])),
+        };
+
+        let cache_keys = vec![
+            Self::site_key(site.site_id),
+            Self::listing_key(site.site_id),
+        ];
+        let category_condition = match category {
+            None => None,
+            Some(category) => {
+                match CategoryService::get_optional(
+                    ctx,
+                    site.site_id,
+                    Reference::Slug(cow!(category)),
+                )
+                .await?
+                {
+                    Some(PageCategoryModel { category_id, .. }) => {
+                        Some(page::Column::PageCategoryId.eq(category_id))
+                    }
+                    None => return Ok(Self::output(ctx.config(), None, cache_keys)),
+                }
+            }
+        };
+
+        // Get one more than the limit, to see if there's another set after
+        let txn = ctx.transaction();
+        let limit = ctx.config().public_api_listing_limit;
+        let mut pages = Page::find()
+            .filter(
+                Condition::all()
+                    .add(page::Column::SiteId.eq(site.site_id))
+                    .add(page::Column::DeletedAt.is_null())
+                    .add_option(category_condition),
+            )
+            .order_by_asc(page::Column::PageId)
+            .offset(offset)
+            .limit(limit + 1)
+            .all(txn)
+            .await?;
+
+        let next_offset = if pages.len() as u64 > limit {
+            pages.truncate(limit as usize);
+            Some(offset + limit)
+        } else {
+            None
+        };
+
+        // Pages guests can't view are left out, rather than ending the listing
+        let mut summaries = Vec::with_capacity(pages.len());
+        for page in pages {
+            if !Self::can_view(ctx, site.site_id, Some(page.page_id)).await? {
+                continue;
+            }
+
+            let revision =
+                StableRevisionService::get_page_revision(ctx, &site, &page).await?;
+
+            summaries.push(PublicPageSummary {
+                page_id: page.page_id,
+                slug: page.slug,
+                title: revision.title,
+                alt_title: revision.alt_title,
+                tags: revision.tags,
+                created_at: page.created_at,
+            });
+        }
+
+        let data = PublicPageList {
+            pages: summaries,
+            next_offset,
+        };
+
+        Ok(Self::output(ctx.config(), Some(data), cache_keys))
+    }
+
+    pub async fn get_user(
+        ctx: &ServiceContext<'_>,
+        slug: String,
+    ) -> Result<PublicOutput<PublicUser>> {
+        let user =
+            match UserService::get_optional(ctx, Reference::Slug(cow!(slug))).await? {
+                Some(user) if user.deleted_at.is_none() => user,
+                _ => return Ok(Self::output(ctx.config(), None, vec![])),
+            };
+
+        let cache_keys = vec![Self::user_key(user.user_id)];
+        let data = PublicUser::from(user);
+
+        Ok(Self::output(ctx.config(), Some(data), cache_keys))
+    }
+
+    /// Gets the site for a domain, if it exists and guests may view it.
+    async fn get_site(
+        ctx: &ServiceContext<'_>,
+        domain: &str,
+    ) -> Result<Option<SiteModel>> {
+        match DomainService::site_from_domain_optional(ctx, domain).await? {
+            Some(site) if site.deleted_at.is_none() => {
+                if Self::can_view(ctx, site.site_id, None).await? {
+                    Ok(Some(site))
+                } else {
+                    debug!("Guests cannot view pages in site ID {}", site.site_id);
+                    Ok(None)
+                }
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Checks whether guests may view pages in a site, or one page in particular.
+    ///
+    /// Since these responses are cached and served to anyone,
+    /// this must be checked before anything is returned.
+    async fn can_view(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        page_id: Option<i64>,
+    ) -> Result<bool> {
+        let permission =
+            PermissionService::check(ctx, site_id, page_id, None, "page_get").await?;
+
+        Ok(permission.allowed)
+    }
+
+    /// Sends a request to the edge cache to purge all responses with these keys.
+    pub async fn purge(ctx: &ServiceContext<'_>, keys: &[String]) -> Result<()> {
+        let purge_url = match ctx.config().public_api_purge_url {
+            Some(ref url) => url,
+            None => return Ok(()),
+        };
+
+        info!("Purging {} keys from edge cache", keys.len());
+        let response = reqwest::Client::new()
+            .post(purge_url)
+            .header("Surrogate-Key", keys.join(" "))
+            .send()
+            .await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            error!("Edge cache purge request failed with {status}");
+            return Err(Error::EdgeCachePurge);
+        }
+
+        Ok(())
+    }
+
+    /// Builds the response, with caching information.
+    ///
+    /// Responses without any cache keys cannot be purged, so they
+    /// are only cached briefly, in case the data is later created.
+    fn output<T>(
+        config: &Config,
+        data: Option<T>,
+        cache_keys: Vec<String>,
+    ) -> PublicOutput<T> {
+        let cache_control = if cache_keys.is_empty() {
+            format!(
+                "public, max-age={}",
+                config.public_api_browser_max_age.as_secs()
+            )
+        } else {
+            format!(
+                "public, max-age={}, s-maxage={}, stale-while-revalidate={stale}, stale-if-error={stale}",
+                config.public_api_browser_max_age.as_secs(),
+                config.public_api_edge_max_age.as_secs(),
+                stale = config.public_api_stale.as_secs(),
+            )
+        };
+
+        PublicOutput {
+            data,
+            cache_keys,
+            cache_control,
+        }
+    }
+
+    #[inline]
+    pub fn site_key(site_id: i64) -> String {
+        format!("site-{site_id}")
+    }
+
+    #[inline]
+    pub fn page_key(page_id: i64) -> String {
+        format!("page-{page_id}")
+    }
+
+    #[inline]
+    pub fn slug_key(site_id: i64, slug: &str) -> String {
+        format!("slug-{site_id}-{slug}")
+    }
+
+    #[inline]
+    pub fn listing_key(site_id: i64) -> String {
+        format!("pages-{site_id}")
+    }
+
+    #[inline]
+    pub fn user_key(user_id: i64) -> String {
+        format!("user-{user_id}")
+    }
+}
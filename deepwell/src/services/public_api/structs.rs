@@ -0,0 +1,118 @@
+/*
+ * services/public_api/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::models::sea_orm_active_enums::UserType;
+use crate::models::user::Model as UserModel;
+use time::OffsetDateTime;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetPublicPage {
+    /// The domain of the site the page is on.
+    pub domain: String,
+    pub slug: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetPublicPageList {
+    /// The domain of the site to list pages for.
+    pub domain: String,
+
+    /// If set, only pages in this category are listed.
+    #[serde(default)]
+    pub category: Option<String>,
+
+    #[serde(default)]
+    pub offset: u64,
+}
+
+/// A response from the public API, along with how it can be cached.
+#[derive(Serialize, Debug, Clone)]
+pub struct PublicOutput<T> {
+    /// The requested data, or `None` if it does not exist.
+    pub data: Option<T>,
+
+    /// The keys this response should be tagged with in the edge cache.
+    pub cache_keys: Vec<String>,
+
+    /// The value for the `Cache-Control` header.
+    pub cache_control: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct PublicPage {
+    pub page_id: i64,
+    pub slug: String,
+    pub title: String,
+    pub alt_title: Option<String>,
+    pub tags: Vec<String>,
+    pub revision_number: i64,
+    pub created_at: OffsetDateTime,
+    pub updated_at: OffsetDateTime,
+    pub html: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct PublicPageSummary {
+    pub page_id: i64,
+    pub slug: String,
+    pub title: String,
+    pub alt_title: Option<String>,
+    pub tags: Vec<String>,
+    pub created_at: OffsetDateTime,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct PublicPageList {
+    pub pages: Vec<PublicPageSummary>,
+
+    /// The offset to request the next set of pages, if there are more.
+    pub next_offset: Option<u64>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct PublicUser {
+    pub user_id: i64,
+    pub user_type: UserType,
+    pub created_at: OffsetDateTime,
+    pub name: String,
+    pub slug: String,
+    pub real_name: Option<String>,
+    pub gender: Option<String>,
+    pub location: Option<String>,
+    pub biography: Option<String>,
+    pub user_page: Option<String>,
+}
+
+impl From<UserModel> for PublicUser {
+    fn from(user: UserModel) -> Self {
+        PublicUser {
+            user_id: user.user_id,
+            user_type: user.user_type,
+            created_at: user.created_at,
+            name: user.name,
+            slug: user.slug,
+            real_name: user.real_name,
+            gender: user.gender,
+            location: user.location,
+            biography: user.biography,
+            user_page: user.user_page,
+        }
+    }
+}
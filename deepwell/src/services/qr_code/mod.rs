@@ -0,0 +1,41 @@
+/*
+ * services/qr_code/mod.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The QR code service, for generating scannable links to pages.
+//!
+//! These are intended for print materials, such as posters or convention badges.
+//! A QR code can either point at the page's full URL, or at its shortlink,
+//! which produces a smaller and more easily scanned code.
+//!
+//! The site's logo (its site user's avatar) can be placed in the center,
+//! which is possible because codes are generated with the highest level
+//! of error correction. Generated images are cached in Redis, keyed by
+//! every parameter which affects the output.
+
+mod prelude {
+    pub use super::super::prelude::*;
+    pub use super::structs::*;
+}
+
+mod service;
+mod structs;
+
+pub use self::service::QrCodeService;
+pub use self::structs::*;
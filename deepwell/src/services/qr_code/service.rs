@@ -0,0 +1,281 @@
+/*
+ * services/qr_code/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::hash::k12_hash;
+use crate::services::shortlink::CreateShortlink;
+use crate::services::{
+    BlobService, DomainService, PageService, RelationService, ShortlinkService,
+    SiteService, UserService,
+};
+use crate::web::Bytes;
+use data_encoding::BASE64;
+use image::imageops::{self, FilterType};
+use image::{DynamicImage, ImageFormat, Luma, Rgba, RgbaImage};
+use qrcode::render::svg;
+use qrcode::types::QrError;
+use qrcode::{EcLevel, QrCode};
+use redis::AsyncCommands;
+use std::io::Cursor;
+
+/// Number of modules making up the blank border around a QR code.
+///
+/// This is four on each side, as required by the specification.
+const QUIET_ZONE_MODULES: u32 = 8;
+
+/// What fraction of the QR code's width the logo may take up.
+///
+/// Codes are generated with level H error correction, which can recover
+/// from roughly 30% damage, so this leaves plenty of margin for scanning.
+const LOGO_SCALE: u32 = 5;
+
+#[derive(Debug)]
+pub struct QrCodeService;
+
+impl QrCodeService {
+    pub async fn get(
+        ctx: &ServiceContext<'_>,
+        GetQrCode {
+            site_id,
+            page: reference,
+            shortlink,
+            format,
+            size,
+            logo,
+        }: GetQrCode<'_>,
+    ) -> Result<QrCodeOutput> {
+        let config = ctx.config();
+        let size = size.unwrap_or(config.qr_code_default_size);
+        if size == 0 {
+            error!("Requested QR code size is zero");
+            return Err(Error::BadRequest);
+        }
+
+        if size > config.qr_code_maximum_size {
+            error!(
+                "Requested QR code size {size} is larger than the maximum {}",
+                config.qr_code_maximum_size,
+            );
+            return Err(Error::QrCodeTooLarge);
+        }
+
+        // Determine the URL to encode
+        let url = if shortlink {
+            let output = ShortlinkService::get_or_create(
+                ctx,
+                CreateShortlink {
+                    site_id,
+                    page: reference,
+                    revision_number: None,
+                },
+            )
+            .await?;
+
+            output.url
+        } else {
+            let page = PageService::get(ctx, site_id, reference).await?;
+            let site = SiteService::get(ctx, Reference::Id(site_id)).await?;
+            let domain = DomainService::domain_for_site(config, &site);
+            format!("https://{domain}/{}", page.slug)
+        };
+
+        info!(
+            "Getting {} QR code for '{url}' (size {size}, logo {logo})",
+            format.name(),
+        );
+
+        let logo_hash = if logo {
+            Self::get_logo_hash(ctx, site_id).await?
+        } else {
+            None
+        };
+
+        // Check if this QR code has been generated recently
+        let cache_key = Self::cache_key(format, size, logo_hash.as_deref(), &url);
+        let mut redis = ctx.redis();
+        let cached: Option<Vec<u8>> = ctx.until_deadline(redis.get(&cache_key)).await?;
+        if let Some(data) = cached {
+            debug!("Found cached QR code '{cache_key}'");
+            return Ok(QrCodeOutput {
+                url,
+                mime: format.mime(),
+                data: Bytes::from(data),
+            });
+        }
+
+        // Generate the QR code
+        let code =
+            QrCode::with_error_correction_level(&url, EcLevel::H).map_err(|error| {
+                match error {
+                    QrError::DataTooLong => Error::QrCodeDataTooLong,
+                    _ => {
+                        error!("Unable to encode URL as QR code: {error}");
+                        Error::BadRequest
+                    }
+                }
+            })?;
+
+        // The largest module size which fits in the requested size.
+        // The output image may be slightly smaller as a result.
+        let modules = code.width() as u32 + QUIET_ZONE_MODULES;
+        let module_size = (size / modules).max(1);
+        let dimension = module_size * modules;
+
+        let logo = match logo_hash {
+            None => None,
+            Some(hash) => {
+                let data = BlobService::get(ctx, &hash).await?;
+                Self::prepare_logo(&data, dimension / LOGO_SCALE)
+            }
+        };
+
+        let data = match format {
+            QrCodeFormat::Svg => Self::render_svg(&code, module_size, logo.as_ref())?,
+            QrCodeFormat::Png => Self::render_png(&code, module_size, logo.as_ref())?,
+        };
+
+        let ttl = config.qr_code_cache_ttl.as_secs() as usize;
+        ctx.until_deadline(redis.set_ex::<_, _, ()>(&cache_key, &data, ttl))
+            .await?;
+
+        Ok(QrCodeOutput {
+            url,
+            mime: format.mime(),
+            data: Bytes::from(data),
+        })
+    }
+
+    /// Gets the blob hash of the site's logo, if it has one.
+    async fn get_logo_hash(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+    ) -> Result<Option<Vec<u8>>> {
+        let user_id = RelationService::get_site_user_id_for_site(ctx, site_id).await?;
+        let user = UserService::get(ctx, Reference::Id(user_id)).await?;
+        if user.avatar_s3_hash.is_none() {
+            debug!("Site ID {site_id} has no logo, not embedding in QR code");
+        }
+
+        Ok(user.avatar_s3_hash)
+    }
+
+    fn cache_key(
+        format: QrCodeFormat,
+        size: u32,
+        logo_hash: Option<&[u8]>,
+        url: &str,
+    ) -> String {
+        let logo_hash = logo_hash.map(hex::encode).unwrap_or_default();
+        let parameters = format!("{}\0{size}\0{logo_hash}\0{url}", format.name());
+        let hash = k12_hash(parameters.as_bytes());
+        format!("qr-code:{}", hex::encode(hash))
+    }
+
+    /// Decodes and scales the logo image.
+    ///
+    /// Since the logo is just decoration, a bad image is not fatal,
+    /// the QR code is produced without it instead.
+    fn prepare_logo(data: &[u8], size: u32) -> Option<RgbaImage> {
+        match image::load_from_memory(data) {
+            Ok(image) => {
+                Some(image.resize(size, size, FilterType::Lanczos3).into_rgba8())
+            }
+            Err(error) => {
+                warn!("Unable to decode site logo, omitting from QR code: {error}");
+                None
+            }
+        }
+    }
+
+    /// Gets the position and size of the logo and its white backing square.
+    ///
+    /// Returns `(backing_x, backing_y, backing_size, logo_x, logo_y)`.
+    fn logo_position(dimension: u32, logo: &RgbaImage) -> (u32, u32, u32, u32, u32) {
+        let padding = logo.width().max(logo.height()) / 10;
+        let backing_size = logo.width().max(logo.height()) + padding * 2;
+        let backing_offset = (dimension - backing_size) / 2;
+        let logo_x = (dimension - logo.width()) / 2;
+        let logo_y = (dimension - logo.height()) / 2;
+        (backing_offset, backing_offset, backing_size, logo_x, logo_y)
+    }
+
+    fn render_svg(
+        code: &QrCode,
+        module_size: u32,
+        logo: Option<&RgbaImage>,
+    ) -> Result<Vec<u8>> {
+        let mut output = code
+            .render::<svg::Color>()
+            .module_dimensions(module_size, module_size)
+            .build();
+
+        if let Some(logo) = logo {
+            let dimension = code.width() as u32 + QUIET_ZONE_MODULES;
+            let dimension = dimension * module_size;
+            let (backing_x, backing_y, backing_size, logo_x, logo_y) =
+                Self::logo_position(dimension, logo);
+
+            let mut png = Vec::new();
+            logo.write_to(&mut Cursor::new(&mut png), ImageFormat::Png)?;
+
+            let elements = format!(
+                r##"<rect x="{backing_x}" y="{backing_y}" width="{backing_size}" height="{backing_size}" fill="#fff"/><image x="{logo_x}" y="{logo_y}" width="{}" height="{}" href="data:image/png;base64,{}"/>"##,
+                logo.width(),
+                logo.height(),
+                BASE64.encode(&png),
+            );
+
+            match output.rfind("</svg>") {
+                Some(index) => output.insert_str(index, &elements),
+                None => warn!("Generated QR code SVG has no closing tag, omitting logo"),
+            }
+        }
+
+        Ok(output.into_bytes())
+    }
+
+    fn render_png(
+        code: &QrCode,
+        module_size: u32,
+        logo: Option<&RgbaImage>,
+    ) -> Result<Vec<u8>> {
+        let image = code
+            .render::<Luma<u8>>()
+            .module_dimensions(module_size, module_size)
+            .build();
+
+        let mut image = DynamicImage::ImageLuma8(image).into_rgba8();
+
+        if let Some(logo) = logo {
+            let (backing_x, backing_y, backing_size, logo_x, logo_y) =
+                Self::logo_position(image.width(), logo);
+
+            let backing =
+                RgbaImage::from_pixel(backing_size, backing_size, Rgba([255; 4]));
+
+            imageops::overlay(&mut image, &backing, backing_x.into(), backing_y.into());
+            imageops::overlay(&mut image, logo, logo_x.into(), logo_y.into());
+        }
+
+        let mut output = Vec::new();
+        image.write_to(&mut Cursor::new(&mut output), ImageFormat::Png)?;
+        Ok(output)
+    }
+}
@@ -0,0 +1,75 @@
+/*
+ * services/qr_code/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::web::{Bytes, Reference};
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetQrCode<'a> {
+    pub site_id: i64,
+    pub page: Reference<'a>,
+
+    /// Whether to encode the page's shortlink rather than its full URL.
+    #[serde(default)]
+    pub shortlink: bool,
+
+    #[serde(default)]
+    pub format: QrCodeFormat,
+
+    /// Width and height of the image in pixels.
+    /// If not set, the configured default is used.
+    #[serde(default)]
+    pub size: Option<u32>,
+
+    /// Whether to embed the site's logo in the center of the code.
+    #[serde(default)]
+    pub logo: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum QrCodeFormat {
+    #[default]
+    Svg,
+    Png,
+}
+
+impl QrCodeFormat {
+    pub fn name(self) -> &'static str {
+        match self {
+            QrCodeFormat::Svg => "svg",
+            QrCodeFormat::Png => "png",
+        }
+    }
+
+    pub fn mime(self) -> &'static str {
+        match self {
+            QrCodeFormat::Svg => "image/svg+xml",
+            QrCodeFormat::Png => "image/png",
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct QrCodeOutput {
+    /// The URL encoded in the QR code.
+    pub url: String,
+    pub mime: &'static str,
+    pub data: Bytes<'static>,
+}
@@ -0,0 +1,44 @@
+/*
+ * services/refresh_token/mod.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The refresh token service, for clients which stay logged in long-term.
+//!
+//! Mobile and API clients cannot be expected to keep a session alive, so
+//! instead they are given a refresh token when logging in. This token is
+//! bound to an identifier for the client, and may be exchanged for a new
+//! session whenever needed.
+//!
+//! Each refresh token can only be used once. Exchanging it also yields a
+//! replacement refresh token, in the same "family" as the original. If an
+//! already-used token is presented again, then it has likely been stolen,
+//! and so the whole family is revoked, logging out both the thief and the
+//! legitimate client. Sessions record the family they were issued for, so
+//! this also ends any sessions already obtained with its tokens.
+
+mod prelude {
+    pub use super::super::prelude::*;
+    pub use super::structs::*;
+}
+
+mod service;
+mod structs;
+
+pub use self::service::RefreshTokenService;
+pub use self::structs::*;
@@ -0,0 +1,274 @@
+/*
+ * services/refresh_token/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::refresh_token::{self, Entity as RefreshToken};
+use crate::models::user::{self, Entity as User};
+use crate::services::session::CreateSession;
+use crate::services::{ImpersonationService, SessionService};
+use crate::utils::{hash_token, new_token};
+use cuid2::cuid;
+use sea_orm::UpdateResult;
+use sea_query::Expr;
+use time::OffsetDateTime;
+
+/// The longest client identifier which can be passed.
+const MAXIMUM_CLIENT_ID_LENGTH: usize = 100;
+
+#[derive(Debug)]
+pub struct RefreshTokenService;
+
+impl RefreshTokenService {
+    /// Issues a refresh token for a logged-in client, starting a new family.
+    pub async fn create(
+        ctx: &ServiceContext<'_>,
+        CreateRefreshToken {
+            session_token,
+            client_id,
+        }: CreateRefreshToken,
+    ) -> Result<CreateRefreshTokenOutput> {
+        check_client_id(&client_id)?;
+
+        let session = SessionService::get(ctx, &session_token).await?;
+        ImpersonationService::check(&session, "refresh-token.create")?;
+        if session.restricted {
+            error!("Cannot issue refresh token for a restricted session");
+            return Err(Error::InvalidSessionToken);
+        }
+
+        info!(
+            "Creating refresh token for user ID {} (client '{client_id}')",
+            session.user_id,
+        );
+
+        let (refresh_token, expires_at) =
+            Self::insert(ctx, session.user_id, client_id, cuid()).await?;
+
+        Ok(CreateRefreshTokenOutput {
+            refresh_token,
+            expires_at,
+        })
+    }
+
+    /// Exchanges a refresh token for a new session and refresh token.
+    ///
+    /// Presenting a token which has already been exchanged revokes every
+    /// token in its family. Any other problem with the token, such as it
+    /// being unknown, expired, or for a different client, is reported as
+    /// a generic authentication failure.
+    pub async fn exchange(
+        ctx: &ServiceContext<'_>,
+        ExchangeRefreshToken {
+            refresh_token,
+            client_id,
+            ip_address,
+            user_agent,
+        }: ExchangeRefreshToken,
+    ) -> Result<ExchangeRefreshTokenOutput> {
+        let txn = ctx.transaction();
+        let token_hash = hash_token(&refresh_token);
+        let token = RefreshToken::find_by_id(token_hash.clone())
+            .one(txn)
+            .await?
+            .ok_or(Error::InvalidAuthentication)?;
+
+        if token.revoked_at.is_some() || token.expires_at <= now() {
+            debug!("Refresh token is revoked or expired");
+            return Err(Error::InvalidAuthentication);
+        }
+
+        if token.client_id != client_id {
+            warn!(
+                "Refresh token for user ID {} was presented by a different client",
+                token.user_id,
+            );
+            return Err(Error::InvalidAuthentication);
+        }
+
+        if token.used_at.is_some() {
+            warn!(
+                "Refresh token for user ID {} was reused, revoking its family",
+                token.user_id,
+            );
+            Self::revoke_family(ctx, &token.family_id).await?;
+            return Ok(ExchangeRefreshTokenOutput::Revoked);
+        }
+
+        // Only mark the token as used if nothing else has in the meantime
+        let UpdateResult { rows_affected } = RefreshToken::update_many()
+            .col_expr(refresh_token::Column::UsedAt, Expr::value(now()))
+            .filter(
+                Condition::all()
+                    .add(refresh_token::Column::TokenHash.eq(token_hash))
+                    .add(refresh_token::Column::UsedAt.is_null()),
+            )
+            .exec(txn)
+            .await?;
+
+        if rows_affected == 0 {
+            warn!("Refresh token was used concurrently, revoking its family");
+            Self::revoke_family(ctx, &token.family_id).await?;
+            return Ok(ExchangeRefreshTokenOutput::Revoked);
+        }
+
+        // Ensure the user can still log in
+        let user = User::find()
+            .filter(
+                Condition::all()
+                    .add(user::Column::UserId.eq(token.user_id))
+                    .add(user::Column::DeletedAt.is_null()),
+            )
+            .one(txn)
+            .await?
+            .ok_or(Error::InvalidAuthentication)?;
+
+        info!("Exchanging refresh token for user ID {}", user.user_id);
+
+        let (refresh_token, expires_at) =
+            Self::insert(ctx, user.user_id, client_id, token.family_id.clone()).await?;
+
+        let session_token = SessionService::create_for_refresh_family(
+            ctx,
+            CreateSession {
+                user_id: user.user_id,
+                ip_address,
+                user_agent,
+                restricted: false,
+                impersonator_id: None,
+            },
+            token.family_id,
+        )
+        .await?;
+
+        Ok(ExchangeRefreshTokenOutput::Refreshed {
+            session_token,
+            refresh_token,
+            expires_at,
+        })
+    }
+
+    /// Revokes the family of the given refresh token, for logging out a client.
+    pub async fn revoke(ctx: &ServiceContext<'_>, refresh_token: &str) -> Result<()> {
+        let txn = ctx.transaction();
+        let token = RefreshToken::find_by_id(hash_token(refresh_token))
+            .one(txn)
+            .await?
+            .ok_or(Error::InvalidAuthentication)?;
+
+        info!("Revoking refresh tokens for user ID {}", token.user_id);
+        Self::revoke_family(ctx, &token.family_id).await?;
+        Ok(())
+    }
+
+    /// Revokes all refresh tokens for a user.
+    ///
+    /// # Returns
+    /// The number of revoked tokens.
+    pub async fn revoke_all(ctx: &ServiceContext<'_>, user_id: i64) -> Result<u64> {
+        info!("Revoking all refresh tokens for user ID {user_id}");
+
+        let txn = ctx.transaction();
+        let UpdateResult { rows_affected } = RefreshToken::update_many()
+            .col_expr(refresh_token::Column::RevokedAt, Expr::value(now()))
+            .filter(
+                Condition::all()
+                    .add(refresh_token::Column::UserId.eq(user_id))
+                    .add(refresh_token::Column::RevokedAt.is_null()),
+            )
+            .exec(txn)
+            .await?;
+
+        Ok(rows_affected)
+    }
+
+    /// Deletes all expired refresh tokens.
+    ///
+    /// Revoked tokens are kept until they expire, so that reuse of
+    /// them is still recognized.
+    ///
+    /// # Returns
+    /// The number of pruned tokens.
+    pub async fn prune(ctx: &ServiceContext<'_>) -> Result<u64> {
+        info!("Pruning all expired refresh tokens");
+
+        let txn = ctx.transaction();
+        let DeleteResult { rows_affected } = RefreshToken::delete_many()
+            .filter(refresh_token::Column::ExpiresAt.lte(now()))
+            .exec(txn)
+            .await?;
+
+        debug!("{rows_affected} expired refresh tokens were pruned");
+        Ok(rows_affected)
+    }
+
+    /// Revokes every token in a family, and logs out the sessions issued for them.
+    async fn revoke_family(ctx: &ServiceContext<'_>, family_id: &str) -> Result<()> {
+        let txn = ctx.transaction();
+        RefreshToken::update_many()
+            .col_expr(refresh_token::Column::RevokedAt, Expr::value(now()))
+            .filter(
+                Condition::all()
+                    .add(refresh_token::Column::FamilyId.eq(family_id))
+                    .add(refresh_token::Column::RevokedAt.is_null()),
+            )
+            .exec(txn)
+            .await?;
+
+        SessionService::invalidate_refresh_family(ctx, family_id).await?;
+        Ok(())
+    }
+
+    /// Inserts a new refresh token, returning it and its expiry.
+    async fn insert(
+        ctx: &ServiceContext<'_>,
+        user_id: i64,
+        client_id: String,
+        family_id: String,
+    ) -> Result<(String, OffsetDateTime)> {
+        let txn = ctx.transaction();
+        let config = ctx.config();
+        let token = new_token(config.session_token_length);
+
+        let created_at = now();
+        let expires_at = created_at + config.refresh_token_duration;
+        let model = refresh_token::ActiveModel {
+            token_hash: Set(hash_token(&token)),
+            family_id: Set(family_id),
+            user_id: Set(user_id),
+            client_id: Set(client_id),
+            created_at: Set(created_at),
+            expires_at: Set(expires_at),
+            used_at: Set(None),
+            revoked_at: Set(None),
+        };
+        model.insert(txn).await?;
+
+        Ok((token, expires_at))
+    }
+}
+
+fn check_client_id(client_id: &str) -> Result<()> {
+    if client_id.is_empty() || client_id.len() > MAXIMUM_CLIENT_ID_LENGTH {
+        error!("Client identifier is empty or too long");
+        return Err(Error::RefreshTokenClientInvalid);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,60 @@
+/*
+ * services/refresh_token/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::net::IpAddr;
+use time::OffsetDateTime;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CreateRefreshToken {
+    pub session_token: String,
+    pub client_id: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct CreateRefreshTokenOutput {
+    pub refresh_token: String,
+    pub expires_at: OffsetDateTime,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ExchangeRefreshToken {
+    pub refresh_token: String,
+    pub client_id: String,
+    pub ip_address: IpAddr,
+    pub user_agent: String,
+}
+
+/// The result of exchanging a refresh token.
+///
+/// Reuse is reported as a result rather than an error,
+/// so that revoking the token family is not rolled back.
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "result", rename_all = "snake_case")]
+pub enum ExchangeRefreshTokenOutput {
+    Refreshed {
+        session_token: String,
+        refresh_token: String,
+        expires_at: OffsetDateTime,
+    },
+
+    /// The token was already used, so its family has been revoked.
+    /// The client must log in again.
+    Revoked,
+}
@@ -63,6 +63,8 @@ pub use self::user_follow::*;
 
 use super::prelude::*;
 use crate::models::relation::{self, Entity as Relation, Model as RelationModel};
+use crate::models::sea_orm_active_enums::RelationObjectType;
+use crate::services::SessionService;
 use serde::Serialize;
 
 // Base service exists here.
@@ -125,6 +127,7 @@ impl RelationService {
         };
 
         let relation = model.insert(txn).await?;
+        Self::rotate_sessions(ctx, &relation).await?;
         Ok(relation)
     }
 
@@ -145,9 +148,25 @@ impl RelationService {
         };
 
         let output = model.update(txn).await?;
+        Self::rotate_sessions(ctx, &output).await?;
         Ok(output)
     }
 
+    /// Has a user's sessions replaced soon after their roles in a site change.
+    async fn rotate_sessions(
+        ctx: &ServiceContext<'_>,
+        relation: &RelationModel,
+    ) -> Result<()> {
+        let role_changed = relation.relation_type == RelationType::SiteMember.value()
+            || relation.relation_type == RelationType::SiteBan.value();
+
+        if role_changed && relation.from_type == RelationObjectType::User {
+            SessionService::require_rotation(ctx, relation.from_id).await?;
+        }
+
+        Ok(())
+    }
+
     pub async fn get_optional(
         ctx: &ServiceContext<'_>,
         reference: RelationReference,
@@ -39,7 +39,6 @@ impl_relation!(
 );
 
 impl RelationService {
-    #[allow(dead_code)] // TEMP
     pub async fn create_site_ban(
         ctx: &ServiceContext<'_>,
         CreateSiteBan {
@@ -0,0 +1,74 @@
+/*
+ * services/render/limiter.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Counts the renders running in this process, to shed low-priority ones under load.
+#[derive(Debug, Default)]
+pub struct RenderLimiter {
+    in_flight: AtomicUsize,
+}
+
+impl RenderLimiter {
+    #[inline]
+    pub fn new() -> Self {
+        RenderLimiter::default()
+    }
+
+    /// Counts a render as running until the returned permit is dropped.
+    ///
+    /// Also returns how many renders were already running, so the
+    /// caller can decide whether to go ahead.
+    pub fn acquire(&self) -> (RenderPermit<'_>, usize) {
+        let previous = self.in_flight.fetch_add(1, Ordering::Relaxed);
+        (RenderPermit { limiter: self }, previous)
+    }
+}
+
+#[derive(Debug)]
+pub struct RenderPermit<'a> {
+    limiter: &'a RenderLimiter,
+}
+
+impl Drop for RenderPermit<'_> {
+    fn drop(&mut self) {
+        self.limiter.in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[test]
+fn render_permits() {
+    let limiter = RenderLimiter::new();
+    let in_flight = || limiter.in_flight.load(Ordering::Relaxed);
+    assert_eq!(in_flight(), 0);
+
+    let (first, previous) = limiter.acquire();
+    assert_eq!(previous, 0);
+
+    let (second, previous) = limiter.acquire();
+    assert_eq!(previous, 1);
+    assert_eq!(in_flight(), 2);
+
+    drop(first);
+    assert_eq!(in_flight(), 1);
+
+    drop(second);
+    assert_eq!(in_flight(), 0);
+}
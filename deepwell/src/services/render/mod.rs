@@ -23,17 +23,22 @@ mod prelude {
     pub use super::structs::*;
     pub use ftml::{
         self,
-        data::PageInfo,
+        data::{
+            AttachedFile, FileInfo, FileReference, ImageDerivative, PageInfo, PageRef,
+            RenderContext,
+        },
         info::VERSION as FTML_VERSION,
         parsing::ParseError,
         render::html::{HtmlOutput, HtmlRender},
-        render::Render,
         settings::WikitextSettings,
+        tree::{ContentStats, GallerySize},
     };
 }
 
+mod limiter;
 mod service;
 mod structs;
 
+pub use self::limiter::{RenderLimiter, RenderPermit};
 pub use self::service::RenderService;
 pub use self::structs::*;
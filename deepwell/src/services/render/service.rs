@@ -19,8 +19,21 @@
  */
 
 use super::prelude::*;
-use crate::services::TextService;
+use super::{RenderLimiter, RenderPermit};
+use crate::models::sea_orm_active_enums::UserType;
+use crate::services::file::GetFile;
+use crate::services::file_revision::FileLicensing;
+use crate::services::{
+    BlobService, CdnService, FileService, PageService, SiteService,
+    StableRevisionService, TextService, ThumbnailService, UserService,
+};
+use ftml::includes::{FetchedPage, IncludeRef, Includer};
+use std::borrow::Cow;
+use std::collections::hash_map::{Entry, HashMap};
+use std::convert::Infallible;
+use std::time::Duration;
 use tokio::time::timeout;
+use wikidot_normalize::normalize;
 
 #[derive(Debug)]
 pub struct RenderService;
@@ -31,27 +44,51 @@ impl RenderService {
         mut wikitext: String,
         page_info: &PageInfo<'_>,
         settings: &WikitextSettings,
+        priority: RenderPriority,
     ) -> Result<RenderOutput> {
         let compiled_generator = FTML_VERSION.clone();
 
+        // Held until this render is finished, so it counts towards the load.
+        let config = ctx.config();
+        let _permit = admit(
+            ctx.render_limiter(),
+            priority,
+            config.render_shed_threshold,
+            config.render_shed_retry_after,
+        )?;
+
         // Isolate the actual render task.
         // This way we can cut it off if it times out.
+        //
+        // Renders are expensive, so don't start one if the request is already
+        // out of time, and don't let one run past the request's deadline.
 
-        let config = ctx.config();
-        let (html_output, errors) = timeout(config.render_timeout, async {
-            // Run ftml to parse and render
-            // TODO include
-            ftml::preprocess(&mut wikitext);
-            let tokens = ftml::tokenize(&wikitext);
-            let result = ftml::parse(&tokens, page_info, settings);
-            let (tree, errors) = result.into();
-            let html_output = HtmlRender.render(&tree, page_info, settings);
-            (html_output, errors)
-        })
-        .await
-        // Not using Error::from() because timeouts could occur in other places,
-        // and this error variant is not specific to all timeouts.
-        .map_err(|_| Error::RenderTimeout)?;
+        ctx.check_deadline()?;
+        let render_timeout = match ctx.remaining_time() {
+            Some(remaining) => remaining.min(ctx.config().render_timeout),
+            None => ctx.config().render_timeout,
+        };
+
+        let (html_output, errors, included_pages, content_stats) =
+            timeout(render_timeout, async {
+                // Run ftml to parse and render
+                // TODO include
+                ftml::preprocess(&mut wikitext);
+                let included_pages = find_includes(&wikitext, settings);
+                let tokens = ftml::tokenize(&wikitext);
+                let result = ftml::parse(&tokens, page_info, settings);
+                let (tree, errors) = result.into();
+                let references = tree.file_references(page_info);
+                let context = Self::render_context(ctx, references, settings).await?;
+                let html_output =
+                    HtmlRender.render_with_context(&tree, page_info, settings, &context);
+                let content_stats = tree.stats();
+                Ok::<_, Error>((html_output, errors, included_pages, content_stats))
+            })
+            .await
+            // Not using Error::from() because timeouts could occur in other places,
+            // and this error variant is not specific to all timeouts.
+            .map_err(|_| Error::RenderTimeout)??;
 
         // Insert compiled HTML into text table
         let compiled_hash = TextService::create(ctx, html_output.body.clone()).await?;
@@ -60,9 +97,479 @@ impl RenderService {
         Ok(RenderOutput {
             html_output,
             errors,
+            included_pages,
             compiled_hash,
             compiled_at: now(),
             compiled_generator,
+            content_stats,
         })
     }
+
+    /// Looks up the attached files a page refers to, so they can be rendered.
+    ///
+    /// Files which don't exist, or whose contents are hidden,
+    /// are left out and so are shown as missing.
+    async fn render_context<'a>(
+        ctx: &ServiceContext<'_>,
+        references: Vec<FileReference<'a>>,
+        settings: &WikitextSettings,
+    ) -> Result<RenderContext<'a>> {
+        let mut files = Vec::new();
+        for reference in references {
+            if let Some(file) = Self::get_attached_file(ctx, reference, settings).await? {
+                files.push(file);
+            }
+        }
+
+        Ok(RenderContext { files })
+    }
+
+    async fn get_attached_file<'a>(
+        ctx: &ServiceContext<'_>,
+        reference: FileReference<'a>,
+        settings: &WikitextSettings,
+    ) -> Result<Option<AttachedFile<'a>>> {
+        debug!(
+            "Looking up file '{}' on page '{}' in site '{}' for render",
+            reference.file, reference.page, reference.site,
+        );
+
+        let site =
+            match SiteService::get_optional(ctx, Reference::Slug(cow!(reference.site)))
+                .await?
+            {
+                Some(site) => site,
+                None => return Ok(None),
+            };
+
+        let mut page_slug = reference.page.to_string();
+        normalize(&mut page_slug);
+        let page = match PageService::get_optional(
+            ctx,
+            site.site_id,
+            Reference::Slug(cow!(page_slug)),
+        )
+        .await?
+        {
+            Some(page) => page,
+            None => return Ok(None),
+        };
+
+        let file = match FileService::get_optional(
+            ctx,
+            GetFile {
+                site_id: site.site_id,
+                page_id: page.page_id,
+                file: Reference::Slug(cow!(reference.file)),
+            },
+        )
+        .await?
+        {
+            Some(file) => file,
+            None => return Ok(None),
+        };
+
+        let revision =
+            StableRevisionService::get_file_revision(ctx, &site, &file).await?;
+        if revision.hidden.iter().any(|field| field == "blob") {
+            debug!("Contents of file ID {} are hidden", file.file_id);
+            return Ok(None);
+        }
+
+        let uploader = UserService::get_optional(ctx, Reference::Id(revision.user_id))
+            .await?
+            .map(|user| Cow::Owned(user.name));
+
+        let license = serde_json::from_value::<FileLicensing>(revision.licensing)
+            .ok()
+            .map(|licensing| Cow::Owned(str!(licensing.describe())));
+
+        let cdn_path = CdnService::get_path(
+            ctx,
+            site.site_id,
+            revision.page_id,
+            &revision.s3_hash,
+            &revision.name,
+        )
+        .await?;
+
+        // Galleries show thumbnails rather than the full image
+        let mut derivatives = Vec::new();
+        if ThumbnailService::supports(&revision.mime_hint) {
+            let thumbnails =
+                ThumbnailService::get_all_hashes(ctx, &revision.s3_hash).await?;
+            let sizes = &ctx.config().thumbnail_sizes;
+            for size in GALLERY_SIZES {
+                let Some(pixels) = thumbnail_size(sizes, size) else {
+                    continue;
+                };
+
+                let thumbnail = thumbnails
+                    .iter()
+                    .find(|(thumbnail_size, _)| *thumbnail_size == pixels);
+
+                if let Some((_, hash)) = thumbnail {
+                    let cdn_path = CdnService::get_path(
+                        ctx,
+                        site.site_id,
+                        revision.page_id,
+                        hash,
+                        &revision.name,
+                    )
+                    .await?;
+
+                    derivatives.push(ImageDerivative {
+                        size,
+                        url: Cow::Owned(cdn_path.path),
+                    });
+                }
+            }
+        }
+
+        // Small images are embedded in the output, if the settings call for it
+        let data = match settings.embed_image_max_size {
+            Some(max_size)
+                if revision.mime_hint.starts_with("image/")
+                    && revision.size_hint as u64 <= max_size as u64 =>
+            {
+                Some(BlobService::get(ctx, &revision.s3_hash).await?)
+            }
+            _ => None,
+        };
+
+        let info = FileInfo {
+            file_name: Cow::Owned(revision.name),
+            file_size: revision.size_hint as u64,
+            file_mime: Cow::Owned(revision.mime_hint),
+            file_url: Cow::Owned(cdn_path.path),
+            uploader_name: uploader,
+            license,
+        };
+
+        Ok(Some(AttachedFile {
+            reference,
+            info,
+            derivatives,
+            data,
+        }))
+    }
+
+    /// Substitutes the contents of included pages into the wikitext.
+    ///
+    /// Regular renders leave includes in place (see `find_includes()`),
+    /// but standalone output such as a printed page has to contain them.
+    /// Included pages may include others in turn, which are followed up to
+    /// `MAXIMUM_INCLUDE_DEPTH` levels deep, beyond which they are left out.
+    pub async fn inline_includes(
+        ctx: &ServiceContext<'_>,
+        site_slug: &str,
+        mut wikitext: String,
+        settings: &WikitextSettings,
+    ) -> Result<String> {
+        for depth in 0.. {
+            let page_refs = find_includes(&wikitext, settings);
+            if page_refs.is_empty() {
+                break;
+            }
+
+            let mut pages = HashMap::new();
+            if depth < MAXIMUM_INCLUDE_DEPTH {
+                for page_ref in page_refs {
+                    if let Entry::Vacant(entry) = pages.entry(page_ref) {
+                        let content =
+                            Self::get_included_page(ctx, site_slug, entry.key()).await?;
+                        entry.insert(content);
+                    }
+                }
+            } else {
+                warn!("Includes nested too deeply, leaving the remaining ones out");
+            }
+
+            wikitext = substitute_includes(&wikitext, settings, &pages);
+        }
+
+        Ok(wikitext)
+    }
+
+    /// Gets the wikitext of an included page, as shown to readers.
+    async fn get_included_page(
+        ctx: &ServiceContext<'_>,
+        site_slug: &str,
+        page_ref: &PageRef<'_>,
+    ) -> Result<Option<String>> {
+        let (site_slug, page_slug) = page_ref.fields_or(site_slug);
+        debug!("Looking up page '{page_slug}' in site '{site_slug}' to include");
+
+        let site = match SiteService::get_optional(ctx, Reference::Slug(cow!(site_slug)))
+            .await?
+        {
+            Some(site) => site,
+            None => return Ok(None),
+        };
+
+        let mut page_slug = str!(page_slug);
+        normalize(&mut page_slug);
+        let page = match PageService::get_optional(
+            ctx,
+            site.site_id,
+            Reference::Slug(cow!(page_slug)),
+        )
+        .await?
+        {
+            Some(page) => page,
+            None => return Ok(None),
+        };
+
+        let revision =
+            StableRevisionService::get_page_revision(ctx, &site, &page).await?;
+        let wikitext = TextService::get(ctx, &revision.wikitext_hash).await?;
+        Ok(Some(wikitext))
+    }
+
+    /// Gets the priority of a render made on behalf of the caller.
+    ///
+    /// Calls made by bots can be retried later, so they are low priority.
+    pub async fn caller_priority(ctx: &ServiceContext<'_>) -> Result<RenderPriority> {
+        let is_bot = match ctx.caller_id() {
+            Some(user_id) => {
+                let user = UserService::get_optional(ctx, Reference::Id(user_id)).await?;
+                matches!(user, Some(user) if user.user_type == UserType::Bot)
+            }
+            None => false,
+        };
+
+        if is_bot {
+            Ok(RenderPriority::Low)
+        } else {
+            Ok(RenderPriority::Normal)
+        }
+    }
+}
+
+/// Decides whether to run a render given how many are already in progress.
+///
+/// When at or over the threshold, low-priority renders are turned away with
+/// a retry hint, so renders people are waiting on stay fast.
+fn admit(
+    limiter: &RenderLimiter,
+    priority: RenderPriority,
+    shed_threshold: usize,
+    retry_after: Duration,
+) -> Result<RenderPermit<'_>> {
+    let (permit, in_flight) = limiter.acquire();
+    if priority == RenderPriority::Low && in_flight >= shed_threshold {
+        warn!(
+            "Shedding low-priority render, {} already in progress",
+            in_flight,
+        );
+
+        return Err(Error::RenderOverloaded {
+            retry_after_secs: retry_after.as_secs(),
+        });
+    }
+
+    Ok(permit)
+}
+
+/// How many levels of nested includes are inlined into standalone output.
+const MAXIMUM_INCLUDE_DEPTH: usize = 5;
+
+/// Gallery sizes which are shown using thumbnails.
+const GALLERY_SIZES: [GallerySize; 4] = [
+    GallerySize::Square,
+    GallerySize::Thumbnail,
+    GallerySize::Small,
+    GallerySize::Medium,
+];
+
+/// Picks which of the configured thumbnail sizes to show at a gallery size.
+///
+/// The configured sizes are in ascending order. Square and thumbnail images
+/// use the smallest, and each larger gallery size uses the next one up,
+/// or the largest there is.
+fn thumbnail_size(sizes: &[u32], size: GallerySize) -> Option<u32> {
+    let index = match size {
+        GallerySize::Square | GallerySize::Thumbnail => 0,
+        GallerySize::Small => 1,
+        GallerySize::Medium => 2,
+        GallerySize::Original => return None,
+    };
+
+    sizes.get(index).or_else(|| sizes.last()).copied()
+}
+
+/// Finds all the pages included by the given wikitext.
+///
+/// Include substitution is not yet performed, but the included
+/// pages are still needed to keep page connections up to date.
+fn find_includes(wikitext: &str, settings: &WikitextSettings) -> Vec<PageRef<'static>> {
+    match ftml::include(wikitext, settings, IncludeCollector, || unreachable!()) {
+        Ok((_, pages)) => pages.iter().map(PageRef::to_owned).collect(),
+        Err(error) => match error {},
+    }
+}
+
+/// Replaces include blocks with the contents of the given pages.
+///
+/// Pages which aren't present, or are `None`, are treated as missing.
+fn substitute_includes(
+    wikitext: &str,
+    settings: &WikitextSettings,
+    pages: &HashMap<PageRef<'static>, Option<String>>,
+) -> String {
+    match ftml::include(wikitext, settings, PageIncluder(pages), || unreachable!()) {
+        Ok((output, _)) => output,
+        Err(error) => match error {},
+    }
+}
+
+/// Includer which fetches nothing, treating every included page as missing.
+#[derive(Debug)]
+struct IncludeCollector;
+
+impl<'t> Includer<'t> for IncludeCollector {
+    type Error = Infallible;
+
+    fn include_pages(
+        &mut self,
+        includes: &[IncludeRef<'t>],
+    ) -> StdResult<Vec<FetchedPage<'t>>, Infallible> {
+        let pages = includes
+            .iter()
+            .map(|include| FetchedPage {
+                page_ref: include.page_ref().clone(),
+                content: None,
+            })
+            .collect();
+
+        Ok(pages)
+    }
+
+    fn no_such_include(
+        &mut self,
+        _page_ref: &PageRef<'t>,
+    ) -> StdResult<Cow<'t, str>, Infallible> {
+        Ok(Cow::Borrowed(""))
+    }
+}
+
+/// Includer which supplies the contents of pages which were fetched earlier.
+#[derive(Debug)]
+struct PageIncluder<'a>(&'a HashMap<PageRef<'static>, Option<String>>);
+
+impl<'t> Includer<'t> for PageIncluder<'_> {
+    type Error = Infallible;
+
+    fn include_pages(
+        &mut self,
+        includes: &[IncludeRef<'t>],
+    ) -> StdResult<Vec<FetchedPage<'t>>, Infallible> {
+        let pages = includes
+            .iter()
+            .map(|include| {
+                let page_ref = include.page_ref();
+                let content = self
+                    .0
+                    .get(&page_ref.to_owned())
+                    .and_then(|content| content.clone())
+                    .map(Cow::Owned);
+
+                FetchedPage {
+                    page_ref: page_ref.clone(),
+                    content,
+                }
+            })
+            .collect();
+
+        Ok(pages)
+    }
+
+    fn no_such_include(
+        &mut self,
+        _page_ref: &PageRef<'t>,
+    ) -> StdResult<Cow<'t, str>, Infallible> {
+        Ok(Cow::Borrowed(""))
+    }
+}
+
+#[test]
+fn thumbnail_sizes() {
+    macro_rules! check {
+        ($sizes:expr, $size:expr, $expected:expr $(,)?) => {{
+            let sizes: &[u32] = &$sizes;
+            assert_eq!(
+                thumbnail_size(sizes, $size),
+                $expected,
+                "Unexpected thumbnail size for {:?} from {:?}",
+                $size,
+                sizes,
+            );
+        }};
+    }
+
+    check!([128, 256, 512], GallerySize::Square, Some(128));
+    check!([128, 256, 512], GallerySize::Thumbnail, Some(128));
+    check!([128, 256, 512], GallerySize::Small, Some(256));
+    check!([128, 256, 512], GallerySize::Medium, Some(512));
+    check!([128, 256, 512], GallerySize::Original, None);
+    check!([200], GallerySize::Medium, Some(200));
+    check!([], GallerySize::Thumbnail, None);
+}
+
+#[test]
+fn shed_low_priority() {
+    let limiter = RenderLimiter::new();
+    let retry_after = Duration::from_secs(5);
+
+    // Under the threshold, all renders run
+    let first = admit(&limiter, RenderPriority::Low, 2, retry_after)
+        .expect("Preview shed under threshold");
+    let _second = admit(&limiter, RenderPriority::Normal, 2, retry_after)
+        .expect("Render shed under threshold");
+
+    // Once saturated, previews are turned away, but other renders still run
+    match admit(&limiter, RenderPriority::Low, 2, retry_after) {
+        Err(Error::RenderOverloaded { retry_after_secs }) => {
+            assert_eq!(retry_after_secs, 5);
+        }
+        Err(error) => panic!("Unexpected error shedding preview: {error}"),
+        Ok(_) => panic!("Preview was not shed while saturated"),
+    }
+
+    let third = admit(&limiter, RenderPriority::Normal, 2, retry_after)
+        .expect("Normal render shed while saturated");
+
+    // Once renders finish, previews run again
+    drop(first);
+    drop(third);
+    admit(&limiter, RenderPriority::Low, 2, retry_after)
+        .expect("Preview shed after renders finished");
+}
+
+#[test]
+fn includes() {
+    let settings = WikitextSettings::from_mode(ftml::settings::WikitextMode::Print);
+    let mut pages = HashMap::new();
+    pages.insert(
+        PageRef::page_only("component:box"),
+        Some(str!("Box of {$color}")),
+    );
+    pages.insert(PageRef::page_only("deleted"), None);
+
+    assert_eq!(
+        substitute_includes(
+            "Before\n[[include-messy component:box color=red]]\nAfter",
+            &settings,
+            &pages,
+        ),
+        "Before\nBox of red\nAfter",
+    );
+    assert_eq!(
+        substitute_includes(
+            "[[include-messy deleted]]\n[[include-messy unknown]]\nText",
+            &settings,
+            &pages,
+        ),
+        "\n\nText",
+    );
 }
@@ -22,11 +22,31 @@ use super::prelude::*;
 use crate::hash::TextHash;
 use time::OffsetDateTime;
 
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Debug, Clone)]
 pub struct RenderOutput {
     pub html_output: HtmlOutput,
     pub errors: Vec<ParseError>,
+
+    /// Pages included via `[[include-messy]]` blocks.
+    pub included_pages: Vec<PageRef<'static>>,
     pub compiled_hash: TextHash,
     pub compiled_at: OffsetDateTime,
     pub compiled_generator: String,
+
+    /// Word count, reading time, and heading outline of the rendered page.
+    pub content_stats: ContentStats,
+}
+
+/// How urgently a render is needed, which decides whether it may be shed.
+///
+/// See `RenderService::render()`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RenderPriority {
+    /// Someone is waiting on this render, such as when saving a page.
+    Normal,
+
+    /// This render can be retried later, such as previews and bot edits.
+    ///
+    /// These are turned away when too many renders are already in progress.
+    Low,
 }
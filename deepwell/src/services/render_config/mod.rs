@@ -0,0 +1,40 @@
+/*
+ * services/render_config/mod.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The render configuration service, for the per-site settings passed to ftml.
+//!
+//! Sites can change how their pages are rendered, such as how strictly wikitext
+//! is parsed or whether content from other websites may be embedded. These are
+//! gathered from the site's settings into a single `RenderSettings`, which is
+//! cached in Redis since it is needed for every render. The cached copy is
+//! cleared whenever anything it was built from changes.
+//!
+//! Callers should get their `WikitextSettings` from here rather than adjusting
+//! the defaults themselves, so all renders for a site agree.
+
+mod prelude {
+    pub use super::super::prelude::*;
+    pub use super::structs::*;
+}
+
+mod service;
+mod structs;
+
+pub use self::service::RenderConfigService;
@@ -0,0 +1,132 @@
+/*
+ * services/render_config/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::services::{CategoryService, CustomBlockService, SiteService};
+use ftml::settings::{WikitextMode, WikitextSettings};
+use redis::AsyncCommands;
+
+#[derive(Debug)]
+pub struct RenderConfigService;
+
+impl RenderConfigService {
+    /// Gets the render settings for a site.
+    ///
+    /// These are read from the cache if present, otherwise they are
+    /// built from the site's settings and then cached.
+    pub async fn get(ctx: &ServiceContext<'_>, site_id: i64) -> Result<RenderSettings> {
+        let cache_key = Self::cache_key(site_id);
+        let mut redis = ctx.redis();
+        let cached: Option<String> = ctx.until_deadline(redis.get(&cache_key)).await?;
+        if let Some(data) = cached {
+            match serde_json::from_str(&data) {
+                Ok(settings) => {
+                    debug!("Found cached render settings for site ID {site_id}");
+                    return Ok(settings);
+                }
+                Err(error) => {
+                    warn!("Cached render settings for site ID {site_id} are invalid, rebuilding: {error}");
+                }
+            }
+        }
+
+        let settings = Self::build(ctx, site_id).await?;
+        let data = serde_json::to_string(&settings)?;
+        let ttl = ctx.config().render_settings_cache_ttl.as_secs() as usize;
+        ctx.until_deadline(redis.set_ex::<_, _, ()>(&cache_key, &data, ttl))
+            .await?;
+
+        Ok(settings)
+    }
+
+    /// Clears the cached render settings for a site.
+    ///
+    /// This must be called whenever anything read in `build()` changes.
+    pub async fn invalidate(ctx: &ServiceContext<'_>, site_id: i64) -> Result<()> {
+        debug!("Clearing cached render settings for site ID {site_id}");
+
+        let mut redis = ctx.redis();
+        ctx.until_deadline(redis.del::<_, ()>(Self::cache_key(site_id)))
+            .await?;
+
+        Ok(())
+    }
+
+    /// Gets the ftml settings for rendering a page in the given category.
+    ///
+    /// Where the category overrides the site's settings, such as
+    /// the parser profile, the category's are used instead.
+    ///
+    /// The site can only restrict what the mode allows, so features
+    /// disabled for safe previews stay disabled.
+    pub async fn wikitext_settings(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        category_slug: &str,
+        mode: WikitextMode,
+    ) -> Result<WikitextSettings> {
+        let render_settings = Self::get(ctx, site_id).await?;
+        let mut settings = WikitextSettings::from_mode(mode);
+        render_settings.apply(&mut settings);
+
+        let category =
+            CategoryService::get_optional(ctx, site_id, Reference::from(category_slug))
+                .await?;
+
+        if let Some(category) = category {
+            if let Some(profile) = category.parser_profile {
+                settings.parser_profile = profile.into();
+            }
+
+            if let Some(state) = category.rating_module {
+                settings.rating_module = state.into();
+            }
+
+            if let Some(state) = category.comments_module {
+                settings.comments_module = state.into();
+            }
+        }
+
+        Ok(settings)
+    }
+
+    /// Builds the render settings for a site from its current settings.
+    async fn build(ctx: &ServiceContext<'_>, site_id: i64) -> Result<RenderSettings> {
+        info!("Building render settings for site ID {site_id}");
+
+        let site = SiteService::get(ctx, Reference::Id(site_id)).await?;
+        let custom_blocks = CustomBlockService::settings(ctx, site_id).await?;
+
+        Ok(RenderSettings {
+            parser_profile: site.parser_profile,
+            heading_id_strategy: site.heading_id_strategy,
+            allow_embeds: site.allow_embeds,
+            allow_css: site.allow_css,
+            allow_external_images: site.allow_external_images,
+            rating_module: site.rating_module,
+            comments_module: site.comments_module,
+            custom_blocks,
+        })
+    }
+
+    fn cache_key(site_id: i64) -> String {
+        format!("render-settings:{site_id}")
+    }
+}
@@ -0,0 +1,107 @@
+/*
+ * services/render_config/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::models::sea_orm_active_enums::{
+    HeadingIdStrategy, ModuleState, ParserProfile,
+};
+use ftml::settings::{
+    CustomBlockSettings, HeadingIdStrategy as FtmlHeadingIdStrategy,
+    ModuleState as FtmlModuleState, ParserProfile as FtmlParserProfile, WikitextSettings,
+};
+
+/// The settings a site has chosen for how its pages are rendered.
+///
+/// See `RenderConfigService::get()`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RenderSettings {
+    /// How leniently wikitext is parsed.
+    ///
+    /// Categories may override this, see `RenderConfigService::wikitext_settings()`.
+    pub parser_profile: ParserProfile,
+
+    /// How the IDs of headings are generated.
+    pub heading_id_strategy: HeadingIdStrategy,
+
+    /// Whether content from other websites may be embedded.
+    pub allow_embeds: bool,
+
+    /// Whether user-provided CSS is permitted.
+    pub allow_css: bool,
+
+    /// Whether images may be loaded from other websites.
+    pub allow_external_images: bool,
+
+    /// Whether the rating module is shown.
+    ///
+    /// Like the parser profile, categories may override this.
+    pub rating_module: ModuleState,
+
+    /// Whether the comments module is shown.
+    pub comments_module: ModuleState,
+
+    /// The blocks the site has registered in addition to the built-in ones.
+    pub custom_blocks: CustomBlockSettings,
+}
+
+impl RenderSettings {
+    /// Applies these settings on top of the defaults for a mode.
+    ///
+    /// A site can only restrict content, so if the mode already forbids
+    /// something (for instance, embeds), it stays forbidden.
+    pub fn apply(&self, settings: &mut WikitextSettings) {
+        settings.parser_profile = self.parser_profile.into();
+        settings.heading_id_strategy = self.heading_id_strategy.into();
+        settings.allow_embeds &= self.allow_embeds;
+        settings.allow_css &= self.allow_css;
+        settings.allow_external_images &= self.allow_external_images;
+        settings.rating_module = self.rating_module.into();
+        settings.comments_module = self.comments_module.into();
+        settings.custom_blocks = self.custom_blocks.clone();
+    }
+}
+
+impl From<ParserProfile> for FtmlParserProfile {
+    fn from(profile: ParserProfile) -> FtmlParserProfile {
+        match profile {
+            ParserProfile::Standard => FtmlParserProfile::Standard,
+            ParserProfile::Compatibility => FtmlParserProfile::Compatibility,
+            ParserProfile::Strict => FtmlParserProfile::Strict,
+        }
+    }
+}
+
+impl From<ModuleState> for FtmlModuleState {
+    fn from(state: ModuleState) -> FtmlModuleState {
+        match state {
+            ModuleState::Enabled => FtmlModuleState::Enabled,
+            ModuleState::Placeholder => FtmlModuleState::Placeholder,
+            ModuleState::Hidden => FtmlModuleState::Hidden,
+        }
+    }
+}
+
+impl From<HeadingIdStrategy> for FtmlHeadingIdStrategy {
+    fn from(strategy: HeadingIdStrategy) -> FtmlHeadingIdStrategy {
+        match strategy {
+            HeadingIdStrategy::Suffix => FtmlHeadingIdStrategy::Suffix,
+            HeadingIdStrategy::Hash => FtmlHeadingIdStrategy::Hash,
+        }
+    }
+}
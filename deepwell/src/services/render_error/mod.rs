@@ -0,0 +1,41 @@
+/*
+ * services/render_error/mod.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The render error service, which tracks pages whose latest render had problems.
+//!
+//! The parser never fails outright, but it does produce diagnostics for syntax
+//! it could not make sense of. Each is given a `RenderSeverity`, and if any
+//! rendering a page are at or above the configured threshold, they are recorded
+//! against the page until it next renders cleanly.
+//!
+//! When a page first starts having errors, its watchers and the site user can
+//! optionally be sent a message about it. This is done in a job, so that a
+//! failure to send does not affect the edit which caused the render.
+
+mod prelude {
+    pub use super::super::prelude::*;
+    pub use super::structs::*;
+}
+
+mod service;
+mod structs;
+
+pub use self::service::RenderErrorService;
+pub use self::structs::*;
@@ -0,0 +1,351 @@
+/*
+ * services/render_error/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::constants::SYSTEM_USER_ID;
+use crate::models::page::{self, Entity as Page, Model as PageModel};
+use crate::models::page_render_error::{
+    self, Entity as PageRenderError, Model as PageRenderErrorModel,
+};
+use crate::models::site::Model as SiteModel;
+use crate::services::job::{Job, JobService};
+use crate::services::message::CreateMessageDraft;
+use crate::services::relation::{RelationDirection, RelationObject, RelationType};
+use crate::services::{
+    DomainService, MessageService, PageService, RelationService, SiteService, UserService,
+};
+use crate::web::RenderSeverity;
+use fluent::{FluentArgs, FluentValue};
+use ftml::parsing::ParseError;
+use sea_orm::TransactionTrait;
+use unic_langid::LanguageIdentifier;
+
+/// The maximum number of pages returned in one listing.
+const MAXIMUM_LISTING_LIMIT: u64 = 100;
+
+#[derive(Debug)]
+pub struct RenderErrorService;
+
+impl RenderErrorService {
+    /// Records the diagnostics from a page's latest render.
+    ///
+    /// If none are at or above the configured threshold, then any
+    /// previously recorded errors for the page are cleared.
+    pub async fn record(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        page_id: i64,
+        errors: &[ParseError],
+    ) -> Result<()> {
+        let config = ctx.config();
+        let txn = ctx.transaction();
+        let diagnostics: Vec<RenderDiagnostic> = errors
+            .iter()
+            .map(|error| RenderDiagnostic {
+                severity: RenderSeverity::of(error.kind()),
+                error: error.clone(),
+            })
+            .filter(|diagnostic| diagnostic.severity >= config.render_error_threshold)
+            .collect();
+
+        let existing = PageRenderError::find_by_id(page_id).one(txn).await?;
+        if diagnostics.is_empty() {
+            if existing.is_some() {
+                info!("Page ID {page_id} now renders cleanly, clearing render errors");
+                PageRenderError::delete_by_id(page_id).exec(txn).await?;
+            }
+
+            return Ok(());
+        }
+
+        let count = |severity| {
+            let count = diagnostics
+                .iter()
+                .filter(|diagnostic| diagnostic.severity == severity)
+                .count();
+
+            i32::try_from(count).unwrap_or(i32::MAX)
+        };
+
+        let model = page_render_error::ActiveModel {
+            page_id: Set(page_id),
+            site_id: Set(site_id),
+            rendered_at: Set(now()),
+            error_count: Set(count(RenderSeverity::Error)),
+            warning_count: Set(count(RenderSeverity::Warning)),
+            notice_count: Set(count(RenderSeverity::Notice)),
+            diagnostics: Set(serde_json::to_value(&diagnostics)?),
+            ..Default::default()
+        };
+
+        debug!(
+            "Recording {} render diagnostics for page ID {page_id} in site ID {site_id}",
+            diagnostics.len(),
+        );
+
+        if existing.is_some() {
+            model.update(txn).await?;
+        } else {
+            model.insert(txn).await?;
+
+            // Only notify when a page starts having errors,
+            // not on every render while they remain.
+            if config.render_error_notify_watchers || config.render_error_notify_site {
+                JobService::queue_job(
+                    ctx,
+                    &Job::NotifyRenderErrors { site_id, page_id },
+                    None,
+                )
+                .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub async fn get_page(
+        ctx: &ServiceContext<'_>,
+        GetPageRenderErrors {
+            site_id,
+            page: reference,
+        }: GetPageRenderErrors<'_>,
+    ) -> Result<Option<PageRenderErrors>> {
+        let page = PageService::get(ctx, site_id, reference).await?;
+        let txn = ctx.transaction();
+        let output = match PageRenderError::find_by_id(page.page_id).one(txn).await? {
+            Some(model) => Some(build_output(model, page)?),
+            None => None,
+        };
+
+        Ok(output)
+    }
+
+    /// Lists the pages in a site whose latest render had errors.
+    pub async fn get_site(
+        ctx: &ServiceContext<'_>,
+        GetSiteRenderErrors {
+            site_id,
+            minimum_severity,
+            after_page_id,
+            limit,
+        }: GetSiteRenderErrors,
+    ) -> Result<Vec<PageRenderErrors>> {
+        info!("Getting pages with render errors for site ID {site_id}");
+
+        let mut condition = Condition::all()
+            .add(page_render_error::Column::SiteId.eq(site_id))
+            .add(page::Column::DeletedAt.is_null());
+
+        if let Some(after_page_id) = after_page_id {
+            condition =
+                condition.add(page_render_error::Column::PageId.gt(after_page_id));
+        }
+
+        match minimum_severity {
+            None | Some(RenderSeverity::Notice) => (),
+            Some(RenderSeverity::Warning) => {
+                condition = condition.add(
+                    Condition::any()
+                        .add(page_render_error::Column::ErrorCount.gt(0))
+                        .add(page_render_error::Column::WarningCount.gt(0)),
+                );
+            }
+            Some(RenderSeverity::Error) => {
+                condition = condition.add(page_render_error::Column::ErrorCount.gt(0));
+            }
+        }
+
+        let txn = ctx.transaction();
+        let rows = PageRenderError::find()
+            .find_also_related(Page)
+            .filter(condition)
+            .order_by_asc(page_render_error::Column::PageId)
+            .limit(limit.min(MAXIMUM_LISTING_LIMIT))
+            .all(txn)
+            .await?;
+
+        let mut output = Vec::with_capacity(rows.len());
+        for (model, page) in rows {
+            // Always present, since pages are joined on a foreign key
+            if let Some(page) = page {
+                output.push(build_output(model, page)?);
+            }
+        }
+
+        Ok(output)
+    }
+
+    /// Sends a message about a page's render errors to the users who should know.
+    ///
+    /// Failing to send to any one recipient is logged rather than returned,
+    /// since the notice is a courtesy and the other recipients should still get it.
+    pub async fn notify(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        page_id: i64,
+    ) -> Result<()> {
+        let config = ctx.config();
+        let txn = ctx.transaction();
+        let errors = match PageRenderError::find_by_id(page_id).one(txn).await? {
+            Some(errors) => errors,
+            None => {
+                debug!("Page ID {page_id} was fixed before its render errors were sent");
+                return Ok(());
+            }
+        };
+
+        let page = match PageService::get_direct_optional(ctx, page_id, false).await? {
+            Some(page) => page,
+            None => return Ok(()),
+        };
+
+        let site = SiteService::get(ctx, Reference::Id(site_id)).await?;
+        let mut recipients = Vec::new();
+
+        if config.render_error_notify_watchers {
+            let watchers = RelationService::get_entries(
+                ctx,
+                RelationType::PageWatch,
+                RelationObject::Page(page_id),
+                RelationDirection::Dest,
+            )
+            .await?;
+
+            recipients.extend(watchers.into_iter().map(|relation| relation.from_id));
+        }
+
+        if config.render_error_notify_site {
+            let site_users = RelationService::get_entries(
+                ctx,
+                RelationType::SiteUser,
+                RelationObject::Site(site_id),
+                RelationDirection::Dest,
+            )
+            .await?;
+
+            recipients.extend(site_users.into_iter().map(|relation| relation.from_id));
+        }
+
+        recipients.sort_unstable();
+        recipients.dedup();
+
+        info!(
+            "Notifying {} users of render errors in page ID {page_id}",
+            recipients.len(),
+        );
+
+        // Each is sent within a savepoint, since a failed query would otherwise
+        // abort the whole transaction, and so the notices to everyone else.
+        for user_id in recipients {
+            let savepoint = ctx.transaction().begin().await?;
+            let result = Self::send_notice(
+                &ctx.with_transaction(&savepoint),
+                user_id,
+                &site,
+                &page,
+                &errors,
+            )
+            .await;
+
+            match result {
+                Ok(()) => savepoint.commit().await?,
+                Err(error) => {
+                    error!(
+                        "Unable to send render error notice to user ID {user_id}: {error}"
+                    );
+                    savepoint.rollback().await?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn send_notice(
+        ctx: &ServiceContext<'_>,
+        user_id: i64,
+        site: &SiteModel,
+        page: &PageModel,
+        errors: &PageRenderErrorModel,
+    ) -> Result<()> {
+        let user = UserService::get(ctx, Reference::Id(user_id)).await?;
+        let locale = user.locales.first().map(String::as_str).unwrap_or("en");
+        let locales = [locale.parse::<LanguageIdentifier>()?];
+
+        let domain = DomainService::domain_for_site(ctx.config(), site);
+        let url = format!("https://{domain}/{}", page.slug);
+        let mut args = FluentArgs::new();
+        args.set("page", fluent_str!(page.slug));
+        args.set("site", fluent_str!(site.name));
+        args.set("url", fluent_str!(url));
+        args.set("errors", errors.error_count);
+        args.set("warnings", errors.warning_count);
+
+        let localization = ctx.localization();
+        let subject =
+            localization.translate(&locales, "render-error-notice-subject", &args)?;
+        let wikitext =
+            localization.translate(&locales, "render-error-notice-body", &args)?;
+
+        let draft = MessageService::create_draft(
+            ctx,
+            CreateMessageDraft {
+                user_id: SYSTEM_USER_ID,
+                recipients: vec![user_id],
+                carbon_copy: vec![],
+                blind_carbon_copy: vec![],
+                locale: str!(locale),
+                subject: subject.to_string(),
+                wikitext: wikitext.to_string(),
+                reply_to: None,
+                forwarded_from: None,
+            },
+        )
+        .await?;
+
+        MessageService::send(ctx, &draft.external_id).await?;
+        Ok(())
+    }
+}
+
+fn build_output(
+    PageRenderErrorModel {
+        page_id,
+        first_failed_at,
+        rendered_at,
+        error_count,
+        warning_count,
+        notice_count,
+        diagnostics,
+        ..
+    }: PageRenderErrorModel,
+    page: PageModel,
+) -> Result<PageRenderErrors> {
+    Ok(PageRenderErrors {
+        page_id,
+        slug: page.slug,
+        first_failed_at,
+        rendered_at,
+        error_count,
+        warning_count,
+        notice_count,
+        diagnostics: serde_json::from_value(diagnostics)?,
+    })
+}
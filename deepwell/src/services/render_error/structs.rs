@@ -0,0 +1,64 @@
+/*
+ * services/render_error/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::web::{Reference, RenderSeverity};
+use ftml::parsing::ParseError;
+use time::OffsetDateTime;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RenderDiagnostic {
+    pub severity: RenderSeverity,
+
+    #[serde(flatten)]
+    pub error: ParseError,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetPageRenderErrors<'a> {
+    pub site_id: i64,
+    pub page: Reference<'a>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetSiteRenderErrors {
+    pub site_id: i64,
+
+    /// If set, only pages with diagnostics at least this severe are returned.
+    #[serde(default)]
+    pub minimum_severity: Option<RenderSeverity>,
+
+    /// If set, only pages with a greater page ID are returned.
+    #[serde(default)]
+    pub after_page_id: Option<i64>,
+
+    pub limit: u64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct PageRenderErrors {
+    pub page_id: i64,
+    pub slug: String,
+    pub first_failed_at: OffsetDateTime,
+    pub rendered_at: OffsetDateTime,
+    pub error_count: i32,
+    pub warning_count: i32,
+    pub notice_count: i32,
+    pub diagnostics: Vec<RenderDiagnostic>,
+}
@@ -0,0 +1,263 @@
+/*
+ * services/revision_comparison/diff.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use similar::{ChangeTag, TextDiff};
+
+/// How many unchanged lines to include around each change.
+const CONTEXT_LINES: usize = 3;
+
+/// A contiguous section of changed lines, with surrounding context.
+///
+/// Line numbers start at 1, as in unified diffs.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct DiffHunk {
+    pub from_line: usize,
+    pub from_count: usize,
+    pub to_line: usize,
+    pub to_count: usize,
+    pub lines: Vec<DiffLine>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct DiffLine {
+    pub kind: DiffLineKind,
+    pub text: String,
+
+    /// The parts of a changed line, marking which words were changed.
+    ///
+    /// Only present when word-level refinement was requested, and
+    /// the line was modified rather than entirely added or removed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub words: Option<Vec<DiffSpan>>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct DiffSpan {
+    pub changed: bool,
+    pub text: String,
+}
+
+#[derive(Serialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffLineKind {
+    Equal,
+    Insert,
+    Delete,
+}
+
+impl From<ChangeTag> for DiffLineKind {
+    fn from(tag: ChangeTag) -> Self {
+        match tag {
+            ChangeTag::Equal => DiffLineKind::Equal,
+            ChangeTag::Insert => DiffLineKind::Insert,
+            ChangeTag::Delete => DiffLineKind::Delete,
+        }
+    }
+}
+
+/// Produces a line diff between two texts, grouped into hunks.
+#[inline]
+pub fn diff_lines(from: &str, to: &str) -> Vec<DiffHunk> {
+    build_hunks(from, to, false)
+}
+
+/// Like `diff_lines()`, but also marks which words changed within modified lines.
+#[inline]
+pub fn diff_lines_refined(from: &str, to: &str) -> Vec<DiffHunk> {
+    build_hunks(from, to, true)
+}
+
+fn build_hunks(from: &str, to: &str, refine: bool) -> Vec<DiffHunk> {
+    let diff = TextDiff::from_lines(from, to);
+    let mut hunks = Vec::new();
+
+    for group in diff.grouped_ops(CONTEXT_LINES) {
+        let (first, last) = match (group.first(), group.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => continue,
+        };
+
+        let from_range = first.old_range().start..last.old_range().end;
+        let to_range = first.new_range().start..last.new_range().end;
+        let lines = if refine {
+            group
+                .iter()
+                .flat_map(|op| diff.iter_inline_changes(op))
+                .map(|change| {
+                    let spans = change
+                        .iter_strings_lossy()
+                        .map(|(changed, text)| DiffSpan {
+                            changed,
+                            text: text.into_owned(),
+                        })
+                        .collect::<Vec<_>>();
+
+                    refined_line(DiffLineKind::from(change.tag()), spans)
+                })
+                .collect()
+        } else {
+            group
+                .iter()
+                .flat_map(|op| diff.iter_changes(op))
+                .map(|change| DiffLine {
+                    kind: DiffLineKind::from(change.tag()),
+                    text: str!(trim_line_ending(change.value())),
+                    words: None,
+                })
+                .collect()
+        };
+
+        hunks.push(DiffHunk {
+            from_line: from_range.start + 1,
+            from_count: from_range.len(),
+            to_line: to_range.start + 1,
+            to_count: to_range.len(),
+            lines,
+        });
+    }
+
+    hunks
+}
+
+fn refined_line(kind: DiffLineKind, mut spans: Vec<DiffSpan>) -> DiffLine {
+    // The line ending is never part of the change
+    if let Some(last) = spans.last_mut() {
+        last.text = str!(trim_line_ending(&last.text));
+    }
+    spans.retain(|span| !span.text.is_empty());
+
+    let text = spans.iter().map(|span| span.text.as_str()).collect();
+    let words = if spans.iter().any(|span| span.changed) {
+        Some(spans)
+    } else {
+        None
+    };
+
+    DiffLine { kind, text, words }
+}
+
+#[inline]
+fn trim_line_ending(line: &str) -> &str {
+    line.trim_end_matches(['\n', '\r'])
+}
+
+#[test]
+fn diff() {
+    macro_rules! line {
+        ($kind:ident, $text:expr $(,)?) => {
+            DiffLine {
+                kind: DiffLineKind::$kind,
+                text: str!($text),
+                words: None,
+            }
+        };
+    }
+
+    assert!(diff_lines("", "").is_empty());
+    assert!(diff_lines("apple\nbanana\n", "apple\nbanana\n").is_empty());
+
+    assert_eq!(
+        diff_lines("apple\nbanana\ncherry\n", "apple\nblueberry\ncherry\n"),
+        vec![DiffHunk {
+            from_line: 1,
+            from_count: 3,
+            to_line: 1,
+            to_count: 3,
+            lines: vec![
+                line!(Equal, "apple"),
+                line!(Delete, "banana"),
+                line!(Insert, "blueberry"),
+                line!(Equal, "cherry"),
+            ],
+        }],
+    );
+
+    assert_eq!(
+        diff_lines("", "first line"),
+        vec![DiffHunk {
+            from_line: 1,
+            from_count: 0,
+            to_line: 1,
+            to_count: 1,
+            lines: vec![line!(Insert, "first line")],
+        }],
+    );
+
+    // Distant changes are split into separate hunks
+    let from = (1..=20).map(|n| format!("{n}\n")).collect::<String>();
+    let to = (1..=20)
+        .map(|n| match n {
+            2 => str!("two\n"),
+            19 => str!("nineteen\n"),
+            _ => format!("{n}\n"),
+        })
+        .collect::<String>();
+    let hunks = diff_lines(&from, &to);
+    assert_eq!(hunks.len(), 2);
+    assert_eq!(hunks[0].from_line, 1);
+    assert_eq!(hunks[1].from_line, 16);
+}
+
+#[test]
+fn diff_refined() {
+    macro_rules! span {
+        ($changed:expr, $text:expr $(,)?) => {
+            DiffSpan {
+                changed: $changed,
+                text: str!($text),
+            }
+        };
+    }
+
+    let hunks = diff_lines_refined(
+        "apple\nthe quick fox\ncherry\n",
+        "apple\nthe slow fox\ncherry\ndurian\n",
+    );
+    assert_eq!(hunks.len(), 1);
+
+    let lines = &hunks[0].lines;
+    assert_eq!(lines.len(), 5);
+    assert_eq!(lines[0].text, "apple");
+    assert_eq!(lines[0].words, None);
+    assert_eq!(lines[1].kind, DiffLineKind::Delete);
+    assert_eq!(lines[1].text, "the quick fox");
+    assert_eq!(
+        lines[1].words,
+        Some(vec![
+            span!(false, "the "),
+            span!(true, "quick"),
+            span!(false, " fox"),
+        ]),
+    );
+    assert_eq!(lines[2].kind, DiffLineKind::Insert);
+    assert_eq!(
+        lines[2].words,
+        Some(vec![
+            span!(false, "the "),
+            span!(true, "slow"),
+            span!(false, " fox"),
+        ]),
+    );
+
+    // Entirely new lines aren't broken down
+    assert_eq!(lines[4].kind, DiffLineKind::Insert);
+    assert_eq!(lines[4].text, "durian");
+    assert_eq!(lines[4].words, None);
+}
@@ -0,0 +1,45 @@
+/*
+ * services/revision_comparison/mod.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The revision comparison service, for permalinks to page diffs.
+//!
+//! A comparison is between two revisions of the same page, and covers both
+//! the metadata which changed (title, slug, tags, etc.) and a line diff of the
+//! wikitext. Each pair of revisions is assigned one token, which is what gets
+//! linked to.
+//!
+//! The comparison document is built on request rather than stored, but since
+//! revisions never change, it comes out the same every time. The only exception
+//! is if a field is hidden on one of the revisions after the fact, in which
+//! case it is dropped from the comparison, same as elsewhere.
+
+mod prelude {
+    pub use super::super::prelude::*;
+    pub use super::diff::*;
+    pub use super::structs::*;
+}
+
+mod diff;
+mod service;
+mod structs;
+
+pub use self::diff::*;
+pub use self::service::RevisionComparisonService;
+pub use self::structs::*;
@@ -0,0 +1,181 @@
+/*
+ * services/revision_comparison/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::page::Model as PageModel;
+use crate::models::page_revision::Model as PageRevisionModel;
+use crate::models::revision_comparison::{
+    self, Entity as RevisionComparisonEntity, Model as RevisionComparisonModel,
+};
+use crate::services::{PageRevisionService, PageService, TextService};
+use cuid2::cuid;
+use serde_json::json;
+
+/// The current version of the comparison document format.
+///
+/// Increment this whenever the structure of `RevisionComparison` changes,
+/// so anything caching comparisons knows to discard old ones.
+pub const COMPARISON_FORMAT: u32 = 1;
+
+#[derive(Debug)]
+pub struct RevisionComparisonService;
+
+impl RevisionComparisonService {
+    /// Compares two revisions of a page, creating a permalink token if one doesn't exist.
+    pub async fn get_or_create(
+        ctx: &ServiceContext<'_>,
+        CreateRevisionComparison {
+            site_id,
+            page: reference,
+            from_revision_number,
+            to_revision_number,
+        }: CreateRevisionComparison<'_>,
+    ) -> Result<RevisionComparison> {
+        if from_revision_number >= to_revision_number {
+            error!(
+                "Cannot compare revision {from_revision_number} to {to_revision_number}, not in order",
+            );
+            return Err(Error::RevisionComparisonInvalid);
+        }
+
+        let txn = ctx.transaction();
+        let PageModel { page_id, .. } = PageService::get(ctx, site_id, reference).await?;
+        let (from, to) = try_join!(
+            PageRevisionService::get(ctx, site_id, page_id, from_revision_number),
+            PageRevisionService::get(ctx, site_id, page_id, to_revision_number),
+        )?;
+
+        info!(
+            "Getting or creating comparison for page ID {page_id}, revision IDs {} and {}",
+            from.revision_id, to.revision_id,
+        );
+
+        let existing = RevisionComparisonEntity::find()
+            .filter(
+                Condition::all()
+                    .add(revision_comparison::Column::FromRevisionId.eq(from.revision_id))
+                    .add(revision_comparison::Column::ToRevisionId.eq(to.revision_id)),
+            )
+            .one(txn)
+            .await?;
+
+        let token = match existing {
+            Some(comparison) => comparison.token,
+            None => {
+                let model = revision_comparison::ActiveModel {
+                    token: Set(cuid()),
+                    site_id: Set(site_id),
+                    page_id: Set(page_id),
+                    from_revision_id: Set(from.revision_id),
+                    to_revision_id: Set(to.revision_id),
+                    ..Default::default()
+                };
+
+                model.insert(txn).await?.token
+            }
+        };
+
+        Self::build(ctx, token, from, to).await
+    }
+
+    pub async fn get_optional(
+        ctx: &ServiceContext<'_>,
+        token: &str,
+    ) -> Result<Option<RevisionComparison>> {
+        let txn = ctx.transaction();
+        let comparison = RevisionComparisonEntity::find_by_id(token).one(txn).await?;
+
+        match comparison {
+            None => Ok(None),
+            Some(RevisionComparisonModel {
+                token,
+                from_revision_id,
+                to_revision_id,
+                ..
+            }) => {
+                let (from, to) = try_join!(
+                    PageRevisionService::get_direct(ctx, from_revision_id),
+                    PageRevisionService::get_direct(ctx, to_revision_id),
+                )?;
+
+                let comparison = Self::build(ctx, token, from, to).await?;
+                Ok(Some(comparison))
+            }
+        }
+    }
+
+    /// Builds the comparison document for two revisions.
+    async fn build(
+        ctx: &ServiceContext<'_>,
+        token: String,
+        from: PageRevisionModel,
+        to: PageRevisionModel,
+    ) -> Result<RevisionComparison> {
+        let is_hidden = |field: &str| {
+            from.hidden.iter().any(|hidden| hidden == field)
+                || to.hidden.iter().any(|hidden| hidden == field)
+        };
+
+        // Compare metadata
+        let mut metadata = Vec::new();
+
+        macro_rules! compare {
+            ($field:ident, $name:expr $(,)?) => {
+                if !is_hidden($name) && from.$field != to.$field {
+                    metadata.push(MetadataDelta {
+                        field: $name,
+                        from: json!(from.$field),
+                        to: json!(to.$field),
+                    });
+                }
+            };
+        }
+
+        compare!(title, "title");
+        compare!(alt_title, "alt_title");
+        compare!(slug, "slug");
+        compare!(tags, "tags");
+
+        // Compare wikitext
+        let wikitext = if is_hidden("wikitext") {
+            None
+        } else if from.wikitext_hash == to.wikitext_hash {
+            Some(Vec::new())
+        } else {
+            let (from_wikitext, to_wikitext) = try_join!(
+                TextService::get(ctx, &from.wikitext_hash),
+                TextService::get(ctx, &to.wikitext_hash),
+            )?;
+
+            Some(diff_lines(&from_wikitext, &to_wikitext))
+        };
+
+        Ok(RevisionComparison {
+            format: COMPARISON_FORMAT,
+            token,
+            site_id: to.site_id,
+            page_id: to.page_id,
+            from: ComparedRevision::from(from),
+            to: ComparedRevision::from(to),
+            metadata,
+            wikitext,
+        })
+    }
+}
@@ -0,0 +1,86 @@
+/*
+ * services/revision_comparison/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::diff::DiffHunk;
+use crate::models::page_revision::Model as PageRevisionModel;
+use crate::models::sea_orm_active_enums::PageRevisionType;
+use crate::web::Reference;
+use time::OffsetDateTime;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CreateRevisionComparison<'a> {
+    pub site_id: i64,
+    pub page: Reference<'a>,
+    pub from_revision_number: i64,
+    pub to_revision_number: i64,
+}
+
+/// A comparison between two revisions of a page.
+///
+/// Fields are serialized in declaration order, and nothing in here depends
+/// on when it was generated, so the JSON output is stable and can be cached.
+#[derive(Serialize, Debug, Clone)]
+pub struct RevisionComparison {
+    /// The version of this document format.
+    pub format: u32,
+    pub token: String,
+    pub site_id: i64,
+    pub page_id: i64,
+    pub from: ComparedRevision,
+    pub to: ComparedRevision,
+
+    /// Metadata fields which differ between the two revisions.
+    ///
+    /// Fields hidden on either revision are never included.
+    pub metadata: Vec<MetadataDelta>,
+
+    /// Diff of the wikitext, as hunks of changed lines with some context.
+    ///
+    /// If the wikitext is hidden on either revision, this is `None`.
+    pub wikitext: Option<Vec<DiffHunk>>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ComparedRevision {
+    pub revision_id: i64,
+    pub revision_number: i64,
+    pub revision_type: PageRevisionType,
+    pub created_at: OffsetDateTime,
+    pub user_id: i64,
+}
+
+impl From<PageRevisionModel> for ComparedRevision {
+    fn from(revision: PageRevisionModel) -> Self {
+        ComparedRevision {
+            revision_id: revision.revision_id,
+            revision_number: revision.revision_number,
+            revision_type: revision.revision_type,
+            created_at: revision.created_at,
+            user_id: revision.user_id,
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct MetadataDelta {
+    pub field: &'static str,
+    pub from: serde_json::Value,
+    pub to: serde_json::Value,
+}
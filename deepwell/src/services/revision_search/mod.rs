@@ -0,0 +1,37 @@
+/*
+ * services/revision_search/mod.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The revision search service, for finding edits by their comments.
+//!
+//! This searches the edit summaries of page and file revisions within a site,
+//! so that moderators can find every edit mentioning some phrase or ticket
+//! number. Queries can match a substring, ignoring case, or use PostgreSQL
+//! full-text search. Comments hidden by staff are never matched.
+
+mod prelude {
+    pub use super::super::prelude::*;
+    pub use super::structs::*;
+}
+
+mod service;
+mod structs;
+
+pub use self::service::RevisionSearchService;
+pub use self::structs::*;
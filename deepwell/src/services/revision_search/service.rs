@@ -0,0 +1,128 @@
+/*
+ * services/revision_search/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::utils::escape_like;
+use sea_orm::{DatabaseBackend, FromQueryResult, Statement};
+
+/// How many matching revisions are returned at once.
+const RESULTS_PER_PAGE: u64 = 50;
+
+#[derive(Debug)]
+pub struct RevisionSearchService;
+
+impl RevisionSearchService {
+    pub async fn search(
+        ctx: &ServiceContext<'_>,
+        SearchRevisionComments {
+            site_id,
+            query,
+            mode,
+            kind,
+            offset,
+        }: SearchRevisionComments,
+    ) -> Result<SearchRevisionCommentsOutput> {
+        info!(
+            "Searching revision comments in site ID {site_id} for {query:?} ({mode:?})"
+        );
+
+        let query = query.trim();
+        if query.is_empty() {
+            error!("Revision comment search query is empty");
+            return Err(Error::BadRequest);
+        }
+
+        let (condition, pattern) = match mode {
+            RevisionSearchMode::Substring => {
+                ("comments ILIKE $2", format!("%{}%", escape_like(query)))
+            }
+            RevisionSearchMode::FullText => (
+                "to_tsvector('simple', comments) @@ websearch_to_tsquery('simple', $2)",
+                str!(query),
+            ),
+        };
+
+        let mut selects = Vec::new();
+        if kind != RevisionSearchKind::File {
+            selects.push(format!(
+                r#"
+                SELECT revision_id, revision_number, page_id, NULL::BIGINT AS file_id,
+                       user_id, created_at, comments
+                FROM page_revision
+                WHERE site_id = $1
+                AND NOT ('comments' = ANY(hidden))
+                AND {condition}
+                "#,
+            ));
+        }
+
+        if kind != RevisionSearchKind::Page {
+            selects.push(format!(
+                r#"
+                SELECT revision_id, revision_number, page_id, file_id,
+                       user_id, created_at, comments
+                FROM file_revision
+                WHERE site_id = $1
+                AND NOT ('comments' = ANY(hidden))
+                AND {condition}
+                "#,
+            ));
+        }
+
+        // Get one more than the limit, to see if there's another set after
+        let sql = format!(
+            r#"
+            SELECT * FROM ({}) AS revisions
+            ORDER BY created_at DESC, revision_id DESC
+            LIMIT $3
+            OFFSET $4
+            "#,
+            selects.join("UNION ALL"),
+        );
+
+        let txn = ctx.transaction();
+        let mut revisions =
+            RevisionCommentMatch::find_by_statement(Statement::from_sql_and_values(
+                DatabaseBackend::Postgres,
+                &sql,
+                [
+                    site_id.into(),
+                    pattern.into(),
+                    (RESULTS_PER_PAGE as i64 + 1).into(),
+                    (offset as i64).into(),
+                ],
+            ))
+            .all(txn)
+            .await?;
+
+        let next_offset = if revisions.len() as u64 > RESULTS_PER_PAGE {
+            revisions.truncate(RESULTS_PER_PAGE as usize);
+            Some(offset + RESULTS_PER_PAGE)
+        } else {
+            None
+        };
+
+        debug!("Found {} revisions with matching comments", revisions.len());
+        Ok(SearchRevisionCommentsOutput {
+            revisions,
+            next_offset,
+        })
+    }
+}
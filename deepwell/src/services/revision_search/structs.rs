@@ -0,0 +1,82 @@
+/*
+ * services/revision_search/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use sea_orm::FromQueryResult;
+use time::OffsetDateTime;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SearchRevisionComments {
+    pub site_id: i64,
+    pub query: String,
+
+    #[serde(default)]
+    pub mode: RevisionSearchMode,
+
+    #[serde(default)]
+    pub kind: RevisionSearchKind,
+
+    #[serde(default)]
+    pub offset: u64,
+}
+
+#[derive(Deserialize, Debug, Default, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RevisionSearchMode {
+    /// Matches comments containing the query, ignoring case.
+    #[default]
+    Substring,
+
+    /// Matches comments with PostgreSQL full-text search.
+    ///
+    /// The query uses web search syntax, so quoted phrases,
+    /// `or`, and `-` to exclude words are all supported.
+    FullText,
+}
+
+#[derive(Deserialize, Debug, Default, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RevisionSearchKind {
+    #[default]
+    All,
+    Page,
+    File,
+}
+
+#[derive(Serialize, FromQueryResult, Debug, Clone)]
+pub struct RevisionCommentMatch {
+    pub revision_id: i64,
+    pub revision_number: i64,
+    pub page_id: i64,
+
+    /// Set if this is a file revision, rather than a page revision.
+    pub file_id: Option<i64>,
+    pub user_id: i64,
+    pub created_at: OffsetDateTime,
+    pub comments: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct SearchRevisionCommentsOutput {
+    /// Matching revisions, newest first.
+    pub revisions: Vec<RevisionCommentMatch>,
+
+    /// The offset to request the next set of revisions, if there are more.
+    pub next_offset: Option<u64>,
+}
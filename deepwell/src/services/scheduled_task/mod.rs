@@ -0,0 +1,39 @@
+/*
+ * services/scheduled_task/mod.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Maintenance tasks which site administrators set up to run on a schedule.
+//!
+//! Each task has a cron expression and an action, such as rerendering a page
+//! which lists recent changes. A recurring job looks for tasks which are due
+//! and runs them, which generally means queuing further jobs. The outcome of
+//! every run is kept, so administrators can see what their tasks have done.
+
+mod prelude {
+    pub use super::super::prelude::*;
+    pub use super::schedule::CronSchedule;
+    pub use super::structs::*;
+}
+
+mod schedule;
+mod service;
+mod structs;
+
+pub use self::service::ScheduledTaskService;
+pub use self::structs::*;
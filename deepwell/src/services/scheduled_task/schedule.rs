@@ -0,0 +1,294 @@
+/*
+ * services/scheduled_task/schedule.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Parsing and evaluation of the cron expressions used by scheduled tasks.
+//!
+//! This supports the usual five fields (minute, hour, day of month, month,
+//! and day of week), each of which may be `*`, a number, a range `a-b`, or a
+//! comma-separated list of these, optionally with a `/step`. Times are in UTC.
+//!
+//! Tasks are limited to running at most once an hour, so the minute field must
+//! be a single number. Names for months and weekdays are not supported.
+
+use time::{Date, Duration, OffsetDateTime, PrimitiveDateTime, Time, UtcOffset};
+
+/// How many days ahead to search for the next time a schedule fires.
+///
+/// Some valid schedules may never fire, such as February 30th, so the
+/// search must stop somewhere. Eight years covers any leap day which
+/// falls on a particular weekday.
+const SEARCH_DAYS: u32 = 8 * 366;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    minute: u8,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+
+    // Per cron convention, if both day fields are restricted,
+    // then a day matching either one is permitted.
+    any_day_of_month: bool,
+    any_day_of_week: bool,
+}
+
+impl CronSchedule {
+    /// Parses a cron expression, returning `None` if it is invalid.
+    pub fn parse(expression: &str) -> Option<Self> {
+        let fields = expression.split_whitespace().collect::<Vec<_>>();
+        let [minute, hours, days_of_month, months, days_of_week] = fields[..] else {
+            return None;
+        };
+
+        let minute = minute.parse::<u8>().ok().filter(|&minute| minute < 60)?;
+        let hours = parse_field(hours, 0, 23)?;
+        let days_of_month = parse_field(days_of_month, 1, 31)?;
+        let months = parse_field(months, 1, 12)?;
+
+        // Both 0 and 7 are Sunday
+        let mut days_of_week = parse_field(days_of_week, 0, 7)?;
+        if days_of_week & (1 << 7) != 0 {
+            days_of_week = (days_of_week | 1) & !(1 << 7);
+        }
+
+        Some(CronSchedule {
+            minute,
+            hours,
+            days_of_month,
+            months,
+            days_of_week,
+            any_day_of_month: fields[2] == "*",
+            any_day_of_week: fields[4] == "*",
+        })
+    }
+
+    /// Finds the first time after the given one that this schedule fires.
+    pub fn next_after(&self, after: OffsetDateTime) -> Option<OffsetDateTime> {
+        let after = after.to_offset(UtcOffset::UTC);
+        let start = after
+            .replace_time(Time::from_hms(after.hour(), after.minute(), 0).ok()?)
+            + Duration::minutes(1);
+
+        let mut date = start.date();
+        for day in 0..SEARCH_DAYS {
+            if self.day_matches(date) {
+                let first_hour = if day == 0 { start.hour() } else { 0 };
+                for hour in first_hour..24 {
+                    if !is_set(self.hours, hour) {
+                        continue;
+                    }
+
+                    // On the starting hour, the minute may already have passed
+                    if day == 0 && hour == start.hour() && self.minute < start.minute() {
+                        continue;
+                    }
+
+                    let time = Time::from_hms(hour, self.minute, 0).ok()?;
+                    return Some(PrimitiveDateTime::new(date, time).assume_utc());
+                }
+            }
+
+            date = date.next_day()?;
+        }
+
+        None
+    }
+
+    fn day_matches(&self, date: Date) -> bool {
+        if !is_set(self.months, u8::from(date.month())) {
+            return false;
+        }
+
+        let day_of_month = is_set(self.days_of_month, date.day());
+        let day_of_week =
+            is_set(self.days_of_week, date.weekday().number_days_from_sunday());
+
+        match (self.any_day_of_month, self.any_day_of_week) {
+            (true, true) => true,
+            (true, false) => day_of_week,
+            (false, true) => day_of_month,
+            (false, false) => day_of_month || day_of_week,
+        }
+    }
+}
+
+#[inline]
+fn is_set(bits: u64, value: u8) -> bool {
+    bits & (1 << value) != 0
+}
+
+/// Parses one field into a bitset of the values it permits.
+fn parse_field(field: &str, minimum: u8, maximum: u8) -> Option<u64> {
+    let parse_value = |value: &str| {
+        value
+            .parse::<u8>()
+            .ok()
+            .filter(|value| (minimum..=maximum).contains(value))
+    };
+
+    let mut bits = 0;
+    for item in field.split(',') {
+        let (range, step) = match item.split_once('/') {
+            Some((range, step)) => {
+                (range, step.parse::<u8>().ok().filter(|&step| step > 0)?)
+            }
+            None => (item, 1),
+        };
+
+        let (start, end) = match range {
+            "*" => (minimum, maximum),
+            _ => match range.split_once('-') {
+                Some((start, end)) => (parse_value(start)?, parse_value(end)?),
+                None => {
+                    let value = parse_value(range)?;
+                    (value, value)
+                }
+            },
+        };
+
+        if start > end {
+            return None;
+        }
+
+        for value in (start..=end).step_by(usize::from(step)) {
+            bits |= 1 << value;
+        }
+    }
+
+    Some(bits)
+}
+
+#[test]
+fn parse() {
+    macro_rules! check {
+        ($expression:expr, $valid:expr $(,)?) => {
+            assert_eq!(
+                CronSchedule::parse($expression).is_some(),
+                $valid,
+                "Unexpected result parsing cron expression {:?}",
+                $expression,
+            );
+        };
+    }
+
+    check!("0 * * * *", true);
+    check!("30 4 * * 1-5", true);
+    check!("15 */6 1,15 * 0", true);
+    check!("0 0 * 1-12/3 7", true);
+    check!("0 0 29 2 *", true);
+
+    check!("", false);
+    check!("* * * * *", false);
+    check!("*/5 * * * *", false);
+    check!("0,30 * * * *", false);
+    check!("60 * * * *", false);
+    check!("0 24 * * *", false);
+    check!("0 0 0 * *", false);
+    check!("0 0 * 13 *", false);
+    check!("0 0 * * 8", false);
+    check!("0 5-1 * * *", false);
+    check!("0 */0 * * *", false);
+    check!("0 * * *", false);
+    check!("0 * * * * *", false);
+    check!("0 0 * * MON", false);
+}
+
+#[test]
+fn next_after() {
+    use time::macros::datetime;
+
+    macro_rules! check {
+        ($expression:expr, $after:expr, $expected:expr $(,)?) => {{
+            let schedule = CronSchedule::parse($expression).expect("Invalid expression");
+            assert_eq!(
+                schedule.next_after($after),
+                $expected,
+                "Unexpected next time for cron expression {:?}",
+                $expression,
+            );
+        }};
+    }
+
+    // Hourly
+    check!(
+        "0 * * * *",
+        datetime!(2024-03-10 12:00:00 UTC),
+        Some(datetime!(2024-03-10 13:00:00 UTC)),
+    );
+    check!(
+        "30 * * * *",
+        datetime!(2024-03-10 12:29:59 UTC),
+        Some(datetime!(2024-03-10 12:30:00 UTC)),
+    );
+
+    // Nightly, rolling over to the next day and year
+    check!(
+        "0 3 * * *",
+        datetime!(2024-03-10 04:00:00 UTC),
+        Some(datetime!(2024-03-11 03:00:00 UTC)),
+    );
+    check!(
+        "0 0 * * *",
+        datetime!(2024-12-31 23:59:00 UTC),
+        Some(datetime!(2025-01-01 00:00:00 UTC)),
+    );
+
+    // Weekly, on Sunday (2024-03-10 is a Sunday)
+    check!(
+        "0 0 * * 0",
+        datetime!(2024-03-10 00:00:00 UTC),
+        Some(datetime!(2024-03-17 00:00:00 UTC)),
+    );
+    check!(
+        "0 0 * * 7",
+        datetime!(2024-03-09 12:00:00 UTC),
+        Some(datetime!(2024-03-10 00:00:00 UTC)),
+    );
+
+    // Both day fields, either matches
+    check!(
+        "0 12 15 * 1",
+        datetime!(2024-03-12 00:00:00 UTC),
+        Some(datetime!(2024-03-15 12:00:00 UTC)),
+    );
+    check!(
+        "0 12 15 * 1",
+        datetime!(2024-03-15 13:00:00 UTC),
+        Some(datetime!(2024-03-18 12:00:00 UTC)),
+    );
+
+    // Leap days
+    check!(
+        "0 0 29 2 *",
+        datetime!(2024-03-01 00:00:00 UTC),
+        Some(datetime!(2028-02-29 00:00:00 UTC)),
+    );
+
+    // Other time zones are converted to UTC
+    check!(
+        "0 6 * * *",
+        datetime!(2024-03-10 01:00:00 -5),
+        Some(datetime!(2024-03-11 06:00:00 UTC)),
+    );
+
+    // Never fires
+    check!("0 0 31 2 *", datetime!(2024-01-01 00:00:00 UTC), None);
+}
@@ -0,0 +1,471 @@
+/*
+ * services/scheduled_task/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::page::Model as PageModel;
+use crate::models::scheduled_task::{
+    self, Entity as ScheduledTask, Model as ScheduledTaskModel,
+};
+use crate::models::scheduled_task_run::{
+    self, Entity as ScheduledTaskRun, Model as ScheduledTaskRunModel,
+};
+use crate::services::{CategoryService, JobService, OutdateService, PageService};
+use crate::web::PageOrder;
+use sea_orm::TransactionTrait;
+
+/// The longest name a scheduled task can have, in bytes.
+const MAXIMUM_NAME_LENGTH: usize = 100;
+
+/// How many due tasks are run in one go by the recurring job.
+const RUN_BATCH_SIZE: u64 = 50;
+
+#[derive(Debug)]
+pub struct ScheduledTaskService;
+
+impl ScheduledTaskService {
+    pub async fn create(
+        ctx: &ServiceContext<'_>,
+        CreateScheduledTask {
+            site_id,
+            user_id,
+            name,
+            schedule,
+            action,
+        }: CreateScheduledTask,
+    ) -> Result<ScheduledTaskModel> {
+        info!("Creating scheduled task '{name}' ({schedule}) in site ID {site_id}");
+
+        let config = ctx.config();
+        let txn = ctx.transaction();
+        let count = ScheduledTask::find()
+            .filter(
+                Condition::all()
+                    .add(scheduled_task::Column::SiteId.eq(site_id))
+                    .add(scheduled_task::Column::DeletedAt.is_null()),
+            )
+            .count(txn)
+            .await?;
+
+        if count >= config.scheduled_task_maximum_per_site {
+            error!(
+                "Site ID {site_id} has reached its scheduled task limit ({})",
+                config.scheduled_task_maximum_per_site,
+            );
+            return Err(Error::ScheduledTaskQuotaExceeded);
+        }
+
+        check_name(&name)?;
+        Self::check_name_conflict(ctx, site_id, &name).await?;
+        let next_run_at = parse_schedule(&schedule)?.next_after(now());
+        Self::check_action(ctx, site_id, &action).await?;
+
+        let model = scheduled_task::ActiveModel {
+            site_id: Set(site_id),
+            created_by: Set(user_id),
+            name: Set(name),
+            schedule: Set(schedule),
+            action: Set(serde_json::to_value(&action)?),
+            next_run_at: Set(next_run_at),
+            ..Default::default()
+        };
+
+        let task = model.insert(txn).await?;
+        Ok(task)
+    }
+
+    pub async fn update(
+        ctx: &ServiceContext<'_>,
+        UpdateScheduledTask {
+            site_id,
+            task_id,
+            body:
+                UpdateScheduledTaskBody {
+                    name,
+                    schedule,
+                    action,
+                    enabled,
+                },
+        }: UpdateScheduledTask,
+    ) -> Result<ScheduledTaskModel> {
+        info!("Updating scheduled task ID {task_id} in site ID {site_id}");
+
+        let txn = ctx.transaction();
+        let task = Self::get(ctx, site_id, task_id).await?;
+        let mut model = scheduled_task::ActiveModel {
+            task_id: Set(task_id),
+            updated_at: Set(Some(now())),
+            ..Default::default()
+        };
+
+        if let ProvidedValue::Set(name) = name {
+            check_name(&name)?;
+            if name != task.name {
+                Self::check_name_conflict(ctx, site_id, &name).await?;
+            }
+
+            model.name = Set(name);
+        }
+
+        if let ProvidedValue::Set(action) = action {
+            Self::check_action(ctx, site_id, &action).await?;
+            model.action = Set(serde_json::to_value(&action)?);
+        }
+
+        // Changing the schedule or re-enabling the task means finding the
+        // next time it's due. Runs missed while disabled are not made up.
+        let reschedule = matches!(schedule, ProvidedValue::Set(_))
+            || matches!(enabled, ProvidedValue::Set(true));
+        let schedule = match schedule {
+            ProvidedValue::Set(schedule) => schedule,
+            ProvidedValue::Unset => task.schedule,
+        };
+
+        if reschedule {
+            let next_run_at = parse_schedule(&schedule)?.next_after(now());
+            model.schedule = Set(schedule);
+            model.next_run_at = Set(next_run_at);
+        }
+
+        if let ProvidedValue::Set(enabled) = enabled {
+            model.enabled = Set(enabled);
+        }
+
+        let task = model.update(txn).await?;
+        Ok(task)
+    }
+
+    pub async fn delete(
+        ctx: &ServiceContext<'_>,
+        DeleteScheduledTask { site_id, task_id }: DeleteScheduledTask,
+    ) -> Result<()> {
+        info!("Deleting scheduled task ID {task_id} in site ID {site_id}");
+
+        // Ensure it exists and is in this site
+        Self::get(ctx, site_id, task_id).await?;
+
+        let txn = ctx.transaction();
+        let model = scheduled_task::ActiveModel {
+            task_id: Set(task_id),
+            deleted_at: Set(Some(now())),
+            ..Default::default()
+        };
+        model.update(txn).await?;
+        Ok(())
+    }
+
+    pub async fn get_optional(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        task_id: i64,
+    ) -> Result<Option<ScheduledTaskModel>> {
+        let txn = ctx.transaction();
+        let task = ScheduledTask::find()
+            .filter(
+                Condition::all()
+                    .add(scheduled_task::Column::TaskId.eq(task_id))
+                    .add(scheduled_task::Column::SiteId.eq(site_id))
+                    .add(scheduled_task::Column::DeletedAt.is_null()),
+            )
+            .one(txn)
+            .await?;
+
+        Ok(task)
+    }
+
+    #[inline]
+    pub async fn get(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        task_id: i64,
+    ) -> Result<ScheduledTaskModel> {
+        find_or_error!(Self::get_optional(ctx, site_id, task_id), ScheduledTask)
+    }
+
+    /// Gets all the scheduled tasks for a site.
+    pub async fn get_all(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+    ) -> Result<Vec<ScheduledTaskModel>> {
+        let txn = ctx.transaction();
+        let tasks = ScheduledTask::find()
+            .filter(
+                Condition::all()
+                    .add(scheduled_task::Column::SiteId.eq(site_id))
+                    .add(scheduled_task::Column::DeletedAt.is_null()),
+            )
+            .order_by_asc(scheduled_task::Column::TaskId)
+            .all(txn)
+            .await?;
+
+        Ok(tasks)
+    }
+
+    /// Gets the most recent runs of a scheduled task, newest first.
+    pub async fn get_runs(
+        ctx: &ServiceContext<'_>,
+        GetScheduledTaskRuns {
+            site_id,
+            task_id,
+            limit,
+        }: GetScheduledTaskRuns,
+    ) -> Result<Vec<ScheduledTaskRunModel>> {
+        let txn = ctx.transaction();
+        let runs = ScheduledTaskRun::find()
+            .filter(
+                Condition::all()
+                    .add(scheduled_task_run::Column::TaskId.eq(task_id))
+                    .add(scheduled_task_run::Column::SiteId.eq(site_id)),
+            )
+            .order_by_desc(scheduled_task_run::Column::RunId)
+            .limit(limit)
+            .all(txn)
+            .await?;
+
+        Ok(runs)
+    }
+
+    /// Runs all the scheduled tasks which are due.
+    ///
+    /// If a task fails, this is recorded in its history, and it is
+    /// still scheduled to run again at the next time.
+    pub async fn run_due(ctx: &ServiceContext<'_>) -> Result<()> {
+        let txn = ctx.transaction();
+        let tasks = ScheduledTask::find()
+            .filter(
+                Condition::all()
+                    .add(scheduled_task::Column::Enabled.eq(true))
+                    .add(scheduled_task::Column::DeletedAt.is_null())
+                    .add(scheduled_task::Column::NextRunAt.lte(now())),
+            )
+            .order_by_asc(scheduled_task::Column::NextRunAt)
+            .limit(RUN_BATCH_SIZE)
+            .all(txn)
+            .await?;
+
+        debug!("Found {} scheduled tasks to run", tasks.len());
+        for task in tasks {
+            Self::run(ctx, task).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn run(
+        ctx: &ServiceContext<'_>,
+        ScheduledTaskModel {
+            task_id,
+            site_id,
+            schedule,
+            action,
+            next_run_at,
+            ..
+        }: ScheduledTaskModel,
+    ) -> Result<()> {
+        debug!("Running scheduled task ID {task_id} in site ID {site_id}");
+
+        // Like event subscribers, the action is run within a savepoint,
+        // so that a failure partway through leaves nothing behind.
+        let txn = ctx.transaction();
+        let result = match serde_json::from_value(action) {
+            Ok(action) => {
+                let savepoint = txn.begin().await?;
+                let result =
+                    Self::perform(&ctx.with_transaction(&savepoint), site_id, &action)
+                        .await;
+
+                match result {
+                    Ok(_) => savepoint.commit().await?,
+                    Err(_) => savepoint.rollback().await?,
+                }
+
+                result
+            }
+            Err(error) => Err(Error::from(error)),
+        };
+
+        let (pages_queued, error) = match result {
+            Ok(pages_queued) => (pages_queued, None),
+            Err(error) => {
+                warn!("Scheduled task ID {task_id} failed: {error}");
+                (0, Some(error.to_string()))
+            }
+        };
+
+        let model = scheduled_task_run::ActiveModel {
+            task_id: Set(task_id),
+            site_id: Set(site_id),
+            scheduled_for: Set(next_run_at.unwrap_or_else(now)),
+            succeeded: Set(error.is_none()),
+            pages_queued: Set(i32::try_from(pages_queued).unwrap_or(i32::MAX)),
+            error: Set(error),
+            ..Default::default()
+        };
+        model.insert(txn).await?;
+
+        // If the job fell behind, any runs missed since are skipped.
+        let next_run_at = CronSchedule::parse(&schedule)
+            .and_then(|schedule| schedule.next_after(now()));
+
+        let model = scheduled_task::ActiveModel {
+            task_id: Set(task_id),
+            last_run_at: Set(Some(now())),
+            next_run_at: Set(next_run_at),
+            ..Default::default()
+        };
+        model.update(txn).await?;
+        Ok(())
+    }
+
+    /// Carries out a task's action, returning how many pages it affected.
+    async fn perform(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        action: &ScheduledAction,
+    ) -> Result<usize> {
+        match action {
+            ScheduledAction::RerenderPage { page_id } => {
+                let page = Self::get_page(ctx, site_id, *page_id).await?;
+                JobService::queue_rerender_page(ctx, site_id, page.page_id, 0).await?;
+                Ok(1)
+            }
+            ScheduledAction::RerenderCategory { category } => {
+                let page_ids = Self::get_category_pages(ctx, site_id, category).await?;
+                for &page_id in &page_ids {
+                    JobService::queue_rerender_page(ctx, site_id, page_id, 0).await?;
+                }
+
+                Ok(page_ids.len())
+            }
+            ScheduledAction::PurgeCategory { category } => {
+                let page_ids = Self::get_category_pages(ctx, site_id, category).await?;
+                OutdateService::outdate_public_pages(ctx, site_id, &page_ids).await?;
+                Ok(page_ids.len())
+            }
+        }
+    }
+
+    /// Ensures the action refers to a page or category which exists in this site.
+    async fn check_action(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        action: &ScheduledAction,
+    ) -> Result<()> {
+        match action {
+            ScheduledAction::RerenderPage { page_id } => {
+                Self::get_page(ctx, site_id, *page_id).await?;
+            }
+            ScheduledAction::RerenderCategory { category }
+            | ScheduledAction::PurgeCategory { category } => {
+                CategoryService::get(ctx, site_id, Reference::from(category.as_str()))
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn get_page(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        page_id: i64,
+    ) -> Result<PageModel> {
+        let page = PageService::get_direct(ctx, page_id, false).await?;
+        if page.site_id != site_id {
+            warn!("Page ID {page_id} is not in site ID {site_id}");
+            return Err(Error::PageNotFound);
+        }
+
+        Ok(page)
+    }
+
+    /// Gets the pages in a category, up to the configured limit.
+    async fn get_category_pages(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        category: &str,
+    ) -> Result<Vec<i64>> {
+        let maximum = ctx.config().scheduled_task_maximum_pages;
+        let pages = PageService::get_all(
+            ctx,
+            site_id,
+            Some(Reference::from(category)),
+            Some(false),
+            PageOrder::default(),
+        )
+        .await?;
+
+        if pages.len() > maximum {
+            warn!(
+                "Category '{category}' in site ID {site_id} has {} pages, only acting on the first {maximum}",
+                pages.len(),
+            );
+        }
+
+        let page_ids = pages
+            .iter()
+            .take(maximum)
+            .map(|page| page.page_id)
+            .collect();
+        Ok(page_ids)
+    }
+
+    async fn check_name_conflict(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        name: &str,
+    ) -> Result<()> {
+        let txn = ctx.transaction();
+        let existing = ScheduledTask::find()
+            .filter(
+                Condition::all()
+                    .add(scheduled_task::Column::SiteId.eq(site_id))
+                    .add(scheduled_task::Column::Name.eq(name))
+                    .add(scheduled_task::Column::DeletedAt.is_null()),
+            )
+            .one(txn)
+            .await?;
+
+        if existing.is_some() {
+            error!("Scheduled task '{name}' already exists in site ID {site_id}");
+            return Err(Error::ScheduledTaskExists);
+        }
+
+        Ok(())
+    }
+}
+
+fn check_name(name: &str) -> Result<()> {
+    if name.is_empty() || name.len() > MAXIMUM_NAME_LENGTH {
+        error!("Scheduled task name is empty or too long");
+        return Err(Error::ScheduledTaskInvalid);
+    }
+
+    Ok(())
+}
+
+fn parse_schedule(schedule: &str) -> Result<CronSchedule> {
+    match CronSchedule::parse(schedule) {
+        Some(schedule) => Ok(schedule),
+        None => {
+            error!("Scheduled task schedule '{schedule}' is invalid");
+            Err(Error::ScheduledTaskInvalid)
+        }
+    }
+}
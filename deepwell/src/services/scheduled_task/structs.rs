@@ -0,0 +1,77 @@
+/*
+ * services/scheduled_task/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+
+/// What a scheduled task does each time it runs.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case", tag = "action")]
+pub enum ScheduledAction {
+    /// Rerenders one page, such as one which lists other pages.
+    RerenderPage { page_id: i64 },
+
+    /// Rerenders every page in a category.
+    RerenderCategory { category: String },
+
+    /// Purges every page in a category from the public API cache.
+    PurgeCategory { category: String },
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CreateScheduledTask {
+    pub site_id: i64,
+    pub user_id: i64,
+    pub name: String,
+
+    /// When to run, as a five-field cron expression in UTC.
+    pub schedule: String,
+    pub action: ScheduledAction,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct UpdateScheduledTask {
+    pub site_id: i64,
+    pub task_id: i64,
+
+    #[serde(flatten)]
+    pub body: UpdateScheduledTaskBody,
+}
+
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct UpdateScheduledTaskBody {
+    pub name: ProvidedValue<String>,
+    pub schedule: ProvidedValue<String>,
+    pub action: ProvidedValue<ScheduledAction>,
+    pub enabled: ProvidedValue<bool>,
+}
+
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct DeleteScheduledTask {
+    pub site_id: i64,
+    pub task_id: i64,
+}
+
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct GetScheduledTaskRuns {
+    pub site_id: i64,
+    pub task_id: i64,
+    pub limit: u64,
+}
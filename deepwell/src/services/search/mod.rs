@@ -0,0 +1,41 @@
+/*
+ * services/search/mod.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The search service, which maintains the full-text index of pages and files.
+//!
+//! Documents in the index are derived from the latest revision of each page
+//! or file, and are kept up to date by subscribing to domain events (see
+//! `EventService`). Since the index is purely derived data, it can be rebuilt
+//! for a site at any time, which is done in batches via the job queue.
+//!
+//! The consistency checker samples documents and objects from a site, and
+//! reports any which are missing from the index, indexed from an outdated
+//! revision, or left behind after their object was deleted.
+
+mod prelude {
+    pub use super::super::prelude::*;
+    pub use super::structs::*;
+}
+
+mod service;
+mod structs;
+
+pub use self::service::SearchService;
+pub use self::structs::*;
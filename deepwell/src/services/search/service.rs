@@ -0,0 +1,508 @@
+/*
+ * services/search/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::file::{self, Entity as File};
+use crate::models::page::{self, Entity as Page};
+use crate::models::sea_orm_active_enums::SearchDocumentType;
+use crate::models::search_document::{
+    self, Entity as SearchDocument, Model as SearchDocumentModel,
+};
+use crate::services::job::{Job, JobService};
+use crate::services::{
+    FileRevisionService, FileService, PageRevisionService, PageService, SiteService,
+    TextService,
+};
+use sea_orm::Order;
+use sea_query::{func::Func, OnConflict, Query, SimpleExpr};
+use std::cmp;
+
+/// The number of pages or files indexed in a single rebuild job.
+const REBUILD_BATCH_SIZE: u64 = 100;
+
+/// The maximum number of each kind of item sampled by a consistency check.
+const MAXIMUM_CHECK_SAMPLE_SIZE: u64 = 500;
+
+#[derive(Debug)]
+pub struct SearchService;
+
+impl SearchService {
+    /// Updates the document for a page from its latest revision.
+    ///
+    /// If the page has been deleted, its document is removed instead.
+    pub async fn index_page(ctx: &ServiceContext<'_>, page_id: i64) -> Result<()> {
+        let page = match PageService::get_direct_optional(ctx, page_id, false).await? {
+            Some(page) => page,
+            None => return Self::remove(ctx, SearchDocumentType::Page, page_id).await,
+        };
+
+        debug!("Indexing page ID {page_id}");
+        let revision =
+            PageRevisionService::get_latest(ctx, page.site_id, page_id).await?;
+        let wikitext = TextService::get(ctx, &revision.wikitext_hash).await?;
+        let body = format!(
+            "{}\n{}\n{}",
+            revision.alt_title.as_deref().unwrap_or(""),
+            revision.tags.join(" "),
+            wikitext,
+        );
+
+        Self::save(
+            ctx,
+            SearchDocumentModel {
+                document_type: SearchDocumentType::Page,
+                document_id: page_id,
+                site_id: page.site_id,
+                revision_id: revision.revision_id,
+                indexed_at: now(),
+                name: revision.slug,
+                title: revision.title,
+                body,
+                tags: revision.tags,
+            },
+        )
+        .await
+    }
+
+    /// Updates the document for a file from its latest revision.
+    ///
+    /// If the file has been deleted, its document is removed instead.
+    pub async fn index_file(ctx: &ServiceContext<'_>, file_id: i64) -> Result<()> {
+        let file = match FileService::get_direct_optional(ctx, file_id, false).await? {
+            Some(file) => file,
+            None => return Self::remove(ctx, SearchDocumentType::File, file_id).await,
+        };
+
+        debug!("Indexing file ID {file_id}");
+        let revision =
+            FileRevisionService::get_latest(ctx, file.site_id, file.page_id, file_id)
+                .await?;
+
+        Self::save(
+            ctx,
+            SearchDocumentModel {
+                document_type: SearchDocumentType::File,
+                document_id: file_id,
+                site_id: file.site_id,
+                revision_id: revision.revision_id,
+                indexed_at: now(),
+                name: revision.name.clone(),
+                title: revision.name,
+                body: revision.mime_hint,
+                tags: Vec::new(),
+            },
+        )
+        .await
+    }
+
+    async fn save(ctx: &ServiceContext<'_>, document: SearchDocumentModel) -> Result<()> {
+        let txn = ctx.transaction();
+        let SearchDocumentModel {
+            document_type,
+            document_id,
+            site_id,
+            revision_id,
+            indexed_at,
+            name,
+            title,
+            body,
+            tags,
+        } = document;
+
+        let model = search_document::ActiveModel {
+            document_type: Set(document_type),
+            document_id: Set(document_id),
+            site_id: Set(site_id),
+            revision_id: Set(revision_id),
+            indexed_at: Set(indexed_at),
+            name: Set(name),
+            title: Set(title),
+            body: Set(body),
+            tags: Set(tags),
+        };
+
+        // Indexing may race with itself, e.g. an edit during a rebuild
+        SearchDocument::insert(model)
+            .on_conflict(
+                OnConflict::columns([
+                    search_document::Column::DocumentType,
+                    search_document::Column::DocumentId,
+                ])
+                .update_columns([
+                    search_document::Column::SiteId,
+                    search_document::Column::RevisionId,
+                    search_document::Column::IndexedAt,
+                    search_document::Column::Name,
+                    search_document::Column::Title,
+                    search_document::Column::Body,
+                    search_document::Column::Tags,
+                ])
+                .to_owned(),
+            )
+            .exec_without_returning(txn)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn remove(
+        ctx: &ServiceContext<'_>,
+        document_type: SearchDocumentType,
+        document_id: i64,
+    ) -> Result<()> {
+        debug!("Removing {document_type:?} ID {document_id} from search index");
+
+        let txn = ctx.transaction();
+        SearchDocument::delete_by_id((document_type, document_id))
+            .exec(txn)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Queues a job to rebuild the search index for a site from scratch.
+    ///
+    /// Existing documents are kept (and updated) during the rebuild,
+    /// so searches continue to work while it runs.
+    pub async fn queue_rebuild(ctx: &ServiceContext<'_>, site_id: i64) -> Result<()> {
+        info!("Queuing search index rebuild for site ID {site_id}");
+
+        // Ensure the site exists
+        SiteService::get(ctx, Reference::Id(site_id)).await?;
+
+        JobService::queue_job(
+            ctx,
+            &Job::RebuildSearchIndex {
+                site_id,
+                document_type: SearchDocumentType::Page,
+                after_id: 0,
+            },
+            None,
+        )
+        .await
+    }
+
+    /// Indexes the next batch of a site's pages or files, as part of a rebuild.
+    ///
+    /// Pages are indexed first, then files, each in order of ID. Once both are
+    /// done, any documents whose page or file has been deleted are removed.
+    ///
+    /// # Returns
+    /// Where the next batch starts, or `None` if the rebuild is complete.
+    pub async fn rebuild(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        document_type: SearchDocumentType,
+        after_id: i64,
+    ) -> Result<Option<(SearchDocumentType, i64)>> {
+        info!(
+            "Rebuilding search index for site ID {site_id} ({document_type:?} IDs after {after_id})",
+        );
+
+        let txn = ctx.transaction();
+        let ids: Vec<i64> = match document_type {
+            SearchDocumentType::Page => Page::find()
+                .filter(
+                    Condition::all()
+                        .add(page::Column::SiteId.eq(site_id))
+                        .add(page::Column::DeletedAt.is_null())
+                        .add(page::Column::PageId.gt(after_id)),
+                )
+                .order_by_asc(page::Column::PageId)
+                .limit(REBUILD_BATCH_SIZE)
+                .all(txn)
+                .await?
+                .into_iter()
+                .map(|page| page.page_id)
+                .collect(),
+            SearchDocumentType::File => File::find()
+                .filter(
+                    Condition::all()
+                        .add(file::Column::SiteId.eq(site_id))
+                        .add(file::Column::DeletedAt.is_null())
+                        .add(file::Column::FileId.gt(after_id)),
+                )
+                .order_by_asc(file::Column::FileId)
+                .limit(REBUILD_BATCH_SIZE)
+                .all(txn)
+                .await?
+                .into_iter()
+                .map(|file| file.file_id)
+                .collect(),
+        };
+
+        for &id in &ids {
+            match document_type {
+                SearchDocumentType::Page => Self::index_page(ctx, id).await?,
+                SearchDocumentType::File => Self::index_file(ctx, id).await?,
+            }
+        }
+
+        let next = match ids.last() {
+            Some(&last_id) if ids.len() as u64 == REBUILD_BATCH_SIZE => {
+                Some((document_type, last_id))
+            }
+            _ => match document_type {
+                SearchDocumentType::Page => Some((SearchDocumentType::File, 0)),
+                SearchDocumentType::File => {
+                    Self::remove_orphans(ctx, site_id).await?;
+                    None
+                }
+            },
+        };
+
+        Ok(next)
+    }
+
+    /// Removes all documents in a site whose page or file has been deleted.
+    async fn remove_orphans(ctx: &ServiceContext<'_>, site_id: i64) -> Result<u64> {
+        let txn = ctx.transaction();
+        let DeleteResult { rows_affected } = SearchDocument::delete_many()
+            .filter(
+                Condition::all()
+                    .add(search_document::Column::SiteId.eq(site_id))
+                    .add(
+                        Condition::any()
+                            .add(
+                                Condition::all()
+                                    .add(
+                                        search_document::Column::DocumentType
+                                            .eq(SearchDocumentType::Page),
+                                    )
+                                    .add(
+                                        search_document::Column::DocumentId
+                                            .not_in_subquery(
+                                                Query::select()
+                                                    .column(page::Column::PageId)
+                                                    .from(Page)
+                                                    .and_where(
+                                                        page::Column::DeletedAt.is_null(),
+                                                    )
+                                                    .to_owned(),
+                                            ),
+                                    ),
+                            )
+                            .add(
+                                Condition::all()
+                                    .add(
+                                        search_document::Column::DocumentType
+                                            .eq(SearchDocumentType::File),
+                                    )
+                                    .add(
+                                        search_document::Column::DocumentId
+                                            .not_in_subquery(
+                                                Query::select()
+                                                    .column(file::Column::FileId)
+                                                    .from(File)
+                                                    .and_where(
+                                                        file::Column::DeletedAt.is_null(),
+                                                    )
+                                                    .to_owned(),
+                                            ),
+                                    ),
+                            ),
+                    ),
+            )
+            .exec(txn)
+            .await?;
+
+        debug!("Removed {rows_affected} orphaned documents from site ID {site_id}");
+        Ok(rows_affected)
+    }
+
+    /// Samples a site's documents, pages, and files, and reports any inconsistencies.
+    pub async fn check(
+        ctx: &ServiceContext<'_>,
+        CheckSearchIndex {
+            site_id,
+            sample_size,
+        }: CheckSearchIndex,
+    ) -> Result<SearchIndexReport> {
+        let sample_size = cmp::min(sample_size, MAXIMUM_CHECK_SAMPLE_SIZE);
+        info!("Checking search index for site ID {site_id} (sample size {sample_size})");
+
+        let txn = ctx.transaction();
+        let site_condition = search_document::Column::SiteId.eq(site_id);
+        let documents = SearchDocument::find()
+            .filter(site_condition.clone())
+            .count(txn)
+            .await?;
+
+        let oldest_indexed_at = SearchDocument::find()
+            .filter(site_condition.clone())
+            .order_by_asc(search_document::Column::IndexedAt)
+            .one(txn)
+            .await?
+            .map(|document| document.indexed_at);
+
+        let newest_indexed_at = SearchDocument::find()
+            .filter(site_condition.clone())
+            .order_by_desc(search_document::Column::IndexedAt)
+            .one(txn)
+            .await?
+            .map(|document| document.indexed_at);
+
+        let mut missing = Vec::new();
+        let mut stale = Vec::new();
+        let mut orphaned = Vec::new();
+        let mut maximum_lag_secs = None;
+
+        // Check sampled documents against their objects
+        let sampled = SearchDocument::find()
+            .filter(site_condition)
+            .order_by(SimpleExpr::FunctionCall(Func::random()), Order::Asc)
+            .limit(sample_size)
+            .all(txn)
+            .await?;
+
+        let sampled_documents = sampled.len() as u64;
+        for document in sampled {
+            let key = SearchDocumentKey {
+                document_type: document.document_type,
+                document_id: document.document_id,
+            };
+
+            // Get the latest revision ID and when it was made
+            let latest = match document.document_type {
+                SearchDocumentType::Page => {
+                    match PageService::get_direct_optional(
+                        ctx,
+                        document.document_id,
+                        false,
+                    )
+                    .await?
+                    {
+                        None => None,
+                        Some(page) => {
+                            let revision = PageRevisionService::get_latest(
+                                ctx,
+                                page.site_id,
+                                page.page_id,
+                            )
+                            .await?;
+
+                            Some((revision.revision_id, revision.created_at))
+                        }
+                    }
+                }
+                SearchDocumentType::File => {
+                    match FileService::get_direct_optional(
+                        ctx,
+                        document.document_id,
+                        false,
+                    )
+                    .await?
+                    {
+                        None => None,
+                        Some(file) => {
+                            let revision = FileRevisionService::get_latest(
+                                ctx,
+                                file.site_id,
+                                file.page_id,
+                                file.file_id,
+                            )
+                            .await?;
+
+                            Some((revision.revision_id, revision.created_at))
+                        }
+                    }
+                }
+            };
+
+            match latest {
+                None => orphaned.push(key),
+                Some((revision_id, _)) if revision_id == document.revision_id => (),
+                Some((_, created_at)) => {
+                    let lag = (now() - created_at).whole_seconds();
+                    maximum_lag_secs = Some(cmp::max(lag, maximum_lag_secs.unwrap_or(0)));
+                    stale.push(key);
+                }
+            }
+        }
+
+        // Check sampled pages and files for documents
+        let mut sampled_keys: Vec<SearchDocumentKey> = Page::find()
+            .filter(
+                Condition::all()
+                    .add(page::Column::SiteId.eq(site_id))
+                    .add(page::Column::DeletedAt.is_null()),
+            )
+            .order_by(SimpleExpr::FunctionCall(Func::random()), Order::Asc)
+            .limit(sample_size)
+            .all(txn)
+            .await?
+            .into_iter()
+            .map(|page| SearchDocumentKey {
+                document_type: SearchDocumentType::Page,
+                document_id: page.page_id,
+            })
+            .collect();
+
+        sampled_keys.extend(
+            File::find()
+                .filter(
+                    Condition::all()
+                        .add(file::Column::SiteId.eq(site_id))
+                        .add(file::Column::DeletedAt.is_null()),
+                )
+                .order_by(SimpleExpr::FunctionCall(Func::random()), Order::Asc)
+                .limit(sample_size)
+                .all(txn)
+                .await?
+                .into_iter()
+                .map(|file| SearchDocumentKey {
+                    document_type: SearchDocumentType::File,
+                    document_id: file.file_id,
+                }),
+        );
+
+        let sampled_objects = sampled_keys.len() as u64;
+        for key in sampled_keys {
+            let document =
+                SearchDocument::find_by_id((key.document_type, key.document_id))
+                    .one(txn)
+                    .await?;
+
+            if document.is_none() {
+                missing.push(key);
+            }
+        }
+
+        debug!(
+            "Search index check for site ID {site_id}: {} missing, {} stale, {} orphaned",
+            missing.len(),
+            stale.len(),
+            orphaned.len(),
+        );
+
+        Ok(SearchIndexReport {
+            site_id,
+            documents,
+            sampled_documents,
+            sampled_objects,
+            missing,
+            stale,
+            orphaned,
+            oldest_indexed_at,
+            newest_indexed_at,
+            maximum_lag_secs,
+        })
+    }
+}
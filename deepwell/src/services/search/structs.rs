@@ -0,0 +1,64 @@
+/*
+ * services/search/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::models::sea_orm_active_enums::SearchDocumentType;
+use time::OffsetDateTime;
+
+#[derive(Serialize, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SearchDocumentKey {
+    pub document_type: SearchDocumentType,
+    pub document_id: i64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CheckSearchIndex {
+    pub site_id: i64,
+
+    /// How many documents, pages, and files to check, each.
+    pub sample_size: u64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct SearchIndexReport {
+    pub site_id: i64,
+
+    /// The total number of documents indexed for this site.
+    pub documents: u64,
+    pub sampled_documents: u64,
+    pub sampled_objects: u64,
+
+    /// Pages or files which have no document in the index.
+    pub missing: Vec<SearchDocumentKey>,
+
+    /// Documents which were indexed from an older revision.
+    pub stale: Vec<SearchDocumentKey>,
+
+    /// Documents whose page or file has been deleted.
+    pub orphaned: Vec<SearchDocumentKey>,
+
+    pub oldest_indexed_at: Option<OffsetDateTime>,
+    pub newest_indexed_at: Option<OffsetDateTime>,
+
+    /// How long ago the oldest unindexed revision among stale documents was made.
+    ///
+    /// This is a measure of how far behind the index is, and is `None`
+    /// if no sampled documents were stale.
+    pub maximum_lag_secs: Option<i64>,
+}
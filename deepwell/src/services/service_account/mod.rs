@@ -0,0 +1,45 @@
+/*
+ * services/service_account/mod.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The service account service, for internal components calling the API.
+//!
+//! Components such as the frontend's server-side rendering, importers, and
+//! scheduled tasks are not users. Rather than being trusted wholesale, each
+//! is given a service account with a key and a list of scopes, which are the
+//! API methods it may call (or prefixes of them, ending in `*`).
+//!
+//! Callers pass their key as `service_key` alongside the usual parameters,
+//! or wrap them as `{"service_key": ..., "params": ...}` for methods which
+//! take positional parameters.
+//! Every call made with a key is checked against its scopes, and the service
+//! account is recorded in any audit log entries the call creates, so that it
+//! is clear which component performed an action. Once every component has a
+//! key, internal methods can be limited to service accounts altogether.
+
+mod prelude {
+    pub use super::super::prelude::*;
+    pub use super::structs::*;
+}
+
+mod service;
+mod structs;
+
+pub use self::service::ServiceAccountService;
+pub use self::structs::*;
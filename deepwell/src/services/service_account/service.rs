@@ -0,0 +1,324 @@
+/*
+ * services/service_account/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::service_account::{
+    self, Entity as ServiceAccountEntity, Model as ServiceAccountModel,
+};
+use crate::services::audit::CreateAuditEntry;
+use crate::services::permission::Requirement;
+use crate::services::{AuditService, PermissionService};
+use crate::utils::{hash_token, new_token};
+
+/// The longest service account name which can be used.
+const MAXIMUM_NAME_LENGTH: usize = 100;
+
+#[derive(Debug)]
+pub struct ServiceAccountService;
+
+impl ServiceAccountService {
+    /// Creates a service account, returning its key.
+    pub async fn create(
+        ctx: &ServiceContext<'_>,
+        CreateServiceAccount {
+            name,
+            description,
+            scopes,
+            acting_user_id,
+        }: CreateServiceAccount,
+    ) -> Result<CreateServiceAccountOutput> {
+        info!("Creating service account '{name}' with scopes {scopes:?}");
+
+        if name.is_empty() || name.len() > MAXIMUM_NAME_LENGTH {
+            error!("Service account name is empty or too long");
+            return Err(Error::ServiceAccountInvalid);
+        }
+
+        check_scopes(&scopes)?;
+
+        let txn = ctx.transaction();
+        let existing = ServiceAccountEntity::find()
+            .filter(service_account::Column::Name.eq(name.as_str()))
+            .one(txn)
+            .await?;
+
+        if existing.is_some() {
+            error!("Service account '{name}' already exists");
+            return Err(Error::ServiceAccountExists);
+        }
+
+        let key = new_key(ctx);
+        let model = service_account::ActiveModel {
+            created_by: Set(acting_user_id),
+            name: Set(name),
+            description: Set(description),
+            key_hash: Set(hash_token(&key)),
+            scopes: Set(scopes),
+            ..Default::default()
+        };
+        let account = model.insert(txn).await?;
+
+        Self::audit(ctx, acting_user_id, "service-account.create", &account).await?;
+        Ok(CreateServiceAccountOutput {
+            service_account_id: account.service_account_id,
+            key,
+        })
+    }
+
+    pub async fn get_all(ctx: &ServiceContext<'_>) -> Result<Vec<ServiceAccount>> {
+        let txn = ctx.transaction();
+        let accounts = ServiceAccountEntity::find()
+            .order_by_asc(service_account::Column::ServiceAccountId)
+            .all(txn)
+            .await?;
+
+        Ok(accounts.into_iter().map(ServiceAccount::from).collect())
+    }
+
+    pub async fn update(
+        ctx: &ServiceContext<'_>,
+        UpdateServiceAccount {
+            service_account_id,
+            acting_user_id,
+            description,
+            scopes,
+        }: UpdateServiceAccount,
+    ) -> Result<ServiceAccount> {
+        info!("Updating service account ID {service_account_id}");
+
+        if let ProvidedValue::Set(ref scopes) = scopes {
+            check_scopes(scopes)?;
+        }
+
+        let txn = ctx.transaction();
+        Self::get_active(ctx, service_account_id).await?;
+
+        let model = service_account::ActiveModel {
+            service_account_id: Set(service_account_id),
+            updated_at: Set(Some(now())),
+            description: description.into_active_value(),
+            scopes: scopes.into_active_value(),
+            ..Default::default()
+        };
+        let account = model.update(txn).await?;
+
+        Self::audit(ctx, acting_user_id, "service-account.update", &account).await?;
+        Ok(account.into())
+    }
+
+    /// Replaces the key for a service account, so the old one no longer works.
+    pub async fn rotate_key(
+        ctx: &ServiceContext<'_>,
+        RotateServiceAccountKey {
+            service_account_id,
+            acting_user_id,
+        }: RotateServiceAccountKey,
+    ) -> Result<String> {
+        info!("Rotating key for service account ID {service_account_id}");
+
+        let txn = ctx.transaction();
+        Self::get_active(ctx, service_account_id).await?;
+
+        let key = new_key(ctx);
+        let model = service_account::ActiveModel {
+            service_account_id: Set(service_account_id),
+            updated_at: Set(Some(now())),
+            key_hash: Set(hash_token(&key)),
+            ..Default::default()
+        };
+        let account = model.update(txn).await?;
+
+        Self::audit(ctx, acting_user_id, "service-account.rotate", &account).await?;
+        Ok(key)
+    }
+
+    pub async fn revoke(
+        ctx: &ServiceContext<'_>,
+        RevokeServiceAccount {
+            service_account_id,
+            acting_user_id,
+        }: RevokeServiceAccount,
+    ) -> Result<()> {
+        info!("Revoking service account ID {service_account_id}");
+
+        let txn = ctx.transaction();
+        Self::get_active(ctx, service_account_id).await?;
+
+        let model = service_account::ActiveModel {
+            service_account_id: Set(service_account_id),
+            updated_at: Set(Some(now())),
+            revoked_at: Set(Some(now())),
+            ..Default::default()
+        };
+        let account = model.update(txn).await?;
+
+        Self::audit(ctx, acting_user_id, "service-account.revoke", &account).await?;
+        Ok(())
+    }
+
+    /// Determines which service account, if any, is calling an API method.
+    ///
+    /// A key which is unknown or revoked, or whose scopes do not include the
+    /// method, is rejected. Calls without a key are allowed, except for internal
+    /// methods when service accounts are required.
+    pub async fn authenticate(
+        ctx: &ServiceContext<'_>,
+        method: &str,
+        service_key: Option<&str>,
+    ) -> Result<Option<ServicePrincipal>> {
+        let service_key = match service_key {
+            Some(service_key) => service_key,
+            None => {
+                if PermissionService::requirement(method) != Some(Requirement::Internal) {
+                    return Ok(None);
+                }
+
+                if ctx.config().service_account_required {
+                    error!("Internal method '{method}' called without a service account");
+                    return Err(Error::ServiceAccountRequired);
+                }
+
+                warn!("Internal method '{method}' called without a service account");
+                return Ok(None);
+            }
+        };
+
+        let txn = ctx.transaction();
+        let account = ServiceAccountEntity::find()
+            .filter(
+                Condition::all()
+                    .add(service_account::Column::KeyHash.eq(hash_token(service_key)))
+                    .add(service_account::Column::RevokedAt.is_null()),
+            )
+            .one(txn)
+            .await?
+            .ok_or(Error::InvalidAuthentication)?;
+
+        if !scopes_allow(&account.scopes, method) {
+            error!(
+                "Service account '{}' does not have the scope to call '{method}'",
+                account.name,
+            );
+            return Err(Error::ServiceAccountForbidden);
+        }
+
+        debug!("Service account '{}' is calling '{method}'", account.name);
+        Ok(Some(ServicePrincipal {
+            service_account_id: account.service_account_id,
+            name: account.name,
+        }))
+    }
+
+    async fn get_active(
+        ctx: &ServiceContext<'_>,
+        service_account_id: i64,
+    ) -> Result<ServiceAccountModel> {
+        let txn = ctx.transaction();
+        ServiceAccountEntity::find()
+            .filter(
+                Condition::all()
+                    .add(service_account::Column::ServiceAccountId.eq(service_account_id))
+                    .add(service_account::Column::RevokedAt.is_null()),
+            )
+            .one(txn)
+            .await?
+            .ok_or(Error::ServiceAccountNotFound)
+    }
+
+    async fn audit(
+        ctx: &ServiceContext<'_>,
+        acting_user_id: i64,
+        action: &str,
+        account: &ServiceAccountModel,
+    ) -> Result<()> {
+        AuditService::record(
+            ctx,
+            CreateAuditEntry {
+                actor_id: acting_user_id,
+                subject_id: acting_user_id,
+                site_id: None,
+                action,
+                data: serde_json::json!({
+                    "service_account_id": account.service_account_id,
+                    "name": account.name,
+                    "scopes": account.scopes,
+                }),
+            },
+        )
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// Ensures each scope is an API method, or a prefix of at least one ending in `*`.
+fn check_scopes(scopes: &[String]) -> Result<()> {
+    for scope in scopes {
+        let valid = match scope.strip_suffix('*') {
+            Some(prefix) => {
+                !prefix.is_empty()
+                    && PermissionService::methods()
+                        .any(|method| method.starts_with(prefix))
+            }
+            None => PermissionService::requirement(scope).is_some(),
+        };
+
+        if !valid {
+            error!("Service account scope '{scope}' does not match any method");
+            return Err(Error::ServiceAccountInvalid);
+        }
+    }
+
+    Ok(())
+}
+
+fn scopes_allow(scopes: &[String], method: &str) -> bool {
+    scopes.iter().any(|scope| match scope.strip_suffix('*') {
+        Some(prefix) => method.starts_with(prefix),
+        None => scope == method,
+    })
+}
+
+/// Securely generates a new service account key.
+#[inline]
+fn new_key(ctx: &ServiceContext<'_>) -> String {
+    new_token(ctx.config().session_token_length)
+}
+
+#[test]
+fn scope_matching() {
+    let scopes = [str!("page_get"), str!("user_*")];
+
+    assert!(scopes_allow(&scopes, "page_get"));
+    assert!(scopes_allow(&scopes, "user_get"));
+    assert!(scopes_allow(&scopes, "user_import"));
+    assert!(!scopes_allow(&scopes, "page_get_score"));
+    assert!(!scopes_allow(&scopes, "page_edit"));
+    assert!(!scopes_allow(&[], "page_get"));
+}
+
+#[test]
+fn scope_validation() {
+    assert!(check_scopes(&[str!("page_get"), str!("user_*")]).is_ok());
+    assert!(check_scopes(&[]).is_ok());
+    assert!(check_scopes(&[str!("*")]).is_err());
+    assert!(check_scopes(&[str!("not_a_method")]).is_err());
+    assert!(check_scopes(&[str!("nothing_*")]).is_err());
+}
@@ -0,0 +1,100 @@
+/*
+ * services/service_account/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::models::service_account::Model as ServiceAccountModel;
+use crate::web::ProvidedValue;
+use time::OffsetDateTime;
+
+/// The service account making an API call.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct ServicePrincipal {
+    pub service_account_id: i64,
+    pub name: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CreateServiceAccount {
+    pub name: String,
+
+    #[serde(default)]
+    pub description: String,
+    pub scopes: Vec<String>,
+    pub acting_user_id: i64,
+}
+
+/// A newly created service account.
+///
+/// The key is only available here, and must be given to the component.
+#[derive(Serialize, Debug, Clone)]
+pub struct CreateServiceAccountOutput {
+    pub service_account_id: i64,
+    pub key: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct UpdateServiceAccount {
+    pub service_account_id: i64,
+    pub acting_user_id: i64,
+
+    #[serde(default)]
+    pub description: ProvidedValue<String>,
+
+    #[serde(default)]
+    pub scopes: ProvidedValue<Vec<String>>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct RotateServiceAccountKey {
+    pub service_account_id: i64,
+    pub acting_user_id: i64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct RevokeServiceAccount {
+    pub service_account_id: i64,
+    pub acting_user_id: i64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ServiceAccount {
+    pub service_account_id: i64,
+    pub created_at: OffsetDateTime,
+    pub updated_at: Option<OffsetDateTime>,
+    pub revoked_at: Option<OffsetDateTime>,
+    pub created_by: i64,
+    pub name: String,
+    pub description: String,
+    pub scopes: Vec<String>,
+}
+
+impl From<ServiceAccountModel> for ServiceAccount {
+    fn from(model: ServiceAccountModel) -> Self {
+        ServiceAccount {
+            service_account_id: model.service_account_id,
+            created_at: model.created_at,
+            updated_at: model.updated_at,
+            revoked_at: model.revoked_at,
+            created_by: model.created_by,
+            name: model.name,
+            description: model.description,
+            scopes: model.scopes,
+        }
+    }
+}
@@ -27,15 +27,24 @@
 //! The session token is the only means through which a session
 //! is validated. It is a unique, securely randomly generated value
 //! which represents the current session. It has a somewhat short
-//! expiry (30 minutes) which needs to be renewed by the client
-//! periodically.
+//! expiry (30 minutes) which is extended as the session is used,
+//! up to a maximum time since the user logged in.
+//!
+//! To limit the usefulness of a stolen token, tokens are periodically
+//! rotated, and also soon after the user's credentials or site roles
+//! change. The old token keeps working for a brief grace period after
+//! being replaced. Changing the password logs out all other sessions.
 
 use super::prelude::*;
 use crate::models::session::{self, Entity as Session, Model as SessionModel};
 use crate::models::user::{self, Entity as User, Model as UserModel};
-use crate::utils::assert_is_csprng;
-use rand::distributions::{Alphanumeric, DistString};
-use rand::thread_rng;
+use crate::services::ImpersonationService;
+use crate::utils::new_token;
+use sea_orm::UpdateResult;
+use sea_query::Expr;
+use std::cmp;
+use std::net::IpAddr;
+use time::{Duration, OffsetDateTime};
 
 #[derive(Debug)]
 pub struct SessionService;
@@ -45,40 +54,122 @@ impl SessionService {
     ///
     /// # Returns
     /// The generated session token.
+    #[inline]
     pub async fn create(
+        ctx: &ServiceContext<'_>,
+        input: CreateSession,
+    ) -> Result<String> {
+        Self::create_internal(ctx, input, None).await
+    }
+
+    /// Creates a new session from exchanging a refresh token.
+    ///
+    /// The session is recorded as part of the token's family, so that it
+    /// can be invalidated if that family is revoked.
+    ///
+    /// # Returns
+    /// The generated session token.
+    #[inline]
+    pub async fn create_for_refresh_family(
+        ctx: &ServiceContext<'_>,
+        input: CreateSession,
+        family_id: String,
+    ) -> Result<String> {
+        Self::create_internal(ctx, input, Some(family_id)).await
+    }
+
+    async fn create_internal(
         ctx: &ServiceContext<'_>,
         CreateSession {
             user_id,
             ip_address,
             user_agent,
             restricted,
+            impersonator_id,
         }: CreateSession,
+        refresh_family_id: Option<String>,
     ) -> Result<String> {
         info!("Creating new session for user ID {user_id} (restricted: {restricted})",);
 
+        let ip_address = Self::stored_ip_address(ctx.config(), ip_address);
+        let SessionModel { session_token, .. } = Self::insert(
+            ctx,
+            user_id,
+            ip_address,
+            user_agent,
+            restricted,
+            impersonator_id,
+            refresh_family_id,
+            now(),
+        )
+        .await?;
+
+        info!("Created new session token");
+        Ok(session_token)
+    }
+
+    /// Inserts a new session row.
+    ///
+    /// The `login_at` time is when the user originally authenticated,
+    /// which limits how far the session can be extended.
+    #[allow(clippy::too_many_arguments)]
+    async fn insert(
+        ctx: &ServiceContext<'_>,
+        user_id: i64,
+        ip_address: String,
+        user_agent: String,
+        restricted: bool,
+        impersonator_id: Option<i64>,
+        refresh_family_id: Option<String>,
+        login_at: OffsetDateTime,
+    ) -> Result<SessionModel> {
         let txn = ctx.transaction();
         let config = ctx.config();
         let token = Self::new_token(config);
         let now = now();
         let expiry = if restricted {
             now + config.restricted_session_duration
+        } else if impersonator_id.is_some() {
+            now + config.impersonation_session_duration
         } else {
-            now + config.normal_session_duration
+            cmp::min(
+                now + config.normal_session_duration,
+                login_at + config.maximum_session_duration,
+            )
         };
 
+        if expiry <= now {
+            error!("Session for user ID {user_id} has reached its maximum duration");
+            return Err(Error::InvalidSessionToken);
+        }
+
         let model = session::ActiveModel {
             session_token: Set(token),
             user_id: Set(user_id),
             created_at: Set(now),
             expires_at: Set(expiry),
-            ip_address: Set(str!(ip_address)), // TODO inet type?
+            login_at: Set(login_at),
+            rotate_at: Set(now + config.session_rotation_interval),
+            replaced_by: Set(None),
+            ip_address: Set(ip_address), // TODO inet type?
             user_agent: Set(user_agent),
             restricted: Set(restricted),
+            impersonator_id: Set(impersonator_id),
+            refresh_family_id: Set(refresh_family_id),
         };
 
-        let SessionModel { session_token, .. } = model.insert(txn).await?;
-        info!("Created new session token");
-        Ok(session_token)
+        let session = model.insert(txn).await?;
+        Ok(session)
+    }
+
+    fn stored_ip_address(config: &Config, ip_address: IpAddr) -> String {
+        let ip_address = if config.truncate_stored_ips {
+            config.redaction_policy.truncate_ip(ip_address)
+        } else {
+            ip_address
+        };
+
+        str!(ip_address)
     }
 
     /// Securely generates a new session token.
@@ -86,10 +177,7 @@ impl SessionService {
     /// Example generated token: `wj:T9iF6vfjoYYE20QzrybV2C1V4K0LchHXsNVipX8G1GZ9vSJf0rvQpJ4YC8c8MAQ3`.
     fn new_token(config: &Config) -> String {
         debug!("Generating a new session token");
-        let mut rng = thread_rng();
-        assert_is_csprng(&rng);
-
-        let mut token = Alphanumeric.sample_string(&mut rng, config.session_token_length);
+        let mut token = new_token(config.session_token_length);
         token.insert_str(0, &config.session_token_prefix);
 
         token
@@ -139,12 +227,13 @@ impl SessionService {
 
         let txn = ctx.transaction();
         let user = User::find()
-            .join(JoinType::Join, user::Relation::Session.def())
+            .join(JoinType::Join, session::Relation::User1.def().rev())
             .filter(
                 Condition::all()
                     .add(session::Column::SessionToken.eq(session_token))
                     .add(session::Column::ExpiresAt.gt(now()))
-                    .add(session::Column::Restricted.eq(restricted)),
+                    .add(session::Column::Restricted.eq(restricted))
+                    .add(user::Column::DeletedAt.is_null()),
             )
             .one(txn)
             .await?
@@ -205,24 +294,200 @@ impl SessionService {
             });
         }
 
-        // Invalid and recreate
-        let (_, session_token) = try_join!(
-            Self::invalidate(ctx, old_session_token),
-            Self::create(
-                ctx,
-                CreateSession {
-                    user_id,
-                    ip_address,
-                    user_agent,
-                    restricted: false,
-                }
-            ),
-        )?;
+        // Impersonation sessions have a fixed lifetime
+        ImpersonationService::check(&old_session, "session.renew")?;
+
+        // Restricted sessions are upgraded after logging in, which starts afresh.
+        // Otherwise the token is rotated, keeping the original login time.
+        if old_session.restricted {
+            let (_, session_token) = try_join!(
+                Self::invalidate(ctx, old_session_token),
+                Self::create(
+                    ctx,
+                    CreateSession {
+                        user_id,
+                        ip_address,
+                        user_agent,
+                        restricted: false,
+                        impersonator_id: None,
+                    }
+                ),
+            )?;
+
+            Ok(session_token)
+        } else {
+            let ip_address = Self::stored_ip_address(ctx.config(), ip_address);
+            let session = Self::rotate(ctx, old_session, ip_address, user_agent).await?;
+            Ok(session.session_token)
+        }
+    }
 
-        Ok(session_token)
+    /// Updates a session in response to activity.
+    ///
+    /// If the session is due for rotation, then it is replaced with a new one,
+    /// which is returned. Otherwise its expiry is extended, up to the maximum
+    /// duration since the user logged in.
+    ///
+    /// If the session was already rotated, such as by a concurrent request,
+    /// then its replacement is returned. Restricted and impersonation sessions
+    /// are returned as-is.
+    pub async fn refresh(
+        ctx: &ServiceContext<'_>,
+        session: SessionModel,
+    ) -> Result<SessionModel> {
+        if session.restricted || session.impersonator_id.is_some() {
+            return Ok(session);
+        }
+
+        if session.replaced_by.is_some() {
+            return Self::replacement(ctx, session).await;
+        }
+
+        let config = ctx.config();
+        let now = now();
+
+        if now >= session.rotate_at {
+            let ip_address = session.ip_address.clone();
+            let user_agent = session.user_agent.clone();
+            return Self::rotate(ctx, session, ip_address, user_agent).await;
+        }
+
+        match sliding_expiry(
+            now,
+            session.login_at,
+            session.expires_at,
+            config.normal_session_duration,
+            config.maximum_session_duration,
+        ) {
+            None => Ok(session),
+            Some(expires_at) => {
+                debug!("Extending session for user ID {}", session.user_id);
+
+                let txn = ctx.transaction();
+                let model = session::ActiveModel {
+                    session_token: Set(session.session_token),
+                    expires_at: Set(expires_at),
+                    ..Default::default()
+                };
+
+                let session = model.update(txn).await?;
+                Ok(session)
+            }
+        }
+    }
+
+    /// Replaces a session with a new one, with a different token.
+    ///
+    /// The old token continues to work for a short grace period,
+    /// so requests which were already using it are not interrupted.
+    ///
+    /// If it has already been replaced, such as by a concurrent request,
+    /// then that replacement is returned instead of creating another.
+    async fn rotate(
+        ctx: &ServiceContext<'_>,
+        old_session: SessionModel,
+        ip_address: String,
+        user_agent: String,
+    ) -> Result<SessionModel> {
+        info!("Rotating session token for user ID {}", old_session.user_id);
+
+        let txn = ctx.transaction();
+        let config = ctx.config();
+        let new_session = Self::insert(
+            ctx,
+            old_session.user_id,
+            ip_address,
+            user_agent,
+            false,
+            None,
+            old_session.refresh_family_id.clone(),
+            old_session.login_at,
+        )
+        .await?;
+
+        // Only replace the session if nothing else has, so that concurrent
+        // rotations can't each leave the user with a different token.
+        let UpdateResult { rows_affected } = Session::update_many()
+            .col_expr(
+                session::Column::ExpiresAt,
+                Expr::value(cmp::min(
+                    old_session.expires_at,
+                    now() + config.session_rotation_grace,
+                )),
+            )
+            .col_expr(
+                session::Column::ReplacedBy,
+                Expr::value(new_session.session_token.clone()),
+            )
+            .filter(
+                Condition::all()
+                    .add(session::Column::SessionToken.eq(&old_session.session_token))
+                    .add(session::Column::ReplacedBy.is_null()),
+            )
+            .exec(txn)
+            .await?;
+
+        if rows_affected == 0 {
+            debug!("Session was already rotated, using its replacement");
+            Session::delete_by_id(new_session.session_token)
+                .exec(txn)
+                .await?;
+
+            let old_session = Session::find_by_id(old_session.session_token)
+                .one(txn)
+                .await?
+                .ok_or(Error::InvalidSessionToken)?;
+
+            return Self::replacement(ctx, old_session).await;
+        }
+
+        Ok(new_session)
+    }
+
+    /// Gets the session which replaced a rotated one.
+    ///
+    /// If the replacement has since expired or been invalidated,
+    /// then the old session is returned as-is.
+    async fn replacement(
+        ctx: &ServiceContext<'_>,
+        session: SessionModel,
+    ) -> Result<SessionModel> {
+        let new_session = match session.replaced_by {
+            Some(ref session_token) => Self::get_optional(ctx, session_token).await?,
+            None => None,
+        };
+
+        Ok(new_session.unwrap_or(session))
+    }
+
+    /// Marks all of a user's sessions as due for rotation.
+    ///
+    /// This is used after changes to a user's credentials,
+    /// so that tokens issued beforehand are soon replaced.
+    ///
+    /// # Returns
+    /// The number of affected sessions.
+    pub async fn require_rotation(ctx: &ServiceContext<'_>, user_id: i64) -> Result<u64> {
+        info!("Requiring session rotation for user ID {user_id}");
+
+        let txn = ctx.transaction();
+        let UpdateResult { rows_affected } = Session::update_many()
+            .col_expr(session::Column::RotateAt, Expr::value(now()))
+            .filter(
+                Condition::all()
+                    .add(session::Column::UserId.eq(user_id))
+                    .add(session::Column::ReplacedBy.is_null()),
+            )
+            .exec(txn)
+            .await?;
+
+        Ok(rows_affected)
     }
 
     /// Invalidates the given session, causing it to be deleted.
+    ///
+    /// If the session was rotated, then the sessions it was replaced by
+    /// and replaced are deleted too, since they are all the same login.
     pub async fn invalidate(
         ctx: &ServiceContext<'_>,
         session_token: String,
@@ -230,17 +495,75 @@ impl SessionService {
         info!("Invalidating session ID {session_token}");
 
         let txn = ctx.transaction();
-        let DeleteResult { rows_affected } =
-            Session::delete_by_id(session_token).exec(txn).await?;
+        let session = match Session::find_by_id(&session_token).one(txn).await? {
+            Some(session) => session,
+            None => {
+                error!("This session was already deleted or does not exist");
+                return Err(Error::InvalidSessionToken);
+            }
+        };
 
-        if rows_affected != 1 {
-            error!("This session was already deleted or does not exist");
-            return Err(Error::InvalidSessionToken);
+        // Walk the rotation chain in both directions
+        let mut tokens = vec![session_token];
+        let mut pending = vec![session];
+        while let Some(session) = pending.pop() {
+            ImpersonationService::end(ctx, &session).await?;
+
+            let related = Session::find()
+                .filter(
+                    Condition::any()
+                        .add(
+                            session::Column::ReplacedBy
+                                .eq(session.session_token.as_str()),
+                        )
+                        .add_option(
+                            session
+                                .replaced_by
+                                .as_deref()
+                                .map(|token| session::Column::SessionToken.eq(token)),
+                        ),
+                )
+                .all(txn)
+                .await?;
+
+            for session in related {
+                if !tokens.contains(&session.session_token) {
+                    tokens.push(session.session_token.clone());
+                    pending.push(session);
+                }
+            }
         }
 
+        debug!("Deleting {} sessions in rotation chain", tokens.len());
+        Session::delete_many()
+            .filter(session::Column::SessionToken.is_in(tokens))
+            .exec(txn)
+            .await?;
+
         Ok(())
     }
 
+    /// Invalidates all sessions issued for a family of refresh tokens.
+    ///
+    /// This includes any sessions they were rotated into.
+    ///
+    /// # Returns
+    /// The number of invalidated sessions.
+    pub async fn invalidate_refresh_family(
+        ctx: &ServiceContext<'_>,
+        family_id: &str,
+    ) -> Result<u64> {
+        info!("Invalidating all sessions for refresh token family {family_id}");
+
+        let txn = ctx.transaction();
+        let DeleteResult { rows_affected } = Session::delete_many()
+            .filter(session::Column::RefreshFamilyId.eq(family_id))
+            .exec(txn)
+            .await?;
+
+        Ok(rows_affected)
+    }
+
     /// Invalidates all others sessions _except_ the one listed.
     /// This enables a user to "log out all other sessions",
     /// a useful security feature. See [WJ-364].
@@ -271,6 +594,8 @@ impl SessionService {
             });
         }
 
+        ImpersonationService::check(&session, "session.invalidate-others")?;
+
         // Delete all sessions from user_id, except if it's this session_token
         let DeleteResult { rows_affected } = Session::delete_many()
             .filter(
@@ -285,6 +610,23 @@ impl SessionService {
         Ok(rows_affected)
     }
 
+    /// Invalidates all sessions for the given user.
+    ///
+    /// # Returns
+    /// The number of invalidated sessions.
+    pub async fn invalidate_all(ctx: &ServiceContext<'_>, user_id: i64) -> Result<u64> {
+        info!("Invalidating all session IDs for user ID {user_id}");
+
+        let txn = ctx.transaction();
+        let DeleteResult { rows_affected } = Session::delete_many()
+            .filter(session::Column::UserId.eq(user_id))
+            .exec(txn)
+            .await?;
+
+        debug!("User ID {user_id}: {rows_affected} sessions were invalidated");
+        Ok(rows_affected)
+    }
+
     /// Prunes all expired sessions from the database.
     ///
     /// # Returns
@@ -293,8 +635,24 @@ impl SessionService {
         info!("Pruning all expired sessions");
 
         let txn = ctx.transaction();
+        let now = now();
+
+        // Impersonated users are notified once the session is over
+        let impersonations = Session::find()
+            .filter(
+                Condition::all()
+                    .add(session::Column::ExpiresAt.lte(now))
+                    .add(session::Column::ImpersonatorId.is_not_null()),
+            )
+            .all(txn)
+            .await?;
+
+        for session in impersonations {
+            ImpersonationService::end(ctx, &session).await?;
+        }
+
         let DeleteResult { rows_affected } = Session::delete_many()
-            .filter(session::Column::ExpiresAt.lte(now()))
+            .filter(session::Column::ExpiresAt.lte(now))
             .exec(txn)
             .await?;
 
@@ -302,3 +660,54 @@ impl SessionService {
         Ok(rows_affected)
     }
 }
+
+/// Determines the new expiry for a session which was just used, if any.
+///
+/// To avoid writing on every request, sessions are only extended once
+/// at least half of the sliding duration has passed.
+fn sliding_expiry(
+    now: OffsetDateTime,
+    login_at: OffsetDateTime,
+    expires_at: OffsetDateTime,
+    sliding_duration: Duration,
+    maximum_duration: Duration,
+) -> Option<OffsetDateTime> {
+    let target = cmp::min(now + sliding_duration, login_at + maximum_duration);
+    if expires_at - now < sliding_duration / 2 && target > expires_at {
+        Some(target)
+    } else {
+        None
+    }
+}
+
+#[test]
+fn sliding_expiry_extension() {
+    let login_at = OffsetDateTime::UNIX_EPOCH;
+    let at = |minutes| login_at + Duration::minutes(minutes);
+    let sliding = Duration::minutes(30);
+    let maximum = Duration::hours(2);
+
+    // Recently extended, nothing to do
+    assert_eq!(
+        sliding_expiry(at(10), login_at, at(35), sliding, maximum),
+        None
+    );
+
+    // Over halfway through, extended
+    assert_eq!(
+        sliding_expiry(at(20), login_at, at(30), sliding, maximum),
+        Some(at(50)),
+    );
+
+    // Capped at the maximum duration
+    assert_eq!(
+        sliding_expiry(at(110), login_at, at(115), sliding, maximum),
+        Some(at(120)),
+    );
+
+    // Already at the maximum duration
+    assert_eq!(
+        sliding_expiry(at(115), login_at, at(120), sliding, maximum),
+        None
+    );
+}
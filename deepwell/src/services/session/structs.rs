@@ -27,6 +27,10 @@ pub struct CreateSession {
     pub ip_address: IpAddr,
     pub user_agent: String,
     pub restricted: bool,
+
+    /// If set, this is an administrator impersonating the user.
+    #[serde(default)]
+    pub impersonator_id: Option<i64>,
 }
 
 #[derive(Deserialize, Debug, Clone)]
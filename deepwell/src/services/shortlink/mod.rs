@@ -0,0 +1,40 @@
+/*
+ * services/shortlink/mod.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The shortlink service, for generating and resolving short URLs to pages.
+//!
+//! Each page (and optionally each revision of it) can have one shortlink,
+//! identified by a random alphanumeric code unique across the instance.
+//! Shortlinks follow page moves, since they store the page ID rather than its slug.
+//!
+//! By default a shortlink is served from the site's own domain under `/-/s/`,
+//! but a site can also configure a separate short domain, in which case
+//! codes are served from its root (e.g. `https://scp.gg/aB3dE9x`).
+
+mod prelude {
+    pub use super::super::prelude::*;
+    pub use super::structs::*;
+}
+
+mod service;
+mod structs;
+
+pub use self::service::ShortlinkService;
+pub use self::structs::*;
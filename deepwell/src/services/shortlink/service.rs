@@ -0,0 +1,267 @@
+/*
+ * services/shortlink/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::page::Model as PageModel;
+use crate::models::shortlink::{self, Entity as Shortlink, Model as ShortlinkModel};
+use crate::models::site::Model as SiteModel;
+use crate::services::{DomainService, PageRevisionService, PageService, SiteService};
+use crate::utils::new_token;
+use sea_query::{Expr, OnConflict};
+
+/// How many times to try generating an unused code before giving up.
+///
+/// With the default code length a collision is already very unlikely,
+/// so hitting this limit means the configured length is far too short.
+const MAX_CODE_ATTEMPTS: usize = 8;
+
+#[derive(Debug)]
+pub struct ShortlinkService;
+
+impl ShortlinkService {
+    /// Gets the shortlink for a page or page revision, creating it if it doesn't exist.
+    pub async fn get_or_create(
+        ctx: &ServiceContext<'_>,
+        CreateShortlink {
+            site_id,
+            page: reference,
+            revision_number,
+        }: CreateShortlink<'_>,
+    ) -> Result<ShortlinkOutput> {
+        let txn = ctx.transaction();
+        let PageModel { page_id, .. } = PageService::get(ctx, site_id, reference).await?;
+        let revision_id = match revision_number {
+            None => None,
+            Some(revision_number) => {
+                let revision =
+                    PageRevisionService::get(ctx, site_id, page_id, revision_number)
+                        .await?;
+
+                Some(revision.revision_id)
+            }
+        };
+
+        info!(
+            "Getting or creating shortlink for page ID {page_id}, revision ID {revision_id:?}",
+        );
+
+        let site = SiteService::get(ctx, Reference::Id(site_id)).await?;
+        let existing = Shortlink::find()
+            .filter(
+                Condition::all()
+                    .add(shortlink::Column::PageId.eq(page_id))
+                    .add(match revision_id {
+                        Some(revision_id) => {
+                            shortlink::Column::RevisionId.eq(revision_id)
+                        }
+                        None => shortlink::Column::RevisionId.is_null(),
+                    }),
+            )
+            .one(txn)
+            .await?;
+
+        let shortlink = match existing {
+            Some(shortlink) => shortlink,
+            None => {
+                let code = Self::new_code(ctx).await?;
+                let model = shortlink::ActiveModel {
+                    code: Set(code),
+                    site_id: Set(site_id),
+                    page_id: Set(page_id),
+                    revision_id: Set(revision_id),
+                    ..Default::default()
+                };
+
+                // If another request created the link in the meantime, return theirs.
+                // Page-level links have a partial index, since NULLs are distinct.
+                let on_conflict = match revision_id {
+                    None => OnConflict::column(shortlink::Column::PageId)
+                        .target_and_where(shortlink::Column::RevisionId.is_null())
+                        .update_column(shortlink::Column::PageId)
+                        .to_owned(),
+                    Some(_) => OnConflict::columns([
+                        shortlink::Column::PageId,
+                        shortlink::Column::RevisionId,
+                    ])
+                    .update_column(shortlink::Column::PageId)
+                    .to_owned(),
+                };
+
+                Shortlink::insert(model)
+                    .on_conflict(on_conflict)
+                    .exec_with_returning(txn)
+                    .await?
+            }
+        };
+
+        let url = Self::build_url(ctx.config(), &site, &shortlink.code);
+        Ok(ShortlinkOutput { shortlink, url })
+    }
+
+    pub async fn get_optional(
+        ctx: &ServiceContext<'_>,
+        code: &str,
+    ) -> Result<Option<ShortlinkModel>> {
+        let txn = ctx.transaction();
+        let shortlink = Shortlink::find()
+            .filter(shortlink::Column::Code.eq(code))
+            .one(txn)
+            .await?;
+
+        Ok(shortlink)
+    }
+
+    #[inline]
+    #[allow(dead_code)] // TEMP
+    pub async fn get(ctx: &ServiceContext<'_>, code: &str) -> Result<ShortlinkModel> {
+        find_or_error!(Self::get_optional(ctx, code), Shortlink)
+    }
+
+    /// Gets all shortlinks for a page, with their click counts.
+    pub async fn get_all_for_page(
+        ctx: &ServiceContext<'_>,
+        GetPageShortlinks {
+            site_id,
+            page: reference,
+        }: GetPageShortlinks<'_>,
+    ) -> Result<Vec<ShortlinkOutput>> {
+        let txn = ctx.transaction();
+        let PageModel { page_id, .. } = PageService::get(ctx, site_id, reference).await?;
+        let site = SiteService::get(ctx, Reference::Id(site_id)).await?;
+
+        let shortlinks = Shortlink::find()
+            .filter(shortlink::Column::PageId.eq(page_id))
+            .order_by_asc(shortlink::Column::CreatedAt)
+            .all(txn)
+            .await?
+            .into_iter()
+            .map(|shortlink| {
+                let url = Self::build_url(ctx.config(), &site, &shortlink.code);
+                ShortlinkOutput { shortlink, url }
+            })
+            .collect();
+
+        Ok(shortlinks)
+    }
+
+    /// Finds where a shortlink points to, and records a click on it.
+    ///
+    /// The domain must be either the site's short domain or one of its regular
+    /// domains, so that one site's short domain cannot be used for another's links.
+    ///
+    /// Returns `None` if the code does not exist, or the page has since been deleted.
+    pub async fn resolve(
+        ctx: &ServiceContext<'_>,
+        ResolveShortlink { domain, code }: ResolveShortlink,
+    ) -> Result<Option<ResolveShortlinkOutput>> {
+        info!("Resolving shortlink '{code}' from domain '{domain}'");
+
+        let shortlink = match Self::get_optional(ctx, &code).await? {
+            Some(shortlink) => shortlink,
+            None => return Ok(None),
+        };
+
+        let site = SiteService::get(ctx, Reference::Id(shortlink.site_id)).await?;
+        let domain_matches = match site.shortlink_domain {
+            Some(ref shortlink_domain) if shortlink_domain == &domain => true,
+            _ => match DomainService::site_from_domain_optional(ctx, &domain).await? {
+                Some(domain_site) => domain_site.site_id == site.site_id,
+                None => false,
+            },
+        };
+
+        if !domain_matches {
+            warn!(
+                "Shortlink '{code}' is for site ID {}, not domain '{domain}'",
+                site.site_id,
+            );
+            return Ok(None);
+        }
+
+        let page = match PageService::get_direct_optional(ctx, shortlink.page_id, false)
+            .await?
+        {
+            Some(page) => page,
+            None => {
+                warn!("Page ID {} for shortlink is deleted", shortlink.page_id);
+                return Ok(None);
+            }
+        };
+
+        let site_domain = DomainService::domain_for_site(ctx.config(), &site);
+        let url = match shortlink.revision_id {
+            None => format!("https://{site_domain}/{}", page.slug),
+            Some(revision_id) => {
+                let revision = PageRevisionService::get_direct(ctx, revision_id).await?;
+                format!(
+                    "https://{site_domain}/{}/revision/{}",
+                    page.slug, revision.revision_number,
+                )
+            }
+        };
+
+        // Record click
+        let txn = ctx.transaction();
+        Shortlink::update_many()
+            .col_expr(
+                shortlink::Column::Clicks,
+                Expr::col(shortlink::Column::Clicks).add(1),
+            )
+            .col_expr(shortlink::Column::LastClickedAt, Expr::value(now()))
+            .filter(shortlink::Column::ShortlinkId.eq(shortlink.shortlink_id))
+            .exec(txn)
+            .await?;
+
+        Ok(Some(ResolveShortlinkOutput {
+            site_id: site.site_id,
+            page_id: page.page_id,
+            revision_id: shortlink.revision_id,
+            url,
+        }))
+    }
+
+    /// Builds the full URL for a shortlink.
+    pub fn build_url(config: &Config, site: &SiteModel, code: &str) -> String {
+        match site.shortlink_domain {
+            Some(ref domain) => format!("https://{domain}/{code}"),
+            None => {
+                let domain = DomainService::domain_for_site(config, site);
+                format!("https://{domain}/-/s/{code}")
+            }
+        }
+    }
+
+    /// Generates a new random shortlink code which is not yet in use.
+    async fn new_code(ctx: &ServiceContext<'_>) -> Result<String> {
+        let length = ctx.config().shortlink_code_length;
+
+        for _ in 0..MAX_CODE_ATTEMPTS {
+            let code = new_token(length);
+            if Self::get_optional(ctx, &code).await?.is_none() {
+                return Ok(code);
+            }
+
+            debug!("Generated shortlink code already exists, retrying");
+        }
+
+        error!("Unable to generate unused shortlink code, length {length} is too short");
+        Err(Error::ShortlinkCodeExhausted)
+    }
+}
@@ -0,0 +1,60 @@
+/*
+ * services/shortlink/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::models::shortlink::Model as ShortlinkModel;
+use crate::web::Reference;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CreateShortlink<'a> {
+    pub site_id: i64,
+    pub page: Reference<'a>,
+
+    /// If set, the shortlink points to this specific revision of the page.
+    #[serde(default)]
+    pub revision_number: Option<i64>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetPageShortlinks<'a> {
+    pub site_id: i64,
+    pub page: Reference<'a>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ShortlinkOutput {
+    #[serde(flatten)]
+    pub shortlink: ShortlinkModel,
+    pub url: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ResolveShortlink {
+    /// The domain the shortlink was requested from.
+    pub domain: String,
+    pub code: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ResolveShortlinkOutput {
+    pub site_id: i64,
+    pub page_id: i64,
+    pub revision_id: Option<i64>,
+    pub url: String,
+}
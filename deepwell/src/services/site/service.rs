@@ -27,7 +27,9 @@ use crate::models::site::{self, Entity as Site, Model as SiteModel};
 use crate::services::alias::CreateAlias;
 use crate::services::relation::CreateSiteUser;
 use crate::services::user::{CreateUser, UpdateUserBody};
-use crate::services::{AliasService, RelationService, UserService};
+use crate::services::{
+    AliasService, DomainService, RelationService, RenderConfigService, UserService,
+};
 use crate::utils::validate_locale;
 
 #[derive(Debug)]
@@ -120,9 +122,20 @@ impl SiteService {
         updating_user_id: i64,
     ) -> Result<SiteModel> {
         let txn = ctx.transaction();
-        let site = Self::get(ctx, reference).await?;
+        let SiteModel { site_id, .. } = Self::get(ctx, reference).await?;
+
+        // Lock the row for the rest of the transaction, see UserService::update()
+        let site = Site::find_by_id(site_id)
+            .lock_exclusive()
+            .one(txn)
+            .await?
+            .ok_or(Error::SiteNotFound)?;
+
+        check_version(input.expected_version, site.version, &site)?;
+
         let mut model = site::ActiveModel {
             site_id: Set(site.site_id),
+            version: Set(site.version + 1),
             ..Default::default()
         };
 
@@ -156,9 +169,80 @@ impl SiteService {
             site_user_body.locales = ProvidedValue::Set(vec![locale]);
         }
 
+        if let ProvidedValue::Set(shortlink_domain) = input.shortlink_domain {
+            if let Some(ref domain) = shortlink_domain {
+                Self::check_shortlink_domain(ctx, site.site_id, domain).await?;
+            }
+
+            model.shortlink_domain = Set(shortlink_domain);
+        }
+
+        if let ProvidedValue::Set(serve_stable_revisions) = input.serve_stable_revisions {
+            model.serve_stable_revisions = Set(serve_stable_revisions);
+        }
+
+        if let ProvidedValue::Set(strip_image_metadata) = input.strip_image_metadata {
+            model.strip_image_metadata = Set(strip_image_metadata);
+        }
+
+        if let ProvidedValue::Set(allow_svg_uploads) = input.allow_svg_uploads {
+            model.allow_svg_uploads = Set(allow_svg_uploads);
+        }
+
+        if let ProvidedValue::Set(parser_profile) = input.parser_profile {
+            model.parser_profile = Set(parser_profile);
+        }
+
+        if let ProvidedValue::Set(heading_id_strategy) = input.heading_id_strategy {
+            model.heading_id_strategy = Set(heading_id_strategy);
+        }
+
+        if let ProvidedValue::Set(allow_embeds) = input.allow_embeds {
+            model.allow_embeds = Set(allow_embeds);
+        }
+
+        if let ProvidedValue::Set(allow_css) = input.allow_css {
+            model.allow_css = Set(allow_css);
+        }
+
+        if let ProvidedValue::Set(allow_external_images) = input.allow_external_images {
+            model.allow_external_images = Set(allow_external_images);
+        }
+
+        if let ProvidedValue::Set(rating_module) = input.rating_module {
+            model.rating_module = Set(rating_module);
+        }
+
+        if let ProvidedValue::Set(comments_module) = input.comments_module {
+            model.comments_module = Set(comments_module);
+        }
+
+        if let ProvidedValue::Set(retention_days) = input.file_revision_retention_days {
+            if matches!(retention_days, Some(days) if days <= 0) {
+                error!("File revision retention must be at least one day");
+                return Err(Error::BadRequest);
+            }
+
+            model.file_revision_retention_days = Set(retention_days);
+        }
+
+        if let ProvidedValue::Set(image_conversion) = input.image_conversion {
+            model.image_conversion = Set(image_conversion);
+        }
+
+        if let ProvidedValue::Set(quality) = input.image_conversion_quality {
+            if !(1..=100).contains(&quality) {
+                error!("Image conversion quality must be between 1 and 100");
+                return Err(Error::BadRequest);
+            }
+
+            model.image_conversion_quality = Set(quality);
+        }
+
         // Update site
         model.updated_at = Set(Some(now()));
         let new_site = model.update(txn).await?;
+        RenderConfigService::invalidate(ctx, new_site.site_id).await?;
 
         // Update site user
         UserService::update(ctx, Reference::Id(site_user_id), site_user_body).await?;
@@ -175,6 +259,45 @@ impl SiteService {
         Ok(new_site)
     }
 
+    /// Ensures a short domain isn't already used by any site, for any purpose.
+    async fn check_shortlink_domain(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        domain: &str,
+    ) -> Result<()> {
+        info!(
+            "Checking if shortlink domain '{domain}' is available for site ID {site_id}"
+        );
+
+        if DomainService::parse_canonical(ctx.config(), domain).is_some()
+            || DomainService::custom_domain_exists(ctx, domain).await?
+        {
+            error!("Shortlink domain '{domain}' is already a site domain");
+            return Err(Error::CustomDomainExists);
+        }
+
+        let txn = ctx.transaction();
+        let other_site = Site::find()
+            .filter(
+                Condition::all()
+                    .add(site::Column::ShortlinkDomain.eq(domain))
+                    .add(site::Column::SiteId.ne(site_id)),
+            )
+            .one(txn)
+            .await?;
+
+        match other_site {
+            Some(other_site) => {
+                error!(
+                    "Shortlink domain '{domain}' is already used by site ID {}",
+                    other_site.site_id,
+                );
+                Err(Error::CustomDomainExists)
+            }
+            _ => Ok(()),
+        }
+    }
+
     /// Updates the slug for a site, leaving behind an alias.
     ///
     /// No alias row checks are performed because of a dependency order requiring
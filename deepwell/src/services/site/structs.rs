@@ -19,6 +19,9 @@
  */
 
 use crate::models::alias::Model as AliasModel;
+use crate::models::sea_orm_active_enums::{
+    HeadingIdStrategy, ImageConversionFormat, ModuleState, ParserProfile,
+};
 use crate::models::site::Model as SiteModel;
 use crate::models::site_domain::Model as SiteDomainModel;
 use crate::web::{ProvidedValue, Reference};
@@ -69,4 +72,53 @@ pub struct UpdateSiteBody {
     pub tagline: ProvidedValue<String>,
     pub description: ProvidedValue<String>,
     pub locale: ProvidedValue<String>,
+    pub shortlink_domain: ProvidedValue<Option<String>>,
+
+    /// Whether pages and files with a stable revision are served at that revision.
+    pub serve_stable_revisions: ProvidedValue<bool>,
+
+    /// Whether to remove metadata, such as EXIF location data, from uploaded images.
+    pub strip_image_metadata: ProvidedValue<bool>,
+
+    /// Whether SVG images may be uploaded.
+    ///
+    /// When allowed, scripts and external references are removed from them.
+    pub allow_svg_uploads: ProvidedValue<bool>,
+
+    /// How leniently the wikitext of pages is parsed.
+    pub parser_profile: ProvidedValue<ParserProfile>,
+
+    /// How the IDs of headings are generated.
+    pub heading_id_strategy: ProvidedValue<HeadingIdStrategy>,
+
+    /// Whether content from other websites may be embedded in pages.
+    pub allow_embeds: ProvidedValue<bool>,
+
+    /// Whether pages may use their own CSS.
+    pub allow_css: ProvidedValue<bool>,
+
+    /// Whether pages may show images from other websites.
+    pub allow_external_images: ProvidedValue<bool>,
+
+    /// Whether the rating module is shown on pages.
+    pub rating_module: ProvidedValue<ModuleState>,
+
+    /// Whether the comments module is shown on pages.
+    pub comments_module: ProvidedValue<ModuleState>,
+
+    /// How many days to keep the contents of intermediate file revisions for.
+    ///
+    /// If `None`, they are kept forever. See `FileRetentionService`.
+    pub file_revision_retention_days: ProvidedValue<Option<i32>>,
+
+    /// Which format uploaded PNG and JPEG images are converted to for serving.
+    ///
+    /// If `None`, images are only served as uploaded.
+    pub image_conversion: ProvidedValue<Option<ImageConversionFormat>>,
+
+    /// The quality of converted images, from 1 to 100.
+    pub image_conversion_quality: ProvidedValue<i32>,
+
+    /// If set, the update fails unless the site is still at this version.
+    pub expected_version: Option<i32>,
 }
@@ -22,6 +22,10 @@
 //!
 //! This encompasses per-site configurable templates such as `_404`,
 //! which display custom "this page does not exist" content.
+//!
+//! Sites may also store per-locale overrides for each special page type,
+//! which take precedence over both their special pages and the instance's
+//! localized fallback messages.
 
 mod prelude {
     pub use super::super::prelude::*;
@@ -20,8 +20,15 @@
 
 use super::prelude::*;
 use crate::models::site::Model as SiteModel;
-use crate::services::{PageRevisionService, PageService, RenderService, TextService};
-use crate::utils::split_category;
+use crate::models::special_page_override::{
+    self, Entity as SpecialPageOverride, Model as SpecialPageOverrideModel,
+};
+use crate::services::render::RenderPriority;
+use crate::services::{
+    PageRevisionService, PageService, RenderConfigService, RenderService, SiteService,
+    TextService,
+};
+use crate::utils::{split_category, validate_locale};
 use crate::web::Reference;
 use fluent::{FluentArgs, FluentValue};
 use ftml::prelude::*;
@@ -33,7 +40,48 @@ use unic_langid::LanguageIdentifier;
 pub struct SpecialPageService;
 
 impl SpecialPageService {
+    /// Gets and renders the specified special page for a site, as would be shown on `slug`.
+    pub async fn get_rendered(
+        ctx: &ServiceContext<'_>,
+        GetSpecialPage {
+            site_id,
+            page_type,
+            locales: locales_str,
+            slug,
+        }: GetSpecialPage,
+    ) -> Result<GetSpecialPageOutput> {
+        let locales = locales_str
+            .iter()
+            .map(|locale| validate_locale(locale))
+            .collect::<Result<Vec<_>>>()?;
+
+        if locales.is_empty() {
+            return Err(Error::NoLocalesSpecified);
+        }
+
+        let site = SiteService::get(ctx, Reference::Id(site_id)).await?;
+        let (category_slug, page_slug) = split_category(&slug);
+        let page_info = PageInfo {
+            page: cow!(page_slug),
+            category: cow_opt!(category_slug),
+            site: cow!(&site.slug),
+            title: cow!(page_slug),
+            alt_title: None,
+            score: ScoreValue::Integer(0),
+            tags: vec![],
+            language: Cow::Owned(str!(&locales[0])),
+            utc_offset: None,
+        };
+
+        Self::get(ctx, &site, page_type, &locales, page_info).await
+    }
+
     /// Gets the specified special page, or the fallback if it doesn't exist.
+    ///
+    /// The wikitext is resolved in the following order:
+    /// 1. The site's override for this page type, for the first requested locale that has one.
+    /// 2. The site's special page (e.g. `_404`), category-specific ones first.
+    /// 3. The instance's localized default message.
     pub async fn get(
         ctx: &ServiceContext<'_>,
         site: &SiteModel,
@@ -72,11 +120,15 @@ impl SpecialPageService {
             SpecialPageType::Banned => {
                 (vec![cow!(config.special_page_banned)], "wiki-page-banned")
             }
+            SpecialPageType::Join => {
+                (vec![cow!(config.special_page_join)], "wiki-page-join")
+            }
         };
 
         // Look through each option to get the special page wikitext.
         let wikitext = Self::get_wikitext(
             ctx,
+            sp_page_type,
             &slugs,
             translate_key,
             site.site_id,
@@ -88,9 +140,21 @@ impl SpecialPageService {
         // Render here with relevant page context.
         // The "page" here is what would've been there in this case,
         // passed in by the caller.
-        let settings = WikitextSettings::from_mode(WikitextMode::Page);
-        let render_output =
-            RenderService::render(ctx, wikitext.clone(), &page_info, &settings).await?;
+        let settings = RenderConfigService::wikitext_settings(
+            ctx,
+            site.site_id,
+            page_info.category.as_deref().unwrap_or("_default"),
+            WikitextMode::Page,
+        )
+        .await?;
+        let render_output = RenderService::render(
+            ctx,
+            wikitext.clone(),
+            &page_info,
+            &settings,
+            RenderPriority::Normal,
+        )
+        .await?;
 
         Ok(GetSpecialPageOutput {
             wikitext,
@@ -123,6 +187,7 @@ impl SpecialPageService {
 
     async fn get_wikitext(
         ctx: &ServiceContext<'_>,
+        sp_page_type: SpecialPageType,
         slugs: &[Cow<'_, str>],
         translate_key: &str,
         site_id: i64,
@@ -135,6 +200,13 @@ impl SpecialPageService {
             "No slugs to check for special page existence",
         );
 
+        // Check for site overrides in each requested locale.
+        if let Some(wikitext) =
+            Self::get_override_wikitext(ctx, site_id, sp_page_type, locales).await?
+        {
+            return Ok(wikitext);
+        }
+
         // Try all the pages listed.
         for slug in slugs {
             if let Some(page) =
@@ -168,4 +240,161 @@ impl SpecialPageService {
 
         Ok(wikitext.into_owned())
     }
+
+    async fn get_override_wikitext(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        sp_page_type: SpecialPageType,
+        locales: &[LanguageIdentifier],
+    ) -> Result<Option<String>> {
+        // Build the list of locales to check, in order of preference.
+        // After each full locale, also check its bare language,
+        // so that an "fr" override applies to "fr-CA" requests.
+        let mut candidates = Vec::with_capacity(locales.len() * 2);
+        for locale in locales {
+            for candidate in [locale.to_string(), locale.language.to_string()] {
+                if !candidates.contains(&candidate) {
+                    candidates.push(candidate);
+                }
+            }
+        }
+
+        if candidates.is_empty() {
+            return Ok(None);
+        }
+
+        let txn = ctx.transaction();
+        let overrides = SpecialPageOverride::find()
+            .filter(
+                Condition::all()
+                    .add(special_page_override::Column::SiteId.eq(site_id))
+                    .add(special_page_override::Column::PageType.eq(sp_page_type.value()))
+                    .add(special_page_override::Column::Locale.is_in(candidates.clone())),
+            )
+            .all(txn)
+            .await?;
+
+        for candidate in &candidates {
+            if let Some(model) = overrides.iter().find(|model| &model.locale == candidate)
+            {
+                debug!("Using site override for special page in locale {candidate}");
+                let wikitext = TextService::get(ctx, &model.wikitext_hash).await?;
+                return Ok(Some(wikitext));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Gets the site's override for this special page in exactly the given locale, if any.
+    pub async fn get_override(
+        ctx: &ServiceContext<'_>,
+        GetSpecialPageOverride {
+            site_id,
+            page_type,
+            locale,
+        }: GetSpecialPageOverride,
+    ) -> Result<Option<SpecialPageOverrideOutput>> {
+        info!(
+            "Getting special page override {page_type:?} for site ID {site_id} in locale {locale}",
+        );
+
+        let locale = validate_locale(&locale)?.to_string();
+        let txn = ctx.transaction();
+        let model =
+            SpecialPageOverride::find_by_id((site_id, str!(page_type.value()), locale))
+                .one(txn)
+                .await?;
+
+        match model {
+            None => Ok(None),
+            Some(SpecialPageOverrideModel {
+                locale,
+                created_at,
+                updated_at,
+                wikitext_hash,
+                ..
+            }) => {
+                let wikitext = TextService::get(ctx, &wikitext_hash).await?;
+                Ok(Some(SpecialPageOverrideOutput {
+                    page_type,
+                    locale,
+                    created_at,
+                    updated_at,
+                    wikitext,
+                }))
+            }
+        }
+    }
+
+    /// Sets the site's override for this special page in the given locale.
+    pub async fn set_override(
+        ctx: &ServiceContext<'_>,
+        SetSpecialPageOverride {
+            site_id,
+            page_type,
+            locale,
+            wikitext,
+        }: SetSpecialPageOverride,
+    ) -> Result<()> {
+        info!(
+            "Setting special page override {page_type:?} for site ID {site_id} in locale {locale}",
+        );
+
+        let locale = validate_locale(&locale)?.to_string();
+        let txn = ctx.transaction();
+        let exists = SpecialPageOverride::find_by_id((
+            site_id,
+            str!(page_type.value()),
+            locale.clone(),
+        ))
+        .one(txn)
+        .await?
+        .is_some();
+
+        let wikitext_hash = TextService::create(ctx, wikitext).await?;
+        let mut model = special_page_override::ActiveModel {
+            site_id: Set(site_id),
+            page_type: Set(str!(page_type.value())),
+            locale: Set(locale),
+            wikitext_hash: Set(wikitext_hash.to_vec()),
+            ..Default::default()
+        };
+
+        if exists {
+            model.updated_at = Set(Some(now()));
+            model.update(txn).await?;
+        } else {
+            model.insert(txn).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Removes the site's override for this special page in the given locale.
+    pub async fn remove_override(
+        ctx: &ServiceContext<'_>,
+        GetSpecialPageOverride {
+            site_id,
+            page_type,
+            locale,
+        }: GetSpecialPageOverride,
+    ) -> Result<()> {
+        info!(
+            "Removing special page override {page_type:?} for site ID {site_id} in locale {locale}",
+        );
+
+        let locale = validate_locale(&locale)?.to_string();
+        let txn = ctx.transaction();
+        let DeleteResult { rows_affected, .. } =
+            SpecialPageOverride::delete_by_id((site_id, str!(page_type.value()), locale))
+                .exec(txn)
+                .await?;
+
+        if rows_affected == 1 {
+            Ok(())
+        } else {
+            Err(Error::SpecialPageOverrideNotFound)
+        }
+    }
 }
@@ -19,18 +19,67 @@
  */
 
 use crate::services::render::RenderOutput;
+use time::OffsetDateTime;
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
 #[serde(rename_all = "kebab-case")]
 pub enum SpecialPageType {
     Template,
     Missing,
     Private,
     Banned,
+    Join,
 }
 
-#[derive(Serialize, Debug)]
+impl SpecialPageType {
+    /// Get the constant string value used to represent this type in the database.
+    pub fn value(self) -> &'static str {
+        match self {
+            SpecialPageType::Template => "template",
+            SpecialPageType::Missing => "missing",
+            SpecialPageType::Private => "private",
+            SpecialPageType::Banned => "banned",
+            SpecialPageType::Join => "join",
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetSpecialPage {
+    pub site_id: i64,
+    pub page_type: SpecialPageType,
+    pub locales: Vec<String>,
+
+    /// The slug of the page this special page is being shown for.
+    pub slug: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
 pub struct GetSpecialPageOutput {
     pub wikitext: String,
     pub render_output: RenderOutput,
 }
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetSpecialPageOverride {
+    pub site_id: i64,
+    pub page_type: SpecialPageType,
+    pub locale: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct SpecialPageOverrideOutput {
+    pub page_type: SpecialPageType,
+    pub locale: String,
+    pub created_at: OffsetDateTime,
+    pub updated_at: Option<OffsetDateTime>,
+    pub wikitext: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SetSpecialPageOverride {
+    pub site_id: i64,
+    pub page_type: SpecialPageType,
+    pub locale: String,
+    pub wikitext: String,
+}
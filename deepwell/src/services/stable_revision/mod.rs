@@ -0,0 +1,40 @@
+/*
+ * services/stable_revision/mod.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The stable revision service, for serving reviewed revisions publicly.
+//!
+//! A page or file can have one of its revisions marked as stable. On sites
+//! which have enabled it, that revision is what readers see, while newer
+//! revisions continue to accumulate until a reviewer marks one of them as
+//! stable in turn. This is similar to MediaWiki's "flagged revisions".
+//!
+//! Pages and files without a stable revision are served at their latest
+//! revision, as are all pages on sites which haven't enabled this.
+
+mod prelude {
+    pub use super::super::prelude::*;
+    pub use super::structs::*;
+}
+
+mod service;
+mod structs;
+
+pub use self::service::StableRevisionService;
+pub use self::structs::*;
@@ -0,0 +1,243 @@
+/*
+ * services/stable_revision/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::file::{self, Model as FileModel};
+use crate::models::file_revision::{Entity as FileRevision, Model as FileRevisionModel};
+use crate::models::page::{self, Model as PageModel};
+use crate::models::page_revision::Model as PageRevisionModel;
+use crate::models::site::Model as SiteModel;
+use crate::services::file_revision::GetFileRevision;
+use crate::services::revision_comparison::CreateRevisionComparison;
+use crate::services::{
+    FileRevisionService, FileService, OutdateService, PageRevisionService, PageService,
+    RevisionComparisonService,
+};
+
+#[derive(Debug)]
+pub struct StableRevisionService;
+
+impl StableRevisionService {
+    /// Marks a page revision as stable, or clears the page's stable revision.
+    pub async fn set_page(
+        ctx: &ServiceContext<'_>,
+        SetStablePageRevision {
+            site_id,
+            page: reference,
+            revision_number,
+        }: SetStablePageRevision<'_>,
+    ) -> Result<SetStableRevisionOutput> {
+        let txn = ctx.transaction();
+        let page = PageService::get(ctx, site_id, reference).await?;
+
+        info!(
+            "Setting stable revision for page ID {} in site ID {site_id} to {revision_number:?}",
+            page.page_id,
+        );
+
+        let stable_revision_id = match revision_number {
+            None => None,
+            Some(revision_number) => {
+                let revision =
+                    PageRevisionService::get(ctx, site_id, page.page_id, revision_number)
+                        .await?;
+
+                check_hidden(&revision.hidden)?;
+                Some(revision.revision_id)
+            }
+        };
+
+        let model = page::ActiveModel {
+            page_id: Set(page.page_id),
+            stable_revision_id: Set(stable_revision_id),
+            ..Default::default()
+        };
+        model.update(txn).await?;
+
+        // What the public sees may have changed
+        OutdateService::outdate_public_page(ctx, site_id, page.page_id).await?;
+
+        Ok(SetStableRevisionOutput {
+            stable_revision_id,
+            stable_revision_number: revision_number,
+        })
+    }
+
+    /// Marks a file revision as stable, or clears the file's stable revision.
+    pub async fn set_file(
+        ctx: &ServiceContext<'_>,
+        SetStableFileRevision {
+            site_id,
+            page_id,
+            file_id,
+            revision_number,
+        }: SetStableFileRevision,
+    ) -> Result<SetStableRevisionOutput> {
+        info!(
+            "Setting stable revision for file ID {file_id} in site ID {site_id} to {revision_number:?}",
+        );
+
+        let txn = ctx.transaction();
+        let file = FileService::get_direct(ctx, file_id, false).await?;
+        if file.site_id != site_id || file.page_id != page_id {
+            warn!("File's site or page ID does not match passed values");
+            return Err(Error::FileNotFound);
+        }
+
+        let stable_revision_id = match revision_number {
+            None => None,
+            Some(revision_number) => {
+                let revision = FileRevisionService::get(
+                    ctx,
+                    GetFileRevision {
+                        site_id,
+                        page_id,
+                        file_id,
+                        revision_number,
+                    },
+                )
+                .await?;
+
+                check_hidden(&revision.hidden)?;
+                Some(revision.revision_id)
+            }
+        };
+
+        let model = file::ActiveModel {
+            file_id: Set(file_id),
+            stable_revision_id: Set(stable_revision_id),
+            ..Default::default()
+        };
+        model.update(txn).await?;
+
+        Ok(SetStableRevisionOutput {
+            stable_revision_id,
+            stable_revision_number: revision_number,
+        })
+    }
+
+    /// Gets the revision of a page which should be shown to readers.
+    ///
+    /// This is the stable revision if the site serves them and the page has one,
+    /// otherwise the latest revision.
+    pub async fn get_page_revision(
+        ctx: &ServiceContext<'_>,
+        site: &SiteModel,
+        page: &PageModel,
+    ) -> Result<PageRevisionModel> {
+        match page.stable_revision_id {
+            Some(revision_id) if site.serve_stable_revisions => {
+                debug!(
+                    "Serving stable revision ID {revision_id} for page ID {}",
+                    page.page_id,
+                );
+
+                PageRevisionService::get_direct(ctx, revision_id).await
+            }
+            _ => PageRevisionService::get_latest(ctx, site.site_id, page.page_id).await,
+        }
+    }
+
+    /// Gets the revision of a file which should be shown to readers.
+    ///
+    /// See `get_page_revision()`.
+    pub async fn get_file_revision(
+        ctx: &ServiceContext<'_>,
+        site: &SiteModel,
+        file: &FileModel,
+    ) -> Result<FileRevisionModel> {
+        match file.stable_revision_id {
+            Some(revision_id) if site.serve_stable_revisions => {
+                debug!(
+                    "Serving stable revision ID {revision_id} for file ID {}",
+                    file.file_id,
+                );
+
+                let txn = ctx.transaction();
+                FileRevision::find_by_id(revision_id)
+                    .one(txn)
+                    .await?
+                    .ok_or(Error::FileRevisionNotFound)
+            }
+            _ => {
+                FileRevisionService::get_latest(
+                    ctx,
+                    site.site_id,
+                    file.page_id,
+                    file.file_id,
+                )
+                .await
+            }
+        }
+    }
+
+    /// Compares a page's stable revision to its latest one, for reviewers.
+    ///
+    /// Returns `None` if the page has no stable revision,
+    /// or if there have been no changes since it.
+    pub async fn get_pending_changes(
+        ctx: &ServiceContext<'_>,
+        GetPendingChanges {
+            site_id,
+            page: reference,
+        }: GetPendingChanges<'_>,
+    ) -> Result<Option<PendingChanges>> {
+        let page = PageService::get(ctx, site_id, reference).await?;
+        let stable_revision_id = match page.stable_revision_id {
+            Some(revision_id) => revision_id,
+            None => return Ok(None),
+        };
+
+        let (stable, latest) = try_join!(
+            PageRevisionService::get_direct(ctx, stable_revision_id),
+            PageRevisionService::get_latest(ctx, site_id, page.page_id),
+        )?;
+
+        if stable.revision_number >= latest.revision_number {
+            return Ok(None);
+        }
+
+        let comparison = RevisionComparisonService::get_or_create(
+            ctx,
+            CreateRevisionComparison {
+                site_id,
+                page: Reference::Id(page.page_id),
+                from_revision_number: stable.revision_number,
+                to_revision_number: latest.revision_number,
+            },
+        )
+        .await?;
+
+        Ok(Some(PendingChanges {
+            stable_revision_number: stable.revision_number,
+            latest_revision_number: latest.revision_number,
+            comparison,
+        }))
+    }
+}
+
+fn check_hidden(hidden: &[String]) -> Result<()> {
+    if hidden.is_empty() {
+        Ok(())
+    } else {
+        error!("Revision has hidden fields {hidden:?}, cannot be stable");
+        Err(Error::StableRevisionHidden)
+    }
+}
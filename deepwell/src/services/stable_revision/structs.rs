@@ -0,0 +1,61 @@
+/*
+ * services/stable_revision/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::services::revision_comparison::RevisionComparison;
+use crate::web::Reference;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SetStablePageRevision<'a> {
+    pub site_id: i64,
+    pub page: Reference<'a>,
+
+    /// The revision to serve, or `None` to go back to serving the latest.
+    pub revision_number: Option<i64>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SetStableFileRevision {
+    pub site_id: i64,
+    pub page_id: i64,
+    pub file_id: i64,
+
+    /// The revision to serve, or `None` to go back to serving the latest.
+    pub revision_number: Option<i64>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct SetStableRevisionOutput {
+    pub stable_revision_id: Option<i64>,
+    pub stable_revision_number: Option<i64>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetPendingChanges<'a> {
+    pub site_id: i64,
+    pub page: Reference<'a>,
+}
+
+/// The changes made to a page since its stable revision, awaiting review.
+#[derive(Serialize, Debug, Clone)]
+pub struct PendingChanges {
+    pub stable_revision_number: i64,
+    pub latest_revision_number: i64,
+    pub comparison: RevisionComparison,
+}
@@ -0,0 +1,44 @@
+/*
+ * services/storage_quota/mod.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The storage quota service, for limiting how much file data each site stores.
+//!
+//! Each site's total is kept in `site_storage`, and is updated as file revisions
+//! with new contents are created, or have their contents purged. This means the
+//! total never has to be recomputed from every revision the site has.
+//!
+//! Blobs are shared by content, so the same data may be stored only once even if
+//! uploaded several times. However, each upload still counts in full, so that
+//! a site's usage doesn't depend on what other sites happen to have uploaded.
+//!
+//! Uploads which would put a site over its quota fail with
+//! `Error::StorageQuotaExceeded`. The quota itself is the storage limit
+//! from the limit service.
+
+mod prelude {
+    pub use super::super::prelude::*;
+    pub use super::structs::*;
+}
+
+mod service;
+mod structs;
+
+pub use self::service::StorageQuotaService;
+pub use self::structs::*;
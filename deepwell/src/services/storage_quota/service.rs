@@ -0,0 +1,151 @@
+/*
+ * services/storage_quota/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::file_revision::Model as FileRevisionModel;
+use crate::models::sea_orm_active_enums::{FileRevisionChange, FileRevisionType};
+use crate::models::site_storage::{self, Entity as SiteStorage};
+use crate::services::LimitService;
+use sea_query::Expr;
+
+#[derive(Debug)]
+pub struct StorageQuotaService;
+
+impl StorageQuotaService {
+    /// Gets how much a site has stored, and how much it may store.
+    pub async fn get_usage(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+    ) -> Result<SiteStorageUsage> {
+        let stored_bytes = Self::get_stored(ctx, site_id).await?;
+        let quota_bytes = LimitService::get(ctx, site_id).await?.storage_bytes;
+
+        Ok(SiteStorageUsage {
+            site_id,
+            stored_bytes,
+            quota_bytes,
+            available_bytes: quota_bytes.saturating_sub(stored_bytes),
+        })
+    }
+
+    /// Checks that the site has room to store an upload of the given size.
+    pub async fn check(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        requested: u64,
+    ) -> Result<()> {
+        let SiteStorageUsage {
+            stored_bytes: used,
+            quota_bytes: quota,
+            available_bytes,
+            ..
+        } = Self::get_usage(ctx, site_id).await?;
+
+        if requested > available_bytes {
+            warn!(
+                "Upload of {requested} bytes exceeds storage quota for site ID {site_id} ({used} of {quota} used)",
+            );
+            return Err(Error::StorageQuotaExceeded {
+                quota,
+                used,
+                requested,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Records that a site has stored more file data.
+    pub async fn add(ctx: &ServiceContext<'_>, site_id: i64, bytes: i64) -> Result<()> {
+        debug!("Adding {bytes} bytes to storage usage for site ID {site_id}");
+
+        let txn = ctx.transaction();
+        let rows_affected = SiteStorage::update_many()
+            .col_expr(
+                site_storage::Column::StoredBytes,
+                Expr::col(site_storage::Column::StoredBytes).add(bytes),
+            )
+            .col_expr(site_storage::Column::UpdatedAt, Expr::value(now()))
+            .filter(site_storage::Column::SiteId.eq(site_id))
+            .exec(txn)
+            .await?
+            .rows_affected;
+
+        // First upload for this site
+        if rows_affected == 0 {
+            let model = site_storage::ActiveModel {
+                site_id: Set(site_id),
+                stored_bytes: Set(bytes),
+                ..Default::default()
+            };
+            model.insert(txn).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Records that a site's file data has been removed from storage.
+    pub async fn remove(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        bytes: i64,
+    ) -> Result<()> {
+        debug!("Removing {bytes} bytes from storage usage for site ID {site_id}");
+
+        let txn = ctx.transaction();
+        SiteStorage::update_many()
+            .col_expr(
+                site_storage::Column::StoredBytes,
+                Expr::col(site_storage::Column::StoredBytes).sub(bytes),
+            )
+            .col_expr(site_storage::Column::UpdatedAt, Expr::value(now()))
+            .filter(site_storage::Column::SiteId.eq(site_id))
+            .exec(txn)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Whether this file revision's size counts towards its site's usage.
+    ///
+    /// Only revisions which introduced new contents count. Others, such as
+    /// renames or deletions, reuse the contents of the revision before them.
+    pub fn is_counted(revision: &FileRevisionModel) -> bool {
+        match revision.revision_type {
+            FileRevisionType::Create => true,
+            FileRevisionType::Update => {
+                revision.changes.contains(&FileRevisionChange::Blob)
+            }
+            FileRevisionType::Delete | FileRevisionType::Undelete => false,
+        }
+    }
+
+    async fn get_stored(ctx: &ServiceContext<'_>, site_id: i64) -> Result<u64> {
+        let txn = ctx.transaction();
+        let stored_bytes = SiteStorage::find_by_id(site_id)
+            .one(txn)
+            .await?
+            .map(|model| model.stored_bytes)
+            .unwrap_or(0);
+
+        // Never negative, see the CHECK constraint
+        Ok(stored_bytes as u64)
+    }
+}
@@ -0,0 +1,33 @@
+/*
+ * services/storage_quota/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+/// How much of its storage quota a site has used.
+#[derive(Serialize, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SiteStorageUsage {
+    pub site_id: i64,
+    pub stored_bytes: u64,
+    pub quota_bytes: u64,
+
+    /// How much more can be stored before reaching the quota.
+    ///
+    /// This is zero if the site is at or over its quota, which can happen
+    /// if the quota was lowered after the files were uploaded.
+    pub available_bytes: u64,
+}
@@ -0,0 +1,41 @@
+/*
+ * services/tag_rule/mod.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The tag rule service, for automatically tagging pages.
+//!
+//! Site administrators can set up rules which add or remove tags whenever a
+//! page is created or edited, provided the page matches the rule's conditions.
+//! These are the page's category, a regular expression matched against its title,
+//! and the most trusted role held on the site by the person editing it.
+//!
+//! Rules are run as part of creating the revision, after the tags provided by
+//! the editor have been applied. Any rules which ended up changing the tags are
+//! listed in the revision, so it is clear why tags were changed.
+
+mod prelude {
+    pub use super::super::prelude::*;
+    pub use super::structs::*;
+}
+
+mod service;
+mod structs;
+
+pub use self::service::TagRuleService;
+pub use self::structs::*;
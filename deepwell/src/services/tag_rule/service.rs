@@ -0,0 +1,364 @@
+/*
+ * services/tag_rule/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::tag_rule::{self, Entity as TagRule, Model as TagRuleModel};
+use crate::services::permission::PermissionRole;
+use crate::services::PermissionService;
+use crate::utils::get_category_name;
+use regex::Regex;
+
+#[derive(Debug)]
+pub struct TagRuleService;
+
+impl TagRuleService {
+    pub async fn create(
+        ctx: &ServiceContext<'_>,
+        CreateTagRule {
+            site_id,
+            category,
+            title_regex,
+            author_min_role,
+            author_max_role,
+            add_tags,
+            remove_tags,
+            description,
+        }: CreateTagRule,
+    ) -> Result<TagRuleModel> {
+        info!("Creating tag rule for site ID {site_id} because '{description}'");
+
+        if add_tags.is_empty() && remove_tags.is_empty() {
+            error!("Tag rule does not add or remove any tags");
+            return Err(Error::TagRuleInvalid);
+        }
+
+        if add_tags.iter().any(|tag| remove_tags.contains(tag)) {
+            error!("Tag rule both adds and removes the same tag");
+            return Err(Error::TagRuleInvalid);
+        }
+
+        if let (Some(min_role), Some(max_role)) = (author_min_role, author_max_role) {
+            if min_role > max_role {
+                error!("Tag rule's minimum author role is above its maximum");
+                return Err(Error::TagRuleInvalid);
+            }
+        }
+
+        if let Some(ref regex) = title_regex {
+            if let Err(error) = Regex::new(regex) {
+                error!("Passed regular expression '{regex}' pattern is invalid: {error}");
+                return Err(Error::TagRuleRegexInvalid(error));
+            }
+        }
+
+        let txn = ctx.transaction();
+        let model = tag_rule::ActiveModel {
+            site_id: Set(site_id),
+            category: Set(category),
+            title_regex: Set(title_regex),
+            author_min_role: Set(author_min_role.map(|role| str!(role.name()))),
+            author_max_role: Set(author_max_role.map(|role| str!(role.name()))),
+            add_tags: Set(add_tags),
+            remove_tags: Set(remove_tags),
+            description: Set(description),
+            ..Default::default()
+        };
+
+        let rule = model.insert(txn).await?;
+        Ok(rule)
+    }
+
+    pub async fn delete(
+        ctx: &ServiceContext<'_>,
+        DeleteTagRule { site_id, rule_id }: DeleteTagRule,
+    ) -> Result<()> {
+        info!("Deleting tag rule ID {rule_id} in site ID {site_id}");
+
+        let txn = ctx.transaction();
+        let rule = TagRule::find()
+            .filter(
+                Condition::all()
+                    .add(tag_rule::Column::RuleId.eq(rule_id))
+                    .add(tag_rule::Column::SiteId.eq(site_id))
+                    .add(tag_rule::Column::DeletedAt.is_null()),
+            )
+            .one(txn)
+            .await?;
+
+        if rule.is_none() {
+            return Err(Error::TagRuleNotFound);
+        }
+
+        let model = tag_rule::ActiveModel {
+            rule_id: Set(rule_id),
+            deleted_at: Set(Some(now())),
+            ..Default::default()
+        };
+        model.update(txn).await?;
+        Ok(())
+    }
+
+    /// Gets all the active tag rules for a site, in the order they are applied.
+    pub async fn get_all(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+    ) -> Result<Vec<TagRuleModel>> {
+        let txn = ctx.transaction();
+        let rules = TagRule::find()
+            .filter(
+                Condition::all()
+                    .add(tag_rule::Column::SiteId.eq(site_id))
+                    .add(tag_rule::Column::DeletedAt.is_null()),
+            )
+            .order_by_asc(tag_rule::Column::RuleId)
+            .all(txn)
+            .await?;
+
+        Ok(rules)
+    }
+
+    /// Runs the site's tag rules against a page being saved, modifying its tags.
+    ///
+    /// Returns the IDs of the rules which changed the tags.
+    pub async fn apply(
+        ctx: &ServiceContext<'_>,
+        TagRuleTarget {
+            site_id,
+            user_id,
+            slug,
+            title,
+        }: TagRuleTarget<'_>,
+        tags: &mut Vec<String>,
+    ) -> Result<Vec<i64>> {
+        let rules = Self::get_all(ctx, site_id).await?;
+        let category = get_category_name(slug);
+
+        // Only look up the author's roles if a rule needs them
+        let author_role = if rules.iter().any(has_author_condition) {
+            let roles = PermissionService::get_roles(ctx, site_id, user_id).await?;
+            roles.into_iter().max()
+        } else {
+            None
+        };
+
+        let mut applied = Vec::new();
+        for rule in rules {
+            if rule_matches(&rule, category, title, author_role)
+                && apply_rule(&rule, tags)
+            {
+                debug!("Tag rule ID {} changed page tags", rule.rule_id);
+                applied.push(rule.rule_id);
+            }
+        }
+
+        Ok(applied)
+    }
+}
+
+fn has_author_condition(rule: &TagRuleModel) -> bool {
+    rule.author_min_role.is_some() || rule.author_max_role.is_some()
+}
+
+/// Checks a rule's conditions against the page being saved.
+///
+/// The author's role is the most trusted one they hold on the site, if any.
+/// Rules with an invalid regular expression or role never match.
+fn rule_matches(
+    rule: &TagRuleModel,
+    category: &str,
+    title: &str,
+    author_role: Option<PermissionRole>,
+) -> bool {
+    if let Some(ref rule_category) = rule.category {
+        if rule_category != category {
+            return false;
+        }
+    }
+
+    if let Some(ref regex) = rule.title_regex {
+        match Regex::new(regex) {
+            Ok(regex) if regex.is_match(title) => (),
+            Ok(_) => return false,
+            Err(error) => {
+                warn!(
+                    "Regular expression for tag rule ID {} is invalid, skipping: {error}",
+                    rule.rule_id,
+                );
+                return false;
+            }
+        }
+    }
+
+    let parse_role = |role: &Option<String>| role.as_deref().map(str::parse).transpose();
+    let (min_role, max_role) = match (
+        parse_role(&rule.author_min_role),
+        parse_role(&rule.author_max_role),
+    ) {
+        (Ok(min_role), Ok(max_role)) => (min_role, max_role),
+        _ => {
+            warn!(
+                "Author role for tag rule ID {} is invalid, skipping",
+                rule.rule_id
+            );
+            return false;
+        }
+    };
+
+    if let Some(min_role) = min_role {
+        if author_role.is_none_or(|role| role < min_role) {
+            return false;
+        }
+    }
+
+    if let Some(max_role) = max_role {
+        if author_role.is_some_and(|role| role > max_role) {
+            return false;
+        }
+    }
+
+    true
+}
+
+/// Adds and removes a rule's tags, returning whether the tags changed.
+fn apply_rule(rule: &TagRuleModel, tags: &mut Vec<String>) -> bool {
+    let mut changed = false;
+
+    for tag in &rule.add_tags {
+        if !tags.contains(tag) {
+            tags.push(tag.clone());
+            changed = true;
+        }
+    }
+
+    let count = tags.len();
+    tags.retain(|tag| !rule.remove_tags.contains(tag));
+    changed |= tags.len() != count;
+    changed
+}
+
+#[cfg(test)]
+fn test_rule() -> TagRuleModel {
+    TagRuleModel {
+        rule_id: 1,
+        created_at: now(),
+        updated_at: None,
+        deleted_at: None,
+        site_id: 1,
+        category: None,
+        title_regex: None,
+        author_min_role: None,
+        author_max_role: None,
+        add_tags: vec![],
+        remove_tags: vec![],
+        description: str!("test"),
+    }
+}
+
+#[test]
+fn tag_rule_apply() {
+    macro_rules! check {
+        ($add:expr, $remove:expr, $tags:expr, $expected:expr, $changed:expr $(,)?) => {{
+            let rule = TagRuleModel {
+                add_tags: $add.iter().map(|tag: &&str| str!(tag)).collect(),
+                remove_tags: $remove.iter().map(|tag: &&str| str!(tag)).collect(),
+                ..test_rule()
+            };
+
+            let mut tags: Vec<String> =
+                $tags.iter().map(|tag: &&str| str!(tag)).collect();
+            let changed = apply_rule(&rule, &mut tags);
+            assert_eq!(tags, $expected, "Tags after applying rule don't match");
+            assert_eq!(changed, $changed, "Whether the tags changed doesn't match");
+        }};
+    }
+
+    check!(["scp"], [], ["tale"], ["tale", "scp"], true);
+    check!(["scp"], [], ["scp", "tale"], ["scp", "tale"], false);
+    check!(
+        [],
+        ["_needs-review"],
+        ["_needs-review", "scp"],
+        ["scp"],
+        true
+    );
+    check!([], ["_needs-review"], ["scp"], ["scp"], false);
+    check!(["scp"], ["tale"], ["tale"], ["scp"], true);
+}
+
+#[test]
+fn tag_rule_conditions() {
+    use PermissionRole::*;
+
+    let rule = test_rule();
+    assert!(rule_matches(&rule, "_default", "Title", None));
+
+    let rule = TagRuleModel {
+        category: Some(str!("scp")),
+        ..test_rule()
+    };
+    assert!(rule_matches(&rule, "scp", "Title", None));
+    assert!(!rule_matches(&rule, "_default", "Title", None));
+
+    let rule = TagRuleModel {
+        title_regex: Some(str!("^SCP-[0-9]+$")),
+        ..test_rule()
+    };
+    assert!(rule_matches(&rule, "_default", "SCP-173", None));
+    assert!(!rule_matches(&rule, "_default", "Tale", None));
+
+    let rule = TagRuleModel {
+        title_regex: Some(str!("(")),
+        ..test_rule()
+    };
+    assert!(!rule_matches(&rule, "_default", "(", None));
+
+    let rule = TagRuleModel {
+        author_min_role: Some(str!("site_member")),
+        ..test_rule()
+    };
+    assert!(!rule_matches(&rule, "_default", "Title", None));
+    assert!(!rule_matches(&rule, "_default", "Title", Some(User)));
+    assert!(rule_matches(&rule, "_default", "Title", Some(SiteMember)));
+    assert!(rule_matches(
+        &rule,
+        "_default",
+        "Title",
+        Some(InstanceAdmin)
+    ));
+
+    let rule = TagRuleModel {
+        author_max_role: Some(str!("user")),
+        ..test_rule()
+    };
+    assert!(rule_matches(&rule, "_default", "Title", None));
+    assert!(rule_matches(&rule, "_default", "Title", Some(User)));
+    assert!(!rule_matches(&rule, "_default", "Title", Some(SiteMember)));
+
+    let rule = TagRuleModel {
+        author_min_role: Some(str!("moderator")),
+        ..test_rule()
+    };
+    assert!(!rule_matches(
+        &rule,
+        "_default",
+        "Title",
+        Some(InstanceAdmin)
+    ));
+}
@@ -0,0 +1,65 @@
+/*
+ * services/tag_rule/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::services::permission::PermissionRole;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CreateTagRule {
+    pub site_id: i64,
+
+    /// The category slug pages must be in, or `_default` for the default category.
+    #[serde(default)]
+    pub category: Option<String>,
+
+    /// A regular expression which page titles must match.
+    #[serde(default)]
+    pub title_regex: Option<String>,
+
+    /// The least trusted role the person making the edit may hold on the site.
+    #[serde(default)]
+    pub author_min_role: Option<PermissionRole>,
+
+    /// The most trusted role the person making the edit may hold on the site.
+    #[serde(default)]
+    pub author_max_role: Option<PermissionRole>,
+
+    #[serde(default)]
+    pub add_tags: Vec<String>,
+
+    #[serde(default)]
+    pub remove_tags: Vec<String>,
+
+    pub description: String,
+}
+
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct DeleteTagRule {
+    pub site_id: i64,
+    pub rule_id: i64,
+}
+
+/// The page revision being created, which tag rules are checked against.
+#[derive(Debug, Copy, Clone)]
+pub struct TagRuleTarget<'a> {
+    pub site_id: i64,
+    pub user_id: i64,
+    pub slug: &'a str,
+    pub title: &'a str,
+}
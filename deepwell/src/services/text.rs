@@ -28,9 +28,12 @@ use super::prelude::*;
 use crate::hash::{k12_hash, TextHash, TEXT_HASH_LENGTH};
 use crate::models::message_draft::{self, Entity as MessageDraft};
 use crate::models::message_record::{self, Entity as MessageRecord};
+use crate::models::page_draft::{self, Entity as PageDraft};
 use crate::models::page_revision::{self, Entity as PageRevision};
+use crate::models::special_page_override::{self, Entity as SpecialPageOverride};
 use crate::models::text::{self, Entity as Text};
-use sea_query::Query;
+use sea_query::{Expr, Query};
+use std::collections::HashMap;
 
 #[derive(Debug)]
 pub struct TextService;
@@ -71,6 +74,29 @@ impl TextService {
             .map(|text| text.is_some())
     }
 
+    /// Gets the sizes of text entries in bytes, without retrieving them.
+    ///
+    /// Hashes which do not exist are not included in the returned map.
+    pub async fn sizes(
+        ctx: &ServiceContext<'_>,
+        hashes: &[&[u8]],
+    ) -> Result<HashMap<Vec<u8>, i64>> {
+        let txn = ctx.transaction();
+        let sizes: Vec<(Vec<u8>, i32)> = Text::find()
+            .select_only()
+            .column(text::Column::Hash)
+            .column_as(Expr::cust("octet_length(contents)"), "size")
+            .filter(text::Column::Hash.is_in(hashes.iter().copied()))
+            .into_tuple()
+            .all(txn)
+            .await?;
+
+        Ok(sizes
+            .into_iter()
+            .map(|(hash, size)| (hash, i64::from(size)))
+            .collect())
+    }
+
     /// Possibly retrieve text, if a flag is set.
     ///
     /// This utility conditionally retrieves the
@@ -150,6 +176,11 @@ impl TextService {
                     .add(not_in_column!(
                         MessageRecord,
                         message_record::Column::CompiledHash,
+                    ))
+                    .add(not_in_column!(PageDraft, page_draft::Column::WikitextHash,))
+                    .add(not_in_column!(
+                        SpecialPageOverride,
+                        special_page_override::Column::WikitextHash,
                     )),
                 // TODO add forum_post_revision
             )
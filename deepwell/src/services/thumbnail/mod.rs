@@ -0,0 +1,45 @@
+/*
+ * services/thumbnail/mod.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The thumbnail service, for producing scaled-down copies of uploaded images.
+//!
+//! When a file revision with a new blob is created, a job is queued to generate
+//! a thumbnail at each configured size. These are stored as ordinary blobs,
+//! and recorded against the source blob's hash, since blobs are shared
+//! between files. An image which already fits within a size is its own thumbnail.
+//!
+//! If the file's site has an image conversion policy, the same job also converts
+//! the image to WebP or AVIF. These derivatives are recorded the same way, and
+//! are preferred when serving the file, but the original is always kept as the
+//! file's contents.
+//!
+//! Only PNG and JPEG images are supported as sources, as those are the formats
+//! the image library is built to decode.
+
+mod prelude {
+    pub use super::super::prelude::*;
+    pub use super::structs::*;
+}
+
+mod service;
+mod structs;
+
+pub use self::service::ThumbnailService;
+pub use self::structs::*;
@@ -0,0 +1,535 @@
+/*
+ * services/thumbnail/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::hash::blob_hash_to_hex;
+use crate::models::file_derivative::{self, Entity as FileDerivative};
+use crate::models::file_revision::{Entity as FileRevision, Model as FileRevisionModel};
+use crate::models::file_thumbnail::{self, Entity as FileThumbnail};
+use crate::models::sea_orm_active_enums::ImageConversionFormat;
+use crate::models::site::Model as SiteModel;
+use crate::services::file_revision::GetFileRevision;
+use crate::services::{BlobService, FileRevisionService, SiteService};
+use image::codecs::avif::AvifEncoder;
+use image::{DynamicImage, ImageFormat, ImageReader};
+use sea_query::OnConflict;
+use std::io::Cursor;
+
+/// How much effort the AVIF encoder spends, from 1 (slowest) to 10 (fastest).
+///
+/// The slowest settings give only slightly smaller images, for
+/// many times the encoding time, which holds up the job queue.
+const AVIF_ENCODER_SPEED: u8 = 6;
+
+#[derive(Debug)]
+pub struct ThumbnailService;
+
+impl ThumbnailService {
+    /// Generates any missing thumbnails and derivatives for the blob of the given file revision.
+    ///
+    /// Derivatives are only generated if the file's site has an image conversion
+    /// policy. Files which aren't supported images, or which are too large or fail
+    /// to decode, are skipped rather than treated as errors. Otherwise the
+    /// job would be retried for an upload which can never succeed.
+    pub async fn generate_for_revision(
+        ctx: &ServiceContext<'_>,
+        revision_id: i64,
+    ) -> Result<()> {
+        let config = ctx.config();
+        let txn = ctx.transaction();
+        let revision = FileRevision::find_by_id(revision_id)
+            .one(txn)
+            .await?
+            .ok_or(Error::FileRevisionNotFound)?;
+
+        let format = match image_format(&revision.mime_hint) {
+            Some(format) => format,
+            None => {
+                debug!(
+                    "File revision ID {revision_id} has MIME type '{}', not generating thumbnails",
+                    revision.mime_hint,
+                );
+                return Ok(());
+            }
+        };
+
+        let site = SiteService::get(ctx, Reference::Id(revision.site_id)).await?;
+        let sizes = Self::get_missing_sizes(ctx, &revision).await?;
+        let conversion = Self::get_missing_conversion(ctx, &site, &revision).await?;
+
+        let hex_hash = blob_hash_to_hex(&revision.s3_hash);
+        if sizes.is_empty() && conversion.is_none() {
+            debug!("All thumbnails and derivatives for blob {hex_hash} already exist");
+            return Ok(());
+        }
+
+        info!(
+            "Generating {} thumbnails and {} derivatives for blob {hex_hash} (file revision ID {revision_id})",
+            sizes.len(),
+            usize::from(conversion.is_some()),
+        );
+
+        let data = BlobService::get(ctx, &revision.s3_hash).await?;
+        let image = match decode(&data, format, config.thumbnail_maximum_source_pixels) {
+            Some(image) => image,
+            None => return Ok(()),
+        };
+
+        for size in sizes {
+            let thumbnail_s3_hash = if image.width() <= size && image.height() <= size {
+                debug!("Image already fits within {size}px, using it as the thumbnail");
+                revision.s3_hash.clone()
+            } else {
+                let data = render(&image, size, format)?;
+                let output = BlobService::create(ctx, &data).await?;
+                output.hash.to_vec()
+            };
+
+            let model = file_thumbnail::ActiveModel {
+                s3_hash: Set(revision.s3_hash.clone()),
+                size: Set(size as i32),
+                thumbnail_s3_hash: Set(thumbnail_s3_hash),
+                ..Default::default()
+            };
+
+            // If two jobs race on the same blob, the results are identical
+            FileThumbnail::insert(model)
+                .on_conflict(
+                    OnConflict::columns([
+                        file_thumbnail::Column::S3Hash,
+                        file_thumbnail::Column::Size,
+                    ])
+                    .do_nothing()
+                    .to_owned(),
+                )
+                .exec_without_returning(txn)
+                .await?;
+        }
+
+        if let Some((format, quality)) = conversion {
+            Self::convert(ctx, &revision, &image, data.len(), format, quality).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Gets the configured thumbnail sizes which this revision's blob doesn't have yet.
+    ///
+    /// Another file with the same blob may have produced some already.
+    async fn get_missing_sizes(
+        ctx: &ServiceContext<'_>,
+        revision: &FileRevisionModel,
+    ) -> Result<Vec<u32>> {
+        let txn = ctx.transaction();
+        let existing: Vec<i32> = FileThumbnail::find()
+            .select_only()
+            .column(file_thumbnail::Column::Size)
+            .filter(file_thumbnail::Column::S3Hash.eq(revision.s3_hash.clone()))
+            .into_tuple()
+            .all(txn)
+            .await?;
+
+        let sizes = ctx
+            .config()
+            .thumbnail_sizes
+            .iter()
+            .copied()
+            .filter(|&size| !existing.contains(&(size as i32)))
+            .collect();
+
+        Ok(sizes)
+    }
+
+    /// Gets the conversion the site's policy calls for, unless this blob already has it.
+    async fn get_missing_conversion(
+        ctx: &ServiceContext<'_>,
+        site: &SiteModel,
+        revision: &FileRevisionModel,
+    ) -> Result<Option<(ImageConversionFormat, i32)>> {
+        let format = match site.image_conversion {
+            Some(format) => format,
+            None => return Ok(None),
+        };
+
+        let quality = site.image_conversion_quality;
+        let txn = ctx.transaction();
+        let existing =
+            FileDerivative::find_by_id((revision.s3_hash.clone(), format, quality))
+                .one(txn)
+                .await?;
+
+        match existing {
+            Some(_) => Ok(None),
+            None => Ok(Some((format, quality))),
+        }
+    }
+
+    /// Converts the image to the given format, and records it as a derivative.
+    ///
+    /// The original blob is always kept as the file's contents. If the converted
+    /// image isn't any smaller, the original is recorded as its own derivative
+    /// instead, so that it keeps being served as-is.
+    async fn convert(
+        ctx: &ServiceContext<'_>,
+        revision: &FileRevisionModel,
+        image: &DynamicImage,
+        original_size: usize,
+        format: ImageConversionFormat,
+        quality: i32,
+    ) -> Result<()> {
+        let data = encode(image, format, quality)?;
+        let (derivative_s3_hash, size) = if data.len() < original_size {
+            debug!(
+                "Converted image to {format:?} (quality {quality}), from {original_size} to {} bytes",
+                data.len(),
+            );
+
+            let output = BlobService::create(ctx, &data).await?;
+            (output.hash.to_vec(), output.size)
+        } else {
+            debug!(
+                "Converting image to {format:?} (quality {quality}) did not make it smaller, keeping original",
+            );
+            (revision.s3_hash.clone(), revision.size_hint)
+        };
+
+        let model = file_derivative::ActiveModel {
+            s3_hash: Set(revision.s3_hash.clone()),
+            format: Set(format),
+            quality: Set(quality),
+            derivative_s3_hash: Set(derivative_s3_hash),
+            size: Set(size),
+            ..Default::default()
+        };
+
+        // If two jobs race on the same blob, the results are identical
+        let txn = ctx.transaction();
+        FileDerivative::insert(model)
+            .on_conflict(
+                OnConflict::columns([
+                    file_derivative::Column::S3Hash,
+                    file_derivative::Column::Format,
+                    file_derivative::Column::Quality,
+                ])
+                .do_nothing()
+                .to_owned(),
+            )
+            .exec_without_returning(txn)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Gets the derivative of this blob which the site's image conversion policy prefers serving.
+    ///
+    /// Returns `None` if the site has no policy, the derivative hasn't been
+    /// generated yet, or converting the image didn't make it any smaller.
+    pub async fn get_derivative(
+        ctx: &ServiceContext<'_>,
+        site: &SiteModel,
+        s3_hash: &[u8],
+    ) -> Result<Option<ImageDerivative>> {
+        let format = match site.image_conversion {
+            Some(format) => format,
+            None => return Ok(None),
+        };
+
+        let txn = ctx.transaction();
+        let derivative = FileDerivative::find_by_id((
+            s3_hash.to_vec(),
+            format,
+            site.image_conversion_quality,
+        ))
+        .one(txn)
+        .await?;
+
+        let derivative = match derivative {
+            Some(derivative) if derivative.derivative_s3_hash != s3_hash => derivative,
+            _ => return Ok(None),
+        };
+
+        Ok(Some(ImageDerivative {
+            format: derivative.format,
+            quality: derivative.quality,
+            mime: str!(conversion_mime(derivative.format)),
+            size: derivative.size,
+            s3_hash: derivative.derivative_s3_hash,
+        }))
+    }
+
+    /// Gets the MIME type of images converted to the given format.
+    #[inline]
+    pub fn derivative_mime(format: ImageConversionFormat) -> &'static str {
+        conversion_mime(format)
+    }
+
+    /// Whether thumbnails can be generated for files with this MIME type.
+    #[inline]
+    pub fn supports(mime_hint: &str) -> bool {
+        image_format(mime_hint).is_some()
+    }
+
+    /// Gets the blob hash of a file revision's thumbnail at the given size.
+    ///
+    /// Returns `None` if the file isn't a supported image,
+    /// or its thumbnails have not been generated yet.
+    pub async fn get_hash(
+        ctx: &ServiceContext<'_>,
+        GetThumbnail {
+            site_id,
+            page_id,
+            file_id,
+            revision_number,
+            size,
+        }: GetThumbnail,
+    ) -> Result<Option<Vec<u8>>> {
+        if !ctx.config().thumbnail_sizes.contains(&size) {
+            error!("Requested thumbnail size {size} is not one which is generated");
+            return Err(Error::ThumbnailSizeInvalid);
+        }
+
+        let revision = FileRevisionService::get(
+            ctx,
+            GetFileRevision {
+                site_id,
+                page_id,
+                file_id,
+                revision_number,
+            },
+        )
+        .await?;
+
+        let txn = ctx.transaction();
+        let hash = FileThumbnail::find_by_id((revision.s3_hash, size as i32))
+            .select_only()
+            .column(file_thumbnail::Column::ThumbnailS3Hash)
+            .into_tuple()
+            .one(txn)
+            .await?;
+
+        Ok(hash)
+    }
+
+    /// Gets the blob hashes of every thumbnail generated for a blob, with their sizes.
+    pub async fn get_all_hashes(
+        ctx: &ServiceContext<'_>,
+        s3_hash: &[u8],
+    ) -> Result<Vec<(u32, Vec<u8>)>> {
+        let txn = ctx.transaction();
+        let thumbnails: Vec<(i32, Vec<u8>)> = FileThumbnail::find()
+            .select_only()
+            .column(file_thumbnail::Column::Size)
+            .column(file_thumbnail::Column::ThumbnailS3Hash)
+            .filter(file_thumbnail::Column::S3Hash.eq(s3_hash))
+            .into_tuple()
+            .all(txn)
+            .await?;
+
+        Ok(thumbnails
+            .into_iter()
+            .map(|(size, hash)| (size as u32, hash))
+            .collect())
+    }
+
+    /// Removes all thumbnails and derivatives of the given blob.
+    ///
+    /// Used when the blob's contents are purged, so that
+    /// scaled-down or converted copies of it do not remain in storage.
+    pub async fn delete_for_blob(ctx: &ServiceContext<'_>, s3_hash: &[u8]) -> Result<()> {
+        Self::delete_derivatives_for_blob(ctx, s3_hash).await?;
+
+        let txn = ctx.transaction();
+        let thumbnails = FileThumbnail::find()
+            .filter(file_thumbnail::Column::S3Hash.eq(s3_hash.to_vec()))
+            .all(txn)
+            .await?;
+
+        if thumbnails.is_empty() {
+            return Ok(());
+        }
+
+        debug!(
+            "Deleting {} thumbnails for blob {}",
+            thumbnails.len(),
+            blob_hash_to_hex(s3_hash),
+        );
+
+        FileThumbnail::delete_many()
+            .filter(file_thumbnail::Column::S3Hash.eq(s3_hash.to_vec()))
+            .exec(txn)
+            .await?;
+
+        // Small images are their own thumbnail, the caller deletes those.
+        // Otherwise, an identical thumbnail could be in use elsewhere.
+        for thumbnail in thumbnails {
+            if thumbnail.thumbnail_s3_hash != s3_hash {
+                BlobService::release(ctx, &thumbnail.thumbnail_s3_hash).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn delete_derivatives_for_blob(
+        ctx: &ServiceContext<'_>,
+        s3_hash: &[u8],
+    ) -> Result<()> {
+        let txn = ctx.transaction();
+        let derivatives = FileDerivative::find()
+            .filter(file_derivative::Column::S3Hash.eq(s3_hash.to_vec()))
+            .all(txn)
+            .await?;
+
+        if derivatives.is_empty() {
+            return Ok(());
+        }
+
+        debug!(
+            "Deleting {} derivatives for blob {}",
+            derivatives.len(),
+            blob_hash_to_hex(s3_hash),
+        );
+
+        FileDerivative::delete_many()
+            .filter(file_derivative::Column::S3Hash.eq(s3_hash.to_vec()))
+            .exec(txn)
+            .await?;
+
+        // As with thumbnails, images which didn't shrink are their own derivative
+        for derivative in derivatives {
+            if derivative.derivative_s3_hash != s3_hash {
+                BlobService::release(ctx, &derivative.derivative_s3_hash).await?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Gets the MIME type of images converted to the given format.
+fn conversion_mime(format: ImageConversionFormat) -> &'static str {
+    match format {
+        ImageConversionFormat::Avif => "image/avif",
+        ImageConversionFormat::Webp => "image/webp",
+    }
+}
+
+/// Determines which image format a file's MIME type hint corresponds to.
+///
+/// Returns `None` if thumbnails cannot be generated for it.
+fn image_format(mime_hint: &str) -> Option<ImageFormat> {
+    // Strip parameters, such as "; charset=binary"
+    let mime = mime_hint.split(';').next().unwrap_or("").trim();
+    match mime {
+        "image/png" => Some(ImageFormat::Png),
+        "image/jpeg" => Some(ImageFormat::Jpeg),
+        _ => None,
+    }
+}
+
+/// Decodes the source image, if it is valid and within the size limit.
+fn decode(data: &[u8], format: ImageFormat, maximum_pixels: u64) -> Option<DynamicImage> {
+    let reader = || ImageReader::with_format(Cursor::new(data), format);
+
+    // Check the header first, so oversized images are never decoded
+    let (width, height) = match reader().into_dimensions() {
+        Ok(dimensions) => dimensions,
+        Err(error) => {
+            warn!("Unable to read image dimensions, not generating thumbnails: {error}");
+            return None;
+        }
+    };
+
+    if u64::from(width) * u64::from(height) > maximum_pixels {
+        warn!(
+            "Image is {width}x{height}, larger than the {maximum_pixels} pixel limit, not generating thumbnails",
+        );
+        return None;
+    }
+
+    match reader().decode() {
+        Ok(image) => Some(image),
+        Err(error) => {
+            warn!("Unable to decode image, not generating thumbnails: {error}");
+            None
+        }
+    }
+}
+
+/// Scales the image down to fit within the given size, and encodes it.
+///
+/// The thumbnail keeps the format of the original, so photographs
+/// remain compact and images with transparency keep it.
+fn render(image: &DynamicImage, size: u32, format: ImageFormat) -> Result<Vec<u8>> {
+    let thumbnail = image.thumbnail(size, size);
+
+    // The JPEG encoder only accepts 8-bit color without an alpha channel
+    let thumbnail = match format {
+        ImageFormat::Jpeg => DynamicImage::ImageRgb8(thumbnail.into_rgb8()),
+        _ => thumbnail,
+    };
+
+    let mut output = Vec::new();
+    thumbnail.write_to(&mut Cursor::new(&mut output), format)?;
+    Ok(output)
+}
+
+/// Encodes the full-size image in the given format, at the given quality.
+///
+/// Unlike thumbnails, these are lossy even for PNG sources, since
+/// the quality setting is how a site chooses between size and fidelity.
+fn encode(
+    image: &DynamicImage,
+    format: ImageConversionFormat,
+    quality: i32,
+) -> Result<Vec<u8>> {
+    let quality = u8::try_from(quality).expect("Image conversion quality exceeds u8");
+
+    // Both encoders only accept 8-bit color, with or without an alpha channel
+    let image = if image.color().has_alpha() {
+        DynamicImage::ImageRgba8(image.to_rgba8())
+    } else {
+        DynamicImage::ImageRgb8(image.to_rgb8())
+    };
+
+    match format {
+        ImageConversionFormat::Avif => {
+            let mut output = Vec::new();
+            let encoder = AvifEncoder::new_with_speed_quality(
+                &mut output,
+                AVIF_ENCODER_SPEED,
+                quality,
+            );
+            image.write_with_encoder(encoder)?;
+            Ok(output)
+        }
+        ImageConversionFormat::Webp => {
+            let (width, height) = (image.width(), image.height());
+            let encoder = match image {
+                DynamicImage::ImageRgba8(ref buffer) => {
+                    webp::Encoder::from_rgba(buffer, width, height)
+                }
+                _ => webp::Encoder::from_rgb(image.as_bytes(), width, height),
+            };
+
+            Ok(encoder.encode(f32::from(quality)).to_vec())
+        }
+    }
+}
@@ -0,0 +1,64 @@
+/*
+ * services/thumbnail/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::models::sea_orm_active_enums::ImageConversionFormat;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetThumbnail {
+    pub site_id: i64,
+    pub page_id: i64,
+    pub file_id: i64,
+    pub revision_number: i64,
+
+    /// Width and height of the thumbnail's bounding box, in pixels.
+    /// Must be one of the configured sizes.
+    pub size: u32,
+}
+
+/// A copy of an image converted to another format, which is served in its place.
+#[derive(Serialize, Debug, Clone)]
+pub struct ImageDerivative {
+    pub format: ImageConversionFormat,
+    pub quality: i32,
+    pub mime: String,
+    pub size: i64,
+
+    #[serde(skip)]
+    pub s3_hash: Vec<u8>,
+}
+
+impl ImageDerivative {
+    /// Gets the file name to serve this derivative under.
+    ///
+    /// This is the original name, with its extension replaced.
+    pub fn file_name(&self, name: &str) -> String {
+        let extension = match self.format {
+            ImageConversionFormat::Avif => "avif",
+            ImageConversionFormat::Webp => "webp",
+        };
+
+        let stem = match name.rsplit_once('.') {
+            Some((stem, _)) if !stem.is_empty() => stem,
+            _ => name,
+        };
+
+        format!("{stem}.{extension}")
+    }
+}
@@ -0,0 +1,42 @@
+/*
+ * services/upload_rule/mod.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The upload rule service, for restricting which files users may upload.
+//!
+//! Sites can limit uploads of particular kinds of files, or files above some
+//! size, to users meeting a permission requirement. For instance, members may
+//! be allowed images of up to 5 MB, while larger images are left to
+//! administrators and executables are not allowed at all.
+//!
+//! Rules match on a MIME pattern, and only the most specific matching pattern
+//! is used. Its rules act as size tiers: the smallest one the file fits into
+//! decides the requirement, and files too large for every tier are rejected.
+//! Files which no rule matches are only subject to the site's limits.
+
+mod prelude {
+    pub use super::super::prelude::*;
+    pub use super::structs::*;
+}
+
+mod service;
+mod structs;
+
+pub use self::service::UploadRuleService;
+pub use self::structs::*;
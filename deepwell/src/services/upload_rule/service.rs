@@ -0,0 +1,335 @@
+/*
+ * services/upload_rule/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::upload_rule::{self, Entity as UploadRule, Model as UploadRuleModel};
+use crate::services::permission::Requirement;
+use crate::services::PermissionService;
+
+#[derive(Debug)]
+pub struct UploadRuleService;
+
+impl UploadRuleService {
+    pub async fn create(
+        ctx: &ServiceContext<'_>,
+        CreateUploadRule {
+            site_id,
+            mime_pattern,
+            maximum_bytes,
+            requirement,
+            description,
+        }: CreateUploadRule,
+    ) -> Result<UploadRuleModel> {
+        info!(
+            "Creating upload rule for '{mime_pattern}' (maximum {maximum_bytes:?} bytes) in site ID {site_id} because '{description}'",
+        );
+
+        let mime_pattern = mime_pattern.to_ascii_lowercase();
+        if !is_valid_pattern(&mime_pattern) {
+            error!("Upload rule MIME pattern '{mime_pattern}' is invalid");
+            return Err(Error::UploadRuleInvalid);
+        }
+
+        let maximum_bytes = match maximum_bytes {
+            None => None,
+            Some(0) => {
+                error!("Upload rule maximum size must be positive");
+                return Err(Error::UploadRuleInvalid);
+            }
+            Some(bytes) => {
+                Some(i64::try_from(bytes).map_err(|_| Error::UploadRuleInvalid)?)
+            }
+        };
+
+        let txn = ctx.transaction();
+        let model = upload_rule::ActiveModel {
+            site_id: Set(site_id),
+            mime_pattern: Set(mime_pattern),
+            maximum_bytes: Set(maximum_bytes),
+            requirement: Set(str!(requirement.name())),
+            description: Set(description),
+            ..Default::default()
+        };
+
+        let rule = model.insert(txn).await?;
+        Ok(rule)
+    }
+
+    pub async fn delete(
+        ctx: &ServiceContext<'_>,
+        DeleteUploadRule { site_id, rule_id }: DeleteUploadRule,
+    ) -> Result<()> {
+        info!("Deleting upload rule ID {rule_id} in site ID {site_id}");
+
+        let txn = ctx.transaction();
+        let rule = UploadRule::find()
+            .filter(
+                Condition::all()
+                    .add(upload_rule::Column::RuleId.eq(rule_id))
+                    .add(upload_rule::Column::SiteId.eq(site_id))
+                    .add(upload_rule::Column::DeletedAt.is_null()),
+            )
+            .one(txn)
+            .await?;
+
+        if rule.is_none() {
+            return Err(Error::UploadRuleNotFound);
+        }
+
+        let model = upload_rule::ActiveModel {
+            rule_id: Set(rule_id),
+            deleted_at: Set(Some(now())),
+            ..Default::default()
+        };
+        model.update(txn).await?;
+        Ok(())
+    }
+
+    /// Gets all the active upload rules for a site.
+    pub async fn get_all(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+    ) -> Result<Vec<UploadRuleModel>> {
+        let txn = ctx.transaction();
+        let rules = UploadRule::find()
+            .filter(
+                Condition::all()
+                    .add(upload_rule::Column::SiteId.eq(site_id))
+                    .add(upload_rule::Column::DeletedAt.is_null()),
+            )
+            .order_by_asc(upload_rule::Column::RuleId)
+            .all(txn)
+            .await?;
+
+        Ok(rules)
+    }
+
+    /// Checks whether a user may upload a file under the site's upload rules.
+    ///
+    /// If not, the error says which rule denied it and why.
+    pub async fn check(
+        ctx: &ServiceContext<'_>,
+        UploadRuleTarget {
+            site_id,
+            user_id,
+            mime,
+            size,
+        }: UploadRuleTarget<'_>,
+    ) -> Result<()> {
+        let rules = Self::get_all(ctx, site_id).await?;
+        let restriction = |rule: &UploadRuleModel, kind: UploadRestrictionKind| {
+            warn!(
+                "Upload of '{mime}' ({size} bytes) by user ID {user_id} denied by upload rule ID {}: {kind:?}",
+                rule.rule_id,
+            );
+
+            Error::UploadRestricted(UploadRestriction {
+                rule_id: rule.rule_id,
+                mime_pattern: rule.mime_pattern.clone(),
+                mime: str!(mime),
+                size,
+                kind,
+            })
+        };
+
+        let rule = match select_rule(&rules, mime, size) {
+            RuleMatch::Unrestricted => return Ok(()),
+            RuleMatch::Permitted(rule) => rule,
+            RuleMatch::TooLarge(rule) => {
+                let maximum_bytes = rule.maximum_bytes.unwrap_or(i64::MAX) as u64;
+                let kind = UploadRestrictionKind::Size { maximum_bytes };
+                return Err(restriction(rule, kind));
+            }
+        };
+
+        let requirement: Requirement = rule.requirement.parse()?;
+        let (allowed, reason) = PermissionService::check_requirement(
+            ctx,
+            site_id,
+            Some(user_id),
+            requirement,
+        )
+        .await?;
+
+        if allowed {
+            debug!(
+                "Upload permitted by upload rule ID {} ({reason:?})",
+                rule.rule_id
+            );
+            Ok(())
+        } else {
+            let kind = UploadRestrictionKind::Requirement {
+                requirement,
+                reason,
+            };
+            Err(restriction(rule, kind))
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum RuleMatch<'a> {
+    /// No rule applies to this kind of file.
+    Unrestricted,
+
+    /// The size tier the file falls into.
+    Permitted(&'a UploadRuleModel),
+
+    /// The file exceeds every size tier, this is the largest.
+    TooLarge(&'a UploadRuleModel),
+}
+
+/// Picks which of the site's upload rules governs a file.
+fn select_rule<'a>(rules: &'a [UploadRuleModel], mime: &str, size: u64) -> RuleMatch<'a> {
+    // Remove parameters, e.g. "; charset=binary"
+    let mime = mime.split(';').next().unwrap_or_default().trim();
+    let specificity =
+        |rule: &UploadRuleModel| pattern_specificity(&rule.mime_pattern, mime);
+
+    let most_specific = match rules.iter().filter_map(specificity).max() {
+        Some(value) => value,
+        None => return RuleMatch::Unrestricted,
+    };
+
+    let tiers = rules
+        .iter()
+        .filter(|rule| specificity(rule) == Some(most_specific));
+
+    let fitting = tiers
+        .clone()
+        .filter(|rule| match rule.maximum_bytes {
+            Some(maximum) => size <= maximum as u64,
+            None => true,
+        })
+        .min_by_key(|rule| rule.maximum_bytes.unwrap_or(i64::MAX));
+
+    match fitting {
+        Some(rule) => RuleMatch::Permitted(rule),
+        None => {
+            // Since nothing fit, every tier has a maximum
+            let largest = tiers
+                .max_by_key(|rule| rule.maximum_bytes)
+                .expect("No upload rules in most specific pattern");
+
+            RuleMatch::TooLarge(largest)
+        }
+    }
+}
+
+/// How closely a MIME pattern matches a type, or `None` if it doesn't.
+fn pattern_specificity(pattern: &str, mime: &str) -> Option<u8> {
+    if pattern == "*" {
+        return Some(0);
+    }
+
+    if let Some(top_level) = pattern.strip_suffix("/*") {
+        let mime_top_level = mime.split('/').next().unwrap_or_default();
+        return (top_level == mime_top_level).then_some(1);
+    }
+
+    (pattern == mime).then_some(2)
+}
+
+fn is_valid_pattern(pattern: &str) -> bool {
+    fn is_valid_part(part: &str) -> bool {
+        !part.is_empty()
+            && part
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.' | '_'))
+    }
+
+    if pattern == "*" {
+        return true;
+    }
+
+    match pattern.split_once('/') {
+        Some((top_level, "*")) => is_valid_part(top_level),
+        Some((top_level, subtype)) => is_valid_part(top_level) && is_valid_part(subtype),
+        None => false,
+    }
+}
+
+#[test]
+fn upload_rule_selection() {
+    fn rule(
+        rule_id: i64,
+        mime_pattern: &str,
+        maximum_bytes: Option<i64>,
+    ) -> UploadRuleModel {
+        UploadRuleModel {
+            rule_id,
+            created_at: now(),
+            deleted_at: None,
+            site_id: 1,
+            mime_pattern: str!(mime_pattern),
+            maximum_bytes,
+            requirement: str!("site_member"),
+            description: String::new(),
+        }
+    }
+
+    use RuleMatch::*;
+
+    const MB: i64 = 1024 * 1024;
+
+    let rules = [
+        rule(1, "image/*", Some(5 * MB)),
+        rule(2, "image/*", Some(20 * MB)),
+        rule(3, "application/x-executable", None),
+        rule(4, "image/svg+xml", Some(MB)),
+    ];
+
+    macro_rules! check {
+        ($rules:expr, $mime:expr, $size:expr, $expected:expr $(,)?) => {
+            assert_eq!(
+                select_rule(&$rules, $mime, $size as u64),
+                $expected,
+                "Selected upload rule doesn't match expected",
+            );
+        };
+    }
+
+    check!(rules, "text/plain", 10, Unrestricted);
+    check!(rules, "image/png; charset=binary", MB, Permitted(&rules[0]));
+    check!(rules, "image/png", 8 * MB, Permitted(&rules[1]));
+    check!(rules, "image/png", 30 * MB, TooLarge(&rules[1]));
+    check!(rules, "image/svg+xml", 100, Permitted(&rules[3]));
+    check!(rules, "image/svg+xml", 2 * MB, TooLarge(&rules[3]));
+    check!(rules, "application/x-executable", 0, Permitted(&rules[2]));
+
+    // More specific patterns win, even over larger tiers
+    let rules = [rule(5, "*", Some(MB)), rule(6, "image/*", None)];
+    check!(rules, "image/gif", 50 * MB, Permitted(&rules[1]));
+    check!(rules, "audio/ogg", 10, Permitted(&rules[0]));
+    check!(rules, "audio/ogg", 2 * MB, TooLarge(&rules[0]));
+}
+
+#[test]
+fn upload_rule_patterns() {
+    assert!(is_valid_pattern("*"));
+    assert!(is_valid_pattern("image/*"));
+    assert!(is_valid_pattern("image/svg+xml"));
+    assert!(is_valid_pattern("application/vnd.ms-excel"));
+    assert!(!is_valid_pattern(""));
+    assert!(!is_valid_pattern("image"));
+    assert!(!is_valid_pattern("*/png"));
+    assert!(!is_valid_pattern("image/"));
+    assert!(!is_valid_pattern("text/plain; charset=utf-8"));
+}
@@ -0,0 +1,78 @@
+/*
+ * services/upload_rule/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::services::permission::{PermissionReason, Requirement};
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CreateUploadRule {
+    pub site_id: i64,
+
+    /// Which files this rule is for, as `type/subtype`, `type/*`, or `*`.
+    pub mime_pattern: String,
+
+    /// The largest file this rule permits, or `None` for no maximum.
+    #[serde(default)]
+    pub maximum_bytes: Option<u64>,
+
+    pub requirement: Requirement,
+    pub description: String,
+}
+
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct DeleteUploadRule {
+    pub site_id: i64,
+    pub rule_id: i64,
+}
+
+/// The file being uploaded, which upload rules are checked against.
+#[derive(Debug, Copy, Clone)]
+pub struct UploadRuleTarget<'a> {
+    pub site_id: i64,
+    pub user_id: i64,
+    pub mime: &'a str,
+    pub size: u64,
+}
+
+/// Which upload rule denied a file, and why.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct UploadRestriction {
+    pub rule_id: i64,
+    pub mime_pattern: String,
+    pub mime: String,
+    pub size: u64,
+
+    #[serde(flatten)]
+    pub kind: UploadRestrictionKind,
+}
+
+#[derive(Serialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(tag = "restriction", rename_all = "snake_case")]
+pub enum UploadRestrictionKind {
+    /// The file is larger than every rule for its type permits.
+    ///
+    /// The rule given is the one with the largest maximum.
+    Size { maximum_bytes: u64 },
+
+    /// The user does not meet the rule's requirement.
+    Requirement {
+        requirement: Requirement,
+        reason: PermissionReason,
+    },
+}
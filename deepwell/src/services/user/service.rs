@@ -24,14 +24,25 @@ use crate::models::user::{self, Entity as User, Model as UserModel};
 use crate::services::alias::CreateAlias;
 use crate::services::blob::{BlobService, CreateBlobOutput};
 use crate::services::email::{EmailClassification, EmailService};
+use crate::services::event::{Event, EventService};
 use crate::services::filter::{FilterClass, FilterType};
-use crate::services::{AliasService, FilterService, PasswordService};
+use crate::services::public_api::PublicUser;
+use crate::services::{
+    AliasService, FilterService, ImpersonationService, OauthService, PasswordService,
+    RefreshTokenService, SessionService, UserBotOwnerService,
+};
 use crate::utils::regex_replace_in_place;
 use once_cell::sync::Lazy;
 use regex::Regex;
 use sea_orm::ActiveValue;
 use std::cmp;
 
+/// Slug prefix for the tombstone identity of deleted users.
+///
+/// Names which would produce a slug with this prefix are reserved,
+/// so that a tombstone cannot be impersonated by a live account.
+const DELETED_USER_SLUG_PREFIX: &str = "deleted-user-";
+
 static LEADING_TRAILING_CHARS: Lazy<Regex> =
     Lazy::new(|| Regex::new(r"(^[\-\s]+)|([\-\s+]$)").unwrap());
 
@@ -65,6 +76,12 @@ impl UserService {
             return Err(Error::UserSlugEmpty);
         }
 
+        // Reserved slug check
+        if is_reserved_slug(&slug) {
+            error!("Cannot create user with reserved slug '{slug}'");
+            return Err(Error::UserNameReserved);
+        }
+
         // Check if username contains the minimum amount of required bytes.
         if name.len() < ctx.config().minimum_name_bytes {
             error!(
@@ -350,13 +367,39 @@ impl UserService {
     ) -> Result<UserModel> {
         // NOTE: Name filter validation occurs in update_name(), not here
         let txn = ctx.transaction();
-        let user = Self::get(ctx, reference).await?;
+        let UserModel { user_id, .. } = Self::get_active(ctx, reference).await?;
+
+        // Lock the row until the end of the transaction, so concurrent
+        // updates see each other's version changes.
+        let user = User::find_by_id(user_id)
+            .lock_exclusive()
+            .one(txn)
+            .await?
+            .ok_or(Error::UserNotFound)?;
+
+        // Only the public fields are returned on conflict, the model has secrets
+        check_version(
+            input.expected_version,
+            user.version,
+            &PublicUser::from(user.clone()),
+        )?;
 
         let mut model = user::ActiveModel {
             user_id: Set(user.user_id),
+            version: Set(user.version + 1),
             ..Default::default()
         };
 
+        // An administrator impersonating the user may not lock them out
+        if input.email.to_option().is_some() || input.email_verified.to_option().is_some()
+        {
+            ImpersonationService::check_context(ctx, user_id, "user.email")?;
+        }
+
+        if input.password.to_option().is_some() {
+            ImpersonationService::check_context(ctx, user_id, "user.password")?;
+        }
+
         // Add each field
         if let ProvidedValue::Set(name) = input.name {
             Self::update_name(ctx, name, &user, &mut model, input.bypass_filter).await?;
@@ -392,6 +435,10 @@ impl UserService {
             model.password = Set(password_hash);
         }
 
+        if let ProvidedValue::Set(magic_link_login) = input.magic_link_login {
+            model.magic_link_login = Set(magic_link_login);
+        }
+
         if let ProvidedValue::Set(locales) = input.locales {
             Self::validate_locales(user.user_type, &locales)?;
             model.locales = Set(locales);
@@ -435,10 +482,35 @@ impl UserService {
             model.avatar_s3_hash = Set(s3_hash);
         }
 
+        // Changing credentials means existing session tokens should be replaced,
+        // and a new password logs out any other sessions and long-lived clients
+        let password_changed = model.password.is_set();
+        let credentials_changed = model.email.is_set()
+            || model.password.is_set()
+            || model.magic_link_login.is_set();
+
         // Update user
         model.updated_at = Set(Some(now()));
         let new_user = model.update(txn).await?;
 
+        if credentials_changed {
+            SessionService::require_rotation(ctx, user.user_id).await?;
+        }
+
+        if password_changed {
+            RefreshTokenService::revoke_all(ctx, user.user_id).await?;
+
+            match input.session_token {
+                Some(ref session_token) => {
+                    SessionService::invalidate_others(ctx, session_token, user.user_id)
+                        .await?;
+                }
+                None => {
+                    SessionService::invalidate_all(ctx, user.user_id).await?;
+                }
+            }
+        }
+
         // Run verification afterwards if the slug changed
         if user.slug != new_user.slug {
             try_join!(
@@ -447,6 +519,21 @@ impl UserService {
             )?;
         }
 
+        EventService::emit(
+            ctx,
+            Event::UserUpdated {
+                user_id: user.user_id,
+            },
+        )
+        .await?;
+
+        // Remove the previous avatar, unless it's still used elsewhere
+        if let Some(old_avatar) = &user.avatar_s3_hash {
+            if new_user.avatar_s3_hash.as_ref() != Some(old_avatar) {
+                BlobService::release(ctx, old_avatar).await?;
+            }
+        }
+
         Ok(new_user)
     }
 
@@ -479,6 +566,12 @@ impl UserService {
             return Err(Error::UserSlugEmpty);
         }
 
+        // Reserved slug check
+        if new_slug != user.slug && is_reserved_slug(&new_slug) {
+            error!("Cannot rename user to reserved slug '{new_slug}'");
+            return Err(Error::UserNameReserved);
+        }
+
         // Perform filter validation
         if !bypass_filter {
             Self::run_name_filter(ctx, &new_name, &new_slug).await?;
@@ -611,6 +704,7 @@ impl UserService {
         };
         model.update(txn).await?;
 
+        SessionService::require_rotation(ctx, user_id).await?;
         Ok(())
     }
 
@@ -645,26 +739,85 @@ impl UserService {
         Ok(())
     }
 
+    /// Deletes a user, leaving behind a tombstone identity.
+    ///
+    /// The row is kept with the same user ID, so that revisions, messages,
+    /// votes, and other records which reference it continue to resolve.
+    /// However the name and slug are replaced with an anonymized placeholder,
+    /// and all personal information (email, password, MFA, avatar, profile)
+    /// is purged. Sessions, aliases, and bot ownerships are removed.
+    ///
+    /// Lookups by slug ignore deleted users, while lookups by ID return
+    /// the tombstone, which is what attribution consumers want.
     pub async fn delete(
         ctx: &ServiceContext<'_>,
         reference: Reference<'_>,
     ) -> Result<UserModel> {
         let txn = ctx.transaction();
-        let user = Self::get(ctx, reference).await?;
+        let user = Self::get_active(ctx, reference).await?;
         info!("Deleting user with ID {}", user.user_id);
+        ImpersonationService::check_context(ctx, user.user_id, "user.delete")?;
 
-        // Remove all user aliases
-        AliasService::remove_all(ctx, AliasType::User, user.user_id).await?;
+        // Remove all user aliases, sessions, refresh tokens, OAuth grants, and bot ownerships
+        try_join!(
+            AliasService::remove_all(ctx, AliasType::User, user.user_id),
+            SessionService::invalidate_all(ctx, user.user_id),
+            RefreshTokenService::revoke_all(ctx, user.user_id),
+            OauthService::revoke_all(ctx, user.user_id),
+            UserBotOwnerService::remove_all(ctx, user.user_id),
+        )?;
 
-        // Set deletion flag
+        // Set deletion flag, replace identity, and purge personal data
         let model = user::ActiveModel {
             user_id: Set(user.user_id),
             deleted_at: Set(Some(now())),
+            updated_at: Set(Some(now())),
+            name: Set(format!("Deleted User {}", user.user_id)),
+            slug: Set(format!("{DELETED_USER_SLUG_PREFIX}{}", user.user_id)),
+            email: Set(String::new()),
+            email_is_alias: Set(None),
+            email_verified_at: Set(None),
+            password: Set(String::new()),
+            multi_factor_secret: Set(None),
+            multi_factor_recovery_codes: Set(None),
+            magic_link_login: Set(false),
+            avatar_s3_hash: Set(None),
+            real_name: Set(None),
+            gender: Set(None),
+            birthday: Set(None),
+            location: Set(None),
+            biography: Set(None),
+            user_page: Set(None),
             ..Default::default()
         };
 
         // Update and return
         let user = model.update(txn).await?;
+        EventService::emit(
+            ctx,
+            Event::UserDeleted {
+                user_id: user.user_id,
+            },
+        )
+        .await?;
+
+        Ok(user)
+    }
+
+    /// Gets a user, failing if they have been deleted.
+    ///
+    /// Since lookups by ID also return deleted users (for attribution),
+    /// operations which modify the user should use this instead.
+    async fn get_active(
+        ctx: &ServiceContext<'_>,
+        reference: Reference<'_>,
+    ) -> Result<UserModel> {
+        let user = Self::get(ctx, reference).await?;
+        if user.deleted_at.is_some() {
+            error!("User ID {} is deleted", user.user_id);
+            return Err(Error::UserNotFound);
+        }
+
         Ok(user)
     }
 
@@ -746,3 +899,8 @@ fn get_user_slug(name: &str, user_type: UserType) -> String {
         get_regular_slug(name)
     }
 }
+
+#[inline]
+fn is_reserved_slug(slug: &str) -> bool {
+    slug.starts_with(DELETED_USER_SLUG_PREFIX)
+}
@@ -72,6 +72,7 @@ pub struct UpdateUserBody {
     pub email: ProvidedValue<String>,
     pub email_verified: ProvidedValue<bool>,
     pub password: ProvidedValue<String>,
+    pub magic_link_login: ProvidedValue<bool>,
     pub locales: ProvidedValue<Vec<String>>,
     pub avatar: ProvidedValue<Option<Bytes<'static>>>,
     pub real_name: ProvidedValue<Option<String>>,
@@ -81,6 +82,14 @@ pub struct UpdateUserBody {
     pub biography: ProvidedValue<Option<String>>,
     pub user_page: ProvidedValue<Option<String>>,
 
+    /// If set, the update fails unless the user is still at this version.
+    pub expected_version: Option<i32>,
+
+    /// The session making this change, which is kept if the password changes.
+    ///
+    /// All of the user's other sessions are invalidated in that case.
+    pub session_token: Option<String>,
+
     #[serde(default)]
     pub bypass_filter: bool,
 }
@@ -163,4 +163,27 @@ impl UserBotOwnerService {
         let was_deleted = rows_affected == 1;
         Ok(RemoveBotOwnerOutput { was_deleted })
     }
+
+    /// Removes all bot ownership records involving the given user.
+    ///
+    /// This covers both the case where the user is the bot, and where
+    /// they are a human owner. Used during user deletion.
+    ///
+    /// # Returns
+    /// The number of records removed.
+    pub async fn remove_all(ctx: &ServiceContext<'_>, user_id: i64) -> Result<u64> {
+        info!("Deleting all bot ownership records for user ID {user_id}");
+
+        let txn = ctx.transaction();
+        let DeleteResult { rows_affected } = UserBotOwner::delete_many()
+            .filter(
+                Condition::any()
+                    .add(user_bot_owner::Column::BotUserId.eq(user_id))
+                    .add(user_bot_owner::Column::HumanUserId.eq(user_id)),
+            )
+            .exec(txn)
+            .await?;
+
+        Ok(rows_affected)
+    }
 }
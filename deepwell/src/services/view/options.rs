@@ -35,6 +35,7 @@ const PAGE_ARGUMENTS_SCHEMA: ArgumentSchema = ArgumentSchema {
         "discuss",
         "history",
         "offset",
+        "revision",
         "data",
     ],
     solo_keys: &[
@@ -64,6 +65,7 @@ pub struct PageOptions {
     comments: bool,
     history: bool,
     offset: Option<i32>,
+    revision: Option<i64>,
     data: String,
 }
 
@@ -132,6 +134,15 @@ impl PageOptions {
             }
         }
 
+        if let Some((value, orig)) = arguments.remove(unicase!("revision")) {
+            match value {
+                ArgumentValue::Integer(revision) => {
+                    options.revision = Some(i64::from(revision))
+                }
+                _ => error!("Invalid value for revision argument: {orig}"),
+            }
+        }
+
         set_str!(data);
 
         // Done processing arguments
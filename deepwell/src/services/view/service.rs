@@ -37,13 +37,13 @@ use crate::services::domain::SiteDomainResult;
 use crate::services::render::RenderOutput;
 use crate::services::special_page::{GetSpecialPageOutput, SpecialPageType};
 use crate::services::{
-    DomainService, PageRevisionService, PageService, SessionService, SpecialPageService,
-    TextService, UserService,
+    DomainService, PageService, SessionService, SpecialPageService,
+    StableRevisionService, TextService, UserService,
 };
 use crate::utils::split_category;
 use fluent::{FluentArgs, FluentValue};
 use ftml::prelude::*;
-use ftml::render::html::HtmlOutput;
+use ftml::render::html::{HtmlCsp, HtmlOutput};
 use ref_map::*;
 use std::borrow::Cow;
 use std::mem;
@@ -123,6 +123,7 @@ impl ViewService {
             //      presumably what'd they'd *like* the message
             //      to be in, if translations are available.
             language: Cow::Owned(str!(&locales[0])),
+            utc_offset: None,
         };
 
         // Helper structure to designate which variant of GetPageViewOutput to return.
@@ -138,68 +139,101 @@ impl ViewService {
         }
 
         // Get wikitext and HTML to return for this page.
-        let (status, wikitext, compiled_html) = match PageService::get_optional(
-            ctx,
-            site.site_id,
-            Reference::Slug(cow!(page_full_slug)),
-        )
-        .await?
-        {
-            // This page exists, return its data directly.
-            Some(page) => {
-                // TODO determine if page needs rerender?
+        let (status, wikitext, compiled_html, compiled_csp) =
+            match PageService::get_optional(
+                ctx,
+                site.site_id,
+                Reference::Slug(cow!(page_full_slug)),
+            )
+            .await?
+            {
+                // This page exists, return its data directly.
+                Some(page) => {
+                    // TODO determine if page needs rerender?
+
+                    // Get associated revision, which may be pinned as stable
+                    let page_revision =
+                        StableRevisionService::get_page_revision(ctx, &site, &page)
+                            .await?;
 
-                // Get associated revision
-                let page_revision =
-                    PageRevisionService::get_latest(ctx, site.site_id, page.page_id)
+                    // Check user access to page
+                    let user_permissions = match user_session {
+                        Some(ref session) => session.user_permissions,
+                        None => {
+                            debug!(
+                                "No user for session, getting guest permission scheme",
+                            );
+
+                            // TODO get permissions from service
+                            UserPermissions
+                        }
+                    };
+
+                    // Determine whether to return the actual page contents,
+                    // or the "private page" data (_public).
+                    //
+                    // This returns false if the user is banned *and* the site
+                    // disallows banned viewing.
+                    if Self::can_access_page(ctx, user_permissions).await? {
+                        debug!("User has page access, return text data");
+
+                        let (wikitext, compiled_html) = try_join!(
+                            TextService::get(ctx, &page_revision.wikitext_hash),
+                            TextService::get(ctx, &page_revision.compiled_hash),
+                        )?;
+                        let compiled_csp: HtmlCsp =
+                            serde_json::from_value(page_revision.compiled_csp.clone())?;
+
+                        (
+                            PageStatus::Found {
+                                page,
+                                page_revision,
+                            },
+                            wikitext,
+                            compiled_html,
+                            compiled_csp,
+                        )
+                    } else {
+                        warn!("User doesn't have page access, returning permission page",);
+
+                        let (page_status, page_type) = if user_permissions.is_banned() {
+                            (PageStatus::Banned, SpecialPageType::Banned)
+                        } else {
+                            (PageStatus::Private, SpecialPageType::Private)
+                        };
+
+                        let GetSpecialPageOutput {
+                            wikitext,
+                            render_output,
+                        } = SpecialPageService::get(
+                            ctx, &site, page_type, &locales, page_info,
+                        )
                         .await?;
 
-                // Check user access to page
-                let user_permissions = match user_session {
-                    Some(ref session) => session.user_permissions,
-                    None => {
-                        debug!("No user for session, getting guest permission scheme",);
+                        let RenderOutput {
+                            html_output:
+                                HtmlOutput {
+                                    body: compiled_html,
+                                    csp: compiled_csp,
+                                    ..
+                                },
+                            ..
+                        } = render_output;
 
-                        // TODO get permissions from service
-                        UserPermissions
+                        (page_status, wikitext, compiled_html, compiled_csp)
                     }
-                };
-
-                // Determine whether to return the actual page contents,
-                // or the "private page" data (_public).
-                //
-                // This returns false if the user is banned *and* the site
-                // disallows banned viewing.
-                if Self::can_access_page(ctx, user_permissions).await? {
-                    debug!("User has page access, return text data");
-
-                    let (wikitext, compiled_html) = try_join!(
-                        TextService::get(ctx, &page_revision.wikitext_hash),
-                        TextService::get(ctx, &page_revision.compiled_hash),
-                    )?;
-
-                    (
-                        PageStatus::Found {
-                            page,
-                            page_revision,
-                        },
-                        wikitext,
-                        compiled_html,
-                    )
-                } else {
-                    warn!("User doesn't have page access, returning permission page",);
-
-                    let (page_status, page_type) = if user_permissions.is_banned() {
-                        (PageStatus::Banned, SpecialPageType::Banned)
-                    } else {
-                        (PageStatus::Private, SpecialPageType::Private)
-                    };
-
+                }
+                // The page is missing, fetch the "missing page" data (_404).
+                None => {
                     let GetSpecialPageOutput {
                         wikitext,
                         render_output,
                     } = SpecialPageService::get(
-                        ctx, &site, page_type, &locales, page_info,
+                        ctx,
+                        &site,
+                        SpecialPageType::Missing,
+                        &locales,
+                        page_info,
                     )
                     .await?;
 
@@ -207,43 +241,21 @@ impl ViewService {
                         html_output:
                             HtmlOutput {
                                 body: compiled_html,
+                                csp: compiled_csp,
                                 ..
                             },
                         ..
                     } = render_output;
 
-                    (page_status, wikitext, compiled_html)
+                    (PageStatus::Missing, wikitext, compiled_html, compiled_csp)
                 }
-            }
-            // The page is missing, fetch the "missing page" data (_404).
-            None => {
-                let GetSpecialPageOutput {
-                    wikitext,
-                    render_output,
-                } = SpecialPageService::get(
-                    ctx,
-                    &site,
-                    SpecialPageType::Missing,
-                    &locales,
-                    page_info,
-                )
-                .await?;
-
-                let RenderOutput {
-                    html_output:
-                        HtmlOutput {
-                            body: compiled_html,
-                            ..
-                        },
-                    ..
-                } = render_output;
-
-                (PageStatus::Missing, wikitext, compiled_html)
-            }
-        };
+            };
 
         // TODO Check if user-agent and IP match?
 
+        // Inline styles in the page, for the Content-Security-Policy header
+        let style_sources = compiled_csp.style_sources();
+
         let viewer = Viewer {
             site,
             redirect_site,
@@ -254,27 +266,35 @@ impl ViewService {
             PageStatus::Found {
                 page,
                 page_revision,
-            } => GetPageViewOutput::PageFound {
-                viewer,
-                options,
-                page,
-                page_revision,
-                redirect_page,
-                wikitext,
-                compiled_html,
-            },
+            } => {
+                let indexing = PageService::get_indexing(ctx, &page).await?;
+
+                GetPageViewOutput::PageFound {
+                    viewer,
+                    options,
+                    page,
+                    page_revision,
+                    indexing,
+                    redirect_page,
+                    wikitext,
+                    compiled_html,
+                    style_sources,
+                }
+            }
             PageStatus::Missing => GetPageViewOutput::PageMissing {
                 viewer,
                 options,
                 redirect_page,
                 wikitext,
                 compiled_html,
+                style_sources,
             },
             PageStatus::Private => GetPageViewOutput::PagePermissions {
                 viewer,
                 options,
                 redirect_page,
                 compiled_html,
+                style_sources,
                 banned: false,
             },
             PageStatus::Banned => GetPageViewOutput::PagePermissions {
@@ -282,6 +302,7 @@ impl ViewService {
                 options,
                 redirect_page,
                 compiled_html,
+                style_sources,
                 banned: true,
             },
         };
@@ -25,6 +25,7 @@ use crate::models::page_revision::Model as PageRevisionModel;
 use crate::models::session::Model as SessionModel;
 use crate::models::site::Model as SiteModel;
 use crate::models::user::Model as UserModel;
+use crate::services::page::PageIndexing;
 
 // TODO replace with actual user permissions type
 #[derive(Serialize, Deserialize, Debug, Copy, Clone)]
@@ -60,9 +61,11 @@ pub enum GetPageViewOutput {
         options: PageOptions,
         page: PageModel,
         page_revision: PageRevisionModel,
+        indexing: PageIndexing,
         redirect_page: Option<String>,
         wikitext: String,
         compiled_html: String,
+        style_sources: Vec<String>,
     },
 
     PageMissing {
@@ -72,6 +75,7 @@ pub enum GetPageViewOutput {
         redirect_page: Option<String>,
         wikitext: String,
         compiled_html: String,
+        style_sources: Vec<String>,
     },
 
     PagePermissions {
@@ -80,6 +84,7 @@ pub enum GetPageViewOutput {
         options: PageOptions,
         redirect_page: Option<String>,
         compiled_html: String,
+        style_sources: Vec<String>,
         banned: bool,
     },
 
@@ -0,0 +1,41 @@
+/*
+ * services/watchlist/mod.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The watchlist service, for following changes to watched pages.
+//!
+//! Which pages a user watches is stored as a `watch` relation. Alongside
+//! that, the latest revision the user has seen of each page is recorded, so
+//! that the watchlist can show how many revisions have been made since then,
+//! with summaries of the most recent ones.
+//!
+//! The watchlist is built with a fixed number of queries however many pages
+//! are watched, so it can be shown in a single request. Forum threads cannot
+//! be watched yet, as DEEPWELL does not have forums.
+
+mod prelude {
+    pub use super::super::prelude::*;
+    pub use super::structs::*;
+}
+
+mod service;
+mod structs;
+
+pub use self::service::WatchlistService;
+pub use self::structs::*;
@@ -0,0 +1,302 @@
+/*
+ * services/watchlist/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::page::{self, Entity as Page};
+use crate::models::page_revision::{
+    self, Entity as PageRevision, Model as PageRevisionModel,
+};
+use crate::models::page_watch_seen::{self, Entity as PageWatchSeen};
+use crate::models::relation::{self, Entity as Relation};
+use crate::models::sea_orm_active_enums::RelationObjectType;
+use crate::services::relation::RelationType;
+use crate::services::{PageRevisionService, TextService};
+use std::collections::HashMap;
+
+/// How many unseen revisions are summarized for each watched page.
+const UNSEEN_SUMMARY_LIMIT: usize = 5;
+
+#[derive(Debug)]
+pub struct WatchlistService;
+
+impl WatchlistService {
+    /// Gets every page the user is watching, along with the changes they haven't seen.
+    ///
+    /// Pages with unseen revisions come first, ordered by most recently changed.
+    pub async fn get(ctx: &ServiceContext<'_>, user_id: i64) -> Result<Vec<WatchedPage>> {
+        info!("Getting watchlist for user ID {user_id}");
+
+        // Get watched pages, and where the user left off in each
+        let txn = ctx.transaction();
+        let watches = Relation::find()
+            .filter(
+                Condition::all()
+                    .add(
+                        relation::Column::RelationType
+                            .eq(RelationType::PageWatch.value()),
+                    )
+                    .add(relation::Column::FromType.eq(RelationObjectType::User))
+                    .add(relation::Column::FromId.eq(user_id))
+                    .add(relation::Column::OverwrittenAt.is_null())
+                    .add(relation::Column::DeletedAt.is_null()),
+            )
+            .all(txn)
+            .await?;
+
+        let page_ids = watches
+            .iter()
+            .map(|watch| watch.dest_id)
+            .collect::<Vec<_>>();
+        let (pages, seen) = try_join!(
+            Page::find()
+                .filter(
+                    Condition::all()
+                        .add(page::Column::PageId.is_in(page_ids.clone()))
+                        .add(page::Column::DeletedAt.is_null()),
+                )
+                .order_by_asc(page::Column::Slug)
+                .all(txn),
+            PageWatchSeen::find()
+                .filter(
+                    Condition::all()
+                        .add(page_watch_seen::Column::UserId.eq(user_id))
+                        .add(page_watch_seen::Column::PageId.is_in(page_ids)),
+                )
+                .all(txn),
+        )?;
+
+        if pages.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let watched_at = watches
+            .into_iter()
+            .map(|watch| (watch.dest_id, watch.created_at))
+            .collect::<HashMap<_, _>>();
+
+        let seen = seen
+            .into_iter()
+            .map(|seen| (seen.page_id, seen.revision_number))
+            .collect::<HashMap<_, _>>();
+
+        // Get the latest revision of each page, and all revisions not yet seen.
+        //
+        // If the user hasn't marked anything as seen on a page, then
+        // everything since they started watching it counts as unseen.
+        let mut unseen_condition = Condition::any();
+        for page in &pages {
+            let condition =
+                Condition::all().add(page_revision::Column::PageId.eq(page.page_id));
+            let condition = match seen.get(&page.page_id) {
+                Some(&revision_number) => condition
+                    .add(page_revision::Column::RevisionNumber.gt(revision_number)),
+                None => condition
+                    .add(page_revision::Column::CreatedAt.gt(watched_at[&page.page_id])),
+            };
+
+            unseen_condition = unseen_condition.add(condition);
+        }
+
+        let latest_revision_ids = pages
+            .iter()
+            .filter_map(|page| page.latest_revision_id)
+            .collect::<Vec<_>>();
+
+        let (latest, unseen) = try_join!(
+            PageRevision::find()
+                .filter(page_revision::Column::RevisionId.is_in(latest_revision_ids))
+                .all(txn),
+            PageRevision::find()
+                .filter(unseen_condition)
+                .order_by_desc(page_revision::Column::RevisionNumber)
+                .all(txn),
+        )?;
+
+        let latest = latest
+            .into_iter()
+            .map(|revision| (revision.page_id, revision))
+            .collect::<HashMap<_, _>>();
+
+        let mut unseen_by_page = HashMap::<i64, Vec<PageRevisionModel>>::new();
+        for revision in unseen {
+            unseen_by_page
+                .entry(revision.page_id)
+                .or_default()
+                .push(revision);
+        }
+
+        // Get the revision just before the oldest unseen one on each page,
+        // so that it can be summarized against what came before.
+        let previous_condition = unseen_by_page
+            .values()
+            .filter_map(|revisions| revisions.last())
+            .filter(|revision| revision.revision_number > 0)
+            .fold(Condition::any(), |condition, revision| {
+                condition.add(
+                    Condition::all()
+                        .add(page_revision::Column::PageId.eq(revision.page_id))
+                        .add(
+                            page_revision::Column::RevisionNumber
+                                .eq(revision.revision_number - 1),
+                        ),
+                )
+            });
+
+        let previous_by_page = if previous_condition.is_empty() {
+            HashMap::new()
+        } else {
+            PageRevision::find()
+                .filter(previous_condition)
+                .all(txn)
+                .await?
+                .into_iter()
+                .map(|revision| (revision.page_id, revision))
+                .collect()
+        };
+
+        // Get wikitext sizes for everything being summarized in one go
+        let mut hashes = Vec::new();
+        for revisions in unseen_by_page.values() {
+            let end = revisions.len().min(UNSEEN_SUMMARY_LIMIT + 1);
+            hashes.extend(&revisions[..end]);
+        }
+        hashes.extend(previous_by_page.values());
+
+        let hashes = hashes
+            .iter()
+            .map(|revision| revision.wikitext_hash.as_slice())
+            .collect::<Vec<_>>();
+
+        let sizes = TextService::sizes(ctx, &hashes).await?;
+
+        // Build watchlist
+        let mut watchlist = Vec::new();
+        for page in pages {
+            let latest = match latest.get(&page.page_id) {
+                Some(latest) => latest,
+                None => {
+                    warn!("Page ID {} has no latest revision, skipping", page.page_id);
+                    continue;
+                }
+            };
+
+            let revisions = unseen_by_page.remove(&page.page_id).unwrap_or_default();
+            let unseen_revisions = revisions
+                .iter()
+                .enumerate()
+                .take(UNSEEN_SUMMARY_LIMIT)
+                .map(|(index, revision)| {
+                    // Revisions are newest first, so the one before is the next one
+                    let previous = revisions
+                        .get(index + 1)
+                        .or_else(|| previous_by_page.get(&page.page_id));
+
+                    UnseenRevision {
+                        revision_id: revision.revision_id,
+                        revision_number: revision.revision_number,
+                        revision_type: revision.revision_type,
+                        created_at: revision.created_at,
+                        user_id: revision.user_id,
+                        summary: PageRevisionService::summarize_with(
+                            revision, previous, &sizes,
+                        ),
+                    }
+                })
+                .collect();
+
+            watchlist.push(WatchedPage {
+                site_id: page.site_id,
+                page_id: page.page_id,
+                slug: page.slug,
+                title: latest.title.clone(),
+                watched_at: watched_at[&page.page_id],
+                latest_revision_number: latest.revision_number,
+                last_seen_revision_number: seen.get(&page.page_id).copied(),
+                unseen_count: revisions.len(),
+                unseen_revisions,
+            });
+        }
+
+        // Stable sort, so pages with nothing unseen stay in slug order
+        watchlist.sort_by(|a, b| {
+            let a = a
+                .unseen_revisions
+                .first()
+                .map(|revision| revision.created_at);
+            let b = b
+                .unseen_revisions
+                .first()
+                .map(|revision| revision.created_at);
+            b.cmp(&a)
+        });
+
+        Ok(watchlist)
+    }
+
+    /// Records that the user has seen a page up to the given revision.
+    ///
+    /// This never moves backwards, so seeing an older revision later on
+    /// doesn't bring back changes which were already seen.
+    pub async fn mark_seen(
+        ctx: &ServiceContext<'_>,
+        MarkWatchSeen {
+            site_id,
+            page_id,
+            user_id,
+            revision_number,
+        }: MarkWatchSeen,
+    ) -> Result<()> {
+        let revision = match revision_number {
+            Some(revision_number) => {
+                PageRevisionService::get(ctx, site_id, page_id, revision_number).await?
+            }
+            None => PageRevisionService::get_latest(ctx, site_id, page_id).await?,
+        };
+
+        info!(
+            "Marking page ID {page_id} seen up to revision {} for user ID {user_id}",
+            revision.revision_number,
+        );
+
+        let txn = ctx.transaction();
+        let existing = PageWatchSeen::find_by_id((user_id, page_id))
+            .one(txn)
+            .await?;
+
+        let model = page_watch_seen::ActiveModel {
+            user_id: Set(user_id),
+            page_id: Set(page_id),
+            revision_number: Set(revision.revision_number),
+            seen_at: Set(now()),
+        };
+
+        match existing {
+            None => {
+                model.insert(txn).await?;
+            }
+            Some(seen) if seen.revision_number < revision.revision_number => {
+                model.update(txn).await?;
+            }
+            Some(_) => debug!("Page was already seen past this revision"),
+        }
+
+        Ok(())
+    }
+}
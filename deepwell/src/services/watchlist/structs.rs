@@ -0,0 +1,61 @@
+/*
+ * services/watchlist/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::models::sea_orm_active_enums::PageRevisionType;
+use crate::services::page_revision::RevisionSummary;
+use time::OffsetDateTime;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct MarkWatchSeen {
+    pub site_id: i64,
+    pub page_id: i64,
+    pub user_id: i64,
+
+    /// The revision which was seen. If not set, the latest revision is used.
+    #[serde(default)]
+    pub revision_number: Option<i64>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct WatchedPage {
+    pub site_id: i64,
+    pub page_id: i64,
+    pub slug: String,
+    pub title: String,
+    pub watched_at: OffsetDateTime,
+    pub latest_revision_number: i64,
+    pub last_seen_revision_number: Option<i64>,
+    pub unseen_count: usize,
+
+    /// Summaries of the most recent unseen revisions, newest first.
+    pub unseen_revisions: Vec<UnseenRevision>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct UnseenRevision {
+    pub revision_id: i64,
+    pub revision_number: i64,
+    pub revision_type: PageRevisionType,
+    pub created_at: OffsetDateTime,
+    pub user_id: i64,
+
+    #[serde(flatten)]
+    pub summary: RevisionSummary,
+}
@@ -20,7 +20,10 @@
 
 //! Utilities related to cryptographic operations and primitives.
 
-use rand::CryptoRng;
+use rand::distributions::{Alphanumeric, DistString};
+use rand::{thread_rng, CryptoRng};
+use sha2::{Digest, Sha256};
+use tiny_keccak::{Hasher, KangarooTwelve};
 
 /// Statically verifies that this random number generator is secure.
 ///
@@ -28,3 +31,29 @@ use rand::CryptoRng;
 /// (cryptographically-secure psuedorandom number generator).
 #[inline]
 pub fn assert_is_csprng(_: &dyn CryptoRng) {}
+
+/// Securely generates a random alphanumeric token, such as for a session or link.
+pub fn new_token(length: usize) -> String {
+    let mut rng = thread_rng();
+    assert_is_csprng(&rng);
+    Alphanumeric.sample_string(&mut rng, length)
+}
+
+/// Hashes a secret token, such as for a login link, for storage.
+///
+/// Only hashes are kept, so the database alone is not enough to use the token.
+pub fn hash_token(token: &str) -> Vec<u8> {
+    Sha256::digest(token.as_bytes()).to_vec()
+}
+
+/// Produces a keyed signature of a message, such as for a signed URL.
+///
+/// KangarooTwelve isn't vulnerable to length extension, so keying it
+/// through its customization string is enough to make this a MAC.
+pub fn sign_message(key: &[u8], message: &[u8]) -> [u8; 32] {
+    let mut signature = [0; 32];
+    let mut hasher = KangarooTwelve::new(key);
+    hasher.update(message);
+    hasher.finalize(&mut signature);
+    signature
+}
@@ -24,14 +24,18 @@ mod category;
 mod crypto;
 mod debug;
 mod locale;
+mod redact;
 mod slug;
 mod string;
 mod time;
+mod url;
 
 pub use self::category::*;
 pub use self::crypto::*;
 pub use self::debug::*;
 pub use self::locale::*;
+pub use self::redact::*;
 pub use self::slug::*;
 pub use self::string::*;
 pub use self::time::*;
+pub use self::url::*;
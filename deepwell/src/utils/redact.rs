@@ -0,0 +1,289 @@
+/*
+ * utils/redact.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Removal of personally-identifying information from free text.
+//!
+//! This is applied to all log output by the logger (see `logger.rs`),
+//! and should be used for anything else which persists text that may
+//! contain such information, like audit entries. Services should not
+//! try to redact values themselves, so that the instance's policy
+//! is applied consistently everywhere.
+
+use once_cell::sync::Lazy;
+use regex::{Captures, NoExpand, Regex};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+static EMAIL_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\b[A-Za-z0-9._%+\-]+@([A-Za-z0-9\-]+(?:\.[A-Za-z0-9\-]+)+)\b").unwrap()
+});
+
+static IPV4_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\b(?:[0-9]{1,3}\.){3}[0-9]{1,3}\b").unwrap());
+
+// This is permissive, candidates are checked by actually parsing them.
+static IPV6_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\b[0-9A-Fa-f]*:[0-9A-Fa-f:]*:[0-9A-Fa-f:.]*\b").unwrap());
+
+/// The instance's policy for what personal information may appear in logs.
+#[derive(Debug, Clone)]
+pub struct RedactionPolicy {
+    emails: bool,
+    ipv4_prefix_bits: u8,
+    ipv6_prefix_bits: u8,
+    session_token: Option<(Regex, String)>,
+}
+
+impl RedactionPolicy {
+    /// Creates a new redaction policy.
+    ///
+    /// IP addresses are truncated to the given prefix length,
+    /// so a value of `32` or `128` respectively leaves them intact,
+    /// and `0` removes them entirely.
+    ///
+    /// Session tokens are recognized by their prefix, so if it is empty
+    /// then they cannot be redacted.
+    pub fn new(
+        emails: bool,
+        session_tokens: bool,
+        session_token_prefix: &str,
+        ipv4_prefix_bits: u8,
+        ipv6_prefix_bits: u8,
+    ) -> Self {
+        assert!(ipv4_prefix_bits <= 32, "IPv4 prefix length too large");
+        assert!(ipv6_prefix_bits <= 128, "IPv6 prefix length too large");
+
+        let session_token = if session_tokens && !session_token_prefix.is_empty() {
+            let pattern = format!("{}[A-Za-z0-9]+", regex::escape(session_token_prefix));
+            let regex =
+                Regex::new(&pattern).expect("Unable to compile session token regex");
+            let replacement = format!("{session_token_prefix}[redacted]");
+            Some((regex, replacement))
+        } else {
+            None
+        };
+
+        RedactionPolicy {
+            emails,
+            ipv4_prefix_bits,
+            ipv6_prefix_bits,
+            session_token,
+        }
+    }
+
+    #[inline]
+    pub fn redacts_emails(&self) -> bool {
+        self.emails
+    }
+
+    #[inline]
+    pub fn redacts_session_tokens(&self) -> bool {
+        self.session_token.is_some()
+    }
+
+    #[inline]
+    pub fn ip_prefix_bits(&self) -> (u8, u8) {
+        (self.ipv4_prefix_bits, self.ipv6_prefix_bits)
+    }
+
+    /// Zeroes out the host portion of an IP address past the configured prefix.
+    pub fn truncate_ip(&self, ip: IpAddr) -> IpAddr {
+        match ip {
+            IpAddr::V4(ip) => {
+                let mask = u32::MAX
+                    .checked_shl(32 - u32::from(self.ipv4_prefix_bits))
+                    .unwrap_or(0);
+
+                IpAddr::V4(Ipv4Addr::from(u32::from(ip) & mask))
+            }
+            IpAddr::V6(ip) => {
+                let mask = u128::MAX
+                    .checked_shl(128 - u32::from(self.ipv6_prefix_bits))
+                    .unwrap_or(0);
+
+                IpAddr::V6(Ipv6Addr::from(u128::from(ip) & mask))
+            }
+        }
+    }
+
+    /// Replaces any personal information in the text, according to this policy.
+    pub fn scrub(&self, text: &str) -> String {
+        let mut text = str!(text);
+
+        if let Some((ref regex, ref replacement)) = self.session_token {
+            if regex.is_match(&text) {
+                text = regex.replace_all(&text, NoExpand(replacement)).into_owned();
+            }
+        }
+
+        if self.emails && EMAIL_REGEX.is_match(&text) {
+            text = EMAIL_REGEX
+                .replace_all(&text, "[redacted]@${1}")
+                .into_owned();
+        }
+
+        let mut scrub_ips = |regex: &Regex| {
+            if regex.is_match(&text) {
+                text = regex
+                    .replace_all(&text, |captures: &Captures| {
+                        let value = &captures[0];
+
+                        // Avoid mangling things like "::" in Rust paths,
+                        // real addresses essentially always have a digit.
+                        if !value.bytes().any(|b| b.is_ascii_digit()) {
+                            return str!(value);
+                        }
+
+                        match value.parse::<IpAddr>() {
+                            Ok(ip) => self.truncate_ip(ip).to_string(),
+                            Err(_) => str!(value),
+                        }
+                    })
+                    .into_owned();
+            }
+        };
+
+        if self.ipv4_prefix_bits < 32 {
+            scrub_ips(&IPV4_REGEX);
+        }
+
+        if self.ipv6_prefix_bits < 128 {
+            scrub_ips(&IPV6_REGEX);
+        }
+
+        text
+    }
+
+    /// Replaces any personal information in the string values of a JSON document.
+    ///
+    /// Object keys are left as-is, since these are chosen by the caller.
+    pub fn scrub_json(&self, value: serde_json::Value) -> serde_json::Value {
+        match value {
+            serde_json::Value::String(text) => {
+                serde_json::Value::String(self.scrub(&text))
+            }
+            serde_json::Value::Array(values) => serde_json::Value::Array(
+                values
+                    .into_iter()
+                    .map(|value| self.scrub_json(value))
+                    .collect(),
+            ),
+            serde_json::Value::Object(map) => serde_json::Value::Object(
+                map.into_iter()
+                    .map(|(key, value)| (key, self.scrub_json(value)))
+                    .collect(),
+            ),
+            value => value,
+        }
+    }
+}
+
+#[test]
+fn truncate_ip() {
+    macro_rules! check {
+        ($policy:expr, $input:expr, $expected:expr $(,)?) => {{
+            let input: IpAddr = $input.parse().unwrap();
+            let expected: IpAddr = $expected.parse().unwrap();
+            assert_eq!(
+                $policy.truncate_ip(input),
+                expected,
+                "Truncated IP address doesn't match expected",
+            );
+        }};
+    }
+
+    let policy = RedactionPolicy::new(true, true, "wj:", 24, 48);
+    check!(policy, "192.168.1.100", "192.168.1.0");
+    check!(policy, "10.0.0.1", "10.0.0.0");
+    check!(
+        policy,
+        "2001:db8:85a3:8d3:1319:8a2e:370:7348",
+        "2001:db8:85a3::"
+    );
+    check!(policy, "::1", "::");
+
+    let policy = RedactionPolicy::new(true, true, "wj:", 32, 0);
+    check!(policy, "192.168.1.100", "192.168.1.100");
+    check!(policy, "2001:db8:85a3:8d3:1319:8a2e:370:7348", "::");
+}
+
+#[test]
+fn scrub() {
+    macro_rules! check {
+        ($policy:expr, $input:expr, $expected:expr $(,)?) => {{
+            assert_eq!(
+                $policy.scrub($input),
+                $expected,
+                "Scrubbed text doesn't match expected",
+            );
+        }};
+    }
+
+    let policy = RedactionPolicy::new(true, true, "wj:", 24, 48);
+    check!(policy, "Nothing to see here", "Nothing to see here");
+    check!(
+        policy,
+        "Looking for user matching name or email 'jdoe@example.com'",
+        "Looking for user matching name or email '[redacted]@example.com'",
+    );
+    check!(
+        policy,
+        "Looking up session with token wj:T9iF6vfjoYYE20QzrybV2C1V4K0L",
+        "Looking up session with token wj:[redacted]",
+    );
+    check!(policy, "Login from 203.0.113.47", "Login from 203.0.113.0");
+    check!(
+        policy,
+        "Login from 2001:db8:85a3:8d3:1319:8a2e:370:7348",
+        "Login from 2001:db8:85a3::",
+    );
+    check!(
+        policy,
+        "Revision type PageRevisionType::Create",
+        "Revision type PageRevisionType::Create",
+    );
+    check!(policy, "Serving on [::]:2747", "Serving on [::]:2747");
+
+    let policy = RedactionPolicy::new(false, false, "wj:", 32, 128);
+    check!(
+        policy,
+        "jdoe@example.com from 203.0.113.47 with wj:abc123",
+        "jdoe@example.com from 203.0.113.47 with wj:abc123",
+    );
+}
+
+#[test]
+fn scrub_json() {
+    use serde_json::json;
+
+    let policy = RedactionPolicy::new(true, true, "wj:", 24, 48);
+    assert_eq!(
+        policy.scrub_json(json!({
+            "reason": "Reported by jdoe@example.com",
+            "pages": [{"slug": "start", "ip": "203.0.113.47"}],
+            "count": 4,
+        })),
+        json!({
+            "reason": "Reported by [redacted]@example.com",
+            "pages": [{"slug": "start", "ip": "203.0.113.0"}],
+            "count": 4,
+        }),
+        "Scrubbed JSON doesn't match expected",
+    );
+}
@@ -50,3 +50,63 @@ pub fn trim_end_matches_in_place(string: &mut String, pattern: &str) {
         string.drain(pattern.len() - 1..);
     }
 }
+
+/// Escapes the wildcards in a string, so it matches literally in a `LIKE` pattern.
+pub fn escape_like(string: &str) -> String {
+    let mut pattern = String::with_capacity(string.len());
+    for ch in string.chars() {
+        if matches!(ch, '%' | '_' | '\\') {
+            pattern.push('\\');
+        }
+
+        pattern.push(ch);
+    }
+
+    pattern
+}
+
+/// Formats a change in size for display, such as `+2.3 MB` or `-512 B`.
+///
+/// Uses decimal (SI) units, with one decimal place past the bytes range.
+pub fn format_byte_delta(delta: i64) -> String {
+    const UNITS: [&str; 5] = ["B", "kB", "MB", "GB", "TB"];
+
+    let sign = if delta < 0 { '-' } else { '+' };
+    let bytes = delta.unsigned_abs();
+    if bytes < 1000 {
+        return format!("{sign}{bytes} B");
+    }
+
+    let mut value = bytes as f64;
+    let mut unit = 0;
+
+    // Compare against the rounding threshold, so 999,999 bytes is "1.0 MB"
+    while value >= 999.95 && unit < UNITS.len() - 1 {
+        value /= 1000.0;
+        unit += 1;
+    }
+
+    format!("{sign}{value:.1} {}", UNITS[unit])
+}
+
+#[test]
+fn byte_delta() {
+    macro_rules! check {
+        ($delta:expr, $expected:expr $(,)?) => {
+            assert_eq!(
+                format_byte_delta($delta),
+                $expected,
+                "Formatted byte delta doesn't match expected",
+            );
+        };
+    }
+
+    check!(0, "+0 B");
+    check!(512, "+512 B");
+    check!(-999, "-999 B");
+    check!(1000, "+1.0 kB");
+    check!(-1500, "-1.5 kB");
+    check!(2_300_000, "+2.3 MB");
+    check!(999_999, "+1.0 MB");
+    check!(4_200_000_000, "+4.2 GB");
+}
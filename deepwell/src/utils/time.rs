@@ -24,3 +24,25 @@ use time::OffsetDateTime;
 pub fn now() -> OffsetDateTime {
     OffsetDateTime::now_utc()
 }
+
+/// Produces a date element for the given time, for use in generated wikitext.
+///
+/// This is used instead of formatting the time directly, so that it is rendered
+/// like any other date, and can be shown in the reader's own timezone.
+pub fn date_wikitext(datetime: OffsetDateTime) -> String {
+    format!("[[date {}]]", datetime.unix_timestamp())
+}
+
+#[test]
+fn date_element() {
+    use time::macros::datetime;
+
+    assert_eq!(
+        date_wikitext(datetime!(2010-01-01 08:10 UTC)),
+        "[[date 1262333400]]",
+    );
+    assert_eq!(
+        date_wikitext(datetime!(2010-01-01 17:10 +09:00)),
+        "[[date 1262333400]]",
+    );
+}
@@ -0,0 +1,96 @@
+/*
+ * utils/url.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use reqwest::Url;
+
+/// Matches the SCP-like syntax for SSH Git remotes, e.g. `git@example.com:repo.git`.
+static SCP_REMOTE_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^[A-Za-z0-9_][A-Za-z0-9._-]*@[A-Za-z0-9.-]+:[A-Za-z0-9._~/-]+$").unwrap()
+});
+
+/// Determines if the given string is an absolute `http` or `https` URL.
+pub fn is_http_url(value: &str) -> bool {
+    match Url::parse(value) {
+        Ok(url) => matches!(url.scheme(), "http" | "https") && url.has_host(),
+        Err(_) => false,
+    }
+}
+
+/// Determines if the given string is a Git remote reachable over the network.
+///
+/// This is an `https` or `ssh` URL, or the SCP-like `user@host:path` syntax.
+/// Other transports, such as local paths or `ext::`, are not permitted.
+pub fn is_git_remote_url(value: &str) -> bool {
+    match Url::parse(value) {
+        Ok(url) => matches!(url.scheme(), "https" | "ssh") && url.has_host(),
+        Err(_) => SCP_REMOTE_REGEX.is_match(value),
+    }
+}
+
+/// Percent-encodes a string for use as a single URL path segment.
+///
+/// Everything except unreserved characters is encoded, including `/`.
+pub fn encode_path_segment(value: &str) -> String {
+    let mut output = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                output.push(char::from(byte));
+            }
+            _ => output.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    output
+}
+
+#[test]
+fn http_url() {
+    assert!(is_http_url("https://example.com/"));
+    assert!(is_http_url("http://scp-wiki.wikijump.com/scp-001"));
+    assert!(!is_http_url("/scp-001"));
+    assert!(!is_http_url("scp-001"));
+    assert!(!is_http_url("javascript:alert(1)"));
+    assert!(!is_http_url("ftp://example.com/file"));
+}
+
+#[test]
+fn git_remote_url() {
+    assert!(is_git_remote_url("https://github.com/scpwiki/mirror.git"));
+    assert!(is_git_remote_url("ssh://git@example.com:2222/wiki.git"));
+    assert!(is_git_remote_url("git@github.com:scpwiki/mirror.git"));
+    assert!(!is_git_remote_url("http://example.com/wiki.git"));
+    assert!(!is_git_remote_url("file:///var/lib/wiki.git"));
+    assert!(!is_git_remote_url("/var/lib/wiki.git"));
+    assert!(!is_git_remote_url("ext::sh -c touch% /tmp/pwned"));
+    assert!(!is_git_remote_url("--upload-pack=touch@host:path"));
+}
+
+#[test]
+fn path_segment() {
+    assert_eq!(encode_path_segment("image.png"), "image.png");
+    assert_eq!(
+        encode_path_segment("my file (1).png"),
+        "my%20file%20%281%29.png"
+    );
+    assert_eq!(encode_path_segment("../etc/passwd"), "..%2Fetc%2Fpasswd");
+    assert_eq!(encode_path_segment("café.jpg"), "caf%C3%A9.jpg");
+}
@@ -26,6 +26,7 @@ mod page_details;
 mod page_order;
 mod provided_value;
 mod reference;
+mod render_severity;
 
 pub use self::bytes::Bytes;
 pub use self::connection_type::ConnectionType;
@@ -35,3 +36,4 @@ pub use self::page_details::PageDetails;
 pub use self::page_order::{PageOrder, PageOrderColumn};
 pub use self::provided_value::ProvidedValue;
 pub use self::reference::Reference;
+pub use self::render_severity::RenderSeverity;
@@ -58,6 +58,16 @@ where
     }
 }
 
+impl<T> From<Option<T>> for ProvidedValue<T> {
+    #[inline]
+    fn from(value: Option<T>) -> ProvidedValue<T> {
+        match value {
+            Some(value) => ProvidedValue::Set(value),
+            None => ProvidedValue::Unset,
+        }
+    }
+}
+
 impl<T> From<ProvidedValue<T>> for Option<T> {
     #[inline]
     fn from(value: ProvidedValue<T>) -> Option<T> {
@@ -0,0 +1,70 @@
+/*
+ * web/render_severity.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use ftml::parsing::ParseErrorKind;
+
+/// How serious a diagnostic produced while rendering wikitext is.
+///
+/// The parser never fails outright, instead falling back to other rules,
+/// so this is a judgement of how likely the output differs from what the
+/// author intended. Variants are ordered from least to most severe.
+#[derive(
+    Serialize, Deserialize, Debug, Copy, Clone, Hash, PartialEq, Eq, PartialOrd, Ord,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum RenderSeverity {
+    /// Syntax which was not matched and rendered as text, which is routine.
+    Notice,
+
+    /// A structure which was malformed, and rendered differently than written.
+    Warning,
+
+    /// A reference to something which does not exist, or a block which could not be parsed.
+    Error,
+}
+
+impl RenderSeverity {
+    pub fn of(kind: ParseErrorKind) -> Self {
+        match kind {
+            ParseErrorKind::EndOfInput
+            | ParseErrorKind::NoRulesMatch
+            | ParseErrorKind::RuleFailed
+            | ParseErrorKind::NotStartOfLine => RenderSeverity::Notice,
+
+            ParseErrorKind::RecursionDepthExceeded
+            | ParseErrorKind::InvalidInclude
+            | ParseErrorKind::NoSuchBlock
+            | ParseErrorKind::BlockMissingName
+            | ParseErrorKind::BlockMissingCloseBrackets
+            | ParseErrorKind::BlockMalformedArguments
+            | ParseErrorKind::BlockMissingArguments
+            | ParseErrorKind::BlockNameNotLowercase
+            | ParseErrorKind::BlockDuplicateArgument
+            | ParseErrorKind::BlockEndMismatch
+            | ParseErrorKind::NoSuchEmbed
+            | ParseErrorKind::NoSuchModule
+            | ParseErrorKind::ModuleMissingName
+            | ParseErrorKind::NoSuchPage
+            | ParseErrorKind::InvalidUrl => RenderSeverity::Error,
+
+            _ => RenderSeverity::Warning,
+        }
+    }
+}
@@ -0,0 +1,29 @@
+/**
+ * Rendered HTML is shared by everyone viewing a page, so dates in it are in
+ * UTC. This shows them in the viewer's own timezone instead, using the
+ * timestamp each `<time>` element carries.
+ */
+export function localizeDates(node: HTMLElement, _html?: string) {
+  const format = new Intl.DateTimeFormat(undefined, {
+    dateStyle: "long",
+    timeStyle: "short",
+    timeZoneName: "shortOffset"
+  })
+
+  function localize() {
+    for (const elem of node.querySelectorAll<HTMLTimeElement>("time.wj-date")) {
+      // Plain dates have no time of day, so they're the same in every timezone
+      const timestamp = Number(elem.dataset.timestamp)
+      if (!elem.dateTime.includes("T") || !Number.isFinite(timestamp)) continue
+
+      elem.textContent = format.format(new Date(timestamp * 1000))
+    }
+  }
+
+  localize()
+
+  return {
+    // Called whenever the HTML changes, e.g. when viewing another revision
+    update: localize
+  }
+}
@@ -0,0 +1,24 @@
+import { client } from "$lib/server/deepwell/index.ts"
+
+/**
+ * Refreshes the session in the request's cookie, since the user is active.
+ *
+ * DEEPWELL may rotate the session, in which case the cookie is updated
+ * to the new token. Returns the current session, or null if there is none.
+ */
+export async function authRefreshSession(cookies): Promise<object | null> {
+  const sessionToken = cookies.get("wikijump_token")
+  if (!sessionToken) return null
+
+  const session = await client.request("session_refresh", [sessionToken])
+  if (session?.session_token && session.session_token !== sessionToken) {
+    cookies.set("wikijump_token", session.session_token, {
+      path: "/",
+      httpOnly: true,
+      secure: true,
+      sameSite: "lax"
+    })
+  }
+
+  return session
+}
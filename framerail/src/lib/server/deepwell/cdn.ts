@@ -0,0 +1,15 @@
+import { client } from "$lib/server/deepwell/index.ts"
+
+export async function cdnGet(
+  hash: string,
+  name: string,
+  expires: Optional<number>,
+  signature: Optional<string>
+): Promise<object> {
+  return client.request("cdn_get", {
+    hash,
+    name,
+    expires,
+    signature
+  })
+}
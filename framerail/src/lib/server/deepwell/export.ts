@@ -0,0 +1,8 @@
+import { client } from "$lib/server/deepwell/index.ts"
+
+export async function exportDownload(exportId: number, userId: number): Promise<object> {
+  return client.request("export_download", {
+    export_id: exportId,
+    user_id: userId
+  })
+}
@@ -0,0 +1,8 @@
+import { client } from "$lib/server/deepwell/index.ts"
+
+export async function gitMirrorFile(domain: string, path: string): Promise<object> {
+  return client.request("git_mirror_file", {
+    domain,
+    path
+  })
+}
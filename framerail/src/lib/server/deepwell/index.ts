@@ -5,9 +5,18 @@ import { JSONRPCClient, JSONRPCRequest } from "json-rpc-2.0"
 export const DEEPWELL_HOST = process.env.DEEPWELL_HOST || "localhost"
 export const DEEPWELL_PORT = 2747
 export const DEEPWELL_URL = `http://${DEEPWELL_HOST}:${DEEPWELL_PORT}/jsonrpc`
+export const DEEPWELL_SERVICE_KEY = process.env.DEEPWELL_SERVICE_KEY
 export const client = new JSONRPCClient(processRawRequest)
 
 async function processRawRequest(request: JSONRPCRequest): Promise<void> {
+  // Wrap the parameters so the key works for positional parameters too
+  if (DEEPWELL_SERVICE_KEY) {
+    request = {
+      ...request,
+      params: { service_key: DEEPWELL_SERVICE_KEY, params: request.params ?? null }
+    }
+  }
+
   const response = await fetch(DEEPWELL_URL, {
     method: "POST",
     headers: { "content-type": "application/json" },
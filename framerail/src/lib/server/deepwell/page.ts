@@ -6,6 +6,7 @@ export async function pageDelete(
   siteId: number,
   pageId: Optional<number>,
   userId: number,
+  sessionToken: Optional<string>,
   slug: string,
   revisionComments: Optional<string>
 ): Promise<object> {
@@ -13,6 +14,7 @@ export async function pageDelete(
     site_id: siteId,
     page: pageId ?? slug,
     user_id: userId,
+    session_token: sessionToken,
     revision_comments: revisionComments
   })
 }
@@ -21,22 +23,26 @@ export async function pageEdit(
   siteId: number,
   pageId: Optional<number>,
   userId: number,
+  sessionToken: Optional<string>,
   slug: string,
   revisionComments: Optional<string>,
   wikitext: string,
   title: string,
   altTitle: string,
-  tags: string[]
+  tags: string[],
+  baseRevisionId: Optional<number>
 ): Promise<object> {
   return client.request(pageId ? "page_edit" : "page_create", {
     site_id: siteId,
     page: pageId ?? slug,
     user_id: userId,
+    session_token: sessionToken,
     revision_comments: revisionComments,
     wikitext,
     title,
     alt_title: altTitle,
-    tags
+    tags,
+    base_revision_id: baseRevisionId
   })
 }
 
@@ -59,6 +65,7 @@ export async function pageMove(
   siteId: number,
   pageId: Optional<number>,
   userId: number,
+  sessionToken: Optional<string>,
   slug: string,
   newSlug: string,
   revisionComments: Optional<string>
@@ -68,6 +75,7 @@ export async function pageMove(
     page: pageId ?? slug,
     new_slug: newSlug,
     user_id: userId,
+    session_token: sessionToken,
     revision_comments: revisionComments
   })
 }
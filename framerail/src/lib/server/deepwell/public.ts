@@ -0,0 +1,53 @@
+import { client } from "$lib/server/deepwell/index.ts"
+
+export async function publicPageGet(domain: string, slug: string): Promise<object> {
+  return client.request("public_page_get", {
+    domain,
+    slug
+  })
+}
+
+export async function publicPageList(
+  domain: string,
+  category: Optional<string>,
+  offset: number
+): Promise<object> {
+  return client.request("public_page_list", {
+    domain,
+    category,
+    offset
+  })
+}
+
+export async function publicUserGet(slug: string): Promise<object> {
+  return client.request("public_user_get", [slug])
+}
+
+/**
+ * Produces the response for a public API request.
+ *
+ * These are tagged with cache keys, so that when DEEPWELL purges
+ * a key, every response containing that data is removed from the CDN.
+ */
+export function publicResponse(output): Response {
+  const headers = {
+    "Content-Type": "application/json",
+    "Cache-Control": output.cache_control,
+    "Access-Control-Allow-Origin": "*"
+  }
+
+  if (output.cache_keys.length > 0) {
+    // Fastly and Varnish use space-separated keys, Cloudflare uses commas
+    headers["Surrogate-Key"] = output.cache_keys.join(" ")
+    headers["Cache-Tag"] = output.cache_keys.join(",")
+  }
+
+  if (output.data === null) {
+    return new Response(JSON.stringify({ message: "Not found" }), {
+      status: 404,
+      headers
+    })
+  }
+
+  return new Response(JSON.stringify(output.data), { headers })
+}
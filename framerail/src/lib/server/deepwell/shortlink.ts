@@ -0,0 +1,8 @@
+import { client } from "$lib/server/deepwell/index.ts"
+
+export async function shortlinkResolve(domain: string, code: string): Promise<object> {
+  return client.request("shortlink_resolve", {
+    domain,
+    code
+  })
+}
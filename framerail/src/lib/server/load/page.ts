@@ -1,6 +1,9 @@
+import { dev } from "$app/environment"
 import defaults from "$lib/defaults"
 import { parseAcceptLangHeader } from "$lib/locales"
+import { authRefreshSession } from "$lib/server/auth/refreshSession"
 import { translate } from "$lib/server/deepwell/translate"
+import { shortlinkResolve } from "$lib/server/deepwell/shortlink.ts"
 import { pageView } from "$lib/server/deepwell/views.ts"
 import type { TranslateKeys } from "$lib/types"
 import type { Optional } from "$lib/types.ts"
@@ -12,13 +15,15 @@ export async function loadPage(
   slug: Optional<string>,
   extra: Optional<string>,
   request,
-  cookies
+  cookies,
+  setHeaders
 ) {
   // Set up parameters
   const url = new URL(request.url)
   const domain = url.hostname
   const route = slug || extra ? { slug, extra } : null
-  const sessionToken = cookies.get("wikijump_token")
+  const session = await authRefreshSession(cookies)
+  const sessionToken = session?.session_token
   let locales = parseAcceptLangHeader(request)
 
   // TODO insert user preference at the beginning of the list
@@ -57,6 +62,14 @@ export async function loadPage(
       errorStatus = 403
       break
     case "site_missing":
+      // This may be a site's short domain instead
+      if (slug && !extra) {
+        const shortlink = await shortlinkResolve(domain, slug)
+        if (shortlink) {
+          throw redirect(302, shortlink.url)
+        }
+      }
+
       checkRedirect = false
       errorStatus = 404
   }
@@ -101,6 +114,14 @@ export async function loadPage(
     }
   }
 
+  // Only allow the inline styles the page was rendered with.
+  // Vite injects styles while developing, so these would be blocked.
+  if (!dev && viewData.style_sources) {
+    setHeaders({
+      "Content-Security-Policy": ["style-src 'self'", ...viewData.style_sources].join(" ")
+    })
+  }
+
   const translated = await translate(locales, translateKeys)
 
   viewData.internationalization = translated
@@ -1,5 +1,6 @@
 import defaults from "$lib/defaults"
 import { parseAcceptLangHeader } from "$lib/locales"
+import { authRefreshSession } from "$lib/server/auth/refreshSession"
 import { translate } from "$lib/server/deepwell/translate"
 import { userView } from "$lib/server/deepwell/user.ts"
 import type { TranslateKeys } from "$lib/types"
@@ -8,7 +9,8 @@ import { error, redirect } from "@sveltejs/kit"
 export async function loadUser(username?: string, request, cookies) {
   const url = new URL(request.url)
   const domain = url.hostname
-  const sessionToken = cookies.get("wikijump_token")
+  const session = await authRefreshSession(cookies)
+  const sessionToken = session?.session_token
   let locales = parseAcceptLangHeader(request)
 
   if (!locales.includes(defaults.fallbackLocale)) locales.push(defaults.fallbackLocale)
@@ -0,0 +1,6 @@
+import type { ParamMatcher } from "@sveltejs/kit"
+
+// Blob hashes are SHA-512, in hexadecimal
+export const match: ParamMatcher = (param) => {
+  return /^[0-9a-f]{128}$/.test(param)
+}
@@ -1,5 +1,5 @@
 import { loadPage } from "$lib/server/load/page.ts"
 
-export async function load({ request, cookies }) {
-  return loadPage(null, null, request, cookies)
+export async function load({ request, cookies, setHeaders }) {
+  return loadPage(null, null, request, cookies, setHeaders)
 }
@@ -1,5 +1,5 @@
 import { loadPage } from "$lib/server/load/page.ts"
 
-export async function load({ params, request, cookies }) {
-  return loadPage(params.slug, params.extra, request, cookies)
+export async function load({ params, request, cookies, setHeaders }) {
+  return loadPage(params.slug, params.extra, request, cookies, setHeaders)
 }
@@ -1,4 +1,4 @@
-import { authGetSession } from "$lib/server/auth/getSession"
+import { authRefreshSession } from "$lib/server/auth/refreshSession"
 import * as page from "$lib/server/deepwell/page"
 
 // Handling of server events from client
@@ -7,11 +7,11 @@ export async function POST(event) {
   let data = await event.request.formData()
   let slug = event.params.slug
 
-  let userSession = event.cookies.get("wikijump_token")
   let ipAddr = event.getClientAddress()
   let userAgent = event.cookies.get("User-Agent")
 
-  let session = await authGetSession(userSession)
+  let session = await authRefreshSession(event.cookies)
+  let userSession = session?.session_token
 
   let extra = event.params.extra
     ?.toLowerCase()
@@ -32,6 +32,8 @@ export async function POST(event) {
       let wikitext = data.get("wikitext")?.toString()
       let title = data.get("title")?.toString()
       let altTitle = data.get("alt-title")?.toString()
+      let baseRevisionIdStr = data.get("base-revision-id")?.toString()
+      let baseRevisionId = baseRevisionIdStr ? parseInt(baseRevisionIdStr) : null
       let tagsStr = data.get("tags")?.toString().trim()
       let tags: string[] = []
       if (tagsStr?.length) tags = tagsStr.split(" ").filter((tag) => tag.length)
@@ -40,12 +42,14 @@ export async function POST(event) {
         siteId,
         pageId,
         session?.user_id,
+        userSession,
         slug,
         comments,
         wikitext,
         title,
         altTitle,
-        tags
+        tags,
+        baseRevisionId
       )
     } else if (extra.includes("history")) {
       /** Retrieve page revision list. */
@@ -60,7 +64,15 @@ export async function POST(event) {
       let comments = data.get("comments")?.toString() ?? ""
       let newSlug = data.get("new-slug")?.toString()
 
-      res = await page.pageMove(siteId, pageId, session.user_id, slug, newSlug, comments)
+      res = await page.pageMove(
+        siteId,
+        pageId,
+        session.user_id,
+        userSession,
+        slug,
+        newSlug,
+        comments
+      )
     } else if (extra.includes("revision")) {
       let revisionNumberStr = data.get("revision-number")?.toString()
       let compiledHtml = data.get("compiled-html")?.toString() === "true"
@@ -93,11 +105,11 @@ export async function DELETE(event) {
   let data = await event.request.formData()
   let slug = event.params.slug
 
-  let userSession = event.cookies.get("wikijump_token")
   let ipAddr = event.getClientAddress()
   let userAgent = event.cookies.get("User-Agent")
 
-  let session = await authGetSession(userSession)
+  let session = await authRefreshSession(event.cookies)
+  let userSession = session?.session_token
 
   let pageIdVal = data.get("page-id")?.toString()
   let pageId = pageIdVal ? parseInt(pageIdVal) : null
@@ -106,7 +118,14 @@ export async function DELETE(event) {
   let comments = data.get("comments")?.toString() ?? ""
 
   try {
-    let res = await page.pageDelete(siteId, pageId, session?.user_id, slug, comments)
+    let res = await page.pageDelete(
+      siteId,
+      pageId,
+      session?.user_id,
+      userSession,
+      slug,
+      comments
+    )
     return new Response(JSON.stringify(res))
   } catch (error) {
     return new Response(
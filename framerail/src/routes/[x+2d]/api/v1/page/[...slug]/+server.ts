@@ -0,0 +1,7 @@
+import { publicPageGet, publicResponse } from "$lib/server/deepwell/public.ts"
+
+export async function GET(event) {
+  const domain = new URL(event.request.url).hostname
+  const output = await publicPageGet(domain, event.params.slug)
+  return publicResponse(output)
+}
@@ -0,0 +1,14 @@
+import { publicPageList, publicResponse } from "$lib/server/deepwell/public.ts"
+
+export async function GET(event) {
+  const url = new URL(event.request.url)
+  const category = url.searchParams.get("category")
+  const offset = parseInt(url.searchParams.get("offset") ?? "0")
+
+  const output = await publicPageList(
+    url.hostname,
+    category,
+    isNaN(offset) ? 0 : Math.max(offset, 0)
+  )
+  return publicResponse(output)
+}
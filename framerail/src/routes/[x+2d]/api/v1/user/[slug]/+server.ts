@@ -0,0 +1,6 @@
+import { publicUserGet, publicResponse } from "$lib/server/deepwell/public.ts"
+
+export async function GET(event) {
+  const output = await publicUserGet(event.params.slug)
+  return publicResponse(output)
+}
@@ -0,0 +1,26 @@
+import { authRefreshSession } from "$lib/server/auth/refreshSession"
+import { exportDownload } from "$lib/server/deepwell/export.ts"
+import { error } from "@sveltejs/kit"
+
+export async function GET(event) {
+  const exportId = parseInt(event.params.id)
+  if (isNaN(exportId)) {
+    throw error(404, { message: "Export not found" })
+  }
+
+  // Exports can only be downloaded by the user who requested them
+  const session = await authRefreshSession(event.cookies)
+  if (!session?.user_id) {
+    throw error(401, { message: "Log in to download this export" })
+  }
+
+  const output = await exportDownload(exportId, session.user_id)
+
+  // Binary data is sent from DEEPWELL as hex
+  return new Response(Buffer.from(output.data, "hex"), {
+    headers: {
+      "Content-Type": output.mime,
+      "Content-Disposition": `attachment; filename="${output.filename}"`
+    }
+  })
+}
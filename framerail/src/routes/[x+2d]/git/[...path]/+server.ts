@@ -0,0 +1,23 @@
+import { gitMirrorFile } from "$lib/server/deepwell/git.ts"
+import { error } from "@sveltejs/kit"
+
+// Serves a site's Git mirror over the "dumb" HTTP protocol,
+// so it can be cloned with: git clone https://<site>/-/git/
+export async function GET(event) {
+  const domain = new URL(event.request.url).hostname
+
+  let output
+  try {
+    output = await gitMirrorFile(domain, event.params.path)
+  } catch (err) {
+    throw error(404, { message: "Git repository file not found" })
+  }
+
+  // Binary data is sent from DEEPWELL as hex
+  return new Response(Buffer.from(output.data, "hex"), {
+    headers: {
+      "Content-Type": "application/octet-stream",
+      "Cache-Control": "no-cache"
+    }
+  })
+}
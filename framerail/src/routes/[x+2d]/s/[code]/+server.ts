@@ -0,0 +1,14 @@
+import { shortlinkResolve } from "$lib/server/deepwell/shortlink.ts"
+import { error, redirect } from "@sveltejs/kit"
+
+export async function GET(event) {
+  const domain = new URL(event.request.url).hostname
+  const shortlink = await shortlinkResolve(domain, event.params.code)
+
+  if (!shortlink) {
+    throw error(404, { message: "Shortlink not found" })
+  }
+
+  // Not permanent, so that every visit is counted
+  throw redirect(302, shortlink.url)
+}
@@ -0,0 +1,34 @@
+import { cdnGet } from "$lib/server/deepwell/cdn.ts"
+import { error } from "@sveltejs/kit"
+
+// Serves file contents by hash. Since the contents at a path never
+// change, a CDN can cache these without ever needing to purge them.
+//
+// These are served from the wiki's own domain, so the response is
+// sandboxed in case a browser renders it as a document anyway.
+export async function GET(event) {
+  const query = event.url.searchParams
+  const expires = query.has("expires") ? parseInt(query.get("expires")) : null
+  const signature = query.get("signature")
+  if (expires !== null && isNaN(expires)) {
+    throw error(404, { message: "File not found" })
+  }
+
+  let output
+  try {
+    output = await cdnGet(event.params.hash, event.params.name, expires, signature)
+  } catch (err) {
+    throw error(404, { message: "File not found" })
+  }
+
+  // Binary data is sent from DEEPWELL as hex
+  return new Response(Buffer.from(output.data, "hex"), {
+    headers: {
+      "Content-Type": output.mime,
+      "Content-Disposition": output.content_disposition,
+      "Cache-Control": output.cache_control,
+      "X-Content-Type-Options": "nosniff",
+      "Content-Security-Policy": "sandbox"
+    }
+  })
+}
@@ -0,0 +1,120 @@
+/*
+ * examples/parse_bench.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Compares the time taken by `parse()` and `parse_parallel()` on wikitext files.
+//!
+//! Pass the paths of the pages to measure, ideally the largest real-world
+//! pages available, since small inputs aren't split at all:
+//!
+//! ```text
+//! $ cargo run --release --features parallel --example parse_bench -- page.txt
+//! ```
+
+extern crate ftml;
+
+use ftml::data::{PageInfo, ScoreValue};
+use ftml::settings::{WikitextMode, WikitextSettings};
+use std::borrow::Cow;
+use std::env;
+use std::fs;
+use std::process;
+use std::time::{Duration, Instant};
+
+/// How many times to parse each page, the median run is reported.
+const RUNS: usize = 21;
+
+fn main() {
+    let paths: Vec<String> = env::args().skip(1).collect();
+    if paths.is_empty() {
+        eprintln!("No files listed.");
+        process::exit(1);
+    }
+
+    let page_info = PageInfo {
+        page: Cow::Borrowed("bench"),
+        category: None,
+        site: Cow::Borrowed("bench"),
+        title: Cow::Borrowed("Benchmark"),
+        alt_title: None,
+        score: ScoreValue::Integer(0),
+        tags: vec![],
+        language: Cow::Borrowed("default"),
+        utc_offset: None,
+    };
+    let settings = WikitextSettings::from_mode(WikitextMode::Page);
+
+    println!(
+        "{:<40} {:>10} {:>12} {:>12} {:>8}",
+        "page", "bytes", "sequential", "parallel", "speedup",
+    );
+
+    for path in paths {
+        let mut text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(error) => {
+                eprintln!("Unable to read {path}: {error}");
+                process::exit(1);
+            }
+        };
+
+        ftml::preprocess(&mut text);
+        let tokens = ftml::tokenize(&text);
+
+        // Both must produce the same output, or the timings mean nothing
+        let (sequential_tree, sequential_errors) =
+            ftml::parse(&tokens, &page_info, &settings).into();
+        let (parallel_tree, parallel_errors) =
+            ftml::parse_parallel(&tokens, &page_info, &settings).into();
+
+        if sequential_tree != parallel_tree || sequential_errors != parallel_errors {
+            eprintln!("Parallel parse of {path} did not match sequential parse!");
+            process::exit(1);
+        }
+
+        let sequential = median_time(|| {
+            ftml::parse(&tokens, &page_info, &settings);
+        });
+        let parallel = median_time(|| {
+            ftml::parse_parallel(&tokens, &page_info, &settings);
+        });
+
+        println!(
+            "{:<40} {:>10} {:>10.2}ms {:>10.2}ms {:>7.2}x",
+            path,
+            text.len(),
+            sequential.as_secs_f64() * 1000.0,
+            parallel.as_secs_f64() * 1000.0,
+            sequential.as_secs_f64() / parallel.as_secs_f64(),
+        );
+    }
+}
+
+fn median_time<F: FnMut()>(mut f: F) -> Duration {
+    let mut times = (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            f();
+            start.elapsed()
+        })
+        .collect::<Vec<_>>();
+
+    times.sort();
+    times[RUNS / 2]
+}
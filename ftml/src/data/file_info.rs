@@ -0,0 +1,60 @@
+/*
+ * data/file_info.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::borrow::Cow;
+
+/// Returned information about a file attached to a page.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct FileInfo<'a> {
+    /// The name of the file, including its extension.
+    pub file_name: Cow<'a, str>,
+
+    /// The size of the file, in bytes.
+    pub file_size: u64,
+
+    /// The MIME type of the file, such as `application/pdf`.
+    pub file_mime: Cow<'a, str>,
+
+    /// The link to download the file.
+    pub file_url: Cow<'a, str>,
+
+    /// The name of the user who uploaded this version of the file, if known.
+    pub uploader_name: Option<Cow<'a, str>>,
+
+    /// The license the file is available under, if one was given.
+    pub license: Option<Cow<'a, str>>,
+}
+
+impl FileInfo<'_> {
+    /// Generate a dummy FileInfo instance for tests.
+    pub fn dummy() -> Self {
+        FileInfo {
+            file_name: cow!("document.pdf"),
+            file_size: 1_572_864,
+            file_mime: cow!("application/pdf"),
+            file_url: cow!(
+                "https://sandbox.wjfiles.com/local--files/some-page/document.pdf"
+            ),
+            uploader_name: Some(cow!("michal-frackowiak")),
+            license: Some(cow!("CC BY-SA 3.0")),
+        }
+    }
+}
@@ -0,0 +1,69 @@
+/*
+ * data/link_metadata.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::borrow::Cow;
+
+/// What a link in rendered output points to.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Hash, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum LinkKind {
+    /// A page on this or another Wikijump site.
+    Page,
+
+    /// A website reached through an interwiki prefix.
+    Interwiki,
+
+    /// Any other website.
+    External,
+
+    /// A location on the current page.
+    Anchor,
+
+    /// A file attached to a page.
+    File,
+
+    /// A user's profile.
+    User,
+}
+
+impl LinkKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            LinkKind::Page => "page",
+            LinkKind::Interwiki => "interwiki",
+            LinkKind::External => "external",
+            LinkKind::Anchor => "anchor",
+            LinkKind::File => "file",
+            LinkKind::User => "user",
+        }
+    }
+}
+
+/// A link emitted when rendering, after classification.
+///
+/// The target is the normalized URL as it appears in the output,
+/// and `rel` is the value of the `rel` attribute added to it, if any.
+#[derive(Serialize, Deserialize, Debug, Clone, Hash, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct LinkMetadata<'a> {
+    pub kind: LinkKind,
+    pub target: Cow<'a, str>,
+    pub rel: Option<Cow<'a, str>>,
+}
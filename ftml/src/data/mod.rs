@@ -21,15 +21,23 @@
 //! Module for POD (plain old data) structs.
 
 mod backlinks;
+mod file_info;
 mod karma;
+mod link_metadata;
 mod page_info;
 mod page_ref;
+mod render_context;
 mod score;
 mod user_info;
 
 pub use self::backlinks::Backlinks;
+pub use self::file_info::FileInfo;
 pub use self::karma::KarmaLevel;
+pub use self::link_metadata::{LinkKind, LinkMetadata};
 pub use self::page_info::PageInfo;
 pub use self::page_ref::{PageRef, PageRefParseError};
+pub use self::render_context::{
+    AttachedFile, FileReference, ImageDerivative, RenderContext,
+};
 pub use self::score::ScoreValue;
 pub use self::user_info::UserInfo;
@@ -20,6 +20,7 @@
 
 use super::ScoreValue;
 use std::borrow::Cow;
+use time::UtcOffset;
 
 /// Metadata information on the article being rendered.
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
@@ -60,6 +61,14 @@ pub struct PageInfo<'a> {
 
     /// The language that this page is being rendered for.
     pub language: Cow<'a, str>,
+
+    /// The timezone offset of the user viewing this page.
+    ///
+    /// Dates are displayed in this timezone. If `None`, then UTC is used.
+    /// This should be left unset for output which is cached and shown to
+    /// everyone, since `<time>` elements can be adjusted by the client.
+    #[serde(default)]
+    pub utc_offset: Option<UtcOffset>,
 }
 
 impl PageInfo<'_> {
@@ -75,6 +84,7 @@ impl PageInfo<'_> {
             score: ScoreValue::Float(69.0),
             tags: vec![cow!("tale"), cow!("_cc")],
             language: cow!("default"),
+            utc_offset: None,
         }
     }
 }
@@ -0,0 +1,128 @@
+/*
+ * data/render_context.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::{FileInfo, PageInfo};
+use crate::tree::{GallerySize, ImageSource};
+use std::borrow::Cow;
+
+/// Information looked up by the caller ahead of rendering.
+///
+/// Rendering is synchronous, so anything which would need to be fetched,
+/// such as metadata for the files a page refers to, is provided here.
+/// See `SyntaxTree::file_references()` for which files are needed.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct RenderContext<'a> {
+    /// The attached files which this page refers to.
+    ///
+    /// File cards for files which are not present here are shown as missing.
+    pub files: Vec<AttachedFile<'a>>,
+}
+
+impl<'a> RenderContext<'a> {
+    pub fn get_file(&self, reference: &FileReference) -> Option<&AttachedFile<'a>> {
+        self.files.iter().find(|file| file.reference == *reference)
+    }
+}
+
+/// A file attached to a page, along with its metadata.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct AttachedFile<'a> {
+    /// Which file this is, as referred to in the page.
+    pub reference: FileReference<'a>,
+
+    /// Metadata about the current version of the file.
+    pub info: FileInfo<'a>,
+
+    /// Links to resized versions of the file, if it is an image.
+    ///
+    /// Gallery sizes which aren't listed use the file itself.
+    #[serde(default)]
+    pub derivatives: Vec<ImageDerivative<'a>>,
+
+    /// The contents of the file, for embedding images in the output.
+    ///
+    /// Only needed if `embed_image_max_size` is set, and only used
+    /// for images no larger than it.
+    #[serde(default)]
+    pub data: Option<Vec<u8>>,
+}
+
+impl AttachedFile<'_> {
+    pub fn get_derivative(&self, size: GallerySize) -> Option<&str> {
+        self.derivatives
+            .iter()
+            .find(|derivative| derivative.size == size)
+            .map(|derivative| derivative.url.as_ref())
+    }
+}
+
+/// A resized version of an attached image, for showing at a gallery size.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct ImageDerivative<'a> {
+    pub size: GallerySize,
+    pub url: Cow<'a, str>,
+}
+
+/// Refers to a file attached to a page, such as for an image or file card.
+///
+/// The fields are as written in the wikitext, and are not normalized.
+#[derive(Serialize, Deserialize, Debug, Clone, Hash, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct FileReference<'a> {
+    /// The slug of the site the page is on.
+    pub site: Cow<'a, str>,
+
+    /// The full slug of the page, including its category if any.
+    pub page: Cow<'a, str>,
+
+    /// The name of the file.
+    pub file: Cow<'a, str>,
+}
+
+impl<'a> FileReference<'a> {
+    /// Gets the file an image source refers to, if any.
+    ///
+    /// Sources from arbitrary URLs are not attached files, and so return `None`.
+    /// Sources which omit the site or page are relative to the current page.
+    pub fn from_source(source: &'a ImageSource, info: &'a PageInfo) -> Option<Self> {
+        let (site, page, file): (&str, Cow<str>, &str) = match source {
+            ImageSource::Url(_) => return None,
+            ImageSource::File1 { file } => {
+                let page: Cow<str> = match &info.category {
+                    Some(category) => Cow::Owned(format!("{category}:{}", info.page)),
+                    None => cow!(&info.page),
+                };
+
+                (&info.site, page, file)
+            }
+            ImageSource::File2 { page, file } => (&info.site, cow!(page), file),
+            ImageSource::File3 { site, page, file } => (site, cow!(page), file),
+        };
+
+        Some(FileReference {
+            site: cow!(site),
+            page,
+            file: cow!(file),
+        })
+    }
+}
@@ -20,7 +20,7 @@
 
 use super::IncludeRef;
 use crate::data::{PageRef, PageRefParseError};
-use crate::settings::WikitextSettings;
+use crate::settings::{ParserProfile, WikitextSettings};
 use pest::iterators::Pairs;
 use pest::Parser;
 use std::borrow::Cow;
@@ -35,7 +35,10 @@ pub fn parse_include_block<'t>(
     start: usize,
     settings: &WikitextSettings,
 ) -> Result<(IncludeRef<'t>, usize), IncludeParseError> {
-    let rule = if settings.use_include_compatibility {
+    let compatibility = settings.use_include_compatibility
+        || settings.parser_profile == ParserProfile::Compatibility;
+
+    let rule = if compatibility {
         Rule::include_compatibility
     } else {
         Rule::include_normal
@@ -129,6 +129,8 @@ pub mod tree;
 
 pub use self::includes::include;
 pub use self::parsing::parse;
+#[cfg(feature = "parallel")]
+pub use self::parsing::parse_parallel;
 pub use self::preproc::preprocess;
 pub use self::tokenizer::{tokenize, Tokenization};
 pub use self::utf16::Utf16IndexMap;
@@ -140,8 +142,9 @@ pub mod prelude {
     pub use super::preprocess;
     pub use super::render::Render;
     pub use super::settings::{
-        InterwikiSettings, WikitextMode, WikitextSettings, DEFAULT_INTERWIKI,
-        EMPTY_INTERWIKI,
+        CustomBlockDefinition, CustomBlockSettings, ExternalLinkRel, HeadingIdStrategy,
+        InterwikiSettings, ModuleState, ParserProfile, WikitextMode, WikitextSettings,
+        DEFAULT_INTERWIKI, EMPTY_INTERWIKI,
     };
     pub use super::tokenizer::{tokenize, Tokenization};
     pub use super::tree::{Element, SyntaxTree};
@@ -29,6 +29,7 @@
 use super::prelude::*;
 use super::rule::{get_rules_for_token, impls::RULE_FALLBACK};
 use super::Parser;
+use crate::tree::ElementSpan;
 use std::mem;
 
 /// Main function that consumes tokens to produce a single element, then returns.
@@ -49,10 +50,14 @@ pub fn consume<'r, 't>(parser: &mut Parser<'r, 't>) -> ParseResult<'r, 't, Eleme
     debug!("Looking for valid rules");
     let mut all_errors = Vec::new();
     let current = parser.current();
+    let span_mark = parser.element_span_mark();
 
     for &rule in get_rules_for_token(current) {
         debug!("Trying rule consumption for tokens (rule {})", rule.name());
 
+        // Discard spans left behind by any previous failed attempt
+        parser.truncate_element_spans(span_mark);
+
         let old_remaining = parser.remaining();
         match rule.try_consume(parser) {
             Ok(output) => {
@@ -63,6 +68,9 @@ pub fn consume<'r, 't>(parser: &mut Parser<'r, 't>) -> ParseResult<'r, 't, Eleme
                     parser.step()?;
                 }
 
+                // Record where the produced elements came from
+                push_element_spans(parser, span_mark, current.span.start, &output.item);
+
                 // Explicitly drop errors
                 //
                 // We're returning the successful consumption
@@ -84,6 +92,8 @@ pub fn consume<'r, 't>(parser: &mut Parser<'r, 't>) -> ParseResult<'r, 't, Eleme
 
     warn!("All rules exhausted, using generic text fallback");
     let element = text!(current.slice);
+    parser.truncate_element_spans(span_mark);
+    parser.push_element_spans([ElementSpan::leaf(current.span.clone())]);
     parser.step()?;
 
     // If we've hit the recursion limit, just bail
@@ -106,3 +116,29 @@ pub fn consume<'r, 't>(parser: &mut Parser<'r, 't>) -> ParseResult<'r, 't, Eleme
 
     ok!(element, all_errors)
 }
+
+/// Records the source span of each element produced by a consumption.
+///
+/// The spans recorded since `span_mark` are of the elements nested within
+/// the consumed one. If the consumption produced several elements, these
+/// cannot be attributed to any one of them, so they are discarded.
+fn push_element_spans(
+    parser: &mut Parser,
+    span_mark: usize,
+    start: usize,
+    elements: &Elements,
+) {
+    let span = start..parser.current().span.start;
+    let children = parser.take_element_spans(span_mark);
+
+    match elements {
+        Elements::None => (),
+        Elements::Single(_) => {
+            parser.push_element_spans([ElementSpan::new(span, children)]);
+        }
+        Elements::Multiple(elements) => {
+            let spans = elements.iter().map(|_| ElementSpan::leaf(span.clone()));
+            parser.push_element_spans(spans);
+        }
+    }
+}
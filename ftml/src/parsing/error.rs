@@ -166,6 +166,15 @@ pub enum ParseErrorKind {
     /// Ruby text block appears outside of a ruby annotation block.
     RubyTextOutsideRuby,
 
+    /// This gallery has no images in it.
+    GalleryEmpty,
+
+    /// This gallery has elements other than images in it.
+    GalleryContainsNonImage,
+
+    /// There is a gallery image outside of a gallery.
+    GalleryImageOutsideGallery,
+
     /// Bibliography contains an element other than a definition list.
     BibliographyContainsNonDefinitionList,
 
@@ -190,6 +199,15 @@ pub enum ParseErrorKind {
     /// Some required arguments where missing when parsing the block.
     BlockMissingArguments,
 
+    /// This block's name is not lowercase, which the strict parser profile requires.
+    BlockNameNotLowercase,
+
+    /// This block was given the same argument more than once.
+    ///
+    /// Otherwise the last value is used, but the strict
+    /// parser profile rejects this as ambiguous.
+    BlockDuplicateArgument,
+
     /// This block expected to end its body here.
     BlockExpectedEnd,
 
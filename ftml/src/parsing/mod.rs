@@ -31,6 +31,9 @@ mod element_condition;
 mod error;
 mod outcome;
 mod paragraph;
+
+#[cfg(feature = "parallel")]
+mod parallel;
 mod parser;
 mod parser_wrap;
 mod result;
@@ -43,14 +46,14 @@ mod prelude {
     pub use crate::parsing::{
         ExtractedToken, ParseError, ParseErrorKind, ParseResult, ParseSuccess, Token,
     };
-    pub use crate::settings::WikitextSettings;
+    pub use crate::settings::{ParserProfile, WikitextSettings};
     pub use crate::text::FullText;
     pub use crate::tree::{Element, Elements, OwnedElementsIterator};
 }
 
 use self::depth::{process_depths, DepthItem, DepthList};
 use self::element_condition::{ElementCondition, ElementConditionType};
-use self::paragraph::{gather_paragraphs, NO_CLOSE_CONDITION};
+use self::paragraph::{gather_paragraphs, gather_spanned_paragraphs, NO_CLOSE_CONDITION};
 use self::parser::Parser;
 use self::parser_wrap::ParserWrap;
 use self::rule::impls::RULE_PAGE;
@@ -61,15 +64,20 @@ use crate::next_index::{NextIndex, TableOfContentsIndex};
 use crate::settings::WikitextSettings;
 use crate::tokenizer::Tokenization;
 use crate::tree::{
-    AttributeMap, BibliographyList, Element, LinkLabel, LinkLocation, LinkType, ListItem,
-    ListType, SyntaxTree,
+    AttributeMap, BibliographyList, Element, ElementSpan, LinkLabel, LinkLocation,
+    LinkType, ListItem, ListType, SyntaxTree,
 };
 use std::borrow::Cow;
 
 pub use self::boolean::{parse_boolean, NonBooleanValue};
 pub use self::error::{ParseError, ParseErrorKind};
 pub use self::outcome::ParseOutcome;
+#[cfg(feature = "parallel")]
+pub use self::parallel::parse_parallel;
+#[cfg(all(test, feature = "parallel"))]
+pub(crate) use self::parallel::parse_segmented;
 pub use self::result::{ParseResult, ParseSuccess};
+pub use self::rule::impls::is_builtin_block;
 pub use self::token::{ExtractedToken, Token};
 
 /// Parse through the given tokens and produce an AST.
@@ -84,14 +92,25 @@ where
     'r: 't,
 {
     // Run parsing, get raw results
-    let UnstructuredParseResult {
+    let result = parse_internal(page_info, settings, tokenization);
+    build_syntax_tree(tokenization, result)
+}
+
+/// Converts the raw results from parsing into the final syntax tree.
+fn build_syntax_tree<'r, 't>(
+    tokenization: &'r Tokenization<'t>,
+    UnstructuredParseResult {
         result,
+        mut element_spans,
         table_of_contents_depths,
         footnotes,
         has_footnote_block,
         bibliographies,
-    } = parse_internal(page_info, settings, tokenization);
-
+    }: UnstructuredParseResult<'r, 't>,
+) -> ParseOutcome<SyntaxTree<'t>>
+where
+    'r: 't,
+{
     // For producing table of contents indexes
     let mut incrementer = Incrementer(0);
 
@@ -127,10 +146,15 @@ where
                     title: None,
                     hide: false,
                 });
+
+                // Not from the source text, so mark it as empty at the end
+                let len = tokenization.full_text().len();
+                element_spans.push(ElementSpan::leaf(len..len));
             }
 
             SyntaxTree::from_element_result(
                 elements,
+                element_spans,
                 errors,
                 table_of_contents,
                 footnotes,
@@ -146,7 +170,9 @@ where
 
             error!("Fatal error occurred at highest-level parsing: {error:#?}");
             let wikitext = tokenization.full_text().inner();
+            let span = 0..wikitext.len();
             let elements = vec![text!(wikitext)];
+            let element_spans = vec![ElementSpan::leaf(span)];
             let errors = vec![error];
             let table_of_contents = vec![];
             let footnotes = vec![];
@@ -154,6 +180,7 @@ where
 
             SyntaxTree::from_element_result(
                 elements,
+                element_spans,
                 errors,
                 table_of_contents,
                 footnotes,
@@ -177,7 +204,19 @@ where
 
     // At the top level, we gather elements into paragraphs
     info!("Running parser on tokens");
-    let result = gather_paragraphs(&mut parser, RULE_PAGE, NO_CLOSE_CONDITION);
+    let (result, element_spans) =
+        match gather_spanned_paragraphs(&mut parser, RULE_PAGE, NO_CLOSE_CONDITION) {
+            Ok(ParseSuccess {
+                item: (elements, spans),
+                errors,
+                paragraph_safe,
+                ..
+            }) => (
+                Ok(ParseSuccess::new(elements, errors, paragraph_safe)),
+                spans,
+            ),
+            Err(error) => (Err(error), vec![]),
+        };
 
     // Build and return
     let table_of_contents_depths = parser.remove_table_of_contents();
@@ -187,6 +226,7 @@ where
 
     UnstructuredParseResult {
         result,
+        element_spans,
         table_of_contents_depths,
         footnotes,
         has_footnote_block,
@@ -216,6 +256,7 @@ fn build_toc_list_element(
             ListItem::Elements {
                 elements: vec![link],
                 attributes: AttributeMap::new(),
+                task: None,
             }
         }
     };
@@ -250,6 +291,12 @@ pub struct UnstructuredParseResult<'r, 't> {
     /// The returned result from parsing.
     pub result: ParseResult<'r, 't, Vec<Element<'t>>>,
 
+    /// The source span of each top-level element in `result`,
+    /// and of the elements nested within them.
+    ///
+    /// Empty if parsing failed.
+    pub element_spans: Vec<ElementSpan>,
+
     /// The "depths" list for table of content entries.
     ///
     /// Each value is a zero-indexed depth of how
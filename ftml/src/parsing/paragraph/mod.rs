@@ -27,6 +27,7 @@ use super::parser::Parser;
 use super::prelude::*;
 use super::rule::Rule;
 use super::token::Token;
+use crate::tree::ElementSpan;
 
 /// Wrapper type to satisfy the issue with generic closure types.
 ///
@@ -51,8 +52,29 @@ type CloseConditionFn = fn(&mut Parser) -> Result<bool, ParseError>;
 pub fn gather_paragraphs<'r, 't, F>(
     parser: &mut Parser<'r, 't>,
     rule: Rule,
-    mut close_condition_fn: Option<F>,
+    close_condition_fn: Option<F>,
 ) -> ParseResult<'r, 't, Vec<Element<'t>>>
+where
+    'r: 't,
+    F: FnMut(&mut Parser<'r, 't>) -> Result<bool, ParseError>,
+{
+    let result = gather_spanned_paragraphs(parser, rule, close_condition_fn)?;
+
+    // Leave the spans for the element being consumed to claim as its children
+    Ok(result.map(|(elements, spans)| {
+        parser.push_element_spans(spans);
+        elements
+    }))
+}
+
+/// Like `gather_paragraphs()`, but returns where in the source text
+/// each of the produced elements came from, rather than recording it
+/// in the parser.
+pub fn gather_spanned_paragraphs<'r, 't, F>(
+    parser: &mut Parser<'r, 't>,
+    rule: Rule,
+    mut close_condition_fn: Option<F>,
+) -> ParseResult<'r, 't, (Vec<Element<'t>>, Vec<ElementSpan>)>
 where
     'r: 't,
     F: FnMut(&mut Parser<'r, 't>) -> Result<bool, ParseError>,
@@ -64,8 +86,12 @@ where
 
     // Create paragraph stack
     let mut stack = ParagraphStack::new();
+    let span_mark = parser.element_span_mark();
 
     loop {
+        // Discard spans left behind by any lookahead
+        parser.truncate_element_spans(span_mark);
+
         let (elements, mut errors, paragraph_safe) = match parser.current().token {
             Token::InputEnd => {
                 if close_condition_fn.is_some() {
@@ -117,29 +143,35 @@ where
 
         debug!("Tokens consumed to produce element");
 
-        // Add new elements to the list
-        push_elements(&mut stack, elements, paragraph_safe);
+        // Add new elements to the list, alongside where they came from
+        let spans = parser.take_element_spans(span_mark);
+        push_elements(&mut stack, elements, spans, paragraph_safe);
 
         // Process errors
         stack.push_errors(&mut errors);
     }
 
-    stack.into_result()
+    parser.truncate_element_spans(span_mark);
+    stack.into_spanned_result()
 }
 
 fn push_elements<'t>(
     stack: &mut ParagraphStack<'t>,
     elements: Elements<'t>,
+    spans: Vec<ElementSpan>,
     paragraph_safe: bool,
 ) {
     stack.reserve_elements(elements.len());
 
+    let mut spans = spans.into_iter();
     for element in elements {
+        let span = spans.next().unwrap_or_default();
+
         // Don't add a line break if the paragraph is otherwise empty
         if stack.current_empty() && element == Element::LineBreak {
             continue;
         }
 
-        stack.push_element(element, paragraph_safe);
+        stack.push_spanned_element(element, span, paragraph_safe);
     }
 }
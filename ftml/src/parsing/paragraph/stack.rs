@@ -19,7 +19,7 @@
  */
 
 use crate::parsing::prelude::*;
-use crate::tree::{AttributeMap, Container, ContainerType};
+use crate::tree::{AttributeMap, Container, ContainerType, ElementSpan};
 use std::mem;
 
 #[derive(Debug, Default)]
@@ -30,6 +30,12 @@ pub struct ParagraphStack<'t> {
     /// Previous elements created, to be outputted in the final `SyntaxTree`.
     finished: Vec<Element<'t>>,
 
+    /// The source spans of each element in the current paragraph, parallel to `current`.
+    current_spans: Vec<ElementSpan>,
+
+    /// The source spans of each finished element, parallel to `finished`.
+    finished_spans: Vec<ElementSpan>,
+
     /// Gathered errors from paragraph parsing.
     errors: Vec<ParseError>,
 }
@@ -48,10 +54,21 @@ impl<'t> ParagraphStack<'t> {
     #[inline]
     pub fn reserve_elements(&mut self, additional: usize) {
         self.current.reserve(additional);
+        self.current_spans.reserve(additional);
     }
 
+    /// Pushes an element which did not come from anywhere in particular in the source.
     #[inline]
     pub fn push_element(&mut self, element: Element<'t>, paragraph_safe: bool) {
+        self.push_spanned_element(element, ElementSpan::default(), paragraph_safe);
+    }
+
+    pub fn push_spanned_element(
+        &mut self,
+        element: Element<'t>,
+        span: ElementSpan,
+        paragraph_safe: bool,
+    ) {
         info!(
             "Pushing element {} to stack (paragraph safe: {}",
             element.name(),
@@ -61,11 +78,13 @@ impl<'t> ParagraphStack<'t> {
         if paragraph_safe {
             // Add it to the current (or new) paragraph. Nothing special.
             self.current.push(element);
+            self.current_spans.push(span);
         } else {
             // This has to be its own "finished" element, outside of any
             // paragraph wrapper. So finish up what we have, then add this element.
             self.end_paragraph();
             self.finished.push(element);
+            self.finished_spans.push(span);
         }
     }
 
@@ -88,6 +107,7 @@ impl<'t> ParagraphStack<'t> {
 
         if let Some(Element::LineBreak) = self.current.last() {
             self.current.pop();
+            self.current_spans.pop();
         }
     }
 
@@ -114,16 +134,26 @@ impl<'t> ParagraphStack<'t> {
     pub fn end_paragraph(&mut self) {
         debug!("Ending the current paragraph to push as a completed element");
 
+        // The paragraph spans from its first element to its last
+        let children = mem::take(&mut self.current_spans);
+        let span = match (children.first(), children.last()) {
+            (Some(first), Some(last)) => first.start..last.end,
+            _ => 0..0,
+        };
+
         if let Some(paragraph) = self.build_paragraph() {
             self.finished.push(paragraph);
+            self.finished_spans.push(ElementSpan::new(span, children));
         }
     }
 
     /// Convert all paragraph context into a `ParseResult.`
     ///
-    /// This returns all collected elements, errors, and returns the final
-    /// paragraph safety value.
-    pub fn into_result<'r>(mut self) -> ParseResult<'r, 't, Vec<Element<'t>>> {
+    /// This returns all collected elements alongside the source span
+    /// of each, errors, and returns the final paragraph safety value.
+    pub fn into_spanned_result<'r>(
+        mut self,
+    ) -> ParseResult<'r, 't, (Vec<Element<'t>>, Vec<ElementSpan>)> {
         info!("Converting paragraph parse stack into ParseResult");
 
         // Finish current paragraph, if any
@@ -131,9 +161,10 @@ impl<'t> ParagraphStack<'t> {
 
         // Deconstruct stack
         let ParagraphStack {
-            current: _,
             finished: elements,
+            finished_spans: spans,
             errors,
+            ..
         } = self;
 
         // If this has any paragraphs in it, or other incompatible elements,
@@ -145,7 +176,7 @@ impl<'t> ParagraphStack<'t> {
         let paragraph_safe = elements.iter().all(|element| element.paragraph_safe());
 
         // Return finished element list
-        ok!(paragraph_safe; elements, errors)
+        ok!(paragraph_safe; (elements, spans), errors)
     }
 
     /// Converts all paragraph context into a set of `Element`s.
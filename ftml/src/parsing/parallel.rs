@@ -0,0 +1,270 @@
+/*
+ * parsing/parallel.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Parsing of large inputs using multiple threads.
+//!
+//! The top level of most documents is a series of blocks separated by
+//! paragraph breaks. The input is split at some of these, and each segment
+//! is parsed on its own, from a fresh parser.
+//!
+//! This is speculative, since whether a paragraph break is at the top level,
+//! rather than say within a `[[div]]`, is only known after parsing. So each
+//! segment keeps going past its planned end until it is at a paragraph break
+//! on the top level. The segments are then merged in order, discarding any
+//! which started within a previous one, and parsing any gaps this leaves.
+//! The result is the same as `parse()`, however the work was scheduled.
+
+use super::paragraph::{gather_spanned_paragraphs, NO_CLOSE_CONDITION};
+use super::parser::Parser;
+use super::rule::impls::RULE_PAGE;
+use super::{
+    build_syntax_tree, parse, ExtractedToken, ParseError, ParseOutcome, ParseSuccess,
+    Token, UnstructuredParseResult,
+};
+use crate::data::PageInfo;
+use crate::settings::WikitextSettings;
+use crate::tokenizer::Tokenization;
+use crate::tree::{BibliographyList, Element, ElementSpan, SyntaxTree};
+use rayon::prelude::*;
+
+/// The fewest tokens which are parsed as their own segment.
+///
+/// Below this, setting up another parser and merging its results
+/// costs more than is saved by running it in parallel.
+const MIN_SEGMENT_TOKENS: usize = 4096;
+
+/// The results of parsing part of the input.
+#[derive(Debug)]
+struct Segment<'t> {
+    /// The index of the token parsing stopped at.
+    end: usize,
+    elements: Vec<Element<'t>>,
+    element_spans: Vec<ElementSpan>,
+    errors: Vec<ParseError>,
+    table_of_contents_depths: Vec<(usize, String)>,
+    footnotes: Vec<Vec<Element<'t>>>,
+    has_footnote_block: bool,
+    bibliographies: BibliographyList<'t>,
+}
+
+/// Parse through the given tokens and produce an AST, using multiple threads.
+///
+/// The output is identical to `parse()`. Inputs too small to be worth
+/// splitting are parsed on the current thread, and so are inputs which
+/// cannot be split, such as a page entirely wrapped in one `[[div]]`.
+pub fn parse_parallel<'r, 't>(
+    tokenization: &'r Tokenization<'t>,
+    page_info: &'r PageInfo<'t>,
+    settings: &'r WikitextSettings,
+) -> ParseOutcome<SyntaxTree<'t>>
+where
+    'r: 't,
+{
+    parse_segmented(tokenization, page_info, settings, MIN_SEGMENT_TOKENS)
+}
+
+/// Implementation of `parse_parallel()`, with a given segment size.
+///
+/// Tests use a tiny segment size, so that every paragraph break is split on.
+pub(crate) fn parse_segmented<'r, 't>(
+    tokenization: &'r Tokenization<'t>,
+    page_info: &'r PageInfo<'t>,
+    settings: &'r WikitextSettings,
+    min_segment_tokens: usize,
+) -> ParseOutcome<SyntaxTree<'t>>
+where
+    'r: 't,
+{
+    let starts = find_segment_starts(tokenization.tokens(), min_segment_tokens);
+    if starts.len() < 2 {
+        debug!("Input is too small to split, parsing on one thread");
+        return parse(tokenization, page_info, settings);
+    }
+
+    info!("Parsing {} segments in parallel", starts.len());
+    let segments = starts
+        .par_iter()
+        .enumerate()
+        .map(|(index, &start)| {
+            let end = starts.get(index + 1).copied();
+            parse_segment(tokenization, page_info, settings, start, end)
+        })
+        .collect::<Vec<_>>();
+
+    match merge_segments(tokenization, page_info, settings, &starts, segments) {
+        Some(result) => build_syntax_tree(tokenization, result),
+        None => {
+            info!("Unable to merge parsed segments, parsing on one thread");
+            parse(tokenization, page_info, settings)
+        }
+    }
+}
+
+/// Finds where to split the input, at paragraph breaks at least the given number of tokens apart.
+///
+/// The first segment always starts at the beginning of the input.
+fn find_segment_starts(
+    tokens: &[ExtractedToken],
+    min_segment_tokens: usize,
+) -> Vec<usize> {
+    let mut starts = vec![0];
+    let mut last = 0;
+
+    for index in 1..tokens.len() {
+        if index - last >= min_segment_tokens && is_segment_start(tokens, index) {
+            starts.push(index);
+            last = index;
+        }
+    }
+
+    starts
+}
+
+/// Whether the token at this index comes right after a paragraph break.
+///
+/// If the parser is on the top level at such a token, then the current
+/// paragraph has just been finished, and nothing before it affects what
+/// follows. So a fresh parser starting here produces the same elements.
+fn is_segment_start(tokens: &[ExtractedToken], index: usize) -> bool {
+    tokens[index - 1].token == Token::ParagraphBreak
+        && !matches!(tokens[index].token, Token::ParagraphBreak | Token::InputEnd)
+}
+
+/// Gets the index of the token the parser is currently on.
+fn token_index(tokens: &[ExtractedToken], parser: &Parser) -> usize {
+    tokens.len() - parser.remaining().len() - 1
+}
+
+/// Parses the input starting from the given token.
+///
+/// If `end` is set, this stops at the first segment start on the top level
+/// which is at or after it. Otherwise, it continues to the end of the input.
+///
+/// Returns `None` if parsing failed, which includes reaching the
+/// end of the input when `end` was set.
+fn parse_segment<'r, 't>(
+    tokenization: &'r Tokenization<'t>,
+    page_info: &'r PageInfo<'t>,
+    settings: &'r WikitextSettings,
+    start: usize,
+    end: Option<usize>,
+) -> Option<Segment<'t>>
+where
+    'r: 't,
+{
+    let tokens = tokenization.tokens();
+    let mut parser = Parser::new_at(tokenization, page_info, settings, start);
+    let result = match end {
+        Some(end) => {
+            let close_condition_fn = |parser: &mut Parser<'r, 't>| {
+                let index = token_index(tokens, parser);
+                Ok(index >= end && is_segment_start(tokens, index))
+            };
+
+            gather_spanned_paragraphs(&mut parser, RULE_PAGE, Some(close_condition_fn))
+        }
+        None => gather_spanned_paragraphs(&mut parser, RULE_PAGE, NO_CLOSE_CONDITION),
+    };
+
+    let ParseSuccess {
+        item: (elements, element_spans),
+        errors,
+        ..
+    } = result.ok()?;
+
+    Some(Segment {
+        end: token_index(tokens, &parser),
+        elements,
+        element_spans,
+        errors,
+        table_of_contents_depths: parser.remove_table_of_contents(),
+        footnotes: parser.remove_footnotes(),
+        has_footnote_block: parser.has_footnote_block(),
+        bibliographies: parser.remove_bibliographies(),
+    })
+}
+
+/// Combines the parsed segments in order, parsing any gaps between them.
+///
+/// Returns `None` if the segments cannot be combined into the same
+/// result as parsing the input in one go.
+fn merge_segments<'r, 't>(
+    tokenization: &'r Tokenization<'t>,
+    page_info: &'r PageInfo<'t>,
+    settings: &'r WikitextSettings,
+    starts: &[usize],
+    segments: Vec<Option<Segment<'t>>>,
+) -> Option<UnstructuredParseResult<'r, 't>>
+where
+    'r: 't,
+{
+    let input_end = tokenization.tokens().len() - 1;
+    let mut segments = starts.iter().copied().zip(segments).peekable();
+    let mut position = 0;
+
+    let mut elements = Vec::new();
+    let mut element_spans = Vec::new();
+    let mut errors = Vec::new();
+    let mut table_of_contents_depths = Vec::new();
+    let mut footnotes = Vec::new();
+    let mut has_footnote_block = false;
+    let mut bibliographies = BibliographyList::new();
+
+    while position < input_end {
+        // Discard segments which started within what has already been parsed
+        while segments.next_if(|&(start, _)| start < position).is_some() {}
+
+        let mut segment = match segments.next_if(|&(start, _)| start == position) {
+            Some((_, segment)) => segment?,
+            None => {
+                let end = segments.peek().map(|&(start, _)| start);
+                debug!("Parsing gap between segments, starting at token {position}");
+                parse_segment(tokenization, page_info, settings, position, end)?
+            }
+        };
+
+        // Bibliography blocks are numbered from zero in each segment,
+        // so they are only correct if no earlier segment had any.
+        if segment.bibliographies.next_index() > 0 && bibliographies.next_index() > 0 {
+            debug!("Bibliographies in several segments, cannot merge");
+            return None;
+        }
+
+        position = segment.end;
+        elements.append(&mut segment.elements);
+        element_spans.append(&mut segment.element_spans);
+        errors.append(&mut segment.errors);
+        table_of_contents_depths.append(&mut segment.table_of_contents_depths);
+        footnotes.append(&mut segment.footnotes);
+        has_footnote_block |= segment.has_footnote_block;
+        bibliographies.append(&mut segment.bibliographies);
+    }
+
+    let paragraph_safe = elements.iter().all(|element| element.paragraph_safe());
+
+    Some(UnstructuredParseResult {
+        result: Ok(ParseSuccess::new(elements, errors, paragraph_safe)),
+        element_spans,
+        table_of_contents_depths,
+        footnotes,
+        has_footnote_block,
+        bibliographies,
+    })
+}
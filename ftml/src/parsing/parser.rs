@@ -25,7 +25,9 @@ use super::RULE_PAGE;
 use crate::data::PageInfo;
 use crate::render::text::TextRender;
 use crate::tokenizer::Tokenization;
-use crate::tree::{AcceptsPartial, Bibliography, BibliographyList, HeadingLevel};
+use crate::tree::{
+    AcceptsPartial, Bibliography, BibliographyList, ElementSpan, HeadingLevel,
+};
 use std::cell::RefCell;
 use std::rc::Rc;
 use std::{mem, ptr};
@@ -69,6 +71,13 @@ pub struct Parser<'r, 't> {
     // overriding later ones.
     bibliographies: Rc<RefCell<BibliographyList<'t>>>,
 
+    // Element spans
+    //
+    // The source spans of elements which have been consumed,
+    // but not yet claimed by the element containing them.
+    // See consume() and gather_paragraphs().
+    element_spans: Rc<RefCell<Vec<ElementSpan>>>,
+
     // Flags
     accepts_partial: AcceptsPartial,
     in_footnote: bool, // Whether we're currently inside [[footnote]] ... [[/footnote]].
@@ -103,6 +112,7 @@ impl<'r, 't> Parser<'r, 't> {
             table_of_contents: make_shared_vec(),
             footnotes: make_shared_vec(),
             bibliographies: Rc::new(RefCell::new(BibliographyList::new())),
+            element_spans: make_shared_vec(),
             accepts_partial: AcceptsPartial::None,
             in_footnote: false,
             has_footnote_block: false,
@@ -110,6 +120,40 @@ impl<'r, 't> Parser<'r, 't> {
         }
     }
 
+    /// Like `new()`, but starting from the token at the given index.
+    ///
+    /// This is used to parse separate parts of the input at the same time,
+    /// see `parse_parallel()`. The index must be at the top level of the
+    /// document, since all other parser state starts out fresh.
+    #[cfg(feature = "parallel")]
+    pub(crate) fn new_at(
+        tokenization: &'r Tokenization<'t>,
+        page_info: &'r PageInfo<'t>,
+        settings: &'r WikitextSettings,
+        index: usize,
+    ) -> Self {
+        let tokens = tokenization.tokens();
+        let (current, remaining) = tokens[index..]
+            .split_first()
+            .expect("Token index for parser start was out of range");
+
+        // Set the start-of-line flag, the same as step() would have.
+        let start_of_line = match index.checked_sub(1) {
+            Some(previous) => matches!(
+                tokens[previous].token,
+                Token::InputStart | Token::LineBreak | Token::ParagraphBreak,
+            ),
+            None => true,
+        };
+
+        Parser {
+            current,
+            remaining,
+            start_of_line,
+            ..Parser::new(tokenization, page_info, settings)
+        }
+    }
+
     // Getters
     #[inline]
     pub fn page_info(&self) -> &PageInfo<'t> {
@@ -121,6 +165,14 @@ impl<'r, 't> Parser<'r, 't> {
         self.settings
     }
 
+    /// Whether ambiguous constructs should be rejected.
+    ///
+    /// See [`ParserProfile::Strict`].
+    #[inline]
+    pub fn strict(&self) -> bool {
+        self.settings.parser_profile == ParserProfile::Strict
+    }
+
     #[inline]
     pub fn full_text(&self) -> FullText<'t> {
         self.full_text
@@ -204,6 +256,22 @@ impl<'r, 't> Parser<'r, 't> {
         }
     }
 
+    pub fn check_embeds(&self) -> Result<(), ParseError> {
+        if self.settings.allow_embeds {
+            Ok(())
+        } else {
+            Err(self.make_err(ParseErrorKind::NotSupportedMode))
+        }
+    }
+
+    pub fn check_css(&self) -> Result<(), ParseError> {
+        if self.settings.allow_css {
+            Ok(())
+        } else {
+            Err(self.make_err(ParseErrorKind::NotSupportedMode))
+        }
+    }
+
     // Table of Contents
     pub fn push_table_of_contents_entry(
         &mut self,
@@ -248,6 +316,34 @@ impl<'r, 't> Parser<'r, 't> {
         mem::take(&mut self.bibliographies.borrow_mut())
     }
 
+    // Element spans
+    #[inline]
+    pub fn element_span_mark(&self) -> usize {
+        self.element_spans.borrow().len()
+    }
+
+    /// Discards any element spans recorded after the given mark.
+    ///
+    /// These come from consumption attempts which did not succeed.
+    #[inline]
+    pub fn truncate_element_spans(&mut self, mark: usize) {
+        self.element_spans.borrow_mut().truncate(mark);
+    }
+
+    pub fn push_element_spans<I>(&mut self, spans: I)
+    where
+        I: IntoIterator<Item = ElementSpan>,
+    {
+        self.element_spans.borrow_mut().extend(spans);
+    }
+
+    /// Removes and returns all element spans recorded after the given mark.
+    pub fn take_element_spans(&mut self, mark: usize) -> Vec<ElementSpan> {
+        let mut guard = self.element_spans.borrow_mut();
+        let mark = mark.min(guard.len());
+        guard.split_off(mark)
+    }
+
     // Special for [[include]], appending a SyntaxTree
     pub fn append_shared_items(
         &mut self,
@@ -43,10 +43,11 @@ impl<'t> Arguments<'t> {
         Arguments::default()
     }
 
-    pub fn insert(&mut self, key: &'t str, value: Cow<'t, str>) {
+    /// Adds an argument, returning the previous value if it was already present.
+    pub fn insert(&mut self, key: &'t str, value: Cow<'t, str>) -> Option<Cow<'t, str>> {
         let key = UniCase::ascii(key);
 
-        self.inner.insert(key, value);
+        self.inner.insert(key, value)
     }
 
     pub fn get(&mut self, key: &'t str) -> Option<Cow<'t, str>> {
@@ -114,6 +115,7 @@ impl<'t> Arguments<'t> {
     pub fn to_attribute_map(&self, settings: &WikitextSettings) -> AttributeMap<'t> {
         let mut map = AttributeMap::from_arguments(&self.inner);
         map.isolate_id(settings);
+        map.strip_style(settings);
         map
     }
 }
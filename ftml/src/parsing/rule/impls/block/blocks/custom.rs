@@ -0,0 +1,103 @@
+/*
+ * parsing/rule/impls/block/blocks/custom.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::settings::CustomBlockBody;
+use crate::tree::CustomBlock;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+/// The block rule for all custom blocks.
+///
+/// This isn't in the block rule map, since the names it accepts
+/// come from `WikitextSettings::custom_blocks` at runtime.
+pub const BLOCK_CUSTOM: BlockRule = BlockRule {
+    name: "block-custom",
+    accepts_names: &[],
+    accepts_star: false,
+    accepts_score: false,
+    accepts_newlines: true,
+    parse_fn,
+};
+
+fn parse_fn<'r, 't>(
+    parser: &mut Parser<'r, 't>,
+    name: &'t str,
+    flag_star: bool,
+    flag_score: bool,
+    in_head: bool,
+) -> ParseResult<'r, 't, Elements<'t>> {
+    info!("Parsing custom block (name '{name}', in-head {in_head})");
+    assert!(!flag_star, "Custom blocks don't allow star flag");
+    assert!(!flag_score, "Custom blocks don't allow score flag");
+
+    let definition = match parser.settings().custom_blocks.get(name) {
+        Some(definition) => definition.clone(),
+        None => return Err(parser.make_err(ParseErrorKind::NoSuchBlock)),
+    };
+
+    let arguments = parser.get_head_map(&BLOCK_CUSTOM, in_head)?;
+
+    // Keep only the declared arguments, under their declared names
+    let mut values = BTreeMap::new();
+    for (key, value) in arguments.to_hash_map() {
+        match definition.argument(&key) {
+            Some(argument) => {
+                values.insert(Cow::Owned(argument.name.clone()), value);
+            }
+            None => {
+                warn!("Ignoring undeclared argument '{key}' to custom block '{name}'")
+            }
+        }
+    }
+
+    let missing = definition.arguments.iter().any(|argument| {
+        argument.required && !values.contains_key(argument.name.as_str())
+    });
+
+    if missing {
+        warn!("Custom block '{name}' is missing required arguments");
+        return Err(parser.make_err(ParseErrorKind::BlockMissingArguments));
+    }
+
+    // Get body content, if the block has any
+    let (elements, errors) = match definition.body {
+        CustomBlockBody::None => (vec![], vec![]),
+        CustomBlockBody::Text => {
+            let text = parser.get_body_text_until(&BLOCK_CUSTOM, &[name])?;
+            (vec![Element::Text(cow!(text))], vec![])
+        }
+        CustomBlockBody::Elements => {
+            let (elements, errors, _) = parser
+                .get_body_elements_until(&BLOCK_CUSTOM, &[name], true)?
+                .into();
+
+            (elements, errors)
+        }
+    };
+
+    let element = Element::CustomBlock(CustomBlock {
+        name: Cow::Owned(definition.name),
+        arguments: values,
+        elements,
+    });
+
+    ok!(element, errors)
+}
@@ -46,6 +46,7 @@ fn parse_fn<'r, 't>(
     assert!(!flag_star, "Embed doesn't allow star flag");
     assert!(!flag_score, "Embed doesn't allow star flag");
     assert_block_name(&BLOCK_EMBED, name);
+    parser.check_embeds()?;
 
     let (name, mut arguments) = parser.get_head_name_map(&BLOCK_EMBED, in_head)?;
     let embed = build_embed(parser, name, &mut arguments)?;
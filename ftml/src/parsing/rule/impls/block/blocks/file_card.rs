@@ -0,0 +1,61 @@
+/*
+ * parsing/rule/impls/block/blocks/file_card.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::tree::ImageSource;
+
+pub const BLOCK_FILE_CARD: BlockRule = BlockRule {
+    name: "block-file-card",
+    accepts_names: &["file-card"],
+    accepts_star: false,
+    accepts_score: false,
+    accepts_newlines: false,
+    parse_fn,
+};
+
+fn parse_fn<'r, 't>(
+    parser: &mut Parser<'r, 't>,
+    name: &'t str,
+    flag_star: bool,
+    flag_score: bool,
+    in_head: bool,
+) -> ParseResult<'r, 't, Elements<'t>> {
+    info!("Parsing file card block (name {name}, in-head {in_head})");
+    assert!(!flag_star, "File card doesn't allow star flag");
+    assert!(!flag_score, "File card doesn't allow score flag");
+    assert_block_name(&BLOCK_FILE_CARD, name);
+
+    let (source, arguments) = parser.get_head_name_map(&BLOCK_FILE_CARD, in_head)?;
+
+    // Only attached files have metadata to show, so URLs are not permitted
+    let source = match ImageSource::parse(source) {
+        Some(ImageSource::Url(_)) | None => {
+            return Err(parser.make_err(ParseErrorKind::BlockMalformedArguments));
+        }
+        Some(source) => source,
+    };
+
+    let element = Element::FileCard {
+        source,
+        attributes: arguments.to_attribute_map(parser.settings()),
+    };
+
+    ok!(element)
+}
@@ -0,0 +1,145 @@
+/*
+ * parsing/rule/impls/block/blocks/gallery.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::parsing::ParserWrap;
+use crate::tree::{
+    AcceptsPartial, GalleryImage, GallerySize, ImageSource, LinkLocation, PartialElement,
+};
+use std::borrow::Cow;
+
+pub const BLOCK_GALLERY: BlockRule = BlockRule {
+    name: "block-gallery",
+    accepts_names: &["gallery"],
+    accepts_star: false,
+    accepts_score: false,
+    accepts_newlines: true,
+    parse_fn: parse_gallery,
+};
+
+pub const BLOCK_GALLERY_IMAGE: BlockRule = BlockRule {
+    name: "block-gallery-image",
+    accepts_names: &["gallery-image"],
+    accepts_star: false,
+    accepts_score: false,
+    accepts_newlines: false,
+    parse_fn: parse_gallery_image,
+};
+
+fn parse_gallery<'r, 't>(
+    parser: &mut Parser<'r, 't>,
+    name: &'t str,
+    flag_star: bool,
+    flag_score: bool,
+    in_head: bool,
+) -> ParseResult<'r, 't, Elements<'t>> {
+    let parser = &mut ParserWrap::new(parser, AcceptsPartial::GalleryImage);
+
+    info!("Parsing gallery block (name '{name}', in-head {in_head})");
+    assert!(!flag_star, "Gallery doesn't allow star flag");
+    assert!(!flag_score, "Gallery doesn't allow score flag");
+    assert_block_name(&BLOCK_GALLERY, name);
+
+    let mut arguments = parser.get_head_map(&BLOCK_GALLERY, in_head)?;
+    let size = parse_size(parser, arguments.get("size"))?.unwrap_or_default();
+
+    let (elements, errors, _) = parser.get_body_elements(&BLOCK_GALLERY, false)?.into();
+
+    // Build element and return
+    let mut images = Vec::new();
+
+    for element in elements {
+        match element {
+            // Append the next image.
+            Element::Partial(PartialElement::GalleryImage(image)) => images.push(image),
+
+            // Ignore internal whitespace.
+            element if element.is_whitespace() => (),
+
+            // Return an error for anything else.
+            _ => return Err(parser.make_err(ParseErrorKind::GalleryContainsNonImage)),
+        }
+    }
+
+    // Ensure it's not empty
+    if images.is_empty() {
+        return Err(parser.make_err(ParseErrorKind::GalleryEmpty));
+    }
+
+    let element = Element::Gallery {
+        images,
+        size,
+        attributes: arguments.to_attribute_map(parser.settings()),
+    };
+
+    ok!(false; element, errors)
+}
+
+fn parse_gallery_image<'r, 't>(
+    parser: &mut Parser<'r, 't>,
+    name: &'t str,
+    flag_star: bool,
+    flag_score: bool,
+    in_head: bool,
+) -> ParseResult<'r, 't, Elements<'t>> {
+    info!("Parsing gallery image block (name '{name}', in-head {in_head})");
+    assert!(!flag_star, "Gallery image doesn't allow star flag");
+    assert!(!flag_score, "Gallery image doesn't allow score flag");
+    assert_block_name(&BLOCK_GALLERY_IMAGE, name);
+
+    let (source, mut arguments) =
+        parser.get_head_name_map(&BLOCK_GALLERY_IMAGE, in_head)?;
+
+    let source = match ImageSource::parse(source) {
+        Some(source) => source,
+        None => return Err(parser.make_err(ParseErrorKind::BlockMalformedArguments)),
+    };
+
+    let link = arguments.get("link").map(LinkLocation::parse);
+    let caption = arguments.get("caption");
+    let size = parse_size(parser, arguments.get("size"))?;
+
+    // Build element and return
+    let element = Element::Partial(PartialElement::GalleryImage(GalleryImage {
+        source,
+        link,
+        caption,
+        size,
+        attributes: arguments.to_attribute_map(parser.settings()),
+    }));
+
+    ok!(false; element)
+}
+
+fn parse_size(
+    parser: &Parser,
+    value: Option<Cow<str>>,
+) -> Result<Option<GallerySize>, ParseError> {
+    match value {
+        None => Ok(None),
+        Some(value) => match GallerySize::parse(&value) {
+            Some(size) => Ok(Some(size)),
+            None => {
+                warn!("Unknown gallery size argument '{value}'");
+                Err(parser.make_err(ParseErrorKind::BlockMalformedArguments))
+            }
+        },
+    }
+}
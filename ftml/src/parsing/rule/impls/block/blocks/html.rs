@@ -40,6 +40,7 @@ fn parse_fn<'r, 't>(
     assert!(!flag_star, "HTML doesn't allow star flag");
     assert!(!flag_score, "HTML doesn't allow score flag");
     assert_block_name(&BLOCK_HTML, name);
+    parser.check_embeds()?;
 
     parser.get_head_none(&BLOCK_HTML, in_head)?;
     let html = parser.get_body_text(&BLOCK_HTML)?;
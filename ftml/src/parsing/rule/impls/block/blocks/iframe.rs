@@ -41,6 +41,7 @@ fn parse_fn<'r, 't>(
     assert!(!flag_star, "iframe doesn't allow star flag");
     assert!(!flag_score, "iframe doesn't allow score flag");
     assert_block_name(&BLOCK_IFRAME, name);
+    parser.check_embeds()?;
 
     let (url, arguments) = parser.get_head_name_map(&BLOCK_IFRAME, in_head)?;
     if !is_url(url) {
@@ -64,6 +64,7 @@ fn parse_fn<'r, 't>(
         mut footnotes,
         has_footnote_block,
         mut bibliographies,
+        ..
     } = include_page(parser, &page_ref)?;
 
     if has_footnote_block {
@@ -112,6 +113,7 @@ fn include_page<'r, 't>(
             vec![],
             false,
         )),
+        element_spans: vec![],
         table_of_contents_depths: vec![],
         footnotes: vec![],
         has_footnote_block: false,
@@ -70,12 +70,15 @@ mod char;
 mod checkbox;
 mod code;
 mod collapsible;
+mod custom;
 mod date;
 mod del;
 mod div;
 mod embed;
 mod equation_ref;
+mod file_card;
 mod footnote;
+mod gallery;
 mod hidden;
 mod html;
 mod ifcategory;
@@ -99,6 +102,7 @@ mod radio;
 mod ruby;
 mod size;
 mod span;
+mod spoiler;
 mod strikethrough;
 mod subscript;
 mod superscript;
@@ -122,12 +126,15 @@ pub use self::char::BLOCK_CHAR;
 pub use self::checkbox::BLOCK_CHECKBOX;
 pub use self::code::BLOCK_CODE;
 pub use self::collapsible::BLOCK_COLLAPSIBLE;
+pub use self::custom::BLOCK_CUSTOM;
 pub use self::date::BLOCK_DATE;
 pub use self::del::BLOCK_DEL;
 pub use self::div::BLOCK_DIV;
 pub use self::embed::BLOCK_EMBED;
 pub use self::equation_ref::BLOCK_EQUATION_REF;
+pub use self::file_card::BLOCK_FILE_CARD;
 pub use self::footnote::{BLOCK_FOOTNOTE, BLOCK_FOOTNOTE_BLOCK};
+pub use self::gallery::{BLOCK_GALLERY, BLOCK_GALLERY_IMAGE};
 pub use self::hidden::BLOCK_HIDDEN;
 pub use self::html::BLOCK_HTML;
 pub use self::ifcategory::BLOCK_IFCATEGORY;
@@ -151,6 +158,7 @@ pub use self::radio::BLOCK_RADIO;
 pub use self::ruby::{BLOCK_RB, BLOCK_RT, BLOCK_RUBY};
 pub use self::size::BLOCK_SIZE;
 pub use self::span::BLOCK_SPAN;
+pub use self::spoiler::{BLOCK_SPOILER, BLOCK_SPOILER_BLOCK};
 pub use self::strikethrough::BLOCK_STRIKETHROUGH;
 pub use self::subscript::BLOCK_SUBSCRIPT;
 pub use self::superscript::BLOCK_SUPERSCRIPT;
@@ -23,9 +23,10 @@ use once_cell::sync::Lazy;
 use std::collections::HashMap;
 use unicase::UniCase;
 
-pub const MODULE_RULES: [ModuleRule; 6] = [
+pub const MODULE_RULES: [ModuleRule; 7] = [
     MODULE_BACKLINKS,
     MODULE_CATEGORIES,
+    MODULE_COMMENTS,
     MODULE_CSS,
     MODULE_JOIN,
     MODULE_PAGE_TREE,
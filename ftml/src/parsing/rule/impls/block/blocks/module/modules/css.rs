@@ -33,6 +33,7 @@ fn parse_fn<'r, 't>(
 ) -> ParseResult<'r, 't, ModuleParseOutput<'t>> {
     info!("Parsing categories module");
     assert_module_name(&MODULE_CSS, name);
+    parser.check_css()?;
 
     let css = parser.get_body_text(&BLOCK_MODULE)?;
     let element = Element::Style(cow!(css));
@@ -30,6 +30,7 @@ mod prelude {
 
 mod backlinks;
 mod categories;
+mod comments;
 mod css;
 mod join;
 mod page_tree;
@@ -37,6 +38,7 @@ mod rate;
 
 pub use self::backlinks::MODULE_BACKLINKS;
 pub use self::categories::MODULE_CATEGORIES;
+pub use self::comments::MODULE_COMMENTS;
 pub use self::css::MODULE_CSS;
 pub use self::join::MODULE_JOIN;
 pub use self::page_tree::MODULE_PAGE_TREE;
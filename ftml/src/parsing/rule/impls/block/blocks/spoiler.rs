@@ -0,0 +1,104 @@
+/*
+ * parsing/rule/impls/block/blocks/spoiler.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+
+pub const BLOCK_SPOILER: BlockRule = BlockRule {
+    name: "block-spoiler",
+    accepts_names: &["spoiler"],
+    accepts_star: false,
+    accepts_score: false,
+    accepts_newlines: false,
+    parse_fn: parse_spoiler,
+};
+
+pub const BLOCK_SPOILER_BLOCK: BlockRule = BlockRule {
+    name: "block-spoiler-block",
+    accepts_names: &["spoilerblock"],
+    accepts_star: false,
+    accepts_score: false,
+    accepts_newlines: true,
+    parse_fn: parse_spoiler_block,
+};
+
+fn parse_spoiler<'r, 't>(
+    parser: &mut Parser<'r, 't>,
+    name: &'t str,
+    flag_star: bool,
+    flag_score: bool,
+    in_head: bool,
+) -> ParseResult<'r, 't, Elements<'t>> {
+    info!("Parsing inline spoiler block (in-head {in_head})");
+    assert!(!flag_star, "Spoiler doesn't allow star flag");
+    assert!(!flag_score, "Spoiler doesn't allow score flag");
+    assert_block_name(&BLOCK_SPOILER, name);
+
+    let arguments = parser.get_head_map(&BLOCK_SPOILER, in_head)?;
+
+    // Get body content, without paragraphs
+    let (elements, errors, paragraph_safe) =
+        parser.get_body_elements(&BLOCK_SPOILER, false)?.into();
+
+    // Build and return element
+    let element = Element::Spoiler {
+        elements,
+        attributes: arguments.to_attribute_map(parser.settings()),
+        block: false,
+        show_text: None,
+        hide_text: None,
+    };
+
+    ok!(paragraph_safe; element, errors)
+}
+
+fn parse_spoiler_block<'r, 't>(
+    parser: &mut Parser<'r, 't>,
+    name: &'t str,
+    flag_star: bool,
+    flag_score: bool,
+    in_head: bool,
+) -> ParseResult<'r, 't, Elements<'t>> {
+    info!("Parsing spoiler block (in-head {in_head})");
+    assert!(!flag_star, "Spoiler block doesn't allow star flag");
+    assert!(!flag_score, "Spoiler block doesn't allow score flag");
+    assert_block_name(&BLOCK_SPOILER_BLOCK, name);
+
+    let mut arguments = parser.get_head_map(&BLOCK_SPOILER_BLOCK, in_head)?;
+
+    // Get display arguments, same as collapsibles
+    let show_text = arguments.get("show");
+    let hide_text = arguments.get("hide");
+
+    // Get body content, with paragraphs.
+    // Discard paragraph_safe, since spoiler blocks never are.
+    let (elements, errors, _) =
+        parser.get_body_elements(&BLOCK_SPOILER_BLOCK, true)?.into();
+
+    // Build and return element
+    let element = Element::Spoiler {
+        elements,
+        attributes: arguments.to_attribute_map(parser.settings()),
+        block: true,
+        show_text,
+        hide_text,
+    };
+
+    ok!(false; element, errors)
+}
@@ -23,7 +23,7 @@ use once_cell::sync::Lazy;
 use std::collections::HashMap;
 use unicase::UniCase;
 
-pub const BLOCK_RULES: [BlockRule; 60] = [
+pub const BLOCK_RULES: [BlockRule; 65] = [
     BLOCK_ALIGN_CENTER,
     BLOCK_ALIGN_JUSTIFY,
     BLOCK_ALIGN_LEFT,
@@ -42,8 +42,11 @@ pub const BLOCK_RULES: [BlockRule; 60] = [
     BLOCK_DIV,
     BLOCK_EMBED,
     BLOCK_EQUATION_REF,
+    BLOCK_FILE_CARD,
     BLOCK_FOOTNOTE,
     BLOCK_FOOTNOTE_BLOCK,
+    BLOCK_GALLERY,
+    BLOCK_GALLERY_IMAGE,
     BLOCK_HIDDEN,
     BLOCK_HTML,
     BLOCK_IFCATEGORY,
@@ -70,6 +73,8 @@ pub const BLOCK_RULES: [BlockRule; 60] = [
     BLOCK_RUBY,
     BLOCK_SIZE,
     BLOCK_SPAN,
+    BLOCK_SPOILER,
+    BLOCK_SPOILER_BLOCK,
     BLOCK_STRIKETHROUGH,
     BLOCK_SUBSCRIPT,
     BLOCK_SUPERSCRIPT,
@@ -99,6 +104,14 @@ pub fn get_block_rule_with_name(name: &str) -> Option<&'static BlockRule> {
     BLOCK_RULE_MAP.get(&name).copied()
 }
 
+/// Whether a block with this name is built into the parser.
+///
+/// Used to prevent custom blocks from shadowing them.
+#[inline]
+pub fn is_builtin_block(name: &str) -> bool {
+    get_block_rule_with_name(name).is_some()
+}
+
 fn build_block_rule_map(block_rules: &'static [BlockRule]) -> BlockRuleMap {
     let mut map = HashMap::new();
 
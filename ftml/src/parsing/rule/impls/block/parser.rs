@@ -108,6 +108,7 @@ where
         &mut self,
         first_iteration: bool,
         block_rule: &BlockRule,
+        end_names: &[&str],
     ) -> Option<&'r ExtractedToken<'t>> {
         self.save_evaluate_fn(|parser| {
             // Check that the end block is on a new line, if required
@@ -128,8 +129,18 @@ where
             let name = name.strip_suffix('_').unwrap_or(name);
 
             // Check if it's valid
-            for end_block_name in block_rule.accepts_names {
-                if name.eq_ignore_ascii_case(end_block_name) {
+            //
+            // Block names are otherwise case-insensitive,
+            // but the strict profile requires them to be lowercase.
+            let strict = parser.strict();
+            for end_block_name in end_names {
+                let matches = if strict {
+                    name == *end_block_name
+                } else {
+                    name.eq_ignore_ascii_case(end_block_name)
+                };
+
+                if matches {
                     return Ok(true);
                 }
             }
@@ -146,6 +157,7 @@ where
     fn get_body_generic<F>(
         &mut self,
         block_rule: &BlockRule,
+        end_names: &[&str],
         mut process: F,
     ) -> Result<(&'r ExtractedToken<'t>, &'r ExtractedToken<'t>), ParseError>
     where
@@ -154,7 +166,7 @@ where
         debug!("Running generic in block body parser");
 
         debug_assert!(
-            !block_rule.accepts_names.is_empty(),
+            !end_names.is_empty(),
             "List of valid end block names is empty, no success is possible",
         );
 
@@ -164,7 +176,7 @@ where
         let start = self.current();
 
         loop {
-            let at_end_block = self.verify_end_block(first, block_rule);
+            let at_end_block = self.verify_end_block(first, block_rule, end_names);
 
             // If there's a match, return the last body token
             if let Some(end) = at_end_block {
@@ -188,14 +200,26 @@ where
     /// The `accepts_newlines` argument designates whether this
     /// block assumes multiline construction (e.g. `[[div]]`, `[[code]]`)
     /// or not (e.g. `[[span]]`).
+    #[inline]
     pub fn get_body_text(
         &mut self,
         block_rule: &BlockRule,
+    ) -> Result<&'t str, ParseError> {
+        self.get_body_text_until(block_rule, block_rule.accepts_names)
+    }
+
+    /// Like `get_body_text()`, but ends at a tail block with one of the given names.
+    ///
+    /// This is for blocks whose names aren't known until runtime.
+    pub fn get_body_text_until(
+        &mut self,
+        block_rule: &BlockRule,
+        end_names: &[&str],
     ) -> Result<&'t str, ParseError> {
         info!("Getting block body as text (rule {})", block_rule.name);
 
         // State variables for collecting span
-        let (start, end) = self.get_body_generic(block_rule, |_| Ok(()))?;
+        let (start, end) = self.get_body_generic(block_rule, end_names, |_| Ok(()))?;
         let slice = self.full_text().slice_partial(start, end);
         Ok(slice)
     }
@@ -205,6 +229,18 @@ where
         &mut self,
         block_rule: &BlockRule,
         as_paragraphs: bool,
+    ) -> ParseResult<'r, 't, Vec<Element<'t>>> {
+        self.get_body_elements_until(block_rule, block_rule.accepts_names, as_paragraphs)
+    }
+
+    /// Like `get_body_elements()`, but ends at a tail block with one of the given names.
+    ///
+    /// This is for blocks whose names aren't known until runtime.
+    pub fn get_body_elements_until(
+        &mut self,
+        block_rule: &BlockRule,
+        end_names: &[&str],
+        as_paragraphs: bool,
     ) -> ParseResult<'r, 't, Vec<Element<'t>>> {
         info!(
             "Getting block body as elements (block rule {}, as-paragraphs {})",
@@ -212,15 +248,16 @@ where
         );
 
         if as_paragraphs {
-            self.get_body_elements_paragraphs(block_rule)
+            self.get_body_elements_paragraphs(block_rule, end_names)
         } else {
-            self.get_body_elements_no_paragraphs(block_rule)
+            self.get_body_elements_no_paragraphs(block_rule, end_names)
         }
     }
 
     fn get_body_elements_paragraphs(
         &mut self,
         block_rule: &BlockRule,
+        end_names: &[&str],
     ) -> ParseResult<'r, 't, Vec<Element<'t>>> {
         let mut first = true;
 
@@ -228,7 +265,7 @@ where
             self,
             self.rule(),
             Some(move |parser: &mut Parser<'r, 't>| {
-                let result = parser.verify_end_block(first, block_rule);
+                let result = parser.verify_end_block(first, block_rule, end_names);
                 first = false;
 
                 Ok(result.is_some())
@@ -239,6 +276,7 @@ where
     fn get_body_elements_no_paragraphs(
         &mut self,
         block_rule: &BlockRule,
+        end_names: &[&str],
     ) -> ParseResult<'r, 't, Vec<Element<'t>>> {
         let mut all_elements = Vec::new();
         let mut all_errors = Vec::new();
@@ -246,7 +284,7 @@ where
         let mut first = true;
 
         loop {
-            let result = self.verify_end_block(first, block_rule);
+            let result = self.verify_end_block(first, block_rule, end_names);
             if result.is_some() {
                 return ok!(paragraph_safe; all_elements, all_errors);
             }
@@ -342,7 +380,10 @@ where
                 let value = parse_string(value_raw);
 
                 // Add to argument map
-                map.insert(key, value);
+                if map.insert(key, value).is_some() && self.strict() {
+                    warn!("Block argument '{key}' given more than once");
+                    return Err(self.make_err(ParseErrorKind::BlockDuplicateArgument));
+                }
             }
         }
 
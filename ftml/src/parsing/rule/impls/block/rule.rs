@@ -19,7 +19,9 @@
  */
 
 use super::super::prelude::*;
+use super::blocks::BLOCK_CUSTOM;
 use super::mapping::get_block_rule_with_name;
+use super::BlockRule;
 
 pub const RULE_BLOCK: Rule = Rule {
     name: "block",
@@ -68,7 +70,7 @@ fn block_skip<'r, 't>(parser: &mut Parser<'r, 't>) -> ParseResult<'r, 't, Elemen
         let (name, _) = parser.get_block_name(false)?;
 
         // Get the block rule: if it accepts newlines, then we consume here
-        match get_block_rule_with_name(name) {
+        match get_block_rule(parser, name) {
             Some(block_rule) => Ok(block_rule.accepts_newlines),
             None => Ok(false),
         }
@@ -84,6 +86,23 @@ fn block_skip<'r, 't>(parser: &mut Parser<'r, 't>) -> ParseResult<'r, 't, Elemen
 
 // Block parsing implementation
 
+/// Gets the block rule for this name, including custom blocks.
+///
+/// Built-in blocks take precedence, though registering a custom
+/// block with the same name as one is already prohibited.
+fn get_block_rule(parser: &Parser, name: &str) -> Option<&'static BlockRule> {
+    if let Some(block) = get_block_rule_with_name(name) {
+        return Some(block);
+    }
+
+    let name = name.strip_suffix('_').unwrap_or(name); // score flag
+    if parser.settings().custom_blocks.get(name).is_some() {
+        return Some(&BLOCK_CUSTOM);
+    }
+
+    None
+}
+
 fn parse_block<'r, 't>(
     parser: &mut Parser<'r, 't>,
     flag_star: bool,
@@ -112,14 +131,22 @@ where
     };
 
     // Get the block rule for this name
-    let block = match get_block_rule_with_name(name) {
+    let block = match get_block_rule(parser, name) {
         Some(block) => block,
-        None => return Err(parser.make_err(ParseErrorKind::NoSuchBlock)),
+        None => {
+            warn!("No built-in or custom block named '{name}'");
+            return Err(parser.make_err(ParseErrorKind::NoSuchBlock));
+        }
     };
 
     // Set block rule for better errors
     parser.set_block(block);
 
+    // Block names are case-insensitive, unless parsing strictly
+    if parser.strict() && name.chars().any(|c| c.is_ascii_uppercase()) {
+        return Err(parser.make_err(ParseErrorKind::BlockNameNotLowercase));
+    }
+
     // Check if this block allows star invocation (the '[[*' token)
     if !block.accepts_star && flag_star {
         return Err(parser.make_err(ParseErrorKind::BlockDisallowsStar));
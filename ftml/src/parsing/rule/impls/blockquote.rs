@@ -31,6 +31,19 @@ pub const RULE_BLOCKQUOTE: Rule = Rule {
     try_consume_fn,
 };
 
+/// A single line within a native blockquote.
+#[derive(Debug)]
+enum QuoteLine<'t> {
+    /// Regular contents, and whether they are paragraph-safe.
+    Contents(Vec<Element<'t>>, bool),
+
+    /// A line with only ">"s, which separates paragraphs.
+    Blank,
+
+    /// An attribution line, such as `> — Author`.
+    Attribution(Vec<Element<'t>>),
+}
+
 fn try_consume_fn<'r, 't>(
     parser: &mut Parser<'r, 't>,
 ) -> ParseResult<'r, 't, Elements<'t>> {
@@ -40,12 +53,30 @@ fn try_consume_fn<'r, 't>(
     let mut depths = Vec::new();
     let mut errors = Vec::new();
 
+    // The depth a line without ">"s would continue at, if any.
+    //
+    // This is only set after a line of regular contents which ended
+    // with a line break, since a paragraph break or blank line
+    // closes the paragraph it would be continuing.
+    let mut lazy_depth = None;
+
     // Produce a depth list with elements
     loop {
         let current = parser.current();
-        let depth = match current.token {
+        let depth = match (current.token, lazy_depth) {
             // 1 or more ">"s in one token. Return ASCII length.
-            Token::Quote => current.slice.len(),
+            (Token::Quote, _) => {
+                let depth = current.slice.len();
+                parser.step()?;
+                parser.get_optional_space()?; // allow whitespace after ">"
+                depth
+            }
+
+            // Lazy continuation, a plain line continuing the previous one.
+            (token, Some(depth)) if !starts_block(token) => {
+                debug!("Found lazy continuation line for blockquote");
+                depth
+            }
 
             // Invalid token, bail
             _ => {
@@ -53,8 +84,6 @@ fn try_consume_fn<'r, 't>(
                 break;
             }
         };
-        parser.step()?;
-        parser.get_optional_space()?; // allow whitespace after ">"
 
         // Check that the depth isn't obscenely deep, to avoid DOS attacks via stack overflow.
         if depth > MAX_BLOCKQUOTE_DEPTH {
@@ -62,9 +91,16 @@ fn try_consume_fn<'r, 't>(
             return Err(parser.make_err(ParseErrorKind::BlockquoteDepthExceeded));
         }
 
+        // Check if this line is an attribution
+        let attribution = is_attribution(parser);
+        if attribution {
+            debug!("Found blockquote attribution line");
+            parser.step_n(2)?;
+        }
+
         // Parse elements until we hit the end of the line
         let mut paragraph_safe = true;
-        let mut elements = collect_consume(
+        let (mut elements, last) = collect_consume_keep(
             parser,
             RULE_BLOCKQUOTE,
             &[
@@ -77,16 +113,31 @@ fn try_consume_fn<'r, 't>(
         )?
         .chain(&mut errors, &mut paragraph_safe);
 
-        // Add a line break for the end of the line
-        elements.push(Element::LineBreak);
+        let line = if attribution {
+            lazy_depth = None;
+            QuoteLine::Attribution(elements)
+        } else if elements.is_empty() {
+            lazy_depth = None;
+            QuoteLine::Blank
+        } else {
+            lazy_depth = match last.token {
+                Token::LineBreak => Some(depth),
+                _ => None,
+            };
+
+            // Add a line break for the end of the line
+            elements.push(Element::LineBreak);
+            QuoteLine::Contents(elements, paragraph_safe)
+        };
 
         // Append blockquote line
         //
         // Depth lists expect zero-based list depths, but tokens are one-based.
         // So, we subtract one.
         //
-        // This will not overflow because Token::Quote requires at least one ">".
-        depths.push((depth - 1, (), (elements, paragraph_safe)))
+        // This will not overflow because Token::Quote requires at least one ">",
+        // and lazy continuation lines reuse the depth of the line before them.
+        depths.push((depth - 1, (), line));
     }
 
     // This blockquote has no rows, so the rule fails
@@ -103,17 +154,78 @@ fn try_consume_fn<'r, 't>(
     ok!(false; elements, errors)
 }
 
-fn build_blockquote_element(list: DepthList<(), (Vec<Element>, bool)>) -> Element {
+/// Determines if a line beginning with this token starts some other construct.
+///
+/// Such lines end the blockquote, rather than being a lazy continuation of it.
+fn starts_block(token: Token) -> bool {
+    matches!(
+        token,
+        Token::LineBreak
+            | Token::ParagraphBreak
+            | Token::InputEnd
+            | Token::Whitespace
+            | Token::LeftBlock
+            | Token::LeftBlockEnd
+            | Token::LeftBlockAnchor
+            | Token::LeftBlockStar
+            | Token::LeftMath
+            | Token::TripleDash
+            | Token::ClearFloatBoth
+            | Token::ClearFloatLeft
+            | Token::ClearFloatRight
+            | Token::Equals
+            | Token::Colon
+            | Token::Heading
+            | Token::BulletItem
+            | Token::NumberedItem
+            | Token::TableColumn
+            | Token::TableColumnLeft
+            | Token::TableColumnRight
+            | Token::TableColumnCenter
+            | Token::TableColumnTitle
+    )
+}
+
+/// Determines if the rest of the line is an attribution, like `— Author`.
+///
+/// Either an em dash or `--` may be used, but it must be followed by a space,
+/// so that strikethrough text at the start of a line isn't mistaken for one.
+fn is_attribution(parser: &Parser) -> bool {
+    let current = parser.current();
+    let dash = match current.token {
+        Token::DoubleDash => true,
+        Token::Other => current.slice == "\u{2014}",
+        _ => false,
+    };
+
+    dash && parser.look_ahead(0).map(|next| next.token) == Some(Token::Whitespace)
+}
+
+fn build_blockquote_element(list: DepthList<(), QuoteLine>) -> Element {
     let mut stack = ParagraphStack::new();
 
     // Convert depth list into a list of elements
     for item in list {
         match item {
-            DepthItem::Item((elements, paragraph_safe)) => {
+            DepthItem::Item(QuoteLine::Contents(elements, paragraph_safe)) => {
                 for element in elements {
                     stack.push_element(element, paragraph_safe);
                 }
             }
+            DepthItem::Item(QuoteLine::Blank) => {
+                stack.pop_line_break();
+                stack.end_paragraph();
+            }
+            DepthItem::Item(QuoteLine::Attribution(elements)) => {
+                let cite = Element::Container(Container::new(
+                    ContainerType::Cite,
+                    elements,
+                    AttributeMap::new(),
+                ));
+
+                stack.pop_line_break();
+                stack.push_element(cite, false);
+            }
             DepthItem::List(_, list) => {
                 let blockquote = build_blockquote_element(list);
                 stack.pop_line_break();
@@ -20,7 +20,7 @@
 
 use super::prelude::*;
 use crate::parsing::{process_depths, DepthItem, DepthList};
-use crate::tree::{AttributeMap, ListItem, ListType};
+use crate::tree::{AttributeMap, ListItem, ListTask, ListType};
 
 const MAX_LIST_DEPTH: usize = 20;
 
@@ -102,6 +102,9 @@ fn try_consume_fn<'r, 't>(
         }
         parser.step()?;
 
+        // Check if this is a task item, i.e. "* [ ] item" or "* [x] item"
+        let task = parse_task(parser)?;
+
         // Parse elements until we hit the end of the line
         let elements = collect_consume(
             parser,
@@ -117,7 +120,7 @@ fn try_consume_fn<'r, 't>(
         .chain(&mut errors, &mut paragraph_safe);
 
         // Append list line
-        depths.push((depth, list_type, elements));
+        depths.push((depth, list_type, (elements, task)));
     }
 
     // This list has no rows, so the rule fails
@@ -134,14 +137,60 @@ fn try_consume_fn<'r, 't>(
     ok!(paragraph_safe; elements, errors)
 }
 
+/// Consumes a task marker after a bullet, if present.
+///
+/// The marker must be `[ ]`, `[x]`, or `[X]`, followed by whitespace.
+/// Otherwise, nothing is consumed and the brackets are left as text.
+fn parse_task(parser: &mut Parser) -> Result<Option<ListTask>, ParseError> {
+    let current = parser.current();
+    if current.token != Token::LeftBracket {
+        return Ok(None);
+    }
+
+    let checked = match parser.look_ahead(0) {
+        Some(ExtractedToken {
+            token: Token::Whitespace,
+            slice: " ",
+            ..
+        }) => false,
+        Some(ExtractedToken {
+            token: Token::Identifier,
+            slice: "x" | "X",
+            ..
+        }) => true,
+        _ => return Ok(None),
+    };
+
+    match (parser.look_ahead(1), parser.look_ahead(2)) {
+        (
+            Some(ExtractedToken {
+                token: Token::RightBracket,
+                ..
+            }),
+            Some(ExtractedToken {
+                token: Token::Whitespace,
+                ..
+            }),
+        ) => (),
+        _ => return Ok(None),
+    }
+
+    debug!("Found list task marker (checked {checked})");
+
+    let offset = current.span.start;
+    parser.step_n(4)?;
+    Ok(Some(ListTask { checked, offset }))
+}
+
 fn build_list_element(
     top_ltype: ListType,
-    list: DepthList<ListType, Vec<Element>>,
+    list: DepthList<ListType, (Vec<Element>, Option<ListTask>)>,
 ) -> Element {
     let build_item = |item| match item {
-        DepthItem::Item(elements) => ListItem::Elements {
+        DepthItem::Item((elements, task)) => ListItem::Elements {
             elements,
             attributes: AttributeMap::new(),
+            task,
         },
         DepthItem::List(ltype, list) => ListItem::SubList {
             element: Box::new(build_list_element(ltype, list)),
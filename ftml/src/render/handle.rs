@@ -18,18 +18,27 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
-use crate::data::{PageInfo, UserInfo};
+use crate::data::{
+    AttachedFile, FileInfo, FileReference, PageInfo, RenderContext, UserInfo,
+};
 use crate::settings::WikitextSettings;
-use crate::tree::{ImageSource, LinkLabel, LinkLocation, Module};
+use crate::tree::{DateItem, GallerySize, ImageSource, LinkLabel, LinkLocation, Module};
 use crate::url::BuildSiteUrl;
 use std::borrow::Cow;
 use std::num::NonZeroUsize;
 use wikidot_normalize::normalize;
 
 #[derive(Debug)]
-pub struct Handle;
+pub struct Handle<'c> {
+    context: &'c RenderContext<'c>,
+}
+
+impl<'c> Handle<'c> {
+    #[inline]
+    pub fn new(context: &'c RenderContext<'c>) -> Self {
+        Handle { context }
+    }
 
-impl Handle {
     pub fn render_module(&self, buffer: &mut String, module: &Module) {
         // Modules only render to HTML
         info!("Rendering module '{}'", module.name());
@@ -64,6 +73,42 @@ impl Handle {
         Some(info)
     }
 
+    /// Fetches metadata about an attached file, for file cards.
+    ///
+    /// Files from arbitrary URLs have no metadata, so `None` is returned for them.
+    pub fn get_file_info(
+        &self,
+        source: &ImageSource,
+        info: &PageInfo,
+        settings: &WikitextSettings,
+    ) -> Option<&'c FileInfo<'c>> {
+        info!("Fetching file info (source '{}')", source.name());
+
+        if let ImageSource::Url(_) = source {
+            warn!("Specified URL file source, which has no metadata");
+            return None;
+        }
+
+        self.get_attached_file(source, info, settings)
+            .map(|file| &file.info)
+    }
+
+    /// Looks up an attached file in the render context.
+    fn get_attached_file(
+        &self,
+        source: &ImageSource,
+        info: &PageInfo,
+        settings: &WikitextSettings,
+    ) -> Option<&'c AttachedFile<'c>> {
+        if !settings.allow_local_paths {
+            warn!("Specified path file source when local paths are disabled");
+            return None;
+        }
+
+        let reference = FileReference::from_source(source, info)?;
+        self.context.get_file(&reference)
+    }
+
     pub fn get_image_link<'a>(
         &self,
         source: &ImageSource<'a>,
@@ -73,6 +118,10 @@ impl Handle {
         info!("Getting file link for image");
 
         let (site, page, file): (&str, &str, &str) = match source {
+            ImageSource::Url(_) if !settings.allow_external_images => {
+                warn!("Specified URL image source when external images are disabled");
+                return None;
+            }
             ImageSource::Url(url) => return Some(Cow::clone(url)),
             ImageSource::File1 { .. }
             | ImageSource::File2 { .. }
@@ -87,12 +136,71 @@ impl Handle {
             ImageSource::File3 { site, page, file } => (site, page, file),
         };
 
+        if let Some(file) = self.get_attached_file(source, info, settings) {
+            return Some(Cow::Owned(file.info.file_url.to_string()));
+        }
+
         // TODO: emit url
         Some(Cow::Owned(format!(
             "https://{site}.wjfiles.com/local--files/{page}/{file}",
         )))
     }
 
+    /// Gets the link to a resized derivative of an image, for galleries.
+    ///
+    /// The original link is used for `Original`, for images from arbitrary URLs,
+    /// and for attached images which don't have a derivative at this size.
+    pub fn get_image_derivative_link<'a>(
+        &self,
+        source: &ImageSource<'a>,
+        size: GallerySize,
+        info: &PageInfo,
+        settings: &WikitextSettings,
+    ) -> Option<Cow<'a, str>> {
+        info!("Getting derivative link for image (size {})", size.name());
+
+        let link = self.get_image_link(source, info, settings)?;
+        if size == GallerySize::Original {
+            return Some(link);
+        }
+
+        let derivative = self
+            .get_attached_file(source, info, settings)
+            .and_then(|file| file.get_derivative(size));
+
+        match derivative {
+            Some(url) => Some(Cow::Owned(str!(url))),
+            None => Some(link),
+        }
+    }
+
+    /// Gets the contents of an attached image, so it can be embedded in the output.
+    ///
+    /// Returns the MIME type and the image data, or `None` if the contents
+    /// were not provided or are larger than `max_size` bytes. Images from
+    /// arbitrary URLs are never fetched, so they are always linked to instead.
+    pub fn get_image_data(
+        &self,
+        source: &ImageSource,
+        info: &PageInfo,
+        settings: &WikitextSettings,
+        max_size: usize,
+    ) -> Option<(&'c str, &'c [u8])> {
+        info!(
+            "Getting image data (source '{}', max size {max_size})",
+            source.name(),
+        );
+
+        let file = self.get_attached_file(source, info, settings)?;
+        let data = file.data.as_deref()?;
+        if data.len() > max_size {
+            debug!("Image too large to embed ({} bytes)", data.len());
+            return None;
+        }
+
+        Some((&file.info.file_mime, data))
+    }
+
     pub fn get_link_label<F>(
         &self,
         site: &str,
@@ -139,6 +247,10 @@ impl Handle {
             "button-copy-clipboard" => "Copy to Clipboard",
             "collapsible-open" => "+ open block",
             "collapsible-hide" => "- hide block",
+            "spoiler" => "Spoiler",
+            "spoiler-show" => "+ show spoiler",
+            "spoiler-hide" => "- hide spoiler",
+            "spoiler-redacted" => "[spoiler]",
             "table-of-contents" => "Table of Contents",
             "footnote" => "Footnote",
             "footnote-block-title" => "Footnotes",
@@ -146,6 +258,11 @@ impl Handle {
             "bibliography-block-title" => "Bibliography",
             "bibliography-cite-not-found" => "Bibliography item not found",
             "image-context-bad" => "No images in this context",
+            "file-card-uploader" => "Uploaded by",
+            "file-card-license" => "License:",
+            "file-card-missing" => "File not found",
+            "custom-block-unavailable" => "This block is not available",
+            "module-disabled" => "This feature is disabled",
             _ => {
                 error!("Unknown message requested (key {message})");
                 "?"
@@ -153,6 +270,18 @@ impl Handle {
         }
     }
 
+    pub fn format_date(&self, language: &str, date: DateItem) -> String {
+        info!("Formatting date (language {language})");
+
+        match date.format_localized(language) {
+            Ok(formatted) => formatted,
+            Err(error) => {
+                error!("Error formatting date into string: {error}");
+                str!("<ERROR>")
+            }
+        }
+    }
+
     pub fn post_html(&self, info: &PageInfo, html: &str) -> String {
         info!("Submitting HTML to create iframe-able snippet");
 
@@ -173,7 +302,7 @@ impl Handle {
     }
 }
 
-impl BuildSiteUrl for Handle {
+impl BuildSiteUrl for Handle<'_> {
     fn build_url(&self, site: &str, path: &str) -> String {
         // TODO make this a parser setting
         // get url of wikijump instance here
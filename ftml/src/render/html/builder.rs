@@ -197,6 +197,11 @@ impl<'c, 'i, 'h, 'e, 't> HtmlBuilderTag<'c, 'i, 'h, 'e, 't> {
 
         let has_value = !value_parts.iter().all(|s| s.is_empty());
 
+        // Inline styles must be listed in the Content Security Policy
+        if has_value && key.eq_ignore_ascii_case("style") {
+            self.ctx.add_style_attribute(&value_parts.concat());
+        }
+
         self.attr_key(key, has_value);
 
         if has_value {
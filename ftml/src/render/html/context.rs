@@ -19,18 +19,19 @@
  */
 
 use super::builder::HtmlBuilder;
+use super::csp::HtmlCsp;
 use super::escape::escape;
 use super::meta::{HtmlMeta, HtmlMetaType};
 use super::output::HtmlOutput;
 use super::random::Random;
 use crate::data::PageRef;
-use crate::data::{Backlinks, PageInfo};
+use crate::data::{Backlinks, LinkKind, LinkMetadata, PageInfo};
 use crate::info;
 use crate::next_index::{NextIndex, TableOfContentsIndex};
 use crate::render::Handle;
 use crate::settings::WikitextSettings;
 use crate::tree::{
-    Bibliography, BibliographyList, Element, LinkLocation, VariableScopes,
+    Bibliography, BibliographyList, Element, LinkLocation, SyntaxTree, VariableScopes,
 };
 use crate::url::is_url;
 use std::borrow::Cow;
@@ -46,8 +47,10 @@ where
     body: String,
     meta: Vec<HtmlMeta>,
     backlinks: Backlinks<'static>,
+    links: Vec<LinkMetadata<'static>>,
+    csp: HtmlCsp,
     info: &'i PageInfo<'i>,
-    handle: &'h Handle,
+    handle: &'h Handle<'h>,
     settings: &'e WikitextSettings,
     random: Random,
 
@@ -81,12 +84,9 @@ impl<'i, 'h, 'e, 't> HtmlContext<'i, 'h, 'e, 't> {
     #[inline]
     pub fn new(
         info: &'i PageInfo<'i>,
-        handle: &'h Handle,
+        handle: &'h Handle<'h>,
         settings: &'e WikitextSettings,
-        table_of_contents: &'e [Element<'t>],
-        footnotes: &'e [Vec<Element<'t>>],
-        bibliographies: &'e BibliographyList<'t>,
-        wikitext_len: usize,
+        tree: &'e SyntaxTree<'t>,
     ) -> Self {
         // Heuristic for improving rendering performance by avoiding reallocating.
         //
@@ -94,7 +94,7 @@ impl<'i, 'h, 'e, 't> HtmlContext<'i, 'h, 'e, 't> {
         // below ~12% of the wikitext input byte length, with the greatest differences
         // being small inputs.
         let capacity = {
-            let input = wikitext_len as f32;
+            let input = tree.wikitext_len as f32;
             let output = input * 1.12;
 
             // Basic sanity check, if this fails
@@ -111,14 +111,16 @@ impl<'i, 'h, 'e, 't> HtmlContext<'i, 'h, 'e, 't> {
             body: String::with_capacity(capacity),
             meta: Self::initial_metadata(info),
             backlinks: Backlinks::new(),
+            links: Vec::new(),
+            csp: HtmlCsp::default(),
             info,
             handle,
             settings,
             random: Random::default(),
             variables: VariableScopes::new(),
-            table_of_contents,
-            footnotes,
-            bibliographies,
+            table_of_contents: &tree.table_of_contents,
+            footnotes: &tree.footnotes,
+            bibliographies: &tree.bibliographies,
             pages_exists: HashMap::new(),
             code_snippet_index: NonZeroUsize::new(1).unwrap(),
             table_of_contents_index: 0,
@@ -169,12 +171,12 @@ impl<'i, 'h, 'e, 't> HtmlContext<'i, 'h, 'e, 't> {
     }
 
     #[inline]
-    pub fn settings(&self) -> &WikitextSettings {
+    pub fn settings(&self) -> &'e WikitextSettings {
         self.settings
     }
 
     #[inline]
-    pub fn handle(&self) -> &'h Handle {
+    pub fn handle(&self) -> &'h Handle<'h> {
         self.handle
     }
 
@@ -285,6 +287,28 @@ impl<'i, 'h, 'e, 't> HtmlContext<'i, 'h, 'e, 't> {
         }
     }
 
+    /// Records a rendered link, returning the `rel` attribute value it should have.
+    ///
+    /// Only links to other websites are given one, per the render settings.
+    pub fn add_link_metadata(
+        &mut self,
+        kind: LinkKind,
+        target: &str,
+    ) -> Option<&'static str> {
+        let rel = match kind {
+            LinkKind::External => self.settings.external_link_rel.value(),
+            _ => None,
+        };
+
+        self.links.push(LinkMetadata {
+            kind,
+            target: Cow::Owned(str!(target)),
+            rel: rel.map(Cow::Borrowed),
+        });
+
+        rel
+    }
+
     pub fn page_exists(&mut self, page_ref: &PageRef) -> bool {
         let (site, page) = page_ref.fields_or(&self.info.site);
 
@@ -299,6 +323,17 @@ impl<'i, 'h, 'e, 't> HtmlContext<'i, 'h, 'e, 't> {
         }
     }
 
+    // Content Security Policy
+    #[inline]
+    pub fn add_style(&mut self, css: &str) {
+        self.csp.add_style(css);
+    }
+
+    #[inline]
+    pub fn add_style_attribute(&mut self, css: &str) {
+        self.csp.add_style_attribute(css);
+    }
+
     // TODO
     #[allow(dead_code)]
     #[inline]
@@ -340,6 +375,8 @@ impl<'i, 'h, 'e, 't> From<HtmlContext<'i, 'h, 'e, 't>> for HtmlOutput {
             body,
             meta,
             backlinks,
+            links,
+            csp,
             ..
         } = ctx;
 
@@ -347,6 +384,8 @@ impl<'i, 'h, 'e, 't> From<HtmlContext<'i, 'h, 'e, 't>> for HtmlOutput {
             body,
             meta,
             backlinks,
+            links,
+            csp,
         }
     }
 }
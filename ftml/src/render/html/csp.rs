@@ -0,0 +1,78 @@
+/*
+ * render/html/csp.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Tracking of inline content for Content Security Policy headers.
+//!
+//! A strict policy forbids inline styles unless their contents match a digest
+//! listed in the policy. The renderer records the digest of each `<style>`
+//! element and `style` attribute, so the caller can build the `style-src`
+//! directive for the page.
+//!
+//! Digests are used rather than nonces since rendered HTML is stored and
+//! served to many requests, whereas a nonce is only valid for one.
+//!
+//! Browsers only check `style` attributes against the policy when it also
+//! contains `'unsafe-hashes'`, which is added as needed.
+
+use data_encoding::BASE64;
+use sha2::{Digest, Sha256};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct HtmlCsp {
+    /// Hash sources for each inline `<style>` element, such as `'sha256-...'`.
+    pub style_digests: Vec<String>,
+
+    /// Hash sources for each `style` attribute value.
+    pub style_attribute_digests: Vec<String>,
+}
+
+impl HtmlCsp {
+    #[inline]
+    pub fn add_style(&mut self, css: &str) {
+        add_digest(&mut self.style_digests, css);
+    }
+
+    #[inline]
+    pub fn add_style_attribute(&mut self, css: &str) {
+        add_digest(&mut self.style_attribute_digests, css);
+    }
+
+    /// Returns the source expressions needed in the `style-src` directive.
+    pub fn style_sources(&self) -> Vec<String> {
+        let mut sources = self.style_digests.clone();
+
+        if !self.style_attribute_digests.is_empty() {
+            sources.push(str!("'unsafe-hashes'"));
+            sources.extend(self.style_attribute_digests.iter().cloned());
+        }
+
+        sources
+    }
+}
+
+fn add_digest(digests: &mut Vec<String>, content: &str) {
+    let hash = Sha256::digest(content.as_bytes());
+    let source = format!("'sha256-{}'", BASE64.encode(&hash));
+
+    if !digests.contains(&source) {
+        digests.push(source);
+    }
+}
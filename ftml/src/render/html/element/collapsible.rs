@@ -76,6 +76,20 @@ pub fn render_collapsible(ctx: &mut HtmlContext, collapsible: Collapsible) {
         show_bottom,
     );
 
+    // In static output, there is no button to open the collapsible,
+    // so render its contents in full instead.
+    if !ctx.settings().interactive {
+        ctx.html()
+            .div()
+            .attr(attr!(
+                "class" => "wj-collapsible wj-collapsible-expanded";;
+                attributes,
+            ))
+            .contents(elements);
+
+        return;
+    }
+
     let show_text = show_text
         .unwrap_or_else(|| ctx.handle().get_message(ctx.language(), "collapsible-open"));
 
@@ -41,7 +41,8 @@ pub fn render_container(ctx: &mut HtmlContext, container: &Container) {
 
 pub fn render_container_internal(ctx: &mut HtmlContext, container: &Container) {
     // Get HTML tag type for this type of container
-    let tag_spec = container.ctype().html_tag(ctx);
+    let settings = ctx.settings();
+    let tag_spec = container.ctype().html_tag(ctx, settings);
 
     // Get correct ID, based on the render setting
     let random_id = choose_id(ctx, &tag_spec);
@@ -0,0 +1,129 @@
+/*
+ * render/html/element/custom_block.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::settings::{TemplateNode, TemplateValue};
+use crate::tree::{AttributeMap, CustomBlock};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use unicase::UniCase;
+
+pub fn render_custom_block(ctx: &mut HtmlContext, block: &CustomBlock) {
+    info!("Rendering custom block '{}'", block.name);
+
+    let template = match ctx.settings().custom_blocks.get(&block.name) {
+        Some(definition) => definition.template(),
+        None => {
+            warn!("No custom block registered with this name");
+            render_custom_block_error(ctx);
+            return;
+        }
+    };
+
+    match template {
+        Ok(nodes) => {
+            ctx.html()
+                .div()
+                .attr(attr!(
+                    "class" => "wj-custom-block",
+                    "data-block" => &block.name,
+                ))
+                .inner(|ctx| render_template(ctx, block, &nodes));
+        }
+        Err(error) => {
+            warn!("Custom block template is invalid: {}", error.name());
+            render_custom_block_error(ctx);
+        }
+    }
+}
+
+fn render_template(ctx: &mut HtmlContext, block: &CustomBlock, nodes: &[TemplateNode]) {
+    for node in nodes {
+        match node {
+            // Templates can't contain tags outside of elements,
+            // so literal text can be output directly.
+            TemplateNode::Text(text) => ctx.push_raw_str(text),
+            TemplateNode::Argument(name) => {
+                if let Some(value) = block.arguments.get(name.as_str()) {
+                    ctx.push_escaped(value);
+                }
+            }
+            TemplateNode::Body => render_elements(ctx, &block.elements),
+            TemplateNode::Element {
+                tag,
+                attributes,
+                children,
+            } => {
+                let attributes = build_attributes(ctx, block, attributes);
+
+                ctx.html()
+                    .tag(tag)
+                    .attr(attr!(;; attributes))
+                    .inner(|ctx| render_template(ctx, block, children));
+            }
+        }
+    }
+}
+
+/// Substitutes arguments into a template's attributes.
+///
+/// The resulting values are sanitized the same way as
+/// attributes passed to blocks in wikitext.
+fn build_attributes<'a>(
+    ctx: &HtmlContext,
+    block: &CustomBlock,
+    attributes: &'a [(String, Vec<TemplateValue>)],
+) -> AttributeMap<'a> {
+    let arguments: HashMap<UniCase<&str>, Cow<str>> = attributes
+        .iter()
+        .map(|(key, parts)| {
+            let mut value = String::new();
+
+            for part in parts {
+                match part {
+                    TemplateValue::Text(text) => value.push_str(text),
+                    TemplateValue::Argument(name) => {
+                        if let Some(argument) = block.arguments.get(name.as_str()) {
+                            value.push_str(argument);
+                        }
+                    }
+                }
+            }
+
+            (UniCase::ascii(key.as_str()), Cow::Owned(value))
+        })
+        .collect();
+
+    let mut attributes = AttributeMap::from_arguments(&arguments);
+    attributes.isolate_id(ctx.settings());
+    attributes.strip_style(ctx.settings());
+    attributes
+}
+
+fn render_custom_block_error(ctx: &mut HtmlContext) {
+    let message = ctx
+        .handle()
+        .get_message(ctx.language(), "custom-block-unavailable");
+
+    ctx.html()
+        .div()
+        .attr(attr!("class" => "wj-error-block"))
+        .contents(message);
+}
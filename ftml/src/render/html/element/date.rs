@@ -41,21 +41,30 @@ pub fn render_date(
         ("", "")
     };
 
+    // Display in the viewer's timezone, if known
+    let date = match ctx.info().utc_offset {
+        Some(offset) => date.to_offset(offset),
+        None => date,
+    };
+
     // Format datetime
     // TODO handle error
-    let formatted_datetime = match date.format() {
+    let machine_datetime = match date.format_machine() {
         Ok(datetime) => datetime,
         Err(error) => {
-            error!("Error formatting date into string: {error}");
-            str!("<ERROR>")
+            error!("Error formatting date into machine-readable string: {error}");
+            str!("")
         }
     };
 
+    let formatted_datetime = ctx.handle().format_date(ctx.language(), date);
+
     // Build HTML elements
     ctx.html()
-        .span()
+        .tag("time")
         .attr(attr!(
             "class" => "wj-date" space hover_class,
+            "datetime" => &machine_datetime,
             "data-timestamp" => &timestamp,
             "data-delta" => &delta,
         ))
@@ -0,0 +1,256 @@
+/*
+ * render/html/element/file_card.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::link::source_kind;
+use super::prelude::*;
+use super::user::render_user;
+use crate::tree::{AttributeMap, ImageSource};
+
+pub fn render_file_card(
+    ctx: &mut HtmlContext,
+    source: &ImageSource,
+    attributes: &AttributeMap,
+) {
+    info!("Rendering file card (source '{}')", source.name());
+
+    let file_info = ctx
+        .handle()
+        .get_file_info(source, ctx.info(), ctx.settings());
+
+    match file_info {
+        Some(file_info) => {
+            debug!(
+                "Got file information (name {}, size {}, mime {})",
+                file_info.file_name.as_ref(),
+                file_info.file_size,
+                file_info.file_mime.as_ref(),
+            );
+
+            let file_type = file_type(&file_info.file_mime);
+            let file_size = format_size(file_info.file_size);
+            let rel = ctx.add_link_metadata(source_kind(source), &file_info.file_url);
+
+            ctx.html()
+                .div()
+                .attr(attr!(
+                    "class" => "wj-file-card",
+                    "data-file-type" => file_type;;
+                    attributes,
+                ))
+                .inner(|ctx| {
+                    ctx.html().span().attr(
+                        attr!("class" => "wj-file-card-icon", "aria-hidden" => "true"),
+                    );
+
+                    ctx.html()
+                        .div()
+                        .attr(attr!("class" => "wj-file-card-details"))
+                        .inner(|ctx| {
+                            ctx.html()
+                                .a()
+                                .attr(attr!(
+                                    "class" => "wj-file-card-name",
+                                    "href" => &file_info.file_url,
+                                    "rel" => rel.unwrap_or(""); if rel.is_some(),
+                                    "download",
+                                ))
+                                .contents(&file_info.file_name);
+
+                            ctx.html()
+                                .span()
+                                .attr(attr!("class" => "wj-file-card-meta"))
+                                .inner(|ctx| {
+                                    ctx.html()
+                                        .span()
+                                        .attr(attr!("class" => "wj-file-card-size"))
+                                        .contents(&file_size);
+
+                                    ctx.html()
+                                        .span()
+                                        .attr(attr!("class" => "wj-file-card-mime"))
+                                        .contents(&file_info.file_mime);
+                                });
+
+                            if let Some(uploader) = &file_info.uploader_name {
+                                let label = ctx
+                                    .handle()
+                                    .get_message(ctx.language(), "file-card-uploader");
+
+                                ctx.html()
+                                    .span()
+                                    .attr(attr!("class" => "wj-file-card-uploader"))
+                                    .inner(|ctx| {
+                                        render_label(ctx, label);
+                                        render_user(ctx, uploader, false);
+                                    });
+                            }
+
+                            if let Some(license) = &file_info.license {
+                                let label = ctx
+                                    .handle()
+                                    .get_message(ctx.language(), "file-card-license");
+
+                                ctx.html()
+                                    .span()
+                                    .attr(attr!("class" => "wj-file-card-license"))
+                                    .inner(|ctx| {
+                                        render_label(ctx, label);
+                                        ctx.html().text(license);
+                                    });
+                            }
+                        });
+                });
+        }
+        None => {
+            debug!("No such file found");
+
+            let file_name = match source {
+                ImageSource::Url(url) => url,
+                ImageSource::File1 { file }
+                | ImageSource::File2 { file, .. }
+                | ImageSource::File3 { file, .. } => file,
+            };
+
+            let message = ctx
+                .handle()
+                .get_message(ctx.language(), "file-card-missing");
+
+            ctx.html()
+                .div()
+                .attr(attr!(
+                    "class" => "wj-file-card wj-file-card-missing",
+                    "data-file-type" => "missing";;
+                    attributes,
+                ))
+                .inner(|ctx| {
+                    ctx.html().span().attr(
+                        attr!("class" => "wj-file-card-icon", "aria-hidden" => "true"),
+                    );
+
+                    ctx.html()
+                        .div()
+                        .attr(attr!("class" => "wj-file-card-details"))
+                        .inner(|ctx| {
+                            ctx.html()
+                                .span()
+                                .attr(attr!("class" => "wj-file-card-name"))
+                                .contents(file_name);
+
+                            ctx.html()
+                                .span()
+                                .attr(attr!("class" => "wj-error-inline"))
+                                .contents(message);
+                        });
+                });
+        }
+    }
+}
+
+fn render_label(ctx: &mut HtmlContext, label: &str) {
+    ctx.html()
+        .span()
+        .attr(attr!("class" => "wj-file-card-label"))
+        .contents(label);
+}
+
+/// Determines which icon to show for a file, based on its MIME type.
+fn file_type(mime: &str) -> &'static str {
+    let mime = match mime.split_once(';') {
+        Some((mime, _)) => mime.trim(),
+        None => mime,
+    };
+
+    let (category, subtype) = mime.split_once('/').unwrap_or((mime, ""));
+
+    match category {
+        "image" => "image",
+        "audio" => "audio",
+        "video" => "video",
+        "text" => "text",
+        "application" => match subtype {
+            "pdf" | "msword" | "rtf" | "epub+zip" => "document",
+            _ if subtype.starts_with("vnd.openxmlformats-officedocument")
+                || subtype.starts_with("vnd.oasis.opendocument") =>
+            {
+                "document"
+            }
+            "zip" | "gzip" | "x-tar" | "x-bzip2" | "x-xz" | "x-7z-compressed"
+            | "vnd.rar" | "x-rar-compressed" => "archive",
+            _ => "file",
+        },
+        _ => "file",
+    }
+}
+
+/// Formats a file size in bytes as a short, human-readable string.
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+
+    let mut size = bytes as f64;
+    let mut unit = 0;
+
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{size:.1} {}", UNITS[unit])
+    }
+}
+
+#[test]
+fn test_file_type() {
+    macro_rules! check {
+        ($mime:expr, $expected:expr $(,)?) => {
+            assert_eq!(
+                file_type($mime),
+                $expected,
+                "Unexpected file type for MIME {:?}",
+                $mime,
+            );
+        };
+    }
+
+    check!("image/png", "image");
+    check!("audio/ogg", "audio");
+    check!("video/webm", "video");
+    check!("text/plain; charset=utf-8", "text");
+    check!("application/pdf", "document");
+    check!(
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+        "document",
+    );
+    check!("application/zip", "archive");
+    check!("application/octet-stream", "file");
+    check!("", "file");
+}
+
+#[test]
+fn test_format_size() {
+    assert_eq!(format_size(0), "0 B");
+    assert_eq!(format_size(1023), "1023 B");
+    assert_eq!(format_size(1024), "1.0 KB");
+    assert_eq!(format_size(1_572_864), "1.5 MB");
+    assert_eq!(format_size(5 * 1024 * 1024 * 1024), "5.0 GB");
+    assert_eq!(format_size(u64::MAX), "16777216.0 TB");
+}
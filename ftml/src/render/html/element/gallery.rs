@@ -0,0 +1,147 @@
+/*
+ * render/html/element/gallery.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::link::{link_kind, source_kind};
+use super::prelude::*;
+use crate::tree::{AttributeMap, GalleryImage, GallerySize};
+use crate::url::normalize_link;
+
+pub fn render_gallery(
+    ctx: &mut HtmlContext,
+    images: &[GalleryImage],
+    size: GallerySize,
+    attributes: &AttributeMap,
+) {
+    info!(
+        "Rendering gallery (images {}, size {})",
+        images.len(),
+        size.name(),
+    );
+
+    ctx.html()
+        .div()
+        .attr(attr!(
+            "class" => "wj-gallery " size.html_class();;
+            attributes,
+        ))
+        .inner(|ctx| {
+            for image in images {
+                render_gallery_image(ctx, image, size);
+            }
+        });
+}
+
+fn render_gallery_image(
+    ctx: &mut HtmlContext,
+    image: &GalleryImage,
+    default_size: GallerySize,
+) {
+    let GalleryImage {
+        source,
+        link,
+        caption,
+        size,
+        attributes,
+    } = image;
+
+    let size = size.unwrap_or(default_size);
+    debug!(
+        "Rendering gallery image (source '{}', size {})",
+        source.name(),
+        size.name(),
+    );
+
+    let image_url =
+        ctx.handle()
+            .get_image_derivative_link(source, size, ctx.info(), ctx.settings());
+
+    // Clicking the image goes to its link if given, or the full-size image otherwise
+    let (href, kind) = match link {
+        Some(link) => {
+            let url = normalize_link(link, ctx.handle());
+            let kind = link_kind(link.link_type(), link, &url);
+            (Some(url), kind)
+        }
+        None => {
+            let url = ctx
+                .handle()
+                .get_image_link(source, ctx.info(), ctx.settings());
+
+            (url, source_kind(source))
+        }
+    };
+
+    let rel = match &href {
+        Some(href) => ctx.add_link_metadata(kind, href),
+        None => None,
+    };
+
+    ctx.html()
+        .tag("figure")
+        .attr(attr!(
+            "class" => "wj-gallery-item " size.html_class(),
+        ))
+        .inner(|ctx| {
+            match &image_url {
+                Some(url) => {
+                    let build_image = |ctx: &mut HtmlContext| {
+                        ctx.html().img().attr(attr!(
+                            "class" => "wj-gallery-image",
+                            "src" => url,
+                            "alt" => caption.as_deref().unwrap_or(""),
+                            "loading" => "lazy",
+                            "crossorigin";;
+                            attributes
+                        ));
+                    };
+
+                    match &href {
+                        Some(href) => {
+                            ctx.html()
+                                .a()
+                                .attr(attr!(
+                                    "href" => href,
+                                    "rel" => rel.unwrap_or(""); if rel.is_some(),
+                                ))
+                                .inner(build_image);
+                        }
+                        None => build_image(ctx),
+                    }
+                }
+                None => {
+                    let message = ctx
+                        .handle()
+                        .get_message(ctx.language(), "image-context-bad");
+
+                    ctx.html()
+                        .div()
+                        .attr(attr!("class" => "wj-error-block"))
+                        .contents(message);
+                }
+            }
+
+            if let Some(caption) = caption {
+                ctx.html()
+                    .tag("figcaption")
+                    .attr(attr!("class" => "wj-gallery-caption"))
+                    .contents(caption);
+            }
+        });
+}
@@ -18,9 +18,11 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
+use super::link::link_kind;
 use super::prelude::*;
 use crate::tree::{AttributeMap, FloatAlignment, ImageSource, LinkLocation};
 use crate::url::normalize_link;
+use data_encoding::BASE64;
 
 pub fn render_image(
     ctx: &mut HtmlContext,
@@ -52,7 +54,7 @@ pub fn render_image(
 
     match source_url {
         // Found URL
-        Some(url) => render_image_element(ctx, &url, link, alignment, attributes),
+        Some(url) => render_image_element(ctx, source, &url, link, alignment, attributes),
 
         // Missing or error
         None => render_image_missing(ctx),
@@ -61,6 +63,7 @@ pub fn render_image(
 
 fn render_image_element(
     ctx: &mut HtmlContext,
+    source: &ImageSource,
     url: &str,
     link: &Option<LinkLocation>,
     alignment: Option<FloatAlignment>,
@@ -68,6 +71,16 @@ fn render_image_element(
 ) {
     debug!("Found URL, rendering image (value '{url}')");
 
+    let embedded_url = ctx.settings().embed_image_max_size.and_then(|max_size| {
+        let (mime, data) =
+            ctx.handle()
+                .get_image_data(source, ctx.info(), ctx.settings(), max_size)?;
+
+        Some(format!("data:{mime};base64,{}", BASE64.encode(data)))
+    });
+
+    let url = embedded_url.as_deref().unwrap_or(url);
+
     let (space, align_class) = match alignment {
         Some(align) => (" ", align.html_class()),
         None => ("", ""),
@@ -91,9 +104,15 @@ fn render_image_element(
             match link {
                 Some(link) => {
                     let url = normalize_link(link, ctx.handle());
+                    let kind = link_kind(link.link_type(), link, &url);
+                    let rel = ctx.add_link_metadata(kind, &url);
+
                     ctx.html()
                         .a()
-                        .attr(attr!("href" => &url))
+                        .attr(attr!(
+                            "href" => &url,
+                            "rel" => rel.unwrap_or(""); if rel.is_some(),
+                        ))
                         .inner(build_image);
                 }
                 None => build_image(ctx),
@@ -19,10 +19,17 @@
  */
 
 use super::prelude::*;
+use crate::data::LinkKind;
+use crate::settings::WikitextSettings;
 use crate::tree::{
-    AnchorTarget, AttributeMap, Element, LinkLabel, LinkLocation, LinkType,
+    heading_id, AnchorTarget, AttributeMap, Element, ImageSource, LinkLabel,
+    LinkLocation, LinkType,
 };
-use crate::url::normalize_link;
+use crate::url::{is_url, normalize_href, normalize_link};
+use std::borrow::Cow;
+
+/// The path prefix for files attached to pages, as in Wikidot.
+const LOCAL_FILES: &str = "local--files/";
 
 pub fn render_anchor(
     ctx: &mut HtmlContext,
@@ -37,11 +44,17 @@ pub fn render_anchor(
         None => "",
     };
 
+    let rel = match attributes.get().get("href") {
+        Some(href) => ctx.add_link_metadata(url_kind(href), href),
+        None => None,
+    };
+
     ctx.html()
         .a()
         .attr(attr!(
             "class" => "wj-anchor",
-            "target" => target_value; if target.is_some();;
+            "target" => target_value; if target.is_some(),
+            "rel" => rel.unwrap_or(""); if rel.is_some();;
             attributes,
         ))
         .contents(elements);
@@ -60,7 +73,14 @@ pub fn render_link(
     // Add to backlinks
     ctx.add_link(link);
 
-    let url = normalize_link(link, ctx.handle());
+    let url = match (ltype, link) {
+        (LinkType::TableOfContents, LinkLocation::Url(url)) => {
+            toc_link(ctx.settings(), url)
+        }
+        _ => normalize_link(link, ctx.handle()),
+    };
+
+    let rel = ctx.add_link_metadata(link_kind(ltype, link, &url), &url);
 
     let target_value = match target {
         Some(target) => target.html_attr(),
@@ -92,6 +112,7 @@ pub fn render_link(
     tag.attr(attr!(
         "href" => &url,
         "target" => target_value; if target.is_some(),
+        "rel" => rel.unwrap_or(""); if rel.is_some(),
         "class" => "wj-link " css_class interwiki_class,
         "data-link-type" => ltype.name(),
     ));
@@ -101,3 +122,118 @@ pub fn render_link(
         tag.contents(label);
     });
 }
+
+/// Points a table of contents entry at its heading.
+///
+/// Entries are numbered when parsing, but heading IDs
+/// depend on the render settings, so this is redone here.
+fn toc_link<'a>(settings: &WikitextSettings, url: &'a str) -> Cow<'a, str> {
+    match url
+        .strip_prefix("#toc")
+        .and_then(|index| index.parse().ok())
+    {
+        Some(index) => Cow::Owned(format!("#{}", heading_id(settings, index))),
+        None => normalize_href(url),
+    }
+}
+
+/// Classifies a link, given the URL it was normalized to.
+pub fn link_kind(ltype: LinkType, link: &LinkLocation, url: &str) -> LinkKind {
+    match (ltype, link) {
+        (LinkType::Interwiki, _) => LinkKind::Interwiki,
+        (_, LinkLocation::Page(page))
+            if page.site().is_none() && page.page().starts_with(LOCAL_FILES) =>
+        {
+            LinkKind::File
+        }
+        (_, LinkLocation::Page(_)) => LinkKind::Page,
+
+        // Relative URLs are normalized like page slugs, which
+        // mangles file paths, so check them as they were written.
+        (_, LinkLocation::Url(original)) if url_kind(original) == LinkKind::File => {
+            LinkKind::File
+        }
+        (_, LinkLocation::Url(_)) => url_kind(url),
+    }
+}
+
+/// Classifies a link from its `href` alone.
+///
+/// Links which are not full URLs are relative to the current site,
+/// so these are either files or pages.
+pub fn url_kind(url: &str) -> LinkKind {
+    if url.starts_with('#') || url == "javascript:;" {
+        LinkKind::Anchor
+    } else if url.starts_with('/') && url[1..].starts_with(LOCAL_FILES) {
+        LinkKind::File
+    } else if is_url(url) {
+        LinkKind::External
+    } else {
+        LinkKind::Page
+    }
+}
+
+/// Classifies a link to an image or file itself.
+pub fn source_kind(source: &ImageSource) -> LinkKind {
+    match source {
+        ImageSource::Url(url) => url_kind(url),
+        _ => LinkKind::File,
+    }
+}
+
+#[test]
+fn link_kinds() {
+    macro_rules! check {
+        ($ltype:expr, $link:expr, $url:expr, $kind:expr $(,)?) => {
+            assert_eq!(
+                link_kind($ltype, &$link, $url),
+                $kind,
+                "Link kind for {:?} doesn't match expected",
+                $url,
+            );
+        };
+    }
+
+    let page = LinkLocation::parse(cow!("scp-001"));
+    let url = |url: &'static str| LinkLocation::Url(cow!(url));
+
+    check!(LinkType::Page, page, "/scp-001", LinkKind::Page);
+    check!(
+        LinkType::Direct,
+        url("/scp-001"),
+        "/scp-001",
+        LinkKind::Page
+    );
+    check!(LinkType::Direct, url("#"), "#", LinkKind::Anchor);
+    check!(LinkType::Anchor, url("#top"), "#top", LinkKind::Anchor);
+    check!(
+        LinkType::Direct,
+        url("javascript:;"),
+        "javascript:;",
+        LinkKind::Anchor,
+    );
+    check!(
+        LinkType::Direct,
+        LinkLocation::parse(cow!("/local--files/start/map.pdf")),
+        "/local--files/start/map.pdf",
+        LinkKind::File,
+    );
+    check!(
+        LinkType::Direct,
+        url("/local--files/start/map.pdf"),
+        "/local--files/start/map.pdf",
+        LinkKind::File,
+    );
+    check!(
+        LinkType::Direct,
+        url("https://example.com/"),
+        "https://example.com/",
+        LinkKind::External,
+    );
+    check!(
+        LinkType::Interwiki,
+        url("https://en.wikipedia.org/wiki/Apple"),
+        "https://en.wikipedia.org/wiki/Apple",
+        LinkKind::Interwiki,
+    );
+}
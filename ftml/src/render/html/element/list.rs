@@ -19,7 +19,7 @@
  */
 
 use super::prelude::*;
-use crate::tree::{AttributeMap, ListItem, ListType};
+use crate::tree::{AttributeMap, ListItem, ListTask, ListType};
 
 pub fn render_list(
     ctx: &mut HtmlContext,
@@ -41,12 +41,18 @@ pub fn render_list(
                 ListItem::Elements {
                     elements,
                     attributes,
+                    task: None,
                 } => {
                     ctx.html()
                         .li()
                         .attr(attr!(;; attributes))
                         .contents(elements);
                 }
+                ListItem::Elements {
+                    elements,
+                    attributes,
+                    task: Some(task),
+                } => render_task_item(ctx, elements, attributes, *task),
                 ListItem::SubList { element } => {
                     render_element(ctx, element);
                 }
@@ -54,3 +60,34 @@ pub fn render_list(
         }
     });
 }
+
+fn render_task_item(
+    ctx: &mut HtmlContext,
+    elements: &[Element],
+    attributes: &AttributeMap,
+    ListTask { checked, offset }: ListTask,
+) {
+    info!("Rendering list task item (checked {checked}, offset {offset})");
+
+    // Toggling isn't supported yet, so the checkbox is read-only.
+    // The offset identifies the item for anything which adds it later.
+    let offset = offset.to_string();
+
+    ctx.html()
+        .li()
+        .attr(attr!(
+            "class" => "wj-task-item";;
+            attributes,
+        ))
+        .inner(|ctx| {
+            ctx.html().input().attr(attr!(
+                "class" => "wj-task-checkbox",
+                "type" => "checkbox",
+                "disabled",
+                "checked"; if checked,
+                "data-task-offset" => &offset,
+            ));
+
+            render_elements(ctx, elements);
+        });
+}
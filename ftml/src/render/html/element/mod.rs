@@ -23,10 +23,13 @@
 mod bibliography;
 mod collapsible;
 mod container;
+mod custom_block;
 mod date;
 mod definition_list;
 mod embed;
+mod file_card;
 mod footnotes;
+mod gallery;
 mod iframe;
 mod image;
 mod include;
@@ -34,6 +37,8 @@ mod input;
 mod link;
 mod list;
 mod math;
+mod module;
+mod spoiler;
 mod style;
 mod table;
 mod tabs;
@@ -52,10 +57,13 @@ mod prelude {
 use self::bibliography::{render_bibcite, render_bibliography};
 use self::collapsible::{render_collapsible, Collapsible};
 use self::container::{render_color, render_container};
+use self::custom_block::render_custom_block;
 use self::date::render_date;
 use self::definition_list::render_definition_list;
 use self::embed::render_embed;
+use self::file_card::render_file_card;
 use self::footnotes::{render_footnote, render_footnote_block};
+use self::gallery::render_gallery;
 use self::iframe::{render_html, render_iframe};
 use self::image::render_image;
 use self::include::{render_include, render_variable};
@@ -63,6 +71,8 @@ use self::input::{render_checkbox, render_radio_button};
 use self::link::{render_anchor, render_link};
 use self::list::render_list;
 use self::math::{render_equation_reference, render_math_block, render_math_inline};
+use self::module::render_module;
+use self::spoiler::render_spoiler;
 use self::style::render_style;
 use self::table::render_table;
 use self::tabs::render_tabview;
@@ -93,7 +103,8 @@ pub fn render_element(ctx: &mut HtmlContext, element: &Element) {
 
     match element {
         Element::Container(container) => render_container(ctx, container),
-        Element::Module(module) => ctx.handle().render_module(ctx.buffer(), module),
+        Element::Module(module) => render_module(ctx, module),
+        Element::CustomBlock(block) => render_custom_block(ctx, block),
         Element::Text(text) => ctx.push_escaped(text),
         Element::Raw(text) => render_wikitext_raw(ctx, text),
         Element::Variable(name) => render_variable(ctx, name),
@@ -120,6 +131,14 @@ pub fn render_element(ctx: &mut HtmlContext, element: &Element) {
             alignment,
             attributes,
         } => render_image(ctx, source, link, *alignment, attributes),
+        Element::Gallery {
+            images,
+            size,
+            attributes,
+        } => render_gallery(ctx, images, *size, attributes),
+        Element::FileCard { source, attributes } => {
+            render_file_card(ctx, source, attributes)
+        }
         Element::List {
             ltype,
             items,
@@ -155,6 +174,20 @@ pub fn render_element(ctx: &mut HtmlContext, element: &Element) {
                 *show_bottom,
             ),
         ),
+        Element::Spoiler {
+            elements,
+            attributes,
+            block,
+            show_text,
+            hide_text,
+        } => render_spoiler(
+            ctx,
+            elements,
+            attributes,
+            *block,
+            ref_cow!(show_text),
+            ref_cow!(hide_text),
+        ),
         Element::TableOfContents { align, attributes } => {
             render_table_of_contents(ctx, *align, attributes)
         }
@@ -0,0 +1,56 @@
+/*
+ * render/html/element/module.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::settings::ModuleState;
+use crate::tree::Module;
+
+pub fn render_module(ctx: &mut HtmlContext, module: &Module) {
+    info!("Rendering module '{}'", module.name());
+
+    match ctx.settings().module_state(module) {
+        ModuleState::Enabled => {
+            if ctx.settings().interactive || !module.is_interactive() {
+                ctx.handle().render_module(ctx.buffer(), module);
+            } else {
+                debug!("Skipping interactive module '{}'", module.name());
+            }
+        }
+        ModuleState::Placeholder => {
+            debug!(
+                "Module '{}' is disabled, rendering placeholder",
+                module.name()
+            );
+
+            let message = ctx.handle().get_message(ctx.language(), "module-disabled");
+
+            ctx.html()
+                .div()
+                .attr(attr!(
+                    "class" => "wj-module-disabled",
+                    "data-module" => module.name(),
+                ))
+                .contents(message);
+        }
+        ModuleState::Hidden => {
+            debug!("Module '{}' is disabled, skipping", module.name());
+        }
+    }
+}
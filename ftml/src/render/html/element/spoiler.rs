@@ -0,0 +1,146 @@
+/*
+ * render/html/element/spoiler.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::tree::AttributeMap;
+
+pub fn render_spoiler(
+    ctx: &mut HtmlContext,
+    elements: &[Element],
+    attributes: &AttributeMap,
+    block: bool,
+    show_text: Option<&str>,
+    hide_text: Option<&str>,
+) {
+    info!(
+        "Rendering spoiler (elements length {}, block {}, show-text {}, hide-text {})",
+        elements.len(),
+        block,
+        show_text.unwrap_or("<default>"),
+        hide_text.unwrap_or("<default>"),
+    );
+
+    // In static output, there is nothing to hover over or click,
+    // so render its contents in full instead.
+    if !ctx.settings().interactive {
+        let class = if block {
+            "wj-spoiler wj-spoiler-block wj-spoiler-expanded"
+        } else {
+            "wj-spoiler wj-spoiler-expanded"
+        };
+
+        let tag = if block { "div" } else { "span" };
+
+        ctx.html()
+            .tag(tag)
+            .attr(attr!(
+                "class" => class;;
+                attributes,
+            ))
+            .contents(elements);
+
+        return;
+    }
+
+    if block {
+        render_spoiler_block(ctx, elements, attributes, show_text, hide_text);
+    } else {
+        render_spoiler_inline(ctx, elements, attributes);
+    }
+}
+
+fn render_spoiler_inline(
+    ctx: &mut HtmlContext,
+    elements: &[Element],
+    attributes: &AttributeMap,
+) {
+    let label = ctx.handle().get_message(ctx.language(), "spoiler");
+
+    // Revealing is done in CSS on hover or focus,
+    // so this needs to be reachable by keyboard.
+    ctx.html()
+        .span()
+        .attr(attr!(
+            "class" => "wj-spoiler",
+            "tabindex" => "0";;
+            attributes,
+        ))
+        .inner(|ctx| {
+            // Visually hidden, for screen readers
+            ctx.html()
+                .span()
+                .attr(attr!("class" => "wj-spoiler-label"))
+                .inner(|ctx| {
+                    ctx.push_escaped(label);
+                    ctx.push_raw_str(": ");
+                });
+
+            ctx.html()
+                .span()
+                .attr(attr!("class" => "wj-spoiler-content"))
+                .contents(elements);
+        });
+}
+
+fn render_spoiler_block(
+    ctx: &mut HtmlContext,
+    elements: &[Element],
+    attributes: &AttributeMap,
+    show_text: Option<&str>,
+    hide_text: Option<&str>,
+) {
+    let show_text = show_text
+        .unwrap_or_else(|| ctx.handle().get_message(ctx.language(), "spoiler-show"));
+
+    let hide_text = hide_text
+        .unwrap_or_else(|| ctx.handle().get_message(ctx.language(), "spoiler-hide"));
+
+    ctx.html()
+        .details()
+        .attr(attr!(
+            "class" => "wj-spoiler wj-spoiler-block";;
+            attributes,
+        ))
+        .inner(|ctx| {
+            // Reveal/hide button
+            ctx.html()
+                .summary()
+                .attr(attr!("class" => "wj-spoiler-button"))
+                .inner(|ctx| {
+                    // Spoiler is hidden text
+                    ctx.html()
+                        .span()
+                        .attr(attr!("class" => "wj-spoiler-show-text"))
+                        .contents(show_text);
+
+                    // Spoiler is revealed text
+                    ctx.html()
+                        .span()
+                        .attr(attr!("class" => "wj-spoiler-hide-text"))
+                        .contents(hide_text);
+                });
+
+            // Content block
+            ctx.html()
+                .div()
+                .attr(attr!("class" => "wj-spoiler-content"))
+                .contents(elements);
+        });
+}
@@ -19,6 +19,7 @@
  */
 
 use super::prelude::*;
+use crate::data::LinkKind;
 
 pub fn render_user(ctx: &mut HtmlContext, name: &str, show_avatar: bool) {
     info!("Rendering user block (name '{name}', show-avatar {show_avatar})");
@@ -34,6 +35,8 @@ pub fn render_user(ctx: &mut HtmlContext, name: &str, show_avatar: bool) {
                     info.user_name.as_ref(),
                 );
 
+                ctx.add_link_metadata(LinkKind::User, &info.user_profile_url);
+
                 ctx.html()
                     .a()
                     .attr(attr!(
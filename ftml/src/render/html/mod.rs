@@ -25,6 +25,7 @@ mod test;
 mod attributes;
 mod builder;
 mod context;
+mod csp;
 mod element;
 mod escape;
 mod meta;
@@ -32,6 +33,7 @@ mod output;
 mod random;
 mod render;
 
+pub use self::csp::HtmlCsp;
 pub use self::meta::{HtmlMeta, HtmlMetaType};
 pub use self::output::HtmlOutput;
 
@@ -40,7 +42,7 @@ use super::prelude;
 
 use self::attributes::AddedAttributes;
 use self::context::HtmlContext;
-use crate::data::PageInfo;
+use crate::data::{PageInfo, RenderContext};
 use crate::render::{Handle, Render};
 use crate::settings::WikitextSettings;
 use crate::tree::SyntaxTree;
@@ -56,6 +58,21 @@ impl Render for HtmlRender {
         tree: &SyntaxTree,
         page_info: &PageInfo,
         settings: &WikitextSettings,
+    ) -> HtmlOutput {
+        self.render_with_context(tree, page_info, settings, &RenderContext::default())
+    }
+}
+
+impl HtmlRender {
+    /// Renders HTML, using information looked up ahead of time by the caller.
+    ///
+    /// Otherwise this is the same as `render()`.
+    pub fn render_with_context(
+        &self,
+        tree: &SyntaxTree,
+        page_info: &PageInfo,
+        settings: &WikitextSettings,
+        context: &RenderContext,
     ) -> HtmlOutput {
         info!(
             "Rendering HTML (site {}, page {}, category {})",
@@ -67,15 +84,8 @@ impl Render for HtmlRender {
             },
         );
 
-        let mut ctx = HtmlContext::new(
-            page_info,
-            &Handle,
-            settings,
-            &tree.table_of_contents,
-            &tree.footnotes,
-            &tree.bibliographies,
-            tree.wikitext_len,
-        );
+        let handle = Handle::new(context);
+        let mut ctx = HtmlContext::new(page_info, &handle, settings, tree);
 
         // Crawl through elements and generate HTML
         ctx.html()
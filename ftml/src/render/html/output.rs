@@ -18,12 +18,50 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
+use super::csp::HtmlCsp;
+use super::escape as html;
 use super::meta::HtmlMeta;
-use crate::data::Backlinks;
+use crate::data::{Backlinks, LinkMetadata};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct HtmlOutput {
     pub body: String,
     pub meta: Vec<HtmlMeta>,
     pub backlinks: Backlinks<'static>,
+    pub links: Vec<LinkMetadata<'static>>,
+    pub csp: HtmlCsp,
+}
+
+impl HtmlOutput {
+    /// Wraps the rendered body into a complete, standalone HTML document.
+    ///
+    /// This is intended for printing or archiving, and is best paired with
+    /// `WikitextMode::Print`. Any stylesheet to apply must be passed in,
+    /// since the document does not link to any external resources.
+    pub fn to_document(&self, title: &str, language: &str, stylesheet: &str) -> String {
+        let mut buffer = String::new();
+        buffer.push_str("<!DOCTYPE html><html lang=\"");
+        html::escape(&mut buffer, language);
+        buffer.push_str("\"><head><meta charset=\"utf-8\" />");
+
+        for meta in &self.meta {
+            meta.render(&mut buffer);
+        }
+
+        buffer.push_str("<title>");
+        html::escape(&mut buffer, title);
+        buffer.push_str("</title>");
+
+        if !stylesheet.is_empty() {
+            // Prevent the stylesheet from closing its own tag.
+            buffer.push_str("<style>");
+            buffer.push_str(&stylesheet.replace("</", "<\\/"));
+            buffer.push_str("</style>");
+        }
+
+        buffer.push_str("</head><body>");
+        buffer.push_str(&self.body);
+        buffer.push_str("</body></html>");
+        buffer
+    }
 }
@@ -20,7 +20,10 @@
 
 use super::prelude::*;
 use super::HtmlRender;
+use crate::data::{AttachedFile, FileInfo, FileReference, LinkKind, RenderContext};
+use crate::settings::{ExternalLinkRel, HeadingIdStrategy};
 use crate::tree::BibliographyList;
+use std::borrow::Cow;
 
 #[test]
 fn html() {
@@ -31,9 +34,178 @@ fn html() {
         vec![],
         vec![],
         vec![],
+        vec![],
         BibliographyList::new(),
         0,
     );
     let (tree, _) = result.into();
     let _output = HtmlRender.render(&tree, &page_info, &settings);
 }
+
+#[test]
+fn csp_styles() {
+    let page_info = PageInfo::dummy();
+    let settings = WikitextSettings::from_mode(WikitextMode::Page);
+    let mut text = str!("[[module CSS]]\n.test { color: red; }\n[[/module]]");
+    crate::preprocess(&mut text);
+
+    let tokens = crate::tokenize(&text);
+    let result = crate::parse(&tokens, &page_info, &settings);
+    let (tree, _) = result.into();
+
+    let output = HtmlRender.render(&tree, &page_info, &settings);
+
+    assert!(output.body.contains("<style>"));
+    assert_eq!(output.csp.style_digests.len(), 1);
+    assert!(output.csp.style_digests[0].starts_with("'sha256-"));
+    assert_eq!(output.csp.style_sources(), output.csp.style_digests);
+}
+
+#[test]
+fn csp_style_attributes() {
+    use sha2::{Digest, Sha256};
+
+    let page_info = PageInfo::dummy();
+    let settings = WikitextSettings::from_mode(WikitextMode::Page);
+    let span = r#"[[span style="color: blue;"]]text[[/span]]"#;
+    let mut text = format!("{span} {span}");
+    crate::preprocess(&mut text);
+
+    let tokens = crate::tokenize(&text);
+    let result = crate::parse(&tokens, &page_info, &settings);
+    let (tree, _) = result.into();
+
+    let output = HtmlRender.render(&tree, &page_info, &settings);
+
+    // Repeated styles are only listed once
+    let digest = format!(
+        "'sha256-{}'",
+        data_encoding::BASE64.encode(&Sha256::digest(b"color: blue;")),
+    );
+
+    assert!(output.csp.style_digests.is_empty());
+    assert_eq!(
+        output.csp.style_attribute_digests,
+        std::slice::from_ref(&digest)
+    );
+    assert_eq!(
+        output.csp.style_sources(),
+        [str!("'unsafe-hashes'"), digest],
+    );
+
+    // Without any style attributes, 'unsafe-hashes' isn't needed
+    let output = HtmlRender.render(&SyntaxTree::default(), &page_info, &settings);
+    assert!(output.csp.style_sources().is_empty());
+}
+
+#[test]
+fn heading_ids() {
+    let page_info = PageInfo::dummy();
+    let parse_settings = WikitextSettings::from_mode(WikitextMode::Page);
+    let mut settings = parse_settings.clone();
+    let mut text = str!("[[toc]]\n\n+ Apple\n\n++ Banana");
+    crate::preprocess(&mut text);
+
+    let tokens = crate::tokenize(&text);
+    let result = crate::parse(&tokens, &page_info, &parse_settings);
+    let (tree, _) = result.into();
+
+    let output = HtmlRender.render(&tree, &page_info, &settings);
+    assert!(output.body.contains("<h1 id=\"toc0\">"));
+    assert!(output.body.contains("href=\"#toc1\""));
+
+    settings.heading_id_prefix = Some(str!("inc-"));
+    let output = HtmlRender.render(&tree, &page_info, &settings);
+    assert!(output.body.contains("<h1 id=\"inc-toc0\">"));
+    assert!(output.body.contains("<h2 id=\"inc-toc1\">"));
+    assert!(output.body.contains("href=\"#inc-toc0\""));
+    assert!(output.body.contains("href=\"#inc-toc1\""));
+
+    settings.heading_id_strategy = HeadingIdStrategy::Hash;
+    let output = HtmlRender.render(&tree, &page_info, &settings);
+    let id = crate::tree::heading_id(&settings, 1);
+    assert!(output.body.contains(&format!("<h2 id=\"{id}\">")));
+    assert!(output.body.contains(&format!("href=\"#{id}\"")));
+}
+
+#[test]
+fn link_metadata() {
+    let page_info = PageInfo::dummy();
+    let parse_settings = WikitextSettings::from_mode(WikitextMode::Page);
+    let mut settings = parse_settings.clone();
+    let mut text = str!(
+        "[[[scp-001]]] [https://example.com/ Example] [#top Top] [/local--files/start/map.pdf Map]"
+    );
+    crate::preprocess(&mut text);
+
+    let tokens = crate::tokenize(&text);
+    let result = crate::parse(&tokens, &page_info, &parse_settings);
+    let (tree, _) = result.into();
+
+    let output = HtmlRender.render(&tree, &page_info, &settings);
+    let kinds: Vec<_> = output.links.iter().map(|link| link.kind).collect();
+    assert_eq!(
+        kinds,
+        [
+            LinkKind::Page,
+            LinkKind::External,
+            LinkKind::Anchor,
+            LinkKind::File,
+        ],
+    );
+    assert_eq!(output.links[0].target, "/scp-001");
+    assert_eq!(output.links[1].target, "https://example.com/");
+    assert!(output.links.iter().all(|link| link.rel.is_none()));
+    assert!(!output.body.contains("rel="));
+
+    settings.external_link_rel = ExternalLinkRel {
+        nofollow: true,
+        ugc: true,
+    };
+    let output = HtmlRender.render(&tree, &page_info, &settings);
+    assert_eq!(output.links[1].rel.as_deref(), Some("nofollow ugc"));
+    assert!(output.body.contains("rel=\"nofollow ugc\""));
+    assert_eq!(output.body.matches("rel=").count(), 1);
+}
+
+#[test]
+fn embed_image() {
+    let page_info = PageInfo::dummy();
+    let mut settings = WikitextSettings::from_mode(WikitextMode::Print);
+    settings.embed_image_max_size = Some(16);
+
+    let mut text = str!("[[image small.png]] [[image large.png]] [[image missing.png]]");
+    crate::preprocess(&mut text);
+
+    let tokens = crate::tokenize(&text);
+    let result = crate::parse(&tokens, &page_info, &settings);
+    let (tree, _) = result.into();
+
+    let file = |name: &'static str, data: &[u8]| AttachedFile {
+        reference: FileReference {
+            site: cow!("sandbox"),
+            page: cow!("some-page"),
+            file: cow!(name),
+        },
+        info: FileInfo {
+            file_name: cow!(name),
+            file_mime: cow!("image/png"),
+            file_url: Cow::Owned(format!("/cdn/{name}")),
+            ..FileInfo::dummy()
+        },
+        derivatives: vec![],
+        data: Some(data.to_vec()),
+    };
+
+    let context = RenderContext {
+        files: vec![file("small.png", b"small"), file("large.png", &[0; 64])],
+    };
+
+    let output = HtmlRender.render_with_context(&tree, &page_info, &settings, &context);
+
+    assert!(output
+        .body
+        .contains(r#"src="data:image/png;base64,c21hbGw=""#));
+    assert!(output.body.contains(r#"src="/cdn/large.png""#));
+    assert!(output.body.contains("missing.png"));
+}
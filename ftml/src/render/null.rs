@@ -52,6 +52,7 @@ fn null() {
         vec![],
         vec![],
         vec![],
+        vec![],
         BibliographyList::new(),
         0,
     );
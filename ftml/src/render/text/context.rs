@@ -33,7 +33,7 @@ where
 {
     output: String,
     info: &'i PageInfo<'i>,
-    handle: &'h Handle,
+    handle: &'h Handle<'h>,
     settings: &'e WikitextSettings,
 
     //
@@ -76,7 +76,7 @@ where
     #[inline]
     pub fn new(
         info: &'i PageInfo<'i>,
-        handle: &'h Handle,
+        handle: &'h Handle<'h>,
         settings: &'e WikitextSettings,
         table_of_contents: &'e [Element<'t>],
         footnotes: &'e [Vec<Element<'t>>],
@@ -122,7 +122,7 @@ where
     }
 
     #[inline]
-    pub fn handle(&self) -> &'h Handle {
+    pub fn handle(&self) -> &'h Handle<'h> {
         self.handle
     }
 
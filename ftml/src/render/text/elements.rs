@@ -28,7 +28,9 @@
 //! Any formatting present must be directly justifiable.
 
 use super::TextContext;
-use crate::tree::{ContainerType, DefinitionListItem, Element, ListItem, Tab};
+use crate::tree::{
+    ContainerType, DefinitionListItem, Element, GalleryImage, ImageSource, ListItem, Tab,
+};
 
 pub fn render_elements(ctx: &mut TextContext, elements: &[Element]) {
     info!("Rendering elements (length {})", elements.len());
@@ -62,6 +64,7 @@ pub fn render_element(ctx: &mut TextContext, element: &Element) {
                 ContainerType::Div
                 | ContainerType::Paragraph
                 | ContainerType::Blockquote
+                | ContainerType::Cite
                 | ContainerType::Header(_) => true,
 
                 // Wrap any ruby text with parentheses
@@ -97,6 +100,10 @@ pub fn render_element(ctx: &mut TextContext, element: &Element) {
         Element::Module(_) => {
             // We don't want to render modules at all
         }
+        Element::CustomBlock(block) => {
+            // Templates are HTML-only, so just the body is rendered
+            render_elements(ctx, &block.elements);
+        }
         Element::Text(text) | Element::Raw(text) | Element::Email(text) => {
             ctx.push_str(text);
         }
@@ -155,6 +162,44 @@ pub fn render_element(ctx: &mut TextContext, element: &Element) {
         Element::Image { .. } => {
             // Text cannot render images, so we don't add anything
         }
+        Element::Gallery { images, .. } => {
+            // Text cannot render images, so list their captions instead
+            if !ctx.ends_with_newline() {
+                ctx.add_newline();
+            }
+
+            for GalleryImage {
+                source, caption, ..
+            } in images
+            {
+                let label = match (caption, source) {
+                    (Some(caption), _) => caption,
+                    (None, ImageSource::Url(url)) => url,
+                    (None, ImageSource::File1 { file })
+                    | (None, ImageSource::File2 { file, .. })
+                    | (None, ImageSource::File3 { file, .. }) => file,
+                };
+
+                ctx.push_str(label);
+                ctx.add_newline();
+            }
+        }
+        Element::FileCard { source, .. } => {
+            // Only show the name of the file, its details need a visual layout
+            let file_name = match source {
+                ImageSource::Url(url) => url,
+                ImageSource::File1 { file }
+                | ImageSource::File2 { file, .. }
+                | ImageSource::File3 { file, .. } => file,
+            };
+
+            if !ctx.ends_with_newline() {
+                ctx.add_newline();
+            }
+
+            ctx.push_str(file_name);
+            ctx.add_newline();
+        }
         Element::List { items, .. } => {
             if !ctx.ends_with_newline() {
                 ctx.add_newline();
@@ -163,12 +208,17 @@ pub fn render_element(ctx: &mut TextContext, element: &Element) {
             for item in items {
                 match item {
                     ListItem::SubList { element } => render_element(ctx, element),
-                    ListItem::Elements { elements, .. } => {
+                    ListItem::Elements { elements, task, .. } => {
                         // Don't do anything if it's empty
                         if elements.is_empty() {
                             continue;
                         }
 
+                        // Show the state of task items
+                        if let Some(task) = task {
+                            ctx.push_str(if task.checked { "[x] " } else { "[ ] " });
+                        }
+
                         // Render elements for this list item
                         render_elements(ctx, elements);
                         ctx.add_newline();
@@ -200,6 +250,21 @@ pub fn render_element(ctx: &mut TextContext, element: &Element) {
 
             render_elements(ctx, elements);
         }
+        Element::Spoiler { block, .. } => {
+            // Spoilers are not revealed in text mode,
+            // only a placeholder noting one was here.
+
+            let placeholder =
+                ctx.handle().get_message(ctx.language(), "spoiler-redacted");
+
+            if *block {
+                ctx.add_newline();
+                ctx.push_str(placeholder);
+                ctx.add_newline();
+            } else {
+                ctx.push_str(placeholder);
+            }
+        }
         Element::TableOfContents { .. } => {
             // Doesn't make sense to have a textual table of contents, skip
         }
@@ -217,14 +282,14 @@ pub fn render_element(ctx: &mut TextContext, element: &Element) {
                 warn!("Time format passed, feature currently not supported!");
             }
 
-            // TODO handle error
-            match value.format() {
-                Ok(datetime) => str_write!(ctx, "{}", datetime),
-                Err(error) => {
-                    error!("Error formatting date into string: {error}");
-                    str_write!(ctx, "<ERROR>");
-                }
+            // Display in the viewer's timezone, if known
+            let value = match ctx.info().utc_offset {
+                Some(offset) => value.to_offset(offset),
+                None => *value,
             };
+
+            let datetime = ctx.handle().format_date(ctx.language(), value);
+            ctx.push_str(&datetime);
         }
         Element::Color { elements, .. } => render_elements(ctx, elements),
         Element::Code { contents, .. } => {
@@ -23,7 +23,7 @@ mod elements;
 
 use self::context::TextContext;
 use self::elements::render_elements;
-use crate::data::PageInfo;
+use crate::data::{PageInfo, RenderContext};
 use crate::render::{Handle, Render};
 use crate::settings::WikitextSettings;
 use crate::tree::{BibliographyList, Element, SyntaxTree};
@@ -73,9 +73,11 @@ impl TextRender {
             },
         );
 
+        let context = RenderContext::default();
+        let handle = Handle::new(&context);
         let mut ctx = TextContext::new(
             page_info,
-            &Handle,
+            &handle,
             settings,
             table_of_contents,
             footnotes,
@@ -0,0 +1,668 @@
+/*
+ * settings/custom_block.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Blocks defined at runtime, such as by a site, rather than built into the parser.
+//!
+//! A custom block is invoked like any other, for instance
+//! `[[weather city="Oslo"]]`, and is rendered through a template.
+//!
+//! Templates are a restricted form of HTML, so that a site can't
+//! use them to run scripts or otherwise escape the usual sanitization.
+//! Only the tags in [`TEMPLATE_TAGS`] and attributes which are safe
+//! in wikitext are permitted, and `{{name}}` placeholders are
+//! substituted with the escaped argument values, or with the
+//! rendered body for `{{body}}`.
+
+use crate::parsing::is_builtin_block;
+use crate::tree::attribute::is_safe_attribute;
+use std::mem;
+use strum_macros::IntoStaticStr;
+use unicase::UniCase;
+
+/// Which HTML tags may appear in a custom block template.
+pub const TEMPLATE_TAGS: [&str; 40] = [
+    "a",
+    "abbr",
+    "b",
+    "blockquote",
+    "br",
+    "caption",
+    "code",
+    "dd",
+    "div",
+    "dl",
+    "dt",
+    "em",
+    "figcaption",
+    "figure",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "hr",
+    "i",
+    "img",
+    "li",
+    "mark",
+    "ol",
+    "p",
+    "pre",
+    "s",
+    "small",
+    "span",
+    "strong",
+    "sub",
+    "sup",
+    "table",
+    "tbody",
+    "td",
+    "th",
+    "thead",
+    "tr",
+];
+
+/// Which template tags have no contents or closing tag.
+const VOID_TAGS: [&str; 3] = ["br", "hr", "img"];
+
+/// The placeholder name which is replaced with the block's body.
+pub const BODY_PLACEHOLDER: &str = "body";
+
+/// The set of custom blocks available when parsing.
+///
+/// Names are matched case-insensitively, like built-in blocks.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+#[serde(transparent)]
+pub struct CustomBlockSettings {
+    blocks: Vec<CustomBlockDefinition>,
+}
+
+impl CustomBlockSettings {
+    #[inline]
+    pub fn new() -> Self {
+        CustomBlockSettings::default()
+    }
+
+    /// Adds a custom block, after checking that its definition is valid.
+    ///
+    /// Names may not be reused, including those of built-in blocks.
+    pub fn register(
+        &mut self,
+        definition: CustomBlockDefinition,
+    ) -> Result<(), CustomBlockError> {
+        definition.validate()?;
+
+        if is_builtin_block(&definition.name) {
+            return Err(CustomBlockError::BuiltinName);
+        }
+
+        if self.get(&definition.name).is_some() {
+            return Err(CustomBlockError::AlreadyRegistered);
+        }
+
+        self.blocks.push(definition);
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&CustomBlockDefinition> {
+        self.blocks
+            .iter()
+            .find(|definition| definition.name.eq_ignore_ascii_case(name))
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = &CustomBlockDefinition> {
+        self.blocks.iter()
+    }
+}
+
+/// The definition of a single custom block.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct CustomBlockDefinition {
+    /// The name the block is invoked with, such as `weather`.
+    ///
+    /// This must be lowercase ASCII letters, digits, and dashes.
+    pub name: String,
+
+    /// Which arguments the block accepts.
+    ///
+    /// Any others passed in are ignored.
+    #[serde(default)]
+    pub arguments: Vec<CustomBlockArgument>,
+
+    /// Whether the block has a body, and how it is parsed.
+    #[serde(default)]
+    pub body: CustomBlockBody,
+
+    /// The HTML template used to render the block.
+    pub template: String,
+}
+
+impl CustomBlockDefinition {
+    /// Checks the block's name, arguments, and template.
+    pub fn validate(&self) -> Result<(), CustomBlockError> {
+        if !valid_name(&self.name) {
+            return Err(CustomBlockError::InvalidName);
+        }
+
+        for (index, argument) in self.arguments.iter().enumerate() {
+            if !valid_name(&argument.name) || argument.name == BODY_PLACEHOLDER {
+                return Err(CustomBlockError::InvalidArgument);
+            }
+
+            if self.arguments[..index]
+                .iter()
+                .any(|other| other.name == argument.name)
+            {
+                return Err(CustomBlockError::DuplicateArgument);
+            }
+        }
+
+        self.template()?;
+        Ok(())
+    }
+
+    /// Parses this block's template.
+    pub fn template(&self) -> Result<Vec<TemplateNode>, CustomBlockError> {
+        TemplateCompiler {
+            definition: self,
+            remaining: &self.template,
+            nodes: Vec::new(),
+            open_tags: Vec::new(),
+        }
+        .compile()
+    }
+
+    #[inline]
+    pub fn argument(&self, name: &str) -> Option<&CustomBlockArgument> {
+        self.arguments
+            .iter()
+            .find(|argument| argument.name.eq_ignore_ascii_case(name))
+    }
+}
+
+/// An argument accepted by a custom block.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct CustomBlockArgument {
+    pub name: String,
+
+    /// Whether the block fails to parse if this argument is absent.
+    #[serde(default)]
+    pub required: bool,
+}
+
+/// What a custom block contains between its head and tail.
+#[derive(Serialize, Deserialize, Debug, Default, Copy, Clone, Hash, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum CustomBlockBody {
+    /// The block has no body or tail, such as `[[weather]]`.
+    #[default]
+    None,
+
+    /// The body is kept as plain text, like `[[code]]`.
+    Text,
+
+    /// The body is parsed as wikitext, like `[[div]]`.
+    Elements,
+}
+
+/// A piece of a parsed template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplateNode {
+    /// Literal text, which is output as written.
+    ///
+    /// It cannot contain tags, but may contain entities such as `&lt;`.
+    Text(String),
+
+    /// The escaped value of the given argument, or empty if it wasn't passed in.
+    Argument(String),
+
+    /// The block's body.
+    Body,
+
+    /// An HTML element, with its attributes and contents.
+    Element {
+        tag: &'static str,
+        attributes: Vec<(String, Vec<TemplateValue>)>,
+        children: Vec<TemplateNode>,
+    },
+}
+
+/// A piece of an attribute value in a template.
+///
+/// Unlike template text, the value is escaped when rendered,
+/// and is sanitized like any other attribute passed in wikitext.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplateValue {
+    Text(String),
+    Argument(String),
+}
+
+/// The reasons a custom block may be rejected.
+#[derive(
+    Serialize, Deserialize, IntoStaticStr, Debug, Copy, Clone, Hash, PartialEq, Eq,
+)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
+pub enum CustomBlockError {
+    /// The block's name isn't lowercase letters, digits, and dashes.
+    InvalidName,
+
+    /// The block's name is used by a built-in block.
+    BuiltinName,
+
+    /// A custom block with this name already exists.
+    AlreadyRegistered,
+
+    /// An argument name isn't valid, or is `body`.
+    InvalidArgument,
+
+    /// An argument is declared more than once.
+    DuplicateArgument,
+
+    /// The template isn't well-formed, such as an unquoted attribute.
+    TemplateMalformed,
+
+    /// The template uses a tag which isn't in [`TEMPLATE_TAGS`].
+    TemplateDisallowedTag,
+
+    /// The template uses an attribute which isn't safe.
+    TemplateDisallowedAttribute,
+
+    /// The template's tags aren't closed, or aren't closed in order.
+    TemplateUnbalanced,
+
+    /// The template has a placeholder which isn't a declared argument.
+    TemplateUnknownPlaceholder,
+}
+
+impl CustomBlockError {
+    #[inline]
+    pub fn name(self) -> &'static str {
+        self.into()
+    }
+}
+
+fn valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('-')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
+
+struct TemplateCompiler<'d, 's> {
+    definition: &'d CustomBlockDefinition,
+    remaining: &'s str,
+    nodes: Vec<TemplateNode>,
+    open_tags: Vec<OpenTag>,
+}
+
+/// An element whose closing tag hasn't been reached yet.
+struct OpenTag {
+    tag: &'static str,
+    attributes: Vec<(String, Vec<TemplateValue>)>,
+
+    /// The nodes preceding this element, which it will be appended to.
+    siblings: Vec<TemplateNode>,
+}
+
+impl TemplateCompiler<'_, '_> {
+    fn compile(mut self) -> Result<Vec<TemplateNode>, CustomBlockError> {
+        while !self.remaining.is_empty() {
+            if self.remaining.starts_with("{{") {
+                let name = self.placeholder()?;
+                let node = if name == BODY_PLACEHOLDER {
+                    TemplateNode::Body
+                } else {
+                    TemplateNode::Argument(name)
+                };
+
+                self.nodes.push(node);
+            } else if self.remaining.starts_with("</") {
+                self.close_tag()?;
+            } else if self.remaining.starts_with('<') {
+                self.open_tag()?;
+            } else {
+                let end = self
+                    .remaining
+                    .find(['<', '{'])
+                    .map(|index| index.max(1))
+                    .unwrap_or(self.remaining.len());
+
+                let (text, rest) = self.remaining.split_at(end);
+                self.push_text(text);
+                self.remaining = rest;
+            }
+        }
+
+        if !self.open_tags.is_empty() {
+            return Err(CustomBlockError::TemplateUnbalanced);
+        }
+
+        Ok(self.nodes)
+    }
+
+    fn push_text(&mut self, text: &str) {
+        match self.nodes.last_mut() {
+            Some(TemplateNode::Text(previous)) => previous.push_str(text),
+            _ => self.nodes.push(TemplateNode::Text(str!(text))),
+        }
+    }
+
+    /// Consumes a `{{name}}` placeholder, returning the name.
+    fn placeholder(&mut self) -> Result<String, CustomBlockError> {
+        let rest = &self.remaining[2..];
+        let end = rest.find("}}").ok_or(CustomBlockError::TemplateMalformed)?;
+        let name = rest[..end].trim();
+
+        if name != BODY_PLACEHOLDER && self.definition.argument(name).is_none() {
+            return Err(CustomBlockError::TemplateUnknownPlaceholder);
+        }
+
+        self.remaining = &rest[end + 2..];
+        Ok(name.to_ascii_lowercase())
+    }
+
+    fn tag_name(&mut self) -> Result<&'static str, CustomBlockError> {
+        let end = self
+            .remaining
+            .find(|c: char| !c.is_ascii_alphanumeric())
+            .unwrap_or(self.remaining.len());
+
+        let (name, rest) = self.remaining.split_at(end);
+        let tag = TEMPLATE_TAGS
+            .iter()
+            .find(|tag| tag.eq_ignore_ascii_case(name))
+            .ok_or(CustomBlockError::TemplateDisallowedTag)?;
+
+        self.remaining = rest;
+        Ok(tag)
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.remaining = self.remaining.trim_start();
+    }
+
+    fn open_tag(&mut self) -> Result<(), CustomBlockError> {
+        self.remaining = &self.remaining[1..];
+        let tag = self.tag_name()?;
+        let mut attributes = Vec::new();
+
+        let self_closing = loop {
+            self.skip_whitespace();
+
+            if let Some(rest) = self.remaining.strip_prefix("/>") {
+                self.remaining = rest;
+                break true;
+            }
+
+            if let Some(rest) = self.remaining.strip_prefix('>') {
+                self.remaining = rest;
+                break false;
+            }
+
+            attributes.push(self.attribute()?);
+        };
+
+        if VOID_TAGS.contains(&tag) {
+            self.nodes.push(TemplateNode::Element {
+                tag,
+                attributes,
+                children: Vec::new(),
+            });
+        } else if self_closing {
+            return Err(CustomBlockError::TemplateMalformed);
+        } else {
+            let siblings = mem::take(&mut self.nodes);
+            self.open_tags.push(OpenTag {
+                tag,
+                attributes,
+                siblings,
+            });
+        }
+
+        Ok(())
+    }
+
+    fn attribute(&mut self) -> Result<(String, Vec<TemplateValue>), CustomBlockError> {
+        let end = self
+            .remaining
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-'))
+            .unwrap_or(self.remaining.len());
+
+        let (key, rest) = self.remaining.split_at(end);
+        if key.is_empty() {
+            return Err(CustomBlockError::TemplateMalformed);
+        }
+
+        if !is_safe_attribute(UniCase::ascii(key)) {
+            return Err(CustomBlockError::TemplateDisallowedAttribute);
+        }
+
+        let key = key.to_ascii_lowercase();
+        let rest = rest.trim_start();
+
+        // Boolean attribute, such as "hidden"
+        let rest = match rest.strip_prefix('=') {
+            Some(rest) => rest.trim_start(),
+            None => {
+                self.remaining = rest;
+                return Ok((key, Vec::new()));
+            }
+        };
+
+        let rest = rest
+            .strip_prefix('"')
+            .ok_or(CustomBlockError::TemplateMalformed)?;
+        let end = rest.find('"').ok_or(CustomBlockError::TemplateMalformed)?;
+        let (mut raw_value, rest) = (&rest[..end], &rest[end + 1..]);
+        self.remaining = rest;
+
+        let mut value = Vec::new();
+        while !raw_value.is_empty() {
+            match raw_value.find("{{") {
+                Some(0) => {
+                    let inner = &raw_value[2..];
+                    let end = inner
+                        .find("}}")
+                        .ok_or(CustomBlockError::TemplateMalformed)?;
+                    let name = inner[..end].trim();
+
+                    if self.definition.argument(name).is_none() {
+                        return Err(CustomBlockError::TemplateUnknownPlaceholder);
+                    }
+
+                    value.push(TemplateValue::Argument(name.to_ascii_lowercase()));
+                    raw_value = &inner[end + 2..];
+                }
+                Some(index) => {
+                    value.push(TemplateValue::Text(str!(&raw_value[..index])));
+                    raw_value = &raw_value[index..];
+                }
+                None => {
+                    value.push(TemplateValue::Text(str!(raw_value)));
+                    break;
+                }
+            }
+        }
+
+        Ok((key, value))
+    }
+
+    fn close_tag(&mut self) -> Result<(), CustomBlockError> {
+        self.remaining = &self.remaining[2..];
+        let tag = self.tag_name()?;
+
+        self.skip_whitespace();
+        self.remaining = self
+            .remaining
+            .strip_prefix('>')
+            .ok_or(CustomBlockError::TemplateMalformed)?;
+
+        let open_tag = match self.open_tags.pop() {
+            Some(open_tag) if open_tag.tag == tag => open_tag,
+            _ => return Err(CustomBlockError::TemplateUnbalanced),
+        };
+
+        let children = mem::replace(&mut self.nodes, open_tag.siblings);
+        self.nodes.push(TemplateNode::Element {
+            tag,
+            attributes: open_tag.attributes,
+            children,
+        });
+
+        Ok(())
+    }
+}
+
+#[test]
+fn custom_block_templates() {
+    fn definition(template: &str) -> CustomBlockDefinition {
+        CustomBlockDefinition {
+            name: str!("weather"),
+            arguments: vec![
+                CustomBlockArgument {
+                    name: str!("city"),
+                    required: true,
+                },
+                CustomBlockArgument {
+                    name: str!("unit"),
+                    required: false,
+                },
+            ],
+            body: CustomBlockBody::Elements,
+            template: str!(template),
+        }
+    }
+
+    macro_rules! check {
+        ($template:expr, $expected:expr $(,)?) => {{
+            let actual = definition($template).template();
+            let expected: Result<Vec<TemplateNode>, CustomBlockError> = $expected;
+
+            assert_eq!(
+                actual, expected,
+                "Actual template compilation doesn't match expected",
+            );
+        }};
+    }
+
+    check!(
+        "Weather in {{city}}",
+        Ok(vec![
+            TemplateNode::Text(str!("Weather in ")),
+            TemplateNode::Argument(str!("city")),
+        ]),
+    );
+    check!(
+        r#"<div class="weather {{unit}}" hidden><b>{{ body }}</b></div><br/>"#,
+        Ok(vec![
+            TemplateNode::Element {
+                tag: "div",
+                attributes: vec![
+                    (
+                        str!("class"),
+                        vec![
+                            TemplateValue::Text(str!("weather ")),
+                            TemplateValue::Argument(str!("unit")),
+                        ],
+                    ),
+                    (str!("hidden"), vec![]),
+                ],
+                children: vec![TemplateNode::Element {
+                    tag: "b",
+                    attributes: vec![],
+                    children: vec![TemplateNode::Body],
+                }],
+            },
+            TemplateNode::Element {
+                tag: "br",
+                attributes: vec![],
+                children: vec![],
+            },
+        ]),
+    );
+    check!(
+        "{ not a placeholder }",
+        Ok(vec![TemplateNode::Text(str!("{ not a placeholder }"))]),
+    );
+    check!(
+        "<script>alert(1)</script>",
+        Err(CustomBlockError::TemplateDisallowedTag),
+    );
+    check!(
+        r#"<div onclick="alert(1)"></div>"#,
+        Err(CustomBlockError::TemplateDisallowedAttribute),
+    );
+    check!(
+        "<div><span></div></span>",
+        Err(CustomBlockError::TemplateUnbalanced)
+    );
+    check!("<div>", Err(CustomBlockError::TemplateUnbalanced));
+    check!(
+        "<div class=weather></div>",
+        Err(CustomBlockError::TemplateMalformed)
+    );
+    check!(
+        "{{country}}",
+        Err(CustomBlockError::TemplateUnknownPlaceholder)
+    );
+    check!("{{city", Err(CustomBlockError::TemplateMalformed));
+}
+
+#[test]
+fn custom_block_registration() {
+    fn definition(name: &str) -> CustomBlockDefinition {
+        CustomBlockDefinition {
+            name: str!(name),
+            arguments: vec![],
+            body: CustomBlockBody::None,
+            template: str!("Block"),
+        }
+    }
+
+    let mut settings = CustomBlockSettings::new();
+
+    assert_eq!(settings.register(definition("weather")), Ok(()));
+    assert_eq!(
+        settings.register(definition("weather")),
+        Err(CustomBlockError::AlreadyRegistered),
+    );
+    assert_eq!(
+        settings.register(definition("div")),
+        Err(CustomBlockError::BuiltinName),
+    );
+    assert_eq!(
+        settings.register(definition("Character Sheet")),
+        Err(CustomBlockError::InvalidName),
+    );
+    assert_eq!(settings.register(definition("character-sheet")), Ok(()));
+    assert!(settings.get("WEATHER").is_some());
+    assert!(settings.get("forecast").is_none());
+}
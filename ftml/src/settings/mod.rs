@@ -18,10 +18,17 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
+mod custom_block;
 mod interwiki;
 
+pub use self::custom_block::{
+    CustomBlockArgument, CustomBlockBody, CustomBlockDefinition, CustomBlockError,
+    CustomBlockSettings, TemplateNode, TemplateValue, BODY_PLACEHOLDER, TEMPLATE_TAGS,
+};
 pub use self::interwiki::{InterwikiSettings, DEFAULT_INTERWIKI, EMPTY_INTERWIKI};
 
+use crate::tree::Module;
+
 const DEFAULT_MINIFY_CSS: bool = true;
 
 /// Settings to tweak behavior in the ftml parser and renderer.
@@ -48,6 +55,12 @@ pub struct WikitextSettings {
     /// It is off by default.
     pub use_include_compatibility: bool,
 
+    /// How leniently wikitext is parsed.
+    ///
+    /// See [`ParserProfile`].
+    #[serde(default)]
+    pub parser_profile: ParserProfile,
+
     /// Whether IDs should have true values, or be excluded or randomly generated.
     ///
     /// In the latter case, IDs can be used for navigation, for instance
@@ -55,6 +68,21 @@ pub struct WikitextSettings {
     /// context where more than one instance of rendered wikitext could be emitted.
     pub use_true_ids: bool,
 
+    /// Prefix for the IDs generated for headings.
+    ///
+    /// When several rendered pages are combined into one document, such as
+    /// a page alongside the pages it transcludes, giving each a distinct
+    /// prefix keeps their table of contents anchors from colliding.
+    ///
+    /// If unset, headings have the same IDs as in Wikidot (`toc0`, `toc1`, etc).
+    pub heading_id_prefix: Option<String>,
+
+    /// How the prefix and heading number are combined into an ID.
+    ///
+    /// See [`HeadingIdStrategy`].
+    #[serde(default)]
+    pub heading_id_strategy: HeadingIdStrategy,
+
     /// Whether to prefix user IDs with `u-`.
     ///
     /// This is a behavior found in Wikidot (although implemented incompletely)
@@ -77,6 +105,61 @@ pub struct WikitextSettings {
     /// * Images
     pub allow_local_paths: bool,
 
+    /// Whether content from other websites may be embedded.
+    ///
+    /// This applies to:
+    /// * Embed
+    /// * Iframe
+    /// * HTML
+    pub allow_embeds: bool,
+
+    /// Whether user-provided CSS is permitted.
+    ///
+    /// This applies to `[[module CSS]]` and `style` attributes.
+    pub allow_css: bool,
+
+    /// Whether images may be loaded from arbitrary URLs.
+    ///
+    /// If disabled, only images attached to pages may be shown.
+    pub allow_external_images: bool,
+
+    /// Whether interactive elements should be rendered.
+    ///
+    /// When disabled, modules which require user interaction (such as
+    /// rating or joining) are omitted, and collapsibles and spoilers are
+    /// rendered already expanded with no toggle buttons.
+    pub interactive: bool,
+
+    /// Whether the rating module is rendered.
+    ///
+    /// Sites can turn ratings off for some or all of their pages,
+    /// which applies to every page using `[[module Rate]]`.
+    ///
+    /// See [`ModuleState`].
+    #[serde(default)]
+    pub rating_module: ModuleState,
+
+    /// Whether the comments module is rendered.
+    ///
+    /// Like `rating_module`, this applies to every page using `[[module Comments]]`.
+    #[serde(default)]
+    pub comments_module: ModuleState,
+
+    /// The largest image, in bytes, which may be embedded in the output.
+    ///
+    /// If set, attached images whose contents are in the render context
+    /// and are no larger than this are inlined as `data:` URIs, so the
+    /// output is self-contained. Other images are linked to as usual.
+    pub embed_image_max_size: Option<usize>,
+
+    /// Which `rel` values are added to links to other websites.
+    ///
+    /// This is set according to the site's policy, for instance marking
+    /// links as user-generated so search engines don't treat them as
+    /// endorsed by the site. Links within Wikijump are unaffected.
+    #[serde(default)]
+    pub external_link_rel: ExternalLinkRel,
+
     /// What interwiki prefixes are supported.
     ///
     /// All instances of `$$` in the destination URL are replaced with the link provided
@@ -90,6 +173,17 @@ pub struct WikitextSettings {
     ///   any beyond that are considered part of the link.
     /// * By convention, prefixes should be all-lowercase.
     pub interwiki: InterwikiSettings,
+
+    /// Which blocks are available beyond the built-in ones.
+    ///
+    /// These are registered at runtime, such as by a site, so that
+    /// bespoke blocks can be added without changes to the parser.
+    /// Invoking a block which is neither built-in nor registered
+    /// is rendered as text, with a parse error.
+    ///
+    /// See [`CustomBlockSettings`].
+    #[serde(default)]
+    pub custom_blocks: CustomBlockSettings,
 }
 
 impl WikitextSettings {
@@ -101,44 +195,150 @@ impl WikitextSettings {
                 mode,
                 enable_page_syntax: true,
                 use_include_compatibility: false,
+                parser_profile: ParserProfile::default(),
                 use_true_ids: true,
+                heading_id_prefix: None,
+                heading_id_strategy: HeadingIdStrategy::Suffix,
                 isolate_user_ids: false,
                 minify_css: DEFAULT_MINIFY_CSS,
                 allow_local_paths: true,
+                allow_embeds: true,
+                allow_css: true,
+                allow_external_images: true,
+                interactive: true,
+                rating_module: ModuleState::Enabled,
+                comments_module: ModuleState::Enabled,
+                embed_image_max_size: None,
+                external_link_rel: ExternalLinkRel::default(),
                 interwiki,
+                custom_blocks: CustomBlockSettings::new(),
+            },
+            WikitextMode::Print => WikitextSettings {
+                mode,
+                enable_page_syntax: true,
+                use_include_compatibility: false,
+                parser_profile: ParserProfile::default(),
+                use_true_ids: true,
+                heading_id_prefix: None,
+                heading_id_strategy: HeadingIdStrategy::Suffix,
+                isolate_user_ids: false,
+                minify_css: DEFAULT_MINIFY_CSS,
+                allow_local_paths: true,
+                allow_embeds: true,
+                allow_css: true,
+                allow_external_images: true,
+                interactive: false,
+                rating_module: ModuleState::Enabled,
+                comments_module: ModuleState::Enabled,
+                embed_image_max_size: None,
+                external_link_rel: ExternalLinkRel::default(),
+                interwiki,
+                custom_blocks: CustomBlockSettings::new(),
             },
             WikitextMode::Draft => WikitextSettings {
                 mode,
                 enable_page_syntax: true,
                 use_include_compatibility: false,
+                parser_profile: ParserProfile::default(),
                 use_true_ids: false,
+                heading_id_prefix: None,
+                heading_id_strategy: HeadingIdStrategy::Suffix,
                 isolate_user_ids: false,
                 minify_css: DEFAULT_MINIFY_CSS,
                 allow_local_paths: true,
+                allow_embeds: true,
+                allow_css: true,
+                allow_external_images: true,
+                interactive: true,
+                rating_module: ModuleState::Enabled,
+                comments_module: ModuleState::Enabled,
+                embed_image_max_size: None,
+                external_link_rel: ExternalLinkRel::default(),
                 interwiki,
+                custom_blocks: CustomBlockSettings::new(),
             },
             WikitextMode::ForumPost | WikitextMode::DirectMessage => WikitextSettings {
                 mode,
                 enable_page_syntax: false,
                 use_include_compatibility: false,
+                parser_profile: ParserProfile::default(),
                 use_true_ids: false,
+                heading_id_prefix: None,
+                heading_id_strategy: HeadingIdStrategy::Suffix,
                 isolate_user_ids: false,
                 minify_css: DEFAULT_MINIFY_CSS,
                 allow_local_paths: false,
+                allow_embeds: true,
+                allow_css: true,
+                allow_external_images: true,
+                interactive: true,
+                rating_module: ModuleState::Enabled,
+                comments_module: ModuleState::Enabled,
+                embed_image_max_size: None,
+                external_link_rel: ExternalLinkRel {
+                    nofollow: false,
+                    ugc: true,
+                },
                 interwiki,
+                custom_blocks: CustomBlockSettings::new(),
+            },
+            WikitextMode::SafePreview => WikitextSettings {
+                mode,
+                enable_page_syntax: false,
+                use_include_compatibility: false,
+                parser_profile: ParserProfile::default(),
+                use_true_ids: false,
+                heading_id_prefix: None,
+                heading_id_strategy: HeadingIdStrategy::Suffix,
+                isolate_user_ids: true,
+                minify_css: DEFAULT_MINIFY_CSS,
+                allow_local_paths: true,
+                allow_embeds: false,
+                allow_css: false,
+                allow_external_images: false,
+                interactive: true,
+                rating_module: ModuleState::Enabled,
+                comments_module: ModuleState::Enabled,
+                embed_image_max_size: None,
+                external_link_rel: ExternalLinkRel::default(),
+                interwiki,
+                custom_blocks: CustomBlockSettings::new(),
             },
             WikitextMode::List => WikitextSettings {
                 mode,
                 enable_page_syntax: true,
                 use_include_compatibility: false,
+                parser_profile: ParserProfile::default(),
                 use_true_ids: false,
+                heading_id_prefix: None,
+                heading_id_strategy: HeadingIdStrategy::Suffix,
                 isolate_user_ids: false,
                 minify_css: DEFAULT_MINIFY_CSS,
                 allow_local_paths: true,
+                allow_embeds: true,
+                allow_css: true,
+                allow_external_images: true,
+                interactive: true,
+                rating_module: ModuleState::Enabled,
+                comments_module: ModuleState::Enabled,
+                embed_image_max_size: None,
+                external_link_rel: ExternalLinkRel::default(),
                 interwiki,
+                custom_blocks: CustomBlockSettings::new(),
             },
         }
     }
+
+    /// Gets whether the given module is rendered.
+    ///
+    /// Modules which can't be turned off are always enabled.
+    pub fn module_state(&self, module: &Module) -> ModuleState {
+        match module {
+            Module::Rate => self.rating_module,
+            Module::Comments => self.comments_module,
+            _ => ModuleState::Enabled,
+        }
+    }
 }
 
 /// What mode parsing and rendering is done in.
@@ -164,4 +364,104 @@ pub enum WikitextMode {
 
     /// Processing for modules or other contexts such as `ListPages`.
     List,
+
+    /// Processing for a printable or archived copy of a page.
+    ///
+    /// This is static output, with no interactive elements.
+    Print,
+
+    /// Processing for a preview of unsaved content from an untrusted user.
+    ///
+    /// Includes, embeds, CSS, and external images are all disabled,
+    /// so rendering can't fetch other pages or reach other websites.
+    SafePreview,
+}
+
+/// Which set of parsing rules to use, beyond the usual syntax.
+///
+/// This is chosen by the site, so that content imported from Wikidot
+/// renders as it did there, while new sites can require cleaner wikitext.
+#[derive(Serialize, Deserialize, Debug, Default, Copy, Clone, Hash, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ParserProfile {
+    /// The usual parsing rules.
+    #[default]
+    Standard,
+
+    /// Mimics the quirks of Wikidot, for imported content.
+    ///
+    /// This currently refers to:
+    /// * `[[include]]` being an alias for `[[include-messy]]`,
+    ///   as if `use_include_compatibility` were set.
+    Compatibility,
+
+    /// Rejects ambiguous constructs, rendering them as text with an error.
+    ///
+    /// This currently refers to:
+    /// * Block names which aren't lowercase, such as `[[DIV]]`.
+    /// * Block arguments given more than once, such as `[[span class="a" class="b"]]`.
+    Strict,
+}
+
+/// How IDs for headings are generated from the configured prefix.
+///
+/// Either way, headings are numbered in the order they appear,
+/// and the table of contents links to the same IDs.
+#[derive(Serialize, Deserialize, Debug, Default, Copy, Clone, Hash, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum HeadingIdStrategy {
+    /// Appends the heading number to the prefix, such as `intro-toc0`.
+    ///
+    /// This is readable, but the prefix must be valid in an ID.
+    #[default]
+    Suffix,
+
+    /// Hashes the prefix with the heading number, such as `toc-4f1e0a9b7c2d3e58`.
+    ///
+    /// This is for arbitrary prefixes, such as page names,
+    /// as the resulting ID is always short and valid.
+    Hash,
+}
+
+/// Whether a module which sites may turn off is rendered.
+///
+/// This way turning off a module, such as ratings, is a change to the
+/// site's settings rather than to every page using it.
+#[derive(Serialize, Deserialize, Debug, Default, Copy, Clone, Hash, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ModuleState {
+    /// The module is rendered as usual.
+    #[default]
+    Enabled,
+
+    /// A notice that the module is disabled is rendered in its place.
+    Placeholder,
+
+    /// Nothing is rendered in place of the module.
+    Hidden,
+}
+
+/// The `rel` attribute values for links to other websites.
+///
+/// By default, none are added.
+#[derive(Serialize, Deserialize, Debug, Default, Copy, Clone, Hash, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case", default)]
+pub struct ExternalLinkRel {
+    /// Whether to add `nofollow`, so the link isn't endorsed by the site.
+    pub nofollow: bool,
+
+    /// Whether to add `ugc`, marking the link as user-generated content.
+    pub ugc: bool,
+}
+
+impl ExternalLinkRel {
+    /// Gets the `rel` attribute value, if any values are enabled.
+    pub fn value(self) -> Option<&'static str> {
+        match (self.nofollow, self.ugc) {
+            (false, false) => None,
+            (true, false) => Some("nofollow"),
+            (false, true) => Some("ugc"),
+            (true, true) => Some("nofollow ugc"),
+        }
+    }
 }
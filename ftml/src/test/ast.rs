@@ -24,12 +24,13 @@
 //! in a dedicated test file.
 
 use super::includer::TestIncluder;
-use crate::data::{PageInfo, ScoreValue};
+use crate::data::{
+    AttachedFile, FileInfo, ImageDerivative, PageInfo, RenderContext, ScoreValue,
+};
 use crate::parsing::ParseError;
 use crate::render::html::HtmlRender;
-use crate::render::Render;
 use crate::settings::{WikitextMode, WikitextSettings};
-use crate::tree::SyntaxTree;
+use crate::tree::{GallerySize, SyntaxTree};
 use once_cell::sync::Lazy;
 use std::borrow::Cow;
 use std::fs::{self, File};
@@ -207,6 +208,7 @@ impl Test<'_> {
             score: ScoreValue::Integer(0),
             tags: vec![cow!("fruit"), cow!("component")],
             language: cow!("default"),
+            utc_offset: None,
         };
 
         let settings = WikitextSettings::from_mode(WikitextMode::Page);
@@ -219,8 +221,74 @@ impl Test<'_> {
         let tokens = crate::tokenize(&text);
         let result = crate::parse(&tokens, &page_info, &settings);
         let (mut tree, errors) = result.into();
+
+        // Parsing in parallel, even split at every paragraph break, must be the same
+        #[cfg(feature = "parallel")]
+        let parallel_matches = {
+            let result =
+                crate::parsing::parse_segmented(&tokens, &page_info, &settings, 1);
+            let (parallel_tree, parallel_errors) = result.into();
+            let matches = parallel_tree == tree && parallel_errors == errors;
+            if !matches {
+                eprintln!(
+                    "Parallel parse did not match:\nExpected: {:#?}\nActual: {:#?}\nErrors: {:#?}",
+                    tree, parallel_tree, parallel_errors,
+                );
+            }
+
+            matches
+        };
+
         tree.wikitext_len = self.tree.wikitext_len; // not stored in the JSON
-        let html_output = HtmlRender.render(&tree, &page_info, &settings);
+
+        // Most tests don't list element spans, only check them if present
+        if self.tree.element_spans.is_empty() {
+            tree.element_spans.clear();
+        }
+
+        // Every attached file exists, except those named "missing"
+        let context = RenderContext {
+            files: tree
+                .file_references(&page_info)
+                .into_iter()
+                .filter(|reference| reference.file != "missing")
+                .map(|reference| {
+                    let url = |directory: &str| {
+                        Cow::Owned(format!(
+                            "https://{}.wjfiles.com/{directory}/{}/{}",
+                            reference.site, reference.page, reference.file,
+                        ))
+                    };
+
+                    let mut info = FileInfo::dummy();
+                    info.file_name = reference.file.clone();
+                    info.file_url = url("local--files");
+
+                    let derivatives = [
+                        GallerySize::Square,
+                        GallerySize::Thumbnail,
+                        GallerySize::Small,
+                        GallerySize::Medium,
+                    ]
+                    .into_iter()
+                    .map(|size| ImageDerivative {
+                        size,
+                        url: url(size.name()),
+                    })
+                    .collect();
+
+                    AttachedFile {
+                        reference,
+                        info,
+                        derivatives,
+                        data: None,
+                    }
+                })
+                .collect(),
+        };
+
+        let html_output =
+            HtmlRender.render_with_context(&tree, &page_info, &settings, &context);
 
         fn json<T>(object: &T) -> String
         where
@@ -235,6 +303,11 @@ impl Test<'_> {
 
         let mut result = TestResult::Pass;
 
+        #[cfg(feature = "parallel")]
+        if !parallel_matches {
+            result = TestResult::Fail;
+        }
+
         if tree != self.tree {
             result = TestResult::Fail;
             eprintln!(
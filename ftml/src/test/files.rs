@@ -0,0 +1,101 @@
+/*
+ * test/files.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::data::{FileReference, PageInfo};
+use crate::settings::{WikitextMode, WikitextSettings};
+use std::borrow::Cow;
+
+fn file(
+    site: &'static str,
+    page: &'static str,
+    file: &'static str,
+) -> FileReference<'static> {
+    FileReference {
+        site: Cow::Borrowed(site),
+        page: Cow::Borrowed(page),
+        file: Cow::Borrowed(file),
+    }
+}
+
+#[test]
+fn file_references() {
+    let settings = WikitextSettings::from_mode(WikitextMode::Page);
+
+    macro_rules! check {
+        ($page_info:expr, $input:expr, $expected:expr $(,)?) => {{
+            let page_info = $page_info;
+            let mut text = str!($input);
+            crate::preprocess(&mut text);
+
+            let tokens = crate::tokenize(&text);
+            let result = crate::parse(&tokens, &page_info, &settings);
+            let (tree, _errors) = result.into();
+            let expected: Vec<FileReference> = $expected;
+
+            assert_eq!(
+                tree.file_references(&page_info),
+                expected,
+                "File references for {:?} don't match expected",
+                $input,
+            );
+        }};
+    }
+
+    check!(PageInfo::dummy(), "no files here", vec![]);
+    check!(
+        PageInfo::dummy(),
+        "[[image https://example.com/apple.png]]",
+        vec![],
+    );
+    check!(
+        PageInfo::dummy(),
+        "[[image apple.png]]",
+        vec![file("sandbox", "some-page", "apple.png")],
+    );
+    check!(
+        PageInfo {
+            category: Some(Cow::Borrowed("fruit")),
+            ..PageInfo::dummy()
+        },
+        "[[file-card report.pdf]]",
+        vec![file("sandbox", "fruit:some-page", "report.pdf")],
+    );
+    check!(
+        PageInfo::dummy(),
+        "[[file-card /other-page/report.pdf]]\n\n[[file-card /other-site/other-page/report.pdf]]",
+        vec![
+            file("sandbox", "other-page", "report.pdf"),
+            file("other-site", "other-page", "report.pdf"),
+        ],
+    );
+    check!(
+        PageInfo::dummy(),
+        "[[gallery]]\n[[gallery-image apple.png]]\n[[gallery-image banana.png]]\n[[/gallery]]\n\n[[collapsible]]\n[[image apple.png]]\n[[/collapsible]]",
+        vec![
+            file("sandbox", "some-page", "apple.png"),
+            file("sandbox", "some-page", "banana.png"),
+        ],
+    );
+    check!(
+        PageInfo::dummy(),
+        "Text[[footnote]][[image apple.png]][[/footnote]]",
+        vec![file("sandbox", "some-page", "apple.png")],
+    );
+}
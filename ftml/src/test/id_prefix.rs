@@ -19,7 +19,10 @@
  */
 
 use crate::data::{PageInfo, ScoreValue};
-use crate::settings::{WikitextMode, WikitextSettings, EMPTY_INTERWIKI};
+use crate::settings::{
+    CustomBlockSettings, ExternalLinkRel, HeadingIdStrategy, ModuleState, ParserProfile,
+    WikitextMode, WikitextSettings, EMPTY_INTERWIKI,
+};
 use crate::tree::{
     AttributeMap, Container, ContainerType, Element, ImageSource, ListItem, ListType,
 };
@@ -48,17 +51,30 @@ fn isolate_user_ids() {
         score: ScoreValue::Integer(0),
         tags: vec![],
         language: cow!("default"),
+        utc_offset: None,
     };
 
     let settings = WikitextSettings {
         mode: WikitextMode::Page,
         enable_page_syntax: true,
         use_true_ids: true,
+        heading_id_prefix: None,
+        heading_id_strategy: HeadingIdStrategy::Suffix,
         use_include_compatibility: false,
+        parser_profile: ParserProfile::Standard,
         isolate_user_ids: true,
         minify_css: false,
         allow_local_paths: true,
+        allow_embeds: true,
+        allow_css: true,
+        allow_external_images: true,
+        interactive: true,
+        rating_module: ModuleState::Enabled,
+        comments_module: ModuleState::Enabled,
+        embed_image_max_size: None,
+        external_link_rel: ExternalLinkRel::default(),
         interwiki: EMPTY_INTERWIKI.clone(),
+        custom_blocks: CustomBlockSettings::new(),
     };
 
     fn append_footnote_block(mut elements: Vec<Element>) -> Vec<Element> {
@@ -184,6 +200,7 @@ fn isolate_user_ids() {
                     cow!("id") => cow!("u-banana"),
                 }),
                 elements: vec![text!("X")],
+                task: None,
             }],
         }],
     );
@@ -199,6 +216,7 @@ fn isolate_user_ids() {
                     cow!("id") => cow!("u-banana"),
                 }),
                 elements: vec![text!("X")],
+                task: None,
             }],
         }],
     );
@@ -215,6 +233,7 @@ fn isolate_user_ids() {
                     cow!("id") => cow!("u-banana"),
                 }),
                 elements: vec![text!("X")],
+                task: None,
             }],
         }],
     );
@@ -230,6 +249,7 @@ fn isolate_user_ids() {
                     cow!("id") => cow!("u-banana"),
                 }),
                 elements: vec![text!("X")],
+                task: None,
             }],
         }],
     );
@@ -19,8 +19,10 @@
  */
 
 mod ast;
+mod files;
 mod id_prefix;
 mod includer;
 mod large;
 mod prop;
 mod settings;
+mod stats;
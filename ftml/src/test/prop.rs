@@ -32,6 +32,7 @@ use proptest::option;
 use proptest::prelude::*;
 use std::borrow::Cow;
 use std::num::NonZeroU32;
+use time::UtcOffset;
 
 // Constants
 
@@ -99,6 +100,7 @@ fn arb_module() -> impl Strategy<Value = Element<'static>> {
         });
 
     prop_oneof![
+        Just(Module::Comments),
         Just(Module::Rate),
         arb_optional_str().prop_map(|page| Module::Backlinks { page }),
         any::<bool>().prop_map(|include_hidden| Module::Categories { include_hidden }),
@@ -218,6 +220,7 @@ where
         ListItem::Elements {
             elements,
             attributes,
+            task: None,
         }
     });
     let leaf = make_list!(proptest::collection::vec(list_item, 1..10));
@@ -288,6 +291,7 @@ where
         Just(ContainerType::Div),
         Just(ContainerType::Mark),
         Just(ContainerType::Blockquote),
+        Just(ContainerType::Cite),
         Just(ContainerType::Insertion),
         Just(ContainerType::Deletion),
         Just(ContainerType::Hidden),
@@ -339,6 +343,28 @@ where
         )
 }
 
+fn arb_spoiler<S>(elements: S) -> impl Strategy<Value = Element<'static>>
+where
+    S: Strategy<Value = Vec<Element<'static>>>,
+{
+    (
+        elements,
+        arb_attribute_map(),
+        any::<bool>(),
+        arb_optional_str(),
+        arb_optional_str(),
+    )
+        .prop_map(|(elements, attributes, block, show_text, hide_text)| {
+            Element::Spoiler {
+                elements,
+                attributes,
+                block,
+                show_text,
+                hide_text,
+            }
+        })
+}
+
 // Syntax Tree
 
 fn arb_element_leaf() -> impl Strategy<Value = Element<'static>> {
@@ -355,6 +381,7 @@ fn arb_element_leaf() -> impl Strategy<Value = Element<'static>> {
         arb_code(),
         cow!(".*").prop_map(|contents| Element::Html { contents }),
         // TODO: Element::Iframe
+        // TODO: Element::CustomBlock
         Just(Element::LineBreak),
         (1..50_u32)
             .prop_map(|count| Element::LineBreaks(NonZeroU32::new(count).unwrap())),
@@ -383,6 +410,7 @@ fn arb_tree() -> impl Strategy<Value = SyntaxTree<'static>> {
                 // TODO: Element::Anchor
                 arb_list(elements!()),
                 arb_collapsible(elements!()),
+                arb_spoiler(elements!()),
                 // TODO: Element::IfCategory
                 // TODO: Element::IfTags
                 // TODO: Element::Color
@@ -403,6 +431,7 @@ fn arb_tree() -> impl Strategy<Value = SyntaxTree<'static>> {
         .prop_map(|(elements, table_of_contents, footnotes, wikitext_len)| {
             SyntaxTree {
                 elements,
+                element_spans: vec![], // not bothering right now
                 table_of_contents,
                 footnotes,
                 bibliographies: BibliographyList::new(), // not bothering right now
@@ -423,17 +452,22 @@ fn arb_page_info() -> impl Strategy<Value = PageInfo<'static>> {
         any::<f64>(),
         proptest::collection::vec(cow!(".+"), 0..20),
         cow!(r"[a-z\-]+"),
+        option::of(-86399..86400),
     )
         .prop_map(
-            |(page, category, site, title, alt_title, score, tags, language)| PageInfo {
-                page,
-                category,
-                site,
-                title,
-                alt_title,
-                score: score.into(),
-                tags,
-                language,
+            |(page, category, site, title, alt_title, score, tags, language, offset)| {
+                PageInfo {
+                    page,
+                    category,
+                    site,
+                    title,
+                    alt_title,
+                    score: score.into(),
+                    tags,
+                    language,
+                    utc_offset: offset
+                        .map(|seconds| UtcOffset::from_whole_seconds(seconds).unwrap()),
+                }
             },
         )
 }
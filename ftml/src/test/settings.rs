@@ -19,17 +19,23 @@
  */
 
 use crate::data::PageInfo;
+use crate::parsing::ParseErrorKind;
 use crate::render::{html::HtmlRender, Render};
-use crate::settings::{WikitextMode, WikitextSettings};
+use crate::settings::{
+    CustomBlockArgument, CustomBlockBody, CustomBlockDefinition, ModuleState,
+    ParserProfile, WikitextMode, WikitextSettings,
+};
 
 #[test]
 fn settings() {
-    const PAGE_MODES: [WikitextMode; 5] = [
+    const PAGE_MODES: [WikitextMode; 7] = [
         WikitextMode::Page,
         WikitextMode::Draft,
         WikitextMode::ForumPost,
         WikitextMode::DirectMessage,
         WikitextMode::List,
+        WikitextMode::Print,
+        WikitextMode::SafePreview,
     ];
 
     let page_info = PageInfo::dummy();
@@ -72,31 +78,337 @@ fn settings() {
         }};
     }
 
-    check!("++ H2", "toc0", [true, false, false, false, false]);
-    check!("[[toc]]", "wj-toc", [true, false, false, false, false]);
+    check!(
+        "++ H2",
+        "toc0",
+        [true, false, false, false, false, true, false]
+    );
+    check!(
+        "[[toc]]",
+        "wj-toc",
+        [true, false, false, false, false, true, false]
+    );
     check!(
         "[[module Rate]]",
         "TODO: module Rate",
-        [true, true, false, false, true],
+        [true, true, false, false, true, false, false],
     );
     check!(
         "[[include-elements page]]",
         "INCLUDED PAGE",
-        [true, true, false, false, true],
+        [true, true, false, false, true, true, false],
     );
     check!(
         "[[image /local-file.png]]",
         "local-file.png",
-        [true, true, false, false, true],
+        [true, true, false, false, true, true, true],
     );
     check!(
         "[[image /some-page/local-file.png]]",
         "local-file.png",
-        [true, true, false, false, true],
+        [true, true, false, false, true, true, true],
     );
     check!(
         "[[image /my-site/some-page/local-file.png]]",
         "local-file.png",
-        [true, true, false, false, true],
+        [true, true, false, false, true, true, true],
+    );
+    check!(
+        "[[module Backlinks]]",
+        "TODO: module Backlinks",
+        [true, true, false, false, true, true, false],
+    );
+    check!(
+        "[[collapsible]]\nText\n[[/collapsible]]",
+        "wj-collapsible-button",
+        [true, true, true, true, true, false, true],
+    );
+    check!(
+        "[[spoilerblock]]\nText\n[[/spoilerblock]]",
+        "wj-spoiler-button",
+        [true, true, true, true, true, false, true],
+    );
+    check!(
+        "[[iframe https://example.com/]]",
+        "<iframe",
+        [true, true, true, true, true, true, false],
+    );
+    check!(
+        "[[embed youtube video=\"dQw4w9WgXcQ\"]]",
+        "wj-embed",
+        [true, true, true, true, true, true, false],
+    );
+    check!(
+        "[[html]]\n<b>Text</b>\n[[/html]]",
+        "<iframe",
+        [true, true, true, true, true, true, false],
+    );
+    check!(
+        "[[image https://example.com/picture.png]]",
+        "example.com/picture.png",
+        [true, true, true, true, true, true, false],
+    );
+    check!(
+        "[[span style=\"color: red;\"]]Text[[/span]]",
+        "color: red;",
+        [true, true, true, true, true, true, false],
+    );
+    check!(
+        "[https://example.com/ Example]",
+        "rel=\"ugc\"",
+        [false, false, true, true, false, false, false],
+    );
+}
+
+#[test]
+fn parser_profiles() {
+    let page_info = PageInfo::dummy();
+
+    macro_rules! check {
+        ($profile:expr, $input:expr, $substring:expr, $error:expr $(,)?) => {{
+            let mut settings = WikitextSettings::from_mode(WikitextMode::Page);
+            settings.parser_profile = $profile;
+
+            let tokens = crate::tokenize($input);
+            let result = crate::parse(&tokens, &page_info, &settings);
+            let (tree, errors) = result.into();
+            let html_output = HtmlRender.render(&tree, &page_info, &settings);
+            let error: Option<ParseErrorKind> = $error;
+
+            println!();
+            println!("Input:  {:?}", $input);
+            println!("Output: {:?}", html_output.body);
+            println!("Errors: {:#?}", errors);
+
+            assert_eq!(
+                html_output.body.contains($substring),
+                error.is_none(),
+                "For {:?}, HTML output doesn't match expected",
+                $profile,
+            );
+
+            if let Some(kind) = error {
+                assert!(
+                    errors.iter().any(|err| err.kind() == kind),
+                    "For {:?}, expected error {:?} not produced",
+                    $profile,
+                    kind,
+                );
+            }
+        }};
+    }
+
+    for profile in [ParserProfile::Standard, ParserProfile::Compatibility] {
+        check!(profile, "[[DIV]]\nText\n[[/DIV]]", "<div>", None);
+        check!(profile, "[[div]]\nText\n[[/Div]]", "<div>", None);
+        check!(
+            profile,
+            "[[span class=\"a\" class=\"b\"]]Text[[/span]]",
+            "class=\"b\"",
+            None,
+        );
+    }
+
+    check!(
+        ParserProfile::Strict,
+        "[[div]]\nText\n[[/div]]",
+        "<div>",
+        None
+    );
+    check!(
+        ParserProfile::Strict,
+        "[[DIV]]\nText\n[[/DIV]]",
+        "<div>",
+        Some(ParseErrorKind::BlockNameNotLowercase),
+    );
+    check!(
+        ParserProfile::Strict,
+        "[[span class=\"a\" class=\"b\"]]Text[[/span]]",
+        "<span",
+        Some(ParseErrorKind::BlockDuplicateArgument),
+    );
+}
+
+#[test]
+fn custom_blocks() {
+    let page_info = PageInfo::dummy();
+    let mut settings = WikitextSettings::from_mode(WikitextMode::Page);
+
+    macro_rules! argument {
+        ($name:expr, $required:expr $(,)?) => {
+            CustomBlockArgument {
+                name: str!($name),
+                required: $required,
+            }
+        };
+    }
+
+    macro_rules! register {
+        ($name:expr, $arguments:expr, $body:expr, $template:expr $(,)?) => {
+            settings
+                .custom_blocks
+                .register(CustomBlockDefinition {
+                    name: str!($name),
+                    arguments: $arguments,
+                    body: $body,
+                    template: str!($template),
+                })
+                .expect("Unable to register custom block")
+        };
+    }
+
+    register!(
+        "weather",
+        vec![argument!("city", true), argument!("link", false)],
+        CustomBlockBody::None,
+        r#"<a class="weather" href="{{link}}">Weather in {{city}}</a>"#,
+    );
+    register!(
+        "character-sheet",
+        vec![argument!("name", true)],
+        CustomBlockBody::Elements,
+        "<h3>{{name}}</h3>{{body}}",
+    );
+    register!(
+        "note",
+        vec![],
+        CustomBlockBody::Text,
+        r#"<pre class="note">{{body}}</pre>"#,
+    );
+
+    macro_rules! check {
+        ($input:expr, $substring:expr, $error:expr $(,)?) => {{
+            let tokens = crate::tokenize($input);
+            let result = crate::parse(&tokens, &page_info, &settings);
+            let (tree, errors) = result.into();
+            let html_output = HtmlRender.render(&tree, &page_info, &settings);
+            let error: Option<ParseErrorKind> = $error;
+
+            println!();
+            println!("Input:  {:?}", $input);
+            println!("Output: {:?}", html_output.body);
+            println!("Errors: {:#?}", errors);
+
+            assert!(
+                html_output.body.contains($substring),
+                "HTML output doesn't contain expected substring {:?}",
+                $substring,
+            );
+
+            match error {
+                Some(kind) => assert!(
+                    errors.iter().any(|err| err.kind() == kind),
+                    "Expected error {:?} not produced",
+                    kind,
+                ),
+                None => assert!(errors.is_empty(), "Errors produced during parsing!"),
+            }
+        }};
+    }
+
+    check!(
+        r#"[[weather city="Oslo" link="https://example.com/oslo"]]"#,
+        r#"<div class="wj-custom-block" data-block="weather"><a class="weather" href="https://example.com/oslo">Weather in Oslo</a></div>"#,
+        None,
+    );
+    check!(
+        r#"[[WEATHER city="<Oslo>" unit="C"]]"#,
+        "Weather in &lt;Oslo&gt;</a>",
+        None,
+    );
+    check!(
+        r#"[[weather city="Oslo" link="javascript:alert(1)"]]"#,
+        r##"href="#invalid-url""##,
+        None,
+    );
+    check!(
+        "[[character-sheet name=\"Alice\"]]\n**Level 3**\n[[/character-sheet]]",
+        "<h3>Alice</h3><p><strong>Level 3</strong></p>",
+        None,
+    );
+    check!(
+        "[[note]]\n**not bold**\n[[/note]]",
+        r#"<pre class="note">**not bold**"#,
+        None,
+    );
+
+    // Missing required argument
+    check!(
+        "[[weather]]",
+        "[[weather]]",
+        Some(ParseErrorKind::BlockMissingArguments),
+    );
+
+    // Not registered at all
+    check!(
+        r#"[[forecast city="Oslo"]]"#,
+        "[[forecast",
+        Some(ParseErrorKind::NoSuchBlock),
+    );
+}
+
+#[test]
+fn module_states() {
+    let page_info = PageInfo::dummy();
+
+    macro_rules! check {
+        ($rating:expr, $comments:expr, $input:expr, $expected:expr $(,)?) => {{
+            let mut settings = WikitextSettings::from_mode(WikitextMode::Page);
+            settings.rating_module = $rating;
+            settings.comments_module = $comments;
+
+            let tokens = crate::tokenize($input);
+            let result = crate::parse(&tokens, &page_info, &settings);
+            let (tree, _errors) = result.into();
+            let html_output = HtmlRender.render(&tree, &page_info, &settings);
+
+            println!();
+            println!("Input:  {:?}", $input);
+            println!("Output: {:?}", html_output.body);
+
+            assert_eq!(
+                html_output.body, $expected,
+                "HTML output doesn't match expected",
+            );
+        }};
+    }
+
+    check!(
+        ModuleState::Enabled,
+        ModuleState::Enabled,
+        "[[module Rate]]",
+        r#"<wj-body class="wj-body"><p>TODO: module Rate</p></wj-body>"#,
+    );
+    check!(
+        ModuleState::Hidden,
+        ModuleState::Enabled,
+        "[[module Rate]]",
+        r#"<wj-body class="wj-body"></wj-body>"#,
+    );
+    check!(
+        ModuleState::Placeholder,
+        ModuleState::Enabled,
+        "[[module Rate]]",
+        r#"<wj-body class="wj-body"><div class="wj-module-disabled" data-module="Rate">This feature is disabled</div></wj-body>"#,
+    );
+    check!(
+        ModuleState::Hidden,
+        ModuleState::Enabled,
+        "[[module Comments]]",
+        r#"<wj-body class="wj-body"><p>TODO: module Comments</p></wj-body>"#,
+    );
+    check!(
+        ModuleState::Enabled,
+        ModuleState::Placeholder,
+        "[[module Comments]]",
+        r#"<wj-body class="wj-body"><div class="wj-module-disabled" data-module="Comments">This feature is disabled</div></wj-body>"#,
+    );
+
+    // Other modules can't be disabled
+    check!(
+        ModuleState::Hidden,
+        ModuleState::Hidden,
+        "[[module Backlinks]]",
+        r#"<wj-body class="wj-body"><p>TODO: module Backlinks</p></wj-body>"#,
     );
 }
@@ -0,0 +1,105 @@
+/*
+ * test/stats.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::data::PageInfo;
+use crate::settings::{WikitextMode, WikitextSettings};
+use crate::tree::{ContentStats, HeadingOutline};
+
+fn stats(input: &str) -> ContentStats {
+    let page_info = PageInfo::dummy();
+    let settings = WikitextSettings::from_mode(WikitextMode::Page);
+    let mut text = str!(input);
+    crate::preprocess(&mut text);
+
+    let tokens = crate::tokenize(&text);
+    let result = crate::parse(&tokens, &page_info, &settings);
+    let (tree, _errors) = result.into();
+    tree.stats()
+}
+
+#[test]
+fn word_count() {
+    macro_rules! check {
+        ($input:expr, $expected:expr $(,)?) => {{
+            let stats = stats($input);
+            assert_eq!(
+                stats.word_count, $expected,
+                "Word count for {:?} doesn't match expected",
+                $input,
+            );
+        }};
+    }
+
+    check!("", 0);
+    check!("apple banana cherry", 3);
+    check!("some **bold** and //italic// text", 5);
+    check!("in**line**word", 1);
+    check!("line one\nline two", 4);
+    check!("first paragraph\n\nsecond paragraph", 4);
+    check!("before\n[[code]]\nlet x = 1;\n[[/code]]\nafter", 2);
+    check!("visible [[hidden]]not counted[[/hidden]]", 1);
+    check!("[[span]]one[[/span]][[span]]two[[/span]]", 1);
+    check!("* item one\n* item two", 4);
+    check!("[https://example.com/ example site]", 2);
+    check!(
+        "text with a footnote[[footnote]]and its body[[/footnote]]",
+        7
+    );
+}
+
+#[test]
+fn reading_time() {
+    assert_eq!(stats("").reading_time_minutes, 0);
+    assert_eq!(stats("word").reading_time_minutes, 1);
+
+    let words = "word ".repeat(450);
+    let stats = stats(&words);
+    assert_eq!(stats.word_count, 450);
+    assert_eq!(stats.reading_time_minutes, 3);
+}
+
+#[test]
+fn headings_and_images() {
+    let stats = stats(
+        "+ Introduction\n\
+         Some text.\n\
+         ++ Details with **bold**\n\
+         [[image example.png]]\n\
+         [[gallery]]\n\
+         [[gallery-image a.png]]\n\
+         [[gallery-image b.png caption=\"Caption\"]]\n\
+         [[/gallery]]",
+    );
+
+    assert_eq!(
+        stats.headings,
+        vec![
+            HeadingOutline {
+                level: 1,
+                text: str!("Introduction"),
+            },
+            HeadingOutline {
+                level: 2,
+                text: str!("Details with bold"),
+            },
+        ],
+    );
+    assert_eq!(stats.image_count, 3);
+}
@@ -111,6 +111,12 @@ impl<'t> AttributeMap<'t> {
         }
     }
 
+    pub fn strip_style(&mut self, settings: &WikitextSettings) {
+        if !settings.allow_css && self.inner.remove("style").is_some() {
+            debug!("Found 'style' attribute, removing since CSS is disabled");
+        }
+    }
+
     pub fn to_owned(&self) -> AttributeMap<'static> {
         let mut inner = BTreeMap::new();
 
@@ -23,6 +23,7 @@
 use super::clone::elements_to_owned;
 use super::{Alignment, AttributeMap, Element, Heading, HtmlTag};
 use crate::next_index::{NextIndex, TableOfContentsIndex};
+use crate::settings::WikitextSettings;
 use strum_macros::IntoStaticStr;
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
@@ -102,6 +103,7 @@ pub enum ContainerType {
     Div,
     Mark,
     Blockquote,
+    Cite,
     Insertion,
     Deletion,
     Hidden,
@@ -121,7 +123,11 @@ impl ContainerType {
     }
 
     #[inline]
-    pub fn html_tag(self, indexer: &mut dyn NextIndex<TableOfContentsIndex>) -> HtmlTag {
+    pub fn html_tag(
+        self,
+        indexer: &mut dyn NextIndex<TableOfContentsIndex>,
+        settings: &WikitextSettings,
+    ) -> HtmlTag {
         match self {
             ContainerType::Bold => HtmlTag::new("strong"),
             ContainerType::Italics => HtmlTag::new("em"),
@@ -134,6 +140,7 @@ impl ContainerType {
             ContainerType::Div => HtmlTag::new("div"),
             ContainerType::Mark => HtmlTag::new("mark"),
             ContainerType::Blockquote => HtmlTag::new("blockquote"),
+            ContainerType::Cite => HtmlTag::new("cite"),
             ContainerType::Insertion => HtmlTag::new("ins"),
             ContainerType::Deletion => HtmlTag::new("del"),
             ContainerType::Hidden => HtmlTag::with_class("span", "wj-hidden"),
@@ -145,7 +152,7 @@ impl ContainerType {
             ContainerType::Align(alignment) => {
                 HtmlTag::with_class("div", alignment.html_class())
             }
-            ContainerType::Header(heading) => heading.html_tag(indexer),
+            ContainerType::Header(heading) => heading.html_tag(indexer, settings),
         }
     }
 
@@ -166,6 +173,7 @@ impl ContainerType {
             ContainerType::Div => false,
             ContainerType::Mark => true,
             ContainerType::Blockquote => false,
+            ContainerType::Cite => true,
             ContainerType::Insertion => true,
             ContainerType::Deletion => true,
             ContainerType::Hidden => true,
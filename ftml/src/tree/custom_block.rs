@@ -0,0 +1,59 @@
+/*
+ * tree/custom_block.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Representation of blocks registered at runtime, rather than built into the parser.
+
+use super::clone::{elements_to_owned, string_to_owned};
+use super::Element;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+/// An invocation of a custom block, such as `[[weather city="Oslo"]]`.
+///
+/// How it is rendered is up to the block's definition, see
+/// [`CustomBlockSettings`](crate::settings::CustomBlockSettings).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct CustomBlock<'t> {
+    /// The name of the block, as registered.
+    pub name: Cow<'t, str>,
+
+    /// The values of the block's declared arguments which were passed in.
+    pub arguments: BTreeMap<Cow<'t, str>, Cow<'t, str>>,
+
+    /// The block's body, if it has one.
+    ///
+    /// If the body is kept as text, this is a single `Element::Text`.
+    pub elements: Vec<Element<'t>>,
+}
+
+impl CustomBlock<'_> {
+    pub fn to_owned(&self) -> CustomBlock<'static> {
+        CustomBlock {
+            name: string_to_owned(&self.name),
+            arguments: self
+                .arguments
+                .iter()
+                .map(|(key, value)| (string_to_owned(key), string_to_owned(value)))
+                .collect(),
+            elements: elements_to_owned(&self.elements),
+        }
+    }
+}
@@ -19,7 +19,8 @@
  */
 
 use std::io;
-use time::format_description::well_known::Rfc2822;
+use time::format_description::well_known::{Rfc2822, Rfc3339};
+use time::macros::format_description;
 use time::{Date, OffsetDateTime, PrimitiveDateTime, UtcOffset};
 
 #[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
@@ -61,19 +62,93 @@ impl DateItem {
         }
     }
 
-    pub fn format(self) -> io::Result<String> {
-        use time::error::Format;
+    /// Converts this date to be in the given timezone.
+    ///
+    /// Plain dates have no time component to shift, so they are returned as-is.
+    /// Datetimes without a timezone are assumed to be in UTC.
+    pub fn to_offset(self, offset: UtcOffset) -> Self {
+        match self {
+            DateItem::Date(_) => self,
+            _ => DateItem::DateTimeTz(self.to_datetime_tz().to_offset(offset)),
+        }
+    }
 
+    pub fn format(self) -> io::Result<String> {
         let result = match self {
             DateItem::Date(date) => date.format(&Rfc2822),
             DateItem::DateTime(datetime) => datetime.format(&Rfc2822),
             DateItem::DateTimeTz(datetime_tz) => datetime_tz.format(&Rfc2822),
         };
 
-        result.map_err(|error| match error {
-            Format::StdIo(io_error) => io_error,
-            _ => io::Error::new(io::ErrorKind::Other, error),
-        })
+        result.map_err(convert_format_error)
+    }
+
+    /// Formats this date in a machine-readable way.
+    ///
+    /// This is suitable for the `datetime` attribute of `<time>` elements.
+    pub fn format_machine(self) -> io::Result<String> {
+        let result = match self {
+            DateItem::Date(date) => {
+                date.format(format_description!("[year]-[month]-[day]"))
+            }
+            _ => self.to_datetime_tz().format(&Rfc3339),
+        };
+
+        result.map_err(convert_format_error)
+    }
+
+    /// Formats this date for display, in the style conventional for the given language.
+    ///
+    /// The `time` crate only has English month names, so languages
+    /// without a specific style use numeric dates instead.
+    pub fn format_localized(self, language: &str) -> io::Result<String> {
+        let primary = language
+            .split(['-', '_'])
+            .next()
+            .unwrap_or("")
+            .to_ascii_lowercase();
+
+        let result = match (self, primary.as_str()) {
+            (DateItem::Date(date), "en") => date.format(format_description!(
+                "[month repr:long] [day padding:none], [year]"
+            )),
+            (DateItem::Date(date), "ko") => date.format(format_description!(
+                "[year]년 [month padding:none]월 [day padding:none]일"
+            )),
+            (DateItem::Date(date), "ja" | "zh") => date.format(format_description!(
+                "[year]年[month padding:none]月[day padding:none]日"
+            )),
+            (DateItem::Date(date), _) => {
+                date.format(format_description!("[year]-[month]-[day]"))
+            }
+            (_, "en") => self.to_datetime_tz().format(format_description!(
+                "[month repr:long] [day padding:none], [year] [hour]:[minute] \
+                 (UTC[offset_hour sign:mandatory]:[offset_minute])"
+            )),
+            (_, "ko") => self.to_datetime_tz().format(format_description!(
+                "[year]년 [month padding:none]월 [day padding:none]일 [hour]:[minute] \
+                 (UTC[offset_hour sign:mandatory]:[offset_minute])"
+            )),
+            (_, "ja" | "zh") => self.to_datetime_tz().format(format_description!(
+                "[year]年[month padding:none]月[day padding:none]日 [hour]:[minute] \
+                 (UTC[offset_hour sign:mandatory]:[offset_minute])"
+            )),
+            (_, _) => self.to_datetime_tz().format(format_description!(
+                "[year]-[month]-[day] [hour]:[minute] \
+                 (UTC[offset_hour sign:mandatory]:[offset_minute])"
+            )),
+        };
+
+        result.map_err(convert_format_error)
+    }
+}
+
+fn convert_format_error(error: time::error::Format) -> io::Error {
+    use time::error::Format;
+
+    match error {
+        Format::StdIo(io_error) => io_error,
+        _ => io::Error::other(error),
     }
 }
 
@@ -115,3 +190,36 @@ cfg_if! {
         }
     }
 }
+
+#[test]
+fn format_localized() {
+    use time::macros::{date, datetime};
+
+    macro_rules! check {
+        ($date:expr, $language:expr, $expected:expr $(,)?) => {{
+            let actual = DateItem::from($date)
+                .format_localized($language)
+                .expect("Unable to format date");
+
+            assert_eq!(
+                actual, $expected,
+                "Actual localized date does not match expected",
+            );
+        }};
+    }
+
+    check!(date!(2010 - 01 - 01), "en", "January 1, 2010");
+    check!(date!(2010 - 01 - 01), "ko", "2010년 1월 1일");
+    check!(date!(2010 - 01 - 01), "zh-Hans", "2010年1月1日");
+    check!(date!(2010 - 01 - 01), "default", "2010-01-01");
+    check!(
+        datetime!(2010-01-01 08:10:00),
+        "en-US",
+        "January 1, 2010 08:10 (UTC+00:00)",
+    );
+    check!(
+        datetime!(2010-01-01 08:10:00 +09:00),
+        "ko",
+        "2010년 1월 1일 08:10 (UTC+09:00)",
+    );
+}
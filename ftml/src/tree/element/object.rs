@@ -21,9 +21,10 @@
 use crate::data::PageRef;
 use crate::tree::clone::*;
 use crate::tree::{
-    Alignment, AnchorTarget, AttributeMap, ClearFloat, Container, DateItem,
-    DefinitionListItem, Embed, FloatAlignment, ImageSource, LinkLabel, LinkLocation,
-    LinkType, ListItem, ListType, Module, PartialElement, Tab, Table, VariableMap,
+    Alignment, AnchorTarget, AttributeMap, ClearFloat, Container, CustomBlock, DateItem,
+    DefinitionListItem, Embed, FloatAlignment, GalleryImage, GallerySize, ImageSource,
+    LinkLabel, LinkLocation, LinkType, ListItem, ListType, Module, PartialElement, Tab,
+    Table, VariableMap,
 };
 use ref_map::*;
 use std::borrow::Cow;
@@ -44,6 +45,12 @@ pub enum Element<'t> {
     /// directly rendered.
     Module(Module<'t>),
 
+    /// A block registered at runtime, along with its arguments and body.
+    ///
+    /// These are defined per-site rather than built into the parser,
+    /// see [`CustomBlockSettings`](crate::settings::CustomBlockSettings).
+    CustomBlock(CustomBlock<'t>),
+
     /// An element only containing text.
     ///
     /// Should be formatted like typical body text.
@@ -118,6 +125,25 @@ pub enum Element<'t> {
         attributes: AttributeMap<'t>,
     },
 
+    /// A gallery of images, shown together in a grid.
+    ///
+    /// The "size" field is the default size of each image,
+    /// which images may individually override.
+    Gallery {
+        images: Vec<GalleryImage<'t>>,
+        size: GallerySize,
+        attributes: AttributeMap<'t>,
+    },
+
+    /// A download card for an attached file.
+    ///
+    /// Shows the file's name, size, type, uploader, and license,
+    /// as provided when rendering.
+    FileCard {
+        source: ImageSource<'t>,
+        attributes: AttributeMap<'t>,
+    },
+
     /// An ordered or unordered list.
     List {
         #[serde(rename = "type")]
@@ -163,6 +189,20 @@ pub enum Element<'t> {
         show_bottom: bool,
     },
 
+    /// A spoiler, containing content obscured until revealed.
+    ///
+    /// The inline form is hidden until hovered over or focused, and the
+    /// block form behaves like a collapsible which always starts folded.
+    /// The show and hide text are only used by the block form.
+    #[serde(rename_all = "kebab-case")]
+    Spoiler {
+        elements: Vec<Element<'t>>,
+        attributes: AttributeMap<'t>,
+        block: bool,
+        show_text: Option<Cow<'t, str>>,
+        hide_text: Option<Cow<'t, str>>,
+    },
+
     /// A table of contents block.
     ///
     /// This contains links to sub-headings on the page.
@@ -319,6 +359,7 @@ impl Element<'_> {
         match self {
             Element::Container(container) => container.ctype().name(),
             Element::Module(module) => module.name(),
+            Element::CustomBlock(_) => "CustomBlock",
             Element::Text(_) => "Text",
             Element::Raw(_) => "Raw",
             Element::Variable(_) => "Variable",
@@ -329,11 +370,14 @@ impl Element<'_> {
             Element::AnchorName(_) => "AnchorName",
             Element::Link { .. } => "Link",
             Element::Image { .. } => "Image",
+            Element::Gallery { .. } => "Gallery",
+            Element::FileCard { .. } => "FileCard",
             Element::List { .. } => "List",
             Element::DefinitionList(_) => "DefinitionList",
             Element::RadioButton { .. } => "RadioButton",
             Element::CheckBox { .. } => "CheckBox",
             Element::Collapsible { .. } => "Collapsible",
+            Element::Spoiler { .. } => "Spoiler",
             Element::TableOfContents { .. } => "TableOfContents",
             Element::Footnote => "Footnote",
             Element::FootnoteBlock { .. } => "FootnoteBlock",
@@ -372,6 +416,7 @@ impl Element<'_> {
         match self {
             Element::Container(container) => container.ctype().paragraph_safe(),
             Element::Module(_) => false,
+            Element::CustomBlock(_) => false,
             Element::Text(_)
             | Element::Raw(_)
             | Element::Variable(_)
@@ -382,10 +427,13 @@ impl Element<'_> {
                 true
             }
             Element::Image { .. } => true,
+            Element::Gallery { .. } => false,
+            Element::FileCard { .. } => false,
             Element::List { .. } => false,
             Element::DefinitionList(_) => false,
             Element::RadioButton { .. } | Element::CheckBox { .. } => true,
             Element::Collapsible { .. } => false,
+            Element::Spoiler { block, .. } => !block,
             Element::TableOfContents { .. } => false,
             Element::Footnote => true,
             Element::FootnoteBlock { .. } => false,
@@ -420,6 +468,7 @@ impl Element<'_> {
         match self {
             Element::Container(container) => Element::Container(container.to_owned()),
             Element::Module(module) => Element::Module(module.to_owned()),
+            Element::CustomBlock(block) => Element::CustomBlock(block.to_owned()),
             Element::Text(text) => Element::Text(string_to_owned(text)),
             Element::Raw(text) => Element::Raw(string_to_owned(text)),
             Element::Variable(name) => Element::Variable(string_to_owned(name)),
@@ -469,6 +518,19 @@ impl Element<'_> {
                 alignment: *alignment,
                 attributes: attributes.to_owned(),
             },
+            Element::Gallery {
+                images,
+                size,
+                attributes,
+            } => Element::Gallery {
+                images: images.iter().map(|image| image.to_owned()).collect(),
+                size: *size,
+                attributes: attributes.to_owned(),
+            },
+            Element::FileCard { source, attributes } => Element::FileCard {
+                source: source.to_owned(),
+                attributes: attributes.to_owned(),
+            },
             Element::DefinitionList(items) => Element::DefinitionList(
                 items.iter().map(|item| item.to_owned()).collect(),
             ),
@@ -505,6 +567,19 @@ impl Element<'_> {
                 show_top: *show_top,
                 show_bottom: *show_bottom,
             },
+            Element::Spoiler {
+                elements,
+                attributes,
+                block,
+                show_text,
+                hide_text,
+            } => Element::Spoiler {
+                elements: elements_to_owned(elements),
+                attributes: attributes.to_owned(),
+                block: *block,
+                show_text: option_string_to_owned(show_text),
+                hide_text: option_string_to_owned(hide_text),
+            },
             Element::TableOfContents { align, attributes } => Element::TableOfContents {
                 align: *align,
                 attributes: attributes.to_owned(),
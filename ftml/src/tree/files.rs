@@ -0,0 +1,128 @@
+/*
+ * tree/files.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Finds the files attached to pages which a syntax tree refers to.
+//!
+//! These need to be looked up by the caller before rendering,
+//! and then passed in through `RenderContext`.
+
+use super::{DefinitionListItem, Element, ImageSource, ListItem, SyntaxTree, Tab};
+use crate::data::{FileReference, PageInfo};
+
+impl<'t> SyntaxTree<'t> {
+    /// Lists every attached file used by images, galleries, or file cards.
+    ///
+    /// Each file is listed once, in order of first appearance.
+    pub fn file_references<'a>(&'a self, info: &'a PageInfo) -> Vec<FileReference<'a>> {
+        let mut collector = FileCollector {
+            info,
+            references: Vec::new(),
+        };
+
+        collector.elements(&self.elements);
+
+        for footnote in &self.footnotes {
+            collector.elements(footnote);
+        }
+
+        for index in 0..self.bibliographies.next_index() {
+            let bibliography = self.bibliographies.get_bibliography(index);
+            for (_, elements) in bibliography.slice() {
+                collector.elements(elements);
+            }
+        }
+
+        collector.references
+    }
+}
+
+#[derive(Debug)]
+struct FileCollector<'a> {
+    info: &'a PageInfo<'a>,
+    references: Vec<FileReference<'a>>,
+}
+
+impl<'a> FileCollector<'a> {
+    fn source(&mut self, source: &'a ImageSource) {
+        if let Some(reference) = FileReference::from_source(source, self.info) {
+            if !self.references.contains(&reference) {
+                self.references.push(reference);
+            }
+        }
+    }
+
+    fn elements(&mut self, elements: &'a [Element]) {
+        for element in elements {
+            self.element(element);
+        }
+    }
+
+    fn element(&mut self, element: &'a Element) {
+        match element {
+            Element::Image { source, .. } | Element::FileCard { source, .. } => {
+                self.source(source)
+            }
+            Element::Gallery { images, .. } => {
+                for image in images {
+                    self.source(&image.source);
+                }
+            }
+            Element::Container(container) => self.elements(container.elements()),
+            Element::Anchor { elements, .. }
+            | Element::Color { elements, .. }
+            | Element::Collapsible { elements, .. }
+            | Element::Spoiler { elements, .. }
+            | Element::Include { elements, .. } => self.elements(elements),
+            Element::CustomBlock(block) => self.elements(&block.elements),
+            Element::Table(table) => {
+                for row in &table.rows {
+                    for cell in &row.cells {
+                        self.elements(&cell.elements);
+                    }
+                }
+            }
+            Element::TabView(tabs) => {
+                for Tab { elements, .. } in tabs {
+                    self.elements(elements);
+                }
+            }
+            Element::List { items, .. } => {
+                for item in items {
+                    match item {
+                        ListItem::Elements { elements, .. } => self.elements(elements),
+                        ListItem::SubList { element } => self.element(element),
+                    }
+                }
+            }
+            Element::DefinitionList(items) => {
+                for DefinitionListItem {
+                    key_elements,
+                    value_elements,
+                    ..
+                } in items
+                {
+                    self.elements(key_elements);
+                    self.elements(value_elements);
+                }
+            }
+            _ => (),
+        }
+    }
+}
@@ -0,0 +1,100 @@
+/*
+ * tree/gallery.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::clone::option_string_to_owned;
+use super::{AttributeMap, ImageSource, LinkLocation};
+use std::borrow::Cow;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct GalleryImage<'t> {
+    pub source: ImageSource<'t>,
+    pub link: Option<LinkLocation<'t>>,
+    pub caption: Option<Cow<'t, str>>,
+
+    /// The size for this image, if different from the rest of the gallery.
+    pub size: Option<GallerySize>,
+    pub attributes: AttributeMap<'t>,
+}
+
+impl GalleryImage<'_> {
+    pub fn to_owned(&self) -> GalleryImage<'static> {
+        GalleryImage {
+            source: self.source.to_owned(),
+            link: self.link.as_ref().map(|link| link.to_owned()),
+            caption: option_string_to_owned(&self.caption),
+            size: self.size,
+            attributes: self.attributes.to_owned(),
+        }
+    }
+}
+
+/// Which size of image to show in a gallery.
+///
+/// Except for `Original`, these are resized derivatives
+/// of the image, as provided in the render context.
+#[derive(Serialize, Deserialize, Debug, Default, Copy, Clone, Hash, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum GallerySize {
+    Square,
+    #[default]
+    Thumbnail,
+    Small,
+    Medium,
+    Original,
+}
+
+impl GallerySize {
+    pub fn parse(value: &str) -> Option<Self> {
+        const NAMES: [(&str, GallerySize); 5] = [
+            ("square", GallerySize::Square),
+            ("thumbnail", GallerySize::Thumbnail),
+            ("small", GallerySize::Small),
+            ("medium", GallerySize::Medium),
+            ("original", GallerySize::Original),
+        ];
+
+        let value = value.trim();
+        NAMES
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(value))
+            .map(|&(_, size)| size)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            GallerySize::Square => "square",
+            GallerySize::Thumbnail => "thumbnail",
+            GallerySize::Small => "small",
+            GallerySize::Medium => "medium",
+            GallerySize::Original => "original",
+        }
+    }
+
+    pub fn html_class(self) -> &'static str {
+        match self {
+            GallerySize::Square => "wj-gallery-square",
+            GallerySize::Thumbnail => "wj-gallery-thumbnail",
+            GallerySize::Small => "wj-gallery-small",
+            GallerySize::Medium => "wj-gallery-medium",
+            GallerySize::Original => "wj-gallery-original",
+        }
+    }
+}
@@ -20,6 +20,9 @@
 
 use super::HtmlTag;
 use crate::next_index::{NextIndex, TableOfContentsIndex};
+use crate::settings::{HeadingIdStrategy, WikitextSettings};
+use data_encoding::HEXLOWER;
+use sha2::{Digest, Sha256};
 use std::convert::TryFrom;
 
 #[derive(Serialize, Deserialize, Debug, Copy, Clone, Hash, PartialEq, Eq)]
@@ -37,11 +40,15 @@ pub struct Heading {
 }
 
 impl Heading {
-    pub fn html_tag(self, indexer: &mut dyn NextIndex<TableOfContentsIndex>) -> HtmlTag {
+    pub fn html_tag(
+        self,
+        indexer: &mut dyn NextIndex<TableOfContentsIndex>,
+        settings: &WikitextSettings,
+    ) -> HtmlTag {
         let tag = self.level.html_tag();
 
         if self.has_toc {
-            let id = format!("toc{}", indexer.next());
+            let id = heading_id(settings, indexer.next());
 
             HtmlTag::with_id(tag, id)
         } else {
@@ -50,6 +57,28 @@ impl Heading {
     }
 }
 
+/// Produces the ID for the heading with the given table of contents index.
+///
+/// The table of contents links to headings using this,
+/// so both always agree for the same settings.
+pub fn heading_id(settings: &WikitextSettings, index: usize) -> String {
+    let prefix = settings.heading_id_prefix.as_deref().unwrap_or("");
+
+    match settings.heading_id_strategy {
+        HeadingIdStrategy::Suffix => format!("{prefix}toc{index}"),
+        HeadingIdStrategy::Hash => {
+            let mut hasher = Sha256::new();
+            hasher.update(prefix.as_bytes());
+            hasher.update(b"\0");
+            hasher.update(index.to_string().as_bytes());
+
+            // 64 bits is plenty to avoid collisions between headings
+            let digest = hasher.finalize();
+            format!("toc-{}", HEXLOWER.encode(&digest[..8]))
+        }
+    }
+}
+
 impl TryFrom<&'_ str> for Heading {
     type Error = ();
 
@@ -209,3 +238,35 @@ fn heading() {
     check!("+++++*", 5, false);
     check!("++++++*", 6, false);
 }
+
+#[test]
+fn heading_ids() {
+    use crate::settings::WikitextMode;
+
+    let mut settings = WikitextSettings::from_mode(WikitextMode::Page);
+    assert_eq!(heading_id(&settings, 0), "toc0");
+    assert_eq!(heading_id(&settings, 12), "toc12");
+
+    settings.heading_id_prefix = Some(str!("include-1-"));
+    assert_eq!(heading_id(&settings, 0), "include-1-toc0");
+    assert_eq!(heading_id(&settings, 12), "include-1-toc12");
+
+    settings.heading_id_strategy = HeadingIdStrategy::Hash;
+    let first = heading_id(&settings, 0);
+    let second = heading_id(&settings, 1);
+    assert!(first.starts_with("toc-"));
+    assert_eq!(first.len(), 20);
+    assert_ne!(first, second);
+    assert_eq!(
+        first,
+        heading_id(&settings, 0),
+        "Heading IDs are not stable"
+    );
+
+    settings.heading_id_prefix = Some(str!("include-2-"));
+    assert_ne!(
+        first,
+        heading_id(&settings, 0),
+        "Prefix not included in hash"
+    );
+}
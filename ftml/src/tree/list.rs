@@ -33,6 +33,10 @@ pub enum ListItem<'t> {
     Elements {
         attributes: AttributeMap<'t>,
         elements: Vec<Element<'t>>,
+
+        /// If this item is a task, as in `* [x] item`, its state.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        task: Option<ListTask>,
     },
 
     /// This item in the list is a sub-list.
@@ -50,9 +54,11 @@ impl ListItem<'_> {
             ListItem::Elements {
                 attributes,
                 elements,
+                task,
             } => ListItem::Elements {
                 attributes: attributes.to_owned(),
                 elements: elements_to_owned(elements),
+                task: *task,
             },
             ListItem::SubList { element } => {
                 let element: &Element = element;
@@ -65,6 +71,20 @@ impl ListItem<'_> {
     }
 }
 
+/// A task in a list, which is either done or not.
+///
+/// The offset identifies the item, so that consumers can later change
+/// its state by replacing the marker in the source.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Hash, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct ListTask {
+    /// Whether this task is marked as done, i.e. `[x]` rather than `[ ]`.
+    pub checked: bool,
+
+    /// The byte offset of the task's `[` marker in the preprocessed wikitext.
+    pub offset: usize,
+}
+
 #[derive(
     Serialize, Deserialize, IntoStaticStr, Debug, Copy, Clone, Hash, PartialEq, Eq,
 )]
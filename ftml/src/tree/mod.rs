@@ -26,10 +26,13 @@ mod bibliography;
 mod clear_float;
 mod clone;
 mod container;
+mod custom_block;
 mod date;
 mod definition_list;
 mod element;
 mod embed;
+mod files;
+mod gallery;
 mod heading;
 mod image;
 mod link;
@@ -37,6 +40,8 @@ mod list;
 mod module;
 mod partial;
 mod ruby;
+mod span;
+mod stats;
 mod tab;
 mod table;
 mod tag;
@@ -48,10 +53,12 @@ pub use self::attribute::AttributeMap;
 pub use self::bibliography::*;
 pub use self::clear_float::*;
 pub use self::container::*;
+pub use self::custom_block::*;
 pub use self::date::DateItem;
 pub use self::definition_list::*;
 pub use self::element::*;
 pub use self::embed::*;
+pub use self::gallery::*;
 pub use self::heading::*;
 pub use self::image::*;
 pub use self::link::*;
@@ -59,6 +66,8 @@ pub use self::list::*;
 pub use self::module::*;
 pub use self::partial::*;
 pub use self::ruby::*;
+pub use self::span::ElementSpan;
+pub use self::stats::*;
 pub use self::tab::*;
 pub use self::table::*;
 pub use self::tag::*;
@@ -77,6 +86,16 @@ pub struct SyntaxTree<'t> {
     /// syntax tree.
     pub elements: Vec<Element<'t>>,
 
+    /// Where in the source text each of the elements came from.
+    ///
+    /// This is parallel to `elements`, with each entry being the byte range
+    /// in the wikitext which produced that element, along with the spans
+    /// of the elements nested inside it. Elements not produced from the
+    /// source, such as an implicit footnote block, have an empty range
+    /// at the end of the input.
+    #[serde(default)]
+    pub element_spans: Vec<ElementSpan>,
+
     /// The full table of contents for this page.
     ///
     /// Depth list conversion happens here, so that depths on the table
@@ -99,6 +118,7 @@ pub struct SyntaxTree<'t> {
 impl<'t> SyntaxTree<'t> {
     pub(crate) fn from_element_result(
         elements: Vec<Element<'t>>,
+        element_spans: Vec<ElementSpan>,
         errors: Vec<ParseError>,
         table_of_contents: Vec<Element<'t>>,
         footnotes: Vec<Vec<Element<'t>>>,
@@ -107,6 +127,7 @@ impl<'t> SyntaxTree<'t> {
     ) -> ParseOutcome<Self> {
         let tree = SyntaxTree {
             elements,
+            element_spans,
             table_of_contents,
             footnotes,
             bibliographies,
@@ -118,6 +139,7 @@ impl<'t> SyntaxTree<'t> {
     pub fn to_owned(&self) -> SyntaxTree<'static> {
         SyntaxTree {
             elements: elements_to_owned(&self.elements),
+            element_spans: self.element_spans.clone(),
             table_of_contents: elements_to_owned(&self.table_of_contents),
             footnotes: elements_lists_to_owned(&self.footnotes),
             bibliographies: self.bibliographies.to_owned(),
@@ -38,6 +38,9 @@ pub enum Module<'t> {
     #[serde(rename_all = "kebab-case")]
     Categories { include_hidden: bool },
 
+    /// A comments section, for discussing the page.
+    Comments,
+
     /// Allows a user to join a site.
     #[serde(rename_all = "kebab-case")]
     Join {
@@ -66,6 +69,13 @@ impl Module<'_> {
         self.into()
     }
 
+    /// Whether this module exists for users to interact with.
+    ///
+    /// Such modules are omitted from static output, such as printing.
+    pub fn is_interactive(&self) -> bool {
+        matches!(self, Module::Comments | Module::Join { .. } | Module::Rate)
+    }
+
     pub fn to_owned(&self) -> Module<'static> {
         match self {
             Module::Backlinks { page } => Module::Backlinks {
@@ -74,6 +84,7 @@ impl Module<'_> {
             Module::Categories { include_hidden } => Module::Categories {
                 include_hidden: *include_hidden,
             },
+            Module::Comments => Module::Comments,
             Module::Join {
                 button_text,
                 attributes,
@@ -18,7 +18,7 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
-use super::{ListItem, RubyText, Tab, TableCell, TableRow};
+use super::{GalleryImage, ListItem, RubyText, Tab, TableCell, TableRow};
 use crate::parsing::ParseErrorKind;
 
 /// Part of an element, as returned by a rule.
@@ -44,6 +44,9 @@ pub enum PartialElement<'t> {
     ///
     /// Outputs HTML `<rt>`. See also https://developer.mozilla.org/en-US/docs/Web/HTML/Element/ruby.
     RubyText(RubyText<'t>),
+
+    /// An image within a gallery.
+    GalleryImage(GalleryImage<'t>),
 }
 
 impl PartialElement<'_> {
@@ -54,6 +57,7 @@ impl PartialElement<'_> {
             PartialElement::TableCell(_) => "TableCell",
             PartialElement::Tab(_) => "Tab",
             PartialElement::RubyText(_) => "RubyText",
+            PartialElement::GalleryImage(_) => "GalleryImage",
         }
     }
 
@@ -65,6 +69,7 @@ impl PartialElement<'_> {
             PartialElement::TableCell(_) => ParseErrorKind::TableCellOutsideTable,
             PartialElement::Tab(_) => ParseErrorKind::TabOutsideTabView,
             PartialElement::RubyText(_) => ParseErrorKind::RubyTextOutsideRuby,
+            PartialElement::GalleryImage(_) => ParseErrorKind::GalleryImageOutsideGallery,
         }
     }
 
@@ -81,6 +86,9 @@ impl PartialElement<'_> {
             }
             PartialElement::Tab(tab) => PartialElement::Tab(tab.to_owned()),
             PartialElement::RubyText(text) => PartialElement::RubyText(text.to_owned()),
+            PartialElement::GalleryImage(image) => {
+                PartialElement::GalleryImage(image.to_owned())
+            }
         }
     }
 }
@@ -98,6 +106,7 @@ pub enum AcceptsPartial {
     TableCell,
     Tab,
     Ruby,
+    GalleryImage,
 }
 
 impl AcceptsPartial {
@@ -109,6 +118,10 @@ impl AcceptsPartial {
                 | (AcceptsPartial::TableCell, PartialElement::TableCell(_))
                 | (AcceptsPartial::Tab, PartialElement::Tab(_))
                 | (AcceptsPartial::Ruby, PartialElement::RubyText(_))
+                | (
+                    AcceptsPartial::GalleryImage,
+                    PartialElement::GalleryImage(_)
+                )
         )
     }
 }
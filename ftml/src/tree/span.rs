@@ -0,0 +1,75 @@
+/*
+ * tree/span.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::ops::Range;
+
+/// Where in the source text an element came from.
+///
+/// The spans of the elements nested within it are listed in `children`,
+/// in the order they were parsed. For containers, this is parallel to
+/// their child elements.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Hash, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct ElementSpan {
+    /// The byte offset in the wikitext where this element starts.
+    pub start: usize,
+
+    /// The byte offset in the wikitext where this element ends (exclusive).
+    pub end: usize,
+
+    /// The spans of the elements within this one.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<ElementSpan>,
+}
+
+impl ElementSpan {
+    #[inline]
+    pub fn new(span: Range<usize>, children: Vec<ElementSpan>) -> Self {
+        ElementSpan {
+            start: span.start,
+            end: span.end,
+            children,
+        }
+    }
+
+    /// Creates a span for an element which has no nested elements.
+    #[inline]
+    pub fn leaf(span: Range<usize>) -> Self {
+        ElementSpan::new(span, vec![])
+    }
+
+    #[inline]
+    pub fn range(&self) -> Range<usize> {
+        self.start..self.end
+    }
+
+    /// Applies the given function to the offsets in this span and all its children.
+    pub fn map_offsets<F>(&mut self, f: &mut F)
+    where
+        F: FnMut(usize) -> usize,
+    {
+        self.start = f(self.start);
+        self.end = f(self.end);
+
+        for child in &mut self.children {
+            child.map_offsets(f);
+        }
+    }
+}
@@ -0,0 +1,242 @@
+/*
+ * tree/stats.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Computes content statistics for a syntax tree.
+//!
+//! These are intended for listings and previews, which want to show
+//! something like "12 min read" without rendering the page.
+//!
+//! Only readable prose is counted. Markup, code blocks, math, raw HTML,
+//! and anything in a hidden or invisible container are all excluded.
+
+use super::{
+    ContainerType, DefinitionListItem, Element, LinkLabel, LinkLocation, ListItem,
+    SyntaxTree, Tab,
+};
+
+/// The assumed reading speed, used to estimate reading time.
+pub const WORDS_PER_MINUTE: usize = 200;
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct ContentStats {
+    /// The number of words of prose in the page.
+    pub word_count: usize,
+
+    /// The estimated time to read the page, in minutes, rounded up.
+    ///
+    /// This is `0` only if the page has no words at all.
+    pub reading_time_minutes: usize,
+
+    /// Every heading in the page, in order of appearance.
+    pub headings: Vec<HeadingOutline>,
+
+    /// The number of images, including those within galleries.
+    pub image_count: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct HeadingOutline {
+    /// The heading's level, from `1` to `6`.
+    pub level: u8,
+
+    /// The plain text of the heading.
+    pub text: String,
+}
+
+impl SyntaxTree<'_> {
+    /// Computes word count, reading time, and other statistics for this tree.
+    pub fn stats(&self) -> ContentStats {
+        let mut collector = StatsCollector::default();
+        collector.elements(&self.elements);
+
+        for footnote in &self.footnotes {
+            collector.break_word();
+            collector.elements(footnote);
+        }
+
+        let StatsCollector {
+            word_count,
+            headings,
+            image_count,
+            ..
+        } = collector;
+
+        ContentStats {
+            word_count,
+            reading_time_minutes: word_count.div_ceil(WORDS_PER_MINUTE),
+            headings,
+            image_count,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct StatsCollector {
+    word_count: usize,
+    image_count: usize,
+    headings: Vec<HeadingOutline>,
+
+    /// Whether the last character seen was part of a word.
+    ///
+    /// Adjacent inline elements, such as in `a**b**c`, form a single word.
+    in_word: bool,
+
+    /// The text of the heading currently being walked, if any.
+    heading: Option<String>,
+}
+
+impl StatsCollector {
+    fn text(&mut self, text: &str) {
+        for ch in text.chars() {
+            if ch.is_whitespace() {
+                self.in_word = false;
+            } else if !self.in_word {
+                self.word_count += 1;
+                self.in_word = true;
+            }
+        }
+
+        if let Some(ref mut heading) = self.heading {
+            heading.push_str(text);
+        }
+    }
+
+    fn break_word(&mut self) {
+        self.in_word = false;
+
+        if let Some(ref mut heading) = self.heading {
+            heading.push(' ');
+        }
+    }
+
+    fn elements(&mut self, elements: &[Element]) {
+        for element in elements {
+            self.element(element);
+        }
+    }
+
+    fn block(&mut self, elements: &[Element]) {
+        self.break_word();
+        self.elements(elements);
+        self.break_word();
+    }
+
+    fn element(&mut self, element: &Element) {
+        match element {
+            Element::Container(container) => match container.ctype() {
+                ContainerType::Hidden
+                | ContainerType::Invisible
+                | ContainerType::RubyText => (),
+                ContainerType::Header(heading) => {
+                    self.break_word();
+                    self.heading = Some(String::new());
+                    self.elements(container.elements());
+
+                    let text = self.heading.take().unwrap_or_default();
+                    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+
+                    self.headings.push(HeadingOutline {
+                        level: heading.level.value(),
+                        text,
+                    });
+                    self.break_word();
+                }
+                ctype if ctype.paragraph_safe() => self.elements(container.elements()),
+                _ => self.block(container.elements()),
+            },
+            Element::Text(text) | Element::Raw(text) | Element::Email(text) => {
+                self.text(text)
+            }
+            Element::User { name, .. } => self.text(name),
+            Element::Link { link, label, .. } => match (label, link) {
+                (LinkLabel::Text(text) | LinkLabel::Url(Some(text)), _)
+                | (LinkLabel::Url(None), LinkLocation::Url(text)) => self.text(text),
+
+                // The page title isn't known without a rendering handle,
+                // so count it as a single word.
+                _ => {
+                    self.break_word();
+                    self.word_count += 1;
+                }
+            },
+            Element::Anchor { elements, .. } | Element::Color { elements, .. } => {
+                self.elements(elements)
+            }
+            Element::Collapsible { elements, .. }
+            | Element::Spoiler { elements, .. }
+            | Element::Include { elements, .. } => self.block(elements),
+            Element::CustomBlock(block) => self.block(&block.elements),
+            Element::Table(table) => {
+                for row in &table.rows {
+                    for cell in &row.cells {
+                        self.block(&cell.elements);
+                    }
+                }
+            }
+            Element::TabView(tabs) => {
+                for Tab { label, elements } in tabs {
+                    self.break_word();
+                    self.text(label);
+                    self.block(elements);
+                }
+            }
+            Element::List { items, .. } => {
+                for item in items {
+                    match item {
+                        ListItem::Elements { elements, .. } => self.block(elements),
+                        ListItem::SubList { element } => self.element(element),
+                    }
+                }
+            }
+            Element::DefinitionList(items) => {
+                for DefinitionListItem {
+                    key_elements,
+                    value_elements,
+                    ..
+                } in items
+                {
+                    self.block(key_elements);
+                    self.block(value_elements);
+                }
+            }
+            Element::Image { .. } => self.image_count += 1,
+            Element::Gallery { images, .. } => {
+                self.image_count += images.len();
+
+                for image in images {
+                    if let Some(caption) = &image.caption {
+                        self.break_word();
+                        self.text(caption);
+                        self.break_word();
+                    }
+                }
+            }
+            Element::LineBreak
+            | Element::LineBreaks(_)
+            | Element::HorizontalRule
+            | Element::ClearFloat(_) => self.break_word(),
+
+            // Code, math, raw HTML, styles, and other non-prose elements.
+            _ => (),
+        }
+    }
+}
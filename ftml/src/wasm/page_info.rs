@@ -91,4 +91,9 @@ impl PageInfo {
     pub fn language(&self) -> String {
         self.inner.language.to_string()
     }
+
+    #[wasm_bindgen(getter)]
+    pub fn utc_offset(&self) -> Option<i32> {
+        self.inner.utc_offset.map(|offset| offset.whole_seconds())
+    }
 }
@@ -23,7 +23,7 @@ use super::prelude::*;
 use super::settings::WikitextSettings;
 use super::tokenizer::Tokenization;
 use crate::parsing::{ParseError as RustParseError, ParseOutcome as RustParseOutcome};
-use crate::tree::SyntaxTree as RustSyntaxTree;
+use crate::tree::{ElementSpan, SyntaxTree as RustSyntaxTree};
 use crate::utf16::Utf16IndexMap;
 use crate::Tokenization as RustTokenization;
 use std::sync::Arc;
@@ -82,6 +82,11 @@ impl SyntaxTree {
     pub fn data(&self) -> Result<JsValue, JsValue> {
         rust_to_js!(*self.inner)
     }
+
+    #[wasm_bindgen]
+    pub fn stats(&self) -> Result<JsValue, JsValue> {
+        rust_to_js!(self.inner.stats())
+    }
 }
 
 // Exported functions
@@ -100,10 +105,11 @@ pub fn parse(
 
     // Deep-clone AST to make it owned, so it can be
     // safely passed to JS, where it will live for an unknown time.
-    let syntax_tree = syntax_tree.to_owned();
+    let mut syntax_tree = syntax_tree.to_owned();
 
-    // Convert errors to use UTF-16 indices
+    // Convert errors and element spans to use UTF-16 indices
     let errors = convert_errors_utf16(tokenization, errors);
+    convert_spans_utf16(tokenization, &mut syntax_tree.element_spans);
 
     // Create inner wrapper
     let inner = Arc::new(RustParseOutcome::new(syntax_tree, errors));
@@ -131,3 +137,17 @@ fn convert_errors_utf16(
         .map(|err| err.to_utf16_indices(&utf16_map))
         .collect()
 }
+
+fn convert_spans_utf16(tokenization: &RustTokenization, spans: &mut [ElementSpan]) {
+    // An empty input can only produce empty spans, which need no mapping.
+    let full_text = tokenization.full_text().inner();
+    if full_text.is_empty() {
+        return;
+    }
+
+    let utf16_map = Utf16IndexMap::new(full_text);
+
+    for span in spans {
+        span.map_offsets(&mut |index| utf16_map.get_index(index));
+    }
+}
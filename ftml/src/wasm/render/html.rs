@@ -60,6 +60,16 @@ impl HtmlOutput {
     pub fn backlinks(&self) -> Result<JsValue, JsValue> {
         rust_to_js!(self.inner.backlinks)
     }
+
+    #[wasm_bindgen]
+    pub fn links(&self) -> Result<JsValue, JsValue> {
+        rust_to_js!(self.inner.links)
+    }
+
+    #[wasm_bindgen]
+    pub fn csp(&self) -> Result<JsValue, JsValue> {
+        rust_to_js!(self.inner.csp)
+    }
 }
 
 // Function exports
@@ -59,6 +59,8 @@ impl WikitextSettings {
             "forum-post" => RustWikitextMode::ForumPost,
             "direct-message" => RustWikitextMode::DirectMessage,
             "list" => RustWikitextMode::List,
+            "print" => RustWikitextMode::Print,
+            "safe-preview" => RustWikitextMode::SafePreview,
             _ => return Err(JsValue::from_str("Unknown mode")),
         };
 
@@ -0,0 +1,23 @@
+### Account Recovery Notices
+
+account-recovery-started-subject = Someone is trying to recover your account
+
+account-recovery-started-body =
+  A request was made to recover the account { $user } using its recovery contacts. If enough of them confirm it, the password can be reset after { $ready }.
+
+  If this was not you, cancel the recovery using the link sent to your email address.
+
+account-recovery-contact-subject = { $user } is recovering their account
+
+account-recovery-contact-body =
+  You are a trusted contact for { $user }, who has asked to recover their account. { $required ->
+      [one] One confirmation is needed
+     *[other] { $required } confirmations are needed
+  } before it can be completed after { $ready }.
+
+  Only confirm the recovery from your account settings if you have checked with { $user } directly that they made this request.
+
+account-recovery-completed-subject = Your account was recovered
+
+account-recovery-completed-body =
+  Your password was reset through account recovery. Multi-factor authentication has been turned off and all sessions have been signed out.
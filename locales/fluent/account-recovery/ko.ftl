@@ -0,0 +1,20 @@
+### 계정 복구 알림
+
+account-recovery-started-subject = 누군가 귀하의 계정을 복구하려고 합니다
+
+account-recovery-started-body =
+  복구 연락처를 사용하여 { $user } 계정을 복구하는 요청이 접수되었습니다. 충분한 연락처가 확인하면 { $ready } 이후에 비밀번호를 재설정할 수 있습니다.
+
+  본인이 요청하지 않았다면 이메일로 전송된 링크를 사용하여 복구를 취소하세요.
+
+account-recovery-contact-subject = { $user }님이 계정을 복구하고 있습니다
+
+account-recovery-contact-body =
+  귀하는 { $user }님의 신뢰할 수 있는 연락처이며, { $user }님이 계정 복구를 요청했습니다. { $ready } 이후에 완료하려면 { $required }건의 확인이 필요합니다.
+
+  { $user }님이 직접 요청했음을 확인한 경우에만 계정 설정에서 복구를 확인하세요.
+
+account-recovery-completed-subject = 계정이 복구되었습니다
+
+account-recovery-completed-body =
+  계정 복구를 통해 비밀번호가 재설정되었습니다. 다단계 인증이 해제되었으며 모든 세션에서 로그아웃되었습니다.
@@ -0,0 +1,20 @@
+### 账户恢复通知
+
+account-recovery-started-subject = 有人正在尝试恢复您的账户
+
+account-recovery-started-body =
+  有人请求使用恢复联系人恢复账户 { $user }。如果足够多的联系人确认，密码可在 { $ready } 之后重置。
+
+  如果这不是您本人的操作，请使用发送到您邮箱的链接取消恢复。
+
+account-recovery-contact-subject = { $user } 正在恢复其账户
+
+account-recovery-contact-body =
+  您是 { $user } 的受信任联系人，对方已请求恢复其账户。需要 { $required } 个确认，才能在 { $ready } 之后完成恢复。
+
+  请仅在直接向 { $user } 核实该请求后，再从账户设置中确认恢复。
+
+account-recovery-completed-subject = 您的账户已恢复
+
+account-recovery-completed-body =
+  您的密码已通过账户恢复重置。多重身份验证已关闭，所有会话均已登出。
@@ -53,6 +53,10 @@ cmftml-lint =
 
   .block-missing-arguments = Block '{ $slice }' is missing one or more required arguments.
 
+  .block-name-not-lowercase = Block names must be lowercase on this site.
+
+  .block-duplicate-argument = This block has an argument given more than once.
+
   .block-expected-end = The block of type '{ $rule }' was expected to end by at least this point.
 
   .block-end-mismatch = The block of type '{ $rule }' was expected to end here, not '{ $slice }'.
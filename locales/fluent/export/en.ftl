@@ -0,0 +1,10 @@
+### Exported Documents
+
+export-contents = Contents
+
+export-attribution = Attribution
+
+export-attribution-authors = By { $authors }
+
+export-license =
+  Unless stated otherwise, this content is licensed under the { $license }.
@@ -0,0 +1,10 @@
+### 내보낸 문서
+
+export-contents = 목차
+
+export-attribution = 저작자 표시
+
+export-attribution-authors = 작성자: { $authors }
+
+export-license =
+  별달리 명시되지 않은 한 이 콘텐츠는 { $license }에 따라 이용할 수 있습니다.
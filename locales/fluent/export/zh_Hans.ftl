@@ -0,0 +1,10 @@
+### 导出文档
+
+export-contents = 目录
+
+export-attribution = 署名
+
+export-attribution-authors = 作者：{ $authors }
+
+export-license =
+  除非另有声明，否则本内容授权位于 { $license } 协议之下。
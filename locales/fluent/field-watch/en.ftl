@@ -0,0 +1,11 @@
+### Field Watch Notices
+
+field-watch-notice-subject = { $fields ->
+    [one] A watched field changed on { $page }
+   *[other] { $fields } watched fields changed on { $page }
+}
+
+field-watch-notice-body =
+  The following fields you are watching were changed on [{ $url } { $page }] on { $site }:
+
+  { $changes }
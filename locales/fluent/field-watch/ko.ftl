@@ -0,0 +1,8 @@
+### 필드 주시 알림
+
+field-watch-notice-subject = { $page } 페이지에서 주시 중인 필드 { $fields }개가 변경되었습니다
+
+field-watch-notice-body =
+  { $site }의 [{ $url } { $page }] 페이지에서 주시 중인 다음 필드가 변경되었습니다:
+
+  { $changes }
@@ -0,0 +1,8 @@
+### 字段关注通知
+
+field-watch-notice-subject = 页面 { $page } 上有 { $fields } 个关注的字段已更改
+
+field-watch-notice-body =
+  { $site } 上的页面 [{ $url } { $page }] 中您关注的以下字段已更改：
+
+  { $changes }
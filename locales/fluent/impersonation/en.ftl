@@ -0,0 +1,12 @@
+### Impersonation Notices
+
+impersonation-notice-subject = An administrator accessed your account
+
+impersonation-notice-body =
+  The administrator { $admin } signed in as you between { $start } and { $end }, for the purposes of support or debugging.
+
+  { $actions ->
+      [0] No actions were taken on your behalf.
+      [one] One action was taken on your behalf.
+     *[other] { $actions } actions were taken on your behalf.
+  } This access has been recorded in the audit log.
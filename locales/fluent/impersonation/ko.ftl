@@ -0,0 +1,11 @@
+### 계정 대리 접속 알림
+
+impersonation-notice-subject = 관리자가 귀하의 계정에 접속했습니다
+
+impersonation-notice-body =
+  관리자 { $admin }님이 지원 또는 디버깅을 위해 { $start }부터 { $end }까지 귀하의 계정으로 로그인했습니다.
+
+  { $actions ->
+      [0] 귀하를 대신하여 수행된 작업이 없습니다.
+     *[other] 귀하를 대신하여 { $actions }개의 작업이 수행되었습니다.
+  } 이 접속 기록은 감사 로그에 남아 있습니다.
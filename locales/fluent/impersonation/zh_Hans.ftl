@@ -0,0 +1,11 @@
+### 代登录通知
+
+impersonation-notice-subject = 管理员访问了您的账户
+
+impersonation-notice-body =
+  管理员 { $admin } 为提供支持或调试，于 { $start } 至 { $end } 期间以您的身份登录。
+
+  { $actions ->
+      [0] 未以您的名义执行任何操作。
+     *[other] 以您的名义执行了 { $actions } 项操作。
+  }本次访问已记录在审计日志中。
@@ -0,0 +1,16 @@
+### Render Error Notices
+
+render-error-notice-subject = The page { $page } has rendering problems
+
+render-error-notice-body =
+  The latest revision of [{ $url } { $page }] on { $site } could not be rendered as written.
+
+  { $errors ->
+      [0] It has no errors
+      [one] It has one error
+     *[other] It has { $errors } errors
+  } and { $warnings ->
+      [0] no warnings.
+      [one] one warning.
+     *[other] { $warnings } warnings.
+  } You will not be notified again until the page renders cleanly.
@@ -0,0 +1,8 @@
+### 렌더링 오류 알림
+
+render-error-notice-subject = { $page } 페이지에 렌더링 문제가 있습니다
+
+render-error-notice-body =
+  { $site }의 [{ $url } { $page }] 페이지의 최신 리비전이 작성된 대로 렌더링되지 않았습니다.
+
+  오류 { $errors }개와 경고 { $warnings }개가 있습니다. 페이지가 정상적으로 렌더링될 때까지 다시 알림을 보내지 않습니다.
@@ -0,0 +1,8 @@
+### 渲染错误通知
+
+render-error-notice-subject = 页面 { $page } 存在渲染问题
+
+render-error-notice-body =
+  { $site } 上的页面 [{ $url } { $page }] 的最新修订版未能按原样渲染。
+
+  共有 { $errors } 个错误和 { $warnings } 个警告。在页面正常渲染之前，您将不会再次收到通知。
@@ -0,0 +1,6 @@
+### Scheduled Publication Notices
+
+scheduled-publish-notice-subject = The page { $page } has been updated
+
+scheduled-publish-notice-body =
+  A scheduled edit to [{ $url } { $title }] on { $site } has been published as revision { $revision }.
@@ -0,0 +1,6 @@
+### 예약 게시 알림
+
+scheduled-publish-notice-subject = { $page } 페이지가 업데이트되었습니다
+
+scheduled-publish-notice-body =
+  { $site }의 [{ $url } { $title }] 페이지에 예약된 편집이 리비전 { $revision }(으)로 게시되었습니다.
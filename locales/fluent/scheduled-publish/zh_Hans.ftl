@@ -0,0 +1,6 @@
+### 定时发布通知
+
+scheduled-publish-notice-subject = 页面 { $page } 已更新
+
+scheduled-publish-notice-body =
+  { $site } 上的页面 [{ $url } { $title }] 的定时编辑已发布为修订版 { $revision }。
@@ -36,6 +36,10 @@ wiki-page-banned = + You have been banned
 
     You are currently banned from this site, and the site settings do not allow banned users to view pages.
 
+wiki-page-join = + Join this site
+
+    This site is accepting new members. If you would like to contribute, please contact the site administrators.
+
 wiki-page-site-slug = <h1>No { -service-name } site exists with this address.</h1>
     <p>
       <a href="https://{ $slug }.{ $domain }/">{ $slug }.{ $domain }</a> does not exist.
@@ -38,6 +38,8 @@ const warningConfig: Record<string, "info" | "warning" | "error" | null> = {
   "block-missing-close-brackets": "error",
   "block-malformed-arguments": "error",
   "block-missing-arguments": "error",
+  "block-name-not-lowercase": "error",
+  "block-duplicate-argument": "error",
   "block-expected-end": "error",
   "block-end-mismatch": "error",
   "no-such-module": "error",